@@ -144,8 +144,8 @@
 use async_std::sync::{Arc, RwLock};
 use async_trait::async_trait;
 use std::convert::TryFrom;
-use zenoh::net::Sample;
-use zenoh::{Properties, Selector, Value, ZError, ZResult};
+use zenoh::net::{QueryPage, Sample};
+use zenoh::{Properties, Selector, Value, ZError, ZErrorKind, ZResult};
 
 pub mod utils;
 
@@ -163,6 +163,52 @@ pub const PROP_STORAGE_PATH_EXPR: &str = "path_expr";
 /// queries' path expression to the stored keys calling [`crate::utils::get_sub_path_exprs()`].
 pub const PROP_STORAGE_PATH_PREFIX: &str = "path_prefix";
 
+/// The `"ttl"` property key that could be used to specify a time-to-live (in seconds)
+/// for the samples of each storage.
+///
+/// It is handled by the storages plugin, whatever the Backend: once a sample is older than its
+/// time-to-live (according to its timestamp), it's removed from this storage (see
+/// [`Storage::on_expired()`]) and a DELETE is published for its path, so it's also removed
+/// from the other storages on the same path expression. The samples already stored when
+/// the storage starts expire according to their timestamp as well.
+pub const PROP_STORAGE_TTL: &str = "ttl";
+
+/// The `"dump_dir"` property key that could be used to specify the directory where each storage
+/// reads and writes the dump files of its import and export operations.
+///
+/// It is handled by the storages plugin, whatever the Backend. The dump files are given relatively
+/// to this directory (no absolute path, no `".."`), and a storage without it refuses the import and
+/// export requests on a file: it can only be exported under a path. The import and export requests
+/// are only accepted by the routers enabling the `admin_write` configuration.
+pub const PROP_STORAGE_DUMP_DIR: &str = "dump_dir";
+
+/// The `"conflict_resolution"` property key that could be used to specify how each storage
+/// resolves the conflicts between the samples received for a same path.
+///
+/// Its value can be `"latest_wins"` (the default, see [`LatestWins`]) or `"first_wins"`
+/// (see [`FirstWins`]). Call [`crate::utils::get_conflict_resolution()`] at creation of a Storage
+/// to get the corresponding [`ConflictResolution`].
+pub const PROP_STORAGE_CONFLICT_RESOLUTION: &str = "conflict_resolution";
+
+/// The `"max_bytes"` property key that could be used to specify a quota on the size (in bytes,
+/// counting the paths and the payloads) of the data stored by each storage.
+///
+/// Like [`PROP_STORAGE_MAX_KEYS`], it is handled by the storages plugin, whatever the Backend,
+/// applying the [`PROP_STORAGE_QUOTA_POLICY`] to the PUTs exceeding the quota.
+pub const PROP_STORAGE_MAX_BYTES: &str = "max_bytes";
+
+/// The `"max_keys"` property key that could be used to specify a quota on the number of paths
+/// stored by each storage.
+pub const PROP_STORAGE_MAX_KEYS: &str = "max_keys";
+
+/// The `"quota_policy"` property key that could be used to specify what a storage does with a PUT
+/// exceeding its quota: `"reject"` it (the default), `"evict_oldest"` (remove the least recently
+/// written paths from this storage to make room for it), or `"alarm"` (store it anyway).
+///
+/// In all cases an alert is published on the `alert` sub-path of the storage's admin path,
+/// the first time the quota is exceeded after a PUT within the quota.
+pub const PROP_STORAGE_QUOTA_POLICY: &str = "quota_policy";
+
 /// Trait to be implemented by a Backend.
 ///
 #[async_trait]
@@ -193,9 +239,109 @@ pub trait Storage: Send + Sync {
     /// Function called for each incoming data ([`Sample`]) to be stored in this storage.
     async fn on_sample(&mut self, sample: Sample) -> ZResult<()>;
 
+    /// Function called instead of [`Storage::on_sample()`] when the caller needs to know whether
+    /// the data was stored, e.g. to account it in the storage's quota. Returns `false` if the
+    /// sample was dropped (e.g. as out-of-date). By default, [`Storage::on_sample()`] is called
+    /// and the sample is assumed to be stored.
+    async fn on_sample_checked(&mut self, sample: Sample) -> ZResult<bool> {
+        self.on_sample(sample).await.map(|_| true)
+    }
+
+    /// Function called with a batch of data ([`Sample`]s) to be stored in this storage,
+    /// e.g. when importing a dump. By default, [`Storage::on_sample()`] is called for each
+    /// sample, but a storage can override it to write the whole batch at once.
+    async fn on_samples(&mut self, samples: Vec<Sample>) -> ZResult<()> {
+        for sample in samples {
+            self.on_sample(sample).await?;
+        }
+        Ok(())
+    }
+
+    /// Function called with a DELETE ([`Sample`]) for a path whose data outlived the storage's
+    /// time-to-live (see [`PROP_STORAGE_TTL`]). The storage must remove this data whatever its
+    /// conflict resolution. By default, [`Storage::on_sample()`] is called, which is enough for
+    /// the storages resolving the conflicts with [`LatestWins`], but not with [`FirstWins`].
+    async fn on_expired(&mut self, sample: Sample) -> ZResult<()> {
+        self.on_sample(sample).await
+    }
+
     /// Function called for each incoming query matching this storage's PathExpression.
     /// This storage should reply with data matching the query calling [`Query::reply()`].
     async fn on_query(&mut self, query: Query) -> ZResult<()>;
+
+    /// Returns all the data ([`Sample`]s) currently stored in this storage (but not the deleted ones),
+    /// e.g. to export it in a dump. By default, an error is returned as a storage is not required
+    /// to support it.
+    async fn get_all_samples(&self) -> ZResult<Vec<Sample>> {
+        Err(ZError::new(
+            ZErrorKind::Other {
+                descr: "This storage doesn't support exporting its content".to_string(),
+            },
+            file!(),
+            line!(),
+            None,
+        ))
+    }
+
+    /// Returns a page of the data ([`Sample`]s) currently stored in this storage (but not the
+    /// deleted ones): at most `page.limit` samples, in lexicographic order of their paths, after
+    /// `page.after` (if any). It's used to export the content of the storage by batches.
+    /// By default, the page is taken from [`Storage::get_all_samples()`], but a storage can
+    /// override it not to load all its data in memory for each page.
+    async fn get_samples_page(&self, page: &QueryPage) -> ZResult<Vec<Sample>> {
+        let samples = self.get_all_samples().await?;
+        Ok(utils::get_samples_page(&samples, page))
+    }
+}
+
+/// A strategy to resolve the conflicts between the samples received by a storage for a same path.
+///
+/// Any `Fn(&Sample, Sample) -> Option<Sample>` closure implements this trait,
+/// allowing a Storage to use a custom merge function.
+pub trait ConflictResolution: Send + Sync {
+    /// Given the `stored` sample for a path and an `incoming` sample for the same path
+    /// (each of them being a PUT or a DELETE), returns the sample to be stored instead of
+    /// the `stored` one, or `None` to keep the `stored` one and drop the `incoming` one.
+    ///
+    /// The Storage is expected to timestamp the samples that don't have a timestamp
+    /// (e.g. with [`zenoh::utils::new_reception_timestamp()`]) before calling this operation.
+    fn resolve(&self, stored: &Sample, incoming: Sample) -> Option<Sample>;
+}
+
+impl<F> ConflictResolution for F
+where
+    F: Fn(&Sample, Sample) -> Option<Sample> + Send + Sync,
+{
+    fn resolve(&self, stored: &Sample, incoming: Sample) -> Option<Sample> {
+        self(stored, incoming)
+    }
+}
+
+/// The sample with the most recent timestamp wins.
+pub struct LatestWins;
+
+impl ConflictResolution for LatestWins {
+    fn resolve(&self, stored: &Sample, incoming: Sample) -> Option<Sample> {
+        if incoming.get_timestamp() > stored.get_timestamp() {
+            Some(incoming)
+        } else {
+            None
+        }
+    }
+}
+
+/// The sample with the oldest timestamp wins: once stored, a sample is neither replaced
+/// nor deleted by the samples published after it.
+pub struct FirstWins;
+
+impl ConflictResolution for FirstWins {
+    fn resolve(&self, stored: &Sample, incoming: Sample) -> Option<Sample> {
+        if incoming.get_timestamp() < stored.get_timestamp() {
+            Some(incoming)
+        } else {
+            None
+        }
+    }
 }
 
 /// An interceptor allowing to modify the data pushed into a storage before it's actually stored.
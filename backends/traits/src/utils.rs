@@ -14,7 +14,110 @@
 
 //! Some useful functions for Backend/Storage implementations.
 
+use crate::{ConflictResolution, FirstWins, LatestWins, PROP_STORAGE_CONFLICT_RESOLUTION};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
 use zenoh::net::utils::resource_name::*;
+use zenoh::net::{QueryPage, Sample};
+use zenoh::{Properties, ZError, ZErrorKind, ZResult};
+
+/// Returns the [`ConflictResolution`] configured in the properties of a Storage
+/// via the [`PROP_STORAGE_CONFLICT_RESOLUTION`] property ([`LatestWins`] by default).
+///
+/// # Examples:
+/// ```
+/// # use zenoh::Properties;
+/// # use zenoh_backend_traits::utils::get_conflict_resolution;
+/// assert!(get_conflict_resolution(&Properties::from("path_expr=/demo/**")).is_ok());
+/// assert!(get_conflict_resolution(&Properties::from("conflict_resolution=first_wins")).is_ok());
+/// assert!(get_conflict_resolution(&Properties::from("conflict_resolution=random")).is_err());
+/// ```
+pub fn get_conflict_resolution(props: &Properties) -> ZResult<Box<dyn ConflictResolution>> {
+    match props
+        .get(PROP_STORAGE_CONFLICT_RESOLUTION)
+        .map(|s| s.as_str())
+    {
+        None | Some("latest_wins") => Ok(Box::new(LatestWins)),
+        Some("first_wins") => Ok(Box::new(FirstWins)),
+        Some(other) => Err(ZError::new(
+            ZErrorKind::Other {
+                descr: format!(
+                    "Invalid {} property: {} (expected latest_wins or first_wins)",
+                    PROP_STORAGE_CONFLICT_RESOLUTION, other
+                ),
+            },
+            file!(),
+            line!(),
+            None,
+        )),
+    }
+}
+
+// A sample ordered by its path
+struct ByPath<'a>(&'a Sample);
+
+impl Ord for ByPath<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.res_name.cmp(&other.0.res_name)
+    }
+}
+
+impl PartialOrd for ByPath<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for ByPath<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.res_name == other.0.res_name
+    }
+}
+
+impl Eq for ByPath<'_> {}
+
+/// Returns the `page` of the given `samples`: at most `page.limit` of them, in lexicographic order
+/// of their paths, after `page.after` (if any). Only the samples of the page are kept in memory
+/// (and cloned) while going through all the samples.
+///
+/// Use this operation in [`Storage::get_samples_page()`](crate::Storage::get_samples_page())
+/// implementation with an iterator on the stored samples.
+///
+/// # Examples:
+/// ```
+/// # use zenoh::net::{QueryPage, Sample};
+/// # use zenoh_backend_traits::utils::get_samples_page;
+/// let samples: Vec<Sample> = ["/c", "/a", "/d", "/b"]
+///     .iter()
+///     .map(|path| Sample { res_name: path.to_string(), payload: vec![].into(), data_info: None })
+///     .collect();
+/// let page = QueryPage { limit: 2, after: Some("/a".to_string()) };
+/// let paths: Vec<String> = get_samples_page(&samples, &page)
+///     .into_iter()
+///     .map(|sample| sample.res_name)
+///     .collect();
+/// assert_eq!(paths, ["/b", "/c"]);
+/// ```
+pub fn get_samples_page<'a, I>(samples: I, page: &QueryPage) -> Vec<Sample>
+where
+    I: IntoIterator<Item = &'a Sample>,
+{
+    let limit = page.limit as usize;
+    // the greatest path of the page is at the top of the heap, to be replaced by a lesser one
+    let mut heap = BinaryHeap::new();
+    for sample in samples {
+        if page.follows(&sample.res_name) {
+            heap.push(ByPath(sample));
+            if heap.len() > limit {
+                heap.pop();
+            }
+        }
+    }
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|sample| sample.0.clone())
+        .collect()
+}
 
 /// Returns the longest prefix in a Path expressions that doesn't contain any '*' character.  
 /// This would be the common prefix of all keys stored in a storage using this Path expression.
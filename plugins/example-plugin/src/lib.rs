@@ -47,6 +47,8 @@ async fn run(runtime: Runtime, args: &'static ArgMatches<'_>) {
         reliability: Reliability::Reliable,
         mode: SubMode::Push,
         period: None,
+        history: History::None,
+        filter: None,
     };
 
     let selector: ResKey = args.value_of("storage-selector").unwrap().into();
@@ -0,0 +1,440 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+//! A plugin caching the replies to the queries on some path expressions, to serve the repeated
+//! queries from the router instead of the (possibly slow) queryables, e.g. database-backed storages.
+//!
+//! The cache declares a storage queryable on each path expression. When it's queried, it replies
+//! with the cached replies to the same selector if they are younger than the TTL. Otherwise it
+//! forwards the query to the other queryables and caches their replies.
+//!
+//! The clients reach the cache of their closest router with a [`Target::Nearest`] query.
+//! With the other targets, the queryables are queried as well.
+//!
+//! The concurrent identical queries wait for the replies of the query being forwarded.
+//! A forwarded query is marked with the `_query_cache` property, so that the caches it reaches
+//! reply from their cache only instead of forwarding it again.
+#![recursion_limit = "256"]
+
+use async_std::sync::Arc;
+use async_std::task;
+use clap::{Arg, ArgMatches};
+use futures::channel::oneshot;
+use futures::prelude::*;
+use log::{debug, error, info, trace, warn};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use zenoh::net::queryable::STORAGE;
+use zenoh::net::runtime::Runtime;
+use zenoh::net::*;
+use zenoh_util::zlock;
+
+const DEFAULT_TTL: &str = "10";
+const DEFAULT_SIZE: &str = "1000";
+// The property marking the queries forwarded by a cache
+const FORWARD_MARKER: &str = "_query_cache";
+
+#[no_mangle]
+pub fn get_expected_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::from_usage(
+            "--query-cache=[PATH_EXPR]... \
+            'A path expression on which the replies to the queries are cached. \
+            Repeat this option to cache several path expressions'",
+        ),
+        Arg::from_usage(
+            "--query-cache-ttl=[SECONDS] \
+            'The time during which the cached replies to a query are served'",
+        )
+        .default_value(DEFAULT_TTL),
+        Arg::from_usage(
+            "--query-cache-size=[SELECTORS] \
+            'The maximum number of selectors whose replies are cached. \
+            When it's reached, the oldest cached replies are evicted'",
+        )
+        .default_value(DEFAULT_SIZE),
+    ]
+}
+
+#[no_mangle]
+pub fn start(runtime: Runtime, args: &'static ArgMatches<'_>) {
+    async_std::task::spawn(run(runtime, args));
+}
+
+async fn run(runtime: Runtime, args: &'static ArgMatches<'_>) {
+    // Try to initiate login.
+    // Required in case of dynamic lib, otherwise no logs.
+    // But cannot be done twice in case of static link.
+    let _ = env_logger::try_init();
+
+    let path_exprs: Vec<String> = match args.values_of("query-cache") {
+        Some(values) => values.map(|v| v.to_string()).collect(),
+        None => {
+            debug!("No --query-cache option: no query is cached");
+            return;
+        }
+    };
+    let ttl = match args.value_of("query-cache-ttl").unwrap().parse::<f64>() {
+        Ok(ttl) if ttl >= 0.0 => Duration::from_secs_f64(ttl),
+        _ => {
+            warn!(
+                "Invalid --query-cache-ttl, use default value {}",
+                DEFAULT_TTL
+            );
+            Duration::from_secs(DEFAULT_TTL.parse().unwrap())
+        }
+    };
+    let capacity = match args.value_of("query-cache-size").unwrap().parse::<usize>() {
+        Ok(capacity) if capacity > 0 => capacity,
+        _ => {
+            warn!(
+                "Invalid --query-cache-size, use default value {}",
+                DEFAULT_SIZE
+            );
+            DEFAULT_SIZE.parse().unwrap()
+        }
+    };
+    info!(
+        "Cache the replies to the queries on {:?} for {} seconds (up to {} selectors)",
+        path_exprs,
+        ttl.as_secs_f64(),
+        capacity
+    );
+
+    // No local routing: the queries forwarded by the cache must not reach its own queryables
+    let session = Session::init(runtime, false, vec![], vec![]).await;
+    let cache = Arc::new(QueryCache {
+        session,
+        ttl,
+        capacity,
+        entries: Mutex::new(Entries::default()),
+    });
+    for path_expr in path_exprs {
+        let cache = cache.clone();
+        task::spawn(async move {
+            let mut queryable = match cache
+                .session
+                .declare_queryable(&path_expr.clone().into(), STORAGE)
+                .await
+            {
+                Ok(queryable) => queryable,
+                Err(e) => {
+                    error!("Failed to declare queryable on {}: {}", path_expr, e);
+                    return;
+                }
+            };
+            while let Some(query) = queryable.receiver().next().await {
+                task::spawn(cache.clone().handle(query));
+            }
+        });
+    }
+}
+
+// Adds the forwarding marker to the properties of `predicate`.
+fn mark(predicate: &str) -> String {
+    // the properties may contain parentheses (e.g. `starttime=now()`): they end at the last one
+    if let Some(open) = predicate.find('(') {
+        if let Some(close) = predicate.rfind(')').filter(|close| *close > open) {
+            let sep = if close == open + 1 { "" } else { ";" };
+            return format!(
+                "{}{}{}{}",
+                &predicate[..close],
+                sep,
+                FORWARD_MARKER,
+                &predicate[close..]
+            );
+        }
+    }
+    let (head, fragment) = predicate.split_at(predicate.find('[').unwrap_or(predicate.len()));
+    let head = if head.is_empty() { "?" } else { head };
+    format!("{}({}){}", head, FORWARD_MARKER, fragment)
+}
+
+// Removes the forwarding marker from `predicate`, or returns None if it isn't marked.
+fn unmark(predicate: &str) -> Option<String> {
+    let marked = [
+        (format!(";{})", FORWARD_MARKER), ")"),
+        (format!("({})", FORWARD_MARKER), ""),
+    ];
+    marked.iter().find_map(|(marker, by)| {
+        predicate.find(marker.as_str()).map(|i| {
+            let unmarked = format!(
+                "{}{}{}",
+                &predicate[..i],
+                by,
+                &predicate[i + marker.len()..]
+            );
+            if unmarked == "?" {
+                String::new()
+            } else {
+                unmarked
+            }
+        })
+    })
+}
+
+#[derive(Default)]
+struct Entries {
+    // for each selector: its cached replies, when they were fetched and their insertion sequence number
+    replies: HashMap<String, (Vec<Sample>, Instant, u64)>,
+    // the cached selectors in insertion order, for the eviction
+    order: BTreeMap<u64, String>,
+    seq: u64,
+    // the selectors being fetched, with the queries waiting for their replies
+    pending: HashMap<String, Vec<oneshot::Sender<Vec<Sample>>>>,
+}
+
+enum Lookup {
+    // The replies are cached and fresh
+    Hit(Vec<Sample>),
+    // The replies are being fetched: wait for them
+    Pending(oneshot::Receiver<Vec<Sample>>),
+    // The replies are not fresh and must not be fetched: the stale ones, if any
+    Stale(Vec<Sample>),
+    // The replies must be fetched
+    Miss,
+}
+
+impl Entries {
+    // Looks up the replies to `selector`, registering their fetch on a miss if `fetch` is set.
+    fn lookup(&mut self, selector: &str, ttl: Duration, fetch: bool) -> Lookup {
+        let cached = self.replies.get(selector);
+        match cached {
+            Some((replies, time, _)) if time.elapsed() < ttl => Lookup::Hit(replies.clone()),
+            _ if !fetch => Lookup::Stale(
+                cached
+                    .map(|(replies, _, _)| replies.clone())
+                    .unwrap_or_default(),
+            ),
+            _ => match self.pending.get_mut(selector) {
+                Some(waiters) => {
+                    let (sender, receiver) = oneshot::channel();
+                    waiters.push(sender);
+                    Lookup::Pending(receiver)
+                }
+                None => {
+                    self.pending.insert(selector.to_string(), vec![]);
+                    Lookup::Miss
+                }
+            },
+        }
+    }
+
+    // Caches the fetched replies to `selector`, if any, and returns them to the waiting queries.
+    // The waiting queries get the stale replies, if any, when the fetch failed.
+    fn insert(&mut self, selector: String, replies: Option<Vec<Sample>>, capacity: usize) {
+        let waiters = self.pending.remove(&selector).unwrap_or_default();
+        let replies = match replies {
+            Some(replies) => replies,
+            None => {
+                let stale = self
+                    .replies
+                    .get(&selector)
+                    .map(|(replies, _, _)| replies.clone())
+                    .unwrap_or_default();
+                for waiter in waiters {
+                    let _ = waiter.send(stale.clone());
+                }
+                return;
+            }
+        };
+        for waiter in waiters {
+            let _ = waiter.send(replies.clone());
+        }
+        if let Some((_, _, seq)) = self.replies.remove(&selector) {
+            self.order.remove(&seq);
+        }
+        if self.replies.len() >= capacity {
+            let oldest = self.order.keys().next().cloned();
+            if let Some(selector) = oldest.and_then(|seq| self.order.remove(&seq)) {
+                trace!("Evict the replies to {} from the query cache", selector);
+                self.replies.remove(&selector);
+            }
+        }
+        self.seq += 1;
+        let seq = self.seq;
+        self.order.insert(seq, selector.clone());
+        self.replies
+            .insert(selector, (replies, Instant::now(), seq));
+    }
+}
+
+struct QueryCache {
+    session: Session,
+    ttl: Duration,
+    capacity: usize,
+    entries: Mutex<Entries>,
+}
+
+impl QueryCache {
+    // Forwards the query to the other queryables, returning their replies or None on failure.
+    async fn fetch(&self, res_name: &str, predicate: &str) -> Option<Vec<Sample>> {
+        let replies = self
+            .session
+            .query(
+                &res_name.into(),
+                &mark(predicate),
+                QueryTarget::default(),
+                QueryConsolidation::default(),
+            )
+            .await;
+        match replies {
+            Ok(replies) => Some(replies.map(|reply| reply.data).collect().await),
+            Err(e) => {
+                warn!("Failed to forward query {}{}: {}", res_name, predicate, e);
+                None
+            }
+        }
+    }
+
+    async fn handle(self: Arc<Self>, query: Query) {
+        // A query forwarded by another cache is served from the cache only, not to loop
+        let (predicate, forwarded) = match unmark(&query.predicate) {
+            Some(predicate) => (predicate, true),
+            None => (query.predicate.clone(), false),
+        };
+        let selector = format!("{}{}", query.res_name, predicate);
+        let lookup = zlock!(self.entries).lookup(&selector, self.ttl, !forwarded);
+        let mut replies = match lookup {
+            Lookup::Hit(replies) => {
+                trace!("Reply to {} from the query cache", selector);
+                replies
+            }
+            Lookup::Pending(receiver) => {
+                trace!(
+                    "Query {} already being fetched, wait for its replies",
+                    selector
+                );
+                receiver.await.unwrap_or_default()
+            }
+            Lookup::Stale(replies) => {
+                debug!(
+                    "Query {} forwarded by another cache, reply cached values",
+                    selector
+                );
+                replies
+            }
+            Lookup::Miss => {
+                trace!("Forward query {}", selector);
+                let replies = self.fetch(&query.res_name, &predicate).await;
+                zlock!(self.entries).insert(selector, replies.clone(), self.capacity);
+                replies.unwrap_or_default()
+            }
+        };
+        if let Some(page) = &query.page {
+            replies.retain(|sample| page.follows(&sample.res_name));
+            replies.sort_by(|s1, s2| s1.res_name.cmp(&s2.res_name));
+            replies.truncate(page.limit as usize);
+        }
+        for sample in replies {
+            query.reply_async(sample).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(res_name: &str) -> Sample {
+        Sample {
+            res_name: res_name.to_string(),
+            payload: ZBuf::from(res_name.as_bytes()),
+            data_info: None,
+        }
+    }
+
+    fn names(samples: &[Sample]) -> Vec<&str> {
+        samples.iter().map(|s| s.res_name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_marker() {
+        for (predicate, marked) in &[
+            ("", "?(_query_cache)"),
+            ("?x>1", "?x>1(_query_cache)"),
+            ("?(starttime=now())", "?(starttime=now();_query_cache)"),
+            ("?()", "?(_query_cache)"),
+            ("?x>1[a;b]", "?x>1(_query_cache)[a;b]"),
+            ("?(p=1)[a]", "?(p=1;_query_cache)[a]"),
+        ] {
+            assert_eq!(mark(predicate), *marked);
+            let unmarked = unmark(marked).unwrap();
+            assert_eq!(mark(&unmarked), *marked);
+        }
+        assert_eq!(unmark(&mark("")).as_deref(), Some(""));
+        assert_eq!(unmark(&mark("?x>1")).as_deref(), Some("?x>1"));
+        assert!(unmark("?(starttime=now())").is_none());
+    }
+
+    #[test]
+    fn test_pending() {
+        let ttl = Duration::from_secs(10);
+        let mut entries = Entries::default();
+        assert!(matches!(entries.lookup("/a", ttl, true), Lookup::Miss));
+
+        // the concurrent queries wait for the fetched replies
+        let waiters: Vec<_> = (0..2)
+            .map(|_| match entries.lookup("/a", ttl, true) {
+                Lookup::Pending(receiver) => receiver,
+                _ => panic!("query not pending"),
+            })
+            .collect();
+        // the forwarded ones don't
+        assert!(matches!(entries.lookup("/a", ttl, false), Lookup::Stale(r) if r.is_empty()));
+
+        entries.insert("/a".to_string(), Some(vec![sample("/a/1")]), 10);
+        for waiter in waiters {
+            let replies = task::block_on(waiter).unwrap();
+            assert_eq!(names(&replies), vec!["/a/1"]);
+        }
+        assert!(
+            matches!(entries.lookup("/a", ttl, true), Lookup::Hit(r) if names(&r) == vec!["/a/1"])
+        );
+        assert!(entries.pending.is_empty());
+    }
+
+    #[test]
+    fn test_fetch_failure() {
+        let mut entries = Entries::default();
+        entries.insert("/a".to_string(), Some(vec![sample("/a/1")]), 10);
+
+        // the waiting queries get the stale replies when the fetch fails
+        assert!(matches!(
+            entries.lookup("/a", Duration::from_secs(0), true),
+            Lookup::Miss
+        ));
+        let waiter = match entries.lookup("/a", Duration::from_secs(0), true) {
+            Lookup::Pending(receiver) => receiver,
+            _ => panic!("query not pending"),
+        };
+        entries.insert("/a".to_string(), None, 10);
+        assert_eq!(names(&task::block_on(waiter).unwrap()), vec!["/a/1"]);
+        assert!(entries.pending.is_empty());
+    }
+
+    #[test]
+    fn test_eviction() {
+        let ttl = Duration::from_secs(10);
+        let mut entries = Entries::default();
+        for selector in &["/a", "/b", "/c"] {
+            assert!(matches!(entries.lookup(selector, ttl, true), Lookup::Miss));
+            entries.insert(selector.to_string(), Some(vec![sample(selector)]), 2);
+        }
+        // the oldest selector is evicted
+        assert!(matches!(entries.lookup("/a", ttl, true), Lookup::Miss));
+        assert!(matches!(entries.lookup("/b", ttl, true), Lookup::Hit(_)));
+        assert!(matches!(entries.lookup("/c", ttl, true), Lookup::Hit(_)));
+    }
+}
@@ -13,13 +13,19 @@
 //
 
 use async_std::sync::Arc;
+use async_tungstenite::tungstenite::handshake::derive_accept_key;
+use async_tungstenite::tungstenite::protocol::Role;
+use async_tungstenite::tungstenite::Message;
+use async_tungstenite::WebSocketStream;
 use clap::{Arg, ArgMatches};
 use futures::prelude::*;
 use http_types::Method;
 use runtime::Runtime;
 use std::convert::TryFrom;
 use std::str::FromStr;
-use tide::http::Mime;
+use std::time::Duration;
+use tide::http::url::form_urlencoded;
+use tide::http::{Mime, Url};
 use tide::sse::Sender;
 use tide::{Request, Response, Server, StatusCode};
 use zenoh::net::*;
@@ -28,11 +34,18 @@ use zenoh::{Change, Selector, Value};
 const PORT_SEPARATOR: char = ':';
 const DEFAULT_HTTP_HOST: &str = "0.0.0.0";
 const DEFAULT_HTTP_PORT: &str = "8000";
+const DEFAULT_READINESS_CRITERIA: &str = "transports,plugins";
+const HEALTH_QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+const WS_PREFIX: &str = "/ws";
+const WS_SEND_TIMEOUT: Duration = Duration::from_secs(10);
+const QUERY_OPTIONS: [&str; 4] = ["consolidation", "target", "timeout", "parameters"];
 
-const SSE_SUB_INFO: SubInfo = SubInfo {
+const STREAM_SUB_INFO: SubInfo = SubInfo {
     reliability: Reliability::Reliable,
     mode: SubMode::Push,
     period: None,
+    history: History::None,
+    filter: None,
 };
 
 fn parse_http_port(arg: &str) -> String {
@@ -104,7 +117,7 @@ fn sample_to_json(sample: Sample) -> String {
     }
 }
 
-async fn to_json(results: ReplyReceiver) -> String {
+async fn to_json(results: impl Stream<Item = Reply>) -> String {
     let values = results
         .filter_map(move |reply| async move { Some(sample_to_json(reply.data)) })
         .collect::<Vec<String>>()
@@ -121,7 +134,7 @@ fn sample_to_html(sample: Sample) -> String {
     )
 }
 
-async fn to_html(results: ReplyReceiver) -> String {
+async fn to_html(results: impl Stream<Item = Reply>) -> String {
     let values = results
         .filter_map(move |reply| async move { Some(sample_to_html(reply.data)) })
         .collect::<Vec<String>>()
@@ -174,6 +187,11 @@ pub fn get_expected_args2<'a, 'b>() -> Vec<Arg<'a, 'b>> {
     vec![
         Arg::from_usage("--rest-http-port 'The REST plugin's http port'")
             .default_value(DEFAULT_HTTP_PORT),
+        Arg::from_usage(
+            "--rest-readiness-criteria=[CRITERIA] 'The comma-separated sections of the router health \
+            required to be ok for /readyz to succeed (transports, plugins)'",
+        )
+        .default_value(DEFAULT_READINESS_CRITERIA),
     ]
 }
 
@@ -182,23 +200,87 @@ pub fn start(runtime: Runtime, args: &'static ArgMatches<'_>) {
     async_std::task::spawn(run(runtime, args.clone()));
 }
 
-async fn query(req: Request<(Arc<Session>, String)>) -> tide::Result<Response> {
-    log::trace!("Incoming GET request: {:?}", req);
+// The options of a GET request, mapped onto the ones of the zenoh query.
+struct QueryOptions {
+    selector: Selector,
+    target: QueryTarget,
+    consolidation: QueryConsolidation,
+    timeout: Option<Duration>,
+}
+
+// Parses the query string of `url` into the options of the zenoh query. The `&` separated
+// `consolidation`, `target`, `timeout` and `parameters` options are taken out of the query
+// string, and the rest of it is forwarded unchanged as the predicate of the selector, with the
+// value of the `parameters` option, if any, in its place.
+fn query_options(url: &Url) -> Result<QueryOptions, String> {
     // Reconstruct Selector from req.url() (no easier way...)
-    let url = req.url();
     let mut s = String::with_capacity(url.as_str().len());
     s.push_str(url.path());
-    if let Some(q) = url.query() {
+    let mut target = QueryTarget::default();
+    let mut consolidation = None;
+    let mut timeout = None;
+    let mut predicates: Vec<String> = vec![];
+    for part in url.query().unwrap_or("").split('&') {
+        let (k, v) = match form_urlencoded::parse(part.as_bytes()).next() {
+            Some((k, v)) if QUERY_OPTIONS.contains(&k.as_ref()) => (k, v),
+            _ => {
+                if !part.is_empty() {
+                    predicates.push(part.to_string());
+                }
+                continue;
+            }
+        };
+        match k.as_ref() {
+            "consolidation" => {
+                consolidation = Some(match v.as_ref() {
+                    "none" => QueryConsolidation::none(),
+                    "latest" => QueryConsolidation::default(),
+                    _ => return Err(format!("Invalid consolidation: {}", v)),
+                })
+            }
+            "target" => {
+                target.target = match v.as_ref() {
+                    "all" => Target::All,
+                    "best_matching" => Target::BestMatching,
+                    _ => return Err(format!("Invalid target: {}", v)),
+                }
+            }
+            "timeout" => match v.parse::<u64>() {
+                Ok(ms) => timeout = Some(Duration::from_millis(ms)),
+                Err(_) => return Err(format!("Invalid timeout: {}", v)),
+            },
+            _ => predicates.push(v.into_owned()),
+        }
+    }
+    if !predicates.is_empty() {
         s.push('?');
-        s.push_str(q);
+        s.push_str(&predicates.join("&"));
     }
-    let selector = match Selector::try_from(s) {
-        Ok(sel) => sel,
+    let selector = Selector::try_from(s).map_err(|e| e.to_string())?;
+    let consolidation = consolidation.unwrap_or_else(|| {
+        if selector.has_time_range() {
+            QueryConsolidation::none()
+        } else {
+            QueryConsolidation::default()
+        }
+    });
+    Ok(QueryOptions {
+        selector,
+        target,
+        consolidation,
+        timeout,
+    })
+}
+
+async fn query(req: Request<(Arc<Session>, String)>) -> tide::Result<Response> {
+    log::trace!("Incoming GET request: {:?}", req);
+    let options = match query_options(req.url()) {
+        Ok(options) => options,
         Err(e) => {
             return Ok(response(
                 StatusCode::BadRequest,
                 Mime::from_str("text/plain").unwrap(),
-                &e.to_string(),
+                &e,
             ))
         }
     };
@@ -230,7 +312,7 @@ async fn query(req: Request<(Arc<Session>, String)>) -> tide::Result<Response> {
                     let mut sub = req
                         .state()
                         .0
-                        .declare_subscriber(&resource, &SSE_SUB_INFO)
+                        .declare_subscriber(&resource, &STREAM_SUB_INFO)
                         .await
                         .unwrap();
                     loop {
@@ -264,24 +346,25 @@ async fn query(req: Request<(Arc<Session>, String)>) -> tide::Result<Response> {
             },
         ))
     } else {
-        let resource = path_to_resource(selector.path_expr.as_str(), &req.state().1);
-        let consolidation = if selector.has_time_range() {
-            QueryConsolidation::none()
-        } else {
-            QueryConsolidation::default()
-        };
+        let resource = path_to_resource(options.selector.path_expr.as_str(), &req.state().1);
         match req
             .state()
             .0
             .query(
                 &resource,
-                &selector.predicate,
-                QueryTarget::default(),
-                consolidation,
+                &options.selector.predicate,
+                options.target,
+                options.consolidation,
             )
             .await
         {
             Ok(receiver) => {
+                // the replies received after the timeout are dropped
+                let deadline = match options.timeout {
+                    Some(timeout) => async_std::task::sleep(timeout).boxed(),
+                    None => future::pending().boxed(),
+                };
+                let receiver = receiver.take_until(deadline);
                 if first_accept == "text/html" {
                     Ok(response(
                         StatusCode::Ok,
@@ -338,6 +421,285 @@ async fn write(mut req: Request<(Arc<Session>, String)>) -> tide::Result<Respons
     }
 }
 
+fn ws_error(e: impl std::fmt::Display) -> Message {
+    Message::Text(serde_json::json!({ "error": e.to_string() }).to_string())
+}
+
+// Writes the data of a frame received on the WebSocket of `resource`:
+// - a binary frame is put as is on `resource`, if it has no wildcard;
+// - a text frame is a JSON object with an optional `"key"` (`resource` by default), an optional
+//   `"kind"` (`"PUT"`, `"PATCH"` or `"DELETE"`, `"PUT"` by default), an optional `"value"`,
+//   an optional `"encoding"` (a MIME type) and an optional `"congestion_control"`
+//   (`"block"` or `"drop"`, `congestion_control` by default).
+async fn ws_write(
+    session: &Session,
+    pid: &str,
+    resource: &ResKey,
+    congestion_control: CongestionControl,
+    frame: Message,
+) -> Result<(), String> {
+    let (resource, payload, encoding, kind, congestion_control) = match frame {
+        Message::Binary(_) if resource.to_string().contains('*') => {
+            return Err(format!("Binary frames can't be put on {}", resource))
+        }
+        Message::Binary(bytes) => (
+            resource.clone(),
+            bytes,
+            encoding::APP_OCTET_STREAM,
+            data_kind::PUT,
+            congestion_control,
+        ),
+        Message::Text(text) => {
+            let json: serde_json::Value = serde_json::from_str(&text).map_err(|e| e.to_string())?;
+            let resource = match json["key"].as_str() {
+                Some(key) => path_to_resource(key, pid),
+                None => resource.clone(),
+            };
+            let kind = match json["kind"].as_str().map(|k| k.to_uppercase()).as_deref() {
+                None | Some("PUT") => data_kind::PUT,
+                Some("PATCH") => data_kind::PATCH,
+                Some("DELETE") => data_kind::DELETE,
+                Some(k) => return Err(format!("Invalid kind: {}", k)),
+            };
+            let (payload, default_encoding) = match &json["value"] {
+                serde_json::Value::Null => (vec![], encoding::APP_OCTET_STREAM),
+                serde_json::Value::String(s) => (s.as_bytes().to_vec(), encoding::TEXT_PLAIN),
+                value => (value.to_string().into_bytes(), encoding::APP_JSON),
+            };
+            let encoding = match json["encoding"].as_str() {
+                Some(mime) => enc_from_mime(Some(Mime::from_str(mime).map_err(|e| e.to_string())?)),
+                None => default_encoding,
+            };
+            let congestion_control = match json["congestion_control"].as_str() {
+                Some(cc) => CongestionControl::from_str(cc).map_err(|e| e.to_string())?,
+                None => congestion_control,
+            };
+            (resource, payload, encoding, kind, congestion_control)
+        }
+        _ => return Ok(()),
+    };
+    session
+        .write_ext(
+            &resource,
+            payload.into(),
+            encoding,
+            kind,
+            congestion_control,
+        )
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Streams the samples matching `resource` on the WebSocket, as JSON text frames
+// or as binary frames with only the payload, until the WebSocket is closed.
+// The frames received on the WebSocket are written with `congestion_control`.
+async fn ws_session<S>(
+    session: Arc<Session>,
+    pid: String,
+    resource: ResKey,
+    binary: bool,
+    congestion_control: CongestionControl,
+    ws: WebSocketStream<S>,
+) where
+    S: futures::io::AsyncRead + futures::io::AsyncWrite + Unpin,
+{
+    log::debug!(
+        "Subscribe to {} for WebSocket (task {})",
+        resource,
+        async_std::task::current().id()
+    );
+    let mut sub = match session
+        .declare_subscriber(&resource, &STREAM_SUB_INFO)
+        .await
+    {
+        Ok(sub) => sub,
+        Err(e) => {
+            log::error!("Error declaring subscriber for WebSocket: {}", e);
+            return;
+        }
+    };
+    let (mut sink, mut frames) = ws.split();
+    loop {
+        let event = async_std::prelude::FutureExt::race(
+            sub.receiver().next().map(Ok),
+            frames.next().map(Err),
+        )
+        .await;
+        let reply = match event {
+            Ok(Some(sample)) => {
+                if binary {
+                    Message::Binary(sample.payload.to_vec())
+                } else {
+                    Message::Text(sample_to_json(sample))
+                }
+            }
+            Err(Some(Ok(Message::Close(_)))) | Err(None) | Ok(None) => break,
+            Err(Some(Ok(frame))) => {
+                match ws_write(&session, &pid, &resource, congestion_control, frame).await {
+                    Ok(()) => continue,
+                    Err(e) => {
+                        log::warn!("Error writing data from the WebSocket: {}", e);
+                        ws_error(e)
+                    }
+                }
+            }
+            Err(Some(Err(e))) => {
+                log::warn!("Error receiving data from the WebSocket: {}", e);
+                break;
+            }
+        };
+        match async_std::future::timeout(WS_SEND_TIMEOUT, sink.send(reply)).await {
+            Ok(Ok(())) => (),
+            Ok(Err(e)) => {
+                log::warn!("Error sending data on the WebSocket: {}", e);
+                break;
+            }
+            Err(_) => {
+                log::debug!(
+                    "WebSocket timeout! (task {})",
+                    async_std::task::current().id()
+                );
+                break;
+            }
+        }
+    }
+    log::debug!(
+        "Unsubscribe and terminate WebSocket (task {})",
+        async_std::task::current().id()
+    );
+    if let Err(e) = sub.undeclare().await {
+        log::error!("Error undeclaring subscriber: {}", e);
+    }
+}
+
+async fn websocket(req: Request<(Arc<Session>, String)>) -> tide::Result<Response> {
+    log::trace!("Incoming WebSocket request: {:?}", req);
+    let is_upgrade = req
+        .header("upgrade")
+        .map_or(false, |h| h.as_str().eq_ignore_ascii_case("websocket"));
+    let key = match req.header("sec-websocket-key") {
+        Some(key) if is_upgrade => key.as_str().to_string(),
+        _ => {
+            return Ok(response(
+                StatusCode::UpgradeRequired,
+                Mime::from_str("text/plain").unwrap(),
+                "A WebSocket upgrade is expected",
+            ))
+        }
+    };
+    let path = req.url().path();
+    let resource = path_to_resource(&path[WS_PREFIX.len()..], &req.state().1);
+    let binary = req
+        .url()
+        .query_pairs()
+        .any(|(k, v)| k == "format" && v == "binary");
+    // The congestion control of the frames written from the WebSocket,
+    // dropping them by default like the PUT requests
+    let congestion_control = match req
+        .url()
+        .query_pairs()
+        .find(|(k, _)| k == "congestion_control")
+    {
+        Some((_, cc)) => match CongestionControl::from_str(&cc) {
+            Ok(cc) => cc,
+            Err(e) => {
+                return Ok(response(
+                    StatusCode::BadRequest,
+                    Mime::from_str("text/plain").unwrap(),
+                    &e.to_string(),
+                ))
+            }
+        },
+        None => CongestionControl::Drop,
+    };
+
+    let mut res = http_types::Response::new(StatusCode::SwitchingProtocols);
+    res.insert_header("upgrade", "websocket");
+    res.insert_header("connection", "Upgrade");
+    res.insert_header("sec-websocket-accept", derive_accept_key(key.as_bytes()));
+    let upgrade = res.recv_upgrade().await;
+    let (session, pid) = req.state().clone();
+    async_std::task::spawn(async move {
+        if let Some(stream) = upgrade.await {
+            let ws = WebSocketStream::from_raw_socket(stream, Role::Server, None).await;
+            ws_session(session, pid, resource, binary, congestion_control, ws).await;
+        }
+    });
+    Ok(res.into())
+}
+
+// Retrieves the health document of the local router from its admin space.
+async fn get_health(req: &Request<(Arc<Session>, String)>) -> Option<serde_json::Value> {
+    let resource = ResKey::from(format!("/@/router/{}/health", req.state().1));
+    let receiver = req
+        .state()
+        .0
+        .query(
+            &resource,
+            "",
+            QueryTarget::default(),
+            QueryConsolidation::default(),
+        )
+        .await
+        .ok()?;
+    let mut replies = receiver.take(1);
+    let reply = async_std::future::timeout(HEALTH_QUERY_TIMEOUT, replies.next())
+        .await
+        .ok()??;
+    serde_json::from_slice(&reply.data.payload.contiguous()).ok()
+}
+
+fn health_response(ready: bool, health: &serde_json::Value) -> Response {
+    response(
+        if ready {
+            StatusCode::Ok
+        } else {
+            StatusCode::ServiceUnavailable
+        },
+        Mime::from_str("application/json").unwrap(),
+        &health.to_string(),
+    )
+}
+
+fn is_section_ok(health: &serde_json::Value, section: &str) -> bool {
+    match &health[section] {
+        serde_json::Value::Array(items) => items.iter().all(|item| item["status"] == "ok"),
+        value => value["status"] == "ok",
+    }
+}
+
+async fn healthz(req: Request<(Arc<Session>, String)>) -> tide::Result<Response> {
+    log::trace!("Incoming liveness request: {:?}", req);
+    match get_health(&req).await {
+        Some(health) => Ok(health_response(true, &health)),
+        None => Ok(response(
+            StatusCode::ServiceUnavailable,
+            Mime::from_str("text/plain").unwrap(),
+            "The router's health document is unavailable",
+        )),
+    }
+}
+
+async fn readyz(
+    req: Request<(Arc<Session>, String)>,
+    criteria: Arc<Vec<String>>,
+) -> tide::Result<Response> {
+    log::trace!("Incoming readiness request: {:?}", req);
+    match get_health(&req).await {
+        Some(health) => {
+            let ready = criteria
+                .iter()
+                .all(|section| is_section_ok(&health, section));
+            Ok(health_response(ready, &health))
+        }
+        None => Ok(response(
+            StatusCode::ServiceUnavailable,
+            Mime::from_str("text/plain").unwrap(),
+            "The router's health document is unavailable",
+        )),
+    }
+}
+
 pub async fn run(runtime: Runtime, args: ArgMatches<'_>) {
     // Try to initiate login.
     // Required in case of dynamic lib, otherwise no logs.
@@ -345,6 +707,14 @@ pub async fn run(runtime: Runtime, args: ArgMatches<'_>) {
     let _ = env_logger::try_init();
 
     let http_port = parse_http_port(args.value_of("rest-http-port").unwrap());
+    let readiness_criteria: Arc<Vec<String>> = Arc::new(
+        args.value_of("rest-readiness-criteria")
+            .unwrap_or(DEFAULT_READINESS_CRITERIA)
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect(),
+    );
 
     let pid = runtime.get_pid_str();
     let session = Session::init(runtime, true, vec![], vec![]).await;
@@ -361,8 +731,13 @@ pub async fn run(runtime: Runtime, args: ArgMatches<'_>) {
             .allow_credentials(false),
     );
 
+    app.at("/healthz").get(healthz);
+    app.at("/readyz")
+        .get(move |req| readyz(req, readiness_criteria.clone()));
+
     app.at("/").get(query);
     app.at("*").get(query);
+    app.at(&format!("{}/*", WS_PREFIX)).get(websocket);
 
     app.at("/").put(write);
     app.at("*").put(write);
@@ -387,3 +762,38 @@ fn path_to_resource(path: &str, pid: &str) -> ResKey {
         ResKey::from(path)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selector(url: &str) -> Result<String, String> {
+        query_options(&Url::parse(url).unwrap()).map(|options| options.selector.to_string())
+    }
+
+    #[test]
+    fn test_query_options() {
+        let base = "http://localhost:8000/demo/**";
+        assert_eq!(selector(base), Ok("/demo/**".to_string()));
+        assert_eq!(
+            selector(&format!("{}?(starttime=now()-1h)", base)),
+            Ok("/demo/**?(starttime=now()-1h)".to_string())
+        );
+        // the options are taken out, and the value predicates forwarded unchanged
+        let options = query_options(
+            &Url::parse(&format!("{}?x=1&timeout=500&target=all&y=2", base)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(options.selector.to_string(), "/demo/**?x=1&y=2");
+        assert_eq!(options.timeout, Some(Duration::from_millis(500)));
+        assert_eq!(options.target.target, Target::All);
+        assert_eq!(
+            selector(&format!(
+                "{}?consolidation=none&parameters=%28starttime%3Dnow%28%29%29",
+                base
+            )),
+            Ok("/demo/**?(starttime=now())".to_string())
+        );
+        assert!(selector(&format!("{}?x=1&target=some", base)).is_err());
+    }
+}
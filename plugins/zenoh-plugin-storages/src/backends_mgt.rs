@@ -11,7 +11,9 @@
 // Contributors:
 //   ADLINK zenoh team, <zenoh@adlink-labs.tech>
 //
+use super::quota::Quota;
 use super::storages_mgt::*;
+use super::StopHandle;
 use async_std::channel::{bounded, Sender};
 use async_std::sync::{Arc, RwLock};
 use async_std::task;
@@ -20,27 +22,38 @@ use futures::select;
 use log::{debug, error, trace, warn};
 use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::path::PathBuf;
+use std::time::Duration;
+use zenoh::net::runtime::AdminPermissions;
 use zenoh::{ChangeKind, Path, PathExpr, Selector, Value, ZError, ZErrorKind, ZResult, Zenoh};
+use zenoh_backend_traits::utils::get_conflict_resolution;
 use zenoh_backend_traits::{
-    IncomingDataInterceptor, OutgoingDataInterceptor, PROP_STORAGE_PATH_EXPR,
+    IncomingDataInterceptor, OutgoingDataInterceptor, PROP_STORAGE_DUMP_DIR,
+    PROP_STORAGE_PATH_EXPR, PROP_STORAGE_TTL,
 };
+use zenoh_util::properties::config::ZN_ADMIN_READ_STR;
 use zenoh_util::{zerror, zerror2};
 
 pub(crate) async fn start_backend(
     backend: Box<dyn zenoh_backend_traits::Backend>,
     admin_path: Path,
     zenoh: Arc<Zenoh>,
-) -> ZResult<Sender<bool>> {
+) -> ZResult<StopHandle> {
     let backend_name = admin_path.clone();
     trace!("Starting backend {}", backend_name);
 
     // Channel for the task to advertise when ready to receive requests
     let (ready_tx, ready_rx) = bounded::<bool>(1);
     // Channel to stop the task
-    let (stop_tx, stop_rx) = bounded::<bool>(1);
+    let (stop_tx, stop_rx) = bounded::<Sender<()>>(1);
 
     task::spawn(async move {
         let workspace = zenoh.workspace(Some(admin_path.clone())).await.unwrap();
+        let runtime = zenoh.session().runtime();
+        let read_permissions = AdminPermissions::reads(
+            &runtime.config(),
+            &format!("/@/router/{}", runtime.get_pid_str()),
+        );
         // admin_path is "/@/.../backend/<beid>"
         // answer to GET on 'admin_path'
         let mut backend_admin = match workspace.register_eval(&PathExpr::from(&admin_path)).await {
@@ -81,12 +94,16 @@ pub(crate) async fn start_backend(
         let mut backend = backend;
         // Map owning handles on alive storages for this backend.
         // Once dropped, a handle will release/stop the backend.
-        let mut storages_handles: HashMap<Path, Sender<bool>> = HashMap::new();
+        let mut storages_handles: HashMap<Path, StopHandle> = HashMap::new();
         loop {
             select!(
                 // on get request on backend_admin
                 get = backend_admin.next().fuse() => {
                     let get = get.unwrap();
+                    if !read_permissions.allows(admin_path.as_str()) {
+                        debug!("Get on {} not answered: not allowed by {}", admin_path, ZN_ADMIN_READ_STR);
+                        continue;
+                    }
                     get.reply_async(admin_path.clone(), backend.get_admin_status().await).await;
                 },
 
@@ -119,7 +136,13 @@ pub(crate) async fn start_backend(
                         ChangeKind::Patch => warn!("PATCH not supported on {}", change.path),
                     }
                 },
-                _ = stop_rx.recv().fuse() => {
+                stop = stop_rx.recv().fuse() => {
+                    // forward the stop request (if any) to the storages
+                    if let Ok(stop) = stop {
+                        for handle in storages_handles.values() {
+                            let _ = handle.send(stop.clone()).await;
+                        }
+                    }
                     trace!("Dropping backend {}", admin_path);
                     return
                 }
@@ -141,7 +164,7 @@ async fn create_and_start_storage(
     in_interceptor: Option<Arc<RwLock<Box<dyn IncomingDataInterceptor>>>>,
     out_interceptor: Option<Arc<RwLock<Box<dyn OutgoingDataInterceptor>>>>,
     zenoh: Arc<Zenoh>,
-) -> ZResult<Sender<bool>> {
+) -> ZResult<StopHandle> {
     trace!("Create storage {}", admin_path);
     if let Value::Properties(props) = value {
         let path_expr_str = props.get(PROP_STORAGE_PATH_EXPR).ok_or_else(|| {
@@ -153,11 +176,43 @@ async fn create_and_start_storage(
             })
         })?;
         let path_expr = PathExpr::try_from(path_expr_str.as_str())?;
+        let ttl = match props.get(PROP_STORAGE_TTL) {
+            Some(ttl) => match ttl.parse::<f64>() {
+                Ok(secs) if secs > 0.0 && secs.is_finite() => Some(Duration::from_secs_f64(secs)),
+                _ => {
+                    return zerror!(ZErrorKind::Other {
+                        descr: format!(
+                            "Can't create storage {}: invalid {} property: {}",
+                            admin_path, PROP_STORAGE_TTL, ttl
+                        )
+                    })
+                }
+            },
+            None => None,
+        };
+        let dump_dir = props.get(PROP_STORAGE_DUMP_DIR).map(PathBuf::from);
+        // check the conflict resolution before the Storage creation, that will apply it
+        if let Err(e) = get_conflict_resolution(&props) {
+            return zerror!(ZErrorKind::Other {
+                descr: format!("Can't create storage {}: {}", admin_path, e)
+            });
+        }
+        let quota = match Quota::from_properties(&props) {
+            Ok(quota) => quota,
+            Err(e) => {
+                return zerror!(ZErrorKind::Other {
+                    descr: format!("Can't create storage {}: {}", admin_path, e)
+                })
+            }
+        };
         let storage = backend.create_storage(props).await?;
         start_storage(
             storage,
             admin_path.clone(),
             path_expr,
+            ttl,
+            quota,
+            dump_dir,
             in_interceptor,
             out_interceptor,
             zenoh,
@@ -0,0 +1,472 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use async_std::io::{ReadExt, WriteExt};
+use serde_cbor::Value as CborValue;
+use serde_json::{json, Value as JsonValue};
+use std::convert::TryFrom;
+use std::path::{Component, Path, PathBuf};
+use zenoh::net::{encoding, DataInfo, Sample};
+use zenoh::{Properties, Timestamp, Value, ZError, ZErrorKind, ZResult};
+use zenoh_util::{zerror, zerror2};
+
+/// The property of an import or export request giving the dump file.
+pub(crate) const PROP_DUMP_FILE: &str = "file";
+/// The property of an export request giving the path under which the dump is put
+/// by chunks (`<path>/0`, `<path>/1`...), instead of a file.
+pub(crate) const PROP_DUMP_PATH: &str = "path";
+/// The property of an import or export request giving the format of the dump:
+/// `"jsonl"` or `"cbor"`. By default, it's deduced from the file extension.
+pub(crate) const PROP_DUMP_FORMAT: &str = "format";
+
+// The number of samples written at once in the storage
+pub(crate) const IMPORT_BATCH_SIZE: usize = 1000;
+// The number of samples read at once from the storage by an export,
+// and in each chunk of an export to a path
+pub(crate) const EXPORT_CHUNK_SIZE: usize = 1000;
+// The number of bytes read at once from a dump file
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    // one JSON object per line, with the payload encoded in base64
+    JsonLines,
+    // a sequence of CBOR maps, with the payload as a byte string
+    Cbor,
+}
+
+/// Returns the path of a dump `file` in the `dump_dir` of a storage. The file must be relative
+/// to this directory and stay in it, so the requests on the admin space can't read or write
+/// any other file of the host. Without `dump_dir`, no dump file is allowed.
+fn dump_file_path(dump_dir: Option<&Path>, file: &str) -> ZResult<PathBuf> {
+    let dump_dir = dump_dir.ok_or_else(|| {
+        zerror2!(ZErrorKind::Other {
+            descr: format!(
+                "Invalid dump file {}: no {} configured for this storage",
+                file,
+                zenoh_backend_traits::PROP_STORAGE_DUMP_DIR
+            )
+        })
+    })?;
+    let relative = Path::new(file);
+    let is_valid = relative.file_name().is_some()
+        && relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    if !is_valid {
+        return zerror!(ZErrorKind::Other {
+            descr: format!(
+                "Invalid dump file {}: expecting a file name relative to {}",
+                file,
+                dump_dir.display()
+            )
+        });
+    }
+    Ok(dump_dir.join(relative))
+}
+
+// Returns the properties of an import or export request: either a dump file,
+// or properties with the dump file (or path) and its format.
+fn request_properties(value: Value) -> ZResult<Properties> {
+    match value {
+        Value::Properties(props) => Ok(props),
+        Value::StringUtf8(file) => {
+            let mut props = Properties::default();
+            props.insert(PROP_DUMP_FILE.to_string(), file);
+            Ok(props)
+        }
+        value => zerror!(ZErrorKind::Other {
+            descr: format!("Invalid dump request: {:?}", value)
+        }),
+    }
+}
+
+fn request_format(props: &Properties, file: Option<&str>) -> ZResult<Format> {
+    match props.get(PROP_DUMP_FORMAT).map(|f| f.as_str()) {
+        Some("jsonl") => Ok(Format::JsonLines),
+        Some("cbor") => Ok(Format::Cbor),
+        None if file.map_or(false, |f| f.ends_with(".cbor")) => Ok(Format::Cbor),
+        None => Ok(Format::JsonLines),
+        Some(format) => zerror!(ZErrorKind::Other {
+            descr: format!("Invalid dump format: {}", format)
+        }),
+    }
+}
+
+/// A request to import a dump file into a storage.
+pub(crate) struct ImportRequest {
+    pub(crate) file: PathBuf,
+    format: Format,
+}
+
+impl ImportRequest {
+    /// Parses the value of a PUT on the import path of a storage: either the dump file,
+    /// or properties with the dump `file` and its `format`.
+    /// The dump file is read in the `dump_dir` of the storage.
+    pub(crate) fn from_value(value: Value, dump_dir: Option<&Path>) -> ZResult<ImportRequest> {
+        let props = request_properties(value)?;
+        let file = props.get(PROP_DUMP_FILE).ok_or_else(|| {
+            zerror2!(ZErrorKind::Other {
+                descr: format!("Invalid import request: no {} property", PROP_DUMP_FILE)
+            })
+        })?;
+        let format = request_format(&props, Some(file))?;
+        let file = dump_file_path(dump_dir, file)?;
+        Ok(ImportRequest { file, format })
+    }
+
+    /// Opens the dump file, to read its samples by batches.
+    pub(crate) async fn open(&self) -> ZResult<DumpReader> {
+        let file = async_std::fs::File::open(&self.file).await.map_err(|e| {
+            zerror2!(ZErrorKind::IoError {
+                descr: format!("Failed to read {}: {}", self.file.display(), e)
+            })
+        })?;
+        Ok(DumpReader {
+            path: self.file.clone(),
+            file,
+            format: self.format,
+            buf: vec![],
+            eof: false,
+            index: 0,
+        })
+    }
+}
+
+/// Reads and decodes the samples of a dump file by batches, without loading the whole dump
+/// in memory: only the batch and the bytes of its last partially read record are kept.
+pub(crate) struct DumpReader {
+    path: PathBuf,
+    file: async_std::fs::File,
+    format: Format,
+    // the bytes read from the file and not decoded yet
+    buf: Vec<u8>,
+    eof: bool,
+    // the index of the next record
+    index: usize,
+}
+
+impl DumpReader {
+    /// Returns the next samples of the dump, up to `max`, or none at the end of the dump.
+    pub(crate) async fn next_batch(&mut self, max: usize) -> ZResult<Vec<Sample>> {
+        let mut samples = vec![];
+        loop {
+            match self.format {
+                Format::JsonLines => self.decode_json_lines(max, &mut samples)?,
+                Format::Cbor => self.decode_cbor(max, &mut samples)?,
+            }
+            if samples.len() >= max || self.eof {
+                return Ok(samples);
+            }
+            self.read_chunk().await?;
+        }
+    }
+
+    async fn read_chunk(&mut self) -> ZResult<()> {
+        let len = self.buf.len();
+        self.buf.resize(len + READ_CHUNK_SIZE, 0);
+        let n = self.file.read(&mut self.buf[len..]).await.map_err(|e| {
+            zerror2!(ZErrorKind::IoError {
+                descr: format!("Failed to read {}: {}", self.path.display(), e)
+            })
+        })?;
+        self.buf.truncate(len + n);
+        self.eof = n == 0;
+        Ok(())
+    }
+
+    // Decodes the complete lines read so far (and the last one at the end of the file).
+    fn decode_json_lines(&mut self, max: usize, samples: &mut Vec<Sample>) -> ZResult<()> {
+        let mut pos = 0;
+        while samples.len() < max {
+            let end = match self.buf[pos..].iter().position(|b| *b == b'\n') {
+                Some(n) => pos + n + 1,
+                None if self.eof && pos < self.buf.len() => self.buf.len(),
+                None => break,
+            };
+            let index = self.index;
+            self.index += 1;
+            let line = std::str::from_utf8(&self.buf[pos..end])
+                .map_err(|_| invalid_record(index, "not UTF-8"))?;
+            pos = end;
+            if let Some(sample) = decode_json_line(index, line)? {
+                samples.push(sample);
+            }
+        }
+        self.buf.drain(..pos);
+        Ok(())
+    }
+
+    // Decodes the complete records read so far.
+    fn decode_cbor(&mut self, max: usize, samples: &mut Vec<Sample>) -> ZResult<()> {
+        let mut pos = 0;
+        while samples.len() < max {
+            let mut records =
+                serde_cbor::Deserializer::from_slice(&self.buf[pos..]).into_iter::<CborValue>();
+            let index = self.index;
+            match records.next() {
+                Some(Ok(record)) => {
+                    pos += records.byte_offset();
+                    self.index += 1;
+                    samples.push(decode_cbor_record(index, record)?);
+                }
+                // the end of the record is not read yet
+                Some(Err(e)) if e.is_eof() && !self.eof => break,
+                Some(Err(e)) => return Err(invalid_record(index, &e.to_string())),
+                None => break,
+            }
+        }
+        self.buf.drain(..pos);
+        Ok(())
+    }
+}
+
+/// Where an export request writes the dump.
+pub(crate) enum ExportTarget {
+    File(PathBuf),
+    Path(zenoh::Path),
+}
+
+impl std::fmt::Display for ExportTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportTarget::File(file) => write!(f, "{}", file.display()),
+            ExportTarget::Path(path) => write!(f, "{}", path),
+        }
+    }
+}
+
+/// A request to export the content of a storage into a dump file, or under a path.
+pub(crate) struct ExportRequest {
+    pub(crate) target: ExportTarget,
+    format: Format,
+}
+
+impl ExportRequest {
+    /// Parses the value of a PUT on the export path of a storage: either the dump file,
+    /// or properties with the dump `file` (or `path`) and its `format`.
+    /// The dump file is written in the `dump_dir` of the storage.
+    pub(crate) fn from_value(value: Value, dump_dir: Option<&Path>) -> ZResult<ExportRequest> {
+        let props = request_properties(value)?;
+        let target = match (props.get(PROP_DUMP_FILE), props.get(PROP_DUMP_PATH)) {
+            (Some(file), None) => ExportTarget::File(dump_file_path(dump_dir, file)?),
+            (None, Some(path)) => ExportTarget::Path(zenoh::Path::try_from(path.as_str())?),
+            _ => {
+                return zerror!(ZErrorKind::Other {
+                    descr: format!(
+                        "Invalid export request: expecting either a {} or a {} property",
+                        PROP_DUMP_FILE, PROP_DUMP_PATH
+                    )
+                })
+            }
+        };
+        let format = request_format(&props, props.get(PROP_DUMP_FILE).map(|f| f.as_str()))?;
+        Ok(ExportRequest { target, format })
+    }
+
+    /// Encodes the samples in the format of this request.
+    pub(crate) fn encode(&self, samples: &[Sample]) -> Vec<u8> {
+        encode(self.format, samples)
+    }
+
+    /// Creates the dump file, to write the samples by batches. They are written in a temporary
+    /// file renamed once complete, not to leave a truncated dump on failure.
+    pub(crate) async fn create(&self, file: &Path) -> ZResult<DumpWriter> {
+        let mut tmp = file.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+        let writer = async_std::fs::File::create(&tmp)
+            .await
+            .map_err(|e| write_error(file, e))?;
+        Ok(DumpWriter {
+            path: file.to_path_buf(),
+            tmp,
+            file: async_std::io::BufWriter::new(writer),
+            format: self.format,
+        })
+    }
+}
+
+/// Encodes and writes the samples of a dump file by batches, without keeping the whole dump
+/// in memory.
+pub(crate) struct DumpWriter {
+    path: PathBuf,
+    // the temporary file written until the dump is complete
+    tmp: PathBuf,
+    file: async_std::io::BufWriter<async_std::fs::File>,
+    format: Format,
+}
+
+impl DumpWriter {
+    /// Appends the samples to the dump.
+    pub(crate) async fn write_batch(&mut self, samples: &[Sample]) -> ZResult<()> {
+        self.file
+            .write_all(&encode(self.format, samples))
+            .await
+            .map_err(|e| write_error(&self.path, e))
+    }
+
+    /// Completes the dump, renaming the temporary file into the dump file.
+    pub(crate) async fn finish(mut self) -> ZResult<()> {
+        self.file
+            .flush()
+            .await
+            .map_err(|e| write_error(&self.path, e))?;
+        async_std::fs::rename(&self.tmp, &self.path)
+            .await
+            .map_err(|e| write_error(&self.path, e))
+    }
+}
+
+fn write_error(file: &Path, e: std::io::Error) -> ZError {
+    zerror2!(ZErrorKind::IoError {
+        descr: format!("Failed to write {}: {}", file.display(), e)
+    })
+}
+
+fn encode(format: Format, samples: &[Sample]) -> Vec<u8> {
+    match format {
+        Format::JsonLines => encode_json_lines(samples),
+        Format::Cbor => encode_cbor(samples),
+    }
+}
+
+fn invalid_record(index: usize, reason: &str) -> ZError {
+    zerror2!(ZErrorKind::Other {
+        descr: format!("Invalid record #{} in dump: {}", index, reason)
+    })
+}
+
+// Builds a sample from the fields of a record: its key, and its optional timestamp and encoding.
+fn make_sample(
+    index: usize,
+    key: String,
+    timestamp: Option<&str>,
+    encoding: Option<&str>,
+    payload: Vec<u8>,
+) -> ZResult<Sample> {
+    let timestamp = match timestamp {
+        Some(ts) => Some(
+            ts.parse::<Timestamp>()
+                .map_err(|_| invalid_record(index, &format!("invalid timestamp {}", ts)))?,
+        ),
+        None => None,
+    };
+    let encoding = match encoding {
+        Some(enc) => Some(
+            encoding::from_str(enc)
+                .map_err(|_| invalid_record(index, &format!("invalid encoding {}", enc)))?,
+        ),
+        None => None,
+    };
+    Ok(Sample {
+        res_name: key,
+        payload: payload.into(),
+        data_info: Some(DataInfo {
+            encoding,
+            timestamp,
+            ..Default::default()
+        }),
+    })
+}
+
+// Each line is: {"key": "/a/b", "timestamp": "<timestamp>", "encoding": "<mime>", "payload": "<base64>"},
+// where the timestamp and the encoding are optional. The blank lines are skipped.
+fn decode_json_line(index: usize, line: &str) -> ZResult<Option<Sample>> {
+    if line.trim().is_empty() {
+        return Ok(None);
+    }
+    let record = serde_json::from_str::<JsonValue>(line)
+        .map_err(|e| invalid_record(index, &e.to_string()))?;
+    let field = |name: &str| record.get(name).and_then(|v| v.as_str());
+    let key = field("key").ok_or_else(|| invalid_record(index, "no key"))?;
+    let payload = base64::decode(field("payload").unwrap_or_default())
+        .map_err(|e| invalid_record(index, &e.to_string()))?;
+    make_sample(
+        index,
+        key.to_string(),
+        field("timestamp"),
+        field("encoding"),
+        payload,
+    )
+    .map(Some)
+}
+
+fn encode_json_lines(samples: &[Sample]) -> Vec<u8> {
+    let mut buf = vec![];
+    for sample in samples {
+        let mut record = json!({
+            "key": sample.res_name,
+            "payload": base64::encode(sample.payload.to_vec()),
+        });
+        if let Some(info) = &sample.data_info {
+            if let Some(ts) = &info.timestamp {
+                record["timestamp"] = json!(ts.to_string());
+            }
+            if let Some(enc) = info.encoding {
+                record["encoding"] = json!(encoding::to_string(enc));
+            }
+        }
+        buf.extend_from_slice(record.to_string().as_bytes());
+        buf.push(b'\n');
+    }
+    buf
+}
+
+// A map with the same fields as the JSON lines, but with the payload as bytes.
+fn decode_cbor_record(index: usize, record: CborValue) -> ZResult<Sample> {
+    let record = match record {
+        CborValue::Map(record) => record,
+        _ => return Err(invalid_record(index, "not a map")),
+    };
+    let field = |name: &str| record.get(&CborValue::Text(name.to_string()));
+    let text = |name: &str| match field(name) {
+        Some(CborValue::Text(s)) => Some(s.as_str()),
+        _ => None,
+    };
+    let key = text("key").ok_or_else(|| invalid_record(index, "no key"))?;
+    let payload = match field("payload") {
+        Some(CborValue::Bytes(payload)) => payload.clone(),
+        Some(CborValue::Text(payload)) => payload.as_bytes().to_vec(),
+        None => vec![],
+        _ => return Err(invalid_record(index, "invalid payload")),
+    };
+    make_sample(
+        index,
+        key.to_string(),
+        text("timestamp"),
+        text("encoding"),
+        payload,
+    )
+}
+
+fn encode_cbor(samples: &[Sample]) -> Vec<u8> {
+    let mut buf = vec![];
+    for sample in samples {
+        let mut record = std::collections::BTreeMap::new();
+        let text = |s: &str| CborValue::Text(s.to_string());
+        record.insert(text("key"), text(&sample.res_name));
+        record.insert(text("payload"), CborValue::Bytes(sample.payload.to_vec()));
+        if let Some(info) = &sample.data_info {
+            if let Some(ts) = &info.timestamp {
+                record.insert(text("timestamp"), text(&ts.to_string()));
+            }
+            if let Some(enc) = info.encoding {
+                record.insert(text("encoding"), text(&encoding::to_string(enc)));
+            }
+        }
+        // writing a Value in a Vec can't fail
+        serde_cbor::to_writer(&mut buf, &CborValue::Map(record)).unwrap();
+    }
+    buf
+}
@@ -0,0 +1,477 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+
+//! A simple persistent backend, storing for each storage the latest sample of each path
+//! in an append-only log file: each received sample is appended to the log, and the log
+//! is replayed at the storage creation. The log is compacted when it contains too many
+//! outdated records, dropping the DELETEs older than a cleanup delay.
+use async_std::fs::{File, OpenOptions};
+use async_std::prelude::*;
+use async_trait::async_trait;
+use log::{debug, trace, warn};
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use zenoh::net::utils::resource_name;
+use zenoh::net::{data_kind, DataInfo, QueryPage, Sample};
+use zenoh::{utils, ChangeKind, Properties, Timestamp, Value, ZError, ZErrorKind, ZResult};
+use zenoh_backend_traits::utils::{get_conflict_resolution, get_samples_page};
+use zenoh_backend_traits::*;
+use zenoh_util::{zerror, zerror2};
+
+/// The `"root"` property key of the backend: the directory where the storages are created.
+pub const PROP_BACKEND_ROOT: &str = "root";
+
+/// The `"dir"` property key of each storage: the directory (relative to the backend's root)
+/// where the storage writes its log.
+pub const PROP_STORAGE_DIR: &str = "dir";
+
+const LOG_FILENAME: &str = "samples.log";
+const TMP_LOG_FILENAME: &str = "samples.log.tmp";
+
+const KIND_PUT: u8 = 0;
+const KIND_DELETE: u8 = 1;
+
+// Minimal number of records in a log before it can be compacted.
+const COMPACTION_MIN_RECORDS: usize = 1000;
+// The log is compacted when it has this many times more records than stored paths.
+const COMPACTION_RATIO: usize = 2;
+// The delay after which a DELETE is dropped at compaction, like in the memory backend:
+// it's only kept to discard the PUTs with an older timestamp received meanwhile.
+const CLEANUP_TIMEOUT_MS: u64 = 5000;
+
+pub fn create_backend(root: PathBuf) -> ZResult<Box<dyn Backend>> {
+    let properties = Properties::from(
+        &[
+            (PROP_BACKEND_TYPE, "fs"),
+            (PROP_BACKEND_ROOT, &root.to_string_lossy()),
+        ][..],
+    );
+    let admin_status = utils::properties_to_json_value(&properties);
+    Ok(Box::new(FsBackend { admin_status, root }))
+}
+
+pub struct FsBackend {
+    admin_status: Value,
+    root: PathBuf,
+}
+
+#[async_trait]
+impl Backend for FsBackend {
+    async fn get_admin_status(&self) -> Value {
+        self.admin_status.clone()
+    }
+
+    async fn create_storage(&mut self, properties: Properties) -> ZResult<Box<dyn Storage>> {
+        debug!("Create FS Storage with properties: {}", properties);
+        let dir = properties.get(PROP_STORAGE_DIR).ok_or_else(|| {
+            zerror2!(ZErrorKind::Other {
+                descr: format!("No {} property for the FS storage", PROP_STORAGE_DIR)
+            })
+        })?;
+        // the storage's directory must remain under the backend's root
+        if !Path::new(dir)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+        {
+            return zerror!(ZErrorKind::Other {
+                descr: format!(
+                    "Invalid {} property for the FS storage: {} (must be a relative path)",
+                    PROP_STORAGE_DIR, dir
+                )
+            });
+        }
+        let dir = self.root.join(dir);
+        Ok(Box::new(FsStorage::new(properties, dir).await?))
+    }
+
+    fn incoming_data_interceptor(&self) -> Option<Box<dyn IncomingDataInterceptor>> {
+        None
+    }
+
+    fn outgoing_data_interceptor(&self) -> Option<Box<dyn OutgoingDataInterceptor>> {
+        None
+    }
+}
+
+struct FsStorage {
+    admin_status: Value,
+    dir: PathBuf,
+    log: File,
+    // the number of records in the log
+    records: usize,
+    // the latest sample (PUT or DELETE) for each path
+    samples: HashMap<String, Sample>,
+    conflict_resolution: Box<dyn ConflictResolution>,
+}
+
+impl FsStorage {
+    async fn new(properties: Properties, dir: PathBuf) -> ZResult<FsStorage> {
+        let admin_status = utils::properties_to_json_value(&properties);
+        let conflict_resolution = get_conflict_resolution(&properties)?;
+
+        async_std::fs::create_dir_all(&dir).await.map_err(|e| {
+            zerror2!(ZErrorKind::IoError {
+                descr: format!("Failed to create directory {}: {}", dir.display(), e)
+            })
+        })?;
+        let path = dir.join(LOG_FILENAME);
+        let (samples, records) = match async_std::fs::read(&path).await {
+            Ok(bytes) => {
+                let (samples, records, len) = load_log(&bytes, &path);
+                if len < bytes.len() {
+                    // cut the invalid end, not to append the next records after it
+                    let file = OpenOptions::new()
+                        .write(true)
+                        .open(&path)
+                        .await
+                        .map_err(to_zerror)?;
+                    file.set_len(len as u64).await.map_err(to_zerror)?;
+                }
+                (samples, records)
+            }
+            Err(_) => (HashMap::new(), 0),
+        };
+        debug!(
+            "Loaded {} paths from {} ({} records)",
+            samples.len(),
+            path.display(),
+            records
+        );
+        let log = open_log(&path).await?;
+
+        Ok(FsStorage {
+            admin_status,
+            dir,
+            log,
+            records,
+            samples,
+            conflict_resolution,
+        })
+    }
+
+    // Returns the sample to append to the log if it's newer than the one stored for its path,
+    // or than the one in `pending` (not yet stored), according to the conflict resolution.
+    fn resolve(&self, mut sample: Sample, pending: &HashMap<String, Sample>) -> Option<Sample> {
+        trace!("on_sample for {}", sample.res_name);
+        let kind = get_kind(&sample);
+        if kind == ChangeKind::Patch {
+            warn!("Received PATCH for {}: not yet supported", sample.res_name);
+            return None;
+        }
+        // timestamp the sample if not yet done, for the conflict resolution
+        let info = sample.data_info.get_or_insert_with(DataInfo::new);
+        if info.timestamp.is_none() {
+            info.timestamp = Some(utils::new_reception_timestamp());
+        }
+
+        let stored = pending
+            .get(&sample.res_name)
+            .or_else(|| self.samples.get(&sample.res_name));
+        match stored {
+            None => Some(sample),
+            Some(stored) => {
+                let path = sample.res_name.clone();
+                let res = self.conflict_resolution.resolve(stored, sample);
+                if res.is_none() {
+                    debug!("{:?} on {} dropped: out-of-date", kind, path);
+                }
+                res
+            }
+        }
+    }
+
+    // Appends the samples to the log with a single write, and only then stores them in
+    // memory: if the write fails, the memory still matches the log.
+    async fn append(&mut self, samples: Vec<Sample>) -> ZResult<()> {
+        if samples.is_empty() {
+            return Ok(());
+        }
+        let mut buf = vec![];
+        for sample in &samples {
+            encode_record(sample, &mut buf);
+        }
+        let len = self.log.metadata().await.map_err(to_zerror)?.len();
+        let written = async {
+            self.log.write_all(&buf).await?;
+            self.log.sync_data().await
+        }
+        .await;
+        if let Err(e) = written {
+            // cut the records partially written, not to append the next records after them
+            if let Err(e) = self.log.set_len(len).await {
+                warn!(
+                    "Failed to truncate {}: {}",
+                    self.dir.join(LOG_FILENAME).display(),
+                    e
+                );
+            }
+            return Err(to_zerror(e));
+        }
+        self.records += samples.len();
+        for sample in samples {
+            self.samples.insert(sample.res_name.clone(), sample);
+        }
+        if self.records >= COMPACTION_MIN_RECORDS
+            && self.records > COMPACTION_RATIO * self.samples.len()
+        {
+            self.compact().await?;
+        }
+        Ok(())
+    }
+
+    // Rewrites the log with only the latest sample of each path, except the DELETEs older
+    // than CLEANUP_TIMEOUT_MS that are dropped.
+    async fn compact(&mut self) -> ZResult<()> {
+        let path = self.dir.join(LOG_FILENAME);
+        let tmp_path = self.dir.join(TMP_LOG_FILENAME);
+        let now = SystemTime::now();
+        let mut buf = vec![];
+        let mut kept = 0;
+        for sample in self.samples.values() {
+            if !is_expired_delete(sample, now) {
+                encode_record(sample, &mut buf);
+                kept += 1;
+            }
+        }
+        debug!(
+            "Compact {} from {} to {} records ({} DELETEs dropped)",
+            path.display(),
+            self.records,
+            kept,
+            self.samples.len() - kept
+        );
+        let mut tmp = File::create(&tmp_path).await.map_err(to_zerror)?;
+        tmp.write_all(&buf).await.map_err(to_zerror)?;
+        tmp.sync_all().await.map_err(to_zerror)?;
+        async_std::fs::rename(&tmp_path, &path)
+            .await
+            .map_err(to_zerror)?;
+        // the expired DELETEs are only dropped from memory once dropped from the log
+        self.samples
+            .retain(|_, sample| !is_expired_delete(sample, now));
+        self.log = open_log(&path).await?;
+        self.records = self.samples.len();
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Storage for FsStorage {
+    async fn get_admin_status(&self) -> Value {
+        self.admin_status.clone()
+    }
+
+    async fn on_sample(&mut self, sample: Sample) -> ZResult<()> {
+        self.on_sample_checked(sample).await.map(|_| ())
+    }
+
+    async fn on_sample_checked(&mut self, sample: Sample) -> ZResult<bool> {
+        match self.resolve(sample, &HashMap::new()) {
+            Some(sample) => self.append(vec![sample]).await.map(|_| true),
+            None => Ok(false),
+        }
+    }
+
+    async fn on_samples(&mut self, samples: Vec<Sample>) -> ZResult<()> {
+        // the accepted samples of the batch, resolved against each other before being stored
+        let mut pending: HashMap<String, Sample> = HashMap::new();
+        let mut accepted = vec![];
+        for sample in samples {
+            if let Some(sample) = self.resolve(sample, &pending) {
+                pending.insert(sample.res_name.clone(), sample.clone());
+                accepted.push(sample);
+            }
+        }
+        self.append(accepted).await
+    }
+
+    async fn on_expired(&mut self, mut sample: Sample) -> ZResult<()> {
+        trace!("on_expired for {}", sample.res_name);
+        let info = sample.data_info.get_or_insert_with(DataInfo::new);
+        if info.timestamp.is_none() {
+            info.timestamp = Some(utils::new_reception_timestamp());
+        }
+        // the expired sample is removed without conflict resolution
+        self.append(vec![sample]).await
+    }
+
+    async fn on_query(&mut self, query: Query) -> ZResult<()> {
+        trace!("on_query for {}", query.res_name());
+        if !query.res_name().contains('*') {
+            if let Some(sample) = self.samples.get(query.res_name()) {
+                if get_kind(sample) == ChangeKind::Put {
+                    query.reply(sample.clone()).await;
+                }
+            }
+        } else {
+            for sample in self.samples.values() {
+                if get_kind(sample) == ChangeKind::Put
+                    && resource_name::intersect(query.res_name(), &sample.res_name)
+                {
+                    query.reply(sample.clone()).await;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn get_all_samples(&self) -> ZResult<Vec<Sample>> {
+        Ok(self
+            .samples
+            .values()
+            .filter(|sample| get_kind(sample) == ChangeKind::Put)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_samples_page(&self, page: &QueryPage) -> ZResult<Vec<Sample>> {
+        Ok(get_samples_page(
+            self.samples
+                .values()
+                .filter(|sample| get_kind(sample) == ChangeKind::Put),
+            page,
+        ))
+    }
+}
+
+fn get_kind(sample: &Sample) -> ChangeKind {
+    sample
+        .data_info
+        .as_ref()
+        .and_then(|info| info.kind)
+        .map_or(ChangeKind::Put, ChangeKind::from)
+}
+
+// Returns true if the sample is a DELETE timestamped more than CLEANUP_TIMEOUT_MS before `now`.
+fn is_expired_delete(sample: &Sample, now: SystemTime) -> bool {
+    get_kind(sample) == ChangeKind::Delete
+        && sample
+            .data_info
+            .as_ref()
+            .and_then(|info| info.timestamp.as_ref())
+            .and_then(|ts| now.duration_since(ts.get_time().to_system_time()).ok())
+            .map_or(false, |age| {
+                age >= Duration::from_millis(CLEANUP_TIMEOUT_MS)
+            })
+}
+
+fn to_zerror(e: std::io::Error) -> ZError {
+    zerror2!(ZErrorKind::IoError {
+        descr: e.to_string()
+    })
+}
+
+async fn open_log(path: &Path) -> ZResult<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+        .map_err(|e| {
+            zerror2!(ZErrorKind::IoError {
+                descr: format!("Failed to open {}: {}", path.display(), e)
+            })
+        })
+}
+
+// A record is: kind (u8), timestamp, path, encoding (u64), payload,
+// where the timestamp, path and payload are prefixed with their length (u32).
+// All integers are little-endian.
+fn encode_record(sample: &Sample, buf: &mut Vec<u8>) {
+    let info = sample.data_info.as_ref();
+    let kind = match get_kind(sample) {
+        ChangeKind::Delete => KIND_DELETE,
+        _ => KIND_PUT,
+    };
+    let timestamp = sample
+        .get_timestamp()
+        .map(|ts| ts.to_string())
+        .unwrap_or_default();
+    let encoding = info.and_then(|info| info.encoding).unwrap_or(0);
+    let payload = sample.payload.to_vec();
+
+    buf.push(kind);
+    for bytes in &[timestamp.as_bytes(), sample.res_name.as_bytes()] {
+        buf.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+        buf.extend_from_slice(bytes);
+    }
+    buf.extend_from_slice(&encoding.to_le_bytes());
+    buf.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    buf.extend_from_slice(&payload);
+}
+
+fn decode_record(bytes: &[u8], pos: &mut usize) -> Option<Sample> {
+    fn take<'a>(bytes: &'a [u8], pos: &mut usize, len: usize) -> Option<&'a [u8]> {
+        let slice = bytes.get(*pos..*pos + len)?;
+        *pos += len;
+        Some(slice)
+    }
+    fn take_sized<'a>(bytes: &'a [u8], pos: &mut usize) -> Option<&'a [u8]> {
+        let len = u32::from_le_bytes(take(bytes, pos, 4)?.try_into().ok()?);
+        take(bytes, pos, len as usize)
+    }
+
+    let kind = take(bytes, pos, 1)?[0];
+    let timestamp = std::str::from_utf8(take_sized(bytes, pos)?).ok()?;
+    let timestamp = timestamp.parse::<Timestamp>().ok()?;
+    let res_name = String::from_utf8(take_sized(bytes, pos)?.to_vec()).ok()?;
+    let encoding = u64::from_le_bytes(take(bytes, pos, 8)?.try_into().ok()?);
+    let payload = take_sized(bytes, pos)?.to_vec();
+
+    let info = DataInfo {
+        kind: if kind == KIND_DELETE {
+            Some(data_kind::DELETE)
+        } else {
+            None
+        },
+        encoding: Some(encoding),
+        timestamp: Some(timestamp),
+        ..Default::default()
+    };
+    Some(Sample {
+        res_name,
+        payload: payload.into(),
+        data_info: Some(info),
+    })
+}
+
+// Replays a log, returning the latest sample for each path, the number of records
+// and the length of the valid part of the log.
+fn load_log(bytes: &[u8], path: &Path) -> (HashMap<String, Sample>, usize, usize) {
+    let mut samples: HashMap<String, Sample> = HashMap::new();
+    let mut records = 0;
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let start = pos;
+        match decode_record(bytes, &mut pos) {
+            Some(sample) => {
+                records += 1;
+                // the records of a path are appended in the resolution order: the last one wins
+                samples.insert(sample.res_name.clone(), sample);
+            }
+            None => {
+                // likely a record partially written before a crash
+                warn!(
+                    "Ignore the truncated or corrupted end of {} ({} bytes)",
+                    path.display(),
+                    bytes.len() - start
+                );
+                return (samples, records, start);
+            }
+        }
+    }
+    (samples, records, pos)
+}
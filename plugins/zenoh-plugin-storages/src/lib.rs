@@ -13,22 +13,28 @@
 //
 #![recursion_limit = "512"]
 
-use async_std::channel::Sender;
+use async_std::channel::{bounded, Sender};
 use async_std::sync::Arc;
 use clap::{Arg, ArgMatches};
 use futures::prelude::*;
+use futures::select;
 use libloading::Symbol;
 use log::{debug, error, warn};
 use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::sync::Mutex;
 use zenoh::net::runtime::Runtime;
 use zenoh::{ChangeKind, Path, Properties, Selector, Value, ZError, ZErrorKind, ZResult, Zenoh};
 use zenoh_backend_traits::{Backend, PROP_STORAGE_PATH_EXPR};
-use zenoh_util::{zerror, LibLoader};
+use zenoh_util::{zerror, zlock, LibLoader};
 
 mod backends_mgt;
 use backends_mgt::*;
+mod dump;
+#[cfg(feature = "fs_backend")]
+mod fs_backend;
 mod memory_backend;
+mod quota;
 mod storages_mgt;
 
 #[no_mangle]
@@ -42,6 +48,15 @@ pub fn get_expected_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
             LibLoader::default_search_paths()
         );
     }
+    #[cfg(feature = "fs_backend")]
+    lazy_static::lazy_static! {
+        static ref FS_ROOT_USAGE: String = format!(
+            "--fs-root=[DIRECTORY] \
+        'The directory where the FS backend creates its storages. \
+        By default: '{}' .",
+            zenoh_util::zenoh_home().join(FS_BACKEND_DIRNAME).display()
+        );
+    }
 
     vec![
         Arg::from_usage(
@@ -56,6 +71,8 @@ pub fn get_expected_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
         )
         .conflicts_with("no-backend"),
         Arg::from_usage(&BACKEND_SEARCH_DIR_USAGE),
+        #[cfg(feature = "fs_backend")]
+        Arg::from_usage(&FS_ROOT_USAGE).conflicts_with("no-backend"),
     ]
 }
 
@@ -64,9 +81,37 @@ pub fn start(runtime: Runtime, args: &'static ArgMatches<'_>) {
     async_std::task::spawn(run(runtime, args));
 }
 
+/// Stops all the backends and storages, and returns once the storages have stored
+/// the samples they already received.
+#[no_mangle]
+pub fn stop() {
+    if let Some(stop_tx) = zlock!(STOP_TX).take() {
+        let (done_tx, done_rx) = bounded::<()>(1);
+        async_std::task::block_on(async move {
+            if stop_tx.send(done_tx).await.is_ok() {
+                // returns an error once all the clones of done_tx are dropped by the storages
+                let _ = done_rx.recv().await;
+            }
+        });
+    }
+}
+
+// A handle owning a backend or storage task. The task stops when the handle is dropped,
+// or when it receives a Sender through it, that it drops once stopped.
+pub(crate) type StopHandle = Sender<Sender<()>>;
+
+lazy_static::lazy_static! {
+    // The handle to stop the plugin, set once it's started.
+    static ref STOP_TX: Mutex<Option<StopHandle>> = Mutex::new(None);
+}
+
 const BACKEND_LIB_PREFIX: &str = "zbackend_";
 const MEMORY_BACKEND_NAME: &str = "memory";
 const MEMORY_STORAGE_NAME: &str = "mem-storage";
+#[cfg(feature = "fs_backend")]
+const FS_BACKEND_NAME: &str = "fs";
+#[cfg(feature = "fs_backend")]
+const FS_BACKEND_DIRNAME: &str = "zbackend_fs";
 
 async fn run(runtime: Runtime, args: &'static ArgMatches<'_>) {
     // Try to initiate login.
@@ -85,6 +130,9 @@ async fn run(runtime: Runtime, args: &'static ArgMatches<'_>) {
         runtime.get_pid_str()
     );
 
+    let (stop_tx, stop_rx) = bounded::<Sender<()>>(1);
+    *zlock!(STOP_TX) = Some(stop_tx);
+
     let zenoh = Arc::new(Zenoh::init(runtime).await);
     let workspace = zenoh
         .workspace(Some(Path::try_from(backends_prefix.clone()).unwrap()))
@@ -92,7 +140,7 @@ async fn run(runtime: Runtime, args: &'static ArgMatches<'_>) {
         .unwrap();
 
     // Map owning handles on alive backends. Once dropped, a handle will release/stop the backend.
-    let mut backend_handles: HashMap<Path, Sender<bool>> = HashMap::new();
+    let mut backend_handles: HashMap<Path, StopHandle> = HashMap::new();
 
     // Start Memory Backend and storages if configured via args
     if !args.is_present("no-backend") {
@@ -125,45 +173,78 @@ async fn run(runtime: Runtime, args: &'static ArgMatches<'_>) {
                 i += 1
             }
         }
+
+        #[cfg(feature = "fs_backend")]
+        {
+            let root = args
+                .value_of("fs-root")
+                .map(std::path::PathBuf::from)
+                .unwrap_or_else(|| zenoh_util::zenoh_home().join(FS_BACKEND_DIRNAME));
+            debug!("FS backend enabled in {}", root.display());
+            let fs_backend = fs_backend::create_backend(root).unwrap();
+            let fs_backend_path =
+                Path::try_from(format!("{}/{}", backends_prefix, FS_BACKEND_NAME)).unwrap();
+            let handle = start_backend(fs_backend, fs_backend_path.clone(), zenoh.clone())
+                .await
+                .unwrap();
+            backend_handles.insert(fs_backend_path, handle);
+        }
     }
 
     // subscribe to PUT/DELETE on 'backends_prefix'/*
     let backends_admin_selector = Selector::try_from(format!("{}/*", backends_prefix)).unwrap();
     if let Ok(mut backends_admin) = workspace.subscribe(&backends_admin_selector).await {
-        while let Some(change) = backends_admin.next().await {
-            debug!("Received change: {:?}", change);
-            match change.kind {
-                ChangeKind::Put => {
-                    #[allow(clippy::map_entry)]
-                    // Disable clippy check because no way to log the warn using map.entry().or_insert()
-                    if !backend_handles.contains_key(&change.path) {
-                        if let Some(value) = change.value {
-                            match load_and_start_backend(
-                                &change.path,
-                                value,
-                                zenoh.clone(),
-                                &lib_loader,
-                            )
-                            .await
-                            {
-                                Ok(handle) => {
-                                    let _ = backend_handles.insert(change.path, handle);
+        loop {
+            select!(
+                change = backends_admin.next().fuse() => {
+                    let change = match change {
+                        Some(change) => change,
+                        None => break,
+                    };
+                    debug!("Received change: {:?}", change);
+                    match change.kind {
+                        ChangeKind::Put => {
+                            #[allow(clippy::map_entry)]
+                            // Disable clippy check because no way to log the warn using map.entry().or_insert()
+                            if !backend_handles.contains_key(&change.path) {
+                                if let Some(value) = change.value {
+                                    match load_and_start_backend(
+                                        &change.path,
+                                        value,
+                                        zenoh.clone(),
+                                        &lib_loader,
+                                    )
+                                    .await
+                                    {
+                                        Ok(handle) => {
+                                            let _ = backend_handles.insert(change.path, handle);
+                                        }
+                                        Err(e) => warn!("{}", e),
+                                    }
+                                } else {
+                                    warn!("Received a PUT on {} without value", change.path);
                                 }
-                                Err(e) => warn!("{}", e),
+                            } else {
+                                warn!("Backend {} already exists", change.path);
                             }
-                        } else {
-                            warn!("Received a PUT on {} without value", change.path);
                         }
-                    } else {
-                        warn!("Backend {} already exists", change.path);
+                        ChangeKind::Delete => {
+                            debug!("Delete backend {}", change.path);
+                            let _ = backend_handles.remove(&change.path);
+                        }
+                        ChangeKind::Patch => warn!("PATCH not supported on {}", change.path),
                     }
+                },
+                stop = stop_rx.recv().fuse() => {
+                    if let Ok(done_tx) = stop {
+                        debug!("Stop all backends");
+                        for (_, handle) in backend_handles.drain() {
+                            let _ = handle.send(done_tx.clone()).await;
+                        }
+                    }
+                    break;
                 }
-                ChangeKind::Delete => {
-                    debug!("Delete backend {}", change.path);
-                    let _ = backend_handles.remove(&change.path);
-                }
-                ChangeKind::Patch => warn!("PATCH not supported on {}", change.path),
-            }
+            );
         }
     } else {
         error!("Failed to subscribe on {}", backends_admin_selector);
@@ -180,7 +261,7 @@ async fn load_and_start_backend(
     value: Value,
     zenoh: Arc<Zenoh>,
     lib_loader: &LibLoader,
-) -> ZResult<Sender<bool>> {
+) -> ZResult<StopHandle> {
     if let Value::Properties(props) = value {
         let name = path.last_segment();
         let (lib, lib_path) = unsafe {
@@ -18,8 +18,9 @@ use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
 use zenoh::net::utils::resource_name;
-use zenoh::net::Sample;
-use zenoh::{utils, ChangeKind, Properties, Timestamp, Value, ZResult};
+use zenoh::net::{DataInfo, QueryPage, Sample};
+use zenoh::{utils, ChangeKind, Properties, Value, ZResult};
+use zenoh_backend_traits::utils::{get_conflict_resolution, get_samples_page};
 use zenoh_backend_traits::*;
 use zenoh_util::collections::{Timed, TimedEvent, TimedHandle, Timer};
 
@@ -89,24 +90,24 @@ impl Drop for MemoryBackend {
 
 enum StoredValue {
     Present {
-        ts: Timestamp,
         sample: Sample,
     },
     Removed {
-        ts: Timestamp,
+        // the DELETE sample, kept for the resolution of the conflicts with the next samples
+        sample: Sample,
         // handle of the TimedEvent that will eventually remove the entry from the map
         cleanup_handle: TimedHandle,
     },
 }
 
 impl StoredValue {
-    fn ts(&self) -> &Timestamp {
+    fn sample(&self) -> &Sample {
         match self {
-            Present { ts, sample: _ } => &ts,
+            Present { sample } => sample,
             Removed {
-                ts,
+                sample,
                 cleanup_handle: _,
-            } => &ts,
+            } => sample,
         }
     }
 }
@@ -116,16 +117,19 @@ struct MemoryStorage {
     admin_status: Value,
     map: Arc<RwLock<HashMap<String, StoredValue>>>,
     timer: Timer,
+    conflict_resolution: Box<dyn ConflictResolution>,
 }
 
 impl MemoryStorage {
     async fn new(properties: Properties) -> ZResult<MemoryStorage> {
         let admin_status = utils::properties_to_json_value(&properties);
+        let conflict_resolution = get_conflict_resolution(&properties)?;
 
         Ok(MemoryStorage {
             admin_status,
             map: Arc::new(RwLock::new(HashMap::new())),
             timer: Timer::new(),
+            conflict_resolution,
         })
     }
 }
@@ -143,6 +147,30 @@ impl MemoryStorage {
         self.timer.add(event).await;
         handle
     }
+
+    async fn to_stored_value(&self, kind: ChangeKind, sample: Sample) -> StoredValue {
+        match kind {
+            ChangeKind::Delete => {
+                // NOTE: even if path is not known yet, we need to store the removal time:
+                // if ever a put with a lower timestamp arrive (e.g. msg inversion between put and remove)
+                // we must drop the put.
+                let cleanup_handle = self.schedule_cleanup(sample.res_name.clone()).await;
+                Removed {
+                    sample,
+                    cleanup_handle,
+                }
+            }
+            _ => Present { sample },
+        }
+    }
+}
+
+fn get_kind(sample: &Sample) -> ChangeKind {
+    sample
+        .data_info
+        .as_ref()
+        .and_then(|info| info.kind)
+        .map_or(ChangeKind::Put, ChangeKind::from)
 }
 
 #[async_trait]
@@ -152,94 +180,81 @@ impl Storage for MemoryStorage {
     }
 
     async fn on_sample(&mut self, sample: Sample) -> ZResult<()> {
+        self.on_sample_checked(sample).await.map(|_| ())
+    }
+
+    async fn on_sample_checked(&mut self, mut sample: Sample) -> ZResult<bool> {
         trace!("on_sample for {}", sample.res_name);
-        let (kind, timestamp) = if let Some(ref info) = sample.data_info {
-            (
-                info.kind.map_or(ChangeKind::Put, ChangeKind::from),
-                match &info.timestamp {
-                    Some(ts) => ts.clone(),
-                    None => utils::new_reception_timestamp(),
-                },
-            )
-        } else {
-            (ChangeKind::Put, utils::new_reception_timestamp())
-        };
-        match kind {
-            ChangeKind::Put => match self.map.write().await.entry(sample.res_name.clone()) {
-                Entry::Vacant(v) => {
-                    v.insert(Present {
-                        sample,
-                        ts: timestamp,
-                    });
-                }
-                Entry::Occupied(mut o) => {
-                    let old_val = o.get();
-                    if old_val.ts() < &timestamp {
+        let kind = get_kind(&sample);
+        if kind == ChangeKind::Patch {
+            warn!("Received PATCH for {}: not yet supported", sample.res_name);
+            return Ok(false);
+        }
+        // timestamp the sample if not yet done, for the conflict resolution
+        let info = sample.data_info.get_or_insert_with(DataInfo::new);
+        if info.timestamp.is_none() {
+            info.timestamp = Some(utils::new_reception_timestamp());
+        }
+
+        match self.map.write().await.entry(sample.res_name.clone()) {
+            Entry::Vacant(v) => {
+                v.insert(self.to_stored_value(kind, sample).await);
+                Ok(true)
+            }
+            Entry::Occupied(mut o) => {
+                let path = sample.res_name.clone();
+                match self.conflict_resolution.resolve(o.get().sample(), sample) {
+                    Some(sample) => {
                         if let Removed {
-                            ts: _,
+                            sample: _,
                             cleanup_handle,
-                        } = old_val
+                        } = o.get()
                         {
                             // cancel timed cleanup
                             cleanup_handle.clone().defuse();
                         }
-                        o.insert(Present {
-                            sample,
-                            ts: timestamp,
-                        });
-                    } else {
-                        debug!("PUT on {} dropped: out-of-date", sample.res_name);
+                        let kind = get_kind(&sample);
+                        o.insert(self.to_stored_value(kind, sample).await);
+                        Ok(true)
                     }
-                }
-            },
-            ChangeKind::Delete => match self.map.write().await.entry(sample.res_name.clone()) {
-                Entry::Vacant(v) => {
-                    // NOTE: even if path is not known yet, we need to store the removal time:
-                    // if ever a put with a lower timestamp arrive (e.g. msg inversion between put and remove)
-                    // we must drop the put.
-                    let cleanup_handle = self.schedule_cleanup(sample.res_name.clone()).await;
-                    v.insert(Removed {
-                        ts: timestamp,
-                        cleanup_handle,
-                    });
-                }
-                Entry::Occupied(mut o) => {
-                    match o.get() {
-                        Removed {
-                            ts: _,
-                            cleanup_handle: _,
-                        } => (), // nothing to do
-                        Present { sample: _, ts } => {
-                            if ts < &timestamp {
-                                let cleanup_handle =
-                                    self.schedule_cleanup(sample.res_name.clone()).await;
-                                o.insert(Removed {
-                                    ts: timestamp,
-                                    cleanup_handle,
-                                });
-                            } else {
-                                debug!("DEL on {} dropped: out-of-date", sample.res_name);
-                            }
-                        }
+                    None => {
+                        debug!("{:?} on {} dropped: out-of-date", kind, path);
+                        Ok(false)
                     }
                 }
-            },
-            ChangeKind::Patch => {
-                warn!("Received PATCH for {}: not yet supported", sample.res_name);
             }
         }
+    }
+
+    async fn on_expired(&mut self, mut sample: Sample) -> ZResult<()> {
+        trace!("on_expired for {}", sample.res_name);
+        let info = sample.data_info.get_or_insert_with(DataInfo::new);
+        if info.timestamp.is_none() {
+            info.timestamp = Some(utils::new_reception_timestamp());
+        }
+        // the expired sample is removed without conflict resolution
+        let path = sample.res_name.clone();
+        let stored_value = self.to_stored_value(ChangeKind::Delete, sample).await;
+        if let Some(Removed {
+            sample: _,
+            cleanup_handle,
+        }) = self.map.write().await.insert(path, stored_value)
+        {
+            // cancel timed cleanup
+            cleanup_handle.defuse();
+        }
         Ok(())
     }
 
     async fn on_query(&mut self, query: Query) -> ZResult<()> {
         trace!("on_query for {}", query.res_name());
         if !query.res_name().contains('*') {
-            if let Some(Present { sample, ts: _ }) = self.map.read().await.get(query.res_name()) {
+            if let Some(Present { sample }) = self.map.read().await.get(query.res_name()) {
                 query.reply(sample.clone()).await;
             }
         } else {
             for (_, stored_value) in self.map.read().await.iter() {
-                if let Present { sample, ts: _ } = stored_value {
+                if let Present { sample } = stored_value {
                     if resource_name::intersect(query.res_name(), &sample.res_name) {
                         let s: Sample = sample.clone();
                         query.reply(s).await;
@@ -249,6 +264,33 @@ impl Storage for MemoryStorage {
         }
         Ok(())
     }
+
+    async fn get_all_samples(&self) -> ZResult<Vec<Sample>> {
+        Ok(self
+            .map
+            .read()
+            .await
+            .values()
+            .filter_map(|stored_value| match stored_value {
+                Present { sample } => Some(sample.clone()),
+                Removed { .. } => None,
+            })
+            .collect())
+    }
+
+    async fn get_samples_page(&self, page: &QueryPage) -> ZResult<Vec<Sample>> {
+        Ok(get_samples_page(
+            self.map
+                .read()
+                .await
+                .values()
+                .filter_map(|stored_value| match stored_value {
+                    Present { sample } => Some(sample),
+                    Removed { .. } => None,
+                }),
+            page,
+        ))
+    }
 }
 
 impl Drop for MemoryStorage {
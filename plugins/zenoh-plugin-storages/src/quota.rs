@@ -0,0 +1,267 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use log::{debug, warn};
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+use zenoh::net::{data_kind, DataInfo, Sample, ZBuf};
+use zenoh::{utils, ChangeKind, Properties, ZError, ZErrorKind, ZResult};
+use zenoh_backend_traits::{
+    PROP_STORAGE_MAX_BYTES, PROP_STORAGE_MAX_KEYS, PROP_STORAGE_QUOTA_POLICY,
+};
+use zenoh_util::zerror;
+
+/// What a storage does with a PUT exceeding its quota.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum QuotaPolicy {
+    // drop the PUT
+    Reject,
+    // remove the least recently written paths to make room for the PUT
+    EvictOldest,
+    // store the PUT anyway, only raising an alert
+    Alarm,
+}
+
+impl QuotaPolicy {
+    fn as_str(&self) -> &'static str {
+        match self {
+            QuotaPolicy::Reject => "reject",
+            QuotaPolicy::EvictOldest => "evict_oldest",
+            QuotaPolicy::Alarm => "alarm",
+        }
+    }
+}
+
+/// The size quota of a storage, with the size of the paths it currently stores.
+pub(crate) struct Quota {
+    max_bytes: Option<usize>,
+    max_keys: Option<usize>,
+    policy: QuotaPolicy,
+    // for each stored path: its size (path + payload) and its write sequence number
+    sizes: HashMap<String, (usize, u64)>,
+    // the stored paths in write order, for the eviction
+    order: BTreeMap<u64, String>,
+    seq: u64,
+    bytes: usize,
+    rejected: u64,
+    evicted: u64,
+    // true if an alert was raised since the last PUT admitted within the quota
+    alerted: bool,
+    // the alert to be published, if any
+    alert: Option<serde_json::Value>,
+}
+
+fn parse_limit(props: &Properties, key: &str) -> ZResult<Option<usize>> {
+    match props.get(key) {
+        Some(value) => match value.parse::<usize>() {
+            Ok(limit) if limit > 0 => Ok(Some(limit)),
+            _ => zerror!(ZErrorKind::Other {
+                descr: format!("Invalid {} property: {}", key, value)
+            }),
+        },
+        None => Ok(None),
+    }
+}
+
+impl Quota {
+    /// Returns the quota configured in the storage properties, if any.
+    pub(crate) fn from_properties(props: &Properties) -> ZResult<Option<Quota>> {
+        let max_bytes = parse_limit(props, PROP_STORAGE_MAX_BYTES)?;
+        let max_keys = parse_limit(props, PROP_STORAGE_MAX_KEYS)?;
+        let policy = match props.get(PROP_STORAGE_QUOTA_POLICY).map(|p| p.as_str()) {
+            Some("reject") | None => QuotaPolicy::Reject,
+            Some("evict_oldest") => QuotaPolicy::EvictOldest,
+            Some("alarm") => QuotaPolicy::Alarm,
+            Some(policy) => {
+                return zerror!(ZErrorKind::Other {
+                    descr: format!("Invalid {} property: {}", PROP_STORAGE_QUOTA_POLICY, policy)
+                })
+            }
+        };
+        if max_bytes.is_none() && max_keys.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(Quota {
+            max_bytes,
+            max_keys,
+            policy,
+            sizes: HashMap::new(),
+            order: BTreeMap::new(),
+            seq: 0,
+            bytes: 0,
+            rejected: 0,
+            evicted: 0,
+            alerted: false,
+            alert: None,
+        }))
+    }
+
+    fn sample_size(sample: &Sample) -> usize {
+        sample.res_name.len() + sample.payload.len()
+    }
+
+    fn insert(&mut self, path: String, size: usize) {
+        self.remove(&path);
+        self.seq += 1;
+        self.bytes += size;
+        self.order.insert(self.seq, path.clone());
+        self.sizes.insert(path, (size, self.seq));
+    }
+
+    fn remove(&mut self, path: &str) {
+        if let Some((size, seq)) = self.sizes.remove(path) {
+            self.bytes -= size;
+            self.order.remove(&seq);
+        }
+    }
+
+    // Returns true if storing `size` bytes for `path` would exceed the quota,
+    // once `freed_bytes` and `freed_keys` removed.
+    fn exceeds(&self, path: &str, size: usize, freed_bytes: usize, freed_keys: usize) -> bool {
+        let (bytes, keys) = match self.sizes.get(path) {
+            Some((old_size, _)) => (self.bytes - old_size + size, self.sizes.len()),
+            None => (self.bytes + size, self.sizes.len() + 1),
+        };
+        self.max_bytes
+            .map_or(false, |max| bytes - freed_bytes > max)
+            || self.max_keys.map_or(false, |max| keys - freed_keys > max)
+    }
+
+    /// Accounts for the content already stored in the storage at its startup.
+    pub(crate) fn load(&mut self, samples: &[Sample]) {
+        for sample in samples {
+            self.insert(sample.res_name.clone(), Quota::sample_size(sample));
+        }
+        debug!(
+            "Storage quota loaded with {} paths ({} bytes)",
+            self.sizes.len(),
+            self.bytes
+        );
+    }
+
+    /// Applies the quota to a sample, returning the samples to be stored:
+    /// none if the sample is rejected, or the sample preceded by the DELETEs of the evicted paths.
+    /// The samples are only accounted once stored (see [`Quota::stored()`]).
+    pub(crate) fn admit(&mut self, sample: Sample) -> Vec<Sample> {
+        if get_kind(&sample) != ChangeKind::Put {
+            return vec![sample];
+        }
+        let size = Quota::sample_size(&sample);
+        if !self.exceeds(&sample.res_name, size, 0, 0) {
+            self.alerted = false;
+            return vec![sample];
+        }
+        match self.policy {
+            QuotaPolicy::Reject => {
+                self.rejected += 1;
+                self.raise_alert(&sample.res_name);
+                vec![]
+            }
+            QuotaPolicy::Alarm => {
+                self.raise_alert(&sample.res_name);
+                vec![sample]
+            }
+            QuotaPolicy::EvictOldest => {
+                // the sample alone exceeds the quota: reject it without evicting anything
+                // (a path always fits under max_keys, which is at least 1)
+                if self.max_bytes.map_or(false, |max| size > max) {
+                    self.rejected += 1;
+                    self.raise_alert(&sample.res_name);
+                    return vec![];
+                }
+                let mut samples = vec![];
+                let (mut freed_bytes, mut freed_keys) = (0, 0);
+                for (path, (path_size, _)) in self
+                    .order
+                    .values()
+                    .filter(|path| **path != sample.res_name)
+                    .filter_map(|path| self.sizes.get(path).map(|entry| (path, entry)))
+                {
+                    if !self.exceeds(&sample.res_name, size, freed_bytes, freed_keys) {
+                        break;
+                    }
+                    freed_bytes += path_size;
+                    freed_keys += 1;
+                    samples.push(Quota::delete_sample(path.clone()));
+                }
+                self.evicted += samples.len() as u64;
+                self.raise_alert(&sample.res_name);
+                samples.push(sample);
+                samples
+            }
+        }
+    }
+
+    /// Accounts for a sample the storage stored. The samples it dropped
+    /// (e.g. as out-of-date) are not accounted.
+    pub(crate) fn stored(&mut self, sample: &Sample) {
+        match get_kind(sample) {
+            ChangeKind::Put => self.insert(sample.res_name.clone(), Quota::sample_size(sample)),
+            ChangeKind::Delete => self.remove(&sample.res_name),
+            ChangeKind::Patch => (),
+        }
+    }
+
+    fn delete_sample(path: String) -> Sample {
+        Sample {
+            res_name: path,
+            payload: ZBuf::new(),
+            data_info: Some(DataInfo {
+                kind: Some(data_kind::DELETE),
+                timestamp: Some(utils::new_reception_timestamp()),
+                ..Default::default()
+            }),
+        }
+    }
+
+    fn raise_alert(&mut self, path: &str) {
+        if !self.alerted {
+            warn!(
+                "Storage quota exceeded by a PUT on {} (policy: {})",
+                path,
+                self.policy.as_str()
+            );
+            self.alerted = true;
+            self.alert = Some(json!({
+                "path": path,
+                "policy": self.policy.as_str(),
+                "quota": self.to_json(),
+            }));
+        }
+    }
+
+    /// Returns the alert to be published since the last call, if any.
+    pub(crate) fn take_alert(&mut self) -> Option<serde_json::Value> {
+        self.alert.take()
+    }
+
+    pub(crate) fn to_json(&self) -> serde_json::Value {
+        json!({
+            "max_bytes": self.max_bytes,
+            "max_keys": self.max_keys,
+            "policy": self.policy.as_str(),
+            "bytes": self.bytes,
+            "keys": self.sizes.len(),
+            "rejected": self.rejected,
+            "evicted": self.evicted,
+        })
+    }
+}
+
+fn get_kind(sample: &Sample) -> ChangeKind {
+    sample
+        .data_info
+        .as_ref()
+        .and_then(|info| info.kind)
+        .map_or(ChangeKind::Put, ChangeKind::from)
+}
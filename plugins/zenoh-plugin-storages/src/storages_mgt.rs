@@ -11,38 +11,293 @@
 // Contributors:
 //   ADLINK zenoh team, <zenoh@adlink-labs.tech>
 //
-use async_std::channel::{bounded, Sender};
+use super::dump::{
+    ExportRequest, ExportTarget, ImportRequest, EXPORT_CHUNK_SIZE, IMPORT_BATCH_SIZE,
+};
+use super::quota::Quota;
+use super::StopHandle;
+use async_std::channel::{bounded, unbounded, Sender};
 use async_std::sync::{Arc, RwLock};
 use async_std::task;
+use async_trait::async_trait;
 use futures::select;
 use futures::stream::StreamExt;
 use futures::FutureExt;
 use log::{debug, error, trace, warn};
+use serde_json::json;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+use zenoh::net::runtime::AdminPermissions;
+use zenoh::net::utils::resource_name;
 use zenoh::net::{
-    queryable, QueryConsolidation, QueryTarget, Reliability, SubInfo, SubMode, Target,
+    data_kind, encoding, queryable, DataInfo, History, QueryConsolidation, QueryPage, QueryTarget,
+    Reliability, Sample, SubInfo, SubMode, Target, ZBuf, ZInt,
+};
+use zenoh::{
+    utils, ChangeKind, Path, PathExpr, Selector, Timestamp, Value, Workspace, ZResult, Zenoh,
 };
-use zenoh::{Path, PathExpr, ZResult, Zenoh};
 use zenoh_backend_traits::{IncomingDataInterceptor, OutgoingDataInterceptor, Query};
+use zenoh_util::collections::{Timed, TimedEvent, TimedHandle, Timer};
+use zenoh_util::properties::config::{ZN_ADMIN_READ_STR, ZN_ADMIN_WRITE_STR};
+
+// The scheduled expirations of the samples of a storage configured with a time-to-live.
+struct Expirations {
+    ttl: Duration,
+    timer: Timer,
+    // for each path: the timestamp of its latest sample, and the generation and handle
+    // of its scheduled expiration
+    scheduled: HashMap<String, (Option<Timestamp>, u64, TimedHandle)>,
+    generation: u64,
+    expired_tx: Sender<(String, u64)>,
+}
 
+impl Expirations {
+    fn new(ttl: Duration, expired_tx: Sender<(String, u64)>) -> Expirations {
+        Expirations {
+            ttl,
+            timer: Timer::new(),
+            scheduled: HashMap::new(),
+            generation: 0,
+            expired_tx,
+        }
+    }
+
+    // (Re)schedules the expiration of the path of a sample, or cancels it if it's a DELETE.
+    async fn on_sample(&mut self, sample: &Sample) {
+        let (kind, timestamp) = match &sample.data_info {
+            Some(info) => (
+                info.kind.map_or(ChangeKind::Put, ChangeKind::from),
+                info.timestamp.clone(),
+            ),
+            None => (ChangeKind::Put, None),
+        };
+        if let Some((latest, _, _)) = self.scheduled.get(&sample.res_name) {
+            if latest.is_some() && timestamp.is_some() && latest > &timestamp {
+                trace!(
+                    "Out-of-date sample for {}: expiration unchanged",
+                    sample.res_name
+                );
+                return;
+            }
+        }
+        if let Some((_, _, handle)) = self.scheduled.remove(&sample.res_name) {
+            handle.defuse();
+        }
+        if let ChangeKind::Put = kind {
+            // the time-to-live runs from the sample's timestamp, or from its reception
+            let age = timestamp
+                .as_ref()
+                .and_then(|ts| {
+                    SystemTime::now()
+                        .duration_since(ts.get_time().to_system_time())
+                        .ok()
+                })
+                .unwrap_or_default();
+            let when = Instant::now() + self.ttl.checked_sub(age).unwrap_or_default();
+            self.generation += 1;
+            let event = TimedEvent::once(
+                when,
+                TimedExpiration {
+                    path: sample.res_name.clone(),
+                    generation: self.generation,
+                    expired_tx: self.expired_tx.clone(),
+                },
+            );
+            let handle = event.get_handle();
+            self.timer.add(event).await;
+            self.scheduled.insert(
+                sample.res_name.clone(),
+                (timestamp, self.generation, handle),
+            );
+        }
+    }
+
+    // Returns true if the expiration `generation` of `path` is still the scheduled one,
+    // i.e. if no newer sample arrived for `path` while it was fired.
+    fn expire(&mut self, path: &str, generation: u64) -> bool {
+        match self.scheduled.get(path) {
+            Some((_, g, _)) if *g == generation => {
+                self.scheduled.remove(path);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+// Upper bounds in milliseconds of the buckets of the latency histograms
+// (plus a last unbounded bucket).
+const LATENCY_BUCKETS_MS: [u64; 7] = [1, 5, 10, 50, 100, 500, 1000];
+
+#[derive(Default)]
+struct Latencies {
+    count: u64,
+    total: Duration,
+    max: Duration,
+    buckets: [u64; LATENCY_BUCKETS_MS.len() + 1],
+}
+
+impl Latencies {
+    fn record(&mut self, latency: Duration) {
+        self.count += 1;
+        self.total += latency;
+        self.max = self.max.max(latency);
+        let ms = latency.as_secs_f64() * 1000.0;
+        let bucket = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|bound| ms <= *bound as f64)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+        self.buckets[bucket] += 1;
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let mean = if self.count > 0 {
+            self.total.as_secs_f64() * 1000.0 / self.count as f64
+        } else {
+            0.0
+        };
+        let histogram = LATENCY_BUCKETS_MS
+            .iter()
+            .map(|bound| format!("le_{}ms", bound))
+            .chain(std::iter::once("inf".to_string()))
+            .zip(self.buckets.iter())
+            .map(|(bucket, count)| (bucket, json!(count)))
+            .collect::<serde_json::Map<_, _>>();
+        json!({
+            "count": self.count,
+            "mean_ms": mean,
+            "max_ms": self.max.as_secs_f64() * 1000.0,
+            "histogram": histogram,
+        })
+    }
+}
+
+// The ingestion and query metrics of a storage, reported in its admin status
+// to detect a degrading volume.
+#[derive(Default)]
+struct StorageMetrics {
+    put: Latencies,
+    delete: Latencies,
+    query: Latencies,
+    errors: u64,
+    max_queue_depth: usize,
+    imported: u64,
+    // the imported samples skipped as their key doesn't match the storage's path expression
+    import_skipped: u64,
+    exported: u64,
+    // the snapshot timestamp of the latest export
+    last_export: Option<Timestamp>,
+}
+
+impl StorageMetrics {
+    // Returns the admin status of a storage, completed with these metrics
+    // and the current depth of its ingestion queue.
+    fn add_to_status(&self, status: Value, queue_depth: usize, quota: &Option<Quota>) -> Value {
+        let mut status = match status {
+            Value::Json(s) => match serde_json::from_str::<serde_json::Value>(&s) {
+                Ok(serde_json::Value::Object(status)) => status,
+                _ => return Value::Json(s),
+            },
+            status => return status,
+        };
+        if let Some(quota) = quota {
+            status.insert("quota".to_string(), quota.to_json());
+        }
+        status.insert(
+            "metrics".to_string(),
+            json!({
+                "queue_depth": queue_depth,
+                "max_queue_depth": self.max_queue_depth,
+                "put": self.put.to_json(),
+                "delete": self.delete.to_json(),
+                "query": self.query.to_json(),
+                "errors": self.errors,
+                "imported": self.imported,
+                "import_skipped": self.import_skipped,
+                "exported": self.exported,
+                "last_export": self.last_export.as_ref().map(|ts| ts.to_string()),
+            }),
+        );
+        Value::Json(serde_json::Value::Object(status).to_string())
+    }
+}
+
+struct TimedExpiration {
+    path: String,
+    generation: u64,
+    expired_tx: Sender<(String, u64)>,
+}
+
+#[async_trait]
+impl Timed for TimedExpiration {
+    async fn run(&mut self) {
+        let _ = self
+            .expired_tx
+            .send((self.path.clone(), self.generation))
+            .await;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn start_storage(
     mut storage: Box<dyn zenoh_backend_traits::Storage>,
     admin_path: Path,
     path_expr: PathExpr,
+    ttl: Option<Duration>,
+    mut quota: Option<Quota>,
+    dump_dir: Option<PathBuf>,
     in_interceptor: Option<Arc<RwLock<Box<dyn IncomingDataInterceptor>>>>,
     out_interceptor: Option<Arc<RwLock<Box<dyn OutgoingDataInterceptor>>>>,
     zenoh: Arc<Zenoh>,
-) -> ZResult<Sender<bool>> {
+) -> ZResult<StopHandle> {
     debug!("Start storage {} on {}", admin_path, path_expr);
 
-    let (tx, rx) = bounded::<bool>(1);
+    let (tx, rx) = bounded::<Sender<()>>(1);
     task::spawn(async move {
         let workspace = zenoh.workspace(Some(admin_path.clone())).await.unwrap();
+        // the status requests are read operations on the admin space,
+        // and the import and export requests are write operations
+        let runtime = zenoh.session().runtime();
+        let root_path = format!("/@/router/{}", runtime.get_pid_str());
+        let read_permissions = AdminPermissions::reads(&runtime.config(), &root_path);
+        let write_permissions = AdminPermissions::writes(&runtime.config(), &root_path);
+
+        // expired paths are sent back by the timer to be deleted in the loop below
+        let (expired_tx, expired_rx) = unbounded::<(String, u64)>();
+        let mut expirations = ttl.map(|ttl| {
+            debug!("Storage {} expires samples after {:?}", admin_path, ttl);
+            Expirations::new(ttl, expired_tx.clone())
+        });
+        let mut metrics = StorageMetrics::default();
+        if quota.is_some() || expirations.is_some() {
+            match storage.get_all_samples().await {
+                Ok(samples) => {
+                    if let Some(quota) = quota.as_mut() {
+                        quota.load(&samples);
+                    }
+                    // the samples stored before the start expire according to their timestamp
+                    if let Some(expirations) = expirations.as_mut() {
+                        for sample in &samples {
+                            expirations.on_sample(sample).await;
+                        }
+                    }
+                }
+                Err(e) => warn!(
+                    "Storage {} can't account its existing content in its quota or expire it: {}",
+                    admin_path, e
+                ),
+            }
+        }
 
         // subscribe on path_expr
         let sub_info = SubInfo {
             reliability: Reliability::Reliable,
             mode: SubMode::Push,
             period: None,
+            history: History::None,
+            filter: None,
         };
         let mut storage_sub = match workspace
             .session()
@@ -80,14 +335,16 @@ pub(crate) async fn start_storage(
         };
         while let Some(reply) = replies.next().await {
             log::trace!("Storage {} aligns data {}", admin_path, reply.data.res_name);
-            // Call incoming data interceptor (if any)
-            let sample = if let Some(ref interceptor) = in_interceptor {
-                interceptor.read().await.on_sample(reply.data).await
-            } else {
-                reply.data
-            };
-            // Call storage
-            if let Err(e) = storage.on_sample(sample).await {
+            if let Err(e) = store_sample(
+                &mut storage,
+                reply.data,
+                &in_interceptor,
+                &mut expirations,
+                &mut quota,
+                &mut metrics,
+            )
+            .await
+            {
                 warn!(
                     "Storage {} raised an error aligning a sample: {}",
                     admin_path, e
@@ -105,6 +362,26 @@ pub(crate) async fn start_storage(
             }
         };
 
+        // import the dump files PUT on 'admin_path'/import
+        let import_selector = Selector::try_from(format!("{}/import", admin_path)).unwrap();
+        let mut storage_import = match workspace.subscribe(&import_selector).await {
+            Ok(storage_import) => storage_import,
+            Err(e) => {
+                error!("Error starting storage {} : {}", admin_path, e);
+                return;
+            }
+        };
+
+        // export the storage content as requested by the PUTs on 'admin_path'/export
+        let export_selector = Selector::try_from(format!("{}/export", admin_path)).unwrap();
+        let mut storage_export = match workspace.subscribe(&export_selector).await {
+            Ok(storage_export) => storage_export,
+            Err(e) => {
+                error!("Error starting storage {} : {}", admin_path, e);
+                return;
+            }
+        };
+
         // answer to queries on path_expr
         let mut storage_queryable = match workspace
             .session()
@@ -118,25 +395,57 @@ pub(crate) async fn start_storage(
             }
         };
 
-        loop {
+        let stop = loop {
             select!(
                 // on get request on storage_admin
                 get = storage_admin.next().fuse() => {
                     let get = get.unwrap();
-                    get.reply_async(admin_path.clone(), storage.get_admin_status().await).await;
+                    if !read_permissions.allows(admin_path.as_str()) {
+                        debug!("Get on {} not answered: not allowed by {}", admin_path, ZN_ADMIN_READ_STR);
+                        continue;
+                    }
+                    let status = metrics.add_to_status(storage.get_admin_status().await, storage_sub.receiver().len(), &quota);
+                    get.reply_async(admin_path.clone(), status).await;
                 },
                 // on sample for path_expr
                 sample = storage_sub.receiver().next().fuse() => {
-                    // Call incoming data interceptor (if any)
-                    let sample = if let Some(ref interceptor) = in_interceptor {
-                        interceptor.read().await.on_sample(sample.unwrap()).await
-                    } else {
-                        sample.unwrap()
-                    };
-                    // Call storage
-                    if let Err(e) = storage.on_sample(sample).await {
+                    // the samples still queued behind this one
+                    metrics.max_queue_depth = metrics.max_queue_depth.max(storage_sub.receiver().len());
+                    if let Err(e) = store_sample(&mut storage, sample.unwrap(), &in_interceptor, &mut expirations, &mut quota, &mut metrics).await {
                         warn!("Storage {} raised an error receiving a sample: {}", admin_path, e);
                     }
+                    publish_alert(&workspace, &admin_path, &mut quota).await;
+                },
+                // on import request
+                change = storage_import.next().fuse() => {
+                    let change = change.unwrap();
+                    match (change.kind, change.value) {
+                        (ChangeKind::Put, Some(_)) if !write_permissions.allows(change.path.as_str()) => {
+                            warn!("Import on {} ignored: not allowed by {}", change.path, ZN_ADMIN_WRITE_STR);
+                        }
+                        (ChangeKind::Put, Some(value)) => {
+                            if let Err(e) = import(&mut storage, value, dump_dir.as_deref(), &path_expr, &in_interceptor, &mut expirations, &mut quota, &mut metrics).await {
+                                warn!("Storage {} failed to import: {}", admin_path, e);
+                            }
+                            publish_alert(&workspace, &admin_path, &mut quota).await;
+                        }
+                        _ => warn!("Only PUT with a value is supported on {}", change.path),
+                    }
+                },
+                // on export request
+                change = storage_export.next().fuse() => {
+                    let change = change.unwrap();
+                    match (change.kind, change.value) {
+                        (ChangeKind::Put, Some(_)) if !write_permissions.allows(change.path.as_str()) => {
+                            warn!("Export on {} ignored: not allowed by {}", change.path, ZN_ADMIN_WRITE_STR);
+                        }
+                        (ChangeKind::Put, Some(value)) => {
+                            if let Err(e) = export(storage.as_ref(), value, dump_dir.as_deref(), &workspace, &mut metrics).await {
+                                warn!("Storage {} failed to export: {}", admin_path, e);
+                            }
+                        }
+                        _ => warn!("Only PUT with a value is supported on {}", change.path),
+                    }
                 },
                 // on query on path_expr
                 query = storage_queryable.receiver().next().fuse() => {
@@ -144,18 +453,289 @@ pub(crate) async fn start_storage(
                     // wrap zenoh::net::Query in zenoh_backend_traits::Query
                     // with outgoing interceptor
                     let query = Query::new(q, out_interceptor.clone());
-                    if let Err(e) = storage.on_query(query).await {
+                    let start = Instant::now();
+                    let result = storage.on_query(query).await;
+                    metrics.query.record(start.elapsed());
+                    if let Err(e) = result {
+                        metrics.errors += 1;
                         warn!("Storage {} raised an error receiving a query: {}", admin_path, e);
                     }
                 },
-                // on storage handle drop
-                _ = rx.recv().fuse() => {
-                    trace!("Dropping storage {}", admin_path);
-                    return
-                }
+                // on expiration of a sample: delete it from this storage and the other ones
+                expired = expired_rx.recv().fuse() => {
+                    let (path, generation) = expired.unwrap();
+                    if expirations.as_mut().map_or(false, |e| e.expire(&path, generation)) {
+                        debug!("Storage {} deletes expired {}", admin_path, path);
+                        // removed from this storage whatever its conflict resolution,
+                        // a DELETE published after a first_wins sample being dropped
+                        let sample = expired_sample(path.clone());
+                        match storage.on_expired(sample.clone()).await {
+                            Ok(()) => {
+                                if let Some(quota) = quota.as_mut() {
+                                    quota.stored(&sample);
+                                }
+                            }
+                            Err(e) => {
+                                metrics.errors += 1;
+                                warn!("Storage {} failed to remove expired {}: {}", admin_path, path, e);
+                            }
+                        }
+                        match Path::try_from(path) {
+                            Ok(path) => {
+                                if let Err(e) = workspace.delete(&path).await {
+                                    warn!("Storage {} failed to delete expired {}: {}", admin_path, path, e);
+                                }
+                            }
+                            Err(e) => warn!("Storage {} can't delete expired sample: {}", admin_path, e),
+                        }
+                    }
+                },
+                // on storage handle drop or stop request
+                stop = rx.recv().fuse() => break stop,
             );
+        };
+
+        // flush the samples already received
+        for sample in storage_sub.receiver().try_iter() {
+            if let Err(e) = store_sample(
+                &mut storage,
+                sample,
+                &in_interceptor,
+                &mut expirations,
+                &mut quota,
+                &mut metrics,
+            )
+            .await
+            {
+                warn!(
+                    "Storage {} raised an error flushing a sample: {}",
+                    admin_path, e
+                );
+            }
         }
+        trace!("Dropping storage {}", admin_path);
+        drop(storage);
+        // notify the stop requester (if any) that the storage is stopped
+        drop(stop);
     });
 
     Ok(tx)
 }
+
+// Returns the DELETE removing an expired path from the storage.
+fn expired_sample(path: String) -> Sample {
+    Sample {
+        res_name: path,
+        payload: ZBuf::new(),
+        data_info: Some(DataInfo {
+            kind: Some(data_kind::DELETE),
+            timestamp: Some(utils::new_reception_timestamp()),
+            ..Default::default()
+        }),
+    }
+}
+
+// Calls the incoming data interceptor (if any), applies the quota (if any) and schedules
+// the expirations (if any), returning the samples to be stored: none if the sample is rejected
+// by the quota, or the sample preceded by the DELETEs of the paths evicted by the quota.
+async fn admit_sample(
+    sample: Sample,
+    in_interceptor: &Option<Arc<RwLock<Box<dyn IncomingDataInterceptor>>>>,
+    expirations: &mut Option<Expirations>,
+    quota: &mut Option<Quota>,
+) -> Vec<Sample> {
+    let sample = if let Some(interceptor) = in_interceptor {
+        interceptor.read().await.on_sample(sample).await
+    } else {
+        sample
+    };
+    let samples = match quota {
+        Some(quota) => quota.admit(sample),
+        None => vec![sample],
+    };
+    if let Some(expirations) = expirations {
+        for sample in &samples {
+            expirations.on_sample(sample).await;
+        }
+    }
+    samples
+}
+
+// Admits the sample and stores the resulting samples, recording their latency in the metrics.
+async fn store_sample(
+    storage: &mut Box<dyn zenoh_backend_traits::Storage>,
+    sample: Sample,
+    in_interceptor: &Option<Arc<RwLock<Box<dyn IncomingDataInterceptor>>>>,
+    expirations: &mut Option<Expirations>,
+    quota: &mut Option<Quota>,
+    metrics: &mut StorageMetrics,
+) -> ZResult<()> {
+    for sample in admit_sample(sample, in_interceptor, expirations, quota).await {
+        let kind = sample
+            .data_info
+            .as_ref()
+            .and_then(|info| info.kind)
+            .map_or(ChangeKind::Put, ChangeKind::from);
+        let start = Instant::now();
+        let result = store_admitted(storage, sample, quota).await;
+        match kind {
+            ChangeKind::Delete => metrics.delete.record(start.elapsed()),
+            _ => metrics.put.record(start.elapsed()),
+        }
+        if result.is_err() {
+            metrics.errors += 1;
+            return result;
+        }
+    }
+    Ok(())
+}
+
+// Stores a sample admitted by the quota (if any), accounting it in the quota once stored:
+// the samples dropped by the storage (e.g. as out-of-date) are not accounted.
+async fn store_admitted(
+    storage: &mut Box<dyn zenoh_backend_traits::Storage>,
+    sample: Sample,
+    quota: &mut Option<Quota>,
+) -> ZResult<()> {
+    match quota {
+        Some(quota) => {
+            let copy = sample.clone();
+            if storage.on_sample_checked(sample).await? {
+                quota.stored(&copy);
+            }
+            Ok(())
+        }
+        None => storage.on_sample(sample).await,
+    }
+}
+
+// Publishes the alert raised by the quota (if any) on 'admin_path'/alert.
+async fn publish_alert(workspace: &Workspace<'_>, admin_path: &Path, quota: &mut Option<Quota>) {
+    if let Some(alert) = quota.as_mut().and_then(|quota| quota.take_alert()) {
+        let path = Path::try_from(format!("{}/alert", admin_path)).unwrap();
+        if let Err(e) = workspace.put(&path, Value::Json(alert.to_string())).await {
+            warn!(
+                "Storage {} failed to publish a quota alert: {}",
+                admin_path, e
+            );
+        }
+    }
+}
+
+// Imports the samples of the dump file of `dump_dir` requested in `value`, reading them
+// and writing them in the storage by batches of IMPORT_BATCH_SIZE samples. The samples
+// with a key not matching `path_expr` are skipped. A malformed record stops the import,
+// keeping the samples of the previous batches.
+#[allow(clippy::too_many_arguments)]
+async fn import(
+    storage: &mut Box<dyn zenoh_backend_traits::Storage>,
+    value: Value,
+    dump_dir: Option<&std::path::Path>,
+    path_expr: &PathExpr,
+    in_interceptor: &Option<Arc<RwLock<Box<dyn IncomingDataInterceptor>>>>,
+    expirations: &mut Option<Expirations>,
+    quota: &mut Option<Quota>,
+    metrics: &mut StorageMetrics,
+) -> ZResult<()> {
+    let request = ImportRequest::from_value(value, dump_dir)?;
+    let mut reader = request.open().await?;
+    debug!("Import samples from {}", request.file.display());
+    let skipped = metrics.import_skipped;
+    loop {
+        let samples = reader.next_batch(IMPORT_BATCH_SIZE).await?;
+        if samples.is_empty() {
+            break;
+        }
+        let mut batch = Vec::with_capacity(samples.len());
+        for sample in samples {
+            if !resource_name::include(path_expr.as_str(), &sample.res_name) {
+                metrics.import_skipped += 1;
+                continue;
+            }
+            for sample in admit_sample(sample, in_interceptor, expirations, quota).await {
+                if quota.is_some() {
+                    // with a quota, the samples are stored one by one for the quota to
+                    // account for each stored sample before admitting the next one
+                    if let Err(e) = store_admitted(storage, sample, quota).await {
+                        metrics.errors += 1;
+                        return Err(e);
+                    }
+                    metrics.imported += 1;
+                } else {
+                    batch.push(sample);
+                }
+            }
+        }
+        if batch.is_empty() {
+            continue;
+        }
+        let count = batch.len() as u64;
+        if let Err(e) = storage.on_samples(batch).await {
+            metrics.errors += 1;
+            return Err(e);
+        }
+        metrics.imported += count;
+    }
+    if metrics.import_skipped > skipped {
+        warn!(
+            "Import from {} skipped {} samples with a key not matching {}",
+            request.file.display(),
+            metrics.import_skipped - skipped,
+            path_expr
+        );
+    }
+    log::info!("Imported {} in storage", request.file.display());
+    Ok(())
+}
+
+// Exports the content of the storage as requested in `value`, in a dump file of `dump_dir`
+// or under a path by chunks, reading the samples from the storage and writing them by pages
+// of EXPORT_CHUNK_SIZE samples.
+async fn export(
+    storage: &dyn zenoh_backend_traits::Storage,
+    value: Value,
+    dump_dir: Option<&std::path::Path>,
+    workspace: &Workspace<'_>,
+    metrics: &mut StorageMetrics,
+) -> ZResult<()> {
+    let request = ExportRequest::from_value(value, dump_dir)?;
+    // the storage receives no sample until the export is complete: its content
+    // is a consistent snapshot of all the samples received before this timestamp
+    let snapshot = utils::new_reception_timestamp();
+    debug!("Export to {} (snapshot at {})", request.target, snapshot);
+    let mut writer = match &request.target {
+        ExportTarget::File(file) => Some(request.create(file).await?),
+        ExportTarget::Path(_) => None,
+    };
+    let mut page = QueryPage {
+        limit: EXPORT_CHUNK_SIZE as ZInt,
+        after: None,
+    };
+    let mut exported = 0;
+    for i in 0.. {
+        let samples = storage.get_samples_page(&page).await?;
+        if samples.is_empty() {
+            break;
+        }
+        if let Some(writer) = writer.as_mut() {
+            writer.write_batch(&samples).await?;
+        } else if let ExportTarget::Path(path) = &request.target {
+            let chunk_path = Path::try_from(format!("{}/{}", path, i))?;
+            let value = Value::Raw(encoding::APP_OCTET_STREAM, request.encode(&samples).into());
+            workspace.put(&chunk_path, value).await?;
+        }
+        exported += samples.len() as u64;
+        page.after = samples.last().map(|sample| sample.res_name.clone());
+    }
+    if let Some(writer) = writer {
+        writer.finish().await?;
+    }
+    metrics.exported += exported;
+    metrics.last_export = Some(snapshot);
+    log::info!(
+        "Exported {} samples of storage to {}",
+        exported,
+        request.target
+    );
+    Ok(())
+}
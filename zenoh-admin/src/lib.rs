@@ -0,0 +1,357 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+
+//! A typed client for the admin space of the zenoh routers, to build management tools
+//! without dealing with the admin paths and their JSON encoding.
+//!
+//! # Examples
+//! ```no_run
+//! # async_std::task::block_on(async {
+//! use zenoh::*;
+//! use zenoh_admin::AdminClient;
+//!
+//! let zenoh = Zenoh::new(net::config::client(None)).await.unwrap();
+//! let admin = AdminClient::new(&zenoh).await.unwrap();
+//! for transport in admin.transports().await.unwrap() {
+//!     println!("{} via {:?}", transport.peer, transport.links);
+//! }
+//! # })
+//! ```
+use futures::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use zenoh::{Selector, Value, Zenoh};
+use zenoh_util::core::{ZError, ZErrorKind, ZResult};
+use zenoh_util::zerror;
+
+/// The information on a router, returned by [`AdminClient::info()`].
+#[derive(Clone, Debug, Deserialize)]
+pub struct RouterInfo {
+    /// The peer id of the router.
+    pub pid: String,
+    /// The version of the router.
+    pub version: String,
+    /// The locators the router listens on.
+    #[serde(default)]
+    pub locators: Vec<String>,
+    /// The sessions of the router with the other peers.
+    #[serde(default, rename = "sessions")]
+    pub transports: Vec<TransportInfo>,
+    /// The peers the router retries to connect to, by locator.
+    #[serde(default)]
+    pub connect_retries: HashMap<String, ConnectRetry>,
+    /// The plugins loaded by the router.
+    #[serde(default)]
+    pub plugins: Vec<PluginInfo>,
+}
+
+/// A session of a router with another peer.
+#[derive(Clone, Debug, Deserialize)]
+pub struct TransportInfo {
+    /// The peer id of the remote peer.
+    pub peer: String,
+    /// The locators of the remote peer the session links are established with.
+    #[serde(default)]
+    pub links: Vec<String>,
+    /// The links flagged as degraded by the link quality probes.
+    #[serde(default)]
+    pub degraded_links: Vec<String>,
+    /// The parameters negotiated when establishing the session.
+    pub parameters: Option<TransportParameters>,
+}
+
+/// The parameters negotiated when establishing a session.
+#[derive(Clone, Debug, Deserialize)]
+pub struct TransportParameters {
+    /// The zenoh protocol version.
+    pub version: u8,
+    /// The resolution of the sequence numbers.
+    pub sn_resolution: u64,
+    /// The maximum size of the batches sent on each link, by destination locator.
+    #[serde(default)]
+    pub batch_sizes: HashMap<String, usize>,
+    /// Whether the data is exchanged via shared memory.
+    #[serde(default)]
+    pub shm: bool,
+    /// The authentication methods used to establish the session.
+    #[serde(default)]
+    pub auth: Vec<String>,
+    /// The subject the remote peer was authenticated as, if any.
+    pub auth_subject: Option<String>,
+    /// Whether the batches are compressed.
+    #[serde(default)]
+    pub compression: bool,
+    /// Whether the QoS priorities are applied.
+    #[serde(default)]
+    pub qos: bool,
+}
+
+/// The state of the retries to connect a configured peer.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ConnectRetry {
+    /// The number of failed attempts.
+    pub attempts: u32,
+    /// The delay before the next attempt, in milliseconds.
+    pub delay_ms: u64,
+    /// The time of the next attempt, in seconds since the UNIX epoch.
+    pub next_attempt: f64,
+}
+
+/// A plugin loaded by a router.
+#[derive(Clone, Debug, Deserialize)]
+pub struct PluginInfo {
+    /// The name of the plugin.
+    pub name: String,
+    /// The path of the plugin library.
+    pub path: String,
+}
+
+/// The health of a router, returned by [`AdminClient::health()`].
+#[derive(Clone, Debug, Deserialize)]
+pub struct Health {
+    /// The peer id of the router.
+    pub pid: String,
+    /// The overall status: `"ok"` or `"degraded"`.
+    pub status: String,
+    /// The health of the transports of the router.
+    pub transports: TransportsHealth,
+    /// The status of the plugins of the router, including the ones that failed to load.
+    #[serde(default)]
+    pub plugins: Vec<PluginStatus>,
+    /// How far ahead of the system clock the HLC of the router has been pushed, in milliseconds.
+    #[serde(default, rename = "hlc", deserialize_with = "deserialize_drift")]
+    pub hlc_drift_ms: Option<u64>,
+}
+
+impl Health {
+    /// Returns true if the router and all its plugins are healthy.
+    pub fn is_ok(&self) -> bool {
+        self.status == "ok"
+    }
+}
+
+/// The liveness of a router, returned by [`AdminClient::liveness()`].
+#[derive(Clone, Debug, Deserialize)]
+pub struct Liveness {
+    /// Whether the routing tables and sessions of the router respond, it listens on a locator
+    /// and its plugins are loaded.
+    pub alive: bool,
+    /// The reasons why the router is not alive.
+    #[serde(default)]
+    pub reasons: Vec<String>,
+}
+
+/// The health of the transports of a router.
+#[derive(Clone, Debug, Deserialize)]
+pub struct TransportsHealth {
+    /// The status of the transports: `"ok"` or `"down"` if the router doesn't listen on any locator.
+    pub status: String,
+    /// The locators the router listens on.
+    #[serde(default)]
+    pub locators: Vec<String>,
+    /// The number of sessions of the router.
+    pub sessions: usize,
+}
+
+/// The status of a plugin of a router.
+#[derive(Clone, Debug, Deserialize)]
+pub struct PluginStatus {
+    /// The name of the plugin, if it was loaded.
+    pub name: Option<String>,
+    /// The path of the plugin library.
+    pub path: String,
+    /// The status of the plugin: `"ok"` or `"failed"`.
+    pub status: String,
+    /// The reason of the failure, if the plugin failed to load.
+    pub reason: Option<String>,
+}
+
+/// The traffic counters of a session.
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+pub struct TransportCounters {
+    pub tx_batches: u64,
+    pub tx_bytes: u64,
+    pub rx_batches: u64,
+    pub rx_bytes: u64,
+    /// Missing from the counters of the routers predating it.
+    #[serde(default)]
+    pub defrag_aborts: u64,
+}
+
+/// The traffic counters of a session of a router, returned by [`AdminClient::stats()`].
+#[derive(Clone, Debug, Deserialize)]
+pub struct TransportStats {
+    /// The counters as seen by the router.
+    pub local: TransportCounters,
+    /// The counters as seen by the remote peer, if it replied in time.
+    pub remote: Option<TransportCounters>,
+}
+
+/// A storage of the storages plugin of a router, returned by [`AdminClient::storages()`].
+#[derive(Clone, Debug)]
+pub struct StorageInfo {
+    /// The name of the backend of the storage.
+    pub backend: String,
+    /// The name of the storage.
+    pub name: String,
+    /// The path expression the storage stores, if reported.
+    pub path_expr: Option<String>,
+    /// The status of the storage as reported by its backend (properties, metrics, quota...).
+    pub status: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct HlcHealth {
+    drift_ms: u64,
+}
+
+fn deserialize_drift<'de, D: serde::Deserializer<'de>>(d: D) -> Result<Option<u64>, D::Error> {
+    Option::<HlcHealth>::deserialize(d).map(|hlc| hlc.map(|hlc| hlc.drift_ms))
+}
+
+/// A client for the admin space of a router.
+pub struct AdminClient<'a> {
+    zenoh: &'a Zenoh,
+    router: String,
+}
+
+impl<'a> AdminClient<'a> {
+    /// Creates a client for the admin space of the router `zenoh` is connected to.
+    pub async fn new(zenoh: &'a Zenoh) -> ZResult<AdminClient<'a>> {
+        match zenoh.router_pid().await {
+            Some(router) => Ok(AdminClient { zenoh, router }),
+            None => zerror!(ZErrorKind::Other {
+                descr: "Not connected to a router".to_string()
+            }),
+        }
+    }
+
+    /// Creates a client for the admin space of the router with the peer id `router`.
+    pub fn with_router(zenoh: &'a Zenoh, router: &str) -> AdminClient<'a> {
+        AdminClient {
+            zenoh,
+            router: router.to_string(),
+        }
+    }
+
+    /// Returns the information on all the reachable routers.
+    pub async fn routers(zenoh: &Zenoh) -> ZResult<Vec<RouterInfo>> {
+        Ok(get_json(zenoh, "/@/router/*")
+            .await?
+            .into_iter()
+            .map(|(_, info)| info)
+            .collect())
+    }
+
+    /// Returns the peer id of the router.
+    pub fn router(&self) -> &str {
+        &self.router
+    }
+
+    async fn get_one<T: DeserializeOwned>(&self, suffix: &str) -> ZResult<T> {
+        let selector = format!("/@/router/{}{}", self.router, suffix);
+        match get_json(self.zenoh, &selector).await?.pop() {
+            Some((_, value)) => Ok(value),
+            None => zerror!(ZErrorKind::Other {
+                descr: format!("No reply from {}", selector)
+            }),
+        }
+    }
+
+    /// Returns the information on the router.
+    pub async fn info(&self) -> ZResult<RouterInfo> {
+        self.get_one("").await
+    }
+
+    /// Returns the sessions of the router with the other peers.
+    pub async fn transports(&self) -> ZResult<Vec<TransportInfo>> {
+        Ok(self.info().await?.transports)
+    }
+
+    /// Returns the health of the router.
+    pub async fn health(&self) -> ZResult<Health> {
+        self.get_one("/health").await
+    }
+
+    /// Returns the liveness of the router, as checked by the watchdog of zenohd.
+    pub async fn liveness(&self) -> ZResult<Liveness> {
+        self.get_one("/liveness").await
+    }
+
+    /// Returns the status of the plugins of the router.
+    pub async fn plugins(&self) -> ZResult<Vec<PluginStatus>> {
+        Ok(self.health().await?.plugins)
+    }
+
+    /// Returns the traffic counters of the sessions of the router, by remote peer id.
+    pub async fn stats(&self) -> ZResult<HashMap<String, TransportStats>> {
+        self.get_one("/stats").await
+    }
+
+    /// Returns the storages of the storages plugin of the router.
+    pub async fn storages(&self) -> ZResult<Vec<StorageInfo>> {
+        let prefix = format!("/@/router/{}/plugin/storages/backend/", self.router);
+        let storages =
+            get_json::<serde_json::Value>(self.zenoh, &[&prefix, "*/storage/*"].concat())
+                .await?
+                .into_iter()
+                .filter_map(|(path, status)| {
+                    // the path is "<prefix><backend>/storage/<name>"
+                    let (backend, name) = path.strip_prefix(&prefix)?.split_once("/storage/")?;
+                    Some(StorageInfo {
+                        backend: backend.to_string(),
+                        name: name.to_string(),
+                        path_expr: status
+                            .get("path_expr")
+                            .and_then(|p| p.as_str())
+                            .map(|p| p.to_string()),
+                        status,
+                    })
+                })
+                .collect();
+        Ok(storages)
+    }
+}
+
+// Gets the JSON values on `selector`, decoded as `T`, with their paths.
+async fn get_json<T: DeserializeOwned>(zenoh: &Zenoh, selector: &str) -> ZResult<Vec<(String, T)>> {
+    let selector = Selector::try_from(selector)?;
+    let workspace = zenoh.workspace(None).await?;
+    let mut data_stream = workspace.get(&selector).await?;
+    let mut values = vec![];
+    while let Some(data) = data_stream.next().await {
+        let json = match data.value {
+            Value::Json(json) => json,
+            value => {
+                log::warn!(
+                    "Unexpected value for {}: {:?} instead of JSON",
+                    data.path,
+                    value
+                );
+                continue;
+            }
+        };
+        match serde_json::from_str(&json) {
+            Ok(value) => values.push((data.path.to_string(), value)),
+            Err(e) => {
+                return zerror!(ZErrorKind::ValueDecodingFailed {
+                    descr: format!("Invalid admin data for {}: {}", data.path, e)
+                })
+            }
+        }
+    }
+    Ok(values)
+}
@@ -0,0 +1,117 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use async_std::prelude::*;
+use async_std::sync::Arc;
+use async_std::task;
+use std::time::Duration;
+use zenoh::net::plugins::PluginsMgr;
+use zenoh::net::runtime::AdminSpace;
+use zenoh::net::*;
+use zenoh::Zenoh;
+use zenoh_admin::AdminClient;
+use zenoh_util::properties::config::*;
+use zenoh_util::LibLoader;
+
+const ROUTER_LOCATOR: &str = "tcp/127.0.0.1:14456";
+const SLEEP: Duration = Duration::from_millis(500);
+
+#[test]
+fn admin_client() {
+    task::block_on(async {
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "router".to_string());
+        config.insert(ZN_LISTENER_KEY, ROUTER_LOCATOR.to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        let router = open(config).await.unwrap();
+        let plugins_mgr = Arc::new(PluginsMgr::new(LibLoader::default()));
+        AdminSpace::start(router.runtime(), plugins_mgr, "test".to_string()).await;
+        let router_pid = router.runtime().get_pid_str();
+
+        // a storage as the storages plugin would declare it in the admin space
+        let storage_path = format!(
+            "/@/router/{}/plugin/storages/backend/memory/storage/demo",
+            router_pid
+        );
+        let mut storage_admin = router
+            .declare_queryable(&storage_path.clone().into(), queryable::EVAL)
+            .await
+            .unwrap();
+        let storage = async {
+            while let Some(query) = storage_admin.receiver().next().await {
+                query
+                    .reply_async(Sample {
+                        res_name: storage_path.clone(),
+                        payload:
+                            r#"{"path_expr":"/demo/**","metrics":{"errors":0}}"#.as_bytes().into(),
+                        data_info: Some(DataInfo {
+                            encoding: Some(encoding::APP_JSON),
+                            ..Default::default()
+                        }),
+                    })
+                    .await;
+            }
+        };
+
+        let admin = async {
+            let mut config = ConfigProperties::default();
+            config.insert(ZN_MODE_KEY, "client".to_string());
+            config.insert(ZN_PEER_KEY, ROUTER_LOCATOR.to_string());
+            config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+            let zenoh = Zenoh::new(config).await.unwrap();
+            task::sleep(SLEEP).await;
+            let client_pid = zenoh.session().id().await;
+
+            let admin = AdminClient::new(&zenoh).await.unwrap();
+            assert_eq!(admin.router(), router_pid);
+
+            let info = admin.info().await.unwrap();
+            assert_eq!(info.pid, router_pid);
+            assert_eq!(info.version, "test");
+            assert_eq!(info.locators.len(), 1);
+
+            let transports = admin.transports().await.unwrap();
+            assert_eq!(transports.len(), 1);
+            assert_eq!(transports[0].peer, client_pid);
+            assert!(!transports[0].links.is_empty());
+            assert!(transports[0].parameters.is_some());
+
+            let health = admin.health().await.unwrap();
+            assert!(health.is_ok());
+            assert_eq!(health.transports.sessions, 1);
+            assert!(admin.plugins().await.unwrap().is_empty());
+            let liveness = admin.liveness().await.unwrap();
+            assert!(liveness.alive, "{:?}", liveness.reasons);
+
+            let stats = admin.stats().await.unwrap();
+            assert!(stats[&client_pid].local.rx_bytes > 0);
+
+            let storages = admin.storages().await.unwrap();
+            assert_eq!(storages.len(), 1);
+            assert_eq!(storages[0].backend, "memory");
+            assert_eq!(storages[0].name, "demo");
+            assert_eq!(storages[0].path_expr.as_deref(), Some("/demo/**"));
+            assert_eq!(storages[0].status["metrics"]["errors"], 0);
+
+            let routers = AdminClient::routers(&zenoh).await.unwrap();
+            assert_eq!(routers.len(), 1);
+            assert_eq!(routers[0].pid, router_pid);
+
+            zenoh.close().await.unwrap();
+        };
+        admin.race(storage).await;
+
+        storage_admin.undeclare().await.unwrap();
+        router.close().await.unwrap();
+    });
+}
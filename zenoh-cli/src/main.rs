@@ -0,0 +1,502 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use async_std::prelude::FutureExt;
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use futures::prelude::*;
+use serde_json::json;
+use std::convert::{TryFrom, TryInto};
+use std::fmt::Display;
+use std::time::{Duration, Instant};
+use zenoh::net::config::ConfigTranscoder;
+use zenoh::net::{whatami, ResKey};
+use zenoh::*;
+use zenoh_util::properties::KeyTranscoder;
+
+// Exit codes
+const EXIT_ERROR: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+const EXIT_NO_DATA: i32 = 3;
+
+const EXIT_CODES_HELP: &str = "EXIT CODES:
+    0    Success
+    1    Error (session opening, invalid path or selector, operation failure...)
+    2    Invalid arguments
+    3    No data (get, admin and scout found nothing)";
+
+#[async_std::main]
+async fn main() {
+    // initiate logging
+    env_logger::init();
+
+    let args = app().get_matches();
+    let json = args.is_present("json");
+    let config = parse_config(&args, json);
+
+    match args.subcommand() {
+        ("put", Some(sub)) => put(config, sub, json).await,
+        ("get", Some(sub)) => get(config, sub, json).await,
+        ("sub", Some(sub)) => subscribe(config, sub, json).await,
+        ("scout", Some(sub)) => scout(config, sub, json).await,
+        ("admin", Some(sub)) => admin(config, sub, json).await,
+        ("bench", Some(sub)) => bench(config, sub, json).await,
+        _ => fail(json, EXIT_USAGE, "Missing subcommand"),
+    }
+}
+
+fn app<'a, 'b>() -> App<'a, 'b> {
+    App::new("zenoh")
+        .about("A command line tool to use and administrate a zenoh infrastructure")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .after_help(EXIT_CODES_HELP)
+        .arg(
+            Arg::from_usage("-m, --mode=[MODE] 'The zenoh session mode (peer by default).")
+                .possible_values(&["peer", "client"])
+                .global(true),
+        )
+        .arg(
+            Arg::from_usage(
+                "-e, --peer=[LOCATOR]...  'Peer locators used to initiate the zenoh session.'",
+            )
+            .number_of_values(1)
+            .global(true),
+        )
+        .arg(
+            Arg::from_usage("-l, --listener=[LOCATOR]...   'Locators to listen on.'")
+                .number_of_values(1)
+                .global(true),
+        )
+        .arg(Arg::from_usage("-c, --config=[FILE]      'A configuration file.'").global(true))
+        .arg(
+            Arg::from_usage(
+                "--cfg=[KEY=VALUE]... 'Any configuration property (ex: --cfg=local_routing=false).'",
+            )
+            .number_of_values(1)
+            .global(true),
+        )
+        .arg(
+            Arg::from_usage(
+                "--no-multicast-scouting 'Disable the multicast-based scouting mechanism.'",
+            )
+            .global(true),
+        )
+        .arg(
+            Arg::from_usage("--json 'Output the results as JSON, one object per line.'")
+                .global(true),
+        )
+        .subcommand(
+            SubCommand::with_name("put")
+                .about("Puts a value on a path")
+                .arg(Arg::from_usage("<PATH> 'The path to put the value on'"))
+                .arg(Arg::from_usage("<VALUE> 'The value to put'"))
+                .arg(
+                    Arg::from_usage("-t, --type=[TYPE] 'The type of the value'")
+                        .possible_values(&["string", "json", "integer", "float"])
+                        .default_value("string"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("get")
+                .about("Gets the values matching a selector")
+                .arg(Arg::from_usage("<SELECTOR> 'The selection of resources to get'")),
+        )
+        .subcommand(
+            SubCommand::with_name("sub")
+                .about("Subscribes to the changes matching a selector")
+                .arg(Arg::from_usage("<SELECTOR> 'The selection of resources to subscribe'"))
+                .arg(Arg::from_usage(
+                    "-n, --count=[COUNT] 'Exit after receiving COUNT changes'",
+                )),
+        )
+        .subcommand(
+            SubCommand::with_name("scout")
+                .about("Scouts for zenoh peers and routers")
+                .arg(
+                    Arg::from_usage("-w, --what=[WHAT] 'What to scout for'")
+                        .possible_values(&["router", "peer", "any"])
+                        .default_value("any"),
+                )
+                .arg(
+                    Arg::from_usage("-t, --timeout=[SECONDS] 'The scouting duration'")
+                        .default_value("1"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("admin")
+                .about("Gets information from the admin space of zenoh routers")
+                .arg(
+                    Arg::from_usage(
+                        "[PATH] 'The admin path under the router (ex: /health, /linkstate/routers)'",
+                    )
+                    .default_value(""),
+                )
+                .arg(Arg::from_usage(
+                    "-r, --router=[PID] 'The router to query (the router of this session by default, \
+                    or all routers if there is none). Use * for all routers'",
+                )),
+        )
+        .subcommand(
+            SubCommand::with_name("bench")
+                .about("Measures the publication throughput on a path")
+                .arg(Arg::from_usage("<PATH> 'The path to publish on'"))
+                .arg(
+                    Arg::from_usage("-s, --size=[BYTES] 'The payload size'").default_value("8"),
+                )
+                .arg(
+                    Arg::from_usage("-n, --count=[COUNT] 'The number of publications'")
+                        .default_value("100000"),
+                ),
+        )
+}
+
+fn parse_config(args: &ArgMatches<'_>, json: bool) -> Properties {
+    let mut config = if let Some(conf_file) = args.value_of("config") {
+        Properties::try_from(std::path::Path::new(conf_file))
+            .unwrap_or_else(|e| fail(json, EXIT_USAGE, e))
+    } else {
+        Properties::default()
+    };
+    if let Some(props) = args.values_of("cfg") {
+        for prop in props {
+            match prop.find('=') {
+                Some(i) if ConfigTranscoder::encode(&prop[..i]).is_some() => {
+                    config.insert(prop[..i].to_lowercase(), prop[i + 1..].to_string());
+                }
+                Some(i) => fail(
+                    json,
+                    EXIT_USAGE,
+                    format!("Unknown configuration property: {}", &prop[..i]),
+                ),
+                None => fail(
+                    json,
+                    EXIT_USAGE,
+                    format!(
+                        "Invalid configuration property (KEY=VALUE expected): {}",
+                        prop
+                    ),
+                ),
+            }
+        }
+    }
+    for key in ["mode", "peer", "listener"].iter() {
+        if let Some(value) = args.values_of(key) {
+            config.insert(key.to_string(), value.collect::<Vec<&str>>().join(","));
+        }
+    }
+    if args.is_present("no-multicast-scouting") {
+        config.insert("multicast_scouting".to_string(), "false".to_string());
+    }
+    config
+}
+
+fn fail<T: Display>(json: bool, code: i32, err: T) -> ! {
+    if json {
+        eprintln!("{}", json!({ "error": err.to_string() }));
+    } else {
+        eprintln!("Error: {}", err);
+    }
+    std::process::exit(code)
+}
+
+fn parse_arg<T: std::str::FromStr>(args: &ArgMatches<'_>, name: &str, json: bool) -> T {
+    let value = args.value_of(name).unwrap();
+    value.parse().unwrap_or_else(|_| {
+        fail(
+            json,
+            EXIT_USAGE,
+            format!("Invalid value for --{}: {}", name, value),
+        )
+    })
+}
+
+async fn open(config: Properties, json: bool) -> Zenoh {
+    Zenoh::new(config.into())
+        .await
+        .unwrap_or_else(|e| fail(json, EXIT_ERROR, e))
+}
+
+fn value_to_json(value: Value) -> serde_json::Value {
+    match value {
+        Value::StringUtf8(s) => json!(s),
+        Value::Properties(p) => json!(*p),
+        Value::Json(s) => serde_json::from_str(&s).unwrap_or_else(|_| json!(s)),
+        Value::Integer(i) => json!(i),
+        Value::Float(f) => json!(f),
+        value => {
+            // encode value as a String, possibly encoding as base64
+            let (_, _, s) = value.encode_to_string();
+            json!(s)
+        }
+    }
+}
+
+fn print_data(data: Data, json: bool) {
+    if json {
+        println!(
+            "{}",
+            json!({
+                "key": data.path.to_string(),
+                "encoding": data.value.encoding_descr(),
+                "time": data.timestamp.to_string(),
+                "value": value_to_json(data.value),
+            })
+        );
+    } else {
+        println!(
+            "{} : {:?} (encoding: {} , timestamp: {})",
+            data.path,
+            data.value,
+            data.value.encoding_descr(),
+            data.timestamp
+        );
+    }
+}
+
+async fn put(config: Properties, args: &ArgMatches<'_>, json: bool) {
+    let path: Path = args
+        .value_of("PATH")
+        .unwrap()
+        .try_into()
+        .unwrap_or_else(|e| fail(json, EXIT_ERROR, e));
+    let value = args.value_of("VALUE").unwrap();
+    let value = match args.value_of("type").unwrap() {
+        "json" => match serde_json::from_str::<serde_json::Value>(value) {
+            Ok(v) => Value::from(&v),
+            Err(e) => fail(json, EXIT_USAGE, format!("Invalid JSON value: {}", e)),
+        },
+        "integer" => Value::Integer(parse_arg(args, "VALUE", json)),
+        "float" => Value::Float(parse_arg(args, "VALUE", json)),
+        _ => Value::from(value),
+    };
+
+    let zenoh = open(config, json).await;
+    let workspace = zenoh
+        .workspace(None)
+        .await
+        .unwrap_or_else(|e| fail(json, EXIT_ERROR, e));
+    workspace
+        .put(&path, value)
+        .await
+        .unwrap_or_else(|e| fail(json, EXIT_ERROR, e));
+    if json {
+        println!("{}", json!({ "key": path.to_string(), "status": "ok" }));
+    }
+    zenoh
+        .close()
+        .await
+        .unwrap_or_else(|e| fail(json, EXIT_ERROR, e));
+}
+
+async fn get_selector(zenoh: &Zenoh, selector: &str, json: bool) -> usize {
+    let selector: Selector = selector
+        .to_string()
+        .try_into()
+        .unwrap_or_else(|e| fail(json, EXIT_ERROR, e));
+    let workspace = zenoh
+        .workspace(None)
+        .await
+        .unwrap_or_else(|e| fail(json, EXIT_ERROR, e));
+    let mut data_stream = workspace
+        .get(&selector)
+        .await
+        .unwrap_or_else(|e| fail(json, EXIT_ERROR, e));
+    let mut count = 0;
+    while let Some(data) = data_stream.next().await {
+        print_data(data, json);
+        count += 1;
+    }
+    count
+}
+
+async fn get(config: Properties, args: &ArgMatches<'_>, json: bool) {
+    let zenoh = open(config, json).await;
+    let count = get_selector(&zenoh, args.value_of("SELECTOR").unwrap(), json).await;
+    zenoh
+        .close()
+        .await
+        .unwrap_or_else(|e| fail(json, EXIT_ERROR, e));
+    if count == 0 {
+        std::process::exit(EXIT_NO_DATA);
+    }
+}
+
+async fn subscribe(config: Properties, args: &ArgMatches<'_>, json: bool) {
+    let max = args
+        .value_of("count")
+        .map(|_| parse_arg::<usize>(args, "count", json));
+    let selector: Selector = args
+        .value_of("SELECTOR")
+        .unwrap()
+        .to_string()
+        .try_into()
+        .unwrap_or_else(|e| fail(json, EXIT_ERROR, e));
+
+    let zenoh = open(config, json).await;
+    let workspace = zenoh
+        .workspace(None)
+        .await
+        .unwrap_or_else(|e| fail(json, EXIT_ERROR, e));
+    let mut change_stream = workspace
+        .subscribe(&selector)
+        .await
+        .unwrap_or_else(|e| fail(json, EXIT_ERROR, e));
+    let mut count = 0;
+    while max != Some(count) {
+        match change_stream.next().await {
+            Some(change) => {
+                if json {
+                    println!(
+                        "{}",
+                        json!({
+                            "key": change.path.to_string(),
+                            "kind": change.kind.to_string(),
+                            "time": change.timestamp.to_string(),
+                            "value": change.value.map(value_to_json),
+                        })
+                    );
+                } else {
+                    println!(
+                        "{} {} : {:?} (timestamp: {})",
+                        change.kind, change.path, change.value, change.timestamp
+                    );
+                }
+                count += 1;
+            }
+            None => break,
+        }
+    }
+    change_stream
+        .close()
+        .await
+        .unwrap_or_else(|e| fail(json, EXIT_ERROR, e));
+    zenoh
+        .close()
+        .await
+        .unwrap_or_else(|e| fail(json, EXIT_ERROR, e));
+}
+
+async fn scout(config: Properties, args: &ArgMatches<'_>, json: bool) {
+    let what = match args.value_of("what").unwrap() {
+        "router" => whatami::ROUTER,
+        "peer" => whatami::PEER,
+        _ => whatami::PEER | whatami::ROUTER,
+    };
+    let timeout = Duration::from_secs_f64(parse_arg(args, "timeout", json));
+
+    let mut receiver = zenoh::net::scout(what, config.into())
+        .await
+        .unwrap_or_else(|e| fail(json, EXIT_ERROR, e));
+    let mut count = 0;
+    let scout = async {
+        while let Some(hello) = receiver.next().await {
+            if json {
+                println!(
+                    "{}",
+                    json!({
+                        "pid": hello.pid.as_ref().map(|pid| pid.to_string()),
+                        "whatami": hello.whatami.map(whatami::to_string),
+                        "locators": hello.locators.as_ref().map(|locators| locators
+                            .iter()
+                            .map(|l| l.to_string())
+                            .collect::<Vec<String>>()),
+                    })
+                );
+            } else {
+                println!("{}", hello);
+            }
+            count += 1;
+        }
+    };
+    scout.race(async_std::task::sleep(timeout)).await;
+    // stop scouting
+    drop(receiver);
+    if count == 0 {
+        std::process::exit(EXIT_NO_DATA);
+    }
+}
+
+async fn admin(config: Properties, args: &ArgMatches<'_>, json: bool) {
+    let zenoh = open(config, json).await;
+    let router = match args.value_of("router") {
+        Some(router) => router.to_string(),
+        None => zenoh.router_pid().await.unwrap_or_else(|| "*".to_string()),
+    };
+    let path = args.value_of("PATH").unwrap();
+    let selector = format!("/@/router/{}{}", router, path);
+    let count = get_selector(&zenoh, &selector, json).await;
+    zenoh
+        .close()
+        .await
+        .unwrap_or_else(|e| fail(json, EXIT_ERROR, e));
+    if count == 0 {
+        std::process::exit(EXIT_NO_DATA);
+    }
+}
+
+async fn bench(config: Properties, args: &ArgMatches<'_>, json: bool) {
+    let path = args.value_of("PATH").unwrap().to_string();
+    let size: usize = parse_arg(args, "size", json);
+    let count: usize = parse_arg(args, "count", json);
+    let payload: Vec<u8> = (0..size).map(|i| (i % 10) as u8).collect();
+
+    let zenoh = open(config, json).await;
+    let session = zenoh.session();
+    let reskey = ResKey::RId(
+        session
+            .declare_resource(&path.clone().into())
+            .await
+            .unwrap_or_else(|e| fail(json, EXIT_ERROR, e)),
+    );
+    let publisher = session
+        .declare_publisher(&reskey)
+        .await
+        .unwrap_or_else(|e| fail(json, EXIT_ERROR, e));
+
+    let start = Instant::now();
+    for _ in 0..count {
+        session
+            .write(&reskey, payload.clone().into())
+            .await
+            .unwrap_or_else(|e| fail(json, EXIT_ERROR, e));
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+    let msgs_per_sec = count as f64 / elapsed;
+    let bytes_per_sec = msgs_per_sec * size as f64;
+
+    if json {
+        println!(
+            "{}",
+            json!({
+                "key": path,
+                "size": size,
+                "count": count,
+                "duration_s": elapsed,
+                "msgs_per_sec": msgs_per_sec,
+                "bytes_per_sec": bytes_per_sec,
+            })
+        );
+    } else {
+        println!(
+            "{} publications of {} bytes on {} in {:.3}s: {:.0} msg/s, {:.0} bytes/s",
+            count, size, path, elapsed, msgs_per_sec, bytes_per_sec
+        );
+    }
+    publisher
+        .undeclare()
+        .await
+        .unwrap_or_else(|e| fail(json, EXIT_ERROR, e));
+    zenoh
+        .close()
+        .await
+        .unwrap_or_else(|e| fail(json, EXIT_ERROR, e));
+}
@@ -0,0 +1,135 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use async_std::sync::Arc;
+use clap::{App, Arg, ArgMatches};
+use std::str::FromStr;
+use std::time::Duration;
+use zenoh::net::*;
+use zenoh::Properties;
+use zenoh_ext::net::*;
+
+#[async_std::main]
+async fn main() {
+    // Initiate logging
+    env_logger::init();
+
+    let (src_config, dst_config, conf) = parse_args();
+
+    println!("Opening sessions...");
+    let src = Arc::new(open(src_config.into()).await.unwrap());
+    let dst = Arc::new(open(dst_config.into()).await.unwrap());
+
+    println!("Starting mirror {:?}", conf);
+    let mirror = Mirror::start(src, dst, &conf).await.unwrap();
+
+    loop {
+        async_std::task::sleep(Duration::from_secs(10)).await;
+        println!(
+            "Mirrored {} publications ({} suppressed loops)",
+            mirror.forwarded(),
+            mirror.suppressed()
+        );
+    }
+}
+
+fn session_config(args: &ArgMatches<'_>, side: &str) -> Properties {
+    let mut config = Properties::default();
+    for key in ["mode", "peer", "listener"].iter() {
+        if let Some(value) = args.values_of(format!("{}-{}", side, key)) {
+            config.insert(key.to_string(), value.collect::<Vec<&str>>().join(","));
+        }
+    }
+    if args.is_present("no-multicast-scouting") {
+        config.insert("multicast_scouting".to_string(), "false".to_string());
+    }
+    config
+}
+
+fn parse_args() -> (Properties, Properties, MirrorConf) {
+    let args = App::new("zenoh-net mirror example")
+        .arg(
+            Arg::from_usage("--src-mode=[MODE]  'The source session mode (peer by default).")
+                .possible_values(&["peer", "client"]),
+        )
+        .arg(Arg::from_usage(
+            "--src-peer=[LOCATOR]...   'Peer locators used to initiate the source session.'",
+        ))
+        .arg(Arg::from_usage(
+            "--src-listener=[LOCATOR]...   'Locators the source session listens on.'",
+        ))
+        .arg(
+            Arg::from_usage("--dst-mode=[MODE]  'The destination session mode (peer by default).")
+                .possible_values(&["peer", "client"]),
+        )
+        .arg(Arg::from_usage(
+            "--dst-peer=[LOCATOR]...   'Peer locators used to initiate the destination session.'",
+        ))
+        .arg(Arg::from_usage(
+            "--dst-listener=[LOCATOR]...   'Locators the destination session listens on.'",
+        ))
+        .arg(
+            Arg::from_usage("-s, --selector=[SELECTOR] 'The selection of resources to mirror'")
+                .default_value("/demo/example/**"),
+        )
+        .arg(Arg::from_usage(
+            "-r, --rewrite=[FROM=TO] 'Republish the resources starting with FROM on resources starting with TO'",
+        ))
+        .arg(Arg::from_usage(
+            "-f, --filter=[FILTER] 'Only mirror the publications whose payload matches FILTER (ex: \"x>1&y<2\")'",
+        ))
+        .arg(Arg::from_usage(
+            "-x, --exclude=[SELECTOR]... 'The selection of resources not to mirror'",
+        ))
+        .arg(Arg::from_usage(
+            "--best-effort 'Subscribe to the mirrored publications in best effort'",
+        ))
+        .arg(Arg::from_usage(
+            "--block 'Block the republications on congestion instead of dropping them'",
+        ))
+        .arg(Arg::from_usage(
+            "-b, --bidirectional 'Also mirror the rewritten resources from the destination to the source'",
+        ))
+        .arg(Arg::from_usage(
+            "--no-multicast-scouting 'Disable the multicast-based scouting mechanism.'",
+        ))
+        .get_matches();
+
+    let src_config = session_config(&args, "src");
+    let dst_config = session_config(&args, "dst");
+
+    let mut conf = MirrorConf::new(args.value_of("selector").unwrap());
+    if let Some(rewrite) = args.value_of("rewrite") {
+        match rewrite.split_once('=') {
+            Some((from, to)) => conf.rewrite(from, to),
+            None => panic!("Invalid --rewrite, expected FROM=TO: {}", rewrite),
+        };
+    }
+    if let Some(filter) = args.value_of("filter") {
+        conf.filter(PayloadFilter::from_str(filter).unwrap());
+    }
+    if let Some(excluded) = args.values_of("exclude") {
+        for expr in excluded {
+            conf.exclude(expr);
+        }
+    }
+    if args.is_present("best-effort") {
+        conf.reliability(Reliability::BestEffort);
+    }
+    if args.is_present("block") {
+        conf.congestion_control(CongestionControl::Block);
+    }
+    conf.bidirectional(args.is_present("bidirectional"));
+
+    (src_config, dst_config, conf)
+}
@@ -0,0 +1,91 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use clap::{App, Arg};
+use std::time::Duration;
+use zenoh::Properties;
+use zenoh_ext::net::*;
+
+#[async_std::main]
+async fn main() {
+    // Initiate logging
+    env_logger::init();
+
+    let (config, conf) = parse_args();
+
+    println!("Opening watched session {:?}...", conf);
+    let watchdog = Watchdog::open(config.into(), &conf, |event| match event {
+        WatchdogEvent::Degraded => println!("Data path degraded"),
+        WatchdogEvent::Restored => println!("Data path restored"),
+    })
+    .await
+    .unwrap();
+
+    loop {
+        async_std::task::sleep(Duration::from_secs(10)).await;
+        match watchdog.rtt() {
+            Some(rtt) if !watchdog.is_degraded() => println!("Round trip time: {:?}", rtt),
+            _ => println!("No route to the routers"),
+        }
+    }
+}
+
+fn parse_args() -> (Properties, WatchdogConf) {
+    let args = App::new("zenoh-net watchdog example")
+        .arg(
+            Arg::from_usage("-m, --mode=[MODE]  'The zenoh session mode (peer by default).")
+                .possible_values(&["peer", "client"]),
+        )
+        .arg(Arg::from_usage(
+            "-e, --peer=[LOCATOR]...  'Peer locators used to initiate the zenoh session.'",
+        ))
+        .arg(
+            Arg::from_usage("-p, --period=[SECONDS] 'The interval between two pings'")
+                .default_value("5"),
+        )
+        .arg(
+            Arg::from_usage("-t, --timeout=[SECONDS] 'The time after which a ping is failed'")
+                .default_value("2"),
+        )
+        .arg(
+            Arg::from_usage(
+                "--threshold=[NUMBER] 'The number of failed pings after which the data path is degraded'",
+            )
+            .default_value("2"),
+        )
+        .arg(Arg::from_usage(
+            "--no-multicast-scouting 'Disable the multicast-based scouting mechanism.'",
+        ))
+        .get_matches();
+
+    let mut config = Properties::default();
+    for key in ["mode", "peer"].iter() {
+        if let Some(value) = args.values_of(key) {
+            config.insert(key.to_string(), value.collect::<Vec<&str>>().join(","));
+        }
+    }
+    if args.is_present("no-multicast-scouting") {
+        config.insert("multicast_scouting".to_string(), "false".to_string());
+    }
+
+    let mut conf = WatchdogConf::default();
+    conf.period(Duration::from_secs_f64(
+        args.value_of("period").unwrap().parse().unwrap(),
+    ))
+    .timeout(Duration::from_secs_f64(
+        args.value_of("timeout").unwrap().parse().unwrap(),
+    ))
+    .threshold(args.value_of("threshold").unwrap().parse().unwrap());
+
+    (config, conf)
+}
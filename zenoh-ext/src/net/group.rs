@@ -9,8 +9,8 @@ use std::ops::Add;
 use std::time::{Duration, Instant};
 use zenoh::net::queryable::EVAL;
 use zenoh::net::{
-    CongestionControl, ConsolidationMode, QueryConsolidation, QueryTarget, Reliability, ResKey,
-    Sample, Session, SubInfo, SubMode,
+    CongestionControl, ConsolidationMode, History, QueryConsolidation, QueryTarget, Reliability,
+    ResKey, Sample, Session, SubInfo, SubMode,
 };
 use zenoh_util::sync::Condition;
 
@@ -24,14 +24,47 @@ pub struct JoinEvent {
     member: Member,
 }
 
+impl JoinEvent {
+    /// Returns the member joining the group.
+    pub fn member(&self) -> &Member {
+        &self.member
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct LeaseExpiredEvent {
     mid: String,
+    info: Option<String>,
+}
+
+impl LeaseExpiredEvent {
+    /// Returns the identifier of the member whose lease expired.
+    pub fn mid(&self) -> &str {
+        &self.mid
+    }
+
+    /// Returns the info the member joined the group with.
+    pub fn info(&self) -> Option<&str> {
+        self.info.as_deref()
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct LeaveEvent {
     mid: String,
+    info: Option<String>,
+}
+
+impl LeaveEvent {
+    /// Returns the identifier of the member leaving the group.
+    pub fn mid(&self) -> &str {
+        &self.mid
+    }
+
+    /// Returns the info the member joined the group with.
+    pub fn info(&self) -> Option<&str> {
+        self.info.as_deref()
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -91,6 +124,8 @@ impl Member {
             lease: DEFAULT_LEASE,
         }
     }
+    /// Sets the info attached to this member (ex: its version or its capabilities),
+    /// delivered with the join, leave and lease expiration events of this member.
     pub fn info(&mut self, i: &str) -> &mut Self {
         self.info = Some(String::from(i));
         self
@@ -104,6 +139,16 @@ impl Member {
         self.liveliness = l;
         self
     }
+
+    /// Returns the identifier of this member.
+    pub fn id(&self) -> &str {
+        &self.mid
+    }
+
+    /// Returns the info attached to this member.
+    pub fn get_info(&self) -> Option<&str> {
+        self.info.as_deref()
+    }
 }
 
 struct GroupState {
@@ -151,15 +196,20 @@ fn spawn_watchdog(s: Arc<GroupState>, period: Duration) -> JoinHandle<()> {
                 .map(|e| String::from(e.0))
                 .collect();
 
-            for e in &expired_members {
-                ms.remove(e);
-            }
+            let expired_members: Vec<Member> = expired_members
+                .iter()
+                .filter_map(|e| ms.remove(e))
+                .map(|e| e.0)
+                .collect();
             drop(ms);
             let u_evt = &*s.user_events_tx.lock().await;
-            for e in expired_members {
+            for m in expired_members {
                 if let Some(tx) = u_evt {
-                    tx.send(GroupEvent::LeaseExpired(LeaseExpiredEvent { mid: e }))
-                        .unwrap()
+                    tx.send(GroupEvent::LeaseExpired(LeaseExpiredEvent {
+                        mid: m.mid,
+                        info: m.info,
+                    }))
+                    .unwrap()
                 }
             }
         }
@@ -221,6 +271,8 @@ async fn advertise_view(z: &Arc<Session>, state: &Arc<GroupState>) {
 async fn net_event_handler(z: Arc<Session>, state: Arc<GroupState>) {
     let sub_info = SubInfo {
         period: None,
+        history: History::None,
+        filter: None,
         mode: SubMode::Push,
         reliability: Reliability::Reliable,
     };
@@ -246,9 +298,11 @@ async fn net_event_handler(z: Arc<Session>, state: Arc<GroupState>) {
                         tx.send(GroupEvent::Join(je)).unwrap()
                     }
                 }
-                GroupNetEvent::Leave(le) => {
+                GroupNetEvent::Leave(mut le) => {
                     log::debug!("Member leaving:\n{:?}", &le.mid);
-                    state.members.lock().await.remove(&le.mid);
+                    if let Some((m, _)) = state.members.lock().await.remove(&le.mid) {
+                        le.info = le.info.or(m.info);
+                    }
                     let u_evt = &*state.user_events_tx.lock().await;
                     if let Some(tx) = u_evt {
                         tx.send(GroupEvent::Leave(le)).unwrap()
@@ -0,0 +1,320 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+
+//! A helper mirroring the publications on a path expression from a session to another,
+//! to bridge two isolated deployments.
+use async_std::sync::Arc;
+use async_std::task::JoinHandle;
+use futures::prelude::*;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use zenoh::net::utils::resource_name;
+use zenoh::net::*;
+use zenoh_util::core::{ZError, ZErrorKind, ZResult};
+use zenoh_util::{zerror, zlock};
+
+// The number of forwarded publications remembered to recognize them when they come back.
+const FORWARDED_CAPACITY: usize = 1024;
+
+/// The configuration of a [`Mirror`].
+#[derive(Clone, Debug)]
+pub struct MirrorConf {
+    path_expr: String,
+    rewrite: Option<(String, String)>,
+    reliability: Reliability,
+    congestion_control: CongestionControl,
+    filter: Option<PayloadFilter>,
+    excluded: Vec<String>,
+    bidirectional: bool,
+}
+
+impl MirrorConf {
+    /// Mirrors the publications on `path_expr`.
+    pub fn new(path_expr: &str) -> MirrorConf {
+        MirrorConf {
+            path_expr: path_expr.to_string(),
+            rewrite: None,
+            reliability: Reliability::Reliable,
+            congestion_control: CongestionControl::Drop,
+            filter: None,
+            excluded: vec![],
+            bidirectional: false,
+        }
+    }
+
+    /// Republishes the publications on the paths starting with `from` on the paths starting with
+    /// `to` instead. The publications on the other paths are republished on the same paths.
+    pub fn rewrite(&mut self, from: &str, to: &str) -> &mut Self {
+        self.rewrite = Some((from.to_string(), to.to_string()));
+        self
+    }
+
+    /// Sets the reliability of the subscription to the mirrored publications.
+    pub fn reliability(&mut self, reliability: Reliability) -> &mut Self {
+        self.reliability = reliability;
+        self
+    }
+
+    /// Sets the congestion control of the republications.
+    pub fn congestion_control(&mut self, congestion_control: CongestionControl) -> &mut Self {
+        self.congestion_control = congestion_control;
+        self
+    }
+
+    /// Only mirrors the publications whose payload matches `filter`.
+    /// The filter is applied by the routers before sending the publications to the mirror.
+    pub fn filter(&mut self, filter: PayloadFilter) -> &mut Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Doesn't mirror the publications on the paths matching `path_expr`,
+    /// in both directions of a bidirectional mirror.
+    pub fn exclude(&mut self, path_expr: &str) -> &mut Self {
+        self.excluded.push(path_expr.to_string());
+        self
+    }
+
+    /// Also mirrors the publications on the rewritten path expression from the destination
+    /// session to the source session.
+    pub fn bidirectional(&mut self, bidirectional: bool) -> &mut Self {
+        self.bidirectional = bidirectional;
+        self
+    }
+
+    fn rewrite_path(&self, path: &str, reverse: bool) -> String {
+        match &self.rewrite {
+            Some((from, to)) => {
+                let (from, to) = if reverse { (to, from) } else { (from, to) };
+                match path.strip_prefix(from.as_str()) {
+                    Some(suffix) => format!("{}{}", to, suffix),
+                    None => path.to_string(),
+                }
+            }
+            None => path.to_string(),
+        }
+    }
+}
+
+// The publications forwarded by a mirror, to suppress them when they are received again
+// by the mirror, either because the source and destination sessions are connected or
+// because they are mirrored back in a bidirectional mirror.
+#[derive(Default)]
+struct Forwarded {
+    counts: HashMap<(String, u64), usize>,
+    order: VecDeque<(String, u64)>,
+}
+
+impl Forwarded {
+    fn insert(&mut self, key: (String, u64)) {
+        if self.order.len() >= FORWARDED_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.remove(&oldest);
+            }
+        }
+        *self.counts.entry(key.clone()).or_insert(0) += 1;
+        self.order.push_back(key);
+    }
+
+    // Returns true if `key` was forwarded, forgetting it.
+    fn take(&mut self, key: &(String, u64)) -> bool {
+        if self.remove(key) {
+            if let Some(pos) = self.order.iter().position(|k| k == key) {
+                self.order.remove(pos);
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    fn remove(&mut self, key: &(String, u64)) -> bool {
+        match self.counts.get_mut(key) {
+            Some(count) => {
+                *count -= 1;
+                if *count == 0 {
+                    self.counts.remove(key);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+struct MirrorState {
+    conf: MirrorConf,
+    forwarded: Mutex<Forwarded>,
+    nb_forwarded: AtomicU64,
+    nb_suppressed: AtomicU64,
+}
+
+/// A mirror republishing on a destination session the publications received on a source session,
+/// with an optional path rewrite, to bridge two isolated deployments.
+///
+/// The publications republished by the mirror are recognized and not mirrored again when they
+/// are received back by the mirror, to avoid the loops when the deployments are connected or when
+/// the mirror is bidirectional. Two distinct mirrors in opposite directions don't recognize
+/// each other's publications: use a single bidirectional mirror instead.
+///
+/// The mirror stops when it's dropped.
+pub struct Mirror {
+    state: Arc<MirrorState>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl Mirror {
+    /// Starts mirroring the publications of `src` on `dst` as configured in `conf`.
+    pub async fn start(src: Arc<Session>, dst: Arc<Session>, conf: &MirrorConf) -> ZResult<Mirror> {
+        let state = Arc::new(MirrorState {
+            conf: conf.clone(),
+            forwarded: Mutex::new(Forwarded::default()),
+            nb_forwarded: AtomicU64::new(0),
+            nb_suppressed: AtomicU64::new(0),
+        });
+        let mut tasks = vec![];
+        tasks.push(spawn_forwarder(src.clone(), dst.clone(), state.clone(), false).await?);
+        if conf.bidirectional {
+            match spawn_forwarder(dst, src, state.clone(), true).await {
+                Ok(task) => tasks.push(task),
+                Err(e) => {
+                    for task in tasks.drain(..) {
+                        task.cancel().await;
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        Ok(Mirror { state, tasks })
+    }
+
+    /// Returns the number of publications republished by this mirror.
+    pub fn forwarded(&self) -> u64 {
+        self.state.nb_forwarded.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of publications not republished because they were
+    /// published by this mirror.
+    pub fn suppressed(&self) -> u64 {
+        self.state.nb_suppressed.load(Ordering::Relaxed)
+    }
+
+    /// Stops this mirror, waiting for its subscriptions to be undeclared.
+    pub async fn stop(mut self) {
+        for task in self.tasks.drain(..) {
+            task.cancel().await;
+        }
+    }
+}
+
+impl Drop for Mirror {
+    fn drop(&mut self) {
+        for task in self.tasks.drain(..) {
+            async_std::task::spawn(task.cancel());
+        }
+    }
+}
+
+fn payload_hash(payload: &ZBuf) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    payload.to_vec().hash(&mut hasher);
+    hasher.finish()
+}
+
+// Spawns the task mirroring the publications of `src` on `dst`, once subscribed.
+async fn spawn_forwarder(
+    src: Arc<Session>,
+    dst: Arc<Session>,
+    state: Arc<MirrorState>,
+    reverse: bool,
+) -> ZResult<JoinHandle<()>> {
+    let path_expr = if reverse {
+        state.conf.rewrite_path(&state.conf.path_expr, false)
+    } else {
+        state.conf.path_expr.clone()
+    };
+    let (ready_tx, ready_rx) = flume::bounded(1);
+    let task = async_std::task::spawn(async move {
+        let sub_info = SubInfo {
+            reliability: state.conf.reliability,
+            mode: SubMode::Push,
+            period: None,
+            history: History::None,
+            filter: state.conf.filter.clone(),
+        };
+        let mut subscriber = match src
+            .declare_subscriber(&path_expr.clone().into(), &sub_info)
+            .await
+        {
+            Ok(subscriber) => {
+                let _ = ready_tx.send(Ok(()));
+                subscriber
+            }
+            Err(e) => {
+                let _ = ready_tx.send(Err(e));
+                return;
+            }
+        };
+        log::debug!("Mirroring publications on {}", path_expr);
+        while let Some(sample) = subscriber.receiver().next().await {
+            let hash = payload_hash(&sample.payload);
+            if zlock!(state.forwarded).take(&(sample.res_name.clone(), hash)) {
+                log::trace!("Suppress mirrored publication on {}", sample.res_name);
+                state.nb_suppressed.fetch_add(1, Ordering::Relaxed);
+                continue;
+            }
+            let path = state.conf.rewrite_path(&sample.res_name, reverse);
+            // the excluded path expressions apply to the paths of the source session
+            let src_path = if reverse { &path } else { &sample.res_name };
+            if state
+                .conf
+                .excluded
+                .iter()
+                .any(|expr| resource_name::intersect(expr, src_path))
+            {
+                continue;
+            }
+
+            zlock!(state.forwarded).insert((path.clone(), hash));
+            log::trace!("Mirror publication on {} to {}", sample.res_name, path);
+            match dst
+                .forward(
+                    &path.clone().into(),
+                    sample.payload,
+                    sample.data_info,
+                    state.conf.congestion_control,
+                )
+                .await
+            {
+                Ok(()) => {
+                    state.nb_forwarded.fetch_add(1, Ordering::Relaxed);
+                }
+                Err(e) => {
+                    zlock!(state.forwarded).take(&(path.clone(), hash));
+                    log::warn!("Failed to mirror publication on {}: {}", path, e);
+                }
+            }
+        }
+    });
+    match ready_rx.recv_async().await {
+        Ok(Ok(())) => Ok(task),
+        Ok(Err(e)) => Err(e),
+        Err(_) => zerror!(ZErrorKind::Other {
+            descr: "Mirror task stopped before subscribing".to_string()
+        }),
+    }
+}
@@ -12,7 +12,13 @@
 //   ADLINK zenoh team, <zenoh@adlink-labs.tech>
 //
 pub mod group;
+pub mod mirror;
 pub mod querying_subscriber;
 pub mod session_ext;
+pub mod watchdog;
+pub mod window;
+pub use mirror::{Mirror, MirrorConf};
 pub use querying_subscriber::{QueryingSubscriber, QueryingSubscriberBuilder};
 pub use session_ext::SessionExt;
+pub use watchdog::{Watchdog, WatchdogConf, WatchdogEvent};
+pub use window::{Window, WindowExt, WindowStream};
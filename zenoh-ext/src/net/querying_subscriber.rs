@@ -48,6 +48,8 @@ impl QueryingSubscriberBuilder<'_> {
             reliability: Reliability::Reliable,
             mode: SubMode::Push,
             period: None,
+            history: History::None,
+            filter: None,
         };
         QueryingSubscriberBuilder {
             session,
@@ -85,6 +87,14 @@ impl QueryingSubscriberBuilder<'_> {
         self.info.period = period;
         self
     }
+
+    /// Change the publications received at the subscription.
+    /// With [`History::Latest`], the latest values are sent by the router's latest value cache
+    /// through the subscription, instead of being queried (see [`QueryingSubscriber::query`]).
+    pub fn history(mut self, history: History) -> Self {
+        self.info.history = history;
+        self
+    }
     /// Change the resource key to be used for queries.
     pub fn query_reskey(mut self, query_reskey: ResKey) -> Self {
         self.query_reskey = query_reskey;
@@ -146,8 +156,10 @@ impl QueryingSubscriber<'_> {
             receiver,
         };
 
-        // start query
-        query_subscriber.query().wait()?;
+        // start query, unless the router sends the latest values through the subscription
+        if query_subscriber.conf.info.history == History::None {
+            query_subscriber.query().wait()?;
+        }
 
         Ok(query_subscriber)
     }
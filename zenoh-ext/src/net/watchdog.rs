@@ -0,0 +1,263 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+
+//! A watchdog verifying that the data path through the routers works end to end,
+//! and notifying the application when it degrades and when it's restored.
+use async_std::prelude::*;
+use async_std::sync::Arc;
+use async_std::task::JoinHandle;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use zenoh::net::queryable::EVAL;
+use zenoh::net::*;
+use zenoh_util::core::{ZError, ZErrorKind, ZResult};
+use zenoh_util::properties::config::{ZN_LISTENER_KEY, ZN_MODE_KEY};
+use zenoh_util::zerror;
+
+/// The configuration of a [`Watchdog`].
+#[derive(Clone, Debug)]
+pub struct WatchdogConf {
+    period: Duration,
+    timeout: Duration,
+    threshold: usize,
+}
+
+impl Default for WatchdogConf {
+    fn default() -> Self {
+        WatchdogConf {
+            period: Duration::from_secs(5),
+            timeout: Duration::from_secs(2),
+            threshold: 2,
+        }
+    }
+}
+
+impl WatchdogConf {
+    /// Sets the interval between two pings (5 seconds by default).
+    pub fn period(&mut self, period: Duration) -> &mut Self {
+        self.period = period;
+        self
+    }
+
+    /// Sets the time after which a ping without reply is failed (2 seconds by default).
+    pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets the number of consecutive failed pings after which the data path is
+    /// considered degraded (2 by default).
+    pub fn threshold(&mut self, threshold: usize) -> &mut Self {
+        self.threshold = threshold.max(1);
+        self
+    }
+}
+
+/// The changes of the health of the data path notified by a [`Watchdog`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WatchdogEvent {
+    /// The data path is degraded: the pings failed `threshold` times in a row.
+    Degraded,
+    /// The data path is restored: a ping succeeded again after a degradation.
+    Restored,
+}
+
+struct WatchdogState {
+    degraded: AtomicBool,
+    // The round trip time of the last successful ping in microseconds, 0 if none
+    rtt: AtomicU64,
+}
+
+/// A session wrapper verifying that the data path of the session through its routers works
+/// end to end: a session being connected doesn't guarantee that its data is routed.
+///
+/// Next to the watched session, the watchdog opens a probe session in client mode with the
+/// same configuration, whose queryable on `/@watchdog/<pid of the watched session>` is queried
+/// periodically by the watched session. The queries and the replies are then routed through
+/// the routers. The watchdog fires a [`WatchdogEvent::Degraded`] event once the pings failed
+/// `threshold` times in a row, and a [`WatchdogEvent::Restored`] event once a ping succeeds
+/// again. The data path is initially assumed to be healthy.
+///
+/// The watchdog stops when it's dropped.
+///
+/// # Examples
+/// ```no_run
+/// # async_std::task::block_on(async {
+/// use zenoh::net::*;
+/// use zenoh_ext::net::*;
+///
+/// let watchdog = Watchdog::open(config::client(None), &WatchdogConf::default(), |event| {
+///     println!("The data path is {:?}", event)
+/// })
+/// .await
+/// .unwrap();
+/// let session = watchdog.session();
+/// # })
+/// ```
+pub struct Watchdog {
+    session: Arc<Session>,
+    state: Arc<WatchdogState>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl Watchdog {
+    /// Opens a session with `config` watched as configured in `conf`,
+    /// calling `callback` on each change of the health of its data path.
+    pub async fn open<F>(
+        config: ConfigProperties,
+        conf: &WatchdogConf,
+        callback: F,
+    ) -> ZResult<Watchdog>
+    where
+        F: Fn(WatchdogEvent) + Send + Sync + 'static,
+    {
+        let mut probe_config = config.clone();
+        probe_config.insert(ZN_MODE_KEY, "client".to_string());
+        probe_config.remove(&ZN_LISTENER_KEY);
+
+        let session = Arc::new(open(config).await?);
+        let probe = open(probe_config).await?;
+        let path = format!("/@watchdog/{}", session.id().await);
+        let mut tasks = vec![spawn_probe(probe, path.clone()).await?];
+
+        let state = Arc::new(WatchdogState {
+            degraded: AtomicBool::new(false),
+            rtt: AtomicU64::new(0),
+        });
+        let c_session = session.clone();
+        let c_state = state.clone();
+        let conf = conf.clone();
+        tasks.push(async_std::task::spawn(async move {
+            let mut failures = 0;
+            loop {
+                match ping(&c_session, &path, conf.timeout).await {
+                    Some(rtt) => {
+                        failures = 0;
+                        c_state
+                            .rtt
+                            .store(rtt.as_micros().max(1) as u64, Ordering::Relaxed);
+                        if c_state.degraded.swap(false, Ordering::AcqRel) {
+                            log::info!("Data path restored (round trip time: {:?})", rtt);
+                            callback(WatchdogEvent::Restored);
+                        }
+                    }
+                    None => {
+                        failures += 1;
+                        if failures >= conf.threshold
+                            && !c_state.degraded.swap(true, Ordering::AcqRel)
+                        {
+                            log::warn!("Data path degraded: {} pings failed", failures);
+                            callback(WatchdogEvent::Degraded);
+                        }
+                    }
+                }
+                async_std::task::sleep(conf.period).await;
+            }
+        }));
+
+        Ok(Watchdog {
+            session,
+            state,
+            tasks,
+        })
+    }
+
+    /// Returns the watched session.
+    pub fn session(&self) -> &Arc<Session> {
+        &self.session
+    }
+
+    /// Returns true if the data path is currently degraded.
+    pub fn is_degraded(&self) -> bool {
+        self.state.degraded.load(Ordering::Acquire)
+    }
+
+    /// Returns the round trip time of the last successful ping, if any.
+    pub fn rtt(&self) -> Option<Duration> {
+        match self.state.rtt.load(Ordering::Relaxed) {
+            0 => None,
+            rtt => Some(Duration::from_micros(rtt)),
+        }
+    }
+
+    /// Stops this watchdog, closing its probe session.
+    pub async fn stop(mut self) {
+        for task in self.tasks.drain(..) {
+            task.cancel().await;
+        }
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        for task in self.tasks.drain(..) {
+            async_std::task::spawn(task.cancel());
+        }
+    }
+}
+
+// Spawns the task replying to the pings on `path` with the probe session, once declared.
+async fn spawn_probe(probe: Session, path: String) -> ZResult<JoinHandle<()>> {
+    let (ready_tx, ready_rx) = flume::bounded(1);
+    let task = async_std::task::spawn(async move {
+        let mut queryable = match probe.declare_queryable(&path.clone().into(), EVAL).await {
+            Ok(queryable) => {
+                let _ = ready_tx.send(Ok(()));
+                queryable
+            }
+            Err(e) => {
+                let _ = ready_tx.send(Err(e));
+                return;
+            }
+        };
+        while let Some(query) = queryable.receiver().next().await {
+            query.reply(Sample {
+                res_name: path.clone(),
+                payload: ZBuf::new(),
+                data_info: None,
+            });
+        }
+    });
+    match ready_rx.recv_async().await {
+        Ok(Ok(())) => Ok(task),
+        Ok(Err(e)) => Err(e),
+        Err(_) => zerror!(ZErrorKind::Other {
+            descr: "Watchdog probe stopped before declaring its queryable".to_string()
+        }),
+    }
+}
+
+// Queries the probe on `path`, returning the round trip time if it replied within `timeout`.
+async fn ping(session: &Session, path: &str, timeout: Duration) -> Option<Duration> {
+    let start = Instant::now();
+    let reply = async {
+        let mut replies = session
+            .query(
+                &path.into(),
+                "",
+                QueryTarget::default(),
+                QueryConsolidation::none(),
+            )
+            .await
+            .ok()?;
+        replies.next().await
+    };
+    match reply.timeout(timeout).await {
+        Ok(Some(_)) => Some(start.elapsed()),
+        _ => {
+            log::debug!("Ping on {} failed", path);
+            None
+        }
+    }
+}
@@ -0,0 +1,227 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use async_std::pin::Pin;
+use async_std::task::{Context, Poll};
+use futures_lite::stream::Stream;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, SystemTime};
+use zenoh::net::*;
+
+/// Windowing operators over a stream of [`Sample`]s, such as the receiver of a
+/// [`Subscriber`] or of a [`QueryingSubscriber`](super::QueryingSubscriber).
+///
+/// The payloads of the samples are expected to be numbers encoded as strings (as the
+/// `zenoh::Value::Float` and `zenoh::Value::Integer` values are). The samples with other
+/// payloads are ignored.
+///
+/// # Examples
+/// ```
+/// # async_std::task::block_on(async {
+/// use futures::prelude::*;
+/// use std::time::Duration;
+/// use zenoh::net::*;
+/// use zenoh_ext::net::WindowExt;
+///
+/// let samples = [1.0, 2.0, 6.0].iter().map(|v| Sample {
+///     res_name: "/demo/temperature".to_string(),
+///     payload: v.to_string().as_bytes().into(),
+///     data_info: None,
+/// });
+/// let means: Vec<String> = stream::iter(samples)
+///     .window(Duration::from_secs(10))
+///     .mean()
+///     .map(|sample| String::from_utf8(sample.payload.to_vec()).unwrap())
+///     .collect()
+///     .await;
+/// assert_eq!(means, vec!["1", "1.5", "3"]);
+/// # })
+/// ```
+///
+/// The derived samples can be republished, for instance on another resource:
+/// ```no_run
+/// # async_std::task::block_on(async {
+/// use futures::prelude::*;
+/// use std::time::Duration;
+/// use zenoh::net::*;
+/// use zenoh_ext::net::WindowExt;
+///
+/// let session = open(config::peer()).await.unwrap();
+/// let sub_info = SubInfo::default();
+/// let mut subscriber = session
+///     .declare_subscriber(&"/demo/counter".into(), &sub_info)
+///     .await
+///     .unwrap();
+/// let mut rates = subscriber.receiver().window(Duration::from_secs(60)).rate();
+/// while let Some(sample) = rates.next().await {
+///     session
+///         .write(&"/demo/counter/rate".into(), sample.payload)
+///         .await
+///         .unwrap();
+/// }
+/// # })
+/// ```
+pub trait WindowExt: Stream<Item = Sample> + Unpin + Sized {
+    /// Keeps the values received for each resource during the last `duration`,
+    /// according to the timestamps of the samples (or their reception time if they have none).
+    fn window(self, duration: Duration) -> Window<Self> {
+        Window {
+            stream: self,
+            duration,
+            values: HashMap::new(),
+        }
+    }
+}
+
+impl<S: Stream<Item = Sample> + Unpin> WindowExt for S {}
+
+/// A sliding time window over a stream of [`Sample`]s, for each resource.
+/// See [`WindowExt`].
+pub struct Window<S> {
+    stream: S,
+    duration: Duration,
+    values: HashMap<String, VecDeque<(SystemTime, f64)>>,
+}
+
+impl<S: Stream<Item = Sample> + Unpin> Window<S> {
+    /// For each sample, produces the mean of the values in the window of its resource.
+    pub fn mean(self) -> WindowStream<S> {
+        WindowStream {
+            window: self,
+            operator: Operator::Mean,
+        }
+    }
+
+    /// For each sample, produces the rate of change per second of the values in the window
+    /// of its resource, i.e. the difference between the last and the first values divided by
+    /// the time elapsed between them. Produces nothing until the window holds 2 values.
+    pub fn rate(self) -> WindowStream<S> {
+        WindowStream {
+            window: self,
+            operator: Operator::Rate,
+        }
+    }
+
+    /// For each sample, produces the difference between the last and the first values in the
+    /// window of its resource. Produces nothing until the window holds 2 values.
+    pub fn delta(self) -> WindowStream<S> {
+        WindowStream {
+            window: self,
+            operator: Operator::Delta,
+        }
+    }
+
+    // Adds the value of a sample to the window of its resource, and returns this window.
+    fn push(&mut self, sample: &Sample) -> Option<&VecDeque<(SystemTime, f64)>> {
+        let value = match String::from_utf8(sample.payload.to_vec())
+            .ok()
+            .and_then(|s| s.trim().parse::<f64>().ok())
+        {
+            Some(value) => value,
+            None => {
+                log::debug!("Ignore non-numeric sample for {}", sample.res_name);
+                return None;
+            }
+        };
+        let time = sample
+            .get_timestamp()
+            .map(|ts| ts.get_time().to_system_time())
+            .unwrap_or_else(SystemTime::now);
+
+        let values = self.values.entry(sample.res_name.clone()).or_default();
+        // keep the values sorted by time, even if samples arrive out of order
+        let pos = values
+            .iter()
+            .rposition(|(t, _)| *t <= time)
+            .map_or(0, |p| p + 1);
+        values.insert(pos, (time, value));
+        let last = values.back().unwrap().0;
+        while let Some((t, _)) = values.front() {
+            match last.duration_since(*t) {
+                Ok(age) if age > self.duration => {
+                    values.pop_front();
+                }
+                _ => break,
+            }
+        }
+        Some(values)
+    }
+}
+
+enum Operator {
+    Mean,
+    Rate,
+    Delta,
+}
+
+impl Operator {
+    fn apply(&self, values: &VecDeque<(SystemTime, f64)>) -> Option<f64> {
+        let (first_time, first) = *values.front()?;
+        let (last_time, last) = *values.back()?;
+        match self {
+            Operator::Mean => {
+                Some(values.iter().map(|(_, v)| v).sum::<f64>() / values.len() as f64)
+            }
+            Operator::Delta if values.len() > 1 => Some(last - first),
+            Operator::Rate if values.len() > 1 => {
+                let elapsed = last_time.duration_since(first_time).ok()?.as_secs_f64();
+                if elapsed > 0.0 {
+                    Some((last - first) / elapsed)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The stream of the [`Sample`]s derived from a [`Window`] by one of its operators.
+///
+/// Each derived sample has the resource name and the timestamp (if any) of the sample that
+/// produced it, and the result of the operator as a float payload.
+pub struct WindowStream<S> {
+    window: Window<S>,
+    operator: Operator,
+}
+
+impl<S: Stream<Item = Sample> + Unpin> Stream for WindowStream<S> {
+    type Item = Sample;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let mself = self.get_mut();
+        loop {
+            let sample = match Pin::new(&mut mself.window.stream).poll_next(cx) {
+                Poll::Ready(Some(sample)) => sample,
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            };
+            let result = match mself.window.push(&sample) {
+                Some(values) => mself.operator.apply(values),
+                None => None,
+            };
+            if let Some(result) = result {
+                let data_info = DataInfo {
+                    encoding: Some(encoding::APP_FLOAT),
+                    timestamp: sample.get_timestamp().cloned(),
+                    ..Default::default()
+                };
+                return Poll::Ready(Some(Sample {
+                    res_name: sample.res_name,
+                    payload: result.to_string().as_bytes().into(),
+                    data_info: Some(data_info),
+                }));
+            }
+        }
+    }
+}
@@ -0,0 +1,242 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+//! Simulated zenoh networks for integration tests.
+//!
+//! A [`TestNetwork`] runs several zenoh runtimes (routers, peers and clients) in the current
+//! process, connected via in-memory links rather than sockets. The conditions of the links
+//! between two nodes (latency, loss, partition) can be changed while the test runs.
+//!
+//! # Examples
+//! ```
+//! # async_std::task::block_on(async {
+//! use std::time::Duration;
+//! use zenoh_testkit::{LinkConditions, TestNetwork};
+//!
+//! let mut net = TestNetwork::new();
+//! net.add_router("r1", &[]).await.unwrap();
+//! net.add_peer("p1", &["r1"]).await.unwrap();
+//! net.add_client("c1", "r1").await.unwrap();
+//!
+//! // slow down the link between p1 and r1
+//! net.set_link(
+//!     "p1",
+//!     "r1",
+//!     LinkConditions { latency: Duration::from_millis(50), ..Default::default() },
+//! );
+//! // isolate c1 from the rest of the network, then restore it
+//! net.partition(&["c1"], &["r1", "p1"]);
+//! net.heal();
+//!
+//! let session = net.node("c1").session();
+//! net.close().await.unwrap();
+//! # })
+//! ```
+use async_std::task;
+use uuid::Uuid;
+use zenoh::net::protocol::core::{whatami, PeerId};
+use zenoh::net::protocol::link::mem::{clear_link_conditions, set_link_conditions};
+use zenoh::net::protocol::link::Locator;
+use zenoh::net::runtime::Runtime;
+use zenoh::net::Session;
+use zenoh_util::core::ZResult;
+use zenoh_util::properties::config::*;
+
+pub use zenoh::net::protocol::link::mem::LinkConditionsMem as LinkConditions;
+
+/// A zenoh runtime of a [`TestNetwork`], with a session opened on it.
+pub struct TestNode {
+    name: String,
+    whatami: whatami::Type,
+    listener: Option<Locator>,
+    runtime: Runtime,
+    session: Session,
+}
+
+impl TestNode {
+    /// The name of this node in its network.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The kind of node ([`whatami::ROUTER`], [`whatami::PEER`] or [`whatami::CLIENT`]).
+    pub fn whatami(&self) -> whatami::Type {
+        self.whatami
+    }
+
+    /// The identifier of this node's runtime.
+    pub fn pid(&self) -> &PeerId {
+        &self.runtime.pid
+    }
+
+    /// The runtime of this node, for starting plugins on it.
+    pub fn runtime(&self) -> &Runtime {
+        &self.runtime
+    }
+
+    /// The session opened on this node's runtime.
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+}
+
+/// A set of zenoh runtimes running in the current process and connected via in-memory links.
+///
+/// The nodes are named by the test. The listeners are prefixed with a random network identifier,
+/// so several networks can run concurrently in the same process with the same node names.
+pub struct TestNetwork {
+    id: String,
+    nodes: Vec<TestNode>,
+    closed: bool,
+}
+
+impl TestNetwork {
+    pub fn new() -> TestNetwork {
+        TestNetwork {
+            id: Uuid::new_v4().to_simple().to_string(),
+            nodes: vec![],
+            closed: false,
+        }
+    }
+
+    fn locator(&self, name: &str) -> String {
+        format!("mem/{}-{}", self.id, name)
+    }
+
+    /// Adds a router, connected to the already added routers named in `connect`.
+    pub async fn add_router(&mut self, name: &str, connect: &[&str]) -> ZResult<&TestNode> {
+        self.add_node(name, whatami::ROUTER, connect).await
+    }
+
+    /// Adds a peer, connected to the already added routers or peers named in `connect`.
+    pub async fn add_peer(&mut self, name: &str, connect: &[&str]) -> ZResult<&TestNode> {
+        self.add_node(name, whatami::PEER, connect).await
+    }
+
+    /// Adds a client, connected to the already added router (or peer) named `router`.
+    pub async fn add_client(&mut self, name: &str, router: &str) -> ZResult<&TestNode> {
+        self.add_node(name, whatami::CLIENT, &[router]).await
+    }
+
+    async fn add_node(
+        &mut self,
+        name: &str,
+        whatami: whatami::Type,
+        connect: &[&str],
+    ) -> ZResult<&TestNode> {
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, whatami::to_string(whatami).to_lowercase());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        let listener = if whatami == whatami::CLIENT {
+            None
+        } else {
+            let locator = self.locator(name);
+            config.insert(ZN_LISTENER_KEY, locator.clone());
+            Some(locator.parse()?)
+        };
+        if !connect.is_empty() {
+            let peers: Vec<String> = connect.iter().map(|n| self.locator(n)).collect();
+            config.insert(ZN_PEER_KEY, peers.join(","));
+        }
+
+        log::debug!("Add node {} to test network {}", name, self.id);
+        let runtime = Runtime::new(0, config, None).await?;
+        let session = Session::init(runtime.clone(), true, vec![], vec![]).await;
+        self.nodes.push(TestNode {
+            name: name.to_string(),
+            whatami,
+            listener,
+            runtime,
+            session,
+        });
+        Ok(self.nodes.last().unwrap())
+    }
+
+    /// Returns the node named `name`.
+    ///
+    /// # Panics
+    /// If there is no such node in this network.
+    pub fn node(&self, name: &str) -> &TestNode {
+        self.nodes
+            .iter()
+            .find(|node| node.name == name)
+            .unwrap_or_else(|| panic!("No node {} in test network", name))
+    }
+
+    /// Returns all the nodes of this network.
+    pub fn nodes(&self) -> &[TestNode] {
+        &self.nodes
+    }
+
+    /// Sets the conditions of the links between the nodes `a` and `b`, in both directions.
+    pub fn set_link(&self, a: &str, b: &str, conditions: LinkConditions) {
+        set_link_conditions(self.node(a).pid(), self.node(b).pid(), conditions);
+    }
+
+    /// Drops all the messages exchanged between any node of `a` and any node of `b`.
+    /// The sessions between them close once their lease expires.
+    pub fn partition(&self, a: &[&str], b: &[&str]) {
+        for x in a {
+            for y in b {
+                self.set_link(
+                    x,
+                    y,
+                    LinkConditions {
+                        partitioned: true,
+                        ..Default::default()
+                    },
+                );
+            }
+        }
+    }
+
+    /// Restores perfect links between all the nodes of this network.
+    pub fn heal(&self) {
+        for x in &self.nodes {
+            for y in &self.nodes {
+                clear_link_conditions(x.pid(), y.pid());
+            }
+        }
+    }
+
+    /// Closes all the nodes of this network.
+    pub async fn close(mut self) -> ZResult<()> {
+        self.close_nodes().await
+    }
+
+    async fn close_nodes(&mut self) -> ZResult<()> {
+        self.closed = true;
+        self.heal();
+        for node in self.nodes.drain(..).rev() {
+            if let Some(listener) = &node.listener {
+                let _ = node.runtime.manager().del_listener(listener).await;
+            }
+            node.session.close().await?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for TestNetwork {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for TestNetwork {
+    fn drop(&mut self) {
+        if !self.closed {
+            let _ = task::block_on(self.close_nodes());
+        }
+    }
+}
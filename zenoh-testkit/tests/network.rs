@@ -0,0 +1,122 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use async_std::prelude::*;
+use async_std::task;
+use std::time::{Duration, Instant};
+use zenoh::net::*;
+use zenoh_testkit::{LinkConditions, TestNetwork};
+
+const TIMEOUT: Duration = Duration::from_secs(60);
+const SLEEP: Duration = Duration::from_millis(500);
+const RECV_TIMEOUT: Duration = Duration::from_secs(2);
+
+async fn subscribe<'a>(net: &'a TestNetwork, node: &str, key: &str) -> Subscriber<'a> {
+    let sub_info = SubInfo {
+        reliability: Reliability::Reliable,
+        mode: SubMode::Push,
+        period: None,
+        history: History::None,
+        filter: None,
+    };
+    net.node(node)
+        .session()
+        .declare_subscriber(&key.into(), &sub_info)
+        .await
+        .unwrap()
+}
+
+async fn publish(net: &TestNetwork, node: &str, key: &str, value: &[u8]) {
+    net.node(node)
+        .session()
+        .write(&key.into(), value.into())
+        .await
+        .unwrap();
+}
+
+async fn recv(sub: &mut Subscriber<'_>) -> Option<Sample> {
+    sub.receiver().next().timeout(RECV_TIMEOUT).await.ok()?
+}
+
+#[test]
+fn testkit_routed_pubsub() {
+    task::block_on(async {
+        let mut net = TestNetwork::new();
+        net.add_router("r1", &[]).await.unwrap();
+        net.add_router("r2", &["r1"]).await.unwrap();
+        net.add_client("c1", "r1").await.unwrap();
+        net.add_client("c2", "r2").await.unwrap();
+
+        let mut sub = subscribe(&net, "c2", "/test/routed").await;
+        task::sleep(SLEEP).await;
+        publish(&net, "c1", "/test/routed", b"hello").await;
+        let sample = recv(&mut sub).await.unwrap();
+        assert_eq!(sample.res_name, "/test/routed");
+        assert_eq!(sample.payload.to_vec(), b"hello".to_vec());
+
+        drop(sub);
+        net.close().timeout(TIMEOUT).await.unwrap().unwrap();
+    });
+}
+
+#[test]
+fn testkit_latency() {
+    task::block_on(async {
+        let latency = Duration::from_millis(300);
+        let mut net = TestNetwork::new();
+        net.add_peer("p1", &[]).await.unwrap();
+        net.add_peer("p2", &["p1"]).await.unwrap();
+
+        let mut sub = subscribe(&net, "p2", "/test/latency").await;
+        task::sleep(SLEEP).await;
+        net.set_link(
+            "p1",
+            "p2",
+            LinkConditions {
+                latency,
+                ..Default::default()
+            },
+        );
+        let start = Instant::now();
+        publish(&net, "p1", "/test/latency", b"slow").await;
+        assert!(recv(&mut sub).await.is_some());
+        assert!(start.elapsed() >= latency);
+
+        drop(sub);
+        net.close().timeout(TIMEOUT).await.unwrap().unwrap();
+    });
+}
+
+#[test]
+fn testkit_partition() {
+    task::block_on(async {
+        let mut net = TestNetwork::new();
+        net.add_peer("p1", &[]).await.unwrap();
+        net.add_peer("p2", &["p1"]).await.unwrap();
+
+        let mut sub = subscribe(&net, "p2", "/test/partition").await;
+        task::sleep(SLEEP).await;
+
+        net.partition(&["p1"], &["p2"]);
+        publish(&net, "p1", "/test/partition", b"lost").await;
+        assert!(recv(&mut sub).await.is_none());
+
+        net.heal();
+        publish(&net, "p1", "/test/partition", b"delivered").await;
+        let sample = recv(&mut sub).await.unwrap();
+        assert_eq!(sample.payload.to_vec(), b"delivered".to_vec());
+
+        drop(sub);
+        net.close().timeout(TIMEOUT).await.unwrap().unwrap();
+    });
+}
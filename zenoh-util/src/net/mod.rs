@@ -182,6 +182,26 @@ pub fn get_interface(name: &str) -> ZResult<Option<IpAddr>> {
     }
 }
 
+/// Get all the IP addresses of the network interface with the given name.
+pub fn get_interface_addresses(name: &str) -> ZResult<Vec<IpAddr>> {
+    #[cfg(unix)]
+    {
+        Ok(pnet::datalink::interfaces()
+            .into_iter()
+            .filter(|iface| iface.name == name)
+            .map(|iface| iface.ips)
+            .flatten()
+            .map(|ipnet| ipnet.ip())
+            .collect())
+    }
+
+    #[cfg(windows)]
+    {
+        // On windows, only the first IPv4 address of the interface is resolved
+        Ok(get_interface(name)?.into_iter().collect())
+    }
+}
+
 /// Get the network interface to bind the UDP sending port to when not specified by user
 pub fn get_multicast_interfaces() -> Vec<IpAddr> {
     #[cfg(unix)]
@@ -207,6 +227,24 @@ pub fn get_multicast_interfaces() -> Vec<IpAddr> {
     }
 }
 
+/// Returns true if `addr` is an IPv6 unicast link-local address (`fe80::/10`), that can't be
+/// bound without the scope id of its interface.
+///
+/// # Examples
+/// ```
+/// use zenoh_util::net::is_ipv6_link_local;
+///
+/// assert!(is_ipv6_link_local(&"fe80::1".parse().unwrap()));
+/// assert!(!is_ipv6_link_local(&"fd00::1".parse().unwrap()));
+/// assert!(!is_ipv6_link_local(&"169.254.0.1".parse().unwrap()));
+/// ```
+pub fn is_ipv6_link_local(addr: &IpAddr) -> bool {
+    match addr {
+        IpAddr::V6(addr) => addr.segments()[0] & 0xffc0 == 0xfe80,
+        IpAddr::V4(_) => false,
+    }
+}
+
 pub fn get_local_addresses() -> ZResult<Vec<IpAddr>> {
     #[cfg(unix)]
     {
@@ -269,3 +307,68 @@ pub fn get_local_addresses() -> ZResult<Vec<IpAddr>> {
         }
     }
 }
+
+/// An IP subnet, in CIDR notation (e.g. `"192.168.1.0/24"` or `"fd00::/8"`).
+/// A single address without prefix length is a subnet containing only this address.
+///
+/// # Examples
+/// ```
+/// use zenoh_util::net::IpSubnet;
+///
+/// let subnet: IpSubnet = "192.168.1.0/24".parse().unwrap();
+/// assert!(subnet.contains(&"192.168.1.42".parse().unwrap()));
+/// assert!(!subnet.contains(&"192.168.2.42".parse().unwrap()));
+/// assert!(!subnet.contains(&"::1".parse().unwrap()));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct IpSubnet {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpSubnet {
+    /// Returns true if `addr` belongs to this subnet.
+    pub fn contains(&self, addr: &IpAddr) -> bool {
+        fn prefix_eq(a: &[u8], b: &[u8], prefix_len: u8) -> bool {
+            let bytes = (prefix_len / 8) as usize;
+            let bits = prefix_len % 8;
+            a[..bytes] == b[..bytes] && (bits == 0 || (a[bytes] ^ b[bytes]) >> (8 - bits) == 0)
+        }
+        match (&self.addr, addr) {
+            (IpAddr::V4(subnet), IpAddr::V4(addr)) => {
+                prefix_eq(&subnet.octets(), &addr.octets(), self.prefix_len)
+            }
+            (IpAddr::V6(subnet), IpAddr::V6(addr)) => {
+                prefix_eq(&subnet.octets(), &addr.octets(), self.prefix_len)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl std::str::FromStr for IpSubnet {
+    type Err = ZError;
+
+    fn from_str(s: &str) -> ZResult<IpSubnet> {
+        let invalid = || {
+            zerror!(ZErrorKind::ValueDecodingFailed {
+                descr: format!("Invalid IP subnet: {}", s)
+            })
+        };
+        let (addr, prefix_len) = match s.find('/') {
+            Some(i) => (&s[..i], Some(&s[i + 1..])),
+            None => (s, None),
+        };
+        let addr: IpAddr = match addr.trim().parse() {
+            Ok(addr) => addr,
+            Err(_) => return invalid(),
+        };
+        let max_len = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix_len.map(|l| l.trim().parse::<u8>()) {
+            Some(Ok(len)) if len <= max_len => len,
+            Some(_) => return invalid(),
+            None => max_len,
+        };
+        Ok(IpSubnet { addr, prefix_len })
+    }
+}
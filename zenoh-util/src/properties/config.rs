@@ -39,13 +39,19 @@ mod consts {
 
     /// A locator to listen on.
     /// String key : `"listener"`.
-    /// Accepted values : `<locator>` (ex: `"tcp/10.10.10.10:7447"`).
-    /// Default value : None.
+    /// Accepted values : `<locator>` (ex: `"tcp/10.10.10.10:7447"`),
+    /// `<proto>/iface:<name>:<port>` (ex: `"tcp/iface:eth0:7447"`) to listen on each address of an interface,
+    /// `<proto>/auto[:<port>]` (ex: `"udp/auto"`) to listen on each active multicast interface.
+    /// Default value : depends on the mode (see [`config_default`](super::config_default)).
     /// Multiple values accepted.
     pub const ZN_LISTENER_KEY: u64 = 0x42;
     pub const ZN_LISTENER_STR: &str = "listener";
+    pub const ZN_LISTENER_ROUTER_DEFAULT: &str = "tcp/0.0.0.0:7447";
+    pub const ZN_LISTENER_PEER_DEFAULT: &str = "tcp/0.0.0.0:0";
 
     /// The user name to use for authentication.
+    /// The handshake protects against the replays, and is not accepted by the peers of
+    /// the versions prior to this protection.
     /// String key : `"user"`.
     /// Accepted values : `<string>`.
     /// Default value : None.
@@ -116,6 +122,8 @@ mod consts {
     pub const ZN_LINK_STATE_DEFAULT: &str = ZN_TRUE;
 
     /// The file path containing the user password dictionary.
+    /// The peers of the versions prior to the protection against the replays are still
+    /// authenticated, without this protection.
     /// String key : `"user_password_dictionary"`.
     /// Accepted values : `<file path>`.
     /// Default value : None.
@@ -227,6 +235,403 @@ mod consts {
     /// Default value : `1024`.
     pub const ZN_OPEN_INCOMING_PENDING_KEY: u64 = 0x67;
     pub const ZN_OPEN_INCOMING_PENDING_STR: &str = "open_incoming_pending";
+
+    /// Configures the maximum size in bytes of a message, once defragmented.
+    /// Larger messages are refused at emission and dropped at reception,
+    /// where they are counted in the `defrag_aborts` counter of the session.
+    /// String key : `"max_message_size"`.
+    /// Accepted values : `<unsigned integer>`.
+    /// Default value : `1073741824 (1 GiB)`.
+    pub const ZN_MAX_MESSAGE_SIZE_KEY: u64 = 0x68;
+    pub const ZN_MAX_MESSAGE_SIZE_STR: &str = "max_message_size";
+
+    /// Indicates if a BLAKE3 digest of the payload should be attached to the published data,
+    /// for the subscribers to check its integrity.
+    /// String key : `"payload_digest"`.
+    /// Accepted values : `"true"`, `"false"`.
+    /// Default value : `"false"`.
+    pub const ZN_PAYLOAD_DIGEST_KEY: u64 = 0x69;
+    pub const ZN_PAYLOAD_DIGEST_STR: &str = "payload_digest";
+    pub const ZN_PAYLOAD_DIGEST_DEFAULT: &str = ZN_FALSE;
+
+    /// The file where to record the events processed by the routing core, for replaying them.
+    /// Only effective if zenoh is built with the `routing_replay` feature.
+    /// String key : `"routing_trace"`.
+    /// Accepted values : `<file path>`.
+    pub const ZN_ROUTING_TRACE_KEY: u64 = 0x6A;
+    pub const ZN_ROUTING_TRACE_STR: &str = "routing_trace";
+
+    /// The file where to cache the routers and peers discovered by multicast scouting.
+    /// At startup, the cached routers and peers are connected right away, without waiting
+    /// for them to answer the scouting (which still goes on to refresh the cache).
+    /// String key : `"scouting_cache"`.
+    /// Accepted values : `<file path>`.
+    pub const ZN_SCOUTING_CACHE_KEY: u64 = 0x6B;
+    pub const ZN_SCOUTING_CACHE_STR: &str = "scouting_cache";
+
+    /// The age beyond which the entries of the scouting cache are discarded.
+    /// String key : `"scouting_cache_max_age"`.
+    /// Accepted values : `<float in seconds>`.
+    /// Default value : `"86400.0"`.
+    pub const ZN_SCOUTING_CACHE_MAX_AGE_KEY: u64 = 0x6C;
+    pub const ZN_SCOUTING_CACHE_MAX_AGE_STR: &str = "scouting_cache_max_age";
+    pub const ZN_SCOUTING_CACHE_MAX_AGE_DEFAULT: &str = "86400.0";
+
+    /// The minimal period between two responses to the scouting probes from a same source address.
+    /// The probes received during this period are ignored.
+    /// String key : `"scouting_response_period"`.
+    /// Accepted values : `<float in seconds>`.
+    /// Default value : `"0.0"` (no limit).
+    pub const ZN_SCOUTING_RESPONSE_PERIOD_KEY: u64 = 0x6D;
+    pub const ZN_SCOUTING_RESPONSE_PERIOD_STR: &str = "scouting_response_period";
+    pub const ZN_SCOUTING_RESPONSE_PERIOD_DEFAULT: &str = "0.0";
+
+    /// The roles that the scouting probes may look for to be answered: the probes looking for
+    /// other roles are ignored (e.g. with `"router"`, a router answers the probes from the clients
+    /// but not the ones from the peers, that look for routers and peers).
+    /// String key : `"scouting_response_what"`.
+    /// Accepted values : a comma separated list of `"router"`, `"peer"` and `"client"`.
+    /// Default value : all the roles.
+    pub const ZN_SCOUTING_RESPONSE_WHAT_KEY: u64 = 0x6E;
+    pub const ZN_SCOUTING_RESPONSE_WHAT_STR: &str = "scouting_response_what";
+
+    /// The subnets from which the scouting probes are answered.
+    /// String key : `"scouting_response_subnets"`.
+    /// Accepted values : a comma separated list of subnets in CIDR notation
+    /// (e.g. `"192.168.1.0/24,fd00::/8"`).
+    /// Default value : all the subnets.
+    pub const ZN_SCOUTING_RESPONSE_SUBNETS_KEY: u64 = 0x6F;
+    pub const ZN_SCOUTING_RESPONSE_SUBNETS_STR: &str = "scouting_response_subnets";
+
+    /// The maximal number of hops to the routers and peers discovered by gossip
+    /// (i.e. via the link state protocol) to automatically connect them.
+    /// String key : `"gossip_radius"`.
+    /// Accepted values : `<unsigned integer>`.
+    /// Default value : no limit.
+    pub const ZN_GOSSIP_RADIUS_KEY: u64 = 0x70;
+    pub const ZN_GOSSIP_RADIUS_STR: &str = "gossip_radius";
+
+    /// The roles of the routers and peers discovered by gossip to automatically connect.
+    /// String key : `"gossip_what"`.
+    /// Accepted values : a comma separated list of `"router"` and `"peer"`.
+    /// Default value : all the roles.
+    pub const ZN_GOSSIP_WHAT_KEY: u64 = 0x71;
+    pub const ZN_GOSSIP_WHAT_STR: &str = "gossip_what";
+
+    /// The subnets where the routers and peers discovered by gossip must have a locator
+    /// to be automatically connected (only the locators in these subnets are then used).
+    /// String key : `"gossip_subnets"`.
+    /// Accepted values : a comma separated list of subnets in CIDR notation
+    /// (e.g. `"192.168.1.0/24,fd00::/8"`).
+    /// Default value : all the subnets.
+    pub const ZN_GOSSIP_SUBNETS_KEY: u64 = 0x72;
+    pub const ZN_GOSSIP_SUBNETS_STR: &str = "gossip_subnets";
+
+    /// The delay before retrying to connect an unreachable configured peer for the first time.
+    /// String key : `"connect_retry_initial"`.
+    /// Accepted values : `<unsigned integer in milliseconds>`.
+    /// Default value : `"1000"`.
+    pub const ZN_CONNECT_RETRY_INITIAL_KEY: u64 = 0x73;
+    pub const ZN_CONNECT_RETRY_INITIAL_STR: &str = "connect_retry_initial";
+    pub const ZN_CONNECT_RETRY_INITIAL_DEFAULT: &str = "1000";
+
+    /// The maximal delay between two attempts to connect an unreachable configured peer.
+    /// String key : `"connect_retry_max"`.
+    /// Accepted values : `<unsigned integer in milliseconds>`.
+    /// Default value : `"4000"`.
+    pub const ZN_CONNECT_RETRY_MAX_KEY: u64 = 0x74;
+    pub const ZN_CONNECT_RETRY_MAX_STR: &str = "connect_retry_max";
+    pub const ZN_CONNECT_RETRY_MAX_DEFAULT: &str = "4000";
+
+    /// The factor applied to the delay between two attempts to connect an unreachable
+    /// configured peer after each failed attempt.
+    /// String key : `"connect_retry_multiplier"`.
+    /// Accepted values : `<float greater than or equal to 1.0>`.
+    /// Default value : `"2.0"`.
+    pub const ZN_CONNECT_RETRY_MULTIPLIER_KEY: u64 = 0x75;
+    pub const ZN_CONNECT_RETRY_MULTIPLIER_STR: &str = "connect_retry_multiplier";
+    pub const ZN_CONNECT_RETRY_MULTIPLIER_DEFAULT: &str = "2.0";
+
+    /// The ratio by which each delay between two connection attempts is randomly spread,
+    /// not to have several nodes retrying at the same time.
+    /// String key : `"connect_retry_jitter"`.
+    /// Accepted values : `<float between 0.0 and 1.0>`.
+    /// Default value : `"0.0"` (no jitter).
+    pub const ZN_CONNECT_RETRY_JITTER_KEY: u64 = 0x76;
+    pub const ZN_CONNECT_RETRY_JITTER_STR: &str = "connect_retry_jitter";
+    pub const ZN_CONNECT_RETRY_JITTER_DEFAULT: &str = "0.0";
+
+    /// The path expressions of the data whose latest values are cached by a router,
+    /// to answer the queries and send them to the new subscribers.
+    /// String key : `"latest_value_cache"`.
+    /// Accepted values : a comma separated list of path expressions (e.g. `"/demo/**,/status/*"`).
+    /// Default value : none (no cache).
+    pub const ZN_LATEST_VALUE_CACHE_KEY: u64 = 0x77;
+    pub const ZN_LATEST_VALUE_CACHE_STR: &str = "latest_value_cache";
+
+    /// The maximal number of paths in the latest value cache of a router.
+    /// When it's full, the least recently updated paths are evicted.
+    /// String key : `"latest_value_cache_size"`.
+    /// Accepted values : `<unsigned integer>`.
+    /// Default value : `"1024"`.
+    pub const ZN_LATEST_VALUE_CACHE_SIZE_KEY: u64 = 0x78;
+    pub const ZN_LATEST_VALUE_CACHE_SIZE_STR: &str = "latest_value_cache_size";
+    pub const ZN_LATEST_VALUE_CACHE_SIZE_DEFAULT: &str = "1024";
+
+    /// The proxy through which the outgoing TCP and TLS links are opened,
+    /// unless overridden by the `proxy` metadata of a peer endpoint.
+    /// String key : `"proxy"`.
+    /// Accepted values : `socks5://[<user>:<password>@]<host>:<port>` or
+    /// `http://[<user>:<password>@]<host>:<port>` (HTTP CONNECT).
+    /// Default value : none (direct connections).
+    pub const ZN_PROXY_KEY: u64 = 0x79;
+    pub const ZN_PROXY_STR: &str = "proxy";
+
+    /// Indicates if a client re-declares its subscribers and queryables to the router it
+    /// reconnects to after losing its session, and buffers its publications meanwhile.
+    /// String key : `"reconnect"`.
+    /// Accepted values : `"true"`, `"false"`.
+    /// Default value : `"false"`.
+    pub const ZN_RECONNECT_KEY: u64 = 0x7a;
+    pub const ZN_RECONNECT_STR: &str = "reconnect";
+    pub const ZN_RECONNECT_DEFAULT: &str = ZN_FALSE;
+
+    /// The maximal number of publications a client in reconnect mode buffers while it's
+    /// disconnected. When it's full, the oldest publications are dropped.
+    /// String key : `"reconnect_buffer_size"`.
+    /// Accepted values : `<unsigned integer>`.
+    /// Default value : `"1024"`.
+    pub const ZN_RECONNECT_BUFFER_SIZE_KEY: u64 = 0x7b;
+    pub const ZN_RECONNECT_BUFFER_SIZE_STR: &str = "reconnect_buffer_size";
+    pub const ZN_RECONNECT_BUFFER_SIZE_DEFAULT: &str = "1024";
+
+    /// The JWT presented by this peer to authenticate when opening a session.
+    /// Being a bearer token, it's only sent on the TLS and QUIC links.
+    /// String key : `"jwt_token"`.
+    /// Accepted values : `<compact JWT>`.
+    /// Default value : None.
+    pub const ZN_JWT_TOKEN_KEY: u64 = 0x7c;
+    pub const ZN_JWT_TOKEN_STR: &str = "jwt_token";
+
+    /// The JSON Web Key Set validating the signature of the JWT presented by the peers.
+    /// If set, the sessions with the peers not presenting a valid JWT are rejected.
+    /// String key : `"jwt_jwks"`.
+    /// An `http://` URL is only accepted with `"jwt_jwks_insecure"`.
+    /// Accepted values : `<file path>`, `https://<host>[:<port>]/<path>`, `http://<host>[:<port>]/<path>`.
+    /// Default value : None.
+    pub const ZN_JWT_JWKS_KEY: u64 = 0x7d;
+    pub const ZN_JWT_JWKS_STR: &str = "jwt_jwks";
+
+    /// The audience the JWT presented by the peers must be intended for (`"aud"` claim).
+    /// String key : `"jwt_audience"`.
+    /// Accepted values : `<string>`.
+    /// Default value : None (the audience is not checked).
+    pub const ZN_JWT_AUDIENCE_KEY: u64 = 0x7e;
+    pub const ZN_JWT_AUDIENCE_STR: &str = "jwt_audience";
+
+    /// The claim of the JWT presented by a peer used as its authenticated subject.
+    /// String key : `"jwt_subject_claim"`.
+    /// Accepted values : `<string>`.
+    /// Default value : `"sub"`.
+    pub const ZN_JWT_SUBJECT_CLAIM_KEY: u64 = 0x7f;
+    pub const ZN_JWT_SUBJECT_CLAIM_STR: &str = "jwt_subject_claim";
+    pub const ZN_JWT_SUBJECT_CLAIM_DEFAULT: &str = "sub";
+
+    /// The maximal number of publications per second a router accepts from each subject
+    /// (see [`ZN_JWT_SUBJECT_CLAIM_KEY`]), or from each peer if it's not authenticated.
+    /// String key : `"quota_publication_rate"`.
+    /// Accepted values : `<unsigned integer>`.
+    /// Default value : None (not limited).
+    pub const ZN_QUOTA_PUBLICATION_RATE_KEY: u64 = 0x80;
+    pub const ZN_QUOTA_PUBLICATION_RATE_STR: &str = "quota_publication_rate";
+
+    /// The maximal number of queries per second a router accepts from each subject,
+    /// or from each peer if it's not authenticated.
+    /// String key : `"quota_query_rate"`.
+    /// Accepted values : `<unsigned integer>`.
+    /// Default value : None (not limited).
+    pub const ZN_QUOTA_QUERY_RATE_KEY: u64 = 0x81;
+    pub const ZN_QUOTA_QUERY_RATE_STR: &str = "quota_query_rate";
+
+    /// The maximal number of subscribers and queryables a router accepts from each subject,
+    /// or from each peer if it's not authenticated. The declarations beyond this quota are
+    /// ignored, and a session repeatedly declaring entities beyond it is closed with the
+    /// `QUOTA` close reason.
+    /// String key : `"quota_declarations"`.
+    /// Accepted values : `<unsigned integer>`.
+    /// Default value : None (not limited).
+    pub const ZN_QUOTA_DECLARATIONS_KEY: u64 = 0x82;
+    pub const ZN_QUOTA_DECLARATIONS_STR: &str = "quota_declarations";
+
+    /// The level up to which the logged records are published by a router in its admin space,
+    /// on `/@/router/<pid>/logger/lines`, for the remote operators to tail its logs.
+    /// The records must also match the filter of the logger.
+    /// String key : `"admin_log_stream"`.
+    /// Accepted values : `"error"`, `"warn"`, `"info"`, `"debug"`, `"trace"`.
+    /// Default value : None (not published).
+    pub const ZN_ADMIN_LOG_STREAM_KEY: u64 = 0x83;
+    pub const ZN_ADMIN_LOG_STREAM_STR: &str = "admin_log_stream";
+
+    /// The file path containing the TLS client private key.
+    /// String key : `"tls_client_private_key"`.
+    /// Accepted values : `<file path>`.
+    /// Default value : None.
+    pub const ZN_TLS_CLIENT_PRIVATE_KEY_KEY: u64 = 0x84;
+    pub const ZN_TLS_CLIENT_PRIVATE_KEY_STR: &str = "tls_client_private_key";
+
+    /// The file path containing the TLS client certificate, presented to the TLS and QUIC servers.
+    /// String key : `"tls_client_certificate"`.
+    /// Accepted values : `<file path>`.
+    /// Default value : None.
+    pub const ZN_TLS_CLIENT_CERTIFICATE_KEY: u64 = 0x85;
+    pub const ZN_TLS_CLIENT_CERTIFICATE_STR: &str = "tls_client_certificate";
+
+    /// Indicates if the peer id of the remote peers must be bound to their TLS certificate,
+    /// i.e. their certificate, signed by the root CA, must have a DNS subject alternative name
+    /// `<pid>.zenoh`. The TLS and QUIC servers then require a client certificate,
+    /// and the sessions are only established over TLS and QUIC links.
+    /// String key : `"tls_peer_id_binding"`.
+    /// Accepted values : `"true"`, `"false"`.
+    /// Default value : `"false"`.
+    pub const ZN_TLS_PEER_ID_BINDING_KEY: u64 = 0x86;
+    pub const ZN_TLS_PEER_ID_BINDING_STR: &str = "tls_peer_id_binding";
+    pub const ZN_TLS_PEER_ID_BINDING_DEFAULT: &str = ZN_FALSE;
+
+    /// The power profile of a client. In `"low_power"`, the messages are only sent at wake
+    /// windows (see [`ZN_POWER_WAKE_WINDOW_KEY`]), the lease and the keep alive interval are
+    /// widened accordingly and the multicast scouting is suspended, to extend the battery life
+    /// of portable devices. The profile can then be switched at runtime.
+    /// String key : `"power_profile"`.
+    /// Accepted values : `"default"`, `"low_power"`.
+    /// Default value : `"default"`.
+    pub const ZN_POWER_PROFILE_KEY: u64 = 0x87;
+    pub const ZN_POWER_PROFILE_STR: &str = "power_profile";
+    pub const ZN_POWER_PROFILE_DEFAULT: &str = "default";
+
+    /// The interval in milliseconds between two wake windows in the `"low_power"` power profile.
+    /// String key : `"power_wake_window"`.
+    /// Accepted values : `<unsigned integer>`.
+    /// Default value : `"1000"`.
+    pub const ZN_POWER_WAKE_WINDOW_KEY: u64 = 0x88;
+    pub const ZN_POWER_WAKE_WINDOW_STR: &str = "power_wake_window";
+    pub const ZN_POWER_WAKE_WINDOW_DEFAULT: &str = "1000";
+
+    /// The maximum size in bytes of the batches sent on a link, capped by the MTU of the link.
+    /// It also bounds the size classes of the buffer pool (see [`ZN_BUFFER_POOL_SIZE_KEY`]).
+    /// String key : `"batch_size"`.
+    /// Accepted values : `<unsigned integer>` up to 65535.
+    /// Default value : `"65535"`.
+    pub const ZN_BATCH_SIZE_KEY: u64 = 0x89;
+    pub const ZN_BATCH_SIZE_STR: &str = "batch_size";
+    pub const ZN_BATCH_SIZE_DEFAULT: &str = "65535";
+
+    /// The amount of memory in bytes kept by the pool of buffers the batches are received onto,
+    /// shared by all the links and split among its size classes.
+    /// String key : `"buffer_pool_size"`.
+    /// Accepted values : `<unsigned integer>`.
+    /// Default value : `"16777216"`.
+    pub const ZN_BUFFER_POOL_SIZE_KEY: u64 = 0x8A;
+    pub const ZN_BUFFER_POOL_SIZE_STR: &str = "buffer_pool_size";
+    pub const ZN_BUFFER_POOL_SIZE_DEFAULT: &str = "16777216";
+
+    /// The policy scheduling the transmission of the priority queues of a link. In `"strict"`,
+    /// a queue is only serviced once the higher priority queues are empty. In `"wrr"`, the
+    /// queues are serviced in a weighted round robin (see [`ZN_TX_WRR_WEIGHTS_KEY`]), so that
+    /// the lower priority queues aren't starved.
+    /// String key : `"tx_scheduling"`.
+    /// Accepted values : `"strict"`, `"wrr"`.
+    /// Default value : `"strict"`.
+    pub const ZN_TX_SCHEDULING_KEY: u64 = 0x8B;
+    pub const ZN_TX_SCHEDULING_STR: &str = "tx_scheduling";
+    pub const ZN_TX_SCHEDULING_DEFAULT: &str = "strict";
+
+    /// The weights of the control, retransmission and data queues in the `"wrr"` scheduling
+    /// policy: the maximum number of batches of a queue sent in a row when the others are pending.
+    /// String key : `"tx_wrr_weights"`.
+    /// Accepted values : `<control>,<retransmission>,<data>`, each a non-zero integer.
+    /// Default value : `"4,2,1"`.
+    pub const ZN_TX_WRR_WEIGHTS_KEY: u64 = 0x8C;
+    pub const ZN_TX_WRR_WEIGHTS_STR: &str = "tx_wrr_weights";
+    pub const ZN_TX_WRR_WEIGHTS_DEFAULT: &str = "4,2,1";
+
+    /// Indicates if a provenance chain should be attached to the published data, to which
+    /// the routers and bridges append their hop, for the subscribers to read the path it took.
+    /// String key : `"provenance"`.
+    /// Accepted values : `"true"`, `"false"`.
+    /// Default value : `"false"`.
+    pub const ZN_PROVENANCE_KEY: u64 = 0x8D;
+    pub const ZN_PROVENANCE_STR: &str = "provenance";
+    pub const ZN_PROVENANCE_DEFAULT: &str = ZN_FALSE;
+
+    /// The provider of the shared memory segment used to negotiate the zero-copy with a peer.
+    /// With `"memfd"` (Linux 5.1 or later), the segment is a sealed memfd only passed along the
+    /// Unix socket links, so that the zero-copy is only negotiated with the peers it was passed to.
+    /// The other memfd segments are only passed to the peers of a session established with
+    /// shared memory, which can only map them read-only.
+    /// The buffers of a memfd segment are copied when forwarded to the other peers.
+    /// With `"named"` (Windows only), the segment is a named file mapping backed by the paging file,
+    /// that leaves no file behind.
+    /// Whatever the provider, there is no watchdog: the buffers still referenced by a peer process
+    /// that terminates abnormally are not freed: they stay allocated until the segment is dropped.
+    /// String key : `"shm_provider"`.
+    /// Accepted values : `"posix"`, `"memfd"`, `"named"`.
+    /// Default value : `"posix"`.
+    pub const ZN_SHM_PROVIDER_KEY: u64 = 0x8E;
+    pub const ZN_SHM_PROVIDER_STR: &str = "shm_provider";
+    pub const ZN_SHM_PROVIDER_DEFAULT: &str = "posix";
+
+    /// In router mode, the UDP address on which the unicast scouts of the clients are answered
+    /// with the locators of this router and of all the routers it knows.
+    /// A same source address is answered at most once per second (or per
+    /// `"scouting_response_period"` if longer), and only from the `"scouting_response_subnets"`.
+    /// String key : `"gossip_listener"`.
+    /// Accepted values : `<ip>:<port>`.
+    pub const ZN_GOSSIP_LISTENER_KEY: u64 = 0x8F;
+    pub const ZN_GOSSIP_LISTENER_STR: &str = "gossip_listener";
+
+    /// In client mode, the gossip listeners of the seed routers scouted when no peer is configured.
+    /// The routers learned from the seeds are connected if no seed answers. They're recorded
+    /// in the `"scouting_cache"`, if any, to be connected as well after a restart.
+    /// String key : `"gossip_seeds"`.
+    /// Accepted values : `<host>:<port>[,<host>:<port>]*`.
+    pub const ZN_GOSSIP_SEEDS_KEY: u64 = 0x90;
+    pub const ZN_GOSSIP_SEEDS_STR: &str = "gossip_seeds";
+
+    /// Which write operations a router accepts on its admin space: the puts on
+    /// `/@/router/<pid>/connect`, `/disconnect`, `/logger` and `/drain`, and the puts
+    /// on the `import` and `export` paths of the storages of the storages plugin.
+    /// Either all of them, none of them, or the ones on the paths matching a list of path
+    /// expressions relative to `/@/router/<pid>` (e.g. `"/logger,/plugin/storages/**"`).
+    /// Those puts are not authenticated: the permissions apply to whoever reaches
+    /// the admin space, so only allow them if it can only be reached by the operators.
+    /// String key : `"admin_write"`.
+    /// Accepted values : `"true"`, `"false"`, `<path_expr>[,<path_expr>]*`.
+    /// Default value : `"false"`.
+    pub const ZN_ADMIN_WRITE_KEY: u64 = 0x91;
+    pub const ZN_ADMIN_WRITE_STR: &str = "admin_write";
+    pub const ZN_ADMIN_WRITE_DEFAULT: &str = ZN_FALSE;
+
+    /// Accept an `http://` URL as `"jwt_jwks"`. Anyone on the path of the JWKS could then
+    /// replace its keys with their own, and authenticate as any subject: only enable it
+    /// if this path is trusted (e.g. for tests, or on the loopback interface).
+    /// String key : `"jwt_jwks_insecure"`.
+    /// Accepted values : `"true"`, `"false"`.
+    /// Default value : `"false"`.
+    pub const ZN_JWT_JWKS_INSECURE_KEY: u64 = 0x92;
+    pub const ZN_JWT_JWKS_INSECURE_STR: &str = "jwt_jwks_insecure";
+    pub const ZN_JWT_JWKS_INSECURE_DEFAULT: &str = ZN_FALSE;
+
+    /// Which paths of its admin space a router answers the queries on: all of them, none of them,
+    /// or the ones matching a list of path expressions relative to `/@/router/<pid>`
+    /// (e.g. `"/stats,/health,/liveness"` to only expose its metrics). It also applies to the
+    /// admin status of the backends and storages of the storages plugin. Like the `"admin_write"`
+    /// permissions, it applies to whoever reaches the admin space.
+    /// String key : `"admin_read"`.
+    /// Accepted values : `"true"`, `"false"`, `<path_expr>[,<path_expr>]*`.
+    /// Default value : `"true"`.
+    pub const ZN_ADMIN_READ_KEY: u64 = 0x93;
+    pub const ZN_ADMIN_READ_STR: &str = "admin_read";
+    pub const ZN_ADMIN_READ_DEFAULT: &str = ZN_TRUE;
 }
 
 pub use consts::*;
@@ -266,6 +671,50 @@ impl KeyTranscoder for ConfigTranscoder {
             ZN_SEQ_NUM_RESOLUTION_STR => Some(ZN_SEQ_NUM_RESOLUTION_KEY),
             ZN_OPEN_TIMEOUT_STR => Some(ZN_OPEN_TIMEOUT_KEY),
             ZN_OPEN_INCOMING_PENDING_STR => Some(ZN_OPEN_INCOMING_PENDING_KEY),
+            ZN_MAX_MESSAGE_SIZE_STR => Some(ZN_MAX_MESSAGE_SIZE_KEY),
+            ZN_PAYLOAD_DIGEST_STR => Some(ZN_PAYLOAD_DIGEST_KEY),
+            ZN_ROUTING_TRACE_STR => Some(ZN_ROUTING_TRACE_KEY),
+            ZN_SCOUTING_CACHE_STR => Some(ZN_SCOUTING_CACHE_KEY),
+            ZN_SCOUTING_CACHE_MAX_AGE_STR => Some(ZN_SCOUTING_CACHE_MAX_AGE_KEY),
+            ZN_SCOUTING_RESPONSE_PERIOD_STR => Some(ZN_SCOUTING_RESPONSE_PERIOD_KEY),
+            ZN_SCOUTING_RESPONSE_WHAT_STR => Some(ZN_SCOUTING_RESPONSE_WHAT_KEY),
+            ZN_SCOUTING_RESPONSE_SUBNETS_STR => Some(ZN_SCOUTING_RESPONSE_SUBNETS_KEY),
+            ZN_GOSSIP_RADIUS_STR => Some(ZN_GOSSIP_RADIUS_KEY),
+            ZN_GOSSIP_WHAT_STR => Some(ZN_GOSSIP_WHAT_KEY),
+            ZN_GOSSIP_SUBNETS_STR => Some(ZN_GOSSIP_SUBNETS_KEY),
+            ZN_CONNECT_RETRY_INITIAL_STR => Some(ZN_CONNECT_RETRY_INITIAL_KEY),
+            ZN_CONNECT_RETRY_MAX_STR => Some(ZN_CONNECT_RETRY_MAX_KEY),
+            ZN_CONNECT_RETRY_MULTIPLIER_STR => Some(ZN_CONNECT_RETRY_MULTIPLIER_KEY),
+            ZN_CONNECT_RETRY_JITTER_STR => Some(ZN_CONNECT_RETRY_JITTER_KEY),
+            ZN_LATEST_VALUE_CACHE_STR => Some(ZN_LATEST_VALUE_CACHE_KEY),
+            ZN_LATEST_VALUE_CACHE_SIZE_STR => Some(ZN_LATEST_VALUE_CACHE_SIZE_KEY),
+            ZN_PROXY_STR => Some(ZN_PROXY_KEY),
+            ZN_RECONNECT_STR => Some(ZN_RECONNECT_KEY),
+            ZN_RECONNECT_BUFFER_SIZE_STR => Some(ZN_RECONNECT_BUFFER_SIZE_KEY),
+            ZN_JWT_TOKEN_STR => Some(ZN_JWT_TOKEN_KEY),
+            ZN_JWT_JWKS_STR => Some(ZN_JWT_JWKS_KEY),
+            ZN_JWT_AUDIENCE_STR => Some(ZN_JWT_AUDIENCE_KEY),
+            ZN_JWT_SUBJECT_CLAIM_STR => Some(ZN_JWT_SUBJECT_CLAIM_KEY),
+            ZN_QUOTA_PUBLICATION_RATE_STR => Some(ZN_QUOTA_PUBLICATION_RATE_KEY),
+            ZN_QUOTA_QUERY_RATE_STR => Some(ZN_QUOTA_QUERY_RATE_KEY),
+            ZN_QUOTA_DECLARATIONS_STR => Some(ZN_QUOTA_DECLARATIONS_KEY),
+            ZN_ADMIN_LOG_STREAM_STR => Some(ZN_ADMIN_LOG_STREAM_KEY),
+            ZN_TLS_CLIENT_PRIVATE_KEY_STR => Some(ZN_TLS_CLIENT_PRIVATE_KEY_KEY),
+            ZN_TLS_CLIENT_CERTIFICATE_STR => Some(ZN_TLS_CLIENT_CERTIFICATE_KEY),
+            ZN_TLS_PEER_ID_BINDING_STR => Some(ZN_TLS_PEER_ID_BINDING_KEY),
+            ZN_POWER_PROFILE_STR => Some(ZN_POWER_PROFILE_KEY),
+            ZN_POWER_WAKE_WINDOW_STR => Some(ZN_POWER_WAKE_WINDOW_KEY),
+            ZN_BATCH_SIZE_STR => Some(ZN_BATCH_SIZE_KEY),
+            ZN_BUFFER_POOL_SIZE_STR => Some(ZN_BUFFER_POOL_SIZE_KEY),
+            ZN_TX_SCHEDULING_STR => Some(ZN_TX_SCHEDULING_KEY),
+            ZN_TX_WRR_WEIGHTS_STR => Some(ZN_TX_WRR_WEIGHTS_KEY),
+            ZN_PROVENANCE_STR => Some(ZN_PROVENANCE_KEY),
+            ZN_SHM_PROVIDER_STR => Some(ZN_SHM_PROVIDER_KEY),
+            ZN_GOSSIP_LISTENER_STR => Some(ZN_GOSSIP_LISTENER_KEY),
+            ZN_GOSSIP_SEEDS_STR => Some(ZN_GOSSIP_SEEDS_KEY),
+            ZN_ADMIN_WRITE_STR => Some(ZN_ADMIN_WRITE_KEY),
+            ZN_JWT_JWKS_INSECURE_STR => Some(ZN_JWT_JWKS_INSECURE_KEY),
+            ZN_ADMIN_READ_STR => Some(ZN_ADMIN_READ_KEY),
             _ => None,
         }
     }
@@ -304,7 +753,114 @@ impl KeyTranscoder for ConfigTranscoder {
             ZN_SEQ_NUM_RESOLUTION_KEY => Some(ZN_SEQ_NUM_RESOLUTION_STR.to_string()),
             ZN_OPEN_TIMEOUT_KEY => Some(ZN_OPEN_TIMEOUT_STR.to_string()),
             ZN_OPEN_INCOMING_PENDING_KEY => Some(ZN_OPEN_INCOMING_PENDING_STR.to_string()),
+            ZN_MAX_MESSAGE_SIZE_KEY => Some(ZN_MAX_MESSAGE_SIZE_STR.to_string()),
+            ZN_PAYLOAD_DIGEST_KEY => Some(ZN_PAYLOAD_DIGEST_STR.to_string()),
+            ZN_ROUTING_TRACE_KEY => Some(ZN_ROUTING_TRACE_STR.to_string()),
+            ZN_SCOUTING_CACHE_KEY => Some(ZN_SCOUTING_CACHE_STR.to_string()),
+            ZN_SCOUTING_CACHE_MAX_AGE_KEY => Some(ZN_SCOUTING_CACHE_MAX_AGE_STR.to_string()),
+            ZN_SCOUTING_RESPONSE_PERIOD_KEY => Some(ZN_SCOUTING_RESPONSE_PERIOD_STR.to_string()),
+            ZN_SCOUTING_RESPONSE_WHAT_KEY => Some(ZN_SCOUTING_RESPONSE_WHAT_STR.to_string()),
+            ZN_SCOUTING_RESPONSE_SUBNETS_KEY => Some(ZN_SCOUTING_RESPONSE_SUBNETS_STR.to_string()),
+            ZN_GOSSIP_RADIUS_KEY => Some(ZN_GOSSIP_RADIUS_STR.to_string()),
+            ZN_GOSSIP_WHAT_KEY => Some(ZN_GOSSIP_WHAT_STR.to_string()),
+            ZN_GOSSIP_SUBNETS_KEY => Some(ZN_GOSSIP_SUBNETS_STR.to_string()),
+            ZN_CONNECT_RETRY_INITIAL_KEY => Some(ZN_CONNECT_RETRY_INITIAL_STR.to_string()),
+            ZN_CONNECT_RETRY_MAX_KEY => Some(ZN_CONNECT_RETRY_MAX_STR.to_string()),
+            ZN_CONNECT_RETRY_MULTIPLIER_KEY => Some(ZN_CONNECT_RETRY_MULTIPLIER_STR.to_string()),
+            ZN_CONNECT_RETRY_JITTER_KEY => Some(ZN_CONNECT_RETRY_JITTER_STR.to_string()),
+            ZN_LATEST_VALUE_CACHE_KEY => Some(ZN_LATEST_VALUE_CACHE_STR.to_string()),
+            ZN_LATEST_VALUE_CACHE_SIZE_KEY => Some(ZN_LATEST_VALUE_CACHE_SIZE_STR.to_string()),
+            ZN_PROXY_KEY => Some(ZN_PROXY_STR.to_string()),
+            ZN_RECONNECT_KEY => Some(ZN_RECONNECT_STR.to_string()),
+            ZN_RECONNECT_BUFFER_SIZE_KEY => Some(ZN_RECONNECT_BUFFER_SIZE_STR.to_string()),
+            ZN_JWT_TOKEN_KEY => Some(ZN_JWT_TOKEN_STR.to_string()),
+            ZN_JWT_JWKS_KEY => Some(ZN_JWT_JWKS_STR.to_string()),
+            ZN_JWT_AUDIENCE_KEY => Some(ZN_JWT_AUDIENCE_STR.to_string()),
+            ZN_JWT_SUBJECT_CLAIM_KEY => Some(ZN_JWT_SUBJECT_CLAIM_STR.to_string()),
+            ZN_QUOTA_PUBLICATION_RATE_KEY => Some(ZN_QUOTA_PUBLICATION_RATE_STR.to_string()),
+            ZN_QUOTA_QUERY_RATE_KEY => Some(ZN_QUOTA_QUERY_RATE_STR.to_string()),
+            ZN_QUOTA_DECLARATIONS_KEY => Some(ZN_QUOTA_DECLARATIONS_STR.to_string()),
+            ZN_ADMIN_LOG_STREAM_KEY => Some(ZN_ADMIN_LOG_STREAM_STR.to_string()),
+            ZN_TLS_CLIENT_PRIVATE_KEY_KEY => Some(ZN_TLS_CLIENT_PRIVATE_KEY_STR.to_string()),
+            ZN_TLS_CLIENT_CERTIFICATE_KEY => Some(ZN_TLS_CLIENT_CERTIFICATE_STR.to_string()),
+            ZN_TLS_PEER_ID_BINDING_KEY => Some(ZN_TLS_PEER_ID_BINDING_STR.to_string()),
+            ZN_POWER_PROFILE_KEY => Some(ZN_POWER_PROFILE_STR.to_string()),
+            ZN_POWER_WAKE_WINDOW_KEY => Some(ZN_POWER_WAKE_WINDOW_STR.to_string()),
+            ZN_BATCH_SIZE_KEY => Some(ZN_BATCH_SIZE_STR.to_string()),
+            ZN_BUFFER_POOL_SIZE_KEY => Some(ZN_BUFFER_POOL_SIZE_STR.to_string()),
+            ZN_TX_SCHEDULING_KEY => Some(ZN_TX_SCHEDULING_STR.to_string()),
+            ZN_TX_WRR_WEIGHTS_KEY => Some(ZN_TX_WRR_WEIGHTS_STR.to_string()),
+            ZN_PROVENANCE_KEY => Some(ZN_PROVENANCE_STR.to_string()),
+            ZN_SHM_PROVIDER_KEY => Some(ZN_SHM_PROVIDER_STR.to_string()),
+            ZN_GOSSIP_LISTENER_KEY => Some(ZN_GOSSIP_LISTENER_STR.to_string()),
+            ZN_GOSSIP_SEEDS_KEY => Some(ZN_GOSSIP_SEEDS_STR.to_string()),
+            ZN_ADMIN_WRITE_KEY => Some(ZN_ADMIN_WRITE_STR.to_string()),
+            ZN_JWT_JWKS_INSECURE_KEY => Some(ZN_JWT_JWKS_INSECURE_STR.to_string()),
+            ZN_ADMIN_READ_KEY => Some(ZN_ADMIN_READ_STR.to_string()),
             _ => None,
         }
     }
 }
+
+/// Returns the key and name of each configuration property, in key order.
+pub fn config_keys() -> impl Iterator<Item = (u64, String)> {
+    (0x40..0x100).filter_map(|key| ConfigTranscoder::decode(key).map(|name| (key, name)))
+}
+
+/// Returns true if the value of the configuration `key` is a secret that must be masked
+/// whenever the configuration is displayed.
+pub fn is_secret(key: u64) -> bool {
+    matches!(
+        key,
+        ZN_PASSWORD_KEY | ZN_USER_PASSWORD_DICTIONARY_KEY | ZN_PROXY_KEY | ZN_JWT_TOKEN_KEY
+    )
+}
+
+/// Returns the default value of the configuration `key` when running in the given `mode`
+/// (`"router"`, `"peer"` or `"client"`), or `None` if the key has no default in this mode.
+pub fn config_default(key: u64, mode: &str) -> Option<&'static str> {
+    match key {
+        ZN_MODE_KEY => Some(ZN_MODE_DEFAULT),
+        ZN_LISTENER_KEY => match mode {
+            "router" => Some(ZN_LISTENER_ROUTER_DEFAULT),
+            "peer" => Some(ZN_LISTENER_PEER_DEFAULT),
+            _ => None,
+        },
+        ZN_MULTICAST_SCOUTING_KEY => Some(ZN_MULTICAST_SCOUTING_DEFAULT),
+        ZN_MULTICAST_INTERFACE_KEY => Some(ZN_MULTICAST_INTERFACE_DEFAULT),
+        ZN_MULTICAST_ADDRESS_KEY => Some(ZN_MULTICAST_ADDRESS_DEFAULT),
+        ZN_SCOUTING_TIMEOUT_KEY => Some(ZN_SCOUTING_TIMEOUT_DEFAULT),
+        ZN_SCOUTING_DELAY_KEY => Some(ZN_SCOUTING_DELAY_DEFAULT),
+        ZN_ADD_TIMESTAMP_KEY => Some(ZN_ADD_TIMESTAMP_DEFAULT),
+        ZN_LINK_STATE_KEY => Some(ZN_LINK_STATE_DEFAULT),
+        ZN_PEERS_AUTOCONNECT_KEY => Some(ZN_PEERS_AUTOCONNECT_DEFAULT),
+        ZN_ZERO_COPY_KEY => Some(ZN_ZERO_COPY_DEFAULT),
+        ZN_ROUTERS_AUTOCONNECT_MULTICAST_KEY => Some(ZN_ROUTERS_AUTOCONNECT_MULTICAST_DEFAULT),
+        ZN_ROUTERS_AUTOCONNECT_GOSSIP_KEY => Some(ZN_ROUTERS_AUTOCONNECT_GOSSIP_DEFAULT),
+        ZN_LOCAL_ROUTING_KEY => Some(ZN_LOCAL_ROUTING_DEFAULT),
+        ZN_PAYLOAD_DIGEST_KEY => Some(ZN_PAYLOAD_DIGEST_DEFAULT),
+        ZN_SCOUTING_CACHE_MAX_AGE_KEY => Some(ZN_SCOUTING_CACHE_MAX_AGE_DEFAULT),
+        ZN_SCOUTING_RESPONSE_PERIOD_KEY => Some(ZN_SCOUTING_RESPONSE_PERIOD_DEFAULT),
+        ZN_CONNECT_RETRY_INITIAL_KEY => Some(ZN_CONNECT_RETRY_INITIAL_DEFAULT),
+        ZN_CONNECT_RETRY_MAX_KEY => Some(ZN_CONNECT_RETRY_MAX_DEFAULT),
+        ZN_CONNECT_RETRY_MULTIPLIER_KEY => Some(ZN_CONNECT_RETRY_MULTIPLIER_DEFAULT),
+        ZN_CONNECT_RETRY_JITTER_KEY => Some(ZN_CONNECT_RETRY_JITTER_DEFAULT),
+        ZN_LATEST_VALUE_CACHE_SIZE_KEY => Some(ZN_LATEST_VALUE_CACHE_SIZE_DEFAULT),
+        ZN_RECONNECT_KEY => Some(ZN_RECONNECT_DEFAULT),
+        ZN_RECONNECT_BUFFER_SIZE_KEY => Some(ZN_RECONNECT_BUFFER_SIZE_DEFAULT),
+        ZN_JWT_SUBJECT_CLAIM_KEY => Some(ZN_JWT_SUBJECT_CLAIM_DEFAULT),
+        ZN_TLS_PEER_ID_BINDING_KEY => Some(ZN_TLS_PEER_ID_BINDING_DEFAULT),
+        ZN_POWER_PROFILE_KEY => Some(ZN_POWER_PROFILE_DEFAULT),
+        ZN_POWER_WAKE_WINDOW_KEY => Some(ZN_POWER_WAKE_WINDOW_DEFAULT),
+        ZN_BATCH_SIZE_KEY => Some(ZN_BATCH_SIZE_DEFAULT),
+        ZN_BUFFER_POOL_SIZE_KEY => Some(ZN_BUFFER_POOL_SIZE_DEFAULT),
+        ZN_TX_SCHEDULING_KEY => Some(ZN_TX_SCHEDULING_DEFAULT),
+        ZN_TX_WRR_WEIGHTS_KEY => Some(ZN_TX_WRR_WEIGHTS_DEFAULT),
+        ZN_PROVENANCE_KEY => Some(ZN_PROVENANCE_DEFAULT),
+        ZN_SHM_PROVIDER_KEY => Some(ZN_SHM_PROVIDER_DEFAULT),
+        ZN_ADMIN_WRITE_KEY => Some(ZN_ADMIN_WRITE_DEFAULT),
+        ZN_JWT_JWKS_INSECURE_KEY => Some(ZN_JWT_JWKS_INSECURE_DEFAULT),
+        ZN_ADMIN_READ_KEY => Some(ZN_ADMIN_READ_DEFAULT),
+        _ => None,
+    }
+}
@@ -35,6 +35,28 @@ impl<T: KeyTranscoder> IntKeyProperties<T> {
     pub fn get_or<'a>(&'a self, key: &u64, default: &'a str) -> &'a str {
         self.get(key).map(|s| &s[..]).or(Some(default)).unwrap()
     }
+
+    /// Builds properties from the environment variables whose name starts with `prefix`.
+    /// The rest of the variable name, lowercased, is the property string key
+    /// (ex: with prefix `"ZENOHD_"`, `ZENOHD_MULTICAST_SCOUTING=false` sets `"multicast_scouting"`).
+    /// Variables with an unknown key are ignored with a warning.
+    pub fn from_env(prefix: &str) -> Self {
+        Self(
+            std::env::vars()
+                .filter_map(|(name, value)| {
+                    let key = name.strip_prefix(prefix)?;
+                    match T::encode(&key.to_lowercase()) {
+                        Some(key) => Some((key, value)),
+                        None => {
+                            log::warn!("Ignoring environment variable {}: unknown key", name);
+                            None
+                        }
+                    }
+                })
+                .collect(),
+            PhantomData,
+        )
+    }
 }
 
 impl<T: KeyTranscoder> Clone for IntKeyProperties<T> {
@@ -333,6 +355,23 @@ mod tests {
             Properties::from(&[("p1", "x=y"), ("p2", "a==b")][..])
         );
     }
+
+    #[test]
+    fn test_properties_from_env() {
+        use super::config::*;
+
+        std::env::set_var("ZTEST_ENV_PEER", "tcp/10.0.0.1:7447,tcp/10.0.0.2:7447");
+        std::env::set_var("ZTEST_ENV_MULTICAST_SCOUTING", "false");
+        std::env::set_var("ZTEST_ENV_NOT_A_KEY", "x");
+
+        let config = ConfigProperties::from_env("ZTEST_ENV_");
+        assert_eq!(config.len(), 2);
+        assert_eq!(
+            config.get(&ZN_PEER_KEY).unwrap(),
+            "tcp/10.0.0.1:7447,tcp/10.0.0.2:7447"
+        );
+        assert_eq!(config.get(&ZN_MULTICAST_SCOUTING_KEY).unwrap(), ZN_FALSE);
+    }
 }
 
 pub struct DummyTranscoder();
@@ -101,6 +101,16 @@ macro_rules! zreceiver{
             pub fn try_iter(&self) -> TryIter<'_, $recv_type> {
                 self.receiver.try_iter()
             }
+
+            #[inline(always)]
+            pub fn len(&self) -> usize {
+                self.receiver.len()
+            }
+
+            #[inline(always)]
+            pub fn is_empty(&self) -> bool {
+                self.receiver.is_empty()
+            }
         }
     }
 }
@@ -43,6 +43,8 @@ fn criterion_benchmark(c: &mut Criterion) {
                     source_sn: Some(12345),
                     first_router_id: Some(PeerId::new(16, [0u8; PeerId::MAX_SIZE])),
                     first_router_sn: Some(12345),
+                    digest: None,
+                    provenance: None,
                     timestamp: Some(uhlc::Timestamp::new(
                         Default::default(),
                         uhlc::ID::new(16, [1u8; uhlc::ID::MAX_SIZE]),
@@ -100,6 +102,8 @@ fn criterion_benchmark(c: &mut Criterion) {
         source_sn: Some(12345),
         first_router_id: Some(PeerId::new(16, [0u8; PeerId::MAX_SIZE])),
         first_router_sn: Some(12345),
+        digest: None,
+        provenance: None,
         timestamp: Some(uhlc::Timestamp::new(
             Default::default(),
             uhlc::ID::new(16, [0u8; uhlc::ID::MAX_SIZE]),
@@ -16,7 +16,7 @@ extern crate criterion;
 use async_std::sync::Arc;
 use criterion::{BenchmarkId, Criterion};
 use zenoh::net::protocol::core::{
-    whatami, CongestionControl, PeerId, Reliability, SubInfo, SubMode,
+    whatami, CongestionControl, History, PeerId, Reliability, SubInfo, SubMode,
 };
 use zenoh::net::protocol::io::ZBuf;
 use zenoh::net::protocol::session::DummyPrimitives;
@@ -51,6 +51,8 @@ fn tables_bench(c: &mut Criterion) {
         reliability: Reliability::Reliable,
         mode: SubMode::Push,
         period: None,
+        history: History::None,
+        filter: None,
     };
 
     for p in [8, 32, 256, 1024, 8192].iter() {
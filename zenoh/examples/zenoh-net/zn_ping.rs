@@ -42,6 +42,8 @@ async fn main() {
         reliability: Reliability::Reliable,
         mode: SubMode::Push,
         period: None,
+        history: History::None,
+        filter: None,
     };
     let mut sub = session
         .declare_subscriber(&reskey_pong, &sub_info)
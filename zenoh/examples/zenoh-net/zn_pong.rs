@@ -43,6 +43,8 @@ async fn main() {
         reliability: Reliability::Reliable,
         mode: SubMode::Push,
         period: None,
+        history: History::None,
+        filter: None,
     };
     let mut sub = session
         .declare_subscriber(&reskey_ping, &sub_info)
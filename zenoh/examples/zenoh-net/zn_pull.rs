@@ -32,6 +32,8 @@ async fn main() {
         reliability: Reliability::Reliable,
         mode: SubMode::Pull,
         period: None,
+        history: History::None,
+        filter: None,
     };
 
     let mut subscriber = session
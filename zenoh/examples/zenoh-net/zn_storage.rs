@@ -38,6 +38,8 @@ async fn main() {
         reliability: Reliability::Reliable,
         mode: SubMode::Push,
         period: None,
+        history: History::None,
+        filter: None,
     };
 
     println!("Declaring Subscriber on {}", selector);
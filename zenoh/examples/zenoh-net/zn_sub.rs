@@ -33,6 +33,8 @@ async fn main() {
         reliability: Reliability::Reliable,
         mode: SubMode::Push,
         period: None,
+        history: History::None,
+        filter: None,
     };
 
     let mut subscriber = session
@@ -37,6 +37,8 @@ fn main() {
         reliability: Reliability::Reliable,
         mode: SubMode::Push,
         period: None,
+        history: History::None,
+        filter: None,
     };
     let mut nm = 0;
     let _sub = session
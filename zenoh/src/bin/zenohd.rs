@@ -11,14 +11,19 @@
 // Contributors:
 //   ADLINK zenoh team, <zenoh@adlink-labs.tech>
 //
-use async_std::future;
+use async_std::prelude::*;
 use async_std::task;
-use clap::{App, Arg, Values};
+use clap::{App, Arg, ArgMatches, Shell, Values};
 use git_version::git_version;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use zenoh::net::plugins::PluginsMgr;
-use zenoh::net::runtime::{AdminSpace, Runtime};
+use zenoh::net::runtime::{liveness, logger, AdminSpace, Runtime};
 use zenoh_util::properties::config::*;
-use zenoh_util::properties::Properties;
+use zenoh_util::properties::{KeyTranscoder, Properties};
 use zenoh_util::LibLoader;
 
 const GIT_VERSION: &str = git_version!(prefix = "v", cargo_prefix = "v");
@@ -28,6 +33,9 @@ lazy_static::lazy_static!(
 );
 
 const DEFAULT_LISTENER: &str = "tcp/0.0.0.0:7447";
+const CONFIG_ENV_PREFIX: &str = "ZENOHD_";
+const CONFIG_WATCH_PERIOD: Duration = Duration::from_millis(1000);
+const DEFAULT_SHUTDOWN_TIMEOUT: &str = "10";
 
 fn get_plugin_search_dirs_from_args() -> Vec<String> {
     let mut result: Vec<String> = vec![];
@@ -59,12 +67,291 @@ fn get_plugins_from_args() -> Vec<String> {
     result
 }
 
+// Where each configuration value comes from: (source, detail)
+type Origins = HashMap<u64, (&'static str, Option<String>)>;
+
+/// Builds the configuration from the configuration file, the environment variables
+/// and the command line arguments (in increasing order of precedence).
+fn build_config(args: &ArgMatches) -> Result<(ConfigProperties, Origins), String> {
+    let mut origins = Origins::new();
+
+    let mut config = if let Some(conf_file) = args.value_of("config") {
+        let content = std::fs::read_to_string(conf_file)
+            .map_err(|e| format!("Unable to read configuration file {}: {}", conf_file, e))?;
+        let config: ConfigProperties = Properties::from(content).into();
+        for key in config.keys() {
+            origins.insert(*key, ("file", Some(conf_file.to_string())));
+        }
+        config
+    } else {
+        ConfigProperties::default()
+    };
+    for (key, value) in ConfigProperties::from_env(CONFIG_ENV_PREFIX).0 {
+        let var = ConfigTranscoder::decode(key)
+            .map(|name| format!("{}{}", CONFIG_ENV_PREFIX, name.to_uppercase()));
+        origins.insert(key, ("env", var));
+        config.insert(key, value);
+    }
+
+    config.insert(ZN_MODE_KEY, "router".to_string());
+    origins.insert(ZN_MODE_KEY, ("cli", Some("zenohd".to_string())));
+
+    let mut peer = args
+        .values_of("peer")
+        .or_else(|| Some(Values::default()))
+        .unwrap()
+        .collect::<Vec<&str>>()
+        .join(",");
+    if !peer.is_empty() {
+        origins.insert(ZN_PEER_KEY, ("cli", Some("--peer".to_string())));
+    }
+    if let Some(val) = config.get(&ZN_PEER_KEY) {
+        peer.push(',');
+        peer.push_str(val);
+    }
+    config.insert(ZN_PEER_KEY, peer);
+
+    let mut listener = args
+        .values_of("listener")
+        .or_else(|| Some(Values::default()))
+        .unwrap()
+        .collect::<Vec<&str>>()
+        .join(",");
+    if args.occurrences_of("listener") > 0 {
+        origins.insert(ZN_LISTENER_KEY, ("cli", Some("--listener".to_string())));
+    } else if !config.contains_key(&ZN_LISTENER_KEY) {
+        origins.insert(ZN_LISTENER_KEY, ("default", Some("zenohd".to_string())));
+    }
+    if let Some(val) = config.get(&ZN_LISTENER_KEY) {
+        if listener == DEFAULT_LISTENER {
+            listener.clear();
+        }
+        listener.push(',');
+        listener.push_str(val);
+    }
+    config.insert(ZN_LISTENER_KEY, listener);
+
+    if args.is_present("no-timestamp") {
+        config.insert(ZN_ADD_TIMESTAMP_KEY, ZN_FALSE.to_string());
+        origins.insert(
+            ZN_ADD_TIMESTAMP_KEY,
+            ("cli", Some("--no-timestamp".to_string())),
+        );
+    } else if !config.contains_key(&ZN_ADD_TIMESTAMP_KEY) {
+        config.insert(ZN_ADD_TIMESTAMP_KEY, ZN_TRUE.to_string());
+        origins.insert(
+            ZN_ADD_TIMESTAMP_KEY,
+            ("default", Some("zenohd".to_string())),
+        );
+    }
+
+    if args.is_present("no-multicast-scouting") {
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        origins.insert(
+            ZN_MULTICAST_SCOUTING_KEY,
+            ("cli", Some("--no-multicast-scouting".to_string())),
+        );
+    } else if !config.contains_key(&ZN_MULTICAST_SCOUTING_KEY) {
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_TRUE.to_string());
+        origins.insert(
+            ZN_MULTICAST_SCOUTING_KEY,
+            ("default", Some("zenohd".to_string())),
+        );
+    }
+
+    Ok((config, origins))
+}
+
+/// Prints the effective value of each configuration key as JSON, with where it comes from.
+fn print_config(config: &ConfigProperties, origins: &Origins) {
+    let mut effective = serde_json::Map::new();
+    for (key, name) in config_keys() {
+        let (value, source, detail) = match config.get(&key) {
+            Some(value) => {
+                let (source, detail) = origins
+                    .get(&key)
+                    .cloned()
+                    .unwrap_or(("default", Some("zenohd".to_string())));
+                (&value[..], source, detail)
+            }
+            None => match config_default(key, "router") {
+                Some(value) => (value, "default", None),
+                None => continue,
+            },
+        };
+        let value = if is_secret(key) { "*****" } else { value };
+        effective.insert(
+            name,
+            json!({ "value": value, "origin": source, "detail": detail }),
+        );
+    }
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&serde_json::Value::Object(effective)).unwrap()
+    );
+}
+
+#[cfg(unix)]
+fn register_reload_signal(flag: Arc<AtomicBool>) {
+    if let Err(e) = signal_hook::flag::register(signal_hook::consts::SIGHUP, flag) {
+        log::warn!("Unable to handle SIGHUP: {}", e);
+    }
+}
+
+#[cfg(not(unix))]
+fn register_reload_signal(_flag: Arc<AtomicBool>) {}
+
+#[cfg(unix)]
+fn register_shutdown_signal(flag: Arc<AtomicBool>) {
+    if let Err(e) = signal_hook::flag::register(signal_hook::consts::SIGTERM, flag) {
+        log::warn!("Unable to handle SIGTERM: {}", e);
+    }
+}
+
+#[cfg(not(unix))]
+fn register_shutdown_signal(_flag: Arc<AtomicBool>) {}
+
+/// Notifies the service manager of `state` when zenohd runs as a systemd service
+/// (see sd_notify(3)), e.g. `"READY=1"`.
+#[cfg(unix)]
+fn sd_notify(state: &str) {
+    use std::os::unix::net::UnixDatagram;
+    let path = match std::env::var("NOTIFY_SOCKET") {
+        Ok(path) => path,
+        Err(_) => return,
+    };
+    let res = UnixDatagram::unbound().and_then(|socket| match path.strip_prefix('@') {
+        // an abstract socket
+        #[cfg(target_os = "linux")]
+        Some(name) => {
+            use std::os::linux::net::SocketAddrExt;
+            let addr = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+            socket.send_to_addr(state.as_bytes(), &addr)
+        }
+        _ => socket.send_to(state.as_bytes(), &path),
+    });
+    if let Err(e) = res {
+        log::warn!("Unable to notify systemd of {}: {}", state, e);
+    }
+}
+
+#[cfg(not(unix))]
+fn sd_notify(_state: &str) {}
+
+/// Returns the period of the notifications expected by the systemd watchdog, if enabled
+/// for zenohd (see sd_watchdog_enabled(3)).
+fn systemd_watchdog_period() -> Option<Duration> {
+    if let Ok(pid) = std::env::var("WATCHDOG_PID") {
+        if pid.parse::<u32>().ok() != Some(std::process::id()) {
+            return None;
+        }
+    }
+    std::env::var("WATCHDOG_USEC")
+        .ok()?
+        .parse::<u64>()
+        .ok()
+        .filter(|usec| *usec > 0)
+        .map(Duration::from_micros)
+}
+
+/// Checks the liveness of zenohd every `period` (see [`liveness::check`]),
+/// and notifies the systemd watchdog as long as it's alive.
+async fn watchdog(runtime: Runtime, plugins_mgr: Arc<PluginsMgr>, period: Duration) {
+    loop {
+        match liveness::check(&runtime, &plugins_mgr).await {
+            Ok(()) => sd_notify("WATCHDOG=1"),
+            Err(reasons) => log::error!("zenohd is not alive: {}", reasons.join("; ")),
+        }
+        task::sleep(period).await;
+    }
+}
+
+/// Reloads the configuration when the configuration file is modified or when SIGHUP is received,
+/// and applies the listeners and peers changes to the runtime. Returns when `shutdown` is set.
+async fn watch_config(runtime: &Runtime, args: &ArgMatches<'_>, shutdown: &AtomicBool) {
+    let conf_file = args.value_of("config");
+    let reload = Arc::new(AtomicBool::new(false));
+    register_reload_signal(reload.clone());
+
+    let modified = || conf_file.and_then(|f| std::fs::metadata(f).and_then(|m| m.modified()).ok());
+    let mut last_modified = modified();
+    loop {
+        task::sleep(CONFIG_WATCH_PERIOD).await;
+        if shutdown.load(Ordering::Acquire) {
+            return;
+        }
+        let current = modified();
+        if !reload.swap(false, Ordering::AcqRel) && current == last_modified {
+            continue;
+        }
+        last_modified = current;
+        let conf_file = match conf_file {
+            Some(conf_file) => conf_file,
+            None => {
+                log::warn!("No configuration file to reload (see --config)");
+                continue;
+            }
+        };
+
+        log::info!("Reloading configuration file {}", conf_file);
+        let (config, origins) = match build_config(args) {
+            Ok(res) => res,
+            Err(e) => {
+                log::error!("{}", e);
+                continue;
+            }
+        };
+        match runtime.reload_config(config).await {
+            Ok(ignored) => {
+                for key in [ZN_LISTENER_KEY, ZN_PEER_KEY].iter() {
+                    if let Some((source, detail)) = origins.get(key) {
+                        runtime.set_config_origin(*key, source, detail.clone());
+                    }
+                }
+                for key in ignored {
+                    log::warn!(
+                        "Configuration key {} changed but requires a restart to be applied",
+                        ConfigTranscoder::decode(key).unwrap_or_else(|| key.to_string())
+                    );
+                }
+            }
+            Err(e) => log::error!("Unable to apply configuration file {}: {}", conf_file, e),
+        }
+    }
+}
+
+/// Leaves the system gracefully: stops accepting new sessions and closes all the sessions
+/// (see [`Runtime::drain`]), then stops the plugins so that they flush their state.
+/// Gives up on what is still pending after `timeout`.
+async fn shutdown(runtime: &Runtime, plugins_mgr: Arc<PluginsMgr>, timeout: Duration) {
+    log::info!("Shutting down zenohd (timeout: {:?})", timeout);
+    sd_notify("STOPPING=1");
+    let deadline = Instant::now() + timeout;
+    // Don't wait for the clients to leave: once their session is closed,
+    // they can reconnect elsewhere right away.
+    match runtime.drain(Duration::default()).timeout(timeout).await {
+        Ok(Ok(())) => (),
+        Ok(Err(e)) => log::warn!("Error while closing the sessions: {}", e),
+        Err(_) => log::warn!("Sessions still closing after {:?}", timeout),
+    }
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    if task::spawn_blocking(move || plugins_mgr.stop_plugins())
+        .timeout(remaining)
+        .await
+        .is_err()
+    {
+        log::warn!("Plugins still stopping after {:?}: exiting anyway", timeout);
+    }
+}
+
 fn main() {
     task::block_on(async {
-        #[cfg(feature = "stats")]
-        env_logger::builder().format_timestamp_millis().init();
-        #[cfg(not(feature = "stats"))]
-        env_logger::init();
+        // the filter of the logger can be changed via the admin space
+        let mut builder = env_logger::Builder::new();
+        if cfg!(feature = "stats") {
+            builder.format_timestamp_millis();
+        }
+        logger::init(builder).unwrap();
 
         log::debug!("zenohd {}", *LONG_VERSION);
 
@@ -79,6 +366,17 @@ fn main() {
         let app = App::new("The zenoh router")
             .version(GIT_VERSION)
             .long_version(LONG_VERSION.as_str())
+            .after_help(
+                "Any configuration key can also be set via an environment variable named \
+                ZENOHD_<KEY> in upper case (ex: ZENOHD_MULTICAST_SCOUTING=false). \
+                Environment variables override the configuration file, \
+                and command line options override both. \
+                The listeners and peers are reloaded from the configuration file when it changes \
+                or when zenohd receives SIGHUP; other changes require a restart. \
+                On SIGTERM, zenohd closes its sessions and stops its plugins before exiting. \
+                When run as a systemd service of type notify, zenohd notifies systemd \
+                when it's ready, alive (see --watchdog) and stopping.",
+            )
             .arg(Arg::from_usage(
                 "-c, --config=[FILE] \
              'The configuration file.'",
@@ -86,12 +384,16 @@ fn main() {
             .arg(Arg::from_usage(
                 "-l, --listener=[LOCATOR]... \
              'A locator on which this router will listen for incoming sessions. \
+             Templates such as tcp/iface:eth0:7447 or udp/auto expand to one locator per interface address. \
+             The lease, keep alive and batch size of the accepted links can be overridden with metadata, \
+             e.g.: tcp/0.0.0.0:7447#lease=1000;keep_alive=250;batch=16384. \
              Repeat this option to open several listeners.'",
                 ).default_value(DEFAULT_LISTENER),
             )
             .arg(Arg::from_usage(
                 "-e, --peer=[LOCATOR]... \
             'A peer locator this router will try to connect to. \
+            Metadata can override the session parameters as for the listeners, e.g.: tcp/10.0.0.1:7447#lease=1000. \
             Repeat this option to connect to several peers.'",
             ))
             .arg(Arg::from_usage(
@@ -118,7 +420,28 @@ fn main() {
                 "--no-multicast-scouting \
              'By default zenohd replies to multicast scouting messages for being discovered by peers and clients. 
               This option disables this feature.'",
-        ));
+        )).arg(Arg::from_usage(
+                "--completions=[SHELL] \
+             'Print a completion script for the given shell and exit.'",
+            ).possible_values(&Shell::variants()),
+            )
+            .arg(Arg::from_usage(
+                "--shutdown-timeout=[SECONDS] \
+             'On SIGTERM, the maximum time to wait for the clients to leave \
+             and for the plugins to flush their state before exiting.'",
+            ).default_value(DEFAULT_SHUTDOWN_TIMEOUT),
+            )
+            .arg(Arg::from_usage(
+                "--watchdog=[SECONDS] \
+             'Check the liveness of zenohd every SECONDS (its routing tables and sessions respond, \
+             it listens on a locator and its plugins are loaded), and notify the systemd watchdog \
+             while alive. By default, half of WatchdogSec when the systemd watchdog is enabled.'",
+            ))
+            .arg(Arg::from_usage(
+                "--print-config=[FORMAT] \
+             'Print the fully merged configuration (file, environment, command line and defaults) and exit.'",
+            ).possible_values(&["json"]),
+            );
 
         // Get plugins search directories from the command line, and create LibLoader
         let plugin_search_dirs = get_plugin_search_dirs_from_args();
@@ -138,62 +461,53 @@ fn main() {
         }
 
         // Add plugins' expected args and parse command line
-        let args = app.args(&plugins_mgr.get_plugins_args()).get_matches();
-
-        let mut config = if let Some(conf_file) = args.value_of("config") {
-            Properties::from(std::fs::read_to_string(conf_file).unwrap()).into()
-        } else {
-            ConfigProperties::default()
-        };
+        let mut app = app.args(&plugins_mgr.get_plugins_args());
+        let args = app.clone().get_matches();
 
-        config.insert(ZN_MODE_KEY, "router".to_string());
-
-        let mut peer = args
-            .values_of("peer")
-            .or_else(|| Some(Values::default()))
-            .unwrap()
-            .collect::<Vec<&str>>()
-            .join(",");
-        if let Some(val) = config.get(&ZN_PEER_KEY) {
-            peer.push(',');
-            peer.push_str(val);
+        if let Some(shell) = args.value_of("completions") {
+            app.gen_completions_to("zenohd", shell.parse().unwrap(), &mut std::io::stdout());
+            return;
         }
-        config.insert(ZN_PEER_KEY, peer);
 
-        let mut listener = args
-            .values_of("listener")
-            .or_else(|| Some(Values::default()))
-            .unwrap()
-            .collect::<Vec<&str>>()
-            .join(",");
-        if let Some(val) = config.get(&ZN_LISTENER_KEY) {
-            if listener == DEFAULT_LISTENER {
-                listener.clear();
-            }
-            listener.push(',');
-            listener.push_str(val);
-        }
-        config.insert(ZN_LISTENER_KEY, listener);
+        let (config, origins) = build_config(&args).unwrap_or_else(|e| {
+            println!("{}. Exiting...", e);
+            std::process::exit(-1);
+        });
 
-        config.insert(
-            ZN_ADD_TIMESTAMP_KEY,
-            if args.is_present("no-timestamp") {
-                ZN_FALSE.to_string()
-            } else {
-                ZN_TRUE.to_string()
-            },
-        );
+        log::debug!("Config: {:?}", &config);
 
-        config.insert(
-            ZN_MULTICAST_SCOUTING_KEY,
-            if args.is_present("no-multicast-scouting") {
-                ZN_FALSE.to_string()
-            } else {
-                ZN_TRUE.to_string()
-            },
-        );
+        if args.is_present("print-config") {
+            print_config(&config, &origins);
+            return;
+        }
 
-        log::debug!("Config: {:?}", &config);
+        let shutdown_timeout = args
+            .value_of("shutdown-timeout")
+            .unwrap()
+            .parse::<f64>()
+            .ok()
+            .filter(|secs| *secs >= 0.0)
+            .map(Duration::from_secs_f64)
+            .unwrap_or_else(|| {
+                println!("Invalid --shutdown-timeout. Exiting...");
+                std::process::exit(-1);
+            });
+        let watchdog_period = match args.value_of("watchdog") {
+            Some(secs) => Some(
+                secs.parse::<f64>()
+                    .ok()
+                    .filter(|secs| *secs > 0.0)
+                    .map(Duration::from_secs_f64)
+                    .unwrap_or_else(|| {
+                        println!("Invalid --watchdog. Exiting...");
+                        std::process::exit(-1);
+                    }),
+            ),
+            None => systemd_watchdog_period().map(|period| period / 2),
+        };
+        // Registered early, not to be killed by SIGTERM while starting
+        let shutdown_requested = Arc::new(AtomicBool::new(false));
+        register_shutdown_signal(shutdown_requested.clone());
 
         let runtime = match Runtime::new(0, config, args.value_of("id")).await {
             Ok(runtime) => runtime,
@@ -202,11 +516,32 @@ fn main() {
                 std::process::exit(-1);
             }
         };
+        for (key, (source, detail)) in origins {
+            runtime.set_config_origin(key, source, detail);
+        }
 
         plugins_mgr.start_plugins(&runtime, &args).await;
 
-        AdminSpace::start(&runtime, plugins_mgr, LONG_VERSION.clone()).await;
+        let plugins_mgr = Arc::new(plugins_mgr);
+        AdminSpace::start(&runtime, plugins_mgr.clone(), LONG_VERSION.clone()).await;
+
+        // The listeners are bound and the plugins loaded
+        sd_notify("READY=1");
+        if let Some(period) = watchdog_period {
+            task::spawn(watchdog(runtime.clone(), plugins_mgr.clone(), period));
+        }
+
+        // A drain requested via the admin space terminates zenohd once completed
+        let drain_events = runtime.drain_events();
+        let drained = shutdown_requested.clone();
+        task::spawn(async move {
+            if drain_events.recv_async().await.is_ok() {
+                drained.store(true, Ordering::Release);
+            }
+        });
+
+        watch_config(&runtime, &args, &shutdown_requested).await;
 
-        future::pending::<()>().await;
+        shutdown(&runtime, plugins_mgr, shutdown_timeout).await;
     });
 }
@@ -19,6 +19,7 @@ use zenoh_util::properties::{IntKeyProperties, KeyTranscoder};
 pub const ZN_INFO_PID_KEY: u64 = 0x00;
 pub const ZN_INFO_PEER_PID_KEY: u64 = 0x01;
 pub const ZN_INFO_ROUTER_PID_KEY: u64 = 0x02;
+pub const ZN_INFO_DIGEST_MISMATCHES_KEY: u64 = 0x03;
 
 /// A transcoder for [InfoProperties](InfoProperties)
 /// able to convert string keys to int keys and reverse.
@@ -29,6 +30,7 @@ impl KeyTranscoder for InfoTranscoder {
             "info_pid" => Some(ZN_INFO_PID_KEY),
             "info_peer_pid" => Some(ZN_INFO_PEER_PID_KEY),
             "info_router_pid" => Some(ZN_INFO_ROUTER_PID_KEY),
+            "info_digest_mismatches" => Some(ZN_INFO_DIGEST_MISMATCHES_KEY),
             _ => None,
         }
     }
@@ -38,6 +40,7 @@ impl KeyTranscoder for InfoTranscoder {
             0x00 => Some("info_pid".to_string()),
             0x01 => Some("info_peer_pid".to_string()),
             0x02 => Some("info_router_pid".to_string()),
+            0x03 => Some("info_digest_mismatches".to_string()),
             key => Some(key.to_string()),
         }
     }
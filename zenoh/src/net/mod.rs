@@ -39,7 +39,9 @@
 //!     let sub_info = SubInfo {
 //!         reliability: Reliability::Reliable,
 //!         mode: SubMode::Push,
-//!         period: None
+//!         period: None,
+//!         history: History::None,
+//!         filter: None,
 //!     };
 //!     let mut subscriber = session.declare_subscriber(&"/resource/name".into(), &sub_info).await.unwrap();
 //!     while let Some(sample) = subscriber.receiver().next().await { println!("Received : {:?}", sample); };
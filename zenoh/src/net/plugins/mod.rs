@@ -26,6 +26,8 @@ zconfigurable! {
 pub struct PluginsMgr {
     pub lib_loader: LibLoader,
     pub plugins: Vec<Plugin>,
+    /// The plugins that failed to load, with the reason of the failure.
+    pub failed_plugins: Vec<(PathBuf, String)>,
 }
 
 impl PluginsMgr {
@@ -33,12 +35,14 @@ impl PluginsMgr {
         PluginsMgr {
             lib_loader,
             plugins: vec![],
+            failed_plugins: vec![],
         }
     }
 
     pub async fn search_and_load_plugins(&mut self) {
         let libs = unsafe { self.lib_loader.load_all_with_prefix(Some(&*PLUGIN_PREFIX)) };
         for lib in libs {
+            let path = lib.1.clone();
             match Plugin::new(lib.0, lib.1, lib.2) {
                 Ok(plugin) => {
                     debug!(
@@ -48,7 +52,10 @@ impl PluginsMgr {
                     );
                     self.plugins.push(plugin);
                 }
-                Err(err) => warn!("{}", err),
+                Err(err) => {
+                    warn!("{}", err);
+                    self.failed_plugins.push((path, err.to_string()));
+                }
             }
         }
     }
@@ -89,6 +96,13 @@ impl PluginsMgr {
             plugin.start(runtime.clone(), args);
         }
     }
+
+    /// Stops the plugins having a `stop()` operation, in the reverse order of their start.
+    pub fn stop_plugins(&self) {
+        for plugin in self.plugins.iter().rev() {
+            plugin.stop();
+        }
+    }
 }
 
 impl Default for PluginsMgr {
@@ -106,9 +120,11 @@ pub struct Plugin {
 
 const START_FN_NAME: &[u8; 6] = b"start\0";
 const GET_ARGS_FN_NAME: &[u8; 18] = b"get_expected_args\0";
+const STOP_FN_NAME: &[u8; 5] = b"stop\0";
 
 type StartFn<'lib> = Symbol<'lib, unsafe extern "C" fn(Runtime, &ArgMatches)>;
 type GetArgsFn<'lib, 'a, 'b> = Symbol<'lib, unsafe extern "C" fn() -> Vec<Arg<'a, 'b>>>;
+type StopFn<'lib> = Symbol<'lib, unsafe extern "C" fn()>;
 
 impl Plugin {
     fn new(lib: Library, path: PathBuf, name: String) -> ZResult<Plugin> {
@@ -150,4 +166,18 @@ impl Plugin {
             start(runtime, args)
         }
     }
+
+    /// Calls the optional `stop()` operation of the plugin, which returns once the plugin
+    /// has released its resources (ex: flushed its storages).
+    pub fn stop(&self) {
+        unsafe {
+            match self.lib.get::<StopFn>(STOP_FN_NAME) {
+                Ok(stop) => {
+                    debug!("Stop plugin {}", self.name);
+                    stop()
+                }
+                Err(_) => trace!("Plugin {} has no stop() operation", self.name),
+            }
+        }
+    }
 }
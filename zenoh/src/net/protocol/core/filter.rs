@@ -0,0 +1,205 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+use zenoh_util::core::{ZError, ZErrorKind, ZResult};
+use zenoh_util::{zerror, zerror2};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+// The operators, longest first for the parsing.
+const OPERATORS: [(&str, Operator); 6] = [
+    ("!=", Operator::Ne),
+    ("<=", Operator::Le),
+    (">=", Operator::Ge),
+    ("=", Operator::Eq),
+    ("<", Operator::Lt),
+    (">", Operator::Gt),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+struct Predicate {
+    field: Vec<String>,
+    operator: Operator,
+    value: Value,
+}
+
+impl Predicate {
+    fn parse(s: &str) -> ZResult<Predicate> {
+        let (pos, op, operator) = OPERATORS
+            .iter()
+            .filter_map(|(op, operator)| s.find(op).map(|pos| (pos, *op, *operator)))
+            .min_by_key(|(pos, op, _)| (*pos, usize::MAX - op.len()))
+            .ok_or_else(|| {
+                zerror2!(ZErrorKind::InvalidSelector {
+                    selector: format!("no comparison operator in predicate '{}'", s)
+                })
+            })?;
+        let field = s[..pos].trim();
+        if field.is_empty() {
+            return zerror!(ZErrorKind::InvalidSelector {
+                selector: format!("no field in predicate '{}'", s)
+            });
+        }
+        let value = s[pos + op.len()..].trim();
+        // a value which is not a JSON literal is a string
+        let value = serde_json::from_str::<Value>(value)
+            .ok()
+            .filter(|v| !v.is_array() && !v.is_object())
+            .unwrap_or_else(|| Value::String(value.to_string()));
+        Ok(Predicate {
+            field: field.split('.').map(|f| f.to_string()).collect(),
+            operator,
+            value,
+        })
+    }
+
+    fn eval(&self, value: &Value) -> bool {
+        let mut field = value;
+        for name in &self.field {
+            field = match field {
+                Value::Object(map) => match map.get(name) {
+                    Some(field) => field,
+                    None => return false,
+                },
+                Value::Array(array) => {
+                    match name.parse::<usize>().ok().and_then(|i| array.get(i)) {
+                        Some(field) => field,
+                        None => return false,
+                    }
+                }
+                _ => return false,
+            };
+        }
+        let ordering = match (field, &self.value) {
+            (Value::Number(n1), Value::Number(n2)) => n1
+                .as_f64()
+                .zip(n2.as_f64())
+                .and_then(|(n1, n2)| n1.partial_cmp(&n2)),
+            (Value::String(s1), Value::String(s2)) => Some(s1.cmp(s2)),
+            (v1, v2) => {
+                if v1 == v2 {
+                    Some(Ordering::Equal)
+                } else {
+                    None
+                }
+            }
+        };
+        match self.operator {
+            Operator::Eq => ordering == Some(Ordering::Equal),
+            Operator::Ne => ordering != Some(Ordering::Equal),
+            Operator::Lt => ordering == Some(Ordering::Less),
+            Operator::Le => matches!(ordering, Some(Ordering::Less) | Some(Ordering::Equal)),
+            Operator::Gt => ordering == Some(Ordering::Greater),
+            Operator::Ge => matches!(ordering, Some(Ordering::Greater) | Some(Ordering::Equal)),
+        }
+    }
+}
+
+/// A filter on the payloads of the samples received by a subscriber.
+///
+/// A filter is a list of predicates separated by `'&'`, with the syntax of the filter part of a
+/// zenoh Selector: `x>1&y<2&z=4`. Each predicate has the form "`field`-`operator`-`value`" where:
+///  * _field_ is the name of a field of the JSON or CBOR payload. The fields of nested objects are
+///    separated by `'.'`, e.g. `position.x`.
+///  * _operator_ is one of the comparison operators: `<` , `>` , `<=` , `>=` , `=` , `!=`
+///  * _value_ is a JSON number, string, boolean or null. Any other value is compared as a string.
+///
+/// A payload matches the filter if it's a JSON object or a CBOR map for which all the predicates
+/// are true. A predicate on a missing field is false.
+#[derive(Clone)]
+pub struct PayloadFilter {
+    expr: String,
+    predicates: Vec<Predicate>,
+}
+
+impl PayloadFilter {
+    /// Returns the expression of this filter.
+    #[inline]
+    pub fn expr(&self) -> &str {
+        &self.expr
+    }
+
+    /// Returns true if `payload` matches this filter.
+    pub fn matches(&self, payload: &[u8]) -> bool {
+        self.matches_value(&PayloadValue::decode(payload))
+    }
+
+    /// Returns true if the decoded `payload` matches this filter.
+    pub fn matches_value(&self, payload: &PayloadValue) -> bool {
+        match &payload.0 {
+            Some(value) => self.predicates.iter().all(|p| p.eval(value)),
+            None => false,
+        }
+    }
+}
+
+/// A payload decoded once to be matched against several [`PayloadFilter`]s.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PayloadValue(Option<Value>);
+
+impl PayloadValue {
+    /// Decodes `payload` as JSON, or else as CBOR. A payload which is neither doesn't match
+    /// any filter.
+    pub fn decode(payload: &[u8]) -> PayloadValue {
+        PayloadValue(
+            serde_json::from_slice::<Value>(payload)
+                .ok()
+                .or_else(|| serde_cbor::from_slice::<Value>(payload).ok()),
+        )
+    }
+}
+
+impl FromStr for PayloadFilter {
+    type Err = ZError;
+
+    fn from_str(s: &str) -> ZResult<PayloadFilter> {
+        let predicates = s
+            .split('&')
+            .map(Predicate::parse)
+            .collect::<ZResult<Vec<Predicate>>>()?;
+        Ok(PayloadFilter {
+            expr: s.to_string(),
+            predicates,
+        })
+    }
+}
+
+impl PartialEq for PayloadFilter {
+    fn eq(&self, other: &PayloadFilter) -> bool {
+        self.expr == other.expr
+    }
+}
+
+impl fmt::Debug for PayloadFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.expr)
+    }
+}
+
+impl fmt::Display for PayloadFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.expr)
+    }
+}
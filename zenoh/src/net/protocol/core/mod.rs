@@ -11,8 +11,11 @@
 // Contributors:
 //   ADLINK zenoh team, <zenoh@adlink-labs.tech>
 //
+mod filter;
 pub mod rname;
 
+pub use filter::{PayloadFilter, PayloadValue};
+
 use std::convert::From;
 use std::fmt;
 use std::hash::{Hash, Hasher};
@@ -20,7 +23,7 @@ use std::str::FromStr;
 use std::sync::atomic::AtomicU64;
 pub use uhlc::Timestamp;
 use zenoh_util::core::{ZError, ZErrorKind};
-use zenoh_util::zerror;
+use zenoh_util::{zerror, zerror2};
 
 pub type TimestampId = uhlc::ID;
 
@@ -228,6 +231,27 @@ impl fmt::Display for PeerId {
     }
 }
 
+impl FromStr for PeerId {
+    type Err = ZError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // filter-out '-' characters (in case s has UUID format)
+        let bytes = hex::decode(s.trim().replace('-', "")).map_err(|e| {
+            zerror2!(ZErrorKind::ValueDecodingFailed {
+                descr: format!("Invalid id: {} - {}", s, e)
+            })
+        })?;
+        if bytes.is_empty() || bytes.len() > PeerId::MAX_SIZE {
+            return zerror!(ZErrorKind::ValueDecodingFailed {
+                descr: format!("Invalid id size: {} ({} bytes max)", s, PeerId::MAX_SIZE)
+            });
+        }
+        let mut id = [0u8; PeerId::MAX_SIZE];
+        id[..bytes.len()].copy_from_slice(&bytes);
+        Ok(PeerId::new(bytes.len(), id))
+    }
+}
+
 // A PeerID can be converted into a Timestamp's ID
 impl From<&PeerId> for uhlc::ID {
     fn from(pid: &PeerId) -> Self {
@@ -284,6 +308,16 @@ pub enum SubMode {
     Pull,
 }
 
+/// The publications a subscriber receives at its declaration, before the new ones.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum History {
+    /// Only the new publications.
+    None,
+    /// The latest values cached by the router the subscriber is connected to,
+    /// if it has a latest value cache covering the subscription.
+    Latest,
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Period {
     pub origin: ZInt,
@@ -296,6 +330,8 @@ pub struct SubInfo {
     pub reliability: Reliability,
     pub mode: SubMode,
     pub period: Option<Period>,
+    pub history: History,
+    pub filter: Option<PayloadFilter>,
 }
 
 impl Default for SubInfo {
@@ -304,6 +340,8 @@ impl Default for SubInfo {
             reliability: Reliability::Reliable,
             mode: SubMode::Push,
             period: None,
+            history: History::None,
+            filter: None,
         }
     }
 }
@@ -323,8 +361,13 @@ pub enum ConsolidationMode {
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct QueryConsolidation {
+    /// The consolidation applied by the routers forwarding the query to other routers,
+    /// starting with the router of the querier.
     pub first_routers: ConsolidationMode,
+    /// The consolidation applied by the last router of the query path, that only forwards
+    /// the query to the queryables of its clients and peers.
     pub last_router: ConsolidationMode,
+    /// The consolidation applied by the querier to the replies it receives.
     pub reception: ConsolidationMode,
 }
 
@@ -351,9 +394,25 @@ impl Default for QueryConsolidation {
 #[derive(Debug, Clone, PartialEq)]
 pub enum Target {
     BestMatching,
-    Complete { n: ZInt },
+    /// Only query the queryables that are complete for the queried resource, i.e. the storages
+    /// declared on a resource that includes it, up to `n` of them on each hop.
+    /// If there are none, all the matching queryables are queried.
+    Complete {
+        n: ZInt,
+    },
     All,
     None,
+    /// Only query the nearest queryable (in number of hops) among the ones that are complete
+    /// for the queried resource, or among all the matching ones if there are none.
+    Nearest,
+    /// Only query `n` of the queryables that are complete for the queried resource, or of all
+    /// the matching ones if there are none, to spread the queries among replicated queryables
+    /// (e.g. replicated storages) instead of querying all of them.
+    /// On each hop, the faces with the fewest pending queries are selected, in turn among
+    /// the equally loaded ones.
+    BestMatchingN {
+        n: ZInt,
+    },
 }
 
 impl Default for Target {
@@ -376,3 +435,22 @@ impl Default for QueryTarget {
         }
     }
 }
+
+/// A page of the replies to a query: each queryable replies with at most `limit` samples,
+/// on the resources that sort after `after` (if any) in lexicographic order.
+///
+/// A queryable honouring pages is expected to reply in lexicographic order of the
+/// resource names, for the next page to resume after the last name of the previous one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryPage {
+    pub limit: ZInt,
+    pub after: Option<String>,
+}
+
+impl QueryPage {
+    /// Returns true if the resource `res_name` sorts after the start of this page.
+    #[inline]
+    pub fn follows(&self, res_name: &str) -> bool {
+        self.after.as_deref().map_or(true, |after| res_name > after)
+    }
+}
@@ -18,14 +18,38 @@ use std::collections::binary_heap::BinaryHeap;
 use std::collections::HashMap;
 use std::fmt;
 use std::mem::align_of;
+#[cfg(target_os = "linux")]
+use std::os::unix::io::RawFd;
 use std::sync::atomic;
 use std::sync::atomic::{AtomicPtr, AtomicUsize};
+#[cfg(target_os = "linux")]
+use std::sync::Mutex;
 use zenoh_util::core::{ZError, ZErrorKind, ZResult};
 use zenoh_util::zerror;
+#[cfg(target_os = "linux")]
+use zenoh_util::{zerror2, zlock};
 
 const MIN_FREE_CHUNK_SIZE: usize = 1_024;
 const ACCOUNTED_OVERHEAD: usize = 4_096;
 const ZENOH_SHM_PREFIX: &str = "zenoh_shm_pid";
+#[cfg(target_os = "linux")]
+const ZENOH_SHM_MEMFD_PREFIX: &str = "zenoh_shm_memfd";
+// The name of a memfd segment, as given by the link of its file descriptor in /proc/self/fd
+#[cfg(target_os = "linux")]
+const MEMFD_NAME_PREFIX: &str = "memfd:";
+// The alignment of the chunks of a memfd segment, each having its reference count at the
+// index offset / MEMFD_CHUNK_ALIGNMENT of the reference counts segment.
+#[cfg(target_os = "linux")]
+const MEMFD_CHUNK_ALIGNMENT: usize = 64;
+// Prevents any new writable mapping of a memfd, while keeping the ones of its creator
+// (F_SEAL_FUTURE_WRITE, since Linux 5.1).
+#[cfg(target_os = "linux")]
+const MEMFD_SEAL_FUTURE_WRITE: libc::c_int = 0x0010;
+#[cfg(windows)]
+const ZENOH_SHM_NAMED_PREFIX: &str = "zenoh_shm_named";
+// The name of a Windows named segment, in the kernel objects namespace of the session
+#[cfg(windows)]
+const NAMED_NAME_PREFIX: &str = "Local\\";
 
 // Chunk header
 type ChunkHeaderType = AtomicUsize;
@@ -199,11 +223,580 @@ impl Clone for SharedMemoryBuf {
     }
 }
 
+/*************************************/
+/*       SHARED MEMORY SEGMENT       */
+/*************************************/
+// A shared memory segment, either opened by path, received as a sealed memfd
+// or opened by name on Windows.
+enum Segment {
+    Shmem(Shmem),
+    #[cfg(target_os = "linux")]
+    Memfd(MemfdSegment),
+    #[cfg(windows)]
+    Named(NamedSegment),
+}
+
+impl Segment {
+    fn as_ptr(&self) -> *mut u8 {
+        match self {
+            Segment::Shmem(shmem) => shmem.as_ptr(),
+            #[cfg(target_os = "linux")]
+            Segment::Memfd(memfd) => memfd.ptr,
+            #[cfg(windows)]
+            Segment::Named(named) => named.ptr,
+        }
+    }
+
+    // Returns the reference count of the chunk at `offset`: its header, but for the memfd
+    // segments whose reference counts are in a segment of their own.
+    fn rc_ptr(&self, offset: usize) -> *mut ChunkHeaderType {
+        match self {
+            #[cfg(target_os = "linux")]
+            Segment::Memfd(memfd) => unsafe { memfd.rc.add(offset / MEMFD_CHUNK_ALIGNMENT) },
+            _ => unsafe { self.as_ptr().add(offset) as *mut ChunkHeaderType },
+        }
+    }
+
+    // Checks that a buffer announced by a peer lies in the segment.
+    fn contains(&self, info: &SharedMemoryBufInfo) -> bool {
+        match self {
+            #[cfg(target_os = "linux")]
+            Segment::Memfd(memfd) => {
+                info.length >= CHUNK_HEADER_SIZE
+                    && info.offset % MEMFD_CHUNK_ALIGNMENT == 0
+                    && info
+                        .offset
+                        .checked_add(info.length)
+                        .map_or(false, |end| end <= memfd.len)
+            }
+            _ => true,
+        }
+    }
+}
+
+// The mappings of a memfd segment and of the segment of its reference counts. The file
+// descriptors themselves are kept by the registry.
+//
+// The processes the segment is passed to can only map it read-only: it is sealed against
+// any new writable mapping. They still update the reference counts of the buffers they
+// read, which are thus in a segment of their own.
+#[cfg(target_os = "linux")]
+struct MemfdSegment {
+    ptr: *mut u8,
+    len: usize,
+    rc: *mut ChunkHeaderType,
+    rc_len: usize,
+}
+
+#[cfg(target_os = "linux")]
+impl MemfdSegment {
+    // Creates a segment of the given size and the segment of its reference counts, and
+    // returns their file descriptors along the mappings.
+    fn create(name: &str, size: usize) -> ZResult<(MemfdSegment, RawFd, RawFd)> {
+        let fd = create_memfd(name, size)?;
+        let ptr = match memfd_mmap(fd, size, libc::PROT_READ | libc::PROT_WRITE) {
+            Ok(ptr) => ptr,
+            Err(e) => {
+                unsafe { libc::close(fd) };
+                return Err(e);
+            }
+        };
+        let seals =
+            libc::F_SEAL_SHRINK | libc::F_SEAL_GROW | MEMFD_SEAL_FUTURE_WRITE | libc::F_SEAL_SEAL;
+        let rc_len = memfd_rc_len(size);
+        let rc = seal_memfd(fd, seals)
+            .and_then(|_| create_sealed_memfd(&format!("{}.rc", name), rc_len))
+            .and_then(|rc_fd| {
+                match memfd_mmap(rc_fd, rc_len, libc::PROT_READ | libc::PROT_WRITE) {
+                    Ok(rc) => Ok((rc_fd, rc)),
+                    Err(e) => {
+                        unsafe { libc::close(rc_fd) };
+                        Err(e)
+                    }
+                }
+            });
+        match rc {
+            Ok((rc_fd, rc)) => Ok((
+                MemfdSegment {
+                    ptr,
+                    len: size,
+                    rc: rc as *mut ChunkHeaderType,
+                    rc_len,
+                },
+                fd,
+                rc_fd,
+            )),
+            Err(e) => {
+                unsafe {
+                    libc::munmap(ptr as *mut libc::c_void, size);
+                    libc::close(fd);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    // Maps a segment received from a peer read-only, and the segment of its reference
+    // counts, provided both are sealed against shrinking and growing: the other end could
+    // otherwise truncate them and crash this process with a SIGBUS when reading them.
+    fn open(fd: RawFd, rc_fd: RawFd) -> ZResult<MemfdSegment> {
+        let len = memfd_sealed_len(fd)?;
+        let rc_len = memfd_sealed_len(rc_fd)?;
+        if rc_len < memfd_rc_len(len) {
+            return zerror!(ZErrorKind::SharedMemoryError {
+                descr: "Refusing to map a memfd with too few reference counts".to_string()
+            });
+        }
+        let ptr = memfd_mmap(fd, len, libc::PROT_READ)?;
+        let rc = match memfd_mmap(rc_fd, rc_len, libc::PROT_READ | libc::PROT_WRITE) {
+            Ok(rc) => rc,
+            Err(e) => {
+                unsafe { libc::munmap(ptr as *mut libc::c_void, len) };
+                return Err(e);
+            }
+        };
+        Ok(MemfdSegment {
+            ptr,
+            len,
+            rc: rc as *mut ChunkHeaderType,
+            rc_len,
+        })
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for MemfdSegment {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.ptr as *mut libc::c_void, self.len);
+            libc::munmap(self.rc as *mut libc::c_void, self.rc_len);
+        }
+    }
+}
+
+// The mapping of a Windows named segment, backed by the paging file. The segment vanishes
+// with the last handle on it, leaving nothing behind when its processes crash.
+#[cfg(windows)]
+struct NamedSegment {
+    handle: winapi::um::winnt::HANDLE,
+    ptr: *mut u8,
+}
+
+#[cfg(windows)]
+impl NamedSegment {
+    fn wide_name(name: &str) -> Vec<u16> {
+        use std::os::windows::ffi::OsStrExt;
+        std::ffi::OsStr::new(name)
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect()
+    }
+
+    fn create(name: &str, size: usize) -> ZResult<NamedSegment> {
+        use winapi::shared::winerror::ERROR_ALREADY_EXISTS;
+        use winapi::um::errhandlingapi::GetLastError;
+        use winapi::um::handleapi::{CloseHandle, INVALID_HANDLE_VALUE};
+        use winapi::um::memoryapi::CreateFileMappingW;
+        use winapi::um::winnt::PAGE_READWRITE;
+
+        let wname = NamedSegment::wide_name(name);
+        let size = size as u64;
+        let handle = unsafe {
+            CreateFileMappingW(
+                INVALID_HANDLE_VALUE,
+                std::ptr::null_mut(),
+                PAGE_READWRITE,
+                (size >> 32) as u32,
+                size as u32,
+                wname.as_ptr(),
+            )
+        };
+        if handle.is_null() {
+            return zerror!(ZErrorKind::SharedMemoryError {
+                descr: format!(
+                    "Unable to create named segment {}: {}",
+                    name,
+                    std::io::Error::last_os_error()
+                )
+            });
+        }
+        if unsafe { GetLastError() } == ERROR_ALREADY_EXISTS {
+            unsafe { CloseHandle(handle) };
+            return zerror!(ZErrorKind::SharedMemoryError {
+                descr: format!("Unable to create named segment {}: already exists", name)
+            });
+        }
+        NamedSegment::map(name, handle)
+    }
+
+    fn open(name: &str) -> ZResult<NamedSegment> {
+        use winapi::um::memoryapi::{OpenFileMappingW, FILE_MAP_ALL_ACCESS};
+
+        let wname = NamedSegment::wide_name(name);
+        let handle = unsafe { OpenFileMappingW(FILE_MAP_ALL_ACCESS, 0, wname.as_ptr()) };
+        if handle.is_null() {
+            return zerror!(ZErrorKind::SharedMemoryError {
+                descr: format!(
+                    "Unable to open named segment {}: {}",
+                    name,
+                    std::io::Error::last_os_error()
+                )
+            });
+        }
+        NamedSegment::map(name, handle)
+    }
+
+    // Maps the whole segment, taking the ownership of `handle`.
+    fn map(name: &str, handle: winapi::um::winnt::HANDLE) -> ZResult<NamedSegment> {
+        use winapi::um::handleapi::CloseHandle;
+        use winapi::um::memoryapi::{MapViewOfFile, FILE_MAP_ALL_ACCESS};
+
+        let ptr = unsafe { MapViewOfFile(handle, FILE_MAP_ALL_ACCESS, 0, 0, 0) };
+        if ptr.is_null() {
+            let e = std::io::Error::last_os_error();
+            unsafe { CloseHandle(handle) };
+            return zerror!(ZErrorKind::SharedMemoryError {
+                descr: format!("Unable to map named segment {}: {}", name, e)
+            });
+        }
+        Ok(NamedSegment {
+            handle,
+            ptr: ptr as *mut u8,
+        })
+    }
+}
+
+#[cfg(windows)]
+impl Drop for NamedSegment {
+    fn drop(&mut self) {
+        use winapi::um::handleapi::CloseHandle;
+        use winapi::um::memoryapi::UnmapViewOfFile;
+
+        unsafe {
+            UnmapViewOfFile(self.ptr as *const winapi::ctypes::c_void);
+            CloseHandle(self.handle);
+        }
+    }
+}
+
+// Creates a memfd of the given size, that can still be sealed.
+#[cfg(target_os = "linux")]
+fn create_memfd(name: &str, size: usize) -> ZResult<RawFd> {
+    let cname = std::ffi::CString::new(name).map_err(|e| {
+        zerror2!(ZErrorKind::SharedMemoryError {
+            descr: format!("Invalid memfd name {}: {}", name, e)
+        })
+    })?;
+    let fd =
+        unsafe { libc::memfd_create(cname.as_ptr(), libc::MFD_CLOEXEC | libc::MFD_ALLOW_SEALING) };
+    if fd < 0 {
+        return zerror!(ZErrorKind::SharedMemoryError {
+            descr: format!(
+                "Unable to create memfd: {}",
+                std::io::Error::last_os_error()
+            )
+        });
+    }
+    if unsafe { libc::ftruncate(fd, size as libc::off_t) } != 0 {
+        let e = std::io::Error::last_os_error();
+        unsafe { libc::close(fd) };
+        return zerror!(ZErrorKind::SharedMemoryError {
+            descr: format!("Unable to size memfd: {}", e)
+        });
+    }
+    Ok(fd)
+}
+
+#[cfg(target_os = "linux")]
+fn seal_memfd(fd: RawFd, seals: libc::c_int) -> ZResult<()> {
+    if unsafe { libc::fcntl(fd, libc::F_ADD_SEALS, seals) } != 0 {
+        return zerror!(ZErrorKind::SharedMemoryError {
+            descr: format!("Unable to seal memfd: {}", std::io::Error::last_os_error())
+        });
+    }
+    Ok(())
+}
+
+// Creates a memfd of the given size, sealed so that the processes it is passed to can
+// neither shrink nor grow it.
+#[cfg(target_os = "linux")]
+fn create_sealed_memfd(name: &str, size: usize) -> ZResult<RawFd> {
+    let fd = create_memfd(name, size)?;
+    let seals = libc::F_SEAL_SHRINK | libc::F_SEAL_GROW | libc::F_SEAL_SEAL;
+    if let Err(e) = seal_memfd(fd, seals) {
+        unsafe { libc::close(fd) };
+        return Err(e);
+    }
+    Ok(fd)
+}
+
+// Returns the size of a memfd, provided it is sealed against shrinking and growing.
+#[cfg(target_os = "linux")]
+fn memfd_sealed_len(fd: RawFd) -> ZResult<usize> {
+    let seals = unsafe { libc::fcntl(fd, libc::F_GET_SEALS) };
+    let required = libc::F_SEAL_SHRINK | libc::F_SEAL_GROW;
+    if seals < 0 || seals & required != required {
+        return zerror!(ZErrorKind::SharedMemoryError {
+            descr: "Refusing to map a memfd that is not sealed against resizing".to_string()
+        });
+    }
+    Ok(memfd_stat(fd)?.st_size as usize)
+}
+
+#[cfg(target_os = "linux")]
+fn memfd_mmap(fd: RawFd, len: usize, prot: libc::c_int) -> ZResult<*mut u8> {
+    let ptr = unsafe { libc::mmap(std::ptr::null_mut(), len, prot, libc::MAP_SHARED, fd, 0) };
+    if ptr == libc::MAP_FAILED {
+        return zerror!(ZErrorKind::SharedMemoryError {
+            descr: format!("Unable to map memfd: {}", std::io::Error::last_os_error())
+        });
+    }
+    Ok(ptr as *mut u8)
+}
+
+// The size of the segment of the reference counts of a memfd segment of the given size.
+#[cfg(target_os = "linux")]
+fn memfd_rc_len(size: usize) -> usize {
+    (size / MEMFD_CHUNK_ALIGNMENT + 1) * CHUNK_HEADER_SIZE
+}
+
+#[cfg(target_os = "linux")]
+fn memfd_stat(fd: RawFd) -> ZResult<libc::stat> {
+    let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+    if unsafe { libc::fstat(fd, &mut stat) } != 0 {
+        return zerror!(ZErrorKind::SharedMemoryError {
+            descr: format!("Unable to stat memfd: {}", std::io::Error::last_os_error())
+        });
+    }
+    Ok(stat)
+}
+
+// Returns the name identifying a memfd segment in the SharedMemoryBufInfos: its name in
+// /proc/self/fd, suffixed with its inode. The name alone is chosen by the process that
+// created the segment, while the inode can't be forged: a peer can't pass a segment of its
+// own in place of the one of another peer.
+#[cfg(target_os = "linux")]
+fn memfd_path(fd: RawFd) -> Option<String> {
+    let name = memfd_name(fd)?;
+    let stat = memfd_stat(fd).ok()?;
+    Some(format!("{}@{}:{}", name, stat.st_dev, stat.st_ino))
+}
+
+// Returns the name of a memfd segment from its file descriptor, if it is a zenoh segment.
+#[cfg(target_os = "linux")]
+fn memfd_name(fd: RawFd) -> Option<String> {
+    let link = std::fs::read_link(format!("/proc/self/fd/{}", fd)).ok()?;
+    let link = link.to_str()?;
+    let name = link.strip_prefix('/')?;
+    let name = name.strip_suffix(" (deleted)").unwrap_or(name);
+    if name.starts_with(MEMFD_NAME_PREFIX)
+        && name[MEMFD_NAME_PREFIX.len()..].starts_with(ZENOH_SHM_MEMFD_PREFIX)
+    {
+        Some(name.to_string())
+    } else {
+        None
+    }
+}
+
+/*************************************/
+/*      MEMFD SEGMENTS REGISTRY      */
+/*************************************/
+// A memfd segment known by this process, identified by its path (see memfd_path),
+// along the segment of its reference counts.
+#[cfg(target_os = "linux")]
+struct MemfdEntry {
+    path: String,
+    fd: RawFd,
+    rc_fd: RawFd,
+    // whether this process created the segment, or received it from a peer
+    own: bool,
+    // whether the segment is passed to the other end before the session is established,
+    // as the one of the SHM authenticator
+    handshake: bool,
+    // the number of links the segment was received on
+    refs: usize,
+}
+
+#[cfg(target_os = "linux")]
+impl MemfdEntry {
+    fn close(self) {
+        unsafe {
+            libc::close(self.fd);
+            libc::close(self.rc_fd);
+        }
+    }
+}
+
+// The memfd segments known by this process: the ones it created and the ones it received.
+// The entries are only appended, the ones of the dropped managers and of the released
+// segments being emptied, so that the links can pass the segments they did not pass yet
+// from the index they reached.
+#[cfg(target_os = "linux")]
+lazy_static! {
+    static ref MEMFD_SEGMENTS: Mutex<Vec<Option<MemfdEntry>>> = Mutex::new(vec![]);
+}
+
+#[cfg(target_os = "linux")]
+fn memfd_lookup(path: &str) -> Option<(RawFd, RawFd)> {
+    zlock!(MEMFD_SEGMENTS)
+        .iter()
+        .flatten()
+        .find(|entry| entry.path == path)
+        .map(|entry| (entry.fd, entry.rc_fd))
+}
+
+/// Returns true if `path` is a memfd segment received from a peer. The buffers of such
+/// segments are copied when forwarded to other peers, which never receive the segment.
+#[cfg(target_os = "linux")]
+pub(crate) fn memfd_is_received(path: &str) -> bool {
+    path.starts_with(MEMFD_NAME_PREFIX)
+        && zlock!(MEMFD_SEGMENTS)
+            .iter()
+            .flatten()
+            .any(|entry| entry.path == path && !entry.own)
+}
+
+#[cfg(target_os = "linux")]
+fn memfd_unregister(path: &str) {
+    for entry in zlock!(MEMFD_SEGMENTS).iter_mut() {
+        if matches!(entry, Some(e) if e.path == path) {
+            if let Some(e) = entry.take() {
+                e.close();
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn memfd_set_handshake(path: &str) {
+    for entry in zlock!(MEMFD_SEGMENTS).iter_mut().flatten() {
+        if entry.path == path {
+            entry.handshake = true;
+        }
+    }
+}
+
+/// Returns duplicates of the file descriptors of the memfd segments created by this process
+/// and registered from `index` on, each followed by the one of its reference counts, at most
+/// `max` file descriptors, and the index to pass the next segments from. With `handshake_only`,
+/// only the segments to pass before the session is established are returned.
+/// The segments received from a peer are never passed to the other ones. The caller closes them.
+#[cfg(target_os = "linux")]
+pub(crate) fn memfd_segments_from(
+    index: usize,
+    max: usize,
+    handshake_only: bool,
+) -> (Vec<RawFd>, usize) {
+    let segments = zlock!(MEMFD_SEGMENTS);
+    let mut fds = vec![];
+    let mut next = index.max(segments.len());
+    for (i, entry) in segments.iter().enumerate().skip(index) {
+        if let Some(entry) = entry {
+            if !entry.own || (handshake_only && !entry.handshake) {
+                continue;
+            }
+            if fds.len() + 2 > max {
+                next = i;
+                break;
+            }
+            let fd = unsafe { libc::dup(entry.fd) };
+            let rc_fd = unsafe { libc::dup(entry.rc_fd) };
+            if fd >= 0 && rc_fd >= 0 {
+                fds.push(fd);
+                fds.push(rc_fd);
+            } else {
+                unsafe {
+                    libc::close(fd);
+                    libc::close(rc_fd);
+                }
+            }
+        }
+    }
+    (fds, next)
+}
+
+/// Registers a memfd segment received from another process along the segment of its
+/// reference counts, taking the ownership of their file descriptors, and returns its path.
+/// The caller releases the segment with [`memfd_release`] when the link it was received on
+/// closes. The file descriptors that are not zenoh segments are closed, and so are the ones
+/// of the segments already known.
+#[cfg(target_os = "linux")]
+pub(crate) fn memfd_register(fd: RawFd, rc_fd: RawFd) -> Option<String> {
+    let path = memfd_path(fd).filter(|_| {
+        let name = memfd_name(fd).map(|name| format!("{}.rc", name));
+        name.is_some() && memfd_name(rc_fd) == name
+    });
+    let path = match path {
+        Some(path) => path,
+        None => {
+            log::debug!("Ignoring received file descriptors that are not a zenoh segment");
+            unsafe {
+                libc::close(fd);
+                libc::close(rc_fd);
+            }
+            return None;
+        }
+    };
+    let mut segments = zlock!(MEMFD_SEGMENTS);
+    let known = segments
+        .iter_mut()
+        .flatten()
+        .find(|entry| entry.path == path)
+        .map(|entry| {
+            if !entry.own {
+                entry.refs += 1;
+            }
+            entry.own
+        });
+    let res = match known {
+        Some(true) => None,
+        Some(false) => Some(path),
+        None => {
+            log::trace!("Registering memfd segment {}", path);
+            segments.push(Some(MemfdEntry {
+                path: path.clone(),
+                fd,
+                rc_fd,
+                own: false,
+                handshake: false,
+                refs: 1,
+            }));
+            return Some(path);
+        }
+    };
+    unsafe {
+        libc::close(fd);
+        libc::close(rc_fd);
+    }
+    res
+}
+
+/// Releases a memfd segment registered with [`memfd_register`], closing its file descriptors
+/// once released by all the links it was received on. The buffers already read from the
+/// segment remain mapped.
+#[cfg(target_os = "linux")]
+pub(crate) fn memfd_release(path: &str) {
+    for entry in zlock!(MEMFD_SEGMENTS).iter_mut() {
+        if let Some(e) = entry {
+            if e.path == path && !e.own {
+                e.refs -= 1;
+                if e.refs == 0 {
+                    log::trace!("Releasing memfd segment {}", path);
+                    if let Some(e) = entry.take() {
+                        e.close();
+                    }
+                }
+                return;
+            }
+        }
+    }
+}
+
 /*************************************/
 /*       SHARED MEMORY READER        */
 /*************************************/
 pub struct SharedMemoryReader {
-    segments: HashMap<String, Shmem>,
+    segments: HashMap<String, Segment>,
 }
 
 unsafe impl Send for SharedMemoryReader {}
@@ -217,9 +810,37 @@ impl SharedMemoryReader {
     }
 
     pub fn connect_map_to_shm(&mut self, info: &SharedMemoryBufInfo) -> ZResult<()> {
+        #[cfg(target_os = "linux")]
+        if info.shm_manager.starts_with(MEMFD_NAME_PREFIX) {
+            let segment = match memfd_lookup(&info.shm_manager) {
+                Some((fd, rc_fd)) => MemfdSegment::open(fd, rc_fd)?,
+                None => {
+                    let e = format!(
+                        "Unable to bind shared memory segment {}: not received",
+                        info.shm_manager
+                    );
+                    log::trace!("{}", e);
+                    return zerror!(ZErrorKind::SharedMemoryError { descr: e });
+                }
+            };
+            self.segments
+                .insert(info.shm_manager.clone(), Segment::Memfd(segment));
+            return Ok(());
+        }
+        #[cfg(windows)]
+        if info.shm_manager.starts_with(NAMED_NAME_PREFIX) {
+            let segment = NamedSegment::open(&info.shm_manager).map_err(|e| {
+                log::trace!("{}", e);
+                e
+            })?;
+            self.segments
+                .insert(info.shm_manager.clone(), Segment::Named(segment));
+            return Ok(());
+        }
         match ShmemConf::new().flink(&info.shm_manager).open() {
             Ok(shm) => {
-                self.segments.insert(info.shm_manager.clone(), shm);
+                self.segments
+                    .insert(info.shm_manager.clone(), Segment::Shmem(shm));
                 Ok(())
             }
             Err(e) => {
@@ -237,9 +858,17 @@ impl SharedMemoryReader {
         // Try read does not increment the reference count as it is assumed
         // that the sender of this buffer has incremented for us.
         match self.segments.get(&info.shm_manager) {
+            Some(shm) if !shm.contains(info) => {
+                let e = format!(
+                    "Invalid buffer in shared memory segment: {}",
+                    info.shm_manager
+                );
+                log::trace!("{}", e);
+                zerror!(ZErrorKind::SharedMemoryError { descr: e })
+            }
             Some(shm) => {
                 let base_ptr = shm.as_ptr();
-                let rc = unsafe { base_ptr.add(info.offset) as *mut ChunkHeaderType };
+                let rc = shm.rc_ptr(info.offset);
                 let rc_ptr = AtomicPtr::<ChunkHeaderType>::new(rc);
                 let buf = unsafe { base_ptr.add(info.offset + CHUNK_HEADER_SIZE) as *mut u8 };
                 let shmb = SharedMemoryBuf {
@@ -288,7 +917,7 @@ pub struct SharedMemoryManager {
     segment_path: String,
     size: usize,
     available: usize,
-    own_segment: Shmem,
+    own_segment: Segment,
     free_list: BinaryHeap<Chunk>,
     busy_list: Vec<Chunk>,
     alignment: usize,
@@ -326,7 +955,89 @@ impl SharedMemoryManager {
                 })
             }
         };
-        let base_ptr = shmem.as_ptr();
+        Ok(SharedMemoryManager::with_segment(
+            path,
+            size,
+            Segment::Shmem(shmem),
+        ))
+    }
+
+    /// Creates a new SharedMemoryManager managing allocations of a region of the
+    /// given size, in a sealed memfd rather than a file.
+    ///
+    /// The segment has no name in the file system: it is only mapped by the processes
+    /// its file descriptor is passed to, over the Unix socket links.
+    #[cfg(target_os = "linux")]
+    pub fn new_memfd(id: String, size: usize) -> ZResult<SharedMemoryManager> {
+        let name = format!("{}_{}_{}", ZENOH_SHM_MEMFD_PREFIX, std::process::id(), id);
+        let real_size = size + ACCOUNTED_OVERHEAD;
+        let (segment, fd, rc_fd) = MemfdSegment::create(&name, real_size)?;
+        let path = match memfd_path(fd) {
+            Some(path) => path,
+            None => {
+                unsafe {
+                    libc::close(fd);
+                    libc::close(rc_fd);
+                }
+                return zerror!(ZErrorKind::SharedMemoryError {
+                    descr: format!("Unable to identify memfd segment {}", name)
+                });
+            }
+        };
+        log::trace!("Creating memfd segment: {}", path);
+        zlock!(MEMFD_SEGMENTS).push(Some(MemfdEntry {
+            path: path.clone(),
+            fd,
+            rc_fd,
+            own: true,
+            handshake: false,
+            refs: 0,
+        }));
+        let mut shm = SharedMemoryManager::with_segment(path, size, Segment::Memfd(segment));
+        shm.alignment = MEMFD_CHUNK_ALIGNMENT;
+        Ok(shm)
+    }
+
+    /// Passes the memfd segment of this manager to the other end of the Unix socket links
+    /// before their session is established, rather than once established with shared memory.
+    /// This is only meant for the segment of the SHM authenticator.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn set_handshake(&self) {
+        if let Segment::Memfd(_) = self.own_segment {
+            memfd_set_handshake(&self.segment_path);
+        }
+    }
+
+    /// Creates a new SharedMemoryManager managing allocations of a region of the
+    /// given size, in a Windows named segment rather than a file.
+    ///
+    /// The segment is backed by the paging file and mapped by name by the other processes
+    /// of the session: it vanishes with the last process mapping it.
+    ///
+    /// Like with the other providers, no watchdog reclaims the chunks still referenced by
+    /// a process that terminated abnormally: they stay allocated until the segment is dropped.
+    #[cfg(windows)]
+    pub fn new_named(id: String, size: usize) -> ZResult<SharedMemoryManager> {
+        let path = format!(
+            "{}{}_{}_{}",
+            NAMED_NAME_PREFIX,
+            ZENOH_SHM_NAMED_PREFIX,
+            std::process::id(),
+            id
+        );
+        let real_size = size + ACCOUNTED_OVERHEAD;
+        log::trace!("Creating named segment: {}", path);
+        let segment = NamedSegment::create(&path, real_size)?;
+        Ok(SharedMemoryManager::with_segment(
+            path,
+            size,
+            Segment::Named(segment),
+        ))
+    }
+
+    fn with_segment(segment_path: String, size: usize, segment: Segment) -> SharedMemoryManager {
+        let real_size = size + ACCOUNTED_OVERHEAD;
+        let base_ptr = segment.as_ptr();
 
         let mut free_list = BinaryHeap::new();
         let chunk = Chunk {
@@ -337,10 +1048,10 @@ impl SharedMemoryManager {
         free_list.push(chunk);
         let busy_list = std::vec::Vec::new();
         let shm = SharedMemoryManager {
-            segment_path: path,
+            segment_path,
             size,
             available: real_size,
-            own_segment: shmem,
+            own_segment: segment,
             free_list,
             busy_list,
             alignment: align_of::<ChunkHeaderType>(),
@@ -349,7 +1060,7 @@ impl SharedMemoryManager {
             "Created SharedMemoryManager for {:?}",
             shm.own_segment.as_ptr()
         );
-        Ok(shm)
+        shm
     }
 
     fn free_chunk_map_to_shmbuf(&self, chunk: &Chunk) -> SharedMemoryBuf {
@@ -359,7 +1070,7 @@ impl SharedMemoryManager {
             shm_manager: self.segment_path.clone(),
             kind: 0,
         };
-        let rc = chunk.base_addr as *mut ChunkHeaderType;
+        let rc = self.own_segment.rc_ptr(chunk.offset);
         unsafe { (*rc).store(1, atomic::Ordering::SeqCst) };
         let rc_ptr = AtomicPtr::<ChunkHeaderType>::new(rc);
         SharedMemoryBuf {
@@ -428,8 +1139,8 @@ impl SharedMemoryManager {
         }
     }
 
-    fn is_free_chunk(chunk: &Chunk) -> bool {
-        let rc_ptr = chunk.base_addr as *mut ChunkHeaderType;
+    fn is_free_chunk(&self, chunk: &Chunk) -> bool {
+        let rc_ptr = self.own_segment.rc_ptr(chunk.offset);
         let rc = unsafe { (*rc_ptr).load(atomic::Ordering::SeqCst) };
         rc == 0
     }
@@ -487,10 +1198,7 @@ impl SharedMemoryManager {
         log::trace!("Running Garbage Collector");
 
         let mut freed = 0;
-        let (free, busy) = self
-            .busy_list
-            .iter()
-            .partition(|&c| SharedMemoryManager::is_free_chunk(c));
+        let (free, busy) = self.busy_list.iter().partition(|&c| self.is_free_chunk(c));
         self.busy_list = busy;
 
         for f in free {
@@ -503,6 +1211,15 @@ impl SharedMemoryManager {
     }
 }
 
+#[cfg(target_os = "linux")]
+impl Drop for SharedMemoryManager {
+    fn drop(&mut self) {
+        if let Segment::Memfd(_) = self.own_segment {
+            memfd_unregister(&self.segment_path);
+        }
+    }
+}
+
 impl fmt::Debug for SharedMemoryManager {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("SharedMemoryManager")
@@ -514,3 +1231,128 @@ impl fmt::Debug for SharedMemoryManager {
             .finish()
     }
 }
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    fn unsealed_memfd(name: &str, size: usize) -> RawFd {
+        let cname = std::ffi::CString::new(name).unwrap();
+        let fd = unsafe { libc::memfd_create(cname.as_ptr(), libc::MFD_CLOEXEC) };
+        assert!(fd >= 0);
+        assert_eq!(unsafe { libc::ftruncate(fd, size as libc::off_t) }, 0);
+        fd
+    }
+
+    // Returns a received segment under `name` along its reference counts
+    fn received_memfd(name: &str, size: usize) -> (RawFd, RawFd) {
+        let fd = create_sealed_memfd(name, size).unwrap();
+        let rc_fd = create_sealed_memfd(&format!("{}.rc", name), memfd_rc_len(size)).unwrap();
+        (fd, rc_fd)
+    }
+
+    #[test]
+    fn memfd_unsealed_rejected() {
+        let rc_fd = create_sealed_memfd("zenoh_shm_memfd_test_rc", memfd_rc_len(4096)).unwrap();
+        let fd = unsealed_memfd("zenoh_shm_memfd_test_unsealed", 4096);
+        assert!(MemfdSegment::open(fd, rc_fd).is_err());
+        unsafe { libc::close(fd) };
+
+        let fd = create_sealed_memfd("zenoh_shm_memfd_test_sealed", 4096).unwrap();
+        assert!(MemfdSegment::open(fd, rc_fd).is_ok());
+        unsafe { libc::close(fd) };
+
+        // the reference counts must cover the whole segment
+        let fd = create_sealed_memfd("zenoh_shm_memfd_test_larger", 2 * 4096).unwrap();
+        assert!(MemfdSegment::open(fd, rc_fd).is_err());
+        unsafe {
+            libc::close(fd);
+            libc::close(rc_fd);
+        }
+    }
+
+    #[test]
+    fn memfd_read_only() {
+        let mut manager =
+            SharedMemoryManager::new_memfd("test_read_only".to_string(), 4096).unwrap();
+        let (fd, rc_fd) = memfd_lookup(&manager.segment_path).unwrap();
+
+        // the segment can't be mapped writable by the processes it is passed to
+        let len = memfd_stat(fd).unwrap().st_size as usize;
+        assert!(memfd_mmap(fd, len, libc::PROT_READ | libc::PROT_WRITE).is_err());
+        let segment = Segment::Memfd(MemfdSegment::open(fd, rc_fd).unwrap());
+
+        // but they update the reference counts of the buffers they read
+        let buf = manager.alloc(64).unwrap();
+        assert!(segment.contains(&buf.info));
+        let rc = segment.rc_ptr(buf.info.offset);
+        unsafe { (*rc).fetch_sub(1, atomic::Ordering::SeqCst) };
+        assert_eq!(buf.ref_count(), 0);
+        std::mem::forget(buf);
+
+        // and the buffers announced out of the segment are refused
+        let info = SharedMemoryBufInfo::new(len, 64, manager.segment_path.clone(), 0);
+        assert!(!segment.contains(&info));
+    }
+
+    #[test]
+    fn memfd_received_segments() {
+        let manager = SharedMemoryManager::new_memfd("test_received".to_string(), 4096).unwrap();
+        let own = manager.segment_path.clone();
+
+        // a segment received under the same name as an own one gets a path of its own
+        let name = own[MEMFD_NAME_PREFIX.len()..own.find('@').unwrap()].to_string();
+        let (fd, rc_fd) = received_memfd(&name, 4096);
+        let received = memfd_path(fd).unwrap();
+        assert_ne!(received, own);
+        let (dup, rc_dup) = unsafe { (libc::dup(fd), libc::dup(rc_fd)) };
+        assert_eq!(memfd_register(fd, rc_fd), Some(received.clone()));
+        assert!(memfd_is_received(&received));
+        assert!(!memfd_is_received(&own));
+        assert_eq!(
+            memfd_lookup(&own).map(|(fd, _)| memfd_path(fd)),
+            Some(Some(own.clone()))
+        );
+
+        // the received segments are not passed to the other peers
+        let (fds, _) = memfd_segments_from(0, usize::MAX, false);
+        let paths: Vec<String> = fds.iter().filter_map(|fd| memfd_path(*fd)).collect();
+        for fd in fds {
+            unsafe { libc::close(fd) };
+        }
+        assert!(paths.contains(&own));
+        assert!(!paths.contains(&received));
+
+        // nor the own ones before the session is established, but for the handshake ones
+        let (fds, _) = memfd_segments_from(0, usize::MAX, true);
+        let paths: Vec<String> = fds.iter().filter_map(|fd| memfd_path(*fd)).collect();
+        for fd in fds {
+            unsafe { libc::close(fd) };
+        }
+        assert!(!paths.contains(&own));
+        manager.set_handshake();
+        let (fds, _) = memfd_segments_from(0, usize::MAX, true);
+        let paths: Vec<String> = fds.iter().filter_map(|fd| memfd_path(*fd)).collect();
+        for fd in fds {
+            unsafe { libc::close(fd) };
+        }
+        assert!(paths.contains(&own));
+
+        // a segment already known is not registered twice, but released by each link
+        let count = zlock!(MEMFD_SEGMENTS).len();
+        assert_eq!(memfd_register(dup, rc_dup), Some(received.clone()));
+        assert_eq!(zlock!(MEMFD_SEGMENTS).len(), count);
+        memfd_release(&received);
+        assert!(memfd_lookup(&received).is_some());
+        memfd_release(&received);
+        assert!(memfd_lookup(&received).is_none());
+
+        // a segment without the segment of its reference counts is not registered
+        let (fd, rc_fd) = received_memfd(&name, 4096);
+        let other = create_sealed_memfd("zenoh_shm_memfd_test_other.rc", 4096).unwrap();
+        let path = memfd_path(fd).unwrap();
+        assert_eq!(memfd_register(fd, other), None);
+        assert!(memfd_lookup(&path).is_none());
+        unsafe { libc::close(rc_fd) };
+    }
+}
@@ -517,9 +517,11 @@ impl ZBuf {
         self.has_shminfo
     }
 
+    /// Returns true if this buffer holds slices mapped from shared memory, as the payloads
+    /// received in shared memory: replying or publishing them keeps them in shared memory.
     #[cfg(feature = "zero-copy")]
     #[inline(always)]
-    pub(crate) fn has_shmbuf(&self) -> bool {
+    pub fn has_shmbuf(&self) -> bool {
         self.has_shmbuf
     }
 }
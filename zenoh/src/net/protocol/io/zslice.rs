@@ -11,6 +11,8 @@
 // Contributors:
 //   ADLINK zenoh team, <zenoh@adlink-labs.tech>
 //
+#[cfg(all(feature = "zero-copy", target_os = "linux"))]
+use super::memfd_is_received;
 #[cfg(feature = "zero-copy")]
 use super::{SharedMemoryBuf, SharedMemoryBufInfo, SharedMemoryReader};
 use std::convert::AsRef;
@@ -309,6 +311,15 @@ impl ZSlice {
     #[inline(never)]
     pub(crate) fn map_to_shminfo(&mut self) -> ZResult<bool> {
         match &self.buf {
+            // The segments received from a peer are not passed to the other ones:
+            // their buffers are copied instead
+            #[cfg(target_os = "linux")]
+            ZSliceBuffer::ShmBuffer(shmb) if memfd_is_received(&shmb.info.shm_manager) => {
+                self.buf = ZSliceBuffer::NetOwnedBuffer(Arc::new(self.as_slice().to_vec()));
+                self.start = 0;
+                self.end = self.buf.len();
+                Ok(false)
+            }
             ZSliceBuffer::ShmBuffer(shmb) => {
                 // Serialize the shmb info
                 let info = shmb.info.serialize()?;
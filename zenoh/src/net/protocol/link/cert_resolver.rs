@@ -0,0 +1,153 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use rustls::internal::pemfile;
+use rustls::sign::{self, CertifiedKey};
+use rustls::{ClientHello, ResolvesClientCert, ResolvesServerCert, SignatureScheme};
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use zenoh_util::core::{ZError, ZErrorKind, ZResult};
+use zenoh_util::{zconfigurable, zerror, zerror2, zlock};
+
+zconfigurable! {
+    // Minimum interval in milliseconds between two checks of the certificate files for a change.
+    static ref TLS_CERT_CHECK_PERIOD: u64 = 1000;
+}
+
+struct LoadedCert {
+    key: CertifiedKey,
+    // the modification times of the certificate and private key files when loaded
+    modified: (Option<SystemTime>, Option<SystemTime>),
+    checked: Instant,
+}
+
+/// A certificate resolver that reloads the certificate and private key files
+/// when they are replaced on disk (e.g. on a certificate renewal), so that the new
+/// connections use them without restarting the listeners.
+/// It resolves the certificate of the TLS servers, and of the TLS clients when configured.
+///
+/// The files are checked for a change at a new connection, at most once every
+/// `TLS_CERT_CHECK_PERIOD`. If the new files are invalid (e.g. not completely written yet),
+/// the previous certificate is kept and the files are checked again at the next connection.
+pub(super) struct ReloadingCertResolver {
+    cert_path: PathBuf,
+    key_path: PathBuf,
+    loaded: Mutex<LoadedCert>,
+}
+
+fn modified(path: &PathBuf) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+impl ReloadingCertResolver {
+    pub(super) fn new(cert_path: &str, key_path: &str) -> ZResult<ReloadingCertResolver> {
+        let cert_path = PathBuf::from(cert_path);
+        let key_path = PathBuf::from(key_path);
+        let modified = (modified(&cert_path), modified(&key_path));
+        let key = ReloadingCertResolver::load(&cert_path, &key_path)?;
+        Ok(ReloadingCertResolver {
+            cert_path,
+            key_path,
+            loaded: Mutex::new(LoadedCert {
+                key,
+                modified,
+                checked: Instant::now(),
+            }),
+        })
+    }
+
+    fn load(cert_path: &PathBuf, key_path: &PathBuf) -> ZResult<CertifiedKey> {
+        let cert = std::fs::read(cert_path).map_err(|e| {
+            zerror2!(ZErrorKind::Other {
+                descr: format!("Invalid TLS certificate file: {}", e)
+            })
+        })?;
+        let certs = pemfile::certs(&mut Cursor::new(cert))
+            .ok()
+            .filter(|certs| !certs.is_empty())
+            .ok_or_else(|| {
+                zerror2!(ZErrorKind::Other {
+                    descr: format!("No certificate found in {}", cert_path.display())
+                })
+            })?;
+
+        let pkey = std::fs::read(key_path).map_err(|e| {
+            zerror2!(ZErrorKind::Other {
+                descr: format!("Invalid TLS private key file: {}", e)
+            })
+        })?;
+        let mut keys = pemfile::pkcs8_private_keys(&mut Cursor::new(&pkey)).unwrap_or_default();
+        if keys.is_empty() {
+            keys = pemfile::rsa_private_keys(&mut Cursor::new(&pkey)).unwrap_or_default();
+        }
+        let key = match keys.first().map(sign::any_supported_type) {
+            Some(Ok(key)) => key,
+            _ => {
+                return zerror!(ZErrorKind::Other {
+                    descr: format!("No valid private key found in {}", key_path.display())
+                })
+            }
+        };
+        Ok(CertifiedKey::new(certs, Arc::new(key)))
+    }
+
+    fn reload_if_changed(&self, loaded: &mut LoadedCert) {
+        let now = Instant::now();
+        if now.duration_since(loaded.checked) < Duration::from_millis(*TLS_CERT_CHECK_PERIOD) {
+            return;
+        }
+        loaded.checked = now;
+        let modified = (modified(&self.cert_path), modified(&self.key_path));
+        if modified == loaded.modified {
+            return;
+        }
+        match ReloadingCertResolver::load(&self.cert_path, &self.key_path) {
+            Ok(key) => {
+                log::info!("Reloaded TLS certificate {}", self.cert_path.display());
+                loaded.key = key;
+                loaded.modified = modified;
+            }
+            Err(e) => log::warn!(
+                "Keep the previous TLS certificate, failed to reload {}: {}",
+                self.cert_path.display(),
+                e
+            ),
+        }
+    }
+}
+
+impl ResolvesServerCert for ReloadingCertResolver {
+    fn resolve(&self, _client_hello: ClientHello) -> Option<CertifiedKey> {
+        let mut loaded = zlock!(self.loaded);
+        self.reload_if_changed(&mut loaded);
+        Some(loaded.key.clone())
+    }
+}
+
+impl ResolvesClientCert for ReloadingCertResolver {
+    fn resolve(
+        &self,
+        _acceptable_issuers: &[&[u8]],
+        _sigschemes: &[SignatureScheme],
+    ) -> Option<CertifiedKey> {
+        let mut loaded = zlock!(self.loaded);
+        self.reload_if_changed(&mut loaded);
+        Some(loaded.key.clone())
+    }
+
+    fn has_certs(&self) -> bool {
+        true
+    }
+}
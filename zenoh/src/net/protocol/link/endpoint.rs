@@ -0,0 +1,202 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use super::super::core::ZInt;
+use super::{Locator, Proxy};
+use std::fmt;
+use std::str::FromStr;
+use zenoh_util::core::{ZError, ZErrorKind, ZResult};
+use zenoh_util::zerror;
+
+/*************************************/
+/*            LINK CONFIG            */
+/*************************************/
+pub const METADATA_SEPARATOR: char = '#';
+const LIST_SEPARATOR: char = ';';
+const FIELD_SEPARATOR: char = '=';
+// Metadata keys
+pub const LEASE_KEY: &str = "lease";
+pub const KEEP_ALIVE_KEY: &str = "keep_alive";
+pub const KEEP_ALIVE_ECHO_KEY: &str = "keep_alive_echo";
+pub const KEEP_ALIVE_FORCED_KEY: &str = "keep_alive_forced";
+pub const BATCH_SIZE_KEY: &str = "batch";
+pub const PROXY_KEY: &str = "proxy";
+
+/// The session parameters overridden for the links of an [`EndPoint`].
+/// The parameters set to `None` take the value configured in the [`SessionManager`](super::super::session::SessionManager).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LinkConfig {
+    /// The lease in milliseconds advertised to the remote peer.
+    pub lease: Option<ZInt>,
+    /// The interval in milliseconds between two keep alive messages.
+    pub keep_alive: Option<ZInt>,
+    /// Whether the keep alive messages carry a token echoed by the peer, to flag the link as
+    /// degraded when the connectivity is one-way only.
+    pub keep_alive_echo: Option<bool>,
+    /// Whether the keep alive messages are sent every keep alive interval even when data is sent
+    /// on the link, e.g. to maintain the bindings of the NATs on the path.
+    pub keep_alive_forced: Option<bool>,
+    /// The maximum size in bytes of the batches sent on the link.
+    pub batch_size: Option<usize>,
+    /// The proxy through which the links are opened (only for outgoing TCP and TLS links).
+    pub proxy: Option<Proxy>,
+}
+
+impl LinkConfig {
+    pub fn is_empty(&self) -> bool {
+        self == &LinkConfig::default()
+    }
+}
+
+fn parse_value<T: FromStr>(key: &str, value: &str) -> ZResult<T> {
+    match value.parse::<T>() {
+        Ok(v) => Ok(v),
+        Err(_) => zerror!(ZErrorKind::InvalidLocator {
+            descr: format!("Invalid value for endpoint metadata {}: {}", key, value)
+        }),
+    }
+}
+
+impl FromStr for LinkConfig {
+    type Err = ZError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut config = LinkConfig::default();
+        for field in s.split(LIST_SEPARATOR).filter(|f| !f.is_empty()) {
+            let (key, value) = match field.find(FIELD_SEPARATOR) {
+                Some(index) => (&field[..index], &field[index + 1..]),
+                None => (field, ""),
+            };
+            match key.trim() {
+                LEASE_KEY => config.lease = Some(parse_value(key, value.trim())?),
+                KEEP_ALIVE_KEY => config.keep_alive = Some(parse_value(key, value.trim())?),
+                KEEP_ALIVE_ECHO_KEY => {
+                    config.keep_alive_echo = Some(parse_value(key, value.trim())?)
+                }
+                KEEP_ALIVE_FORCED_KEY => {
+                    config.keep_alive_forced = Some(parse_value(key, value.trim())?)
+                }
+                BATCH_SIZE_KEY => config.batch_size = Some(parse_value(key, value.trim())?),
+                PROXY_KEY => config.proxy = Some(value.trim().parse()?),
+                _ => {
+                    return zerror!(ZErrorKind::InvalidLocator {
+                        descr: format!("Unknown endpoint metadata: {}", key)
+                    })
+                }
+            }
+        }
+        if config.lease == Some(0) || config.keep_alive == Some(0) || config.batch_size == Some(0) {
+            return zerror!(ZErrorKind::InvalidLocator {
+                descr: format!("Invalid endpoint metadata: {}", s)
+            });
+        }
+        Ok(config)
+    }
+}
+
+impl fmt::Display for LinkConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut fields = vec![];
+        if let Some(lease) = self.lease {
+            fields.push(format!("{}{}{}", LEASE_KEY, FIELD_SEPARATOR, lease));
+        }
+        if let Some(keep_alive) = self.keep_alive {
+            fields.push(format!(
+                "{}{}{}",
+                KEEP_ALIVE_KEY, FIELD_SEPARATOR, keep_alive
+            ));
+        }
+        if let Some(keep_alive_echo) = self.keep_alive_echo {
+            fields.push(format!(
+                "{}{}{}",
+                KEEP_ALIVE_ECHO_KEY, FIELD_SEPARATOR, keep_alive_echo
+            ));
+        }
+        if let Some(keep_alive_forced) = self.keep_alive_forced {
+            fields.push(format!(
+                "{}{}{}",
+                KEEP_ALIVE_FORCED_KEY, FIELD_SEPARATOR, keep_alive_forced
+            ));
+        }
+        if let Some(batch_size) = self.batch_size {
+            fields.push(format!(
+                "{}{}{}",
+                BATCH_SIZE_KEY, FIELD_SEPARATOR, batch_size
+            ));
+        }
+        if let Some(proxy) = &self.proxy {
+            fields.push(format!("{}{}{}", PROXY_KEY, FIELD_SEPARATOR, proxy));
+        }
+        write!(f, "{}", fields.join(&LIST_SEPARATOR.to_string()))
+    }
+}
+
+/*************************************/
+/*             ENDPOINT              */
+/*************************************/
+/// A [`Locator`] with some optional metadata overriding the session parameters of its links,
+/// in the form `<locator>#<key>=<value>;<key>=<value>`. For instance:
+/// `tcp/10.0.0.1:7447#lease=1000;batch=16384`.
+///
+/// The supported keys are `lease` and `keep_alive` (in milliseconds), `keep_alive_echo` and
+/// `keep_alive_forced` (`true` or `false`), `batch` (in bytes) and `proxy`
+/// (e.g. `tls/server:7447#proxy=socks5://10.0.0.2:1080`, see [`Proxy`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EndPoint {
+    pub locator: Locator,
+    pub config: LinkConfig,
+}
+
+impl EndPoint {
+    /// Splits an endpoint string into its locator and its metadata, if any.
+    pub fn split(s: &str) -> (&str, Option<&str>) {
+        match s.find(METADATA_SEPARATOR) {
+            Some(index) => (&s[..index], Some(&s[index + 1..])),
+            None => (s, None),
+        }
+    }
+}
+
+impl From<Locator> for EndPoint {
+    fn from(locator: Locator) -> EndPoint {
+        EndPoint {
+            locator,
+            config: LinkConfig::default(),
+        }
+    }
+}
+
+impl FromStr for EndPoint {
+    type Err = ZError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (locator, metadata) = EndPoint::split(s);
+        Ok(EndPoint {
+            locator: locator.parse()?,
+            config: match metadata {
+                Some(metadata) => metadata.parse()?,
+                None => LinkConfig::default(),
+            },
+        })
+    }
+}
+
+impl fmt::Display for EndPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.config.is_empty() {
+            write!(f, "{}", self.locator)
+        } else {
+            write!(f, "{}{}{}", self.locator, METADATA_SEPARATOR, self.config)
+        }
+    }
+}
@@ -11,6 +11,8 @@
 // Contributors:
 //   ADLINK zenoh team, <zenoh@adlink-labs.tech>
 //
+#[cfg(feature = "transport_mem")]
+use super::mem::{LocatorMem, LocatorPropertyMem};
 #[cfg(feature = "transport_quic")]
 use super::quic::{LocatorPropertyQuic, LocatorQuic};
 #[cfg(feature = "transport_tcp")]
@@ -24,6 +26,7 @@ use super::unixsock_stream::{LocatorPropertyUnixSocketStream, LocatorUnixSocketS
 use std::cmp::PartialEq;
 use std::fmt;
 use std::hash::Hash;
+use std::net::{IpAddr, SocketAddr};
 use std::str::FromStr;
 use zenoh_util::core::{ZError, ZErrorKind, ZResult};
 use zenoh_util::properties::config::ConfigProperties;
@@ -44,6 +47,8 @@ pub const STR_TLS: &str = "tls";
 pub const STR_QUIC: &str = "quic";
 #[cfg(all(feature = "transport_unixsock-stream", target_family = "unix"))]
 pub const STR_UNIXSOCK_STREAM: &str = "unixsock-stream";
+#[cfg(feature = "transport_mem")]
+pub const STR_MEM: &str = "mem";
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum LocatorProtocol {
@@ -57,6 +62,8 @@ pub enum LocatorProtocol {
     Quic,
     #[cfg(all(feature = "transport_unixsock-stream", target_family = "unix"))]
     UnixSocketStream,
+    #[cfg(feature = "transport_mem")]
+    Mem,
 }
 
 impl fmt::Display for LocatorProtocol {
@@ -72,6 +79,8 @@ impl fmt::Display for LocatorProtocol {
             LocatorProtocol::Quic => write!(f, "{}", STR_QUIC)?,
             #[cfg(all(feature = "transport_unixsock-stream", target_family = "unix"))]
             LocatorProtocol::UnixSocketStream => write!(f, "{}", STR_UNIXSOCK_STREAM)?,
+            #[cfg(feature = "transport_mem")]
+            LocatorProtocol::Mem => write!(f, "{}", STR_MEM)?,
         }
         Ok(())
     }
@@ -92,6 +101,8 @@ pub enum Locator {
     Quic(LocatorQuic),
     #[cfg(all(feature = "transport_unixsock-stream", target_family = "unix"))]
     UnixSocketStream(LocatorUnixSocketStream),
+    #[cfg(feature = "transport_mem")]
+    Mem(LocatorMem),
 }
 
 impl FromStr for Locator {
@@ -124,6 +135,8 @@ impl FromStr for Locator {
             STR_QUIC => addr.parse().map(Locator::Quic),
             #[cfg(all(feature = "transport_unixsock-stream", target_family = "unix"))]
             STR_UNIXSOCK_STREAM => addr.parse().map(Locator::UnixSocketStream),
+            #[cfg(feature = "transport_mem")]
+            STR_MEM => addr.parse().map(Locator::Mem),
             _ => {
                 let e = format!("Invalid protocol locator: {}", proto);
                 zerror!(ZErrorKind::InvalidLocator { descr: e })
@@ -145,6 +158,46 @@ impl Locator {
             Locator::Quic(..) => LocatorProtocol::Quic,
             #[cfg(all(feature = "transport_unixsock-stream", target_family = "unix"))]
             Locator::UnixSocketStream(..) => LocatorProtocol::UnixSocketStream,
+            #[cfg(feature = "transport_mem")]
+            Locator::Mem(..) => LocatorProtocol::Mem,
+        }
+    }
+
+    /// Returns the socket address of this locator, if it is an IP based locator with a resolved address.
+    pub fn get_socket_addr(&self) -> Option<SocketAddr> {
+        match self {
+            #[cfg(feature = "transport_tcp")]
+            Locator::Tcp(LocatorTcp::SocketAddr(addr)) => Some(*addr),
+            #[cfg(feature = "transport_udp")]
+            Locator::Udp(LocatorUdp::SocketAddr(addr)) => Some(*addr),
+            #[cfg(feature = "transport_tls")]
+            Locator::Tls(LocatorTls::SocketAddr(addr)) => Some(*addr),
+            #[cfg(feature = "transport_quic")]
+            Locator::Quic(LocatorQuic::SocketAddr(addr)) => Some(*addr),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
+
+    /// Returns the IP address of this locator, if it is an IP based locator with a resolved address.
+    pub fn get_ip(&self) -> Option<IpAddr> {
+        self.get_socket_addr().map(|addr| addr.ip())
+    }
+
+    /// Returns true if a link with this locator as source address was accepted by a listener
+    /// on `listener`, i.e. if both are equal or if `listener` is bound to the unspecified
+    /// address on the same protocol and port.
+    pub fn is_accepted_by(&self, listener: &Locator) -> bool {
+        if self == listener {
+            return true;
+        }
+        match (self.get_socket_addr(), listener.get_socket_addr()) {
+            (Some(src), Some(listener_addr)) => {
+                self.get_proto() == listener.get_proto()
+                    && listener_addr.ip().is_unspecified()
+                    && src.port() == listener_addr.port()
+            }
+            _ => false,
         }
     }
 }
@@ -162,6 +215,8 @@ impl fmt::Display for Locator {
             Locator::Quic(addr) => write!(f, "{}/{}", STR_QUIC, addr)?,
             #[cfg(all(feature = "transport_unixsock-stream", target_family = "unix"))]
             Locator::UnixSocketStream(addr) => write!(f, "{}/{}", STR_UNIXSOCK_STREAM, addr)?,
+            #[cfg(feature = "transport_mem")]
+            Locator::Mem(addr) => write!(f, "{}/{}", STR_MEM, addr)?,
         }
         Ok(())
     }
@@ -182,6 +237,8 @@ pub enum LocatorProperty {
     Quic(LocatorPropertyQuic),
     #[cfg(all(feature = "transport_unixsock-stream", target_family = "unix"))]
     UnixSocketStream(LocatorPropertyUnixSocketStream),
+    #[cfg(feature = "transport_mem")]
+    Mem(LocatorPropertyMem),
 }
 
 impl LocatorProperty {
@@ -197,6 +254,8 @@ impl LocatorProperty {
             LocatorProperty::Quic(..) => LocatorProtocol::Quic,
             #[cfg(all(feature = "transport_unixsock-stream", target_family = "unix"))]
             LocatorProperty::UnixSocketStream(..) => LocatorProtocol::UnixSocketStream,
+            #[cfg(feature = "transport_mem")]
+            LocatorProperty::Mem(..) => LocatorProtocol::Mem,
         }
     }
 
@@ -237,6 +296,8 @@ impl fmt::Display for LocatorProperty {
             LocatorProperty::Quic(..) => write!(f, "{}", STR_QUIC)?,
             #[cfg(all(feature = "transport_unixsock-stream", target_family = "unix"))]
             LocatorProperty::UnixSocketStream(..) => write!(f, "{}", STR_UNIXSOCK_STREAM)?,
+            #[cfg(feature = "transport_mem")]
+            LocatorProperty::Mem(..) => write!(f, "{}", STR_MEM)?,
         }
         Ok(())
     }
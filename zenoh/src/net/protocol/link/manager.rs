@@ -11,6 +11,8 @@
 // Contributors:
 //   ADLINK zenoh team, <zenoh@adlink-labs.tech>
 //
+#[cfg(feature = "transport_mem")]
+use super::mem::LinkManagerMem;
 #[cfg(feature = "transport_quic")]
 use super::quic::LinkManagerQuic;
 use super::session::SessionManager;
@@ -42,6 +44,8 @@ impl LinkManagerBuilder {
             LocatorProtocol::UnixSocketStream => {
                 Arc::new(LinkManagerUnixSocketStream::new(manager))
             }
+            #[cfg(feature = "transport_mem")]
+            LocatorProtocol::Mem => Arc::new(LinkManagerMem::new(manager)),
         }
     }
 }
@@ -0,0 +1,397 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+//! In-memory links between sessions living in the same process.
+//!
+//! A `mem/<name>` listener registers `<name>` in a process-wide table, and any session of the
+//! same process can connect to it. The messages are exchanged via channels, without any socket.
+//! The conditions of the links between two peers (latency, loss, partition) can be changed at
+//! any time with [`set_link_conditions`], which makes this transport suitable for simulating
+//! networks in tests.
+use super::session::SessionManager;
+use super::{Link, LinkManagerTrait, LinkTrait, Locator, LocatorProperty};
+use crate::net::protocol::core::PeerId;
+use async_std::task;
+use async_trait::async_trait;
+use flume::{Receiver, Sender};
+use rand::Rng;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+use zenoh_util::core::{ZError, ZErrorKind, ZResult};
+use zenoh_util::{zerror, zerror2, zlock, zread, zwrite};
+
+// Maximum MTU (MEM PDU) in bytes.
+// NOTE: The batches are exchanged as whole messages on the in-memory channels,
+//       hence the MTU is only constrained by the 16 bits used in Zenoh for
+//       encoding the batch size.
+const MEM_MAX_MTU: usize = 65_535;
+
+zconfigurable! {
+    // Default MTU (MEM PDU) in bytes.
+    static ref MEM_DEFAULT_MTU: usize = MEM_MAX_MTU;
+}
+
+lazy_static! {
+    // The listeners of the process: name -> session manager accepting the links
+    static ref MEM_LISTENERS: Mutex<HashMap<String, SessionManager>> = Mutex::new(HashMap::new());
+    // The conditions applied to the links between two peers
+    static ref MEM_CONDITIONS: RwLock<HashMap<(PeerId, PeerId), LinkConditionsMem>> =
+        RwLock::new(HashMap::new());
+}
+
+/// The conditions applied to the in-memory links between two peers.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LinkConditionsMem {
+    /// The delay added to the delivery of each batch.
+    pub latency: Duration,
+    /// The probability (between 0.0 and 1.0) for a batch to be dropped.
+    pub loss: f64,
+    /// If true, all the batches are dropped in both directions.
+    pub partitioned: bool,
+}
+
+fn conditions_key(a: &PeerId, b: &PeerId) -> (PeerId, PeerId) {
+    if a.as_slice() <= b.as_slice() {
+        (a.clone(), b.clone())
+    } else {
+        (b.clone(), a.clone())
+    }
+}
+
+/// Sets the conditions of all the in-memory links between the peers `a` and `b`,
+/// in both directions. This applies to the existing links as well as to the future ones.
+pub fn set_link_conditions(a: &PeerId, b: &PeerId, conditions: LinkConditionsMem) {
+    zwrite!(MEM_CONDITIONS).insert(conditions_key(a, b), conditions);
+}
+
+/// Restores perfect in-memory links between the peers `a` and `b`.
+pub fn clear_link_conditions(a: &PeerId, b: &PeerId) {
+    zwrite!(MEM_CONDITIONS).remove(&conditions_key(a, b));
+}
+
+/// Returns the conditions of the in-memory links between the peers `a` and `b`.
+pub fn get_link_conditions(a: &PeerId, b: &PeerId) -> LinkConditionsMem {
+    zread!(MEM_CONDITIONS)
+        .get(&conditions_key(a, b))
+        .cloned()
+        .unwrap_or_default()
+}
+
+#[allow(unreachable_patterns)]
+fn get_mem_name(locator: &Locator) -> ZResult<String> {
+    match locator {
+        Locator::Mem(name) => Ok(name.0.clone()),
+        _ => {
+            let e = format!("Not a Mem locator: {:?}", locator);
+            log::debug!("{}", e);
+            zerror!(ZErrorKind::InvalidLocator { descr: e })
+        }
+    }
+}
+
+/*************************************/
+/*             LOCATOR               */
+/*************************************/
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LocatorMem(String);
+
+impl FromStr for LocatorMem {
+    type Err = ZError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            let e = format!("Invalid Mem locator: {:?}", s);
+            zerror!(ZErrorKind::InvalidLocator { descr: e })
+        }
+        Ok(LocatorMem(s.to_string()))
+    }
+}
+
+impl fmt::Display for LocatorMem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)?;
+        Ok(())
+    }
+}
+
+/*************************************/
+/*            PROPERTY               */
+/*************************************/
+pub type LocatorPropertyMem = ();
+
+/*************************************/
+/*              LINK                 */
+/*************************************/
+// A batch in flight, with the instant it must be delivered at
+type Batch = (Instant, Vec<u8>);
+
+pub struct LinkMem {
+    // The local and remote names of the link
+    src: String,
+    dst: String,
+    // The local and remote peers, used for looking up the link conditions
+    local: PeerId,
+    remote: PeerId,
+    // The channel to the remote end, dropped when the link is closed
+    tx: Mutex<Option<Sender<Batch>>>,
+    // The channel from the remote end
+    rx: Receiver<Batch>,
+}
+
+impl LinkMem {
+    fn new(
+        src: String,
+        dst: String,
+        local: PeerId,
+        remote: PeerId,
+        tx: Sender<Batch>,
+        rx: Receiver<Batch>,
+    ) -> LinkMem {
+        LinkMem {
+            src,
+            dst,
+            local,
+            remote,
+            tx: Mutex::new(Some(tx)),
+            rx,
+        }
+    }
+}
+
+#[async_trait]
+impl LinkTrait for LinkMem {
+    async fn close(&self) -> ZResult<()> {
+        log::trace!("Closing Mem link: {}", self);
+        // Dropping the sender makes the remote end fail on read
+        zlock!(self.tx).take();
+        Ok(())
+    }
+
+    async fn write(&self, buffer: &[u8]) -> ZResult<usize> {
+        let tx = match zlock!(self.tx).as_ref() {
+            Some(tx) => tx.clone(),
+            None => {
+                let e = format!("Write error on Mem link {}: link closed", self);
+                log::trace!("{}", e);
+                return zerror!(ZErrorKind::IoError { descr: e });
+            }
+        };
+
+        let conditions = get_link_conditions(&self.local, &self.remote);
+        if conditions.partitioned
+            || (conditions.loss > 0.0 && rand::thread_rng().gen::<f64>() < conditions.loss)
+        {
+            log::trace!("Batch dropped on Mem link {}: {:?}", self, conditions);
+            return Ok(buffer.len());
+        }
+
+        let deadline = Instant::now() + conditions.latency;
+        tx.send_async((deadline, buffer.to_vec()))
+            .await
+            .map_err(|e| {
+                let e = format!("Write error on Mem link {}: {}", self, e);
+                log::trace!("{}", e);
+                zerror2!(ZErrorKind::IoError { descr: e })
+            })?;
+        Ok(buffer.len())
+    }
+
+    async fn write_all(&self, buffer: &[u8]) -> ZResult<()> {
+        let _ = self.write(buffer).await?;
+        Ok(())
+    }
+
+    async fn read(&self, buffer: &mut [u8]) -> ZResult<usize> {
+        let (deadline, batch) = self.rx.recv_async().await.map_err(|e| {
+            let e = format!("Read error on Mem link {}: {}", self, e);
+            log::trace!("{}", e);
+            zerror2!(ZErrorKind::IoError { descr: e })
+        })?;
+        let now = Instant::now();
+        if deadline > now {
+            task::sleep(deadline - now).await;
+        }
+        if batch.len() > buffer.len() {
+            let e = format!(
+                "Read error on Mem link {}: batch of {} bytes exceeds buffer of {} bytes",
+                self,
+                batch.len(),
+                buffer.len()
+            );
+            log::trace!("{}", e);
+            return zerror!(ZErrorKind::IoError { descr: e });
+        }
+        buffer[..batch.len()].copy_from_slice(&batch);
+        Ok(batch.len())
+    }
+
+    async fn read_exact(&self, buffer: &mut [u8]) -> ZResult<()> {
+        let n = self.read(buffer).await?;
+        if n != buffer.len() {
+            let e = format!(
+                "Read error on Mem link {}: read {} bytes instead of {}",
+                self,
+                n,
+                buffer.len()
+            );
+            log::trace!("{}", e);
+            return zerror!(ZErrorKind::IoError { descr: e });
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn get_src(&self) -> Locator {
+        Locator::Mem(LocatorMem(self.src.clone()))
+    }
+
+    #[inline(always)]
+    fn get_dst(&self) -> Locator {
+        Locator::Mem(LocatorMem(self.dst.clone()))
+    }
+
+    #[inline(always)]
+    fn get_mtu(&self) -> usize {
+        *MEM_DEFAULT_MTU
+    }
+
+    #[inline(always)]
+    fn is_reliable(&self) -> bool {
+        true
+    }
+
+    #[inline(always)]
+    fn is_streamed(&self) -> bool {
+        false
+    }
+}
+
+impl fmt::Display for LinkMem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} => {}", self.src, self.dst)?;
+        Ok(())
+    }
+}
+
+impl fmt::Debug for LinkMem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Mem")
+            .field("src", &self.src)
+            .field("dst", &self.dst)
+            .finish()
+    }
+}
+
+/*************************************/
+/*          LISTENER                 */
+/*************************************/
+pub struct LinkManagerMem {
+    manager: SessionManager,
+    listeners: Arc<RwLock<HashSet<String>>>,
+}
+
+impl LinkManagerMem {
+    pub(crate) fn new(manager: SessionManager) -> Self {
+        Self {
+            manager,
+            listeners: Arc::new(RwLock::new(HashSet::new())),
+        }
+    }
+}
+
+#[async_trait]
+impl LinkManagerTrait for LinkManagerMem {
+    async fn new_link(&self, locator: &Locator, _ps: Option<&LocatorProperty>) -> ZResult<Link> {
+        let name = get_mem_name(locator)?;
+        let remote_manager = zlock!(MEM_LISTENERS).get(&name).cloned().ok_or_else(|| {
+            let e = format!("Can not create a new Mem link to {}: no listener", name);
+            log::warn!("{}", e);
+            zerror2!(ZErrorKind::Other { descr: e })
+        })?;
+
+        let local = self.manager.pid();
+        let remote = remote_manager.pid();
+        let src = format!("{}", Uuid::new_v4());
+        let (tx_local, rx_remote) = flume::unbounded();
+        let (tx_remote, rx_local) = flume::unbounded();
+
+        // Hand over the remote end of the link to the listening session manager
+        let remote_link = Arc::new(LinkMem::new(
+            name.clone(),
+            src.clone(),
+            remote.clone(),
+            local.clone(),
+            tx_remote,
+            rx_remote,
+        ));
+        log::debug!("Accepted Mem connection on: {}", name);
+        task::spawn(async move {
+            remote_manager
+                .handle_new_link(Link(remote_link), None)
+                .await;
+        });
+
+        let link = Arc::new(LinkMem::new(src, name, local, remote, tx_local, rx_local));
+        Ok(Link(link))
+    }
+
+    async fn new_listener(
+        &self,
+        locator: &Locator,
+        _ps: Option<&LocatorProperty>,
+    ) -> ZResult<Locator> {
+        let name = get_mem_name(locator)?;
+        let mut guard = zlock!(MEM_LISTENERS);
+        if guard.contains_key(&name) {
+            let e = format!(
+                "Can not create a new Mem listener on {}: name already in use",
+                name
+            );
+            log::warn!("{}", e);
+            return zerror!(ZErrorKind::InvalidLink { descr: e });
+        }
+        guard.insert(name.clone(), self.manager.clone());
+        zwrite!(self.listeners).insert(name);
+
+        Ok(locator.clone())
+    }
+
+    async fn del_listener(&self, locator: &Locator) -> ZResult<()> {
+        let name = get_mem_name(locator)?;
+        if !zwrite!(self.listeners).remove(&name) {
+            let e = format!(
+                "Can not delete the Mem listener because it has not been found: {}",
+                name
+            );
+            log::trace!("{}", e);
+            return zerror!(ZErrorKind::InvalidLink { descr: e });
+        }
+        zlock!(MEM_LISTENERS).remove(&name);
+        Ok(())
+    }
+
+    fn get_listeners(&self) -> Vec<Locator> {
+        zread!(self.listeners)
+            .iter()
+            .map(|x| Locator::Mem(LocatorMem(x.clone())))
+            .collect()
+    }
+
+    fn get_locators(&self) -> Vec<Locator> {
+        self.get_listeners()
+    }
+}
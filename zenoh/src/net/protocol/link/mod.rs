@@ -11,8 +11,14 @@
 // Contributors:
 //   ADLINK zenoh team, <zenoh@adlink-labs.tech>
 //
+#[cfg(any(feature = "transport_tls", feature = "transport_quic"))]
+mod cert_resolver;
+mod endpoint;
 mod locator;
 mod manager;
+#[cfg(feature = "transport_mem")]
+pub mod mem;
+mod proxy;
 #[cfg(feature = "transport_quic")]
 pub mod quic;
 #[cfg(feature = "transport_tcp")]
@@ -29,8 +35,10 @@ use super::proto::SessionMessage;
 use super::session;
 use async_std::sync::Arc;
 use async_trait::async_trait;
+pub use endpoint::*;
 pub use locator::*;
 pub use manager::*;
+pub use proxy::*;
 use std::cmp::PartialEq;
 use std::fmt;
 use std::hash::{Hash, Hasher};
@@ -52,8 +60,24 @@ pub trait LinkTrait {
     fn get_dst(&self) -> Locator;
     fn is_reliable(&self) -> bool;
     fn is_streamed(&self) -> bool;
+    /// Returns the DER encoded certificate presented by the remote end of the link,
+    /// if the link is secured and the remote end presented one.
+    fn get_peer_certificate(&self) -> Option<Vec<u8>> {
+        None
+    }
+    /// Notifies the link that its session was established with shared memory: the links
+    /// able to pass the shared memory segments of this process may now pass them.
+    fn set_shm(&self) {}
     async fn write(&self, buffer: &[u8]) -> ZResult<usize>;
     async fn write_all(&self, buffer: &[u8]) -> ZResult<()>;
+    /// Writes all the `buffers` in order, each as a whole. The datagram links may write
+    /// them with a single system call.
+    async fn write_all_batch(&self, buffers: &[&[u8]]) -> ZResult<()> {
+        for buffer in buffers {
+            self.write_all(buffer).await?;
+        }
+        Ok(())
+    }
     async fn read(&self, buffer: &mut [u8]) -> ZResult<usize>;
     async fn read_exact(&self, buffer: &mut [u8]) -> ZResult<()>;
     async fn close(&self) -> ZResult<()>;
@@ -170,6 +194,16 @@ impl From<Arc<dyn LinkTrait + Send + Sync>> for Link {
 #[async_trait]
 pub trait LinkManagerTrait {
     async fn new_link(&self, dst: &Locator, property: Option<&LocatorProperty>) -> ZResult<Link>;
+    async fn new_link_via_proxy(
+        &self,
+        dst: &Locator,
+        _property: Option<&LocatorProperty>,
+        _proxy: &Proxy,
+    ) -> ZResult<Link> {
+        zerror!(ZErrorKind::InvalidLink {
+            descr: format!("Can not open a link to {} through a proxy", dst)
+        })
+    }
     async fn new_listener(
         &self,
         locator: &Locator,
@@ -0,0 +1,268 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use super::LocatorProtocol;
+use std::fmt;
+use std::str::FromStr;
+use zenoh_util::core::{ZError, ZErrorKind, ZResult};
+use zenoh_util::zerror;
+#[cfg(any(feature = "transport_tcp", feature = "transport_tls"))]
+use {async_std::net::TcpStream, async_std::prelude::*, std::net::IpAddr, zenoh_util::zerror2};
+
+const SOCKS5_SCHEME: &str = "socks5://";
+const HTTP_SCHEME: &str = "http://";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyKind {
+    Socks5,
+    HttpConnect,
+}
+
+/// A proxy through which the outgoing TCP and TLS links are opened, in the form
+/// `socks5://[<user>:<password>@]<host>:<port>` or `http://[<user>:<password>@]<host>:<port>`
+/// (HTTP CONNECT tunnel).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proxy {
+    pub kind: ProxyKind,
+    /// The `<host>:<port>` address of the proxy.
+    pub addr: String,
+    /// The user name and password to authenticate with the proxy, if any.
+    pub credentials: Option<(String, String)>,
+}
+
+impl Proxy {
+    /// Returns true if the links of `protocol` can be opened through a proxy.
+    pub fn supports(protocol: &LocatorProtocol) -> bool {
+        #[allow(unreachable_patterns)]
+        match protocol {
+            #[cfg(feature = "transport_tcp")]
+            LocatorProtocol::Tcp => true,
+            #[cfg(feature = "transport_tls")]
+            LocatorProtocol::Tls => true,
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for Proxy {
+    type Err = ZError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, rest) = if let Some(rest) = s.strip_prefix(SOCKS5_SCHEME) {
+            (ProxyKind::Socks5, rest)
+        } else if let Some(rest) = s.strip_prefix(HTTP_SCHEME) {
+            (ProxyKind::HttpConnect, rest)
+        } else {
+            return zerror!(ZErrorKind::InvalidLocator {
+                descr: format!("Invalid proxy (expected socks5:// or http://): {}", s)
+            });
+        };
+        let rest = rest.trim_end_matches('/');
+        let (credentials, addr) = match rest.rfind('@') {
+            Some(index) => {
+                let credentials = &rest[..index];
+                match credentials.find(':') {
+                    Some(i) => (
+                        Some((
+                            credentials[..i].to_string(),
+                            credentials[i + 1..].to_string(),
+                        )),
+                        &rest[index + 1..],
+                    ),
+                    None => {
+                        return zerror!(ZErrorKind::InvalidLocator {
+                            descr: format!(
+                                "Invalid proxy credentials (expected user:password): {}",
+                                s
+                            )
+                        })
+                    }
+                }
+            }
+            None => (None, rest),
+        };
+        match addr.rfind(':').map(|i| addr[i + 1..].parse::<u16>()) {
+            Some(Ok(_)) => Ok(Proxy {
+                kind,
+                addr: addr.to_string(),
+                credentials,
+            }),
+            _ => zerror!(ZErrorKind::InvalidLocator {
+                descr: format!("Invalid proxy address (expected host:port): {}", s)
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Proxy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let scheme = match self.kind {
+            ProxyKind::Socks5 => SOCKS5_SCHEME,
+            ProxyKind::HttpConnect => HTTP_SCHEME,
+        };
+        match &self.credentials {
+            Some((user, password)) => write!(f, "{}{}:{}@{}", scheme, user, password, self.addr),
+            None => write!(f, "{}{}", scheme, self.addr),
+        }
+    }
+}
+
+/*************************************/
+/*             HANDSHAKES            */
+/*************************************/
+#[cfg(any(feature = "transport_tcp", feature = "transport_tls"))]
+fn proxy_error(proxy: &Proxy, dst: &str, reason: &str) -> ZError {
+    zerror2!(ZErrorKind::InvalidLink {
+        descr: format!(
+            "Can not connect to {} through proxy {}: {}",
+            dst, proxy.addr, reason
+        )
+    })
+}
+
+#[cfg(any(feature = "transport_tcp", feature = "transport_tls"))]
+impl Proxy {
+    /// Opens a TCP stream to `dst` (`<host>:<port>`) through the proxy.
+    /// The host names are resolved by the proxy.
+    pub(super) async fn connect(&self, dst: &str) -> ZResult<TcpStream> {
+        let mut stream = TcpStream::connect(&self.addr)
+            .await
+            .map_err(|e| proxy_error(self, dst, &e.to_string()))?;
+        let res = match self.kind {
+            ProxyKind::Socks5 => self.socks5_handshake(&mut stream, dst).await,
+            ProxyKind::HttpConnect => self.http_handshake(&mut stream, dst).await,
+        };
+        match res {
+            Ok(()) => {
+                log::debug!("Connected to {} through proxy {}", dst, self.addr);
+                Ok(stream)
+            }
+            Err(e) => Err(proxy_error(self, dst, &e.to_string())),
+        }
+    }
+
+    // See RFC 1928 and RFC 1929
+    async fn socks5_handshake(&self, stream: &mut TcpStream, dst: &str) -> std::io::Result<()> {
+        let fail = |reason: String| std::io::Error::new(std::io::ErrorKind::Other, reason);
+        let (host, port) = split_host_port(dst).ok_or_else(|| fail("invalid address".into()))?;
+
+        // Method negotiation: no authentication, or user/password if we have credentials
+        match &self.credentials {
+            Some(_) => stream.write_all(&[0x05, 0x02, 0x00, 0x02]).await?,
+            None => stream.write_all(&[0x05, 0x01, 0x00]).await?,
+        }
+        let mut reply = [0u8; 2];
+        stream.read_exact(&mut reply).await?;
+        match (reply[1], &self.credentials) {
+            (0x00, _) => {}
+            (0x02, Some((user, password))) => {
+                if user.len() > 255 || password.len() > 255 {
+                    return Err(fail("credentials too long".into()));
+                }
+                let mut auth = vec![0x01, user.len() as u8];
+                auth.extend_from_slice(user.as_bytes());
+                auth.push(password.len() as u8);
+                auth.extend_from_slice(password.as_bytes());
+                stream.write_all(&auth).await?;
+                stream.read_exact(&mut reply).await?;
+                if reply[1] != 0x00 {
+                    return Err(fail("authentication failed".into()));
+                }
+            }
+            _ => return Err(fail("no acceptable authentication method".into())),
+        }
+
+        // Connect request
+        let mut request = vec![0x05, 0x01, 0x00];
+        match host.parse::<IpAddr>() {
+            Ok(IpAddr::V4(ip)) => {
+                request.push(0x01);
+                request.extend_from_slice(&ip.octets());
+            }
+            Ok(IpAddr::V6(ip)) => {
+                request.push(0x04);
+                request.extend_from_slice(&ip.octets());
+            }
+            Err(_) => {
+                if host.len() > 255 {
+                    return Err(fail("host name too long".into()));
+                }
+                request.push(0x03);
+                request.push(host.len() as u8);
+                request.extend_from_slice(host.as_bytes());
+            }
+        }
+        request.extend_from_slice(&port.to_be_bytes());
+        stream.write_all(&request).await?;
+
+        let mut reply = [0u8; 4];
+        stream.read_exact(&mut reply).await?;
+        if reply[1] != 0x00 {
+            return Err(fail(format!("connection refused (code {})", reply[1])));
+        }
+        // Skip the bound address and port
+        let len = match reply[3] {
+            0x01 => 4,
+            0x04 => 16,
+            0x03 => {
+                let mut len = [0u8; 1];
+                stream.read_exact(&mut len).await?;
+                len[0] as usize
+            }
+            atyp => return Err(fail(format!("invalid address type {}", atyp))),
+        };
+        let mut bound = vec![0u8; len + 2];
+        stream.read_exact(&mut bound).await?;
+        Ok(())
+    }
+
+    // See RFC 7231, section 4.3.6
+    async fn http_handshake(&self, stream: &mut TcpStream, dst: &str) -> std::io::Result<()> {
+        let fail = |reason: String| std::io::Error::new(std::io::ErrorKind::Other, reason);
+        let mut request = format!("CONNECT {} HTTP/1.1\r\nHost: {}\r\n", dst, dst);
+        if let Some((user, password)) = &self.credentials {
+            request.push_str(&format!(
+                "Proxy-Authorization: Basic {}\r\n",
+                base64::encode(format!("{}:{}", user, password))
+            ));
+        }
+        request.push_str("\r\n");
+        stream.write_all(request.as_bytes()).await?;
+
+        // Read the response headers byte per byte, not to consume the tunneled data
+        let mut response = vec![];
+        let mut byte = [0u8; 1];
+        while !response.ends_with(b"\r\n\r\n") {
+            if response.len() >= 8192 {
+                return Err(fail("response headers too long".into()));
+            }
+            stream.read_exact(&mut byte).await?;
+            response.push(byte[0]);
+        }
+        let response = String::from_utf8_lossy(&response);
+        let status = response.lines().next().unwrap_or_default();
+        match status.split_whitespace().nth(1) {
+            Some(code) if code.starts_with('2') => Ok(()),
+            _ => Err(fail(format!("unexpected response: {}", status))),
+        }
+    }
+}
+
+// Splits "<host>:<port>" or "[<ipv6>]:<port>"
+#[cfg(any(feature = "transport_tcp", feature = "transport_tls"))]
+fn split_host_port(addr: &str) -> Option<(&str, u16)> {
+    let index = addr.rfind(':')?;
+    let port = addr[index + 1..].parse().ok()?;
+    let host = addr[..index].trim_start_matches('[').trim_end_matches(']');
+    Some((host, port))
+}
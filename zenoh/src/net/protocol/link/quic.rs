@@ -11,6 +11,7 @@
 // Contributors:
 //   ADLINK zenoh team, <zenoh@adlink-labs.tech>
 //
+use super::cert_resolver::ReloadingCertResolver;
 use super::session::SessionManager;
 use super::{Link, LinkManagerTrait, LinkTrait, Locator, LocatorProperty};
 use async_std::fs;
@@ -181,17 +182,36 @@ impl LocatorPropertyQuic {
         config: &ConfigProperties,
     ) -> ZResult<Option<LocatorProperty>> {
         let mut client_config: Option<ClientConfigBuilder> = None;
+        let mut root_store: Option<rustls::RootCertStore> = None;
         if let Some(tls_ca_certificate) = config.get(&ZN_TLS_ROOT_CA_CERTIFICATE_KEY) {
             let ca = fs::read(tls_ca_certificate).await.map_err(|e| {
                 let e = format!("Invalid QUIC CA certificate file: {}", e);
                 zerror2!(ZErrorKind::IoError { descr: e })
             })?;
+            let mut rs = rustls::RootCertStore::empty();
+            let _ = rs
+                .add_pem_file(&mut std::io::Cursor::new(&ca))
+                .map_err(|_| {
+                    let e = "Invalid QUIC CA certificate file".to_string();
+                    zerror2!(ZErrorKind::IoError { descr: e })
+                })?;
+            root_store = Some(rs);
             let ca = Certificate::from_pem(&ca).map_err(|e| {
                 let e = format!("Invalid QUIC CA certificate file: {}", e);
                 zerror2!(ZErrorKind::IoError { descr: e })
             })?;
 
-            let mut cc = ClientConfigBuilder::default();
+            let mut cc = ClientConfig::default();
+            if let Some(tls_client_private_key) = config.get(&ZN_TLS_CLIENT_PRIVATE_KEY_KEY) {
+                if let Some(tls_client_certificate) = config.get(&ZN_TLS_CLIENT_CERTIFICATE_KEY) {
+                    // the certificate files are reloaded when they change
+                    let resolver =
+                        ReloadingCertResolver::new(tls_client_certificate, tls_client_private_key)?;
+                    Arc::make_mut(&mut cc.crypto).client_auth_cert_resolver = Arc::new(resolver);
+                    log::debug!("QUIC client certificate is configured");
+                }
+            }
+            let mut cc = ClientConfigBuilder::new(cc);
             cc.protocols(ALPN_QUIC_HTTP);
             cc.add_certificate_authority(ca).map_err(|e| {
                 let e = format!("Invalid QUIC CA certificate file: {}", e);
@@ -202,23 +222,18 @@ impl LocatorPropertyQuic {
             log::debug!("QUIC client is configured");
         }
 
+        // If the peer ids are bound to the certificates, the clients must present one
+        let peer_id_binding = config
+            .get_or(&ZN_TLS_PEER_ID_BINDING_KEY, ZN_TLS_PEER_ID_BINDING_DEFAULT)
+            .to_lowercase()
+            == ZN_TRUE;
+
         let mut server_config: Option<ServerConfigBuilder> = None;
         if let Some(tls_server_private_key) = config.get(&ZN_TLS_SERVER_PRIVATE_KEY_KEY) {
             if let Some(tls_server_certificate) = config.get(&ZN_TLS_SERVER_CERTIFICATE_KEY) {
-                let pkey = fs::read(tls_server_private_key).await.map_err(|e| {
-                    let e = format!("Invalid TLS private key file: {}", e);
-                    zerror2!(ZErrorKind::IoError { descr: e })
-                })?;
-                let keys = PrivateKey::from_pem(&pkey).unwrap();
-
-                let certs = fs::read(tls_server_certificate).await.map_err(|e| {
-                    let e = format!("Invalid TLS server certificate file: {}", e);
-                    zerror2!(ZErrorKind::IoError { descr: e })
-                })?;
-                let certs = CertificateChain::from_pem(&certs).map_err(|e| {
-                    let e = format!("Invalid TLS server certificate file: {}", e);
-                    zerror2!(ZErrorKind::IoError { descr: e })
-                })?;
+                // the certificate files are reloaded when they change
+                let resolver =
+                    ReloadingCertResolver::new(tls_server_certificate, tls_server_private_key)?;
 
                 let mut tc = TransportConfig::default();
                 // We do not accept unidireactional streams.
@@ -233,12 +248,23 @@ impl LocatorPropertyQuic {
                 })?;
                 let mut sc = ServerConfig::default();
                 sc.transport = Arc::new(tc);
+                let crypto = Arc::make_mut(&mut sc.crypto);
+                crypto.cert_resolver = Arc::new(resolver);
+                if peer_id_binding {
+                    match root_store.as_ref() {
+                        Some(rs) => crypto.set_client_certificate_verifier(
+                            rustls::AllowAnyAuthenticatedClient::new(rs.clone()),
+                        ),
+                        None => {
+                            return zerror!(ZErrorKind::Other {
+                                descr: "The TLS peer id binding requires a TLS root CA certificate"
+                                    .to_string()
+                            })
+                        }
+                    }
+                }
                 let mut sc = ServerConfigBuilder::new(sc);
                 sc.protocols(ALPN_QUIC_HTTP);
-                sc.certificate(certs, keys).map_err(|e| {
-                    let e = format!("Invalid TLS server configuration: {}", e);
-                    zerror2!(ZErrorKind::Other { descr: e })
-                })?;
 
                 server_config = Some(sc);
                 log::debug!("QUIC server is configured");
@@ -303,6 +329,8 @@ pub struct LinkQuic {
     src_addr: SocketAddr,
     send: AsyncMutex<SendStream>,
     recv: AsyncMutex<RecvStream>,
+    // The end-entity certificate presented by the remote end, if any
+    peer_certificate: Option<Vec<u8>>,
 }
 
 impl LinkQuic {
@@ -312,12 +340,20 @@ impl LinkQuic {
         send: SendStream,
         recv: RecvStream,
     ) -> LinkQuic {
+        // Keep the end-entity certificate of the remote end, verified during the handshake
+        let peer_certificate = connection
+            .connection
+            .authentication_data()
+            .peer_certificates
+            .and_then(|certs| certs.iter().next().map(|cert| cert.0.clone()));
+
         // Build the Quic object
         LinkQuic {
             connection,
             src_addr,
             send: AsyncMutex::new(send),
             recv: AsyncMutex::new(recv),
+            peer_certificate,
         }
     }
 }
@@ -409,6 +445,10 @@ impl LinkTrait for LinkQuic {
     fn is_streamed(&self) -> bool {
         true
     }
+
+    fn get_peer_certificate(&self) -> Option<Vec<u8>> {
+        self.peer_certificate.clone()
+    }
 }
 
 impl Drop for LinkQuic {
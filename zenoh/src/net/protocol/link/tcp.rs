@@ -12,7 +12,7 @@
 //   ADLINK zenoh team, <zenoh@adlink-labs.tech>
 //
 use super::session::SessionManager;
-use super::{Link, LinkManagerTrait, LinkTrait, Locator, LocatorProperty};
+use super::{Link, LinkManagerTrait, LinkTrait, Locator, LocatorProperty, Proxy};
 use async_std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
 use async_std::prelude::*;
 use async_std::task;
@@ -51,6 +51,19 @@ zconfigurable! {
     static ref TCP_ACCEPT_THROTTLE_TIME: u64 = 100_000;
 }
 
+// Returns the "<host>:<port>" destination of a TCP locator, without resolving it
+#[allow(unreachable_patterns)]
+fn get_tcp_host(locator: &Locator) -> ZResult<String> {
+    match locator {
+        Locator::Tcp(LocatorTcp::SocketAddr(addr)) => Ok(addr.to_string()),
+        Locator::Tcp(LocatorTcp::DnsName(addr)) => Ok(addr.clone()),
+        _ => {
+            let e = format!("Not a TCP locator: {}", locator);
+            zerror!(ZErrorKind::InvalidLocator { descr: e })
+        }
+    }
+}
+
 #[allow(unreachable_patterns)]
 async fn get_tcp_addr(locator: &Locator) -> ZResult<SocketAddr> {
     match locator {
@@ -316,6 +329,34 @@ impl LinkManagerTrait for LinkManagerTcp {
         Ok(Link(link))
     }
 
+    async fn new_link_via_proxy(
+        &self,
+        locator: &Locator,
+        _ps: Option<&LocatorProperty>,
+        proxy: &Proxy,
+    ) -> ZResult<Link> {
+        let dst_host = get_tcp_host(locator)?;
+        let stream = proxy.connect(&dst_host).await?;
+
+        let src_addr = stream.local_addr().map_err(|e| {
+            let e = format!("Can not create a new TCP link bound to {}: {}", dst_host, e);
+            zerror2!(ZErrorKind::InvalidLink { descr: e })
+        })?;
+
+        // The link is identified by its destination, unless it can only be resolved by the proxy
+        let dst_addr = match get_tcp_addr(locator).await {
+            Ok(addr) => addr,
+            Err(_) => stream.peer_addr().map_err(|e| {
+                let e = format!("Can not create a new TCP link bound to {}: {}", dst_host, e);
+                zerror2!(ZErrorKind::InvalidLink { descr: e })
+            })?,
+        };
+
+        let link = Arc::new(LinkTcp::new(stream, src_addr, dst_addr));
+
+        Ok(Link(link))
+    }
+
     async fn new_listener(
         &self,
         locator: &Locator,
@@ -11,11 +11,12 @@
 // Contributors:
 //   ADLINK zenoh team, <zenoh@adlink-labs.tech>
 //
+use super::cert_resolver::ReloadingCertResolver;
 use super::session::SessionManager;
-use super::{Link, LinkManagerTrait, LinkTrait, Locator, LocatorProperty};
+use super::{Link, LinkManagerTrait, LinkTrait, Locator, LocatorProperty, Proxy};
 pub use async_rustls::rustls::*;
 pub use async_rustls::webpki::*;
-use async_rustls::{rustls::internal::pemfile, TlsAcceptor, TlsConnector, TlsStream};
+use async_rustls::{TlsAcceptor, TlsConnector, TlsStream};
 use async_std::fs;
 use async_std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
 use async_std::prelude::*;
@@ -59,6 +60,19 @@ zconfigurable! {
     static ref TLS_ACCEPT_THROTTLE_TIME: u64 = 100_000;
 }
 
+// Returns the "<host>:<port>" destination of a TLS locator, without resolving it
+#[allow(unreachable_patterns)]
+fn get_tls_host(locator: &Locator) -> ZResult<String> {
+    match locator {
+        Locator::Tls(LocatorTls::SocketAddr(addr)) => Ok(addr.to_string()),
+        Locator::Tls(LocatorTls::DnsName(addr)) => Ok(addr.clone()),
+        _ => {
+            let e = format!("Not a TLS locator: {}", locator);
+            zerror!(ZErrorKind::InvalidLocator { descr: e })
+        }
+    }
+}
+
 #[allow(unreachable_patterns)]
 async fn get_tls_addr(locator: &Locator) -> ZResult<SocketAddr> {
     match locator {
@@ -182,45 +196,66 @@ impl LocatorPropertyTls {
     pub(super) async fn from_properties(
         config: &ConfigProperties,
     ) -> ZResult<Option<LocatorProperty>> {
-        let mut client_config: Option<ClientConfig> = None;
+        let mut root_store: Option<RootCertStore> = None;
         if let Some(tls_ca_certificate) = config.get(&ZN_TLS_ROOT_CA_CERTIFICATE_KEY) {
             let ca = fs::read(tls_ca_certificate).await.map_err(|e| {
                 zerror2!(ZErrorKind::Other {
                     descr: format!("Invalid TLS CA certificate file: {}", e)
                 })
             })?;
+            let mut rs = RootCertStore::empty();
+            let _ = rs.add_pem_file(&mut Cursor::new(ca)).map_err(|_| {
+                zerror2!(ZErrorKind::Other {
+                    descr: "Invalid TLS CA certificate file".to_string()
+                })
+            })?;
+            root_store = Some(rs);
+        }
+
+        let mut client_config: Option<ClientConfig> = None;
+        if let Some(rs) = root_store.as_ref() {
             let mut cc = ClientConfig::new();
-            let _ = cc
-                .root_store
-                .add_pem_file(&mut Cursor::new(ca))
-                .map_err(|_| {
-                    zerror2!(ZErrorKind::Other {
-                        descr: "Invalid TLS CA certificate file".to_string()
-                    })
-                })?;
+            cc.root_store = rs.clone();
+            if let Some(tls_client_private_key) = config.get(&ZN_TLS_CLIENT_PRIVATE_KEY_KEY) {
+                if let Some(tls_client_certificate) = config.get(&ZN_TLS_CLIENT_CERTIFICATE_KEY) {
+                    // the certificate files are reloaded when they change
+                    let resolver =
+                        ReloadingCertResolver::new(tls_client_certificate, tls_client_private_key)?;
+                    cc.client_auth_cert_resolver = Arc::new(resolver);
+                    log::debug!("TLS client certificate is configured");
+                }
+            }
             client_config = Some(cc);
             log::debug!("TLS client is configured");
         }
 
+        // If the peer ids are bound to the certificates, the clients must present one
+        let peer_id_binding = config
+            .get_or(&ZN_TLS_PEER_ID_BINDING_KEY, ZN_TLS_PEER_ID_BINDING_DEFAULT)
+            .to_lowercase()
+            == ZN_TRUE;
+
         let mut server_config: Option<ServerConfig> = None;
         if let Some(tls_server_private_key) = config.get(&ZN_TLS_SERVER_PRIVATE_KEY_KEY) {
             if let Some(tls_server_certificate) = config.get(&ZN_TLS_SERVER_CERTIFICATE_KEY) {
-                let pkey = fs::read(tls_server_private_key).await.map_err(|e| {
-                    zerror2!(ZErrorKind::Other {
-                        descr: format!("Invalid TLS private key file: {}", e)
-                    })
-                })?;
-                let mut keys = pemfile::rsa_private_keys(&mut Cursor::new(pkey)).unwrap();
-
-                let cert = fs::read(tls_server_certificate).await.map_err(|e| {
-                    zerror2!(ZErrorKind::Other {
-                        descr: format!("Invalid TLS server certificate file: {}", e)
-                    })
-                })?;
-                let certs = pemfile::certs(&mut Cursor::new(cert)).unwrap();
-
-                let mut sc = ServerConfig::new(NoClientAuth::new());
-                sc.set_single_cert(certs, keys.remove(0)).unwrap();
+                // the certificate files are reloaded when they change
+                let resolver =
+                    ReloadingCertResolver::new(tls_server_certificate, tls_server_private_key)?;
+                let client_auth = if peer_id_binding {
+                    match root_store.as_ref() {
+                        Some(rs) => AllowAnyAuthenticatedClient::new(rs.clone()),
+                        None => {
+                            return zerror!(ZErrorKind::Other {
+                                descr: "The TLS peer id binding requires a TLS root CA certificate"
+                                    .to_string()
+                            })
+                        }
+                    }
+                } else {
+                    NoClientAuth::new()
+                };
+                let mut sc = ServerConfig::new(client_auth);
+                sc.cert_resolver = Arc::new(resolver);
                 server_config = Some(sc);
                 log::debug!("TLS server is configured");
             }
@@ -321,6 +356,8 @@ pub struct LinkTls {
     src_addr: SocketAddr,
     // The destination socket address of this link (address used on the local host)
     dst_addr: SocketAddr,
+    // The DER encoded certificate presented by the remote end, if any
+    peer_certificate: Option<Vec<u8>>,
     // Make sure there are no concurrent read or writes
     write_mtx: AsyncMutex<()>,
     read_mtx: AsyncMutex<()>,
@@ -331,7 +368,13 @@ unsafe impl Sync for LinkTls {}
 
 impl LinkTls {
     fn new(socket: TlsStream<TcpStream>, src_addr: SocketAddr, dst_addr: SocketAddr) -> LinkTls {
-        let (tcp_stream, _) = socket.get_ref();
+        let (tcp_stream, tls_session) = socket.get_ref();
+        // Keep the end-entity certificate of the remote end, verified during the handshake
+        let peer_certificate = tls_session
+            .get_peer_certificates()
+            .and_then(|certs| certs.into_iter().next())
+            .map(|cert| cert.0);
+
         // Set the TLS nodelay option
         if let Err(err) = tcp_stream.set_nodelay(true) {
             log::warn!(
@@ -362,6 +405,7 @@ impl LinkTls {
             inner: UnsafeCell::new(socket),
             src_addr,
             dst_addr,
+            peer_certificate,
             write_mtx: AsyncMutex::new(()),
             read_mtx: AsyncMutex::new(()),
         }
@@ -460,6 +504,10 @@ impl LinkTrait for LinkTls {
     fn is_streamed(&self) -> bool {
         true
     }
+
+    fn get_peer_certificate(&self) -> Option<Vec<u8>> {
+        self.peer_certificate.clone()
+    }
 }
 
 impl Drop for LinkTls {
@@ -523,6 +571,45 @@ impl LinkManagerTls {
     }
 }
 
+async fn new_tls_link(
+    domain: DNSName,
+    ps: Option<&LocatorProperty>,
+    tcp_stream: TcpStream,
+    dst_addr: SocketAddr,
+) -> ZResult<Link> {
+    let host: &str = domain.as_ref().into();
+
+    let src_addr = tcp_stream.local_addr().map_err(|e| {
+        let e = format!("Can not create a new TLS link bound to {}: {}", host, e);
+        zerror2!(ZErrorKind::InvalidLink { descr: e })
+    })?;
+
+    // Initialize the TLS stream
+    let config = match ps {
+        Some(prop) => {
+            let tls_prop = get_tls_prop(prop)?;
+            match tls_prop.client.as_ref() {
+                Some(conf) => conf.clone(),
+                None => Arc::new(ClientConfig::new()),
+            }
+        }
+        None => Arc::new(ClientConfig::new()),
+    };
+    let connector = TlsConnector::from(config);
+    let tls_stream = connector
+        .connect(domain.as_ref(), tcp_stream)
+        .await
+        .map_err(|e| {
+            let e = format!("Can not create a new TLS link bound to {}: {}", host, e);
+            zerror2!(ZErrorKind::InvalidLink { descr: e })
+        })?;
+    let tls_stream = TlsStream::Client(tls_stream);
+
+    let link = Arc::new(LinkTls::new(tls_stream, src_addr, dst_addr));
+
+    Ok(Link(link))
+}
+
 #[async_trait]
 impl LinkManagerTrait for LinkManagerTls {
     async fn new_link(&self, locator: &Locator, ps: Option<&LocatorProperty>) -> ZResult<Link> {
@@ -536,40 +623,34 @@ impl LinkManagerTrait for LinkManagerTls {
             zerror2!(ZErrorKind::Other { descr: e })
         })?;
 
-        let src_addr = tcp_stream.local_addr().map_err(|e| {
-            let e = format!("Can not create a new TLS link bound to {}: {}", host, e);
-            zerror2!(ZErrorKind::InvalidLink { descr: e })
-        })?;
-
         let dst_addr = tcp_stream.peer_addr().map_err(|e| {
             let e = format!("Can not create a new TLS link bound to {}: {}", host, e);
             zerror2!(ZErrorKind::InvalidLink { descr: e })
         })?;
 
-        // Initialize the TLS stream
-        let config = match ps {
-            Some(prop) => {
-                let tls_prop = get_tls_prop(prop)?;
-                match tls_prop.client.as_ref() {
-                    Some(conf) => conf.clone(),
-                    None => Arc::new(ClientConfig::new()),
-                }
-            }
-            None => Arc::new(ClientConfig::new()),
-        };
-        let connector = TlsConnector::from(config);
-        let tls_stream = connector
-            .connect(domain.as_ref(), tcp_stream)
-            .await
-            .map_err(|e| {
-                let e = format!("Can not create a new TLS link bound to {}: {}", host, e);
-                zerror2!(ZErrorKind::InvalidLink { descr: e })
-            })?;
-        let tls_stream = TlsStream::Client(tls_stream);
+        new_tls_link(domain, ps, tcp_stream, dst_addr).await
+    }
 
-        let link = Arc::new(LinkTls::new(tls_stream, src_addr, dst_addr));
+    async fn new_link_via_proxy(
+        &self,
+        locator: &Locator,
+        ps: Option<&LocatorProperty>,
+        proxy: &Proxy,
+    ) -> ZResult<Link> {
+        let domain = get_tls_dns(locator).await?;
+        let dst_host = get_tls_host(locator)?;
+        let tcp_stream = proxy.connect(&dst_host).await?;
+
+        // The link is identified by its destination, unless it can only be resolved by the proxy
+        let dst_addr = match get_tls_addr(locator).await {
+            Ok(addr) => addr,
+            Err(_) => tcp_stream.peer_addr().map_err(|e| {
+                let e = format!("Can not create a new TLS link bound to {}: {}", dst_host, e);
+                zerror2!(ZErrorKind::InvalidLink { descr: e })
+            })?,
+        };
 
-        Ok(Link(link))
+        new_tls_link(domain, ps, tcp_stream, dst_addr).await
     }
 
     async fn new_listener(
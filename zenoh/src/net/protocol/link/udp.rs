@@ -64,6 +64,11 @@ zconfigurable! {
     // Amount of time in microseconds to throttle the accept loop upon an error.
     // Default set to 100 ms.
     static ref UDP_ACCEPT_THROTTLE_TIME: u64 = 100_000;
+    // Maximum number of datagrams a listener drains from its socket on each wake-up.
+    // On Linux, the datagrams already queued in the socket are read with a single
+    // recvmmsg() system call. Each datagram requires an RX buffer of UDP_MAX_MTU bytes.
+    // Set it to 1 to read one datagram per system call.
+    static ref UDP_RX_BATCH: usize = 16;
 }
 
 #[allow(unreachable_patterns)]
@@ -134,6 +139,7 @@ pub type LocatorPropertyUdp = ();
 type LinkHashMap = Arc<Mutex<HashMap<(SocketAddr, SocketAddr), Weak<LinkUdpUnconnected>>>>;
 type LinkInput = (RecyclingObject<Box<[u8]>>, usize);
 type LinkLeftOver = (RecyclingObject<Box<[u8]>>, usize, usize);
+type LinkDatagram = (RecyclingObject<Box<[u8]>>, usize, SocketAddr);
 
 struct LinkUdpConnected {
     socket: Arc<UdpSocket>,
@@ -266,6 +272,36 @@ impl LinkTrait for LinkUdp {
         Ok(())
     }
 
+    async fn write_all_batch(&self, buffers: &[&[u8]]) -> ZResult<()> {
+        // On Linux, the datagrams the socket can take without blocking are written with a
+        // single sendmmsg() system call, and the remaining ones are written one at a time
+        #[cfg(target_os = "linux")]
+        let written = {
+            let res = match &self.variant {
+                LinkUdpVariant::Connected(link) => send_batch(&link.socket, buffers, None),
+                LinkUdpVariant::Unconnected(link) => match link.socket.upgrade() {
+                    Some(socket) => send_batch(&socket, buffers, Some(self.dst_addr)),
+                    None => Ok(0),
+                },
+            };
+            match res {
+                Ok(written) => written,
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => 0,
+                Err(e) => {
+                    return zerror!(ZErrorKind::IoError {
+                        descr: e.to_string()
+                    })
+                }
+            }
+        };
+        #[cfg(not(target_os = "linux"))]
+        let written = 0;
+        for buffer in &buffers[written..] {
+            self.write_all(buffer).await?;
+        }
+        Ok(())
+    }
+
     async fn read(&self, buffer: &mut [u8]) -> ZResult<usize> {
         match &self.variant {
             LinkUdpVariant::Connected(link) => link.read(buffer).await,
@@ -541,7 +577,9 @@ async fn accept_read_task(
 
     log::trace!("Ready to accept UDP connections on: {:?}", src_addr);
     // Buffers for deserialization
-    let pool = RecyclingObjectPool::new(1, || vec![0u8; UDP_MAX_MTU].into_boxed_slice());
+    let batch = (*UDP_RX_BATCH).max(1);
+    let pool = RecyclingObjectPool::new(batch, || vec![0u8; UDP_MAX_MTU].into_boxed_slice());
+    let mut received: Vec<LinkDatagram> = Vec::with_capacity(batch);
     while active.load(Ordering::Acquire) {
         let mut buff = pool.take().await;
         // Wait for incoming connections
@@ -565,42 +603,301 @@ async fn accept_read_task(
                 continue;
             }
         };
+        received.push((buff, n, dst_addr));
+
+        // Drain the datagrams that are already queued in the socket
+        #[cfg(target_os = "linux")]
+        if batch > 1 {
+            let mut buffs = Vec::with_capacity(batch - 1);
+            while buffs.len() < batch - 1 {
+                match pool.try_take() {
+                    Some(b) => buffs.push(b),
+                    None => break,
+                }
+            }
+            if !buffs.is_empty() {
+                match recv_batch(&socket, &mut buffs) {
+                    Ok(res) => {
+                        let mut buffs = buffs.drain(..);
+                        for datagram in res {
+                            let buff = buffs.next().unwrap();
+                            match datagram {
+                                Some((n, dst_addr)) => received.push((buff, n, dst_addr)),
+                                None => buff.recycle().await,
+                            }
+                        }
+                        for b in buffs {
+                            b.recycle().await;
+                        }
+                    }
+                    Err(e) => {
+                        if e.kind() != std::io::ErrorKind::WouldBlock {
+                            log::trace!("UDP batched reception on {} failed: {}", src_addr, e);
+                        }
+                        for b in buffs.drain(..) {
+                            b.recycle().await;
+                        }
+                    }
+                }
+            }
+        }
 
-        let link = loop {
-            let res = zgetlink!(src_addr, dst_addr);
-            match res {
-                Some(link) => break link.upgrade(),
+        for (buff, n, dst_addr) in received.drain(..) {
+            let link = loop {
+                let res = zgetlink!(src_addr, dst_addr);
+                match res {
+                    Some(link) => break link.upgrade(),
+                    None => {
+                        // A new peers has sent data to this socket
+                        log::debug!("Accepted UDP connection on {}: {}", src_addr, dst_addr);
+                        let unconnected = Arc::new(LinkUdpUnconnected {
+                            socket: Arc::downgrade(&socket),
+                            links: links.clone(),
+                            input: Mvar::new(),
+                            leftover: AsyncMutex::new(None),
+                        });
+                        zaddlink!(src_addr, dst_addr, Arc::downgrade(&unconnected));
+                        // Create the new link object
+                        let link = Arc::new(LinkUdp::new(
+                            src_addr,
+                            dst_addr,
+                            LinkUdpVariant::Unconnected(unconnected),
+                        ));
+                        // Add the new link to the set of connected peers
+                        manager.handle_new_link(Link(link), None).await;
+                    }
+                }
+            };
+
+            match link {
+                Some(link) => {
+                    link.received(buff, n).await;
+                }
                 None => {
-                    // A new peers has sent data to this socket
-                    log::debug!("Accepted UDP connection on {}: {}", src_addr, dst_addr);
-                    let unconnected = Arc::new(LinkUdpUnconnected {
-                        socket: Arc::downgrade(&socket),
-                        links: links.clone(),
-                        input: Mvar::new(),
-                        leftover: AsyncMutex::new(None),
-                    });
-                    zaddlink!(src_addr, dst_addr, Arc::downgrade(&unconnected));
-                    // Create the new link object
-                    let link = Arc::new(LinkUdp::new(
-                        src_addr,
-                        dst_addr,
-                        LinkUdpVariant::Unconnected(unconnected),
-                    ));
-                    // Add the new link to the set of connected peers
-                    manager.handle_new_link(Link(link), None).await;
+                    zdellink!(src_addr, dst_addr);
                 }
             }
-        };
+        }
+    }
+
+    Ok(())
+}
 
-        match link {
-            Some(link) => {
-                link.received(buff, n).await;
+// Reads the datagrams already queued in the socket with a single recvmmsg() system call,
+// without blocking. Returns the length and the source address of each datagram, in the
+// same order as the buffers they have been written into, or None for the datagrams that
+// are dropped: the ones truncated to the size of their buffer, and the ones from an
+// unsupported address family.
+#[cfg(target_os = "linux")]
+fn recv_batch(
+    socket: &UdpSocket,
+    buffers: &mut [RecyclingObject<Box<[u8]>>],
+) -> std::io::Result<Vec<Option<(usize, SocketAddr)>>> {
+    use std::os::unix::io::AsRawFd;
+
+    let mut addrs: Vec<libc::sockaddr_storage> = vec![unsafe { std::mem::zeroed() }; buffers.len()];
+    let mut iovecs: Vec<libc::iovec> = buffers
+        .iter_mut()
+        .map(|b| libc::iovec {
+            iov_base: b.as_mut_ptr() as *mut libc::c_void,
+            iov_len: b.len(),
+        })
+        .collect();
+    let mut msgs: Vec<libc::mmsghdr> = iovecs
+        .iter_mut()
+        .zip(addrs.iter_mut())
+        .map(|(iov, addr)| {
+            let mut hdr: libc::msghdr = unsafe { std::mem::zeroed() };
+            hdr.msg_name = addr as *mut libc::sockaddr_storage as *mut libc::c_void;
+            hdr.msg_namelen = std::mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+            hdr.msg_iov = iov as *mut libc::iovec;
+            hdr.msg_iovlen = 1;
+            libc::mmsghdr {
+                msg_hdr: hdr,
+                msg_len: 0,
+            }
+        })
+        .collect();
+
+    let n = unsafe {
+        libc::recvmmsg(
+            socket.as_raw_fd(),
+            msgs.as_mut_ptr(),
+            msgs.len() as libc::c_uint,
+            libc::MSG_DONTWAIT,
+            std::ptr::null_mut(),
+        )
+    };
+    if n < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    let mut res = Vec::with_capacity(n as usize);
+    for (msg, addr) in msgs.iter().zip(addrs.iter()).take(n as usize) {
+        match sockaddr_to_addr(addr) {
+            Some(src) if msg.msg_hdr.msg_flags & libc::MSG_TRUNC != 0 => {
+                log::warn!("Dropped UDP datagram from {}: truncated", src);
+                res.push(None);
             }
+            Some(src) => res.push(Some((msg.msg_len as usize, src))),
             None => {
-                zdellink!(src_addr, dst_addr);
+                log::warn!(
+                    "Dropped UDP datagram: unsupported address family {}",
+                    addr.ss_family
+                );
+                res.push(None);
             }
         }
     }
+    Ok(res)
+}
 
-    Ok(())
+// Writes the `buffers` with a single sendmmsg() system call, without blocking, each as a
+// datagram to `dst_addr` if the socket is not connected. Returns the number of datagrams
+// written, the first ones of `buffers`.
+#[cfg(target_os = "linux")]
+fn send_batch(
+    socket: &UdpSocket,
+    buffers: &[&[u8]],
+    dst_addr: Option<SocketAddr>,
+) -> std::io::Result<usize> {
+    use std::os::unix::io::AsRawFd;
+
+    let mut dst = dst_addr.as_ref().map(addr_to_sockaddr);
+    let mut iovecs: Vec<libc::iovec> = buffers
+        .iter()
+        .map(|b| libc::iovec {
+            iov_base: b.as_ptr() as *mut libc::c_void,
+            iov_len: b.len(),
+        })
+        .collect();
+    let mut msgs: Vec<libc::mmsghdr> = iovecs
+        .iter_mut()
+        .map(|iov| {
+            let mut hdr: libc::msghdr = unsafe { std::mem::zeroed() };
+            if let Some((addr, len)) = dst.as_mut() {
+                hdr.msg_name = addr as *mut libc::sockaddr_storage as *mut libc::c_void;
+                hdr.msg_namelen = *len;
+            }
+            hdr.msg_iov = iov as *mut libc::iovec;
+            hdr.msg_iovlen = 1;
+            libc::mmsghdr {
+                msg_hdr: hdr,
+                msg_len: 0,
+            }
+        })
+        .collect();
+
+    let n = unsafe {
+        libc::sendmmsg(
+            socket.as_raw_fd(),
+            msgs.as_mut_ptr(),
+            msgs.len() as libc::c_uint,
+            libc::MSG_DONTWAIT,
+        )
+    };
+    if n < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(n as usize)
+}
+
+#[cfg(target_os = "linux")]
+fn addr_to_sockaddr(addr: &SocketAddr) -> (libc::sockaddr_storage, libc::socklen_t) {
+    let mut storage: libc::sockaddr_storage = unsafe { std::mem::zeroed() };
+    let len = match addr {
+        SocketAddr::V4(addr) => {
+            let sin = unsafe { &mut *(&mut storage as *mut _ as *mut libc::sockaddr_in) };
+            sin.sin_family = libc::AF_INET as libc::sa_family_t;
+            sin.sin_port = addr.port().to_be();
+            sin.sin_addr = libc::in_addr {
+                s_addr: u32::from(*addr.ip()).to_be(),
+            };
+            std::mem::size_of::<libc::sockaddr_in>()
+        }
+        SocketAddr::V6(addr) => {
+            let sin6 = unsafe { &mut *(&mut storage as *mut _ as *mut libc::sockaddr_in6) };
+            sin6.sin6_family = libc::AF_INET6 as libc::sa_family_t;
+            sin6.sin6_port = addr.port().to_be();
+            sin6.sin6_flowinfo = addr.flowinfo();
+            sin6.sin6_addr = libc::in6_addr {
+                s6_addr: addr.ip().octets(),
+            };
+            sin6.sin6_scope_id = addr.scope_id();
+            std::mem::size_of::<libc::sockaddr_in6>()
+        }
+    };
+    (storage, len as libc::socklen_t)
+}
+
+#[cfg(target_os = "linux")]
+fn sockaddr_to_addr(addr: &libc::sockaddr_storage) -> Option<SocketAddr> {
+    use std::net::{Ipv4Addr, Ipv6Addr, SocketAddrV4, SocketAddrV6};
+
+    match addr.ss_family as libc::c_int {
+        libc::AF_INET => {
+            let addr = unsafe { &*(addr as *const _ as *const libc::sockaddr_in) };
+            let ip = Ipv4Addr::from(u32::from_be(addr.sin_addr.s_addr));
+            Some(SocketAddr::V4(SocketAddrV4::new(
+                ip,
+                u16::from_be(addr.sin_port),
+            )))
+        }
+        libc::AF_INET6 => {
+            let addr = unsafe { &*(addr as *const _ as *const libc::sockaddr_in6) };
+            let ip = Ipv6Addr::from(addr.sin6_addr.s6_addr);
+            Some(SocketAddr::V6(SocketAddrV6::new(
+                ip,
+                u16::from_be(addr.sin6_port),
+                addr.sin6_flowinfo,
+                addr.sin6_scope_id,
+            )))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sockaddr() {
+        for addr in &["127.0.0.1:7447", "[::1]:7447", "[fe80::1%1]:7447"] {
+            let addr: SocketAddr = addr.parse().unwrap();
+            let (storage, _) = addr_to_sockaddr(&addr);
+            assert_eq!(sockaddr_to_addr(&storage), Some(addr));
+        }
+        let storage: libc::sockaddr_storage = unsafe { std::mem::zeroed() };
+        assert_eq!(sockaddr_to_addr(&storage), None);
+    }
+
+    #[test]
+    fn test_batch() {
+        task::block_on(async {
+            let sender = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            let receiver = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            let src_addr = sender.local_addr().unwrap();
+            let dst_addr = receiver.local_addr().unwrap();
+
+            let pool = RecyclingObjectPool::new(4, || vec![0u8; 8].into_boxed_slice());
+            let mut buffers = vec![];
+            for _ in 0..4 {
+                buffers.push(pool.take().await);
+            }
+            // nothing is queued in the socket
+            let err = recv_batch(&receiver, &mut buffers).unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::WouldBlock);
+
+            let datagrams: [&[u8]; 3] = [b"a", b"bc", b"too long datagram"];
+            assert_eq!(send_batch(&sender, &datagrams, Some(dst_addr)).unwrap(), 3);
+            task::sleep(Duration::from_millis(100)).await;
+
+            // the truncated datagram is dropped, not the following ones
+            let res = recv_batch(&receiver, &mut buffers).unwrap();
+            assert_eq!(res, vec![Some((1, src_addr)), Some((2, src_addr)), None]);
+            assert_eq!(&buffers[0][..1], b"a");
+            assert_eq!(&buffers[1][..2], b"bc");
+        });
+    }
 }
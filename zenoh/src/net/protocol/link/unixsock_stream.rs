@@ -11,8 +11,11 @@
 // Contributors:
 //   ADLINK zenoh team, <zenoh@adlink-labs.tech>
 //
+#[cfg(all(feature = "zero-copy", target_os = "linux"))]
+use super::super::io::{memfd_register, memfd_release, memfd_segments_from};
 use super::session::SessionManager;
 use super::{Link, LinkManagerTrait, LinkTrait, Locator, LocatorProperty};
+use async_io::Async;
 use async_std::os::unix::net::{UnixListener, UnixStream};
 use async_std::path::{Path, PathBuf};
 use async_std::prelude::*;
@@ -23,14 +26,19 @@ use std::collections::HashMap;
 use std::fmt;
 use std::fs::remove_file;
 use std::net::Shutdown;
-use std::os::unix::io::RawFd;
+use std::os::unix::io::{FromRawFd, IntoRawFd, RawFd};
+use std::os::unix::net::UnixStream as StdUnixStream;
 use std::str::FromStr;
 use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(all(feature = "zero-copy", target_os = "linux"))]
+use std::sync::Mutex;
 use std::sync::{Arc, RwLock};
 use std::time::Duration;
 use uuid::Uuid;
 use zenoh_util::core::{ZError, ZErrorKind, ZResult};
 use zenoh_util::sync::Signal;
+#[cfg(all(feature = "zero-copy", target_os = "linux"))]
+use zenoh_util::zlock;
 use zenoh_util::{zerror, zerror2, zread, zwrite};
 
 // Default MTU (UnixSocketStream PDU) in bytes.
@@ -49,6 +57,14 @@ zconfigurable! {
     static ref UNIXSOCKSTREAM_ACCEPT_THROTTLE_TIME: u64 = 100_000;
 }
 
+// The maximum number of file descriptors passed along a single write (SCM_MAX_FD on Linux).
+#[cfg(all(feature = "zero-copy", target_os = "linux"))]
+const UNIXSOCKSTREAM_MAX_FDS: usize = 253;
+// The maximum number of memfd segments received from the other end of a link. The file
+// descriptors passed beyond are closed straight away.
+#[cfg(all(feature = "zero-copy", target_os = "linux"))]
+const UNIXSOCKSTREAM_MAX_SEGMENTS: usize = 64;
+
 #[allow(unreachable_patterns)]
 fn get_unix_path(locator: &Locator) -> ZResult<PathBuf> {
     match locator {
@@ -118,21 +134,196 @@ pub type LocatorPropertyUnixSocketStream = ();
 /*              LINK                 */
 /*************************************/
 pub struct LinkUnixSocketStream {
-    // The underlying socket as returned from the async-std library
-    socket: UnixStream,
+    // The underlying socket, registered with the reactor of the async-std library
+    // to send and receive file descriptors along the bytes
+    socket: Async<StdUnixStream>,
     // The Unix domain socket source path
     src_path: String,
     // The Unix domain socker destination path (random UUIDv4)
     dst_path: String,
+    // The memfd segments passed to and received from the other end
+    #[cfg(all(feature = "zero-copy", target_os = "linux"))]
+    segments: Mutex<LinkSegments>,
+}
+
+#[cfg(all(feature = "zero-copy", target_os = "linux"))]
+#[derive(Default)]
+struct LinkSegments {
+    // Whether the session of the link was established with shared memory
+    shm: bool,
+    // The index of the next memfd segment to pass to the other end before the session
+    // is established with shared memory, and once established
+    handshake_index: usize,
+    index: usize,
+    // The paths of the memfd segments received from the other end
+    received: Vec<String>,
 }
 
 impl LinkUnixSocketStream {
-    fn new(socket: UnixStream, src_path: String, dst_path: String) -> LinkUnixSocketStream {
-        LinkUnixSocketStream {
+    fn new(
+        socket: UnixStream,
+        src_path: String,
+        dst_path: String,
+    ) -> ZResult<LinkUnixSocketStream> {
+        let socket = unsafe { StdUnixStream::from_raw_fd(socket.into_raw_fd()) };
+        let socket = Async::new(socket).map_err(|e| {
+            zerror2!(ZErrorKind::IoError {
+                descr: e.to_string()
+            })
+        })?;
+        Ok(LinkUnixSocketStream {
             socket,
             src_path,
             dst_path,
+            #[cfg(all(feature = "zero-copy", target_os = "linux"))]
+            segments: Mutex::new(LinkSegments::default()),
+        })
+    }
+}
+
+// The memfd segments created by this process are passed to the other end along the first
+// write following their creation, so that it can map them before reading their buffers.
+// Until the session of the link is established with shared memory, only the segment of the
+// SHM authenticator is passed. The segments received from a peer are never passed to the
+// other peers, and are released when the link closes.
+#[cfg(all(feature = "zero-copy", target_os = "linux"))]
+impl LinkUnixSocketStream {
+    async fn send(&self, buffer: &[u8]) -> std::io::Result<usize> {
+        use nix::sys::socket::{sendmsg, ControlMessage, MsgFlags};
+        use nix::sys::uio::IoVec;
+        use std::os::unix::io::AsRawFd;
+
+        let (shm, (fds, next)) = {
+            let segments = zlock!(self.segments);
+            let shm = segments.shm;
+            let index = if shm {
+                segments.index
+            } else {
+                segments.handshake_index
+            };
+            (
+                shm,
+                memfd_segments_from(index, UNIXSOCKSTREAM_MAX_FDS, !shm),
+            )
+        };
+        if fds.is_empty() {
+            self.set_segments_index(shm, next);
+            return (&self.socket).write(buffer).await;
+        }
+
+        let res = self
+            .socket
+            .write_with(|socket| {
+                let iov = [IoVec::from_slice(buffer)];
+                let cmsgs = [ControlMessage::ScmRights(&fds)];
+                sendmsg(socket.as_raw_fd(), &iov, &cmsgs, MsgFlags::empty(), None)
+                    .map_err(nix_to_io_error)
+            })
+            .await;
+        for fd in fds {
+            unsafe { libc::close(fd) };
         }
+        if res.is_ok() {
+            log::trace!("Passed memfd segments on UnixSocketStream link: {}", self);
+            self.set_segments_index(shm, next);
+        }
+        res
+    }
+
+    fn set_segments_index(&self, shm: bool, next: usize) {
+        let mut segments = zlock!(self.segments);
+        if shm {
+            segments.index = next;
+        } else {
+            segments.handshake_index = next;
+        }
+    }
+
+    // Registers the segments passed by the other end, each followed by the segment of its
+    // reference counts, up to UNIXSOCKSTREAM_MAX_SEGMENTS.
+    fn register_segments(&self, fds: Vec<RawFd>) {
+        let mut segments = zlock!(self.segments);
+        for pair in fds.chunks(2) {
+            let (fd, rc_fd) = match *pair {
+                [fd, rc_fd] => (fd, rc_fd),
+                _ => {
+                    unsafe { libc::close(pair[0]) };
+                    break;
+                }
+            };
+            if segments.received.len() >= UNIXSOCKSTREAM_MAX_SEGMENTS {
+                log::warn!(
+                    "Ignoring the memfd segments beyond {} on UnixSocketStream link: {}",
+                    UNIXSOCKSTREAM_MAX_SEGMENTS,
+                    self
+                );
+                unsafe {
+                    libc::close(fd);
+                    libc::close(rc_fd);
+                }
+                continue;
+            }
+            if let Some(path) = memfd_register(fd, rc_fd) {
+                if segments.received.contains(&path) {
+                    memfd_release(&path);
+                } else {
+                    segments.received.push(path);
+                }
+            }
+        }
+    }
+
+    fn release_segments(&self) {
+        for path in zlock!(self.segments).received.drain(..) {
+            memfd_release(&path);
+        }
+    }
+
+    async fn recv(&self, buffer: &mut [u8]) -> std::io::Result<usize> {
+        use nix::sys::socket::{recvmsg, ControlMessageOwned, MsgFlags};
+        use nix::sys::uio::IoVec;
+        use std::os::unix::io::AsRawFd;
+
+        let mut cmsg_buffer = nix::cmsg_space!([RawFd; UNIXSOCKSTREAM_MAX_FDS]);
+        self.socket
+            .read_with(|socket| {
+                let iov = [IoVec::from_mut_slice(&mut *buffer)];
+                let msg = recvmsg(
+                    socket.as_raw_fd(),
+                    &iov,
+                    Some(&mut cmsg_buffer),
+                    MsgFlags::MSG_CMSG_CLOEXEC,
+                )
+                .map_err(nix_to_io_error)?;
+                for cmsg in msg.cmsgs() {
+                    if let ControlMessageOwned::ScmRights(fds) = cmsg {
+                        self.register_segments(fds);
+                    }
+                }
+                Ok(msg.bytes)
+            })
+            .await
+    }
+}
+
+#[cfg(not(all(feature = "zero-copy", target_os = "linux")))]
+impl LinkUnixSocketStream {
+    async fn send(&self, buffer: &[u8]) -> std::io::Result<usize> {
+        (&self.socket).write(buffer).await
+    }
+
+    async fn recv(&self, buffer: &mut [u8]) -> std::io::Result<usize> {
+        (&self.socket).read(buffer).await
+    }
+
+    fn release_segments(&self) {}
+}
+
+#[cfg(all(feature = "zero-copy", target_os = "linux"))]
+fn nix_to_io_error(e: nix::Error) -> std::io::Error {
+    match e.as_errno() {
+        Some(errno) => std::io::Error::from_raw_os_error(errno as i32),
+        None => std::io::Error::new(std::io::ErrorKind::Other, e),
     }
 }
 
@@ -140,8 +331,9 @@ impl LinkUnixSocketStream {
 impl LinkTrait for LinkUnixSocketStream {
     async fn close(&self) -> ZResult<()> {
         log::trace!("Closing UnixSocketStream link: {}", self);
+        self.release_segments();
         // Close the underlying UnixSocketStream socket
-        let res = self.socket.shutdown(Shutdown::Both);
+        let res = self.socket.get_ref().shutdown(Shutdown::Both);
         log::trace!("UnixSocketStream link shutdown {}: {:?}", self, res);
         res.map_err(|e| {
             zerror2!(ZErrorKind::IoError {
@@ -151,7 +343,7 @@ impl LinkTrait for LinkUnixSocketStream {
     }
 
     async fn write(&self, buffer: &[u8]) -> ZResult<usize> {
-        (&self.socket).write(buffer).await.map_err(|e| {
+        self.send(buffer).await.map_err(|e| {
             let e = format!("Write error on UnixSocketStream link {}: {}", self, e);
             log::trace!("{}", e);
             zerror2!(ZErrorKind::IoError { descr: e })
@@ -159,15 +351,27 @@ impl LinkTrait for LinkUnixSocketStream {
     }
 
     async fn write_all(&self, buffer: &[u8]) -> ZResult<()> {
-        (&self.socket).write_all(buffer).await.map_err(|e| {
-            let e = format!("Write error on UnixSocketStream link {}: {}", self, e);
-            log::trace!("{}", e);
-            zerror2!(ZErrorKind::IoError { descr: e })
-        })
+        let mut written = 0;
+        while written < buffer.len() {
+            match self.send(&buffer[written..]).await {
+                Ok(0) => {
+                    let e = format!("Write error on UnixSocketStream link {}: closed", self);
+                    log::trace!("{}", e);
+                    return zerror!(ZErrorKind::IoError { descr: e });
+                }
+                Ok(n) => written += n,
+                Err(e) => {
+                    let e = format!("Write error on UnixSocketStream link {}: {}", self, e);
+                    log::trace!("{}", e);
+                    return zerror!(ZErrorKind::IoError { descr: e });
+                }
+            }
+        }
+        Ok(())
     }
 
     async fn read(&self, buffer: &mut [u8]) -> ZResult<usize> {
-        (&self.socket).read(buffer).await.map_err(|e| {
+        self.recv(buffer).await.map_err(|e| {
             let e = format!("Read error on UnixSocketStream link {}: {}", self, e);
             log::trace!("{}", e);
             zerror2!(ZErrorKind::IoError { descr: e })
@@ -175,11 +379,23 @@ impl LinkTrait for LinkUnixSocketStream {
     }
 
     async fn read_exact(&self, buffer: &mut [u8]) -> ZResult<()> {
-        (&self.socket).read_exact(buffer).await.map_err(|e| {
-            let e = format!("Read error on UnixSocketStream link {}: {}", self, e);
-            log::trace!("{}", e);
-            zerror2!(ZErrorKind::IoError { descr: e })
-        })
+        let mut read = 0;
+        while read < buffer.len() {
+            match self.recv(&mut buffer[read..]).await {
+                Ok(0) => {
+                    let e = format!("Read error on UnixSocketStream link {}: closed", self);
+                    log::trace!("{}", e);
+                    return zerror!(ZErrorKind::IoError { descr: e });
+                }
+                Ok(n) => read += n,
+                Err(e) => {
+                    let e = format!("Read error on UnixSocketStream link {}: {}", self, e);
+                    log::trace!("{}", e);
+                    return zerror!(ZErrorKind::IoError { descr: e });
+                }
+            }
+        }
+        Ok(())
     }
 
     #[inline(always)]
@@ -210,12 +426,18 @@ impl LinkTrait for LinkUnixSocketStream {
     fn is_streamed(&self) -> bool {
         true
     }
+
+    #[cfg(all(feature = "zero-copy", target_os = "linux"))]
+    fn set_shm(&self) {
+        zlock!(self.segments).shm = true;
+    }
 }
 
 impl Drop for LinkUnixSocketStream {
     fn drop(&mut self) {
+        self.release_segments();
         // Close the underlying UnixSocketStream socket
-        let _ = self.socket.shutdown(Shutdown::Both);
+        let _ = self.socket.get_ref().shutdown(Shutdown::Both);
     }
 }
 
@@ -349,7 +571,7 @@ impl LinkManagerTrait for LinkManagerUnixSocketStream {
             stream,
             local_path_str,
             remote_path_str,
-        ));
+        )?);
 
         Ok(Link(link))
     }
@@ -607,11 +829,13 @@ async fn accept_task(
         log::debug!("Accepted UnixSocketStream connection on: {:?}", src_addr,);
 
         // Create the new link object
-        let link = Arc::new(LinkUnixSocketStream::new(
-            stream,
-            src_path.clone(),
-            dst_path,
-        ));
+        let link = match LinkUnixSocketStream::new(stream, src_path.clone(), dst_path) {
+            Ok(link) => Arc::new(link),
+            Err(e) => {
+                log::warn!("{}", e);
+                continue;
+            }
+        };
 
         // Communicate the new link to the initial session manager
         manager.handle_new_link(Link(link), None).await;
@@ -15,6 +15,13 @@ use super::core::*;
 use super::io::{ZBuf, ZSlice};
 use super::link::Locator;
 use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+use zenoh_util::zconfigurable;
+
+zconfigurable! {
+    /// The maximum number of hops of the provenance chain of a DataInfo.
+    pub static ref PROVENANCE_MAX_HOPS: usize = 16;
+}
 
 /*************************************/
 /*               IDS                 */
@@ -117,6 +124,8 @@ pub mod smsg {
         pub const MAX_SESSIONS: u8 = 0x03;
         pub const MAX_LINKS: u8 = 0x04;
         pub const EXPIRED: u8 = 0x05;
+        pub const QUOTA: u8 = 0x06;
+        pub const DRAIN: u8 = 0x07;
     }
 }
 
@@ -174,6 +183,8 @@ pub mod zmsg {
             pub const SRCSN: ZInt = 1 << 8; // 0x100
             pub const RTRID: ZInt = 1 << 9; // 0x200
             pub const RTRSN: ZInt = 1 << 10; // 0x400
+            pub const DIGEST: ZInt = 1 << 11; // 0x800
+            pub const PROVENANCE: ZInt = 1 << 12; // 0x1000
         }
     }
 
@@ -197,6 +208,8 @@ pub mod zmsg {
 
         pub mod flag {
             pub const PERIOD: u8 = 0x80;
+            pub const HISTORY: u8 = 0x40;
+            pub const FILTER: u8 = 0x20;
         }
     }
 
@@ -405,14 +418,17 @@ impl RoutingContext {
 /// -  0: Payload kind
 /// -  1: Payload encoding
 /// -  2: Payload timestamp
-/// -  3: Payload source_id
-/// -  4: Payload source_sn
+/// -  3: Reserved
+/// -  4: Reserved
 /// -  5: Payload is sliced
 /// -  6: Reserved
-/// -  7: Reserved
-/// -  8: First router_id
-/// -  9: First router_sn
-/// - 10-63: Reserved
+/// -  7: Payload source_id
+/// -  8: Payload source_sn
+/// -  9: First router_id
+/// - 10: First router_sn
+/// - 11: Payload digest
+/// - 12: Provenance chain
+/// - 13-63: Reserved
 ///
 ///  7 6 5 4 3 2 1 0
 /// +-+-+-+---------+
@@ -432,6 +448,10 @@ impl RoutingContext {
 /// +---------------+
 /// ~first_router_sn~ if options & (1 << 10)
 /// +---------------+
+/// ~    digest     ~ if options & (1 << 11)
+/// +---------------+
+/// ~  provenance   ~ if options & (1 << 12)
+/// +---------------+
 ///
 /// - if options & (1 << 5) then the payload is sliced
 ///
@@ -447,12 +467,62 @@ pub struct DataInfo {
     pub source_sn: Option<ZInt>,
     pub first_router_id: Option<PeerId>,
     pub first_router_sn: Option<ZInt>,
+    /// The BLAKE3 hash of the payload, set by the publisher for end-to-end integrity checks.
+    pub digest: Option<Vec<u8>>,
+    /// The hops the data went through, from the publisher to the last router or bridge.
+    pub provenance: Option<Vec<ProvenanceHop>>,
 }
 
 impl DataInfo {
     pub fn new() -> DataInfo {
         DataInfo::default()
     }
+
+    /// Appends the hop of `pid` to the provenance chain, if any. Once the chain holds
+    /// [`PROVENANCE_MAX_HOPS`] hops, the oldest hop after the publisher's one is dropped.
+    pub fn append_hop(&mut self, pid: PeerId) {
+        if let Some(provenance) = self.provenance.as_mut() {
+            if provenance.len() >= *PROVENANCE_MAX_HOPS {
+                provenance.remove(1.min(provenance.len() - 1));
+            }
+            provenance.push(ProvenanceHop::new(pid));
+        }
+    }
+}
+
+/// # ProvenanceHop
+///
+/// A hop of the provenance chain of a DataInfo
+///
+/// ```text
+///
+///  7 6 5 4 3 2 1 0
+/// +-+-+-+---------+
+/// ~      pid      ~
+/// +---------------+
+/// ~     time      ~
+/// +---------------+
+///
+/// ```
+///
+/// The provenance chain is encoded as its number of hops followed by the hops.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProvenanceHop {
+    /// The id of the publisher, router or bridge.
+    pub pid: PeerId,
+    /// The time the data went through it, in microseconds since the UNIX epoch.
+    pub time: ZInt,
+}
+
+impl ProvenanceHop {
+    /// Returns the hop of `pid` at the current time.
+    pub fn new(pid: PeerId) -> ProvenanceHop {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_micros() as ZInt;
+        ProvenanceHop { pid, time }
+    }
 }
 
 impl Default for DataInfo {
@@ -467,6 +537,8 @@ impl Default for DataInfo {
             source_sn: None,
             first_router_id: None,
             first_router_sn: None,
+            digest: None,
+            provenance: None,
         }
     }
 }
@@ -499,6 +571,12 @@ impl Options for DataInfo {
         if self.first_router_sn.is_some() {
             options |= zmsg::data::info::RTRSN;
         }
+        if self.digest.is_some() {
+            options |= zmsg::data::info::DIGEST;
+        }
+        if self.provenance.is_some() {
+            options |= zmsg::data::info::PROVENANCE;
+        }
         options
     }
 
@@ -524,6 +602,8 @@ impl Options for DataInfo {
             || self.source_sn.is_some()
             || self.first_router_id.is_some()
             || self.first_router_sn.is_some()
+            || self.digest.is_some()
+            || self.provenance.is_some()
     }
 }
 
@@ -740,10 +820,15 @@ impl Header for ForgetPublisher {
 /// +---------------+
 /// ~    ResKey     ~ if K==1 then only numerical id
 /// +---------------+
-/// |P|  SubMode    | if S==1. Otherwise: SubMode=Push
+/// |P|H|F| SubMode | if S==1. Otherwise: SubMode=Push
 /// +---------------+
 /// ~    Period     ~ if P==1. Otherwise: None
 /// +---------------+
+/// ~    Filter     ~ if F==1. Otherwise: None
+/// +---------------+
+///
+/// H: History - if H==1 then the subscriber asks for the latest values (History::Latest)
+/// F: Filter - if F==1 then the subscriber only wants the payloads matching the Filter expression
 /// ```
 #[derive(Debug, Clone, PartialEq)]
 pub struct Subscriber {
@@ -758,7 +843,11 @@ impl Header for Subscriber {
         if self.info.reliability == Reliability::Reliable {
             header |= zmsg::flag::R;
         }
-        if !(self.info.mode == SubMode::Push && self.info.period.is_none()) {
+        if !(self.info.mode == SubMode::Push
+            && self.info.period.is_none()
+            && self.info.history == History::None
+            && self.info.filter.is_none())
+        {
             header |= zmsg::flag::S;
         }
         if self.key.is_numerical() {
@@ -936,7 +1025,7 @@ impl Control for Pull {
 /// ```text
 ///  7 6 5 4 3 2 1 0
 /// +-+-+-+-+-+-+-+-+
-/// |K|X|T|  QUERY  |
+/// |K|N|T|  QUERY  |
 /// +-+-+-+---------+
 /// ~    ResKey     ~ if K==1 then only numerical id
 /// +---------------+
@@ -948,6 +1037,10 @@ impl Control for Pull {
 /// +---------------+
 /// ~ consolidation ~
 /// +---------------+
+/// ~     limit     ~ if N==1
+/// +---------------+
+/// ~     after     ~ if N==1 (empty if the page is the first one)
+/// +---------------+
 /// ```
 #[derive(Debug, Clone, PartialEq)]
 pub struct Query {
@@ -956,6 +1049,7 @@ pub struct Query {
     pub qid: ZInt,
     pub target: Option<QueryTarget>,
     pub consolidation: QueryConsolidation,
+    pub page: Option<QueryPage>,
 }
 
 impl Header for Query {
@@ -965,6 +1059,9 @@ impl Header for Query {
         if self.target.is_some() {
             header |= zmsg::flag::T;
         }
+        if self.page.is_some() {
+            header |= zmsg::flag::N;
+        }
         if self.key.is_numerical() {
             header |= zmsg::flag::K;
         }
@@ -1193,6 +1290,7 @@ impl ZenohMessage {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     #[inline(always)]
     pub fn make_query(
         key: ResKey,
@@ -1200,6 +1298,7 @@ impl ZenohMessage {
         qid: ZInt,
         target: Option<QueryTarget>,
         consolidation: QueryConsolidation,
+        page: Option<QueryPage>,
         routing_context: Option<RoutingContext>,
         attachment: Option<Attachment>,
     ) -> ZenohMessage {
@@ -1210,6 +1309,7 @@ impl ZenohMessage {
                 qid,
                 target,
                 consolidation,
+                page,
             }),
             routing_context,
             reply_context: None,
@@ -448,6 +448,24 @@ impl ZBuf {
         if imsg::has_option(options, zmsg::data::info::RTRSN) {
             info.first_router_sn = Some(self.read_zint()?);
         }
+        if imsg::has_option(options, zmsg::data::info::DIGEST) {
+            info.digest = Some(self.read_bytes_array()?);
+        }
+        if imsg::has_option(options, zmsg::data::info::PROVENANCE) {
+            let len = self.read_zint_as_usize()?;
+            // A chain never holds more hops: reject it before allocating
+            if len > *PROVENANCE_MAX_HOPS {
+                log::trace!("Provenance chain of {} hops rejected", len);
+                return None;
+            }
+            let mut provenance = Vec::with_capacity(len);
+            for _ in 0..len {
+                let pid = self.read_peerid()?;
+                let time = self.read_zint()?;
+                provenance.push(ProvenanceHop { pid, time });
+            }
+            info.provenance = Some(provenance);
+        }
 
         Some(info)
     }
@@ -517,10 +535,10 @@ impl ZBuf {
                     Reliability::BestEffort
                 };
                 let key = self.read_reskey(imsg::has_flag(header, zmsg::flag::K))?;
-                let (mode, period) = if imsg::has_flag(header, zmsg::flag::S) {
+                let (mode, period, history, filter) = if imsg::has_flag(header, zmsg::flag::S) {
                     self.read_submode()?
                 } else {
-                    (SubMode::Push, None)
+                    (SubMode::Push, None, History::None, None)
                 };
                 Some(Declaration::Subscriber(Subscriber {
                     key,
@@ -528,6 +546,8 @@ impl ZBuf {
                         reliability,
                         mode,
                         period,
+                        history,
+                        filter,
                     },
                 }))
             }
@@ -573,6 +593,13 @@ impl ZBuf {
             None
         };
         let consolidation = self.read_consolidation()?;
+        let page = if imsg::has_flag(header, zmsg::flag::N) {
+            let limit = self.read_zint()?;
+            let after = Some(self.read_string()?).filter(|after| !after.is_empty());
+            Some(QueryPage { limit, after })
+        } else {
+            None
+        };
 
         Some(ZenohBody::Query(Query {
             key,
@@ -580,6 +607,7 @@ impl ZBuf {
             qid,
             target,
             consolidation,
+            page,
         }))
     }
 
@@ -631,12 +659,15 @@ impl ZBuf {
         })
     }
 
-    fn read_submode(&mut self) -> Option<(SubMode, Option<Period>)> {
+    #[allow(clippy::type_complexity)]
+    fn read_submode(
+        &mut self,
+    ) -> Option<(SubMode, Option<Period>, History, Option<PayloadFilter>)> {
         use super::zmsg::declaration::flag::*;
         use super::zmsg::declaration::id::*;
 
         let mode_flag = self.read()?;
-        let mode = match mode_flag & !PERIOD {
+        let mode = match mode_flag & !(PERIOD | HISTORY | FILTER) {
             MODE_PUSH => SubMode::Push,
             MODE_PULL => SubMode::Pull,
             id => {
@@ -653,7 +684,24 @@ impl ZBuf {
         } else {
             None
         };
-        Some((mode, period))
+        let history = if imsg::has_flag(mode_flag, HISTORY) {
+            History::Latest
+        } else {
+            History::None
+        };
+        let filter = if imsg::has_flag(mode_flag, FILTER) {
+            let expr = self.read_string()?;
+            match expr.parse::<PayloadFilter>() {
+                Ok(filter) => Some(filter),
+                Err(e) => {
+                    log::trace!("INVALID FILTER FOR SubMode: {}", e);
+                    return None;
+                }
+            }
+        } else {
+            None
+        };
+        Some((mode, period, history, filter))
     }
 
     fn read_query_target(&mut self) -> Option<QueryTarget> {
@@ -672,6 +720,11 @@ impl ZBuf {
             }
             2 => Some(Target::All),
             3 => Some(Target::None),
+            4 => Some(Target::Nearest),
+            5 => {
+                let n = self.read_zint()?;
+                Some(Target::BestMatchingN { n })
+            }
             id => {
                 log::trace!("UNEXPECTED ID FOR Target: {}", id);
                 None
@@ -302,6 +302,16 @@ impl WBuf {
         if let Some(sn) = &info.first_router_sn {
             zcheck!(self.write_zint(*sn));
         }
+        if let Some(digest) = &info.digest {
+            zcheck!(self.write_bytes_array(digest));
+        }
+        if let Some(provenance) = &info.provenance {
+            zcheck!(self.write_usize_as_zint(provenance.len()));
+            for hop in provenance.iter() {
+                zcheck!(self.write_peerid(&hop.pid));
+                zcheck!(self.write_zint(hop.time));
+            }
+        }
 
         true
     }
@@ -326,7 +336,7 @@ impl WBuf {
                 zcheck!(self.write(header));
                 zcheck!(self.write_reskey(&s.key));
                 if imsg::has_flag(header, zmsg::flag::S) {
-                    zcheck!(self.write_submode(&s.info.mode, &s.info.period))
+                    zcheck!(self.write_submode(&s.info))
                 }
                 true
             }
@@ -352,21 +362,33 @@ impl WBuf {
         }
     }
 
-    fn write_submode(&mut self, mode: &SubMode, period: &Option<Period>) -> bool {
-        let period_mask: u8 = if period.is_some() {
+    fn write_submode(&mut self, info: &SubInfo) -> bool {
+        let mut period_mask: u8 = if info.period.is_some() {
             zmsg::declaration::flag::PERIOD
         } else {
             0
         };
-        zcheck!(match mode {
+        if info.history == History::Latest {
+            period_mask |= zmsg::declaration::flag::HISTORY;
+        }
+        if info.filter.is_some() {
+            period_mask |= zmsg::declaration::flag::FILTER;
+        }
+        zcheck!(match info.mode {
             SubMode::Push => self.write(zmsg::declaration::id::MODE_PUSH | period_mask),
             SubMode::Pull => self.write(zmsg::declaration::id::MODE_PULL | period_mask),
         });
-        if let Some(p) = period {
-            self.write_zint(p.origin) && self.write_zint(p.period) && self.write_zint(p.duration)
-        } else {
-            true
+        if let Some(p) = &info.period {
+            zcheck!(
+                self.write_zint(p.origin)
+                    && self.write_zint(p.period)
+                    && self.write_zint(p.duration)
+            );
         }
+        if let Some(filter) = &info.filter {
+            zcheck!(self.write_string(filter.expr()));
+        }
+        true
     }
 
     fn write_unit(&mut self, unit: &Unit) -> bool {
@@ -391,7 +413,12 @@ impl WBuf {
         if let Some(t) = query.target.as_ref() {
             zcheck!(self.write_query_target(t));
         }
-        self.write_consolidation(&query.consolidation)
+        zcheck!(self.write_consolidation(&query.consolidation));
+        if let Some(page) = query.page.as_ref() {
+            zcheck!(self.write_zint(page.limit));
+            zcheck!(self.write_string(page.after.as_deref().unwrap_or("")));
+        }
+        true
     }
 
     fn write_link_state_list(&mut self, link_state_list: &LinkStateList) -> bool {
@@ -436,6 +463,8 @@ impl WBuf {
             Target::Complete { n } => self.write_zint(1 as ZInt) && self.write_zint(*n),
             Target::All => self.write_zint(2 as ZInt),
             Target::None => self.write_zint(3 as ZInt),
+            Target::Nearest => self.write_zint(4 as ZInt),
+            Target::BestMatchingN { n } => self.write_zint(5 as ZInt) && self.write_zint(*n),
         }
     }
 
@@ -0,0 +1,627 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use super::super::link::LocatorProtocol;
+use super::{
+    attachment, AuthenticatedPeerLink, PeerAuthenticator, PeerAuthenticatorOutput,
+    PeerAuthenticatorTrait,
+};
+use super::{PeerId, Property, WBuf, ZBuf, ZInt};
+use async_std::fs;
+use async_std::io;
+use async_std::net::TcpStream;
+use async_std::prelude::*;
+use async_std::sync::{Arc, Mutex};
+use async_trait::async_trait;
+use http_types::Url;
+use ring::signature::{self, RsaPublicKeyComponents, UnparsedPublicKey};
+use serde_json::Value;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use zenoh_util::core::{ZError, ZErrorKind, ZResult};
+use zenoh_util::properties::config::*;
+use zenoh_util::{zasynclock, zconfigurable};
+
+const WBUF_SIZE: usize = 64;
+const JWT_VERSION: ZInt = 0;
+const JWT_METHOD: &str = "jwt";
+
+zconfigurable! {
+    // The minimal interval in seconds between two fetches of the JWKS, when a JWT is signed
+    // with a key unknown to the authenticator (e.g. after a rotation of the keys).
+    static ref JWT_JWKS_REFRESH_INTERVAL: u64 = 60;
+    // The timeout in seconds of a fetch of the JWKS.
+    static ref JWT_JWKS_FETCH_TIMEOUT: u64 = 10;
+    // The maximal size in bytes of a response to a fetch of the JWKS.
+    static ref JWT_JWKS_MAX_RESPONSE_SIZE: u64 = 1_048_576;
+    // The tolerated clock skew in seconds when checking the validity period of a JWT.
+    static ref JWT_CLOCK_SKEW: u64 = 60;
+}
+
+/*************************************/
+/*             InitSyn               */
+/*************************************/
+///  7 6 5 4 3 2 1 0
+/// +-+-+-+-+-+-+-+-+
+/// |0 0 0|  ATTCH  |
+/// +-+-+-+---------+
+/// ~    version    ~
+/// +---------------+
+/// ~     token     ~
+/// +---------------+
+struct InitSynProperty {
+    version: ZInt,
+    token: Vec<u8>,
+}
+
+impl WBuf {
+    fn write_init_syn_property_jwt(&mut self, init_syn_property: &InitSynProperty) -> bool {
+        zcheck!(self.write_zint(init_syn_property.version));
+        self.write_bytes_array(&init_syn_property.token)
+    }
+}
+
+impl ZBuf {
+    fn read_init_syn_property_jwt(&mut self) -> Option<InitSynProperty> {
+        let version = self.read_zint()?;
+        let token = self.read_bytes_array()?;
+        Some(InitSynProperty { version, token })
+    }
+}
+
+/*************************************/
+/*               JWKS                */
+/*************************************/
+enum PublicKey {
+    Rsa { n: Vec<u8>, e: Vec<u8> },
+    Ec { crv: String, point: Vec<u8> },
+}
+
+struct Jwk {
+    kid: Option<String>,
+    key: PublicKey,
+}
+
+impl Jwk {
+    // Returns true if `signature` is a valid signature of `message` with this key and `alg`.
+    fn verify(&self, alg: &str, message: &[u8], signature: &[u8]) -> bool {
+        match &self.key {
+            PublicKey::Rsa { n, e } => {
+                let params = match alg {
+                    "RS256" => &signature::RSA_PKCS1_2048_8192_SHA256,
+                    "RS384" => &signature::RSA_PKCS1_2048_8192_SHA384,
+                    "RS512" => &signature::RSA_PKCS1_2048_8192_SHA512,
+                    "PS256" => &signature::RSA_PSS_2048_8192_SHA256,
+                    "PS384" => &signature::RSA_PSS_2048_8192_SHA384,
+                    "PS512" => &signature::RSA_PSS_2048_8192_SHA512,
+                    _ => return false,
+                };
+                RsaPublicKeyComponents { n, e }
+                    .verify(params, message, signature)
+                    .is_ok()
+            }
+            PublicKey::Ec { crv, point } => {
+                let alg = match (alg, crv.as_str()) {
+                    ("ES256", "P-256") => &signature::ECDSA_P256_SHA256_FIXED,
+                    ("ES384", "P-384") => &signature::ECDSA_P384_SHA384_FIXED,
+                    _ => return false,
+                };
+                UnparsedPublicKey::new(alg, point)
+                    .verify(message, signature)
+                    .is_ok()
+            }
+        }
+    }
+}
+
+fn invalid_jwks(source: &str, reason: &str) -> ZError {
+    zerror2!(ZErrorKind::Other {
+        descr: format!("Invalid JWKS {}: {}", source, reason)
+    })
+}
+
+fn decode_base64url(s: &str) -> Option<Vec<u8>> {
+    base64::decode_config(s, base64::URL_SAFE_NO_PAD).ok()
+}
+
+// Parses the RSA and EC signature keys of a JSON Web Key Set (RFC 7517).
+fn parse_jwks(source: &str, content: &str) -> ZResult<Vec<Jwk>> {
+    let jwks: Value =
+        serde_json::from_str(content).map_err(|e| invalid_jwks(source, &e.to_string()))?;
+    let keys = jwks["keys"]
+        .as_array()
+        .ok_or_else(|| invalid_jwks(source, "no keys"))?;
+    let field = |key: &Value, name: &str| key[name].as_str().and_then(decode_base64url);
+    let mut jwks = vec![];
+    for key in keys {
+        if key["use"].as_str().unwrap_or("sig") != "sig" {
+            continue;
+        }
+        let public_key = match key["kty"].as_str() {
+            Some("RSA") => match (field(key, "n"), field(key, "e")) {
+                (Some(n), Some(e)) => {
+                    // the leading zeros of the modulus are rejected by the verification
+                    let start = n.iter().position(|b| *b != 0).unwrap_or(n.len());
+                    PublicKey::Rsa {
+                        n: n[start..].to_vec(),
+                        e,
+                    }
+                }
+                _ => return Err(invalid_jwks(source, "RSA key without n or e")),
+            },
+            Some("EC") => match (key["crv"].as_str(), field(key, "x"), field(key, "y")) {
+                (Some(crv), Some(x), Some(y)) => {
+                    let mut point = vec![0x04];
+                    point.extend_from_slice(&x);
+                    point.extend_from_slice(&y);
+                    PublicKey::Ec {
+                        crv: crv.to_string(),
+                        point,
+                    }
+                }
+                _ => return Err(invalid_jwks(source, "EC key without crv, x or y")),
+            },
+            kty => {
+                log::debug!("Ignore JWK of unsupported type {:?} in {}", kty, source);
+                continue;
+            }
+        };
+        jwks.push(Jwk {
+            kid: key["kid"].as_str().map(|kid| kid.to_string()),
+            key: public_key,
+        });
+    }
+    Ok(jwks)
+}
+
+// Sends a GET request for `path` on `stream` and returns the body of the response.
+async fn http_get<S: io::Read + io::Write + Unpin>(
+    mut stream: S,
+    host: &str,
+    path: &str,
+) -> std::io::Result<Vec<u8>> {
+    let fail = |reason: String| std::io::Error::new(std::io::ErrorKind::Other, reason);
+    // HTTP/1.0: the body of the response is neither chunked nor kept alive
+    let request = format!(
+        "GET {} HTTP/1.0\r\nHost: {}\r\nAccept: application/json\r\n\r\n",
+        path, host
+    );
+    stream.write_all(request.as_bytes()).await?;
+    let mut response = vec![];
+    let max = *JWT_JWKS_MAX_RESPONSE_SIZE;
+    (&mut stream)
+        .take(max + 1)
+        .read_to_end(&mut response)
+        .await?;
+    if response.len() as u64 > max {
+        return Err(fail(format!("HTTP response larger than {} bytes", max)));
+    }
+    let end = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| fail("invalid HTTP response".into()))?;
+    let status_line = String::from_utf8_lossy(&response[..end])
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    match status_line.split_whitespace().nth(1) {
+        Some("200") => Ok(response.split_off(end + 4)),
+        _ => Err(fail(format!("unexpected HTTP response: {}", status_line))),
+    }
+}
+
+// Reads the JWKS from a file or from an https:// URL, or from an http:// URL if `allow_http`.
+async fn fetch_jwks(source: &str, allow_http: bool) -> ZResult<String> {
+    let url = match Url::parse(source) {
+        Ok(url) if url.scheme() == "http" || url.scheme() == "https" => url,
+        _ => {
+            return fs::read_to_string(source)
+                .await
+                .map_err(|e| invalid_jwks(source, &e.to_string()));
+        }
+    };
+    let tls = url.scheme() == "https";
+    // Anyone on the path of an http:// JWKS could replace its keys with their own
+    if !tls && !allow_http {
+        return Err(invalid_jwks(
+            source,
+            &format!("http:// requires {}=true", ZN_JWT_JWKS_INSECURE_STR),
+        ));
+    }
+    // The host of an IPv6 address is enclosed in brackets
+    let host = match url.host_str() {
+        Some(host) => host,
+        None => return Err(invalid_jwks(source, "no host")),
+    };
+    let addr = format!(
+        "{}:{}",
+        host,
+        url.port_or_known_default()
+            .unwrap_or(if tls { 443 } else { 80 })
+    );
+    let path = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    };
+
+    let fetch = async {
+        let stream = TcpStream::connect(&addr).await?;
+        if !tls {
+            return http_get(stream, host, &path).await;
+        }
+        #[cfg(feature = "transport_tls")]
+        {
+            use async_rustls::rustls::ClientConfig;
+            use async_rustls::webpki::DNSNameRef;
+            use async_rustls::TlsConnector;
+
+            let fail = |reason: String| std::io::Error::new(std::io::ErrorKind::Other, reason);
+            let domain = url
+                .domain()
+                .ok_or_else(|| fail("https requires a domain name".into()))?;
+            let domain = DNSNameRef::try_from_ascii_str(domain).map_err(|e| fail(e.to_string()))?;
+            let mut config = ClientConfig::new();
+            config
+                .root_store
+                .add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
+            let stream = TlsConnector::from(std::sync::Arc::new(config))
+                .connect(domain, stream)
+                .await?;
+            http_get(stream, host, &path).await
+        }
+        #[cfg(not(feature = "transport_tls"))]
+        {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "https requires the transport_tls feature",
+            ))
+        }
+    };
+    let body = fetch
+        .timeout(Duration::from_secs(*JWT_JWKS_FETCH_TIMEOUT))
+        .await
+        .map_err(|e| invalid_jwks(source, &e.to_string()))?
+        .map_err(|e| invalid_jwks(source, &e.to_string()))?;
+    String::from_utf8(body).map_err(|e| invalid_jwks(source, &e.to_string()))
+}
+
+/*************************************/
+/*            Validator              */
+/*************************************/
+struct Keys {
+    jwks: Vec<Jwk>,
+    fetched: Instant,
+}
+
+/// The validation of the JWT presented by the peers: their signature against the keys
+/// of a JSON Web Key Set, their validity period and their audience.
+pub struct JwtValidator {
+    source: String,
+    allow_http: bool,
+    audience: Option<String>,
+    subject_claim: String,
+    keys: Mutex<Keys>,
+}
+
+impl JwtValidator {
+    /// Creates a validator of the JWT signed with the keys of the JWKS read from `source`,
+    /// a file path or an `https://` URL. The JWKS is fetched again when a JWT is signed
+    /// with an unknown key. An `http://` URL is only accepted if `allow_http`: anyone on
+    /// its path could replace the keys of the JWKS with their own.
+    ///
+    /// The JWT must be intended for `audience`, if any, and the `subject_claim` of a valid JWT
+    /// is the subject the peer is authenticated as.
+    pub async fn new(
+        source: &str,
+        allow_http: bool,
+        audience: Option<String>,
+        subject_claim: String,
+    ) -> ZResult<JwtValidator> {
+        let jwks = parse_jwks(source, &fetch_jwks(source, allow_http).await?)?;
+        log::debug!("Loaded {} JWK(s) from {}", jwks.len(), source);
+        Ok(JwtValidator {
+            source: source.to_string(),
+            allow_http,
+            audience,
+            subject_claim,
+            keys: Mutex::new(Keys {
+                jwks,
+                fetched: Instant::now(),
+            }),
+        })
+    }
+
+    async fn verify_signature(
+        &self,
+        alg: &str,
+        kid: Option<&str>,
+        message: &[u8],
+        signature: &[u8],
+    ) -> bool {
+        let matches = |jwk: &Jwk| kid.is_none() || jwk.kid.as_deref() == kid;
+        let refresh = {
+            let mut keys = zasynclock!(self.keys);
+            let refresh = !keys.jwks.iter().any(matches)
+                && keys.fetched.elapsed() >= Duration::from_secs(*JWT_JWKS_REFRESH_INTERVAL);
+            if refresh {
+                // Concurrent validations keep using the current keys during the fetch
+                keys.fetched = Instant::now();
+            }
+            refresh
+        };
+        if refresh {
+            let res = fetch_jwks(&self.source, self.allow_http)
+                .await
+                .and_then(|content| parse_jwks(&self.source, &content));
+            match res {
+                Ok(jwks) => {
+                    log::debug!("Reloaded {} JWK(s) from {}", jwks.len(), self.source);
+                    zasynclock!(self.keys).jwks = jwks;
+                }
+                Err(e) => log::warn!("{}", e),
+            }
+        }
+        zasynclock!(self.keys)
+            .jwks
+            .iter()
+            .filter(|jwk| matches(jwk))
+            .any(|jwk| jwk.verify(alg, message, signature))
+    }
+
+    // Validates a compact JWT (RFC 7519) and returns its subject.
+    async fn validate(&self, token: &str) -> Result<String, String> {
+        let parts: Vec<&str> = token.split('.').collect();
+        let (header, payload, signature) = match parts.as_slice() {
+            [header, payload, signature] => (header, payload, signature),
+            _ => return Err("malformed token".to_string()),
+        };
+        let decode_json = |part: &str| -> Option<Value> {
+            decode_base64url(part).and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        };
+        let header = decode_json(header).ok_or("malformed header")?;
+        let claims = decode_json(payload).ok_or("malformed claims")?;
+        let signature = decode_base64url(signature).ok_or("malformed signature")?;
+
+        let alg = header["alg"].as_str().ok_or("no signature algorithm")?;
+        let message = &token.as_bytes()[..token.len() - parts[2].len() - 1];
+        if !self
+            .verify_signature(alg, header["kid"].as_str(), message, &signature)
+            .await
+        {
+            return Err("invalid signature".to_string());
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let skew = *JWT_CLOCK_SKEW;
+        match claims["exp"].as_u64() {
+            Some(exp) if exp.saturating_add(skew) > now => (),
+            Some(_) => return Err("expired token".to_string()),
+            None => return Err("no expiration time".to_string()),
+        }
+        if let Some(nbf) = claims["nbf"].as_u64() {
+            if nbf > now.saturating_add(skew) {
+                return Err("token not yet valid".to_string());
+            }
+        }
+        if let Some(audience) = &self.audience {
+            let intended = match &claims["aud"] {
+                Value::String(aud) => aud == audience,
+                Value::Array(auds) => auds.iter().any(|aud| aud.as_str() == Some(audience)),
+                _ => false,
+            };
+            if !intended {
+                return Err(format!("token not intended for {}", audience));
+            }
+        }
+        match &claims[&self.subject_claim] {
+            Value::String(subject) => Ok(subject.clone()),
+            Value::Number(subject) => Ok(subject.to_string()),
+            _ => Err(format!("no {} claim", self.subject_claim)),
+        }
+    }
+}
+
+/*************************************/
+/*          Authenticator            */
+/*************************************/
+pub struct JwtAuthenticator {
+    token: Option<String>,
+    validator: Option<JwtValidator>,
+}
+
+impl JwtAuthenticator {
+    /// Creates an authenticator presenting `token` to the peers it opens a session with,
+    /// and requiring a JWT valid for `validator` from the peers opening a session with it.
+    pub fn new(token: Option<String>, validator: Option<JwtValidator>) -> JwtAuthenticator {
+        JwtAuthenticator { token, validator }
+    }
+
+    pub async fn from_properties(config: &ConfigProperties) -> ZResult<Option<JwtAuthenticator>> {
+        let token = config.get(&ZN_JWT_TOKEN_KEY).cloned();
+        if token.is_some() {
+            log::debug!("JWT has been configured");
+        }
+
+        let validator = match config.get(&ZN_JWT_JWKS_KEY) {
+            Some(jwks) => {
+                let subject_claim = config
+                    .get_or(&ZN_JWT_SUBJECT_CLAIM_KEY, ZN_JWT_SUBJECT_CLAIM_DEFAULT)
+                    .to_string();
+                let audience = config.get(&ZN_JWT_AUDIENCE_KEY).cloned();
+                let allow_http = config
+                    .get_or(&ZN_JWT_JWKS_INSECURE_KEY, ZN_JWT_JWKS_INSECURE_DEFAULT)
+                    .to_lowercase()
+                    == ZN_TRUE;
+                Some(JwtValidator::new(jwks, allow_http, audience, subject_claim).await?)
+            }
+            None => None,
+        };
+
+        if token.is_some() || validator.is_some() {
+            log::debug!("JWT authentication is enabled");
+            Ok(Some(JwtAuthenticator::new(token, validator)))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+// The JWT being a bearer token, it is only sent on the links encrypting their traffic
+fn is_encrypted(link: &AuthenticatedPeerLink) -> bool {
+    let encrypted: &[LocatorProtocol] = &[
+        #[cfg(feature = "transport_tls")]
+        LocatorProtocol::Tls,
+        #[cfg(feature = "transport_quic")]
+        LocatorProtocol::Quic,
+    ];
+    encrypted.contains(&link.dst.get_proto())
+}
+
+#[async_trait]
+impl PeerAuthenticatorTrait for JwtAuthenticator {
+    async fn get_init_syn_properties(
+        &self,
+        link: &AuthenticatedPeerLink,
+        _peer_id: &PeerId,
+    ) -> ZResult<PeerAuthenticatorOutput> {
+        let mut res = PeerAuthenticatorOutput::default();
+        // If no token is configured, don't initiate the JWT authentication
+        let token = match self.token.as_ref() {
+            Some(token) => token,
+            None => return Ok(res),
+        };
+        if !is_encrypted(link) {
+            log::warn!("Not sending the JWT on unencrypted link: {}", link);
+            return Ok(res);
+        }
+
+        let init_syn_property = InitSynProperty {
+            version: JWT_VERSION,
+            token: token.as_bytes().to_vec(),
+        };
+        let mut wbuf = WBuf::new(WBUF_SIZE, false);
+        wbuf.write_init_syn_property_jwt(&init_syn_property);
+        let zbuf: ZBuf = wbuf.into();
+
+        let prop = Property {
+            key: attachment::authorization::JWT,
+            value: zbuf.to_vec(),
+        };
+        res.properties.push(prop);
+        Ok(res)
+    }
+
+    async fn handle_init_syn(
+        &self,
+        link: &AuthenticatedPeerLink,
+        _peer_id: &PeerId,
+        _sn_resolution: ZInt,
+        properties: &[Property],
+    ) -> ZResult<PeerAuthenticatorOutput> {
+        let mut res = PeerAuthenticatorOutput::default();
+        // If no JWKS is configured, don't require a JWT
+        let validator = match self.validator.as_ref() {
+            Some(validator) => validator,
+            None => return Ok(res),
+        };
+
+        let tmp = properties
+            .iter()
+            .find(|p| p.key == attachment::authorization::JWT);
+        let mut zbuf: ZBuf = match tmp {
+            Some(p) => p.value.clone().into(),
+            None => {
+                return zerror!(ZErrorKind::InvalidMessage {
+                    descr: format!("Received InitSyn with no JWT on link: {}", link),
+                });
+            }
+        };
+        let init_syn_property = match zbuf.read_init_syn_property_jwt() {
+            Some(isp) => isp,
+            None => {
+                return zerror!(ZErrorKind::InvalidMessage {
+                    descr: format!("Received InitSyn with invalid attachment on link: {}", link),
+                });
+            }
+        };
+
+        if init_syn_property.version > JWT_VERSION {
+            return zerror!(ZErrorKind::InvalidMessage {
+                descr: format!("Rejected InitSyn with invalid attachment on link: {}", link),
+            });
+        }
+
+        let token = String::from_utf8_lossy(&init_syn_property.token);
+        match validator.validate(&token).await {
+            Ok(subject) => {
+                log::debug!("Authenticated {} with a JWT on link: {}", subject, link);
+                res.session.methods.push(JWT_METHOD);
+                res.session.subject = Some(subject);
+                Ok(res)
+            }
+            Err(e) => zerror!(ZErrorKind::InvalidMessage {
+                descr: format!("Received InitSyn with invalid JWT on link {}: {}", link, e),
+            }),
+        }
+    }
+
+    async fn handle_init_ack(
+        &self,
+        link: &AuthenticatedPeerLink,
+        _peer_id: &PeerId,
+        _sn_resolution: ZInt,
+        _properties: &[Property],
+    ) -> ZResult<PeerAuthenticatorOutput> {
+        let mut res = PeerAuthenticatorOutput::default();
+        // The JWT has not been sent on an unencrypted link
+        if self.token.is_some() && is_encrypted(link) {
+            res.session.methods.push(JWT_METHOD);
+        }
+        Ok(res)
+    }
+
+    async fn handle_open_syn(
+        &self,
+        _link: &AuthenticatedPeerLink,
+        _properties: &[Property],
+    ) -> ZResult<PeerAuthenticatorOutput> {
+        Ok(PeerAuthenticatorOutput::default())
+    }
+
+    async fn handle_open_ack(
+        &self,
+        _link: &AuthenticatedPeerLink,
+        _properties: &[Property],
+    ) -> ZResult<PeerAuthenticatorOutput> {
+        Ok(PeerAuthenticatorOutput::default())
+    }
+
+    async fn handle_link_err(&self, _link: &AuthenticatedPeerLink) {}
+
+    async fn handle_close(&self, _peer_id: &PeerId) {}
+}
+
+impl From<Arc<JwtAuthenticator>> for PeerAuthenticator {
+    fn from(v: Arc<JwtAuthenticator>) -> PeerAuthenticator {
+        PeerAuthenticator(v)
+    }
+}
+
+impl From<JwtAuthenticator> for PeerAuthenticator {
+    fn from(v: JwtAuthenticator) -> PeerAuthenticator {
+        Self::from(Arc::new(v))
+    }
+}
@@ -12,8 +12,10 @@
 //   ADLINK zenoh team, <zenoh@adlink-labs.tech>
 //
 pub(super) mod attachment;
+mod jwt;
 #[cfg(feature = "zero-copy")]
 mod shm;
+mod tls_peer_id;
 mod userpassword;
 #[cfg(feature = "zero-copy")]
 use super::core;
@@ -24,10 +26,12 @@ use super::io::{WBuf, ZBuf};
 use super::link::{Link, Locator, LocatorProperty};
 use async_std::sync::Arc;
 use async_trait::async_trait;
+pub use jwt::*;
 #[cfg(feature = "zero-copy")]
 pub use shm::*;
 use std::fmt;
 use std::ops::Deref;
+pub use tls_peer_id::*;
 pub use userpassword::*;
 use zenoh_util::core::ZResult;
 use zenoh_util::properties::config::*;
@@ -112,6 +116,16 @@ impl PeerAuthenticator {
             pas.push(pa.into());
         }
 
+        let mut res = JwtAuthenticator::from_properties(config).await?;
+        if let Some(pa) = res.take() {
+            pas.push(pa.into());
+        }
+
+        let mut res = TlsPeerIdAuthenticator::from_properties(config).await?;
+        if let Some(pa) = res.take() {
+            pas.push(pa.into());
+        }
+
         #[cfg(feature = "zero-copy")]
         {
             let mut res = SharedMemoryAuthenticator::from_properties(config).await?;
@@ -139,6 +153,7 @@ pub struct AuthenticatedPeerLink {
     pub dst: Locator,
     pub peer_id: Option<PeerId>,
     pub properties: Option<LocatorProperty>,
+    pub peer_certificate: Option<Vec<u8>>,
 }
 
 impl fmt::Display for AuthenticatedPeerLink {
@@ -150,19 +165,34 @@ impl fmt::Display for AuthenticatedPeerLink {
 // Authenticated peer session
 pub struct AuthenticatedPeerSession {
     pub is_local: bool,
+    // The authentication methods used to establish the session (ex: "usrpwd", "shm")
+    pub methods: Vec<&'static str>,
+    // The subject the peer was authenticated as, if any (ex: the subject of its JWT)
+    pub subject: Option<String>,
 }
 
 impl AuthenticatedPeerSession {
-    pub fn merge(self, other: Self) -> Self {
+    pub fn merge(mut self, other: Self) -> Self {
+        for method in other.methods {
+            if !self.methods.contains(&method) {
+                self.methods.push(method);
+            }
+        }
         Self {
             is_local: self.is_local || other.is_local,
+            methods: self.methods,
+            subject: self.subject.or(other.subject),
         }
     }
 }
 
 impl Default for AuthenticatedPeerSession {
     fn default() -> Self {
-        Self { is_local: false }
+        Self {
+            is_local: false,
+            methods: vec![],
+            subject: None,
+        }
     }
 }
 
@@ -29,6 +29,7 @@ use zenoh_util::zcheck;
 const WBUF_SIZE: usize = 64;
 const SHM_VERSION: ZInt = 0;
 const SHM_NAME: &str = "shmauth";
+const SHM_METHOD: &str = "shm";
 // Let's use a ZInt as a challenge
 const SHM_SIZE: usize = std::mem::size_of::<ZInt>();
 
@@ -160,14 +161,29 @@ impl SharedMemoryAuthenticator {
             let mut prng = PseudoRng::from_entropy();
             let challenge = prng.gen::<ZInt>();
 
-            let mut _manager =
-                SharedMemoryManager::new(format!("{}.{}", SHM_NAME, challenge), SHM_SIZE).map_err(
-                    |e| {
-                        zerror2!(ZErrorKind::Other {
-                            descr: e.to_string()
-                        })
-                    },
-                )?;
+            let id = format!("{}.{}", SHM_NAME, challenge);
+            let provider = config.get_or(&ZN_SHM_PROVIDER_KEY, ZN_SHM_PROVIDER_DEFAULT);
+            let manager = match provider {
+                "posix" => SharedMemoryManager::new(id, SHM_SIZE),
+                #[cfg(target_os = "linux")]
+                "memfd" => SharedMemoryManager::new_memfd(id, SHM_SIZE),
+                #[cfg(windows)]
+                "named" => SharedMemoryManager::new_named(id, SHM_SIZE),
+                _ => {
+                    return zerror!(ZErrorKind::Other {
+                        descr: format!("Invalid {}: {}", ZN_SHM_PROVIDER_STR, provider)
+                    })
+                }
+            };
+            let mut _manager = manager.map_err(|e| {
+                zerror2!(ZErrorKind::Other {
+                    descr: e.to_string()
+                })
+            })?;
+            // The segment is passed along the Unix socket links before the session is
+            // established, for the peers to read the challenge
+            #[cfg(target_os = "linux")]
+            _manager.set_handshake();
 
             let mut buffer = _manager.alloc(SHM_SIZE).unwrap();
             let slice = unsafe { buffer.as_mut_slice() };
@@ -367,6 +383,7 @@ impl PeerAuthenticatorTrait for SharedMemoryAuthenticator {
         res.properties.push(prop);
         if init_ack_property.challenge == self.challenge {
             res.session.is_local = true;
+            res.session.methods.push(SHM_METHOD);
         }
         Ok(res)
     }
@@ -398,6 +415,7 @@ impl PeerAuthenticatorTrait for SharedMemoryAuthenticator {
         let mut res = PeerAuthenticatorOutput::default();
         if open_syn_property.challenge == self.challenge {
             res.session.is_local = true;
+            res.session.methods.push(SHM_METHOD);
         }
         Ok(res)
     }
@@ -0,0 +1,194 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use super::{
+    AuthenticatedPeerLink, PeerAuthenticator, PeerAuthenticatorOutput, PeerAuthenticatorTrait,
+};
+use super::{PeerId, Property, ZInt};
+use async_std::sync::Arc;
+use async_trait::async_trait;
+use zenoh_util::core::{ZError, ZErrorKind, ZResult};
+use zenoh_util::properties::config::*;
+use zenoh_util::zerror;
+
+const TLS_PEER_ID_METHOD: &str = "tls_pid";
+// The suffix of the DNS subject alternative name binding a peer id to a certificate
+const PEER_ID_NAME_SUFFIX: &str = ".zenoh";
+
+// Returns whether the DER encoded X.509 `certificate` has the DNS subject alternative
+// name `name`, or None if the certificate can't be decoded
+#[cfg(any(feature = "transport_tls", feature = "transport_quic"))]
+fn is_valid_for_dns_name(certificate: &[u8], name: &str) -> Option<bool> {
+    use std::convert::TryFrom;
+    use webpki::{DnsNameRef, EndEntityCert, Error};
+
+    let certificate = EndEntityCert::try_from(certificate).ok()?;
+    let name = DnsNameRef::try_from_ascii_str(name).ok()?;
+    match certificate.verify_is_valid_for_dns_name(name) {
+        Ok(()) => Some(true),
+        Err(Error::CertNotValidForName) => Some(false),
+        Err(_) => None,
+    }
+}
+
+// Without TLS, there is no certificate to bind the peer ids to
+#[cfg(not(any(feature = "transport_tls", feature = "transport_quic")))]
+fn is_valid_for_dns_name(_certificate: &[u8], _name: &str) -> Option<bool> {
+    None
+}
+
+/*************************************/
+/*          Authenticator            */
+/*************************************/
+/// An authenticator requiring the peer id of the remote peers to be bound to the TLS
+/// certificate they presented when establishing the link, i.e. their certificate must have
+/// a DNS subject alternative name `<pid>.zenoh`. Since the certificate has been verified
+/// against the root CA during the TLS handshake, a peer can't use the peer id of another one.
+pub struct TlsPeerIdAuthenticator;
+
+impl TlsPeerIdAuthenticator {
+    pub fn new() -> TlsPeerIdAuthenticator {
+        TlsPeerIdAuthenticator
+    }
+
+    pub async fn from_properties(
+        config: &ConfigProperties,
+    ) -> ZResult<Option<TlsPeerIdAuthenticator>> {
+        let binding = config
+            .get_or(&ZN_TLS_PEER_ID_BINDING_KEY, ZN_TLS_PEER_ID_BINDING_DEFAULT)
+            .to_lowercase()
+            == ZN_TRUE;
+        if binding {
+            log::debug!("TLS peer id binding is enabled");
+            Ok(Some(TlsPeerIdAuthenticator::new()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn check_binding(link: &AuthenticatedPeerLink, peer_id: &PeerId) -> ZResult<()> {
+        let certificate = match link.peer_certificate.as_ref() {
+            Some(certificate) => certificate,
+            None => {
+                return zerror!(ZErrorKind::InvalidMessage {
+                    descr: format!(
+                        "No TLS certificate presented by {} on link: {}",
+                        peer_id, link
+                    ),
+                });
+            }
+        };
+        let expected = format!("{}{}", peer_id, PEER_ID_NAME_SUFFIX);
+        let bound = match is_valid_for_dns_name(certificate, &expected) {
+            Some(bound) => bound,
+            None => {
+                return zerror!(ZErrorKind::InvalidMessage {
+                    descr: format!(
+                        "Invalid TLS certificate presented by {} on link: {}",
+                        peer_id, link
+                    ),
+                });
+            }
+        };
+        if bound {
+            log::debug!(
+                "Authenticated {} with its TLS certificate on link: {}",
+                peer_id,
+                link
+            );
+            Ok(())
+        } else {
+            zerror!(ZErrorKind::InvalidMessage {
+                descr: format!(
+                    "The TLS certificate presented on link {} is not bound to {}",
+                    link, peer_id
+                ),
+            })
+        }
+    }
+}
+
+impl Default for TlsPeerIdAuthenticator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl PeerAuthenticatorTrait for TlsPeerIdAuthenticator {
+    async fn get_init_syn_properties(
+        &self,
+        _link: &AuthenticatedPeerLink,
+        _peer_id: &PeerId,
+    ) -> ZResult<PeerAuthenticatorOutput> {
+        Ok(PeerAuthenticatorOutput::default())
+    }
+
+    async fn handle_init_syn(
+        &self,
+        link: &AuthenticatedPeerLink,
+        peer_id: &PeerId,
+        _sn_resolution: ZInt,
+        _properties: &[Property],
+    ) -> ZResult<PeerAuthenticatorOutput> {
+        TlsPeerIdAuthenticator::check_binding(link, peer_id)?;
+        let mut res = PeerAuthenticatorOutput::default();
+        res.session.methods.push(TLS_PEER_ID_METHOD);
+        Ok(res)
+    }
+
+    async fn handle_init_ack(
+        &self,
+        link: &AuthenticatedPeerLink,
+        peer_id: &PeerId,
+        _sn_resolution: ZInt,
+        _properties: &[Property],
+    ) -> ZResult<PeerAuthenticatorOutput> {
+        TlsPeerIdAuthenticator::check_binding(link, peer_id)?;
+        let mut res = PeerAuthenticatorOutput::default();
+        res.session.methods.push(TLS_PEER_ID_METHOD);
+        Ok(res)
+    }
+
+    async fn handle_open_syn(
+        &self,
+        _link: &AuthenticatedPeerLink,
+        _properties: &[Property],
+    ) -> ZResult<PeerAuthenticatorOutput> {
+        Ok(PeerAuthenticatorOutput::default())
+    }
+
+    async fn handle_open_ack(
+        &self,
+        _link: &AuthenticatedPeerLink,
+        _properties: &[Property],
+    ) -> ZResult<PeerAuthenticatorOutput> {
+        Ok(PeerAuthenticatorOutput::default())
+    }
+
+    async fn handle_link_err(&self, _link: &AuthenticatedPeerLink) {}
+
+    async fn handle_close(&self, _peer_id: &PeerId) {}
+}
+
+impl From<Arc<TlsPeerIdAuthenticator>> for PeerAuthenticator {
+    fn from(v: Arc<TlsPeerIdAuthenticator>) -> PeerAuthenticator {
+        PeerAuthenticator(v)
+    }
+}
+
+impl From<TlsPeerIdAuthenticator> for PeerAuthenticator {
+    fn from(v: TlsPeerIdAuthenticator) -> PeerAuthenticator {
+        Self::from(Arc::new(v))
+    }
+}
@@ -20,15 +20,26 @@ use async_std::fs;
 use async_std::sync::{Arc, Mutex, RwLock};
 use async_trait::async_trait;
 use rand::{Rng, SeedableRng};
-use std::collections::{HashMap, HashSet};
+use ring::constant_time;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use zenoh_util::core::{ZError, ZErrorKind, ZResult};
 use zenoh_util::crypto::{hmac, PseudoRng};
 use zenoh_util::properties::config::*;
 use zenoh_util::properties::Properties;
-use zenoh_util::{zasynclock, zasyncread, zasyncwrite};
+use zenoh_util::{zasynclock, zasyncread, zasyncwrite, zconfigurable};
 
 const WBUF_SIZE: usize = 64;
-const USRPWD_VERSION: ZInt = 0;
+// Version 1 binds the HMAC to the nonces of both peers and to the peer id of the opener.
+// The openers of version 0 are still accepted, with the HMAC of the password keyed by the
+// nonce of the acceptor: their handshakes are not bound to the opener nor checked for replays.
+const USRPWD_VERSION: ZInt = 1;
+const USRPWD_METHOD: &str = "usrpwd";
+
+zconfigurable! {
+    // The number of recent handshakes remembered to detect their replays.
+    static ref USRPWD_REPLAY_WINDOW: usize = 4096;
+}
 
 /// # Attachment decorator
 ///
@@ -58,20 +69,26 @@ const USRPWD_VERSION: ZInt = 0;
 /// +-+-+-+---------+
 /// ~    version    ~
 /// +---------------+
+/// ~     nonce     ~
+/// +---------------+
 struct InitSynProperty {
     version: ZInt,
+    nonce: ZInt,
 }
 
 impl WBuf {
     fn write_init_syn_property_usrpwd(&mut self, init_syn_property: &InitSynProperty) -> bool {
-        self.write_zint(init_syn_property.version)
+        zcheck!(self.write_zint(init_syn_property.version));
+        self.write_zint(init_syn_property.nonce)
     }
 }
 
 impl ZBuf {
     fn read_init_syn_property_usrpwd(&mut self) -> Option<InitSynProperty> {
         let version = self.read_zint()?;
-        Some(InitSynProperty { version })
+        // The nonce is only sent since version 1
+        let nonce = if version > 0 { self.read_zint()? } else { 0 };
+        Some(InitSynProperty { version, nonce })
     }
 }
 
@@ -135,6 +152,62 @@ impl ZBuf {
 /*************************************/
 /*          Authenticator            */
 /*************************************/
+// Computes the response to the challenge of the acceptor: the HMAC of the nonces of both peers
+// and of the peer id of the opener, keyed by the password. Since the acceptor never reuses a
+// nonce, a response captured on a handshake can't be replayed on another one.
+fn hmac_challenge(
+    password: &[u8],
+    acceptor_nonce: ZInt,
+    opener_nonce: ZInt,
+    opener_pid: &PeerId,
+) -> ZResult<Vec<u8>> {
+    let mut data = vec![];
+    data.extend_from_slice(&acceptor_nonce.to_le_bytes());
+    data.extend_from_slice(&opener_nonce.to_le_bytes());
+    data.extend_from_slice(opener_pid.as_slice());
+    hmac::sign(password, &data)
+}
+
+// The values seen in the recent handshakes, to detect their replays
+struct ReplayWindow {
+    order: VecDeque<Vec<u8>>,
+    seen: HashSet<Vec<u8>>,
+}
+
+impl ReplayWindow {
+    fn new() -> ReplayWindow {
+        ReplayWindow {
+            order: VecDeque::new(),
+            seen: HashSet::new(),
+        }
+    }
+
+    fn contains(&self, value: &[u8]) -> bool {
+        self.seen.contains(value)
+    }
+
+    // Returns false if the value has already been seen
+    fn insert(&mut self, value: Vec<u8>) -> bool {
+        if !self.seen.insert(value.clone()) {
+            return false;
+        }
+        self.order.push_back(value);
+        if self.order.len() > *USRPWD_REPLAY_WINDOW {
+            if let Some(old) = self.order.pop_front() {
+                self.seen.remove(&old);
+            }
+        }
+        true
+    }
+}
+
+struct Nonces {
+    version: ZInt,
+    peer_id: PeerId,
+    acceptor: ZInt,
+    opener: ZInt,
+}
+
 struct Credentials {
     user: Vec<u8>,
     password: Vec<u8>,
@@ -148,9 +221,14 @@ struct Authenticated {
 pub struct UserPasswordAuthenticator {
     lookup: RwLock<HashMap<Vec<u8>, Vec<u8>>>,
     credentials: Option<Credentials>,
-    nonces: Mutex<HashMap<(Locator, Locator), (PeerId, ZInt)>>,
+    // The nonces of the handshakes accepted, by link
+    nonces: Mutex<HashMap<(Locator, Locator), Nonces>>,
+    // The nonces of the handshakes opened, with the local peer id, by link
+    challenges: Mutex<HashMap<(Locator, Locator), (PeerId, ZInt)>>,
     authenticated: Mutex<HashMap<PeerId, Authenticated>>,
     prng: Mutex<PseudoRng>,
+    replay_window: Mutex<ReplayWindow>,
+    rejected_replays: AtomicUsize,
 }
 
 impl UserPasswordAuthenticator {
@@ -166,11 +244,27 @@ impl UserPasswordAuthenticator {
             lookup: RwLock::new(lookup),
             credentials,
             nonces: Mutex::new(HashMap::new()),
+            challenges: Mutex::new(HashMap::new()),
             authenticated: Mutex::new(HashMap::new()),
             prng: Mutex::new(PseudoRng::from_entropy()),
+            replay_window: Mutex::new(ReplayWindow::new()),
+            rejected_replays: AtomicUsize::new(0),
         }
     }
 
+    /// Returns the number of handshakes rejected as replays of a previous handshake.
+    pub fn rejected_replays(&self) -> usize {
+        self.rejected_replays.load(Ordering::Relaxed)
+    }
+
+    fn reject_replay(&self, link: &AuthenticatedPeerLink, message: &str) -> ZResult<()> {
+        self.rejected_replays.fetch_add(1, Ordering::Relaxed);
+        log::warn!("Rejected a replayed {} on link: {}", message, link);
+        zerror!(ZErrorKind::InvalidMessage {
+            descr: format!("Rejected a replayed {} on link: {}", message, link),
+        })
+    }
+
     pub async fn add_user(&self, user: Vec<u8>, password: Vec<u8>) -> ZResult<()> {
         let mut guard = zasyncwrite!(self.lookup);
         guard.insert(user, password);
@@ -222,8 +316,8 @@ impl UserPasswordAuthenticator {
 impl PeerAuthenticatorTrait for UserPasswordAuthenticator {
     async fn get_init_syn_properties(
         &self,
-        _link: &AuthenticatedPeerLink,
-        _peer_id: &PeerId,
+        link: &AuthenticatedPeerLink,
+        peer_id: &PeerId,
     ) -> ZResult<PeerAuthenticatorOutput> {
         let mut res = PeerAuthenticatorOutput::default();
         // If credentials are not configured, don't initiate the USRPWD authentication
@@ -231,8 +325,14 @@ impl PeerAuthenticatorTrait for UserPasswordAuthenticator {
             return Ok(res);
         }
 
+        let nonce: ZInt = zasynclock!(self.prng).gen();
+        zasynclock!(self.challenges).insert(
+            (link.src.clone(), link.dst.clone()),
+            (peer_id.clone(), nonce),
+        );
         let init_syn_property = InitSynProperty {
             version: USRPWD_VERSION,
+            nonce,
         };
         let mut wbuf = WBuf::new(WBUF_SIZE, false);
         wbuf.write_init_syn_property_usrpwd(&init_syn_property);
@@ -250,7 +350,7 @@ impl PeerAuthenticatorTrait for UserPasswordAuthenticator {
         &self,
         link: &AuthenticatedPeerLink,
         peer_id: &PeerId,
-        sn_resolution: ZInt,
+        _sn_resolution: ZInt,
         properties: &[Property],
     ) -> ZResult<PeerAuthenticatorOutput> {
         let res = properties
@@ -275,12 +375,27 @@ impl PeerAuthenticatorTrait for UserPasswordAuthenticator {
 
         if init_syn_property.version > USRPWD_VERSION {
             return zerror!(ZErrorKind::InvalidMessage {
-                descr: format!("Rejected InitSyn with invalid attachment on link: {}", link),
+                descr: format!(
+                    "Rejected InitSyn with unsupported version {} on link: {}",
+                    init_syn_property.version, link
+                ),
             });
         }
 
+        // The version 0 openers send no nonce, so their InitSyn can't be checked for replays
+        if init_syn_property.version == 0 {
+            log::warn!(
+                "Accepting the USRPWD handshake of version 0, not protected against the replays, on link: {}",
+                link
+            );
+        } else if !zasynclock!(self.replay_window)
+            .insert(init_syn_property.nonce.to_le_bytes().to_vec())
+        {
+            self.reject_replay(link, "InitSyn")?;
+        }
+
         // Create the InitAck attachment
-        let nonce = zasynclock!(self.prng).gen_range(0..sn_resolution);
+        let nonce: ZInt = zasynclock!(self.prng).gen();
         let init_ack_property = InitAckProperty { nonce };
         // Encode the InitAck property
         let mut wbuf = WBuf::new(WBUF_SIZE, false);
@@ -292,10 +407,13 @@ impl PeerAuthenticatorTrait for UserPasswordAuthenticator {
         };
 
         // Insert the nonce in the set of sent nonces
-        zasynclock!(self.nonces).insert(
-            (link.src.clone(), link.dst.clone()),
-            (peer_id.clone(), nonce),
-        );
+        let nonces = Nonces {
+            version: init_syn_property.version,
+            peer_id: peer_id.clone(),
+            acceptor: nonce,
+            opener: init_syn_property.nonce,
+        };
+        zasynclock!(self.nonces).insert((link.src.clone(), link.dst.clone()), nonces);
 
         let mut res = PeerAuthenticatorOutput::default();
         res.properties.push(prop);
@@ -315,6 +433,18 @@ impl PeerAuthenticatorTrait for UserPasswordAuthenticator {
             Some(cr) => cr,
             None => return Ok(res),
         };
+        let (pid, opener_nonce) =
+            match zasynclock!(self.challenges).remove(&(link.src.clone(), link.dst.clone())) {
+                Some(challenge) => challenge,
+                None => {
+                    return zerror!(ZErrorKind::InvalidMessage {
+                        descr: format!(
+                            "Received InitAck but no nonce has been associated to link: {}",
+                            link
+                        ),
+                    });
+                }
+            };
 
         let tmp = properties
             .iter()
@@ -336,9 +466,13 @@ impl PeerAuthenticatorTrait for UserPasswordAuthenticator {
             }
         };
 
-        // Create the HMAC of the password using the nonce received as a key (it's a challenge)
-        let key = init_ack_property.nonce.to_le_bytes();
-        let hmac = hmac::sign(&key, &credentials.password)?;
+        // Respond to the challenge with the nonce received
+        let hmac = hmac_challenge(
+            &credentials.password,
+            init_ack_property.nonce,
+            opener_nonce,
+            &pid,
+        )?;
         // Create the OpenSyn attachment
         let open_syn_property = OpenSynProperty {
             user: credentials.user.clone(),
@@ -353,6 +487,7 @@ impl PeerAuthenticatorTrait for UserPasswordAuthenticator {
             value: zbuf.to_vec(),
         };
         res.properties.push(prop);
+        res.session.methods.push(USRPWD_METHOD);
         Ok(res)
     }
 
@@ -361,18 +496,17 @@ impl PeerAuthenticatorTrait for UserPasswordAuthenticator {
         link: &AuthenticatedPeerLink,
         properties: &[Property],
     ) -> ZResult<PeerAuthenticatorOutput> {
-        let (peer_id, nonce) =
-            match zasynclock!(self.nonces).remove(&(link.src.clone(), link.dst.clone())) {
-                Some(tuple) => tuple,
-                None => {
-                    return zerror!(ZErrorKind::InvalidMessage {
-                        descr: format!(
-                            "Received OpenSyn but no nonce has been associated to link: {}",
-                            link
-                        ),
-                    });
-                }
-            };
+        let nonces = match zasynclock!(self.nonces).remove(&(link.src.clone(), link.dst.clone())) {
+            Some(nonces) => nonces,
+            None => {
+                return zerror!(ZErrorKind::InvalidMessage {
+                    descr: format!(
+                        "Received OpenSyn but no nonce has been associated to link: {}",
+                        link
+                    ),
+                });
+            }
+        };
 
         let res = properties
             .iter()
@@ -403,14 +537,23 @@ impl PeerAuthenticatorTrait for UserPasswordAuthenticator {
             }
         };
 
-        // Create the HMAC of the password using the nonce received as challenge
-        let key = nonce.to_le_bytes();
-        let hmac = hmac::sign(&key, &password)?;
-        if hmac != open_syn_property.hmac {
+        // Check the response to the challenge sent with the InitAck
+        let hmac = if nonces.version == 0 {
+            hmac::sign(&nonces.acceptor.to_le_bytes(), &password)?
+        } else {
+            hmac_challenge(&password, nonces.acceptor, nonces.opener, &nonces.peer_id)?
+        };
+        if constant_time::verify_slices_are_equal(&hmac, &open_syn_property.hmac).is_err() {
+            // A response to a previous challenge is a replay of its OpenSyn
+            if zasynclock!(self.replay_window).contains(&open_syn_property.hmac) {
+                self.reject_replay(link, "OpenSyn")?;
+            }
             return zerror!(ZErrorKind::InvalidMessage {
                 descr: format!("Received OpenSyn with invalid password on link: {}", link),
             });
         }
+        zasynclock!(self.replay_window).insert(hmac);
+        let peer_id = nonces.peer_id;
 
         // Check PID validity
         let mut guard = zasynclock!(self.authenticated);
@@ -437,7 +580,9 @@ impl PeerAuthenticatorTrait for UserPasswordAuthenticator {
             }
         }
 
-        Ok(PeerAuthenticatorOutput::default())
+        let mut res = PeerAuthenticatorOutput::default();
+        res.session.methods.push(USRPWD_METHOD);
+        Ok(res)
     }
 
     async fn handle_open_ack(
@@ -450,6 +595,7 @@ impl PeerAuthenticatorTrait for UserPasswordAuthenticator {
 
     async fn handle_link_err(&self, link: &AuthenticatedPeerLink) {
         zasynclock!(self.nonces).remove(&(link.src.clone(), link.dst.clone()));
+        zasynclock!(self.challenges).remove(&(link.src.clone(), link.dst.clone()));
 
         // Need to check if it authenticated and remove it if this is the last link
         let mut guard = zasynclock!(self.authenticated);
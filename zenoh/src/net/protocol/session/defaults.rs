@@ -51,12 +51,19 @@ zconfigurable! {
     //       session lease.
     pub static ref ZN_LINK_KEEP_ALIVE: ZInt = 2_500;
 
+    // Number of consecutive keep alive tokens not echoed by the peer before flagging
+    // a link as degraded, when the keep alive echo is enabled on the link
+    pub static ref ZN_LINK_KEEP_ALIVE_ECHO_MISSED: ZInt = 3;
+
     // Default timeout when opening a session in milliseconds
     pub static ref ZN_OPEN_TIMEOUT: ZInt = 10_000;
 
     // Default maximum number of pending sessions being opened with the host
     pub static ref ZN_OPEN_INCOMING_PENDING: usize = 1_024;
 
+    // Default maximum size in bytes of a (defragmented) message: 1 GiB
+    pub static ref ZN_MAX_MESSAGE_SIZE: usize = 1_073_741_824;
+
     // Parameters of the link transmission queue
     // - The size of each queue relates to the number of batches a given queue can contain.
     // - The amount of memory being allocated for each queue is then QUEUE_SIZE_XXX * ZN_BATCH_SIZE.
@@ -69,6 +76,11 @@ zconfigurable! {
     // The default backoff time in nanoseconds to allow the batching to potentially progress
     pub static ref ZN_QUEUE_PULL_BACKOFF: u64 = 100;
 
-    // The total size of buffers allocated at RX side per link. Default 16MB.
+    // Maximum number of queued batches written at once on a datagram link (e.g. UDP),
+    // with a single system call on the links supporting it. Set it to 1 to write one
+    // batch at a time.
+    pub static ref ZN_LINK_TX_DATAGRAMS: usize = 8;
+
+    // The default amount of memory kept by the pool of RX buffers shared by all the links. Default 16MB.
     pub static ref ZN_RX_BUFF_SIZE: usize = 16_777_216;
 }
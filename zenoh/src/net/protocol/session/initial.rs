@@ -17,7 +17,7 @@ use super::authenticator::{
 use super::core::{PeerId, Property, WhatAmI, ZInt};
 use super::defaults::ZN_DEFAULT_SEQ_NUM_RESOLUTION;
 use super::io::{WBuf, ZBuf, ZSlice};
-use super::link::Link;
+use super::link::{Link, LinkConfig};
 use super::proto::{
     smsg, Attachment, Close, InitAck, InitSyn, OpenAck, OpenSyn, SessionBody, SessionMessage,
 };
@@ -61,6 +61,7 @@ fn properties_from_attachment(mut att: Attachment) -> ZResult<Vec<Property>> {
 struct Cookie {
     whatami: WhatAmI,
     pid: PeerId,
+    version: u8,
     sn_resolution: ZInt,
     nonce: ZInt,
 }
@@ -69,6 +70,7 @@ impl WBuf {
     fn write_cookie(&mut self, cookie: &Cookie) -> bool {
         zcheck!(self.write_zint(cookie.whatami));
         zcheck!(self.write_peerid(&cookie.pid));
+        zcheck!(self.write(cookie.version));
         zcheck!(self.write_zint(cookie.sn_resolution));
         zcheck!(self.write_zint(cookie.nonce));
         true
@@ -79,12 +81,14 @@ impl ZBuf {
     fn read_cookie(&mut self) -> Option<Cookie> {
         let whatami = self.read_zint()?;
         let pid = self.read_peerid()?;
+        let version = self.read()?;
         let sn_resolution = self.read_zint()?;
         let nonce = self.read_zint()?;
 
         Some(Cookie {
             whatami,
             pid,
+            version,
             sn_resolution,
             nonce,
         })
@@ -318,10 +322,11 @@ async fn open_send_open_syn(
     manager: &SessionManager,
     link: &Link,
     _auth_link: &AuthenticatedPeerLink,
+    config: &LinkConfig,
     input: OpenInitAckOutput,
 ) -> IResult<OpenOpenSynOutput> {
     // Build and send an OpenSyn message
-    let lease = manager.config.lease;
+    let lease = manager.get_lease(config);
     let message = SessionMessage::make_open_syn(
         lease,
         input.initial_sn_tx,
@@ -422,22 +427,28 @@ async fn open_stages(
     manager: &SessionManager,
     link: &Link,
     auth_link: &AuthenticatedPeerLink,
+    config: &LinkConfig,
 ) -> IResult<OpenAckOutput> {
     let output = open_send_init_syn(manager, link, auth_link).await?;
     let output = open_recv_init_ack(manager, link, auth_link, output).await?;
-    let output = open_send_open_syn(manager, link, auth_link, output).await?;
+    let output = open_send_open_syn(manager, link, auth_link, config, output).await?;
     open_recv_open_ack(manager, link, auth_link, output).await
 }
 
-pub(super) async fn open_link(manager: &SessionManager, link: &Link) -> ZResult<Session> {
+pub(super) async fn open_link(
+    manager: &SessionManager,
+    link: &Link,
+    config: &LinkConfig,
+) -> ZResult<Session> {
     let auth_link = AuthenticatedPeerLink {
         src: link.get_src(),
         dst: link.get_src(),
         peer_id: None,
         properties: None,
+        peer_certificate: link.get_peer_certificate(),
     };
 
-    let res = open_stages(manager, link, &auth_link).await;
+    let res = open_stages(manager, link, &auth_link, config).await;
     let info = match res {
         Ok(v) => v,
         Err((e, reason)) => {
@@ -446,13 +457,15 @@ pub(super) async fn open_link(manager: &SessionManager, link: &Link) -> ZResult<
         }
     };
 
+    // The peer accepted the session, thus our version
     let res = manager.init_session(
         &info.pid,
         info.whatami,
+        manager.config.version,
         info.sn_resolution,
         info.initial_sn_tx,
         info.initial_sn_rx,
-        info.auth_session.is_local,
+        info.auth_session,
     );
     let session = match res {
         Ok(s) => s,
@@ -475,11 +488,20 @@ pub(super) async fn open_link(manager: &SessionManager, link: &Link) -> ZResult<
         //       check which considers a link as failed when no messages are received in 3.5 times the
         //       target interval. For simplicity, we compute the keep_alive interval as 1/4 of the
         //       session lease.
-        let keep_alive = manager.config.keep_alive.min(info.lease / 4);
+        let keep_alive = manager.get_keep_alive(config).min(info.lease / 4);
         let _ = transport.add_link(link.clone())?;
 
         // Start the TX loop
-        let _ = transport.start_tx(&link, keep_alive, manager.config.batch_size)?;
+        let batch_size = config.batch_size.unwrap_or(manager.config.batch_size);
+        let keep_alive_echo = config.keep_alive_echo.unwrap_or(false);
+        let keep_alive_forced = config.keep_alive_forced.unwrap_or(false);
+        transport.start_tx(
+            &link,
+            keep_alive,
+            keep_alive_echo,
+            keep_alive_forced,
+            batch_size,
+        )?;
 
         // Assign a callback if the session is new
         loop {
@@ -517,6 +539,7 @@ pub(super) async fn open_link(manager: &SessionManager, link: &Link) -> ZResult<
 struct AcceptInitSynOutput {
     whatami: WhatAmI,
     pid: PeerId,
+    version: u8,
     sn_resolution: ZInt,
     init_ack_attachment: Option<Attachment>,
     auth_session: AuthenticatedPeerSession,
@@ -621,6 +644,7 @@ async fn accept_recv_init_syn(
     let output = AcceptInitSynOutput {
         whatami: init_syn_whatami,
         pid: init_syn_pid,
+        version: init_syn_version,
         sn_resolution: init_syn_sn_resolution,
         init_ack_attachment: attachment_from_properties(&auth.properties).ok(),
         auth_session: auth.session,
@@ -645,6 +669,7 @@ async fn accept_send_init_ack(
     let cookie = Cookie {
         whatami: input.whatami,
         pid: input.pid.clone(),
+        version: input.version,
         sn_resolution: agreed_sn_resolution,
         nonce: zasynclock!(manager.prng).gen_range(0..agreed_sn_resolution),
     };
@@ -882,10 +907,11 @@ async fn accept_init_session(
         .init_session(
             &input.cookie.pid,
             input.cookie.whatami,
+            input.cookie.version,
             input.cookie.sn_resolution,
             open_ack_initial_sn,
             input.initial_sn,
-            input.auth_session.is_local,
+            input.auth_session,
         )
         .map_err(|e| (e, Some(smsg::close_reason::INVALID)))?;
 
@@ -918,11 +944,12 @@ async fn accept_send_open_ack(
     manager: &SessionManager,
     link: &Link,
     _auth_link: &AuthenticatedPeerLink,
+    config: &LinkConfig,
     input: AcceptInitSessionOutput,
 ) -> ZResult<AcceptOpenAckOutput> {
     // Build OpenAck message
     let message = SessionMessage::make_open_ack(
-        manager.config.lease,
+        manager.get_lease(config),
         input.initial_sn,
         input.open_ack_attachment,
     );
@@ -941,6 +968,7 @@ async fn accept_finalize_session(
     manager: &SessionManager,
     link: &Link,
     _auth_link: &AuthenticatedPeerLink,
+    config: &LinkConfig,
     input: AcceptOpenAckOutput,
 ) -> ZResult<()> {
     // Retrive the session's transport
@@ -957,9 +985,18 @@ async fn accept_finalize_session(
         //       check which considers a link as failed when no messages are received in 3.5 times the
         //       target interval. For simplicity, we compute the keep_alive interval as 1/4 of the
         //       session lease.
-        let keep_alive = manager.config.keep_alive.min(input.lease / 4);
+        let keep_alive = manager.get_keep_alive(config).min(input.lease / 4);
         // Start the TX loop
-        let _ = transport.start_tx(&link, keep_alive, manager.config.batch_size)?;
+        let batch_size = config.batch_size.unwrap_or(manager.config.batch_size);
+        let keep_alive_echo = config.keep_alive_echo.unwrap_or(false);
+        let keep_alive_forced = config.keep_alive_forced.unwrap_or(false);
+        transport.start_tx(
+            &link,
+            keep_alive,
+            keep_alive_echo,
+            keep_alive_forced,
+            batch_size,
+        )?;
 
         // Assign a callback if the session is new
         loop {
@@ -1014,16 +1051,18 @@ async fn accept_session_stages(
     manager: &SessionManager,
     link: &Link,
     auth_link: &AuthenticatedPeerLink,
+    config: &LinkConfig,
     input: AcceptInitSessionOutput,
 ) -> ZResult<()> {
-    let output = accept_send_open_ack(manager, link, auth_link, input).await?;
-    accept_finalize_session(manager, link, auth_link, output).await
+    let output = accept_send_open_ack(manager, link, auth_link, config, input).await?;
+    accept_finalize_session(manager, link, auth_link, config, output).await
 }
 
 pub(super) async fn accept_link(
     manager: &SessionManager,
     link: &Link,
     auth_link: &AuthenticatedPeerLink,
+    config: &LinkConfig,
 ) -> ZResult<()> {
     let res = accept_link_stages(manager, link, auth_link).await;
     let output = match res {
@@ -1035,7 +1074,7 @@ pub(super) async fn accept_link(
     };
 
     let session = output.session.clone();
-    let res = accept_session_stages(manager, link, auth_link, output).await;
+    let res = accept_session_stages(manager, link, auth_link, config, output).await;
     if let Err(e) = res {
         let _ = session.close().await;
         return Err(e);
@@ -12,26 +12,30 @@
 //   ADLINK zenoh team, <zenoh@adlink-labs.tech>
 //
 use super::authenticator::{
-    AuthenticatedPeerLink, DummyLinkAuthenticator, DummyPeerAuthenticator, LinkAuthenticator,
-    PeerAuthenticator,
+    AuthenticatedPeerLink, AuthenticatedPeerSession, DummyLinkAuthenticator,
+    DummyPeerAuthenticator, LinkAuthenticator, PeerAuthenticator,
 };
 use super::core::{PeerId, WhatAmI, ZInt};
 use super::defaults::{
     ZN_DEFAULT_BATCH_SIZE, ZN_DEFAULT_SEQ_NUM_RESOLUTION, ZN_LINK_KEEP_ALIVE, ZN_LINK_LEASE,
-    ZN_OPEN_INCOMING_PENDING, ZN_OPEN_TIMEOUT,
+    ZN_MAX_MESSAGE_SIZE, ZN_OPEN_INCOMING_PENDING, ZN_OPEN_TIMEOUT, ZN_QUEUE_NUM, ZN_RX_BUFF_SIZE,
 };
 #[cfg(feature = "zero-copy")]
 use super::io::SharedMemoryReader;
 use super::link::{
-    Link, LinkManager, LinkManagerBuilder, Locator, LocatorProperty, LocatorProtocol,
+    Link, LinkConfig, LinkManager, LinkManagerBuilder, Locator, LocatorProperty, LocatorProtocol,
+    Proxy,
 };
-use super::transport::SessionTransport;
-use super::{Session, SessionHandler};
+use super::transport::{BufferPool, SessionTransport};
+#[cfg(feature = "stats")]
+use super::BufferPoolStats;
+use super::{PowerProfile, SchedulingPolicy, Session, SessionHandler};
 use async_std::prelude::*;
 use async_std::sync::{Arc as AsyncArc, Mutex as AsyncMutex};
 use async_std::task;
 use rand::{RngCore, SeedableRng};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 #[cfg(feature = "zero-copy")]
 use std::sync::RwLock;
 use std::sync::{Arc, Mutex};
@@ -40,9 +44,15 @@ use zenoh_util::core::{ZError, ZErrorKind, ZResult};
 use zenoh_util::crypto::{BlockCipher, PseudoRng};
 use zenoh_util::properties::config::ConfigProperties;
 use zenoh_util::properties::config::{
+    ZN_BATCH_SIZE_KEY, ZN_BATCH_SIZE_STR, ZN_BUFFER_POOL_SIZE_KEY, ZN_BUFFER_POOL_SIZE_STR,
     ZN_LINK_KEEP_ALIVE_KEY, ZN_LINK_KEEP_ALIVE_STR, ZN_LINK_LEASE_KEY, ZN_LINK_LEASE_STR,
-    ZN_OPEN_INCOMING_PENDING_KEY, ZN_OPEN_INCOMING_PENDING_STR, ZN_OPEN_TIMEOUT_KEY,
-    ZN_OPEN_TIMEOUT_STR, ZN_SEQ_NUM_RESOLUTION_KEY, ZN_SEQ_NUM_RESOLUTION_STR,
+    ZN_MAX_MESSAGE_SIZE_KEY, ZN_MAX_MESSAGE_SIZE_STR, ZN_OPEN_INCOMING_PENDING_KEY,
+    ZN_OPEN_INCOMING_PENDING_STR, ZN_OPEN_TIMEOUT_KEY, ZN_OPEN_TIMEOUT_STR,
+    ZN_POWER_PROFILE_DEFAULT, ZN_POWER_PROFILE_KEY, ZN_POWER_PROFILE_STR,
+    ZN_POWER_WAKE_WINDOW_DEFAULT, ZN_POWER_WAKE_WINDOW_KEY, ZN_POWER_WAKE_WINDOW_STR, ZN_PROXY_KEY,
+    ZN_PROXY_STR, ZN_SEQ_NUM_RESOLUTION_KEY, ZN_SEQ_NUM_RESOLUTION_STR, ZN_TX_SCHEDULING_DEFAULT,
+    ZN_TX_SCHEDULING_KEY, ZN_TX_SCHEDULING_STR, ZN_TX_WRR_WEIGHTS_DEFAULT, ZN_TX_WRR_WEIGHTS_KEY,
+    ZN_TX_WRR_WEIGHTS_STR,
 };
 use zenoh_util::{zasynclock, zerror, zlock};
 
@@ -95,11 +105,16 @@ use zenoh_util::{zasynclock, zerror, zlock};
 ///     open_timeout: None,             // Use the default open timeout
 ///     open_incoming_pending: None,    // Use the default amount of pending incoming sessions
 ///     batch_size: None,               // Use the default batch size
+///     max_message_size: None,         // Use the default maximum message size
 ///     max_sessions: Some(5),          // Accept any number of sessions
 ///     max_links: None,                // Allow any number of links in a single session
 ///     peer_authenticator: None,       // Accept any incoming session
 ///     link_authenticator: None,       // Accept any incoming link
 ///     locator_property: None,         // No specific link property
+///     proxy: None,                    // Open the TCP and TLS links directly
+///     power_profile: None,            // Send the messages as soon as possible
+///     buffer_pool_size: None,         // Use the default size of the pool of RX buffers
+///     tx_scheduling: None,            // Service the priority queues strictly
 /// };
 /// let manager_opt = SessionManager::new(config, Some(opt_config));
 /// ```
@@ -110,6 +125,7 @@ pub struct SessionManagerConfig {
     pub handler: Arc<dyn SessionHandler + Send + Sync>,
 }
 
+#[derive(Default)]
 pub struct SessionManagerOptionalConfig {
     pub lease: Option<ZInt>,
     pub keep_alive: Option<ZInt>,
@@ -117,11 +133,16 @@ pub struct SessionManagerOptionalConfig {
     pub open_timeout: Option<ZInt>,
     pub open_incoming_pending: Option<usize>,
     pub batch_size: Option<usize>,
+    pub max_message_size: Option<usize>,
     pub max_sessions: Option<usize>,
     pub max_links: Option<usize>,
     pub peer_authenticator: Option<Vec<PeerAuthenticator>>,
     pub link_authenticator: Option<Vec<LinkAuthenticator>>,
     pub locator_property: Option<Vec<LocatorProperty>>,
+    pub proxy: Option<Proxy>,
+    pub power_profile: Option<PowerProfile>,
+    pub buffer_pool_size: Option<usize>,
+    pub tx_scheduling: Option<SchedulingPolicy>,
 }
 
 impl SessionManagerOptionalConfig {
@@ -157,6 +178,59 @@ impl SessionManagerOptionalConfig {
         let open_timeout = zparse!(ZN_OPEN_TIMEOUT_KEY, ZN_OPEN_TIMEOUT_STR);
         let open_incoming_pending =
             zparse!(ZN_OPEN_INCOMING_PENDING_KEY, ZN_OPEN_INCOMING_PENDING_STR);
+        let batch_size = zparse!(ZN_BATCH_SIZE_KEY, ZN_BATCH_SIZE_STR);
+        let max_message_size = zparse!(ZN_MAX_MESSAGE_SIZE_KEY, ZN_MAX_MESSAGE_SIZE_STR);
+        let buffer_pool_size = zparse!(ZN_BUFFER_POOL_SIZE_KEY, ZN_BUFFER_POOL_SIZE_STR);
+        let proxy = zparse!(ZN_PROXY_KEY, ZN_PROXY_STR);
+        let power_profile = match config.get_or(&ZN_POWER_PROFILE_KEY, ZN_POWER_PROFILE_DEFAULT) {
+            "default" => None,
+            "low_power" => {
+                let wake_window = zparse!(ZN_POWER_WAKE_WINDOW_KEY, ZN_POWER_WAKE_WINDOW_STR)
+                    .unwrap_or_else(|| ZN_POWER_WAKE_WINDOW_DEFAULT.parse().unwrap());
+                Some(PowerProfile::LowPower { wake_window })
+            }
+            profile => {
+                let e = format!(
+                    "Failed to read configuration {}: {} is not a valid entry",
+                    ZN_POWER_PROFILE_STR, profile
+                );
+                log::warn!("{}", e);
+                return zerror!(ZErrorKind::ValueDecodingFailed { descr: e });
+            }
+        };
+        let tx_scheduling = match config.get_or(&ZN_TX_SCHEDULING_KEY, ZN_TX_SCHEDULING_DEFAULT) {
+            "strict" => None,
+            "wrr" => {
+                let weights = config.get_or(&ZN_TX_WRR_WEIGHTS_KEY, ZN_TX_WRR_WEIGHTS_DEFAULT);
+                let parsed: Option<Vec<usize>> = weights
+                    .split(',')
+                    .map(|w| w.trim().parse().ok().filter(|w| *w > 0))
+                    .collect();
+                match parsed {
+                    Some(parsed) if parsed.len() == ZN_QUEUE_NUM => {
+                        let mut wrr = [0; ZN_QUEUE_NUM];
+                        wrr.copy_from_slice(&parsed);
+                        Some(SchedulingPolicy::WeightedRoundRobin { weights: wrr })
+                    }
+                    _ => {
+                        let e = format!(
+                            "Failed to read configuration {}: {} is not a valid entry",
+                            ZN_TX_WRR_WEIGHTS_STR, weights
+                        );
+                        log::warn!("{}", e);
+                        return zerror!(ZErrorKind::ValueDecodingFailed { descr: e });
+                    }
+                }
+            }
+            policy => {
+                let e = format!(
+                    "Failed to read configuration {}: {} is not a valid entry",
+                    ZN_TX_SCHEDULING_STR, policy
+                );
+                log::warn!("{}", e);
+                return zerror!(ZErrorKind::ValueDecodingFailed { descr: e });
+            }
+        };
 
         let opt_config = SessionManagerOptionalConfig {
             lease,
@@ -164,7 +238,8 @@ impl SessionManagerOptionalConfig {
             sn_resolution,
             open_timeout,
             open_incoming_pending,
-            batch_size: None,
+            batch_size,
+            max_message_size,
             max_sessions: None,
             max_links: None,
             peer_authenticator: if peer_authenticator.is_empty() {
@@ -182,6 +257,10 @@ impl SessionManagerOptionalConfig {
             } else {
                 Some(locator_property)
             },
+            proxy,
+            power_profile,
+            buffer_pool_size,
+            tx_scheduling,
         };
         Ok(Some(opt_config))
     }
@@ -197,11 +276,14 @@ pub(super) struct SessionManagerConfigInner {
     pub(super) open_timeout: ZInt,
     pub(super) open_incoming_pending: usize,
     pub(super) batch_size: usize,
+    pub(super) max_message_size: usize,
     pub(super) max_sessions: Option<usize>,
     pub(super) max_links: Option<usize>,
     pub(super) peer_authenticator: Vec<PeerAuthenticator>,
     pub(super) link_authenticator: Vec<LinkAuthenticator>,
     pub(super) locator_property: HashMap<LocatorProtocol, LocatorProperty>,
+    pub(super) proxy: Option<Proxy>,
+    pub(super) tx_scheduling: SchedulingPolicy,
     pub(super) handler: Arc<dyn SessionHandler + Send + Sync>,
 }
 
@@ -226,6 +308,12 @@ pub struct SessionManager {
     protocols: Arc<Mutex<HashMap<LocatorProtocol, LinkManager>>>,
     // Established sessions
     sessions: Arc<Mutex<HashMap<PeerId, Arc<SessionTransport>>>>,
+    // Session parameters overridden per endpoint (peer or listener locator)
+    link_configs: Arc<Mutex<HashMap<Locator, LinkConfig>>>,
+    // The interval in milliseconds between two wake windows in low power (0 otherwise)
+    pub(super) wake_window: Arc<AtomicU64>,
+    // The pool of buffers the batches are received onto
+    pub(super) buffer_pool: Arc<BufferPool>,
     #[cfg(feature = "zero-copy")]
     pub(super) shmr: Arc<RwLock<SharedMemoryReader>>,
 }
@@ -242,11 +330,16 @@ impl SessionManager {
         let mut open_timeout = *ZN_OPEN_TIMEOUT;
         let mut open_incoming_pending = *ZN_OPEN_INCOMING_PENDING;
         let mut batch_size = ZN_DEFAULT_BATCH_SIZE;
+        let mut max_message_size = *ZN_MAX_MESSAGE_SIZE;
         let mut max_sessions = None;
         let mut max_links = None;
         let mut peer_authenticator = vec![DummyPeerAuthenticator::make()];
         let mut link_authenticator = vec![DummyLinkAuthenticator::make()];
         let mut locator_property = HashMap::new();
+        let mut proxy = None;
+        let mut power_profile = PowerProfile::Default;
+        let mut buffer_pool_size = *ZN_RX_BUFF_SIZE;
+        let mut tx_scheduling = SchedulingPolicy::Strict;
 
         // Override default values if provided
         if let Some(mut opt) = opt_config.take() {
@@ -268,6 +361,9 @@ impl SessionManager {
             if let Some(v) = opt.batch_size.take() {
                 batch_size = v;
             }
+            if let Some(v) = opt.max_message_size.take() {
+                max_message_size = v;
+            }
             max_sessions = opt.max_sessions;
             max_links = opt.max_links;
             if let Some(v) = opt.peer_authenticator.take() {
@@ -281,6 +377,16 @@ impl SessionManager {
                     locator_property.insert(p.get_proto(), p);
                 }
             }
+            proxy = opt.proxy.take();
+            if let Some(v) = opt.power_profile.take() {
+                power_profile = v;
+            }
+            if let Some(v) = opt.buffer_pool_size.take() {
+                buffer_pool_size = v;
+            }
+            if let Some(v) = opt.tx_scheduling.take() {
+                tx_scheduling = v;
+            }
         }
 
         let config_inner = SessionManagerConfigInner {
@@ -293,11 +399,14 @@ impl SessionManager {
             open_timeout,
             open_incoming_pending,
             batch_size,
+            max_message_size,
             max_sessions,
             max_links,
             peer_authenticator,
             link_authenticator,
             locator_property,
+            proxy,
+            tx_scheduling,
             handler: config.handler,
         };
 
@@ -311,6 +420,9 @@ impl SessionManager {
             config: Arc::new(config_inner),
             protocols: Arc::new(Mutex::new(HashMap::new())),
             sessions: Arc::new(Mutex::new(HashMap::new())),
+            link_configs: Arc::new(Mutex::new(HashMap::new())),
+            wake_window: Arc::new(AtomicU64::new(power_profile.wake_window().unwrap_or(0))),
+            buffer_pool: Arc::new(BufferPool::new(buffer_pool_size, batch_size)),
             opened: AsyncArc::new(AsyncMutex::new(HashMap::new())),
             incoming: AsyncArc::new(AsyncMutex::new(HashMap::new())),
             prng: AsyncArc::new(AsyncMutex::new(prng)),
@@ -324,6 +436,92 @@ impl SessionManager {
         self.config.pid.clone()
     }
 
+    pub fn max_message_size(&self) -> usize {
+        self.config.max_message_size
+    }
+
+    /*************************************/
+    /*            LINK CONFIG            */
+    /*************************************/
+    /// Overrides the session parameters for the links opened towards `locator`, or accepted
+    /// by a listener on `locator`. An empty `config` removes the override.
+    pub fn set_link_config(&self, locator: &Locator, config: LinkConfig) {
+        let mut guard = zlock!(self.link_configs);
+        if config.is_empty() {
+            guard.remove(locator);
+        } else {
+            guard.insert(locator.clone(), config);
+        }
+    }
+
+    pub fn get_link_config(&self, locator: &Locator) -> LinkConfig {
+        zlock!(self.link_configs)
+            .get(locator)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    // Returns the config of the listener that accepted a link from its source locator
+    fn get_accepted_link_config(&self, src: &Locator) -> LinkConfig {
+        let guard = zlock!(self.link_configs);
+        match guard.get(src) {
+            Some(config) => config.clone(),
+            None => guard
+                .iter()
+                .find(|(listener, _)| src.is_accepted_by(listener))
+                .map(|(_, config)| config.clone())
+                .unwrap_or_default(),
+        }
+    }
+
+    /*************************************/
+    /*           POWER PROFILE           */
+    /*************************************/
+    /// Switches the power profile. The coalescing of the messages applies to all the sessions
+    /// at once, while the widened lease and keep alive interval only apply to the sessions
+    /// opened afterwards.
+    pub fn set_power_profile(&self, profile: PowerProfile) {
+        log::debug!("Power profile set to {}", profile);
+        let wake_window = profile.wake_window().unwrap_or(0);
+        self.wake_window.store(wake_window, Ordering::Release);
+    }
+
+    pub fn get_power_profile(&self) -> PowerProfile {
+        match self.wake_window.load(Ordering::Acquire) {
+            0 => PowerProfile::Default,
+            wake_window => PowerProfile::LowPower { wake_window },
+        }
+    }
+
+    /*************************************/
+    /*            BUFFER POOL            */
+    /*************************************/
+    /// Returns the counters of the pool of buffers the batches are received onto.
+    #[cfg(feature = "stats")]
+    pub fn get_buffer_pool_stats(&self) -> BufferPoolStats {
+        self.buffer_pool.get_stats()
+    }
+
+    // The lease advertised on a link. In low power, the peer must tolerate the keep alives
+    // delayed until the next wake window.
+    pub(super) fn get_lease(&self, config: &LinkConfig) -> ZInt {
+        let lease = config.lease.unwrap_or(self.config.lease);
+        match self.get_power_profile().wake_window() {
+            Some(wake_window) => lease.max(4 * wake_window),
+            None => lease,
+        }
+    }
+
+    // The keep alive interval on a link, before being bounded by the lease of the peer.
+    // In low power, no keep alive is needed between two wake windows.
+    pub(super) fn get_keep_alive(&self, config: &LinkConfig) -> ZInt {
+        let keep_alive = config.keep_alive.unwrap_or(self.config.keep_alive);
+        match self.get_power_profile().wake_window() {
+            Some(wake_window) => keep_alive.max(wake_window),
+            None => keep_alive,
+        }
+    }
+
     /*************************************/
     /*              LISTENER             */
     /*************************************/
@@ -402,7 +600,8 @@ impl SessionManager {
     }
 
     async fn del_link_manager(&self, protocol: &LocatorProtocol) -> ZResult<()> {
-        match zlock!(self.protocols).remove(protocol) {
+        let lm = zlock!(self.protocols).remove(protocol);
+        match lm {
             Some(lm) => {
                 let mut listeners = lm.get_listeners();
                 for l in listeners.drain(..) {
@@ -432,15 +631,18 @@ impl SessionManager {
             .collect()
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub(super) fn init_session(
         &self,
         peer: &PeerId,
         whatami: WhatAmI,
+        version: u8,
         sn_resolution: ZInt,
         initial_sn_tx: ZInt,
         initial_sn_rx: ZInt,
-        is_shm: bool,
+        auth_session: AuthenticatedPeerSession,
     ) -> ZResult<Session> {
+        let is_shm = auth_session.is_local;
         let mut guard = zlock!(self.sessions);
 
         // First verify if the session already exists
@@ -494,10 +696,13 @@ impl SessionManager {
             self.clone(),
             peer.clone(),
             whatami,
+            version,
             sn_resolution,
             initial_sn_tx,
             initial_sn_rx,
             is_shm,
+            auth_session.methods,
+            auth_session.subject,
         ));
 
         // Create a weak reference to the session
@@ -535,10 +740,21 @@ impl SessionManager {
         // Automatically create a new link manager for the protocol if it does not exist
         let manager = self.get_or_new_link_manager(&locator.get_proto()).await;
         let ps = self.config.locator_property.get(&locator.get_proto());
+        let config = self.get_link_config(locator);
+        // The proxy of the endpoint, or else the default one if the protocol supports it
+        let proxy = config.proxy.as_ref().or_else(|| {
+            self.config
+                .proxy
+                .as_ref()
+                .filter(|_| Proxy::supports(&locator.get_proto()))
+        });
         // Create a new link associated by calling the Link Manager
-        let link = manager.new_link(&locator, ps).await?;
+        let link = match proxy {
+            Some(proxy) => manager.new_link_via_proxy(&locator, ps, proxy).await?,
+            None => manager.new_link(&locator, ps).await?,
+        };
         // Open the link
-        super::initial::open_link(self, &link).await
+        super::initial::open_link(self, &link, &config).await
     }
 
     pub(crate) async fn handle_new_link(&self, link: Link, properties: Option<LocatorProperty>) {
@@ -594,10 +810,12 @@ impl SessionManager {
                 dst: link.get_dst(),
                 peer_id,
                 properties,
+                peer_certificate: link.get_peer_certificate(),
             };
 
+            let config = c_manager.get_accepted_link_config(&link.get_src());
             let timeout = Duration::from_millis(c_manager.config.open_timeout);
-            let res = super::initial::accept_link(&c_manager, &link, &auth_link)
+            let res = super::initial::accept_link(&c_manager, &link, &auth_link, &config)
                 .timeout(timeout)
                 .await;
             match res {
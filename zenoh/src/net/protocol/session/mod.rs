@@ -22,7 +22,7 @@ use super::core;
 use super::core::{PeerId, WhatAmI, ZInt};
 use super::io;
 use super::link;
-use super::link::Link;
+use super::link::{Link, Locator};
 use super::proto;
 use super::proto::{smsg, ZenohMessage};
 use super::session;
@@ -31,6 +31,7 @@ pub use manager::*;
 pub use primitives::*;
 use std::any::Any;
 use std::fmt;
+use std::time::Duration;
 use transport::*;
 use zenoh_util::core::{ZError, ZErrorKind, ZResult};
 
@@ -42,10 +43,94 @@ pub trait SessionEventHandler {
     fn new_link(&self, link: Link);
     fn del_link(&self, link: Link);
     fn closing(&self);
-    fn closed(&self);
+    fn closed(&self, reason: CloseReason);
     fn as_any(&self) -> &dyn Any;
 }
 
+/// The reason why a [`Session`] with a peer was closed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CloseReason {
+    /// The session was closed locally.
+    Closed,
+    /// The session was closed by the peer, with the given close reason code.
+    ClosedByPeer(u8),
+    /// Nothing was received from the peer during the lease period.
+    LeaseExpired,
+    /// The last link with the peer failed.
+    LinkError,
+}
+
+impl fmt::Display for CloseReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CloseReason::Closed => write!(f, "closed"),
+            CloseReason::ClosedByPeer(reason) => write!(f, "closed by peer (reason: {})", reason),
+            CloseReason::LeaseExpired => write!(f, "lease expired"),
+            CloseReason::LinkError => write!(f, "link error"),
+        }
+    }
+}
+
+/// The power profile of a [`SessionManager`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PowerProfile {
+    /// The messages are sent as soon as possible.
+    #[default]
+    Default,
+    /// For the clients on battery powered devices: the messages are coalesced and only sent
+    /// every `wake_window` milliseconds, to reduce the radio wake-ups. The sessions opened
+    /// meanwhile advertise a lease and use a keep alive interval widened to the wake window,
+    /// and the multicast scouting is suspended.
+    LowPower { wake_window: ZInt },
+}
+
+impl PowerProfile {
+    /// Returns the interval in milliseconds between two wake windows, if any.
+    pub fn wake_window(&self) -> Option<ZInt> {
+        match self {
+            PowerProfile::Default => None,
+            PowerProfile::LowPower { wake_window } => Some(*wake_window),
+        }
+    }
+}
+
+impl fmt::Display for PowerProfile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PowerProfile::Default => write!(f, "default"),
+            PowerProfile::LowPower { wake_window } => {
+                write!(f, "low power (wake window: {} ms)", wake_window)
+            }
+        }
+    }
+}
+
+/// The policy scheduling the transmission of the priority queues of the links
+/// (control, retransmission and data, in decreasing priority).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SchedulingPolicy {
+    /// A queue is only serviced once the higher priority queues are empty:
+    /// the data can be starved by a sustained control traffic.
+    #[default]
+    Strict,
+    /// The queues are serviced in turn, each sending up to its weight in batches in a row
+    /// when the others are pending. The weights are indexed by the priority of the queues.
+    WeightedRoundRobin {
+        weights: [usize; defaults::ZN_QUEUE_NUM],
+    },
+}
+
+impl fmt::Display for SchedulingPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SchedulingPolicy::Strict => write!(f, "strict"),
+            SchedulingPolicy::WeightedRoundRobin { weights } => {
+                write!(f, "weighted round robin (weights: {:?})", weights)
+            }
+        }
+    }
+}
+
 pub trait SessionHandler {
     fn new_session(&self, session: Session) -> ZResult<Arc<dyn SessionEventHandler + Send + Sync>>;
 }
@@ -68,7 +153,7 @@ impl SessionEventHandler for DummySessionEventHandler {
     fn new_link(&self, _link: Link) {}
     fn del_link(&self, _link: Link) {}
     fn closing(&self) {}
-    fn closed(&self) {}
+    fn closed(&self, _reason: CloseReason) {}
 
     fn as_any(&self) -> &dyn Any {
         self
@@ -80,6 +165,61 @@ impl SessionEventHandler for DummySessionEventHandler {
 /*************************************/
 const STR_ERR: &str = "Session closed";
 
+/// The parameters negotiated with a peer when establishing a [`Session`],
+/// to check which optimizations are active with this peer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionParameters {
+    /// The zenoh protocol version.
+    pub version: u8,
+    /// The resolution of the sequence numbers.
+    pub sn_resolution: ZInt,
+    /// The maximum size of the batches sent on each link, by destination locator:
+    /// the configured batch size, capped by the MTU of the link.
+    pub batch_sizes: Vec<(Locator, usize)>,
+    /// Whether the data is exchanged via shared memory.
+    pub is_shm: bool,
+    /// The authentication methods used to establish the session (ex: `"usrpwd"`, `"shm"`).
+    pub auth_methods: Vec<&'static str>,
+    /// The subject the peer was authenticated as, if any (ex: the subject of its JWT).
+    pub auth_subject: Option<String>,
+    /// Whether the batches are compressed (not supported by this protocol version).
+    pub compression: bool,
+    /// Whether the QoS priorities are applied (not supported by this protocol version).
+    pub qos: bool,
+}
+
+/// The traffic counters of a [`Session`], summed over all its links.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SessionStats {
+    /// The number of batches sent.
+    pub tx_batches: ZInt,
+    /// The number of bytes sent.
+    pub tx_bytes: ZInt,
+    /// The number of batches received.
+    pub rx_batches: ZInt,
+    /// The number of bytes received.
+    pub rx_bytes: ZInt,
+    /// The number of received messages dropped because they exceeded the maximum message size
+    /// once defragmented.
+    pub defrag_aborts: ZInt,
+}
+
+/// The counters of the pool of buffers the batches are received onto,
+/// shared by all the sessions of a [`SessionManager`].
+#[cfg(feature = "stats")]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BufferPoolStats {
+    /// The number of buffers taken from the pool.
+    pub hits: ZInt,
+    /// The number of buffers allocated outside the pool, their size class being exhausted
+    /// or the batch exceeding the largest class.
+    pub misses: ZInt,
+    /// The number of buffers allocated into the pool.
+    pub allocated: ZInt,
+    /// The amount of memory in bytes allocated into the pool.
+    pub allocated_bytes: ZInt,
+}
+
 /// [`Session`] is the session handler returned when opening a new session
 #[derive(Clone)]
 pub struct Session(Weak<SessionTransport>);
@@ -125,6 +265,12 @@ impl Session {
         Ok(transport.is_shm())
     }
 
+    #[inline(always)]
+    pub fn get_parameters(&self) -> ZResult<SessionParameters> {
+        let transport = zweak!(self.0, STR_ERR);
+        Ok(transport.get_parameters())
+    }
+
     #[inline(always)]
     pub fn get_callback(&self) -> ZResult<Option<Arc<dyn SessionEventHandler + Send + Sync>>> {
         let transport = zweak!(self.0, STR_ERR);
@@ -137,6 +283,29 @@ impl Session {
         Ok(transport.get_links())
     }
 
+    /// Returns the links on which the keep alive tokens are no longer echoed by the peer,
+    /// hinting at a one-way connectivity. Only the links with the `keep_alive_echo`
+    /// endpoint metadata enabled are checked.
+    #[inline(always)]
+    pub fn get_degraded_links(&self) -> ZResult<Vec<Link>> {
+        let transport = zweak!(self.0, STR_ERR);
+        Ok(transport.get_degraded_links())
+    }
+
+    #[inline(always)]
+    pub fn get_stats(&self) -> ZResult<SessionStats> {
+        let transport = zweak!(self.0, STR_ERR);
+        Ok(transport.stats.get())
+    }
+
+    /// Requests the traffic counters of this session as seen by the remote peer.
+    /// The request is carried in-band, as an attachment of a `Ping` message:
+    /// a peer not supporting it ignores the request and a `Timeout` error is returned.
+    pub async fn get_remote_stats(&self, timeout: Duration) -> ZResult<SessionStats> {
+        let transport = zweak!(self.0, STR_ERR);
+        transport.get_remote_stats(timeout).await
+    }
+
     #[inline(always)]
     pub fn schedule(&self, message: ZenohMessage) -> ZResult<()> {
         let transport = zweak!(self.0, STR_ERR);
@@ -160,9 +329,14 @@ impl Session {
 
     #[inline(always)]
     pub async fn close(&self) -> ZResult<()> {
+        self.close_with_reason(smsg::close_reason::GENERIC).await
+    }
+
+    /// Closes the session, notifying the peer of the given close `reason`.
+    pub(crate) async fn close_with_reason(&self, reason: u8) -> ZResult<()> {
         // Return Ok if the session has already been closed
         match self.0.upgrade() {
-            Some(transport) => transport.close(smsg::close_reason::GENERIC).await,
+            Some(transport) => transport.close(reason).await,
             None => Ok(()),
         }
     }
@@ -11,7 +11,7 @@
 // Contributors:
 //   ADLINK zenoh team, <zenoh@adlink-labs.tech>
 //
-use super::super::SessionEventHandler;
+use super::super::{CloseReason, SessionEventHandler};
 use super::link::Link;
 use super::proto::{
     Data, Declaration, Declare, LinkStateList, Pull, Query, ZenohBody, ZenohMessage,
@@ -120,7 +120,7 @@ impl<P: 'static + Primitives + Send + Sync> SessionEventHandler for DeMux<P> {
                 qid,
                 target,
                 consolidation,
-                ..
+                page,
             }) => {
                 self.primitives.send_query(
                     &key,
@@ -128,6 +128,7 @@ impl<P: 'static + Primitives + Send + Sync> SessionEventHandler for DeMux<P> {
                     qid,
                     target.unwrap_or_default(),
                     consolidation,
+                    page,
                     msg.routing_context,
                 );
             }
@@ -156,7 +157,7 @@ impl<P: 'static + Primitives + Send + Sync> SessionEventHandler for DeMux<P> {
         self.primitives.send_close();
     }
 
-    fn closed(&self) {}
+    fn closed(&self, _reason: CloseReason) {}
 
     fn as_any(&self) -> &dyn Any {
         self
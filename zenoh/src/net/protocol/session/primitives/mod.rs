@@ -21,7 +21,8 @@ use super::proto;
 use super::session;
 
 use super::core::{
-    CongestionControl, PeerId, QueryConsolidation, QueryTarget, Reliability, ResKey, SubInfo, ZInt,
+    CongestionControl, PeerId, QueryConsolidation, QueryPage, QueryTarget, Reliability, ResKey,
+    SubInfo, ZInt,
 };
 use super::io::ZBuf;
 use super::proto::{DataInfo, RoutingContext};
@@ -56,6 +57,7 @@ pub trait Primitives {
         routing_context: Option<RoutingContext>,
     );
 
+    #[allow(clippy::too_many_arguments)]
     fn send_query(
         &self,
         reskey: &ResKey,
@@ -63,6 +65,7 @@ pub trait Primitives {
         qid: ZInt,
         target: QueryTarget,
         consolidation: QueryConsolidation,
+        page: Option<QueryPage>,
         routing_context: Option<RoutingContext>,
     );
 
@@ -134,6 +137,7 @@ impl Primitives for DummyPrimitives {
         _qid: ZInt,
         _target: QueryTarget,
         _consolidation: QueryConsolidation,
+        _page: Option<QueryPage>,
         _routing_context: Option<RoutingContext>,
     ) {
     }
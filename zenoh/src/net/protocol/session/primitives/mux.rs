@@ -12,7 +12,7 @@
 //   ADLINK zenoh team, <zenoh@adlink-labs.tech>
 //
 use super::core::{CongestionControl, PeerId, Reliability, ResKey, ZInt};
-use super::core::{QueryConsolidation, QueryTarget, SubInfo};
+use super::core::{QueryConsolidation, QueryPage, QueryTarget, SubInfo};
 use super::io::ZBuf;
 use super::proto::{
     zmsg, DataInfo, Declaration, ForgetPublisher, ForgetQueryable, ForgetResource,
@@ -146,6 +146,7 @@ impl Primitives for Mux {
         qid: ZInt,
         target: QueryTarget,
         consolidation: QueryConsolidation,
+        page: Option<QueryPage>,
         routing_context: Option<RoutingContext>,
     ) {
         let target_opt = if target == QueryTarget::default() {
@@ -159,6 +160,7 @@ impl Primitives for Mux {
             qid,
             target_opt,
             consolidation,
+            page,
             routing_context,
             None,
         ));
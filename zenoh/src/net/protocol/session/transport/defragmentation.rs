@@ -14,15 +14,20 @@
 use super::core::{Reliability, ZInt};
 use super::io::{ZBuf, ZSlice};
 use super::proto::ZenohMessage;
-use super::SeqNum;
+use super::{SeqNum, SessionTransportStats};
 
+use std::sync::Arc;
 use zenoh_util::core::{ZError, ZErrorKind, ZResult};
 
 pub(crate) struct DefragBuffer {
     // Keep track of the next expected fragment
     sn: SeqNum,
     buffer: ZBuf,
+    capacity: usize,
     reliability: Reliability,
+    // The current message exceeds the capacity: its fragments are dropped until the final one
+    aborted: bool,
+    stats: Arc<SessionTransportStats>,
 }
 
 impl DefragBuffer {
@@ -30,22 +35,33 @@ impl DefragBuffer {
         initial_sn: ZInt,
         sn_resolution: ZInt,
         reliability: Reliability,
+        capacity: usize,
+        stats: Arc<SessionTransportStats>,
     ) -> DefragBuffer {
         DefragBuffer {
             sn: SeqNum::new(initial_sn, sn_resolution),
             buffer: ZBuf::new(),
+            capacity,
             reliability,
+            aborted: false,
+            stats,
         }
     }
 
     #[inline(always)]
     pub(crate) fn is_empty(&self) -> bool {
-        self.buffer.is_empty()
+        self.buffer.is_empty() && !self.aborted
+    }
+
+    #[inline(always)]
+    pub(crate) fn is_aborted(&self) -> bool {
+        self.aborted
     }
 
     #[inline(always)]
     pub(crate) fn clear(&mut self) {
-        self.buffer.clear()
+        self.buffer.clear();
+        self.aborted = false;
     }
 
     #[inline(always)]
@@ -61,7 +77,21 @@ impl DefragBuffer {
             });
         }
 
-        self.buffer.add_zslice(zslice);
+        if !self.aborted {
+            let new_len = self.buffer.len() + zslice.len();
+            if new_len > self.capacity {
+                log::warn!(
+                    "Defragmented message of at least {} bytes exceeds the maximum message size of {} bytes: dropped",
+                    new_len,
+                    self.capacity
+                );
+                self.buffer.clear();
+                self.aborted = true;
+                self.stats.defrag_abort();
+            } else {
+                self.buffer.add_zslice(zslice);
+            }
+        }
         self.sn.increment();
 
         Ok(())
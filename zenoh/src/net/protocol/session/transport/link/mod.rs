@@ -13,6 +13,7 @@
 //
 mod batch;
 mod pipeline;
+mod scheduler;
 
 use super::super::super::link::Link;
 use super::core;
@@ -22,21 +23,67 @@ use super::io::{ZBuf, ZSlice};
 use super::proto;
 use super::proto::SessionMessage;
 use super::session;
-use super::session::defaults::{ZN_QUEUE_PRIO_CTRL, ZN_RX_BUFF_SIZE};
-use super::{SeqNumGenerator, SessionTransport};
+use super::session::defaults::{
+    ZN_LINK_KEEP_ALIVE_ECHO_MISSED, ZN_LINK_TX_DATAGRAMS, ZN_QUEUE_PRIO_CTRL,
+};
+use super::session::CloseReason;
+use super::{BufferPool, SeqNumGenerator, SessionTransport, SessionTransportStats};
 use async_std::prelude::*;
 use async_std::task;
 use async_std::task::JoinHandle;
 use batch::*;
 pub(crate) use pipeline::*;
-use std::sync::atomic::{AtomicBool, Ordering};
+use scheduler::*;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
-use zenoh_util::collections::RecyclingObjectPool;
+use std::time::{Duration, Instant};
+use zenoh_util::collections::RecyclingObject;
 use zenoh_util::core::{ZError, ZErrorKind, ZResult};
 use zenoh_util::sync::Signal;
 use zenoh_util::zerror;
 
+// The keep alive tokens sent on a link and echoed by the peer
+#[derive(Default)]
+pub(crate) struct KeepAliveEcho {
+    // The last token sent
+    sent: AtomicU64,
+    // The last token echoed by the peer
+    echoed: AtomicU64,
+    degraded: AtomicBool,
+}
+
+impl KeepAliveEcho {
+    fn next_token(&self, link: &Link) -> ZInt {
+        let token = self.sent.fetch_add(1, Ordering::AcqRel) + 1;
+        let missed = token - 1 - self.echoed.load(Ordering::Acquire);
+        if missed >= *ZN_LINK_KEEP_ALIVE_ECHO_MISSED && !self.degraded.swap(true, Ordering::AcqRel)
+        {
+            log::warn!(
+                "{}: {} keep alive tokens not echoed by the peer, flagging the link as degraded",
+                link,
+                missed
+            );
+        }
+        token
+    }
+
+    pub(crate) fn echo(&self, token: ZInt, link: &Link) {
+        if token == 0 || token > self.sent.load(Ordering::Acquire) {
+            log::debug!("{}: ignoring unexpected keep alive token {}", link, token);
+            return;
+        }
+        self.echoed.fetch_max(token, Ordering::AcqRel);
+        if self.degraded.swap(false, Ordering::AcqRel) {
+            log::info!("{}: keep alive tokens echoed again by the peer", link);
+        }
+    }
+
+    #[inline]
+    pub(crate) fn is_degraded(&self) -> bool {
+        self.degraded.load(Ordering::Acquire)
+    }
+}
+
 #[derive(Clone)]
 pub(crate) struct SessionTransportLink {
     // The underlying link
@@ -45,6 +92,10 @@ pub(crate) struct SessionTransportLink {
     transport: SessionTransport,
     // The transmission pipeline
     pipeline: Option<Arc<TransmissionPipeline>>,
+    // The batch size of the transmission pipeline
+    batch_size: usize,
+    // The keep alive tokens echoed by the peer, if enabled
+    keep_alive_echo: Option<Arc<KeepAliveEcho>>,
     // The signals to stop TX/RX tasks
     handle_tx: Option<Arc<JoinHandle<()>>>,
     active_rx: Arc<AtomicBool>,
@@ -56,8 +107,10 @@ impl SessionTransportLink {
     pub(crate) fn new(transport: SessionTransport, link: Link) -> SessionTransportLink {
         SessionTransportLink {
             transport,
+            batch_size: link.get_mtu(),
             inner: link,
             pipeline: None,
+            keep_alive_echo: None,
             handle_tx: None,
             active_rx: Arc::new(AtomicBool::new(false)),
             signal_rx: Signal::new(),
@@ -72,38 +125,69 @@ impl SessionTransportLink {
         &self.inner
     }
 
+    #[inline]
+    pub(crate) fn get_batch_size(&self) -> usize {
+        self.batch_size
+    }
+
     #[inline]
     pub(crate) fn get_pipeline(&self) -> Option<Arc<TransmissionPipeline>> {
         self.pipeline.clone()
     }
 
+    #[inline]
+    pub(crate) fn get_keep_alive_echo(&self) -> Option<&Arc<KeepAliveEcho>> {
+        self.keep_alive_echo.as_ref()
+    }
+
     pub(crate) fn start_tx(
         &mut self,
         keep_alive: ZInt,
+        keep_alive_echo: bool,
+        keep_alive_forced: bool,
         batch_size: usize,
         sn_reliable: Arc<Mutex<SeqNumGenerator>>,
         sn_best_effort: Arc<Mutex<SeqNumGenerator>>,
     ) {
         if self.handle_tx.is_none() {
             // The pipeline
+            self.batch_size = batch_size.min(self.inner.get_mtu());
             let pipeline = Arc::new(TransmissionPipeline::new(
-                batch_size.min(self.inner.get_mtu()),
+                self.batch_size,
                 self.inner.is_streamed(),
                 sn_reliable,
                 sn_best_effort,
+                self.transport.manager.config.tx_scheduling,
             ));
             self.pipeline = Some(pipeline.clone());
+            if keep_alive_echo {
+                self.keep_alive_echo = Some(Arc::new(KeepAliveEcho::default()));
+            }
 
             // Spawn the TX task
             let c_link = self.inner.clone();
             let c_transport = self.transport.clone();
+            let c_echo = self.keep_alive_echo.clone();
+            let c_stats = self.transport.stats.clone();
+            let c_wake_window = self.transport.manager.wake_window.clone();
             let handle = task::spawn(async move {
-                let res = tx_task(pipeline, c_link.clone(), keep_alive).await;
+                let res = tx_task(
+                    pipeline,
+                    c_link.clone(),
+                    keep_alive,
+                    keep_alive_forced,
+                    c_wake_window,
+                    c_echo,
+                    c_stats,
+                )
+                .await;
                 if let Err(e) = res {
                     log::debug!("{}", e);
                     // Spawn a task to avoid a deadlock waiting for this same task
                     // to finish in the close() joining its handle
-                    task::spawn(async move { c_transport.del_link(&c_link).await });
+                    task::spawn(async move {
+                        c_transport.del_link(&c_link, CloseReason::LinkError).await
+                    });
                 }
             });
             self.handle_tx = Some(Arc::new(handle));
@@ -138,9 +222,13 @@ impl SessionTransportLink {
                 c_active.store(false, Ordering::Release);
                 if let Err(e) = res {
                     log::debug!("{}", e);
+                    let reason = match e.get_kind() {
+                        ZErrorKind::Timeout {} => CloseReason::LeaseExpired,
+                        _ => CloseReason::LinkError,
+                    };
                     // Spawn a task to avoid a deadlock waiting for this same task
                     // to finish in the close() joining its handle
-                    task::spawn(async move { c_transport.del_link(&c_link).await });
+                    task::spawn(async move { c_transport.del_link(&c_link, reason).await });
                 }
             });
             self.handle_rx = Some(Arc::new(handle));
@@ -175,24 +263,108 @@ impl SessionTransportLink {
 /*************************************/
 /*              TASKS                */
 /*************************************/
-async fn tx_task(pipeline: Arc<TransmissionPipeline>, link: Link, keep_alive: ZInt) -> ZResult<()> {
+async fn tx_task(
+    pipeline: Arc<TransmissionPipeline>,
+    link: Link,
+    keep_alive: ZInt,
+    forced: bool,
+    wake_window: Arc<AtomicU64>,
+    echo: Option<Arc<KeepAliveEcho>>,
+    stats: Arc<SessionTransportStats>,
+) -> ZResult<()> {
     let keep_alive = Duration::from_millis(keep_alive);
+    // The last time a batch was written on the link and the last time a keep alive was sent
+    let mut last_tx = Instant::now();
+    let mut last_keep_alive = Instant::now();
+    let mut last_token = Instant::now();
+    // The wake windows are aligned on the start of the link
+    let start = Instant::now();
     loop {
-        match pipeline.pull().timeout(keep_alive).await {
+        // Any batch written on the link keeps the lease alive: wait for a keep alive interval
+        // since the last activity, unless the keep alives are forced to be sent periodically
+        // (e.g. to maintain the NAT bindings) regardless of the data traffic
+        let last = if forced { last_keep_alive } else { last_tx };
+        let wait = keep_alive
+            .checked_sub(last.elapsed())
+            .unwrap_or_else(|| Duration::from_millis(0));
+        match pipeline.pull().timeout(wait).await {
             Ok(res) => match res {
                 Some((batch, index)) => {
-                    // Send the buffer on the link
-                    let _ = link.write_all(batch.as_bytes()).await?;
-                    // Reinsert the batch into the queue
-                    pipeline.refill(batch, index);
+                    // In low power, coalesce the batches until the next wake window.
+                    // The wake windows are bounded by the keep alive interval not to let
+                    // the peer expire the lease.
+                    let window = wake_window
+                        .load(Ordering::Acquire)
+                        .min(keep_alive.as_millis() as u64);
+                    if window > 0 {
+                        let since = start.elapsed().as_millis() as u64 % window;
+                        task::sleep(Duration::from_millis(window - since)).await;
+                    }
+                    let mut next = Some((batch, index));
+                    while let Some(first) = next.take() {
+                        let mut batches = vec![first];
+                        // On the datagram links, write at once the batches already queued
+                        if !link.is_streamed() {
+                            while batches.len() < *ZN_LINK_TX_DATAGRAMS {
+                                match pipeline
+                                    .pull()
+                                    .timeout(Duration::from_millis(0))
+                                    .await
+                                    .ok()
+                                    .flatten()
+                                {
+                                    Some(batch) => batches.push(batch),
+                                    None => break,
+                                }
+                            }
+                        }
+                        // Send the buffers on the link
+                        let buffers = batches
+                            .iter()
+                            .map(|(batch, _)| batch.as_bytes())
+                            .collect::<Vec<&[u8]>>();
+                        link.write_all_batch(&buffers).await?;
+                        for (batch, index) in batches.drain(..) {
+                            stats.tx(batch.as_bytes().len());
+                            // Reinsert the batch into the queue
+                            pipeline.refill(batch, index);
+                        }
+                        // Send at once all the batches coalesced during the wake window
+                        if window > 0 {
+                            next = pipeline
+                                .pull()
+                                .timeout(Duration::from_millis(0))
+                                .await
+                                .ok()
+                                .flatten();
+                        }
+                    }
+                    last_tx = Instant::now();
                 }
                 None => break,
             },
             Err(_) => {
-                let pid = None;
+                // The tokens to be echoed also keep the lease alive
+                if echo.is_none() {
+                    let pid = None;
+                    let attachment = None;
+                    let message = SessionMessage::make_keep_alive(pid, attachment);
+                    pipeline.push_session_message(message, ZN_QUEUE_PRIO_CTRL);
+                }
+                // Reschedule from now, the keep alive or the token being about to be written
+                last_tx = Instant::now();
+                last_keep_alive = last_tx;
+            }
+        }
+
+        // Send a token to be echoed by the peer every keep alive interval,
+        // even when the link is busy, to check that the peer receives what we send
+        if let Some(echo) = echo.as_ref() {
+            if last_token.elapsed() >= keep_alive {
                 let attachment = None;
-                let message = SessionMessage::make_keep_alive(pid, attachment);
+                let message = SessionMessage::make_ping(echo.next_token(&link), attachment);
                 pipeline.push_session_message(message, ZN_QUEUE_PRIO_CTRL);
+                last_token = Instant::now();
             }
         }
     }
@@ -221,17 +393,19 @@ async fn rx_task_stream(
     active: Arc<AtomicBool>,
 ) -> ZResult<()> {
     enum Action {
-        Read(usize),
+        Read(RecyclingObject<Box<[u8]>>, usize),
         Stop,
     }
 
-    async fn read(link: &Link, buffer: &mut [u8]) -> ZResult<Action> {
+    async fn read(link: &Link, pool: &BufferPool) -> ZResult<Action> {
         // 16 bits for reading the batch length
         let mut length = [0u8, 0u8];
         link.read_exact(&mut length).await?;
         let n = u16::from_le_bytes(length) as usize;
+        // Retrieve one buffer fitting the batch
+        let mut buffer = pool.take(n);
         link.read_exact(&mut buffer[0..n]).await?;
-        Ok(Action::Read(n))
+        Ok(Action::Read(buffer, n))
     }
 
     async fn stop(signal: Signal) -> ZResult<Action> {
@@ -242,27 +416,25 @@ async fn rx_task_stream(
     let lease = Duration::from_millis(lease);
     // The ZBuf to read a message batch onto
     let mut zbuf = ZBuf::new();
-    // The pool of buffers
-    let n = 1 + (*ZN_RX_BUFF_SIZE / link.get_mtu());
-    let pool = RecyclingObjectPool::new(n, || vec![0u8; link.get_mtu()].into_boxed_slice());
+    // The pool of buffers shared by all the links
+    let pool = transport.manager.buffer_pool.clone();
     while active.load(Ordering::Acquire) {
         // Clear the ZBuf
         zbuf.clear();
 
-        // Retrieve one buffer
-        let mut buffer = pool.try_take().unwrap_or_else(|| pool.alloc());
-
         // Async read from the underlying link
-        let action = read(&link, &mut buffer)
+        let action = read(&link, &pool)
             .race(stop(signal.clone()))
             .timeout(lease)
             .await
             .map_err(|_| {
-                let e = format!("{}: expired after {} milliseconds", link, lease.as_millis());
-                zerror2!(ZErrorKind::IoError { descr: e })
+                log::debug!("{}: expired after {} milliseconds", link, lease.as_millis());
+                zerror2!(ZErrorKind::Timeout {})
             })??;
         match action {
-            Action::Read(n) => {
+            Action::Read(buffer, n) => {
+                // Count the 16 bits of the batch length as well, like on the TX side
+                transport.stats.rx(2 + n);
                 zbuf.add_zslice(ZSlice::new(buffer.into(), 0, n));
 
                 while zbuf.can_read() {
@@ -306,14 +478,13 @@ async fn rx_task_dgram(
     let lease = Duration::from_millis(lease);
     // The ZBuf to read a message batch onto
     let mut zbuf = ZBuf::new();
-    // The pool of buffers
-    let n = 1 + (*ZN_RX_BUFF_SIZE / link.get_mtu());
-    let pool = RecyclingObjectPool::new(n, || vec![0u8; link.get_mtu()].into_boxed_slice());
+    // The pool of buffers shared by all the links
+    let pool = transport.manager.buffer_pool.clone();
     while active.load(Ordering::Acquire) {
         // Clear the zbuf
         zbuf.clear();
-        // Retrieve one buffer
-        let mut buffer = pool.try_take().unwrap_or_else(|| pool.alloc());
+        // Retrieve one buffer fitting any datagram of the link
+        let mut buffer = pool.take(link.get_mtu());
 
         // Async read from the underlying link
         let action = read(&link, &mut buffer)
@@ -321,8 +492,8 @@ async fn rx_task_dgram(
             .timeout(lease)
             .await
             .map_err(|_| {
-                let e = format!("{}: expired after {} milliseconds", link, lease.as_millis());
-                zerror2!(ZErrorKind::IoError { descr: e })
+                log::debug!("{}: expired after {} milliseconds", link, lease.as_millis());
+                zerror2!(ZErrorKind::Timeout {})
             })??;
         match action {
             Action::Read(n) => {
@@ -332,6 +503,8 @@ async fn rx_task_dgram(
                     return zerror!(ZErrorKind::IoError { descr: e });
                 }
 
+                transport.stats.rx(n);
+
                 // Add the received bytes to the ZBuf for deserialization
                 zbuf.add_zslice(ZSlice::new(buffer.into(), 0, n));
 
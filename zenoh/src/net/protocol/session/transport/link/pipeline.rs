@@ -26,7 +26,8 @@ use super::session::defaults::{
     ZN_QUEUE_SIZE_DATA,
     ZN_QUEUE_SIZE_RETX,
 };
-use super::{SeqNumGenerator, SerializationBatch};
+use super::session::SchedulingPolicy;
+use super::{Scheduler, SeqNumGenerator, SerializationBatch};
 use async_std::task;
 use std::collections::VecDeque;
 use std::fmt;
@@ -212,6 +213,8 @@ pub(crate) struct TransmissionPipeline {
     // A single conditional variable for all the priority queues
     // The conditional variable requires a MutexGuard from stage_out
     cond_canpull: AsyncCondvar,
+    // The order in which the priority queues are pulled
+    scheduler: Scheduler,
 }
 
 impl TransmissionPipeline {
@@ -221,6 +224,7 @@ impl TransmissionPipeline {
         is_streamed: bool,
         sn_reliable: Arc<Mutex<SeqNumGenerator>>,
         sn_best_effort: Arc<Mutex<SeqNumGenerator>>,
+        scheduling: SchedulingPolicy,
     ) -> TransmissionPipeline {
         // Conditional variables
         let mut cond_canrefill = vec![];
@@ -293,6 +297,7 @@ impl TransmissionPipeline {
             stage_refill: stage_refill.into_boxed_slice(),
             cond_canrefill: cond_canrefill.into_boxed_slice(),
             cond_canpull,
+            scheduler: Scheduler::new(scheduling),
         }
     }
 
@@ -489,8 +494,10 @@ impl TransmissionPipeline {
 
         let mut backoff = Duration::from_nanos(*ZN_QUEUE_PULL_BACKOFF);
         loop {
-            for priority in 0..ZN_QUEUE_NUM {
+            let order = self.scheduler.order();
+            for priority in order.iter().copied() {
                 if let Some(batch) = self.try_pull_queue(priority).await {
+                    self.scheduler.pulled(priority);
                     return Some((batch, priority));
                 }
             }
@@ -498,14 +505,16 @@ impl TransmissionPipeline {
             let action = {
                 let mut out_guard = zlock!(self.stage_out);
                 let mut is_pipeline_really_empty = true;
-                for priority in 0..out_guard.len() {
+                for priority in order.iter().copied() {
                     if let Some(batch) = out_guard[priority].try_pull() {
+                        self.scheduler.pulled(priority);
                         return Some((batch, priority));
                     }
 
                     // Check if an incomplete (non-empty) batch is available in the state IN pipeline.
                     if let Ok(mut in_guard) = self.stage_in[priority].try_lock() {
                         if let Some(batch) = in_guard.try_pull() {
+                            self.scheduler.pulled(priority);
                             return Some((batch, priority));
                         }
                     } else {
@@ -717,6 +726,7 @@ mod tests {
             is_streamed,
             sn_reliable,
             sn_best_effort,
+            SchedulingPolicy::Strict,
         ));
 
         // Total amount of bytes to send in each test
@@ -814,6 +824,7 @@ mod tests {
             is_streamed,
             sn_reliable,
             sn_best_effort,
+            SchedulingPolicy::Strict,
         ));
 
         let counter = Arc::new(AtomicUsize::new(0));
@@ -923,6 +934,7 @@ mod tests {
             is_streamed,
             sn_reliable,
             sn_best_effort,
+            SchedulingPolicy::Strict,
         ));
 
         let counter = Arc::new(AtomicUsize::new(0));
@@ -990,6 +1002,7 @@ mod tests {
             is_streamed,
             sn_reliable,
             sn_best_effort,
+            SchedulingPolicy::Strict,
         ));
         let count = Arc::new(AtomicUsize::new(0));
         let size = Arc::new(AtomicUsize::new(0));
@@ -0,0 +1,110 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use super::session::defaults::ZN_QUEUE_NUM;
+use super::session::SchedulingPolicy;
+use std::sync::Mutex;
+use zenoh_util::zlock;
+
+/// The order in which the priority queues of a pipeline are pulled.
+pub(super) struct Scheduler {
+    // The weights of the queues in a weighted round robin, none if strict
+    weights: Option<[usize; ZN_QUEUE_NUM]>,
+    // The queue being serviced in turn, and its remaining number of batches
+    current: Mutex<(usize, usize)>,
+}
+
+impl Scheduler {
+    pub(super) fn new(policy: SchedulingPolicy) -> Scheduler {
+        match policy {
+            SchedulingPolicy::Strict => Scheduler {
+                weights: None,
+                current: Mutex::new((0, 0)),
+            },
+            SchedulingPolicy::WeightedRoundRobin { mut weights } => {
+                // A queue with no weight would lose its turn forever
+                for w in weights.iter_mut() {
+                    *w = (*w).max(1);
+                }
+                Scheduler {
+                    weights: Some(weights),
+                    current: Mutex::new((0, weights[0])),
+                }
+            }
+        }
+    }
+
+    /// Returns the priorities of the queues in the order to try to pull them:
+    /// from the highest priority if strict, from the queue in turn otherwise.
+    pub(super) fn order(&self) -> [usize; ZN_QUEUE_NUM] {
+        let first = match self.weights {
+            Some(_) => zlock!(self.current).0,
+            None => 0,
+        };
+        let mut order = [0; ZN_QUEUE_NUM];
+        for (i, priority) in order.iter_mut().enumerate() {
+            *priority = (first + i) % ZN_QUEUE_NUM;
+        }
+        order
+    }
+
+    /// Accounts a batch pulled from the queue `priority`. A queue serviced while it wasn't
+    /// its turn, the queues in turn being empty, takes the turn.
+    pub(super) fn pulled(&self, priority: usize) {
+        if let Some(weights) = self.weights.as_ref() {
+            let mut current = zlock!(self.current);
+            if current.0 != priority {
+                *current = (priority, weights[priority]);
+            }
+            current.1 -= 1;
+            if current.1 == 0 {
+                let next = (priority + 1) % ZN_QUEUE_NUM;
+                *current = (next, weights[next]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scheduler_strict() {
+        let scheduler = Scheduler::new(SchedulingPolicy::Strict);
+        for priority in [2, 1, 0, 2].iter() {
+            assert_eq!(scheduler.order(), [0, 1, 2]);
+            scheduler.pulled(*priority);
+        }
+    }
+
+    #[test]
+    fn scheduler_wrr() {
+        let scheduler = Scheduler::new(SchedulingPolicy::WeightedRoundRobin { weights: [2, 0, 1] });
+        // All the queues pending: each queue in turn for its weight
+        let mut pulled = vec![];
+        for _ in 0..8 {
+            let priority = scheduler.order()[0];
+            scheduler.pulled(priority);
+            pulled.push(priority);
+        }
+        assert_eq!(pulled, vec![0, 0, 1, 2, 0, 0, 1, 2]);
+
+        // The queue 0 in turn is empty: the next pending queue takes the turn
+        assert_eq!(scheduler.order(), [0, 1, 2]);
+        scheduler.pulled(2);
+        assert_eq!(scheduler.order(), [0, 1, 2]);
+        scheduler.pulled(1);
+        assert_eq!(scheduler.order(), [2, 0, 1]);
+    }
+}
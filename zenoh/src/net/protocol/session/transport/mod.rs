@@ -13,8 +13,10 @@
 //
 mod defragmentation;
 mod link;
+mod pool;
 mod rx;
 mod seq_num;
+mod stats;
 mod tx;
 
 use super::core;
@@ -24,13 +26,19 @@ use super::link::Link;
 use super::proto;
 use super::proto::{SessionMessage, ZenohMessage};
 use super::session;
-use super::session::defaults::ZN_QUEUE_PRIO_DATA;
-use super::session::{SessionEventHandler, SessionManager};
+use super::session::defaults::{ZN_QUEUE_PRIO_CTRL, ZN_QUEUE_PRIO_DATA};
+use super::session::{
+    CloseReason, SessionEventHandler, SessionManager, SessionParameters, SessionStats,
+};
+use async_std::prelude::*;
 use async_std::sync::{Arc as AsyncArc, Mutex as AsyncMutex, MutexGuard as AsyncMutexGuard};
 use defragmentation::*;
 use link::*;
+pub(super) use pool::*;
 pub(super) use seq_num::*;
+use stats::*;
 use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
 use zenoh_util::core::{ZError, ZErrorKind, ZResult};
 use zenoh_util::zerror;
 
@@ -62,6 +70,8 @@ impl SessionTransportChannel {
         reliability: Reliability,
         initial_sn: ZInt,
         sn_resolution: ZInt,
+        max_message_size: usize,
+        stats: Arc<SessionTransportStats>,
     ) -> SessionTransportChannel {
         // Set the sequence number in the state as it had
         // received a message with initial_sn - 1
@@ -73,7 +83,13 @@ impl SessionTransportChannel {
 
         SessionTransportChannel {
             sn: SeqNum::new(last_initial_sn, sn_resolution),
-            defrag: DefragBuffer::new(initial_sn, sn_resolution, reliability),
+            defrag: DefragBuffer::new(
+                initial_sn,
+                sn_resolution,
+                reliability,
+                max_message_size,
+                stats,
+            ),
         }
     }
 }
@@ -89,6 +105,8 @@ pub(crate) struct SessionTransport {
     pub(super) pid: PeerId,
     // The remote whatami
     pub(super) whatami: WhatAmI,
+    // The protocol version
+    pub(super) version: u8,
     // The SN resolution
     pub(super) sn_resolution: ZInt,
     // The sn generator for the TX reliable channel
@@ -105,24 +123,37 @@ pub(crate) struct SessionTransport {
     pub(super) callback: Arc<RwLock<Option<Arc<dyn SessionEventHandler + Send + Sync>>>>,
     // Mutex for notification
     pub(super) alive: AsyncArc<AsyncMutex<bool>>,
+    // The traffic counters
+    pub(super) stats: Arc<SessionTransportStats>,
     // The session transport can do shm
     is_shm: bool,
+    // The authentication methods used to establish the session
+    auth_methods: Vec<&'static str>,
+    // The subject the peer was authenticated as
+    auth_subject: Option<String>,
 }
 
 impl SessionTransport {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         manager: SessionManager,
         pid: PeerId,
         whatami: WhatAmI,
+        version: u8,
         sn_resolution: ZInt,
         initial_sn_tx: ZInt,
         initial_sn_rx: ZInt,
         is_shm: bool,
+        auth_methods: Vec<&'static str>,
+        auth_subject: Option<String>,
     ) -> SessionTransport {
+        let max_message_size = manager.config.max_message_size;
+        let stats = Arc::new(SessionTransportStats::default());
         SessionTransport {
             manager,
             pid,
             whatami,
+            version,
             sn_resolution,
             tx_sn_reliable: Arc::new(Mutex::new(SeqNumGenerator::new(
                 initial_sn_tx,
@@ -136,16 +167,23 @@ impl SessionTransport {
                 Reliability::Reliable,
                 initial_sn_rx,
                 sn_resolution,
+                max_message_size,
+                stats.clone(),
             ))),
             rx_best_effort: Arc::new(Mutex::new(SessionTransportChannel::new(
                 Reliability::BestEffort,
                 initial_sn_rx,
                 sn_resolution,
+                max_message_size,
+                stats.clone(),
             ))),
             links: Arc::new(RwLock::new(vec![].into_boxed_slice())),
             callback: Arc::new(RwLock::new(None)),
             alive: AsyncArc::new(AsyncMutex::new(true)),
+            stats,
             is_shm,
+            auth_methods,
+            auth_subject,
         }
     }
 
@@ -156,6 +194,53 @@ impl SessionTransport {
         self.is_shm
     }
 
+    pub(crate) fn get_parameters(&self) -> SessionParameters {
+        SessionParameters {
+            version: self.version,
+            sn_resolution: self.sn_resolution,
+            batch_sizes: zread!(self.links)
+                .iter()
+                .map(|l| (l.get_link().get_dst(), l.get_batch_size()))
+                .collect(),
+            is_shm: self.is_shm,
+            auth_methods: self.auth_methods.clone(),
+            auth_subject: self.auth_subject.clone(),
+            compression: false,
+            qos: false,
+        }
+    }
+
+    pub(crate) async fn get_remote_stats(&self, timeout: Duration) -> ZResult<SessionStats> {
+        let pipeline = zread!(self.links).iter().find_map(|l| l.get_pipeline());
+        let pipeline = match pipeline {
+            Some(pipeline) => pipeline,
+            None => {
+                return zerror!(ZErrorKind::InvalidSession {
+                    descr: format!("No link available with peer: {}", self.pid)
+                })
+            }
+        };
+
+        // The request id is carried as the hash of the Ping, and echoed in the Pong
+        let (id, attachment, receiver) = self.stats.new_request();
+        let msg = SessionMessage::make_ping(id, Some(attachment));
+        pipeline.push_session_message(msg, ZN_QUEUE_PRIO_CTRL);
+
+        let res = receiver.recv_async().timeout(timeout).await;
+        self.stats.end_request(id);
+        match res {
+            Ok(Ok(stats)) => Ok(stats),
+            _ => {
+                log::debug!(
+                    "No transport counters received from peer {} after {} ms",
+                    self.pid,
+                    timeout.as_millis()
+                );
+                zerror!(ZErrorKind::Timeout {})
+            }
+        }
+    }
+
     pub(crate) fn get_callback(&self) -> Option<Arc<dyn SessionEventHandler + Send + Sync>> {
         zread!(self.callback).clone()
     }
@@ -172,8 +257,8 @@ impl SessionTransport {
     /*************************************/
     /*           TERMINATION             */
     /*************************************/
-    pub(super) async fn delete(&self) -> ZResult<()> {
-        log::debug!("Closing session with peer: {}", self.pid);
+    pub(super) async fn delete(&self, reason: CloseReason) -> ZResult<()> {
+        log::debug!("Closing session with peer: {} ({})", self.pid, reason);
 
         // Mark the transport as no longer alive and keep the lock
         // to avoid concurrent new_session and closing/closed notifications
@@ -202,7 +287,7 @@ impl SessionTransport {
 
         // Notify the callback that we have closed the session
         if let Some(cb) = callback.as_ref() {
-            cb.closed();
+            cb.closed(reason);
         }
 
         Ok(())
@@ -230,7 +315,7 @@ impl SessionTransport {
             }
 
             // Remove the link from the channel
-            self.del_link(&link).await?;
+            self.del_link(&link, CloseReason::Closed).await?;
         }
 
         Ok(())
@@ -257,7 +342,7 @@ impl SessionTransport {
             p.push_session_message(msg, ZN_QUEUE_PRIO_DATA);
         }
         // Terminate and clean up the session
-        self.delete().await
+        self.delete(CloseReason::Closed).await
     }
 
     /*************************************/
@@ -302,6 +387,10 @@ impl SessionTransport {
             });
         }
 
+        if self.is_shm {
+            link.set_shm();
+        }
+
         // Create a channel link from a link
         let link = SessionTransportLink::new(self.clone(), link);
 
@@ -314,12 +403,21 @@ impl SessionTransport {
         Ok(())
     }
 
-    pub(crate) fn start_tx(&self, link: &Link, keep_alive: ZInt, batch_size: usize) -> ZResult<()> {
+    pub(crate) fn start_tx(
+        &self,
+        link: &Link,
+        keep_alive: ZInt,
+        keep_alive_echo: bool,
+        keep_alive_forced: bool,
+        batch_size: usize,
+    ) -> ZResult<()> {
         let mut guard = zwrite!(self.links);
         match zlinkgetmut!(guard, link) {
             Some(l) => {
                 l.start_tx(
                     keep_alive,
+                    keep_alive_echo,
+                    keep_alive_forced,
                     batch_size,
                     self.tx_sn_reliable.clone(),
                     self.tx_sn_best_effort.clone(),
@@ -379,7 +477,7 @@ impl SessionTransport {
         }
     }
 
-    pub(crate) async fn del_link(&self, link: &Link) -> ZResult<()> {
+    pub(crate) async fn del_link(&self, link: &Link, reason: CloseReason) -> ZResult<()> {
         enum Target {
             Session,
             Link(Box<SessionTransportLink>),
@@ -414,7 +512,7 @@ impl SessionTransport {
         };
 
         match target {
-            Target::Session => self.delete().await,
+            Target::Session => self.delete(reason).await,
             Target::Link(stl) => stl.close().await,
         }
     }
@@ -425,4 +523,12 @@ impl SessionTransport {
             .map(|l| l.get_link().clone())
             .collect()
     }
+
+    pub(crate) fn get_degraded_links(&self) -> Vec<Link> {
+        zread!(self.links)
+            .iter()
+            .filter(|l| matches!(l.get_keep_alive_echo(), Some(e) if e.is_degraded()))
+            .map(|l| l.get_link().clone())
+            .collect()
+    }
 }
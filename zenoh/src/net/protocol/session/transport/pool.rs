@@ -0,0 +1,133 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+#[cfg(feature = "stats")]
+use super::session::BufferPoolStats;
+use async_std::sync::Arc;
+#[cfg(feature = "stats")]
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use zenoh_util::collections::{LifoQueue, RecyclingObject};
+
+// The size of the buffers of the smallest class
+const MIN_CLASS_SIZE: usize = 512;
+// The batches are at most 65535 bytes, their length being encoded on 16 bits on streamed links
+const MAX_CLASS_SIZE: usize = 65_536;
+
+struct SizeClass {
+    size: usize,
+    // The maximum number of buffers of the class
+    capacity: usize,
+    // The number of buffers allocated so far, either idle in the queue or in use
+    allocated: AtomicUsize,
+    queue: Arc<LifoQueue<Box<[u8]>>>,
+}
+
+// The pool of buffers the batches are received onto, shared by all the links of a manager.
+// The buffers are sorted in classes of power of two sizes up to the maximum batch size, each
+// class keeping an equal share of the memory of the pool. A buffer is taken from the smallest
+// class fitting the batch and recycled into it once all the messages read from it are dropped.
+// The classes are filled lazily: once a class is exhausted, or if the batch exceeds the largest
+// class, a buffer is allocated outside the pool and released once dropped.
+pub(crate) struct BufferPool {
+    classes: Box<[SizeClass]>,
+    #[cfg(feature = "stats")]
+    hits: AtomicU64,
+    #[cfg(feature = "stats")]
+    misses: AtomicU64,
+}
+
+impl BufferPool {
+    pub(crate) fn new(size: usize, max_batch_size: usize) -> BufferPool {
+        let max_class_size = max_batch_size
+            .max(MIN_CLASS_SIZE)
+            .next_power_of_two()
+            .min(MAX_CLASS_SIZE);
+        let sizes: Vec<usize> = std::iter::successors(Some(MIN_CLASS_SIZE), |s| Some(2 * s))
+            .take_while(|s| *s <= max_class_size)
+            .collect();
+        let share = size / sizes.len();
+        let classes = sizes
+            .into_iter()
+            .map(|size| SizeClass {
+                size,
+                capacity: share / size,
+                allocated: AtomicUsize::new(0),
+                queue: Arc::new(LifoQueue::new(share / size)),
+            })
+            .collect();
+
+        BufferPool {
+            classes,
+            #[cfg(feature = "stats")]
+            hits: AtomicU64::new(0),
+            #[cfg(feature = "stats")]
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    // Returns a buffer of at least `len` bytes
+    pub(crate) fn take(&self, len: usize) -> RecyclingObject<Box<[u8]>> {
+        let class = match self.classes.iter().find(|c| c.size >= len) {
+            Some(class) => class,
+            None => return self.alloc(len),
+        };
+
+        if let Some(buffer) = class.queue.try_pull() {
+            #[cfg(feature = "stats")]
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return RecyclingObject::new(buffer, Arc::downgrade(&class.queue));
+        }
+
+        // The buffers of a class are never more than its queue can hold,
+        // so that recycling them never waits
+        let fill = class
+            .allocated
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| {
+                if n < class.capacity {
+                    Some(n + 1)
+                } else {
+                    None
+                }
+            });
+        match fill {
+            Ok(_) => RecyclingObject::new(
+                vec![0u8; class.size].into_boxed_slice(),
+                Arc::downgrade(&class.queue),
+            ),
+            Err(_) => self.alloc(class.size),
+        }
+    }
+
+    // Allocates a buffer outside the pool
+    fn alloc(&self, len: usize) -> RecyclingObject<Box<[u8]>> {
+        #[cfg(feature = "stats")]
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        vec![0u8; len].into_boxed_slice().into()
+    }
+
+    #[cfg(feature = "stats")]
+    pub(crate) fn get_stats(&self) -> BufferPoolStats {
+        let mut stats = BufferPoolStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            ..Default::default()
+        };
+        for class in self.classes.iter() {
+            let allocated = class.allocated.load(Ordering::Acquire) as u64;
+            stats.allocated += allocated;
+            stats.allocated_bytes += allocated * class.size as u64;
+        }
+        stats
+    }
+}
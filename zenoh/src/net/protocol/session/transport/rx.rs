@@ -12,7 +12,11 @@
 //   ADLINK zenoh team, <zenoh@adlink-labs.tech>
 //
 use super::core::{Channel, PeerId, ZInt};
-use super::proto::{Close, Frame, FramePayload, SessionBody, SessionMessage, ZenohMessage};
+use super::proto::{
+    Attachment, Close, Frame, FramePayload, Ping, Pong, SessionBody, SessionMessage, ZenohMessage,
+};
+use super::session::defaults::ZN_QUEUE_PRIO_CTRL;
+use super::session::CloseReason;
 use super::{Link, SessionTransport, SessionTransportChannel};
 use async_std::task;
 use std::sync::MutexGuard;
@@ -74,15 +78,55 @@ impl SessionTransport {
         // to finish in the link close() joining the rx handle
         task::spawn(async move {
             if link_only {
-                let _ = c_transport.del_link(&c_link).await;
+                let _ = c_transport
+                    .del_link(&c_link, CloseReason::ClosedByPeer(reason))
+                    .await;
             } else {
-                let _ = c_transport.delete().await;
+                let _ = c_transport.delete(CloseReason::ClosedByPeer(reason)).await;
             }
         });
 
         Ok(())
     }
 
+    fn handle_ping(&self, link: &Link, hash: ZInt, attachment: Option<Attachment>) -> ZResult<()> {
+        // Echo the keep alive token on the same link, or reply to the
+        // request of the traffic counters
+        let pipeline = zread!(self.links)
+            .iter()
+            .find(|l| l.get_link() == link)
+            .and_then(|l| l.get_pipeline());
+        if let Some(pipeline) = pipeline {
+            let attachment = attachment.and_then(|a| self.stats.handle_request(a));
+            let msg = SessionMessage::make_pong(hash, attachment);
+            pipeline.push_session_message(msg, ZN_QUEUE_PRIO_CTRL);
+        }
+        Ok(())
+    }
+
+    fn handle_pong(&self, link: &Link, hash: ZInt, attachment: Option<Attachment>) -> ZResult<()> {
+        // The reply to a request of the traffic counters
+        if let Some(attachment) = attachment {
+            self.stats.handle_reply(hash, attachment);
+            return Ok(());
+        }
+
+        let echo = zread!(self.links)
+            .iter()
+            .find(|l| l.get_link() == link)
+            .and_then(|l| l.get_keep_alive_echo().cloned());
+        match echo {
+            Some(echo) => echo.echo(hash, link),
+            None => log::debug!(
+                "Session: {}. Unexpected keep alive token on link {}: {}",
+                self.pid,
+                link,
+                hash
+            ),
+        }
+        Ok(())
+    }
+
     fn handle_frame(
         &self,
         sn: ZInt,
@@ -114,7 +158,11 @@ impl SessionTransport {
                     let _ = guard.defrag.sync(sn);
                 }
                 guard.defrag.push(sn, buffer)?;
-                if is_final {
+                if is_final && guard.defrag.is_aborted() {
+                    // The message exceeded the maximum message size: its fragments were dropped
+                    guard.defrag.clear();
+                    Ok(())
+                } else if is_final {
                     // When zero-copy feature is disabled, msg does not need to be mutable
                     let msg = guard.defrag.defragment().ok_or_else(|| {
                         let e = format!("Session: {}. Defragmentation error.", self.pid);
@@ -146,6 +194,8 @@ impl SessionTransport {
                 reason,
                 link_only,
             }) => self.handle_close(link, pid, reason, link_only),
+            SessionBody::Ping(Ping { hash }) => self.handle_ping(link, hash, msg.attachment),
+            SessionBody::Pong(Pong { hash }) => self.handle_pong(link, hash, msg.attachment),
             _ => {
                 log::debug!(
                     "Session: {}. Message handling not implemented: {:?}",
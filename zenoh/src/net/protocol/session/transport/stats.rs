@@ -0,0 +1,143 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use super::core::{Property, ZInt};
+use super::io::{WBuf, ZBuf};
+use super::proto::Attachment;
+use super::session::SessionStats;
+use flume::{Receiver, Sender};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use zenoh_util::zlock;
+
+// The attachment property of a Ping requesting the transport counters of the peer,
+// and of the Pong replying to it with the counters.
+const STATS_PROPERTY: ZInt = 0x53;
+
+const WBUF_SIZE: usize = 64;
+
+// The traffic counters of a transport, and its pending requests of the counters of the peer
+#[derive(Default)]
+pub(crate) struct SessionTransportStats {
+    tx_batches: AtomicU64,
+    tx_bytes: AtomicU64,
+    rx_batches: AtomicU64,
+    rx_bytes: AtomicU64,
+    defrag_aborts: AtomicU64,
+    request_id: AtomicU64,
+    requests: Mutex<HashMap<ZInt, Sender<SessionStats>>>,
+}
+
+impl SessionTransportStats {
+    #[inline]
+    pub(crate) fn tx(&self, bytes: usize) {
+        self.tx_batches.fetch_add(1, Ordering::Relaxed);
+        self.tx_bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub(crate) fn rx(&self, bytes: usize) {
+        self.rx_batches.fetch_add(1, Ordering::Relaxed);
+        self.rx_bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub(crate) fn defrag_abort(&self) {
+        self.defrag_aborts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn get(&self) -> SessionStats {
+        SessionStats {
+            tx_batches: self.tx_batches.load(Ordering::Relaxed),
+            tx_bytes: self.tx_bytes.load(Ordering::Relaxed),
+            rx_batches: self.rx_batches.load(Ordering::Relaxed),
+            rx_bytes: self.rx_bytes.load(Ordering::Relaxed),
+            defrag_aborts: self.defrag_aborts.load(Ordering::Relaxed),
+        }
+    }
+
+    /*************************************/
+    /*         IN-BAND REQUESTS          */
+    /*************************************/
+    // Registers a new request of the counters of the peer, returning its id and the
+    // attachment of the Ping to send
+    pub(super) fn new_request(&self) -> (ZInt, Attachment, Receiver<SessionStats>) {
+        let id = self.request_id.fetch_add(1, Ordering::AcqRel);
+        let (sender, receiver) = flume::bounded(1);
+        zlock!(self.requests).insert(id, sender);
+        let attachment = make_attachment(vec![]);
+        (id, attachment, receiver)
+    }
+
+    pub(super) fn end_request(&self, id: ZInt) {
+        zlock!(self.requests).remove(&id);
+    }
+
+    // Returns the attachment of the Pong replying to a Ping with `attachment`, if it's a request
+    pub(super) fn handle_request(&self, attachment: Attachment) -> Option<Attachment> {
+        read_attachment(attachment)?;
+        let stats = self.get();
+        let mut wbuf = WBuf::new(WBUF_SIZE, false);
+        wbuf.write_zint(stats.tx_batches);
+        wbuf.write_zint(stats.tx_bytes);
+        wbuf.write_zint(stats.rx_batches);
+        wbuf.write_zint(stats.rx_bytes);
+        wbuf.write_zint(stats.defrag_aborts);
+        Some(make_attachment(ZBuf::from(wbuf).to_vec()))
+    }
+
+    // Handles the attachment of a Pong replying to the request `id`
+    pub(super) fn handle_reply(&self, id: ZInt, attachment: Attachment) {
+        let mut zbuf: ZBuf = match read_attachment(attachment) {
+            Some(value) => value.into(),
+            None => return,
+        };
+        let stats = (|| {
+            Some(SessionStats {
+                tx_batches: zbuf.read_zint()?,
+                tx_bytes: zbuf.read_zint()?,
+                rx_batches: zbuf.read_zint()?,
+                rx_bytes: zbuf.read_zint()?,
+                // Not sent by the peers predating this counter
+                defrag_aborts: zbuf.read_zint().unwrap_or(0),
+            })
+        })();
+        match stats {
+            Some(stats) => {
+                if let Some(sender) = zlock!(self.requests).remove(&id) {
+                    let _ = sender.send(stats);
+                }
+            }
+            None => log::debug!("Invalid transport counters received for request {}", id),
+        }
+    }
+}
+
+fn make_attachment(value: Vec<u8>) -> Attachment {
+    let mut wbuf = WBuf::new(WBUF_SIZE, false);
+    wbuf.write_properties(&[Property {
+        key: STATS_PROPERTY,
+        value,
+    }]);
+    Attachment::make(wbuf.into())
+}
+
+fn read_attachment(mut attachment: Attachment) -> Option<Vec<u8>> {
+    attachment
+        .buffer
+        .read_properties()?
+        .into_iter()
+        .find(|p| p.key == STATS_PROPERTY)
+        .map(|p| p.value)
+}
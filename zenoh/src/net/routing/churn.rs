@@ -0,0 +1,160 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use serde_json::json;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use zenoh_util::zconfigurable;
+
+zconfigurable! {
+    // Duration in seconds of the sliding window over which the churn rate of a face is computed.
+    static ref CHURN_WINDOW: u64 = 10;
+    // Number of undeclared entities of a face whose timestamps are kept.
+    static ref CHURN_HISTORY: usize = 16;
+}
+
+fn secs(time: SystemTime) -> f64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+/// The declarations and undeclarations of the entities of a face, to spot the applications
+/// declaring and undeclaring in tight loops and destabilizing the routing.
+///
+/// The entities are identified as in the quotas, by their kind and resource name (ex: `sub:/a/b`).
+#[derive(Default)]
+pub(crate) struct Churn {
+    // the declaration time of the entities currently declared
+    declared: HashMap<String, SystemTime>,
+    // the last undeclared entities, with their declaration and undeclaration times
+    undeclared: VecDeque<(String, Option<SystemTime>, SystemTime)>,
+    // the number of declarations and undeclarations within the window, per second
+    // since `start`, in at most CHURN_WINDOW buckets
+    buckets: VecDeque<(u64, u64)>,
+    start: Option<Instant>,
+    declarations: u64,
+    undeclarations: u64,
+}
+
+impl Churn {
+    // Returns the current second since the first event.
+    fn second(&mut self) -> u64 {
+        self.start
+            .get_or_insert_with(Instant::now)
+            .elapsed()
+            .as_secs()
+    }
+
+    fn record(&mut self) {
+        let second = self.second();
+        match self.buckets.back_mut() {
+            Some((s, count)) if *s == second => *count += 1,
+            _ => self.buckets.push_back((second, 1)),
+        }
+        let window = *CHURN_WINDOW;
+        while matches!(self.buckets.front(), Some((s, _)) if s + window <= second) {
+            self.buckets.pop_front();
+        }
+    }
+
+    pub(crate) fn declare(&mut self, entity: String) {
+        // a redeclaration keeps the time of the first declaration
+        self.declared.entry(entity).or_insert_with(SystemTime::now);
+        self.declarations += 1;
+        self.record();
+    }
+
+    pub(crate) fn undeclare(&mut self, entity: &str) {
+        let declared = self.declared.remove(entity);
+        if self.undeclared.len() >= *CHURN_HISTORY {
+            self.undeclared.pop_front();
+        }
+        self.undeclared
+            .push_back((entity.to_string(), declared, SystemTime::now()));
+        self.undeclarations += 1;
+        self.record();
+    }
+
+    /// Returns the declaration time of `entity`, in seconds since the epoch.
+    pub(crate) fn declared(&self, entity: &str) -> Option<f64> {
+        self.declared.get(entity).map(|time| secs(*time))
+    }
+
+    /// Returns the number of declarations and undeclarations per second over the last window.
+    pub(crate) fn rate(&self) -> f64 {
+        let window = *CHURN_WINDOW;
+        let second = match self.start {
+            Some(start) => start.elapsed().as_secs(),
+            None => return 0.0,
+        };
+        let count: u64 = self
+            .buckets
+            .iter()
+            .filter(|(s, _)| s + window > second)
+            .map(|(_, count)| count)
+            .sum();
+        count as f64 / Duration::from_secs(window).as_secs_f64()
+    }
+
+    pub(crate) fn to_json(&self) -> serde_json::Value {
+        let undeclared = self
+            .undeclared
+            .iter()
+            .map(|(entity, declared, undeclared)| {
+                json!({
+                    "entity": entity,
+                    "declared": declared.map(secs),
+                    "undeclared": secs(*undeclared),
+                })
+            })
+            .collect::<Vec<_>>();
+        json!({
+            "declarations": self.declarations,
+            "undeclarations": self.undeclarations,
+            "rate": self.rate(),
+            "undeclared": undeclared,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_churn() {
+        let mut churn = Churn::default();
+        assert_eq!(churn.rate(), 0.0);
+
+        for i in 0..1000 {
+            let entity = format!("sub:/a/{}", i);
+            churn.declare(entity.clone());
+            assert!(churn.declared(&entity).is_some());
+            churn.undeclare(&entity);
+            assert!(churn.declared(&entity).is_none());
+        }
+        // the memory used by a tight loop is bounded
+        assert!(churn.buckets.len() <= *CHURN_WINDOW as usize);
+        assert_eq!(churn.undeclared.len(), *CHURN_HISTORY);
+        assert_eq!(churn.rate(), 2000.0 / *CHURN_WINDOW as f64);
+
+        let json = churn.to_json();
+        assert_eq!(json["declarations"], 1000);
+        assert_eq!(json["undeclarations"], 1000);
+        assert_eq!(
+            json["undeclared"][*CHURN_HISTORY - 1]["entity"],
+            "sub:/a/999"
+        );
+    }
+}
@@ -11,15 +11,18 @@
 // Contributors:
 //   ADLINK zenoh team, <zenoh@adlink-labs.tech>
 //
+use super::churn::Churn;
 use super::protocol::core::{
-    whatami, CongestionControl, PeerId, QueryConsolidation, QueryTarget, Reliability, ResKey,
-    SubInfo, WhatAmI, ZInt,
+    queryable, whatami, CongestionControl, PeerId, QueryConsolidation, QueryPage, QueryTarget,
+    Reliability, ResKey, SubInfo, SubMode, WhatAmI, ZInt,
 };
 use super::protocol::io::ZBuf;
 use super::protocol::proto::{DataInfo, RoutingContext};
 use super::protocol::session::Primitives;
+use super::quotas::SubjectQuotas;
 use super::router::*;
 use async_std::sync::Arc;
+use serde_json::json;
 use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::sync::RwLock;
@@ -34,10 +37,13 @@ pub struct FaceState {
     pub(super) remote_mappings: HashMap<ZInt, Arc<Resource>>,
     pub(super) local_subs: HashSet<Arc<Resource>>,
     pub(super) remote_subs: HashSet<Arc<Resource>>,
+    pub(super) remote_pubs: HashSet<Arc<Resource>>,
     pub(super) local_qabls: HashMap<Arc<Resource>, ZInt>,
     pub(super) remote_qabls: HashSet<Arc<Resource>>,
     pub(super) next_qid: ZInt,
     pub(super) pending_queries: HashMap<ZInt, Arc<Query>>,
+    pub(super) quotas: Option<SubjectQuotas>,
+    pub(super) churn: Churn,
 }
 
 impl FaceState {
@@ -47,6 +53,7 @@ impl FaceState {
         whatami: WhatAmI,
         primitives: Arc<dyn Primitives + Send + Sync>,
         link_id: usize,
+        quotas: Option<SubjectQuotas>,
     ) -> Arc<FaceState> {
         Arc::new(FaceState {
             id,
@@ -58,10 +65,13 @@ impl FaceState {
             remote_mappings: HashMap::new(),
             local_subs: HashSet::new(),
             remote_subs: HashSet::new(),
+            remote_pubs: HashSet::new(),
             local_qabls: HashMap::new(),
             remote_qabls: HashSet::new(),
             next_qid: 0,
             pending_queries: HashMap::new(),
+            quotas,
+            churn: Churn::default(),
         })
     }
 
@@ -81,6 +91,74 @@ impl FaceState {
         }
         id
     }
+
+    /// Returns the publishers, subscribers and queryables declared on this face,
+    /// with their key expressions, QoS and declaration times, and the churn of
+    /// its declarations as a JSON object.
+    pub(crate) fn entities(&self) -> serde_json::Value {
+        let mut publishers = self
+            .remote_pubs
+            .iter()
+            .map(|res| {
+                json!({
+                    "key": res.name(),
+                    "declared": self.churn.declared(&format!("pub:{}", res.name())),
+                })
+            })
+            .collect::<Vec<_>>();
+        publishers.sort_by(|p1, p2| p1["key"].as_str().cmp(&p2["key"].as_str()));
+        let mut subscribers = self
+            .remote_subs
+            .iter()
+            .filter_map(|res| {
+                let sub_info = res.session_ctxs.get(&self.id)?.subs.as_ref()?;
+                Some(json!({
+                    "key": res.name(),
+                    "reliability": match sub_info.reliability {
+                        Reliability::Reliable => "reliable",
+                        Reliability::BestEffort => "best_effort",
+                    },
+                    "mode": match sub_info.mode {
+                        SubMode::Push => "push",
+                        SubMode::Pull => "pull",
+                    },
+                    "period": sub_info.period.as_ref().map(|p| json!({
+                        "origin": p.origin,
+                        "period": p.period,
+                        "duration": p.duration,
+                    })),
+                    "declared": self.churn.declared(&format!("sub:{}", res.name())),
+                }))
+            })
+            .collect::<Vec<_>>();
+        subscribers.sort_by(|s1, s2| s1["key"].as_str().cmp(&s2["key"].as_str()));
+        let mut queryables = self
+            .remote_qabls
+            .iter()
+            .filter_map(|res| {
+                let kind = res.session_ctxs.get(&self.id)?.qabl?;
+                Some(json!({
+                    "key": res.name(),
+                    "kind": match kind {
+                        queryable::STORAGE => "storage",
+                        queryable::EVAL => "eval",
+                        _ => "all_kinds",
+                    },
+                    "declared": self.churn.declared(&format!("qabl:{}", res.name())),
+                }))
+            })
+            .collect::<Vec<_>>();
+        queryables.sort_by(|q1, q2| q1["key"].as_str().cmp(&q2["key"].as_str()));
+        json!({
+            "face": self.id,
+            "pid": self.pid.to_string(),
+            "whatami": whatami::to_string(self.whatami).to_lowercase(),
+            "publishers": publishers,
+            "subscribers": subscribers,
+            "queryables": queryables,
+            "churn": self.churn.to_json(),
+        })
+    }
 }
 
 impl fmt::Display for FaceState {
@@ -89,21 +167,82 @@ impl fmt::Display for FaceState {
     }
 }
 
+// Records the primitive called on a face in the routing trace, if any.
+macro_rules! trace_primitive {
+    ($tables:expr, $face:expr, $primitive:ident($($arg:expr),*)) => {
+        #[cfg(feature = "routing_replay")]
+        if let Some(trace) = &$tables.trace {
+            trace.face($face.id).$primitive($($arg),*);
+        }
+    };
+}
+
 #[derive(Clone)]
 pub struct Face {
     pub(crate) tables: Arc<RwLock<Tables>>,
     pub(crate) state: Arc<FaceState>,
 }
 
+impl Face {
+    // Records the declaration of the `kind` entity on `reskey` in the quotas of the face, keyed
+    // on its resource name so that an entity declared both by rid and by name is counted once.
+    // Returns false if it exceeds the declarations quota, for the declaration to be ignored.
+    fn declare_quota(&self, tables: &Tables, kind: &str, reskey: &ResKey) -> bool {
+        let quotas = match &self.state.quotas {
+            Some(quotas) => quotas,
+            None => return true,
+        };
+        let (prefixid, suffix) = reskey.into();
+        let entity = match tables.get_mapping(&self.state, &prefixid) {
+            Some(prefix) => format!("{}:{}{}", kind, prefix.name(), suffix),
+            // the declaration of an unknown rid is rejected by the routing
+            None => return true,
+        };
+        if quotas.declare(self.state.id, entity.clone()) {
+            return true;
+        }
+        if quotas.reject() {
+            log::warn!(
+                "Declaration {} from {} exceeds the declarations quota of {}: too many rejected declarations, session closed",
+                entity,
+                self.state.pid,
+                quotas.subject()
+            );
+        } else {
+            log::warn!(
+                "Declaration {} from {} exceeds the declarations quota of {}: ignored",
+                entity,
+                self.state.pid,
+                quotas.subject()
+            );
+        }
+        false
+    }
+
+    fn undeclare_quota(&self, tables: &Tables, kind: &str, reskey: &ResKey) {
+        if let Some(quotas) = &self.state.quotas {
+            let (prefixid, suffix) = reskey.into();
+            if let Some(prefix) = tables.get_mapping(&self.state, &prefixid) {
+                quotas.undeclare(
+                    self.state.id,
+                    &format!("{}:{}{}", kind, prefix.name(), suffix),
+                );
+            }
+        }
+    }
+}
+
 impl Primitives for Face {
     fn decl_resource(&self, rid: ZInt, reskey: &ResKey) {
         let (prefixid, suffix) = reskey.into();
         let mut tables = zwrite!(self.tables);
+        trace_primitive!(tables, self.state, decl_resource(rid, reskey));
         declare_resource(&mut tables, &mut self.state.clone(), rid, prefixid, suffix);
     }
 
     fn forget_resource(&self, rid: ZInt) {
         let mut tables = zwrite!(self.tables);
+        trace_primitive!(tables, self.state, forget_resource(rid));
         undeclare_resource(&mut tables, &mut self.state.clone(), rid);
     }
 
@@ -115,6 +254,14 @@ impl Primitives for Face {
     ) {
         let (prefixid, suffix) = reskey.into();
         let mut tables = zwrite!(self.tables);
+        trace_primitive!(
+            tables,
+            self.state,
+            decl_subscriber(reskey, sub_info, routing_context)
+        );
+        if !self.declare_quota(&tables, "sub", reskey) {
+            return;
+        }
         match (tables.whatami, self.state.whatami) {
             (whatami::ROUTER, whatami::ROUTER) => match routing_context {
                 Some(routing_context) => {
@@ -197,6 +344,12 @@ impl Primitives for Face {
     fn forget_subscriber(&self, reskey: &ResKey, routing_context: Option<RoutingContext>) {
         let (prefixid, suffix) = reskey.into();
         let mut tables = zwrite!(self.tables);
+        trace_primitive!(
+            tables,
+            self.state,
+            forget_subscriber(reskey, routing_context)
+        );
+        self.undeclare_quota(&tables, "sub", reskey);
         match (tables.whatami, self.state.whatami) {
             (whatami::ROUTER, whatami::ROUTER) => match routing_context {
                 Some(routing_context) => {
@@ -268,13 +421,29 @@ impl Primitives for Face {
         }
     }
 
-    fn decl_publisher(&self, _reskey: &ResKey, _routing_context: Option<RoutingContext>) {}
+    fn decl_publisher(&self, reskey: &ResKey, _routing_context: Option<RoutingContext>) {
+        let (prefixid, suffix) = reskey.into();
+        let mut tables = zwrite!(self.tables);
+        declare_client_publication(&mut tables, &mut self.state.clone(), prefixid, suffix);
+    }
 
-    fn forget_publisher(&self, _reskey: &ResKey, _routing_context: Option<RoutingContext>) {}
+    fn forget_publisher(&self, reskey: &ResKey, _routing_context: Option<RoutingContext>) {
+        let (prefixid, suffix) = reskey.into();
+        let mut tables = zwrite!(self.tables);
+        forget_client_publication(&mut tables, &mut self.state.clone(), prefixid, suffix);
+    }
 
     fn decl_queryable(&self, reskey: &ResKey, kind: ZInt, routing_context: Option<RoutingContext>) {
         let (prefixid, suffix) = reskey.into();
         let mut tables = zwrite!(self.tables);
+        trace_primitive!(
+            tables,
+            self.state,
+            decl_queryable(reskey, kind, routing_context)
+        );
+        if !self.declare_quota(&tables, "qabl", reskey) {
+            return;
+        }
         match (tables.whatami, self.state.whatami) {
             (whatami::ROUTER, whatami::ROUTER) => match routing_context {
                 Some(routing_context) => {
@@ -357,6 +526,12 @@ impl Primitives for Face {
     fn forget_queryable(&self, reskey: &ResKey, routing_context: Option<RoutingContext>) {
         let (prefixid, suffix) = reskey.into();
         let mut tables = zwrite!(self.tables);
+        trace_primitive!(
+            tables,
+            self.state,
+            forget_queryable(reskey, routing_context)
+        );
+        self.undeclare_quota(&tables, "qabl", reskey);
         match (tables.whatami, self.state.whatami) {
             (whatami::ROUTER, whatami::ROUTER) => match routing_context {
                 Some(routing_context) => {
@@ -437,6 +612,18 @@ impl Primitives for Face {
         data_info: Option<DataInfo>,
         routing_context: Option<RoutingContext>,
     ) {
+        trace_primitive!(
+            zread!(self.tables),
+            self.state,
+            send_data(
+                reskey,
+                payload.clone(),
+                _reliability,
+                congestion_control,
+                data_info.clone(),
+                routing_context
+            )
+        );
         let (prefixid, suffix) = reskey.into();
         full_reentrant_route_data(
             &self.tables,
@@ -457,10 +644,37 @@ impl Primitives for Face {
         qid: ZInt,
         target: QueryTarget,
         consolidation: QueryConsolidation,
+        page: Option<QueryPage>,
         routing_context: Option<RoutingContext>,
     ) {
+        if let Some(quotas) = &self.state.quotas {
+            if !quotas.allow_query() {
+                log::debug!(
+                    "Query on {}{} from {} exceeds the queries quota of {}: replied with no reply",
+                    reskey,
+                    predicate,
+                    self.state.pid,
+                    quotas.subject()
+                );
+                self.state.primitives.send_reply_final(qid);
+                return;
+            }
+        }
         let (prefixid, suffix) = reskey.into();
         let mut tables = zwrite!(self.tables);
+        trace_primitive!(
+            tables,
+            self.state,
+            send_query(
+                reskey,
+                predicate,
+                qid,
+                target.clone(),
+                consolidation.clone(),
+                page.clone(),
+                routing_context
+            )
+        );
         route_query(
             &mut tables,
             &self.state,
@@ -470,6 +684,7 @@ impl Primitives for Face {
             qid,
             target,
             consolidation,
+            page,
             routing_context,
         );
     }
@@ -484,6 +699,18 @@ impl Primitives for Face {
         payload: ZBuf,
     ) {
         let mut tables = zwrite!(self.tables);
+        trace_primitive!(
+            tables,
+            self.state,
+            send_reply_data(
+                qid,
+                replier_kind,
+                replier_id.clone(),
+                reskey.clone(),
+                info.clone(),
+                payload.clone()
+            )
+        );
         route_send_reply_data(
             &mut tables,
             &mut self.state.clone(),
@@ -498,6 +725,7 @@ impl Primitives for Face {
 
     fn send_reply_final(&self, qid: ZInt) {
         let mut tables = zwrite!(self.tables);
+        trace_primitive!(tables, self.state, send_reply_final(qid));
         route_send_reply_final(&mut tables, &mut self.state.clone(), qid);
     }
 
@@ -510,6 +738,11 @@ impl Primitives for Face {
     ) {
         let (prefixid, suffix) = reskey.into();
         let mut tables = zwrite!(self.tables);
+        trace_primitive!(
+            tables,
+            self.state,
+            send_pull(is_final, reskey, pull_id, max_samples)
+        );
         pull_data(
             &mut tables,
             &self.state.clone(),
@@ -0,0 +1,320 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use super::face::{Face, FaceState};
+use super::protocol::core::{
+    queryable::STORAGE, rname, CongestionControl, History, PeerId, QueryConsolidation, QueryPage,
+    QueryTarget, Reliability, ResKey, SubInfo, SubMode, ZInt,
+};
+use super::protocol::io::ZBuf;
+use super::protocol::proto::{data_kind, DataInfo, RoutingContext};
+use super::protocol::session::Primitives;
+use super::resource::Resource;
+use super::router::{Router, Tables};
+use async_std::sync::Arc;
+use async_std::task;
+use log::{error, trace};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use zenoh_util::properties::config::*;
+
+#[derive(Default)]
+struct Entries {
+    // for each path: its latest payload and data info, and its update sequence number
+    values: HashMap<String, (ZBuf, Option<DataInfo>, u64)>,
+    // the cached paths in update order, for the eviction
+    order: BTreeMap<u64, String>,
+    seq: u64,
+}
+
+/// A cache of the latest values published on some path expressions, resident in a router.
+///
+/// It answers the queries on these path expressions as a storage would, and sends the cached
+/// values matching a new subscription of a client asking for them (with [`History::Latest`](super::protocol::core::History))
+/// to this client, for cheap retained values without a storage plugin. It's bounded by a number of paths: when it's full, the least
+/// recently updated path is evicted.
+pub struct LatestValueCache {
+    pid: PeerId,
+    path_exprs: Vec<String>,
+    capacity: usize,
+    face: Mutex<Option<Arc<Face>>>,
+    mappings: Mutex<HashMap<ZInt, String>>,
+    entries: Mutex<Entries>,
+}
+
+impl LatestValueCache {
+    /// Starts the latest value cache configured in `config`, if any.
+    pub fn start(router: &Router, config: &ConfigProperties) -> Option<Arc<LatestValueCache>> {
+        let path_exprs = config
+            .get(&ZN_LATEST_VALUE_CACHE_KEY)?
+            .split(',')
+            .map(|expr| expr.trim().to_string())
+            .filter(|expr| !expr.is_empty())
+            .collect::<Vec<String>>();
+        let capacity = match config
+            .get_or(
+                &ZN_LATEST_VALUE_CACHE_SIZE_KEY,
+                ZN_LATEST_VALUE_CACHE_SIZE_DEFAULT,
+            )
+            .parse::<usize>()
+        {
+            Ok(capacity) if capacity > 0 => capacity,
+            _ => {
+                log::warn!(
+                    "Invalid {}, use default value {}",
+                    ZN_LATEST_VALUE_CACHE_SIZE_STR,
+                    ZN_LATEST_VALUE_CACHE_SIZE_DEFAULT
+                );
+                ZN_LATEST_VALUE_CACHE_SIZE_DEFAULT.parse().unwrap()
+            }
+        };
+        log::info!(
+            "Cache the latest values of {:?} (up to {} paths)",
+            path_exprs,
+            capacity
+        );
+        let cache = Arc::new(LatestValueCache {
+            pid: zread!(router.tables).pid.clone(),
+            path_exprs,
+            capacity,
+            face: Mutex::new(None),
+            mappings: Mutex::new(HashMap::new()),
+            entries: Mutex::new(Entries::default()),
+        });
+
+        let face = router.new_primitives(cache.clone());
+        zlock!(cache.face).replace(face.clone());
+        let sub_info = SubInfo {
+            reliability: Reliability::Reliable,
+            mode: SubMode::Push,
+            period: None,
+            history: History::None,
+            filter: None,
+        };
+        for path_expr in &cache.path_exprs {
+            face.decl_subscriber(&path_expr.clone().into(), &sub_info, None);
+            face.decl_queryable(&path_expr.clone().into(), STORAGE, None);
+        }
+        zwrite!(router.tables).latest_value_cache = Some(cache.clone());
+        Some(cache)
+    }
+
+    /// Returns the number of paths in the cache.
+    pub fn len(&self) -> usize {
+        zlock!(self.entries).values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn reskey_to_string(&self, key: &ResKey) -> Option<String> {
+        match key {
+            ResKey::RId(id) => zlock!(self.mappings).get(id).cloned(),
+            ResKey::RIdWithSuffix(id, suffix) => zlock!(self.mappings)
+                .get(id)
+                .map(|prefix| format!("{}{}", prefix, suffix)),
+            ResKey::RName(name) => Some(name.clone()),
+        }
+    }
+
+    fn is_own_face(&self, face: &Arc<FaceState>) -> bool {
+        zlock!(self.face)
+            .as_ref()
+            .map_or(false, |own| own.state.id == face.id)
+    }
+
+    // Returns the cached values whose path intersects `res_name`.
+    fn matching_values(&self, res_name: &str) -> Vec<(String, ZBuf, Option<DataInfo>)> {
+        zlock!(self.entries)
+            .values
+            .iter()
+            .filter(|(path, _)| rname::intersect(res_name, path))
+            .map(|(path, (payload, info, _))| (path.clone(), payload.clone(), info.clone()))
+            .collect()
+    }
+
+    /// Sends the cached values matching the new subscription of `face` on `res_name`.
+    pub(crate) fn send_initial_values(
+        &self,
+        tables: &Tables,
+        face: &Arc<FaceState>,
+        res_name: &str,
+        sub_info: &SubInfo,
+    ) {
+        if self.is_own_face(face) {
+            return;
+        }
+        for (path, payload, info) in self.matching_values(res_name) {
+            trace!("Send cached value of {} to {}", path, face);
+            let reskey = Resource::get_best_key(&tables.root_res, &path, face.id);
+            face.primitives.send_data(
+                &reskey,
+                payload,
+                sub_info.reliability,
+                CongestionControl::Drop,
+                info,
+                None,
+            );
+        }
+    }
+}
+
+impl Primitives for LatestValueCache {
+    fn decl_resource(&self, rid: ZInt, reskey: &ResKey) {
+        trace!("recv Resource {} {:?}", rid, reskey);
+        match self.reskey_to_string(reskey) {
+            Some(s) => {
+                zlock!(self.mappings).insert(rid, s);
+            }
+            None => error!("Unknown rid {}!", rid),
+        }
+    }
+
+    fn forget_resource(&self, _rid: ZInt) {
+        trace!("recv Forget Resource {}", _rid);
+    }
+
+    fn decl_publisher(&self, _reskey: &ResKey, _routing_context: Option<RoutingContext>) {}
+
+    fn forget_publisher(&self, _reskey: &ResKey, _routing_context: Option<RoutingContext>) {}
+
+    fn decl_subscriber(
+        &self,
+        _reskey: &ResKey,
+        _sub_info: &SubInfo,
+        _routing_context: Option<RoutingContext>,
+    ) {
+    }
+
+    fn forget_subscriber(&self, _reskey: &ResKey, _routing_context: Option<RoutingContext>) {}
+
+    fn decl_queryable(
+        &self,
+        _reskey: &ResKey,
+        _kind: ZInt,
+        _routing_context: Option<RoutingContext>,
+    ) {
+    }
+
+    fn forget_queryable(&self, _reskey: &ResKey, _routing_context: Option<RoutingContext>) {}
+
+    fn send_data(
+        &self,
+        reskey: &ResKey,
+        payload: ZBuf,
+        _reliability: Reliability,
+        _congestion_control: CongestionControl,
+        data_info: Option<DataInfo>,
+        _routing_context: Option<RoutingContext>,
+    ) {
+        let path = match self.reskey_to_string(reskey) {
+            Some(path) => path,
+            None => {
+                error!("Received data for unknown ResKey {:?}", reskey);
+                return;
+            }
+        };
+        let kind = data_info
+            .as_ref()
+            .and_then(|info| info.kind)
+            .unwrap_or(data_kind::DEFAULT);
+        let mut entries = zlock!(self.entries);
+        if let Some((_, _, seq)) = entries.values.remove(&path) {
+            entries.order.remove(&seq);
+        }
+        match kind {
+            data_kind::DELETE => trace!("Remove {} from the latest value cache", path),
+            data_kind::PUT => {
+                if entries.values.len() >= self.capacity {
+                    let oldest = entries.order.keys().next().cloned();
+                    if let Some(path) = oldest.and_then(|seq| entries.order.remove(&seq)) {
+                        trace!("Evict {} from the latest value cache", path);
+                        entries.values.remove(&path);
+                    }
+                }
+                entries.seq += 1;
+                let seq = entries.seq;
+                entries.order.insert(seq, path.clone());
+                entries.values.insert(path, (payload, data_info, seq));
+            }
+            _ => trace!("Ignore PATCH on {} in the latest value cache", path),
+        }
+    }
+
+    fn send_query(
+        &self,
+        reskey: &ResKey,
+        _predicate: &str,
+        qid: ZInt,
+        _target: QueryTarget,
+        _consolidation: QueryConsolidation,
+        page: Option<QueryPage>,
+        _routing_context: Option<RoutingContext>,
+    ) {
+        let mut values = match self.reskey_to_string(reskey) {
+            Some(name) => self.matching_values(&name),
+            None => {
+                error!("Received query for unknown ResKey {:?}", reskey);
+                vec![]
+            }
+        };
+        if let Some(page) = page {
+            values.retain(|(path, _, _)| page.follows(path));
+            values.sort_by(|(path1, _, _), (path2, _, _)| path1.cmp(path2));
+            values.truncate(page.limit as usize);
+        }
+        let pid = self.pid.clone();
+        let face = zlock!(self.face).as_ref().unwrap().clone();
+        // router is not re-entrant
+        task::spawn(async move {
+            for (path, payload, info) in values {
+                face.send_reply_data(
+                    qid,
+                    STORAGE,
+                    pid.clone(),
+                    ResKey::RName(path),
+                    info,
+                    payload,
+                );
+            }
+            face.send_reply_final(qid);
+        });
+    }
+
+    fn send_reply_data(
+        &self,
+        _qid: ZInt,
+        _replier_kind: ZInt,
+        _replier_id: PeerId,
+        _reskey: ResKey,
+        _info: Option<DataInfo>,
+        _payload: ZBuf,
+    ) {
+    }
+
+    fn send_reply_final(&self, _qid: ZInt) {}
+
+    fn send_pull(
+        &self,
+        _is_final: bool,
+        _reskey: &ResKey,
+        _pull_id: ZInt,
+        _max_samples: &Option<ZInt>,
+    ) {
+    }
+
+    fn send_close(&self) {
+        trace!("recv Close");
+    }
+}
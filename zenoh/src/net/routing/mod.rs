@@ -11,10 +11,16 @@
 // Contributors:
 //   ADLINK zenoh team, <zenoh@adlink-labs.tech>
 //
+pub mod churn;
 pub mod face;
+pub mod latest_value_cache;
 pub mod network;
+pub mod publication_buffer;
 pub mod pubsub;
 pub mod queries;
+pub mod quotas;
+#[cfg(feature = "routing_replay")]
+pub mod replay;
 pub mod resource;
 pub mod router;
 
@@ -11,6 +11,7 @@
 // Contributors:
 //   ADLINK zenoh team, <zenoh@adlink-labs.tech>
 //
+use async_std::sync::Arc;
 use petgraph::graph::NodeIndex;
 use petgraph::visit::{IntoNodeReferences, VisitMap, Visitable};
 use std::convert::TryInto;
@@ -21,7 +22,22 @@ use super::protocol::link::Locator;
 use super::protocol::proto::{LinkState, ZenohMessage};
 use super::protocol::session::Session;
 
-use super::runtime::Runtime;
+use super::runtime::{parse_roles, parse_subnets, Runtime};
+use zenoh_util::core::ZResult;
+use zenoh_util::net::IpSubnet;
+use zenoh_util::properties::config::*;
+
+/// Where the link states are sent on a link: the session with the linked node,
+/// or the recorder of the outputs of a replayed routing trace.
+pub(crate) trait LinkStateSender: Send + Sync {
+    fn send_link_states(&self, msg: ZenohMessage) -> ZResult<()>;
+}
+
+impl LinkStateSender for Session {
+    fn send_link_states(&self, msg: ZenohMessage) -> ZResult<()> {
+        self.handle_message(msg)
+    }
+}
 
 pub(crate) struct Node {
     pub(crate) pid: PeerId,
@@ -38,17 +54,16 @@ impl std::fmt::Debug for Node {
 }
 
 pub(crate) struct Link {
-    pub(crate) session: Session,
+    sender: Arc<dyn LinkStateSender>,
     pid: PeerId,
     mappings: VecMap<PeerId>,
     local_mappings: VecMap<ZInt>,
 }
 
 impl Link {
-    fn new(session: Session) -> Self {
-        let pid = session.get_pid().unwrap();
+    fn new(pid: PeerId, sender: Arc<dyn LinkStateSender>) -> Self {
         Link {
-            session,
+            sender,
             pid,
             mappings: VecMap::new(),
             local_mappings: VecMap::new(),
@@ -84,6 +99,79 @@ pub(crate) struct Tree {
     pub(crate) directions: Vec<Option<NodeIndex>>,
 }
 
+// The restrictions on the routers and peers discovered by gossip to automatically connect.
+pub(crate) struct GossipFilter {
+    radius: Option<usize>,
+    what: whatami::Type,
+    subnets: Vec<IpSubnet>,
+}
+
+impl GossipFilter {
+    fn from_config(config: &ConfigProperties) -> GossipFilter {
+        let radius = config
+            .get(&ZN_GOSSIP_RADIUS_KEY)
+            .and_then(|radius| match radius.parse() {
+                Ok(radius) => Some(radius),
+                Err(_) => {
+                    log::warn!("Ignore invalid {}: {}", ZN_GOSSIP_RADIUS_STR, radius);
+                    None
+                }
+            });
+        let what = match config.get(&ZN_GOSSIP_WHAT_KEY) {
+            Some(roles) => parse_roles(roles, ZN_GOSSIP_WHAT_STR),
+            None => whatami::ROUTER | whatami::PEER,
+        };
+        let subnets = parse_subnets(
+            config.get_or(&ZN_GOSSIP_SUBNETS_KEY, ""),
+            ZN_GOSSIP_SUBNETS_STR,
+        );
+        GossipFilter {
+            radius,
+            what,
+            subnets,
+        }
+    }
+
+    // Returns the locators of a discovered node to be connected, if any.
+    fn filter(&self, node: &Node, hops: Option<usize>) -> Option<Vec<Locator>> {
+        if node.whatami & self.what == 0 {
+            return None;
+        }
+        if let Some(radius) = self.radius {
+            if hops.map_or(true, |hops| hops > radius) {
+                return None;
+            }
+        }
+        let locators = node.locators.as_ref()?;
+        if self.subnets.is_empty() {
+            return Some(locators.clone());
+        }
+        let locators = locators
+            .iter()
+            .filter(|l| {
+                l.get_ip()
+                    .map_or(false, |ip| self.subnets.iter().any(|s| s.contains(&ip)))
+            })
+            .cloned()
+            .collect::<Vec<Locator>>();
+        if locators.is_empty() {
+            None
+        } else {
+            Some(locators)
+        }
+    }
+}
+
+impl Default for GossipFilter {
+    fn default() -> GossipFilter {
+        GossipFilter {
+            radius: None,
+            what: whatami::ROUTER | whatami::PEER,
+            subnets: vec![],
+        }
+    }
+}
+
 pub(crate) struct Network {
     pub(crate) name: String,
     pub(crate) peers_autoconnect: bool,
@@ -91,15 +179,21 @@ pub(crate) struct Network {
     pub(crate) idx: NodeIndex,
     pub(crate) links: VecMap<Link>,
     pub(crate) trees: Vec<Tree>,
+    // Distance from this node to each node of the graph (each hop weighs about 100).
+    pub(crate) distances: Vec<f64>,
     pub(crate) graph: petgraph::stable_graph::StableUnGraph<Node, f64>,
-    pub(crate) runtime: Runtime,
+    pub(crate) whatami: whatami::Type,
+    // None for the networks of a replayed routing trace, that never send messages.
+    pub(crate) runtime: Option<Runtime>,
+    pub(crate) gossip_filter: GossipFilter,
 }
 
 impl Network {
     pub(crate) fn new(
         name: String,
         pid: PeerId,
-        runtime: Runtime,
+        whatami: whatami::Type,
+        runtime: Option<Runtime>,
         peers_autoconnect: bool,
         routers_autoconnect_gossip: bool,
     ) -> Self {
@@ -107,7 +201,7 @@ impl Network {
         log::debug!("{} Add node (self) {}", name, pid);
         let idx = graph.add_node(Node {
             pid,
-            whatami: runtime.whatami,
+            whatami,
             locators: None,
             sn: 1,
             links: vec![],
@@ -123,7 +217,13 @@ impl Network {
                 childs: vec![],
                 directions: vec![None],
             }],
+            distances: vec![0.0],
             graph,
+            whatami,
+            gossip_filter: runtime
+                .as_ref()
+                .map(|runtime| GossipFilter::from_config(&runtime.config()))
+                .unwrap_or_default(),
             runtime,
         }
     }
@@ -162,7 +262,10 @@ impl Network {
 
     #[inline]
     fn get_locators(&self) -> Vec<Locator> {
-        self.runtime.manager().get_locators()
+        match &self.runtime {
+            Some(runtime) => runtime.manager().get_locators(),
+            None => vec![],
+        }
     }
 
     fn add_node(&mut self, node: Node) -> NodeIndex {
@@ -219,10 +322,10 @@ impl Network {
         ZenohMessage::make_link_state_list(list, None)
     }
 
-    fn send_on_link(&self, idxs: Vec<(NodeIndex, bool)>, session: &Session) {
+    fn send_on_link(&self, idxs: Vec<(NodeIndex, bool)>, link: &Link) {
         let msg = self.make_msg(idxs);
-        log::trace!("{} Send to {:?} {:?}", self.name, session.get_pid(), msg);
-        if let Err(e) = session.handle_message(msg) {
+        log::trace!("{} Send to {} {:?}", self.name, link.pid, msg);
+        if let Err(e) = link.sender.send_link_states(msg) {
             log::debug!("{} Error sending LinkStateList: {}", self.name, e);
         }
     }
@@ -235,7 +338,7 @@ impl Network {
         for link in self.links.values() {
             if predicate(link) {
                 log::trace!("{} Send to {} {:?}", self.name, link.pid, msg);
-                if let Err(e) = link.session.handle_message(msg.clone()) {
+                if let Err(e) = link.sender.send_link_states(msg.clone()) {
                     log::debug!("{} Error sending LinkStateList: {}", self.name, e);
                 }
             }
@@ -449,20 +552,29 @@ impl Network {
             .filter(|ls| !removed.iter().any(|(idx, _)| idx == &ls.1))
             .collect::<Vec<(Vec<PeerId>, NodeIndex, bool)>>();
 
-        if (self.peers_autoconnect && self.runtime.whatami == whatami::PEER)
-            || (self.routers_autoconnect_gossip && self.runtime.whatami == whatami::ROUTER)
+        if (self.peers_autoconnect && self.whatami == whatami::PEER)
+            || (self.routers_autoconnect_gossip && self.whatami == whatami::ROUTER)
         {
             // Connect discovered peers
+            let hops = if self.gossip_filter.radius.is_some() {
+                Some(petgraph::algo::dijkstra(&self.graph, self.idx, None, |_| 1))
+            } else {
+                None
+            };
             for (_, idx, _) in &link_states {
                 let node = &self.graph[*idx];
-                if (self.runtime.whatami == whatami::PEER
+                if (self.whatami == whatami::PEER
                     && (node.whatami == whatami::PEER || node.whatami == whatami::ROUTER))
-                    || (self.runtime.whatami == whatami::ROUTER && node.whatami == whatami::ROUTER)
+                    || (self.whatami == whatami::ROUTER && node.whatami == whatami::ROUTER)
                 {
-                    if let Some(locators) = &node.locators {
-                        let runtime = self.runtime.clone();
+                    let node_hops = hops.as_ref().and_then(|hops| hops.get(idx).cloned());
+                    let locators = self.gossip_filter.filter(node, node_hops);
+                    if locators.is_none() {
+                        log::trace!("{} Do not connect filtered out {}", self.name, node.pid);
+                    }
+                    if let (Some(runtime), Some(locators)) = (&self.runtime, locators) {
+                        let runtime = runtime.clone();
                         let pid = node.pid.clone();
-                        let locators = locators.clone();
                         async_std::task::spawn(async move {
                             // random backoff
                             async_std::task::sleep(std::time::Duration::from_millis(
@@ -503,20 +615,22 @@ impl Network {
                         })
                         .collect();
                     if !new_idxs.is_empty() || !updated_idxs.is_empty() {
-                        self.send_on_link(
-                            [&new_idxs[..], &updated_idxs[..]].concat(),
-                            &link.session,
-                        );
+                        self.send_on_link([&new_idxs[..], &updated_idxs[..]].concat(), link);
                     }
                 } else if !new_idxs.is_empty() {
-                    self.send_on_link(new_idxs.clone(), &link.session);
+                    self.send_on_link(new_idxs.clone(), link);
                 }
             }
         }
         removed
     }
 
-    pub(crate) fn add_link(&mut self, session: Session) -> usize {
+    pub(crate) fn add_link(
+        &mut self,
+        pid: PeerId,
+        whatami: whatami::Type,
+        sender: Arc<dyn LinkStateSender>,
+    ) -> usize {
         let free_index = {
             let mut i = 0;
             while self.links.contains_key(i) {
@@ -524,10 +638,9 @@ impl Network {
             }
             i
         };
-        self.links.insert(free_index, Link::new(session.clone()));
+        self.links
+            .insert(free_index, Link::new(pid.clone(), sender));
 
-        let pid = session.get_pid().unwrap();
-        let whatami = session.get_whatami().unwrap();
         let (idx, new) = match self.get_idx(&pid) {
             Some(idx) => (idx, false),
             None => {
@@ -558,7 +671,7 @@ impl Network {
         }
 
         let idxs = self.graph.node_indices().map(|i| (i, true)).collect();
-        self.send_on_link(idxs, &session);
+        self.send_on_link(idxs, &self.links[free_index]);
         free_index
     }
 
@@ -597,7 +710,7 @@ impl Network {
         );
 
         for link in self.links.values() {
-            if let Err(e) = link.session.handle_message(msg.clone()) {
+            if let Err(e) = link.sender.send_link_states(msg.clone()) {
                 log::debug!("{} Error sending LinkStateList: {}", self.name, e);
             }
         }
@@ -605,6 +718,13 @@ impl Network {
         removed
     }
 
+    /// Sends the link state of this node on all the links, to advertise its current locators.
+    pub(crate) fn refresh_locators(&mut self) {
+        log::trace!("{} refresh_locators", self.name);
+        self.graph[self.idx].sn += 1;
+        self.send_on_links(vec![(self.idx, false)], |_| true);
+    }
+
     fn remove_detached_nodes(&mut self) -> Vec<(NodeIndex, Node)> {
         let mut dfs_stack = vec![self.idx];
         let mut visit_map = self.graph.visit_map();
@@ -644,9 +764,11 @@ impl Network {
         });
 
         for tree_root_idx in &indexes {
-            let path = petgraph::algo::bellman_ford(&self.graph, *tree_root_idx)
-                .unwrap()
-                .1;
+            let (distances, path) =
+                petgraph::algo::bellman_ford(&self.graph, *tree_root_idx).unwrap();
+            if *tree_root_idx == self.idx {
+                self.distances = distances;
+            }
 
             if log::log_enabled!(log::Level::Debug) {
                 let ps: Vec<Option<String>> = path
@@ -0,0 +1,134 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use super::face::FaceState;
+use super::protocol::core::{whatami, CongestionControl, Reliability, ResKey};
+use super::protocol::io::ZBuf;
+use super::protocol::proto::DataInfo;
+use super::router::Tables;
+use async_std::sync::Arc;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use zenoh_util::properties::config::*;
+use zenoh_util::zlock;
+
+struct Publication {
+    res_name: String,
+    congestion_control: CongestionControl,
+    info: Option<DataInfo>,
+    payload: ZBuf,
+}
+
+/// The publications of a client in reconnect mode, buffered while it has no session with a router.
+///
+/// They are sent to the next router the client connects to, once its subscribers and queryables
+/// have been re-declared to it. It's bounded by a number of publications: when it's full, the
+/// oldest publication is dropped.
+pub struct PublicationBuffer {
+    capacity: usize,
+    publications: Mutex<VecDeque<Publication>>,
+}
+
+impl PublicationBuffer {
+    /// Returns the publication buffer of a client configured in reconnect mode in `config`, if any.
+    pub fn from_config(config: &ConfigProperties) -> Option<PublicationBuffer> {
+        if config
+            .get_or(&ZN_RECONNECT_KEY, ZN_RECONNECT_DEFAULT)
+            .to_lowercase()
+            != ZN_TRUE
+        {
+            return None;
+        }
+        let capacity = match config
+            .get_or(
+                &ZN_RECONNECT_BUFFER_SIZE_KEY,
+                ZN_RECONNECT_BUFFER_SIZE_DEFAULT,
+            )
+            .parse::<usize>()
+        {
+            Ok(capacity) => capacity,
+            Err(_) => {
+                log::warn!(
+                    "Invalid {}, use default value {}",
+                    ZN_RECONNECT_BUFFER_SIZE_STR,
+                    ZN_RECONNECT_BUFFER_SIZE_DEFAULT
+                );
+                ZN_RECONNECT_BUFFER_SIZE_DEFAULT.parse().unwrap()
+            }
+        };
+        Some(PublicationBuffer {
+            capacity,
+            publications: Mutex::new(VecDeque::new()),
+        })
+    }
+
+    // Returns true if the client owning `tables` has no session with a router or a peer.
+    #[inline]
+    pub(crate) fn is_disconnected(tables: &Tables) -> bool {
+        !tables
+            .faces
+            .values()
+            .any(|face| face.whatami != whatami::CLIENT)
+    }
+
+    pub(crate) fn push(
+        &self,
+        res_name: String,
+        congestion_control: CongestionControl,
+        info: Option<DataInfo>,
+        payload: ZBuf,
+    ) {
+        if self.capacity == 0 {
+            log::trace!("Drop publication on {} while disconnected", res_name);
+            return;
+        }
+        let mut publications = zlock!(self.publications);
+        if publications.len() >= self.capacity {
+            if let Some(dropped) = publications.pop_front() {
+                log::debug!(
+                    "Publication buffer full: drop publication on {}",
+                    dropped.res_name
+                );
+            }
+        }
+        log::trace!("Buffer publication on {} while disconnected", res_name);
+        publications.push_back(Publication {
+            res_name,
+            congestion_control,
+            info,
+            payload,
+        });
+    }
+
+    // Sends the buffered publications to `face`, in their publication order.
+    pub(crate) fn flush(&self, face: &Arc<FaceState>) {
+        let publications = std::mem::take(&mut *zlock!(self.publications));
+        if !publications.is_empty() {
+            log::debug!(
+                "Send {} buffered publications on {}",
+                publications.len(),
+                face
+            );
+        }
+        for publication in publications {
+            face.primitives.send_data(
+                &ResKey::RName(publication.res_name),
+                publication.payload,
+                Reliability::Reliable,
+                publication.congestion_control,
+                publication.info,
+                None,
+            );
+        }
+    }
+}
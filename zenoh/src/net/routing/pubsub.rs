@@ -20,14 +20,15 @@ use zenoh_util::sync::get_mut_unchecked;
 use zenoh_util::zread;
 
 use super::protocol::core::{
-    whatami, CongestionControl, PeerId, Reliability, SubInfo, SubMode, ZInt,
+    whatami, CongestionControl, History, PayloadValue, PeerId, Reliability, SubInfo, SubMode, ZInt,
 };
 use super::protocol::io::ZBuf;
 use super::protocol::proto::{DataInfo, RoutingContext};
 
 use super::face::FaceState;
 use super::network::Network;
-use super::resource::{elect_router, PullCaches, Resource, Route, SessionContext};
+use super::publication_buffer::PublicationBuffer;
+use super::resource::{elect_router, PullCaches, Resource, Route, SessionContext, SubFilters};
 use super::router::Tables;
 
 #[inline]
@@ -66,9 +67,12 @@ fn propagate_simple_subscription(
     sub_info: &SubInfo,
     src_face: &mut Arc<FaceState>,
 ) {
+    // a client subscription asking for the latest values is declared again to the router,
+    // for it to send them
+    let redeclare = sub_info.history == History::Latest && tables.whatami == whatami::CLIENT;
     for dst_face in &mut tables.faces.values_mut() {
         if src_face.id != dst_face.id
-            && !dst_face.local_subs.contains(res)
+            && (!dst_face.local_subs.contains(res) || redeclare)
             && match tables.whatami {
                 whatami::ROUTER => dst_face.whatami == whatami::CLIENT,
                 whatami::PEER => dst_face.whatami == whatami::CLIENT,
@@ -243,7 +247,7 @@ fn register_client_subscription(
         match res.session_ctxs.get_mut(&face.id) {
             Some(mut ctx) => match &ctx.subs {
                 Some(info) => {
-                    if SubMode::Pull == info.mode {
+                    if SubMode::Pull == info.mode || info.filter != sub_info.filter {
                         get_mut_unchecked(&mut ctx).subs = Some(sub_info.clone());
                     }
                 }
@@ -282,10 +286,15 @@ pub fn declare_client_subscription(
             Resource::match_resource(&tables, &mut res);
 
             register_client_subscription(tables, face, &mut res, sub_info);
+            get_mut_unchecked(face)
+                .churn
+                .declare(format!("sub:{}", res.name()));
             match tables.whatami {
                 whatami::ROUTER => {
+                    // the payload filter only applies to the data sent to this client
                     let mut propa_sub_info = sub_info.clone();
                     propa_sub_info.mode = SubMode::Push;
+                    propa_sub_info.filter = None;
                     register_router_subscription(
                         tables,
                         face,
@@ -297,6 +306,7 @@ pub fn declare_client_subscription(
                 whatami::PEER => {
                     let mut propa_sub_info = sub_info.clone();
                     propa_sub_info.mode = SubMode::Push;
+                    propa_sub_info.filter = None;
                     register_peer_subscription(
                         tables,
                         face,
@@ -311,6 +321,12 @@ pub fn declare_client_subscription(
             }
 
             compute_matches_data_routes(tables, &mut res);
+
+            if sub_info.history == History::Latest {
+                if let Some(cache) = tables.latest_value_cache.clone() {
+                    cache.send_initial_values(tables, face, &res.name(), sub_info);
+                }
+            }
         }
         None => log::error!("Declare subscription for unknown rid {}!", prefixid),
     }
@@ -589,6 +605,9 @@ pub fn forget_client_subscription(
     match tables.get_mapping(&face, &prefixid) {
         Some(prefix) => match Resource::get_resource(prefix, suffix) {
             Some(mut res) => {
+                get_mut_unchecked(face)
+                    .churn
+                    .undeclare(&format!("sub:{}", res.name()));
                 undeclare_client_subscription(tables, face, &mut res);
             }
             None => log::error!("Undeclare unknown subscription!"),
@@ -597,11 +616,88 @@ pub fn forget_client_subscription(
     }
 }
 
+// A client forwards the publications of its session to the router or peer it's connected to,
+// for them to be listed there.
+fn propagate_simple_publication(tables: &Tables, res: &Arc<Resource>, src_face: &Arc<FaceState>) {
+    if tables.whatami == whatami::CLIENT {
+        for dst_face in tables.faces.values() {
+            if src_face.id != dst_face.id && dst_face.whatami != whatami::CLIENT {
+                let reskey = Resource::get_best_key(res, "", dst_face.id);
+                dst_face.primitives.decl_publisher(&reskey, None);
+            }
+        }
+    }
+}
+
+fn propagate_forget_simple_publication(
+    tables: &Tables,
+    res: &Arc<Resource>,
+    src_face: &Arc<FaceState>,
+) {
+    if tables.whatami == whatami::CLIENT {
+        for dst_face in tables.faces.values() {
+            if src_face.id != dst_face.id && dst_face.whatami != whatami::CLIENT {
+                let reskey = Resource::get_best_key(res, "", dst_face.id);
+                dst_face.primitives.forget_publisher(&reskey, None);
+            }
+        }
+    }
+}
+
+/// Records a publisher declared by a client. The publishers are not routed: they are only
+/// recorded to list the entities declared on each face.
+pub fn declare_client_publication(
+    tables: &mut Tables,
+    face: &mut Arc<FaceState>,
+    prefixid: ZInt,
+    suffix: &str,
+) {
+    match tables.get_mapping(&face, &prefixid).cloned() {
+        Some(mut prefix) => {
+            let mut res = Resource::make_resource(tables, &mut prefix, suffix);
+            Resource::match_resource(&tables, &mut res);
+            log::debug!("Register client publication {} for {}", res.name(), face);
+            propagate_simple_publication(tables, &res, face);
+            get_mut_unchecked(face)
+                .churn
+                .declare(format!("pub:{}", res.name()));
+            get_mut_unchecked(face).remote_pubs.insert(res);
+        }
+        None => log::error!("Declare publication for unknown rid {}!", prefixid),
+    }
+}
+
+pub fn forget_client_publication(
+    tables: &mut Tables,
+    face: &mut Arc<FaceState>,
+    prefixid: ZInt,
+    suffix: &str,
+) {
+    match tables.get_mapping(&face, &prefixid) {
+        Some(prefix) => match Resource::get_resource(prefix, suffix)
+            .and_then(|res| get_mut_unchecked(face).remote_pubs.take(&res))
+        {
+            Some(mut res) => {
+                log::debug!("Unregister client publication {} for {}", res.name(), face);
+                propagate_forget_simple_publication(tables, &res, face);
+                get_mut_unchecked(face)
+                    .churn
+                    .undeclare(&format!("pub:{}", res.name()));
+                Resource::clean(&mut res);
+            }
+            None => log::error!("Undeclare unknown publication!"),
+        },
+        None => log::error!("Undeclare publication with unknown prefix!"),
+    }
+}
+
 pub(crate) fn pubsub_new_face(tables: &mut Tables, face: &mut Arc<FaceState>) {
     let sub_info = SubInfo {
         reliability: Reliability::Reliable, // TODO
         mode: SubMode::Push,
         period: None,
+        history: History::None,
+        filter: None,
     };
     if face.whatami == whatami::CLIENT && tables.whatami != whatami::CLIENT {
         for sub in &tables.router_subs {
@@ -695,6 +791,8 @@ pub(crate) fn pubsub_tree_change(
                                 reliability: Reliability::Reliable, // TODO
                                 mode: SubMode::Push,
                                 period: None,
+                                history: History::None,
+                                filter: None,
                             };
                             send_sourced_subscription_to_net_childs(
                                 tables,
@@ -879,6 +977,45 @@ fn compute_matching_pulls(
     Arc::new(pull_caches)
 }
 
+fn compute_sub_filters(tables: &Tables, prefix: &Arc<Resource>, suffix: &str) -> Arc<SubFilters> {
+    let mut sub_filters: SubFilters = HashMap::new();
+    let mut unfiltered = HashSet::new();
+    let res = Resource::get_resource(prefix, suffix);
+    let matches = res
+        .as_ref()
+        .map(|res| res.context.as_ref())
+        .flatten()
+        .map(|ctx| Cow::from(&ctx.matches))
+        .unwrap_or_else(|| {
+            Cow::from(Resource::get_matches(
+                tables,
+                &[&prefix.name(), suffix].concat(),
+            ))
+        });
+
+    for mres in matches.iter() {
+        let mres = mres.upgrade().unwrap();
+        for (sid, context) in &mres.session_ctxs {
+            if let Some(subinfo) = &context.subs {
+                if subinfo.mode == SubMode::Push {
+                    match &subinfo.filter {
+                        Some(filter) => sub_filters
+                            .entry(*sid)
+                            .or_insert_with(Vec::new)
+                            .push(filter.clone()),
+                        None => {
+                            unfiltered.insert(*sid);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    // a face with an unfiltered subscription receives all the data
+    sub_filters.retain(|sid, _| !unfiltered.contains(sid));
+    Arc::new(sub_filters)
+}
+
 pub(crate) fn compute_data_routes(tables: &mut Tables, res: &mut Arc<Resource>) {
     if res.context.is_some() {
         let mut res_mut = res.clone();
@@ -924,6 +1061,7 @@ pub(crate) fn compute_data_routes(tables: &mut Tables, res: &mut Arc<Resource>)
                 Some(compute_data_route(tables, res, "", None, whatami::CLIENT));
         }
         res_mut.context_mut().matching_pulls = compute_matching_pulls(tables, res, "");
+        res_mut.context_mut().sub_filters = compute_sub_filters(tables, res, "");
     }
 }
 
@@ -1086,10 +1224,59 @@ fn get_matching_pulls(
         .unwrap_or_else(|| compute_matching_pulls(tables, prefix, suffix))
 }
 
+#[inline]
+fn get_sub_filters(
+    tables: &Tables,
+    res: &Option<Arc<Resource>>,
+    prefix: &Arc<Resource>,
+    suffix: &str,
+) -> Arc<SubFilters> {
+    res.as_ref()
+        .map(|res| res.context.as_ref())
+        .flatten()
+        .map(|ctx| ctx.sub_filters.clone())
+        .unwrap_or_else(|| compute_sub_filters(tables, prefix, suffix))
+}
+
+// Returns true if `payload` should be sent to `face`: if the face has no subscription with
+// a payload filter, or if one of the filters of its subscriptions matches the payload.
+// The payload is decoded in `value` by the first face with a filter, and reused by the others.
+#[inline]
+fn sub_filters_accept(
+    sub_filters: &SubFilters,
+    face: &FaceState,
+    payload: &ZBuf,
+    value: &mut Option<PayloadValue>,
+) -> bool {
+    match sub_filters.get(&face.id) {
+        Some(filters) => {
+            let value = value.get_or_insert_with(|| PayloadValue::decode(&payload.contiguous()));
+            let accepted = filters.iter().any(|filter| filter.matches_value(value));
+            if !accepted {
+                log::trace!("Data filtered out for {}", face);
+            }
+            accepted
+        }
+        None => true,
+    }
+}
+
+// Appends the hop of a router to the provenance chain of the data received from another node.
+#[inline]
+fn append_router_hop(tables: &Tables, face: &FaceState, info: &mut Option<DataInfo>) {
+    if tables.whatami == whatami::ROUTER && face.pid != tables.pid {
+        if let Some(info) = info.as_mut() {
+            info.append_hop(tables.pid.clone());
+        }
+    }
+}
+
 macro_rules! send_to_first {
-    ($route:expr, $srcface:expr, $payload:expr, $congestion_control:expr, $data_info:expr) => {
+    ($route:expr, $sub_filters:expr, $srcface:expr, $payload:expr, $congestion_control:expr, $data_info:expr) => {
         let (outface, reskey, context) = $route.values().next().unwrap();
-        if $srcface.id != outface.id {
+        if $srcface.id != outface.id
+            && sub_filters_accept(&$sub_filters, outface, &$payload, &mut None)
+        {
             outface
                 .primitives
                 .send_data(
@@ -1105,9 +1292,12 @@ macro_rules! send_to_first {
 }
 
 macro_rules! send_to_all {
-    ($route:expr, $srcface:expr, $payload:expr, $congestion_control:expr, $data_info:expr) => {
+    ($route:expr, $sub_filters:expr, $srcface:expr, $payload:expr, $congestion_control:expr, $data_info:expr) => {
+        let mut payload_value = None;
         for (outface, reskey, context) in $route.values() {
-            if $srcface.id != outface.id {
+            if $srcface.id != outface.id
+                && sub_filters_accept(&$sub_filters, outface, &$payload, &mut payload_value)
+            {
                 outface
                     .primitives
                     .send_data(
@@ -1159,19 +1349,35 @@ pub fn route_data(
             let res = Resource::get_resource(&prefix, suffix);
             let route = get_data_route(&tables, face, &res, &prefix, suffix, routing_context);
             let matching_pulls = get_matching_pulls(&tables, &res, &prefix, suffix);
+            let sub_filters = get_sub_filters(&tables, &res, &prefix, suffix);
 
             if !(route.is_empty() && matching_pulls.is_empty()) {
-                let data_info = treat_timestamp!(&tables.hlc, info);
+                let mut data_info = treat_timestamp!(&tables.hlc, info);
+                append_router_hop(&tables, face, &mut data_info);
 
                 if route.len() == 1 && matching_pulls.len() == 0 {
-                    send_to_first!(route, face, payload, congestion_control, data_info);
+                    send_to_first!(
+                        route,
+                        sub_filters,
+                        face,
+                        payload,
+                        congestion_control,
+                        data_info
+                    );
                 } else {
                     if !matching_pulls.is_empty() {
                         let lock = zlock!(tables.pull_caches_lock);
                         cache_data!(matching_pulls, prefix, suffix, payload, data_info);
                         drop(lock);
                     }
-                    send_to_all!(route, face, payload, congestion_control, data_info);
+                    send_to_all!(
+                        route,
+                        sub_filters,
+                        face,
+                        payload,
+                        congestion_control,
+                        data_info
+                    );
                 }
             }
         }
@@ -1198,16 +1404,50 @@ pub fn full_reentrant_route_data(
         Some(prefix) => {
             log::trace!("Route data for res {}{}", prefix.name(), suffix,);
 
+            if let Some(quotas) = &face.quotas {
+                if !quotas.allow_publication() {
+                    log::debug!(
+                        "Publication on {}{} from {} exceeds the publications quota of {}: dropped",
+                        prefix.name(),
+                        suffix,
+                        face.pid,
+                        quotas.subject()
+                    );
+                    return;
+                }
+            }
+
+            if let Some(buffer) = &tables.publication_buffer {
+                if face.whatami == whatami::CLIENT && PublicationBuffer::is_disconnected(&tables) {
+                    buffer.push(
+                        [&prefix.name(), suffix].concat(),
+                        congestion_control,
+                        info,
+                        payload,
+                    );
+                    return;
+                }
+            }
+
             let res = Resource::get_resource(&prefix, suffix);
             let route = get_data_route(&tables, face, &res, &prefix, suffix, routing_context);
             let matching_pulls = get_matching_pulls(&tables, &res, &prefix, suffix);
+            let sub_filters = get_sub_filters(&tables, &res, &prefix, suffix);
 
             if !(route.is_empty() && matching_pulls.is_empty()) {
-                let data_info = treat_timestamp!(&tables.hlc, info);
+                let mut data_info = treat_timestamp!(&tables.hlc, info);
+                append_router_hop(&tables, face, &mut data_info);
 
                 if route.len() == 1 && matching_pulls.len() == 0 {
                     drop(tables);
-                    send_to_first!(route, face, payload, congestion_control, data_info);
+                    send_to_first!(
+                        route,
+                        sub_filters,
+                        face,
+                        payload,
+                        congestion_control,
+                        data_info
+                    );
                 } else {
                     if !matching_pulls.is_empty() {
                         let lock = zlock!(tables.pull_caches_lock);
@@ -1215,7 +1455,14 @@ pub fn full_reentrant_route_data(
                         drop(lock);
                     }
                     drop(tables);
-                    send_to_all!(route, face, payload, congestion_control, data_info);
+                    send_to_all!(
+                        route,
+                        sub_filters,
+                        face,
+                        payload,
+                        congestion_control,
+                        data_info
+                    );
                 }
             }
         }
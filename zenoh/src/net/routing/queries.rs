@@ -15,10 +15,13 @@ use async_std::sync::Arc;
 use petgraph::graph::NodeIndex;
 use std::borrow::Cow;
 use std::collections::HashMap;
+use std::sync::Mutex;
 use zenoh_util::sync::get_mut_unchecked;
+use zenoh_util::{zconfigurable, zlock};
 
 use super::protocol::core::{
-    queryable, whatami, PeerId, QueryConsolidation, QueryTarget, ResKey, ZInt,
+    queryable, rname, whatami, ConsolidationMode, PeerId, QueryConsolidation, QueryPage,
+    QueryTarget, ResKey, Target, Timestamp, ZInt,
 };
 use super::protocol::io::ZBuf;
 use super::protocol::proto::{DataInfo, RoutingContext};
@@ -28,9 +31,83 @@ use super::network::Network;
 use super::resource::{elect_router, Resource, Route, SessionContext};
 use super::router::Tables;
 
+zconfigurable! {
+    // Maximum number of resources for which a router keeps track of the replies
+    // of a query when consolidating them in-network. Beyond it, the replies for
+    // new resources are forwarded without consolidation.
+    static ref ROUTER_CONSOLIDATION_MAX_RESOURCES: usize = 1024;
+}
+
+type ReplyEntry = (ZInt, PeerId, ResKey, Option<DataInfo>, ZBuf);
+
+// The timestamp of the latest reply for each resource, with the reply itself if it's held.
+type ConsolidatedReplies = HashMap<String, (Timestamp, Option<ReplyEntry>)>;
+
 pub(crate) struct Query {
     src_face: Arc<FaceState>,
     src_qid: ZInt,
+    consolidation: ConsolidationMode,
+    replies: Mutex<ConsolidatedReplies>,
+}
+
+impl Query {
+    // Returns the reply to forward right away (if any) according to the consolidation mode.
+    // With a Full consolidation, the latest reply for each resource is held until the query
+    // is finalized. With a Lazy consolidation, only replies newer than the previously
+    // forwarded one for the same resource are forwarded. The replies without timestamp
+    // can't be ordered, and are forwarded without consolidation.
+    fn consolidate(&self, res_name: Option<String>, reply: ReplyEntry) -> Option<ReplyEntry> {
+        let timestamp = match reply.3.as_ref().and_then(|info| info.timestamp.as_ref()) {
+            Some(timestamp) => timestamp.clone(),
+            None => return Some(reply),
+        };
+        let res_name = match (self.consolidation, res_name) {
+            (ConsolidationMode::None, _) | (_, None) => return Some(reply),
+            (_, Some(res_name)) => res_name,
+        };
+        let mut replies = zlock!(self.replies);
+        match replies.get(&res_name) {
+            Some((latest, _)) => {
+                if timestamp <= *latest {
+                    return None;
+                }
+            }
+            None => {
+                if replies.len() >= *ROUTER_CONSOLIDATION_MAX_RESOURCES {
+                    return Some(reply);
+                }
+            }
+        }
+        match self.consolidation {
+            ConsolidationMode::Full => {
+                replies.insert(res_name, (timestamp, Some(reply)));
+                None
+            }
+            _ => {
+                replies.insert(res_name, (timestamp, None));
+                Some(reply)
+            }
+        }
+    }
+
+    fn send_reply_final(&self) {
+        for (_, (_, reply)) in zlock!(self.replies).drain() {
+            if let Some((replier_kind, replier_id, reskey, info, payload)) = reply {
+                self.src_face.primitives.clone().send_reply_data(
+                    self.src_qid,
+                    replier_kind,
+                    replier_id,
+                    reskey,
+                    info,
+                    payload,
+                );
+            }
+        }
+        self.src_face
+            .primitives
+            .clone()
+            .send_reply_final(self.src_qid);
+    }
 }
 
 fn local_router_qabl_kind(tables: &Tables, res: &Arc<Resource>) -> ZInt {
@@ -360,6 +437,9 @@ pub fn declare_client_queryable(
             Resource::match_resource(&tables, &mut res);
 
             register_client_queryable(tables, face, &mut res, kind);
+            get_mut_unchecked(face)
+                .churn
+                .declare(format!("qabl:{}", res.name()));
 
             match tables.whatami {
                 whatami::ROUTER => {
@@ -687,6 +767,9 @@ pub fn forget_client_queryable(
     match tables.get_mapping(&face, &prefixid) {
         Some(prefix) => match Resource::get_resource(prefix, suffix) {
             Some(mut res) => {
+                get_mut_unchecked(face)
+                    .churn
+                    .undeclare(&format!("qabl:{}", res.name()));
                 undeclare_client_queryable(tables, face, &mut res);
             }
             None => log::error!("Undeclare unknown queryable!"),
@@ -827,6 +910,7 @@ fn insert_faces_for_qabls(
     net: &Network,
     source: usize,
     qabls: &HashMap<PeerId, ZInt>,
+    distances: &mut HashMap<usize, f64>,
 ) {
     if net.trees.len() > source {
         for (qabl, qabl_kind) in qabls.iter() {
@@ -836,6 +920,15 @@ fn insert_faces_for_qabls(
                         if let Some(direction) = net.trees[source].directions[qabl_idx.index()] {
                             if net.graph.contains_node(direction) {
                                 if let Some(face) = tables.get_face(&net.graph[direction].pid) {
+                                    let distance = net
+                                        .distances
+                                        .get(qabl_idx.index())
+                                        .cloned()
+                                        .unwrap_or(f64::MAX);
+                                    distances
+                                        .entry(face.id)
+                                        .and_modify(|d| *d = d.min(distance))
+                                        .or_insert(distance);
                                     route.entry(face.id).or_insert_with(|| {
                                         let reskey =
                                             Resource::get_best_key(prefix, suffix, face.id);
@@ -861,15 +954,51 @@ fn insert_faces_for_qabls(
     }
 }
 
+// When `complete` is set, only the complete queryables are considered: the storages
+// declared on a resource that includes the whole queried resource.
 fn compute_query_route(
     tables: &Tables,
     prefix: &Arc<Resource>,
     suffix: &str,
     kind: ZInt,
+    complete: bool,
+    source: Option<usize>,
+    source_type: whatami::Type,
+) -> Arc<Route> {
+    compute_query_route_with_distances(
+        tables,
+        prefix,
+        suffix,
+        kind,
+        complete,
+        source,
+        source_type,
+        &mut HashMap::new(),
+    )
+}
+
+// Same as `compute_query_route`, also filling `distances` with the distance from this node
+// to the nearest matching queryable reachable through each face of the route.
+#[allow(clippy::too_many_arguments)]
+fn compute_query_route_with_distances(
+    tables: &Tables,
+    prefix: &Arc<Resource>,
+    suffix: &str,
+    kind: ZInt,
+    complete: bool,
     source: Option<usize>,
     source_type: whatami::Type,
+    distances: &mut HashMap<usize, f64>,
 ) -> Arc<Route> {
     let mut route = HashMap::new();
+    let kind = if complete {
+        if kind != queryable::ALL_KINDS && kind & queryable::STORAGE == 0 {
+            return Arc::new(route);
+        }
+        queryable::STORAGE
+    } else {
+        kind
+    };
     let res_name = [&prefix.name(), suffix].concat();
     let res = Resource::get_resource(prefix, suffix);
     let matches = res
@@ -884,6 +1013,9 @@ fn compute_query_route(
 
     for mres in matches.iter() {
         let mres = mres.upgrade().unwrap();
+        if complete && !rname::include(&mres.name(), &res_name) {
+            continue;
+        }
         if tables.whatami == whatami::ROUTER {
             if master || source_type == whatami::ROUTER {
                 let net = tables.routers_net.as_ref().unwrap();
@@ -900,6 +1032,7 @@ fn compute_query_route(
                     net,
                     router_source,
                     &mres.context().router_qabls,
+                    distances,
                 );
             }
 
@@ -918,6 +1051,7 @@ fn compute_query_route(
                     net,
                     peer_source,
                     &mres.context().peer_qabls,
+                    distances,
                 );
             }
         }
@@ -937,12 +1071,17 @@ fn compute_query_route(
                 net,
                 peer_source,
                 &mres.context().peer_qabls,
+                distances,
             );
         }
 
         if tables.whatami != whatami::ROUTER || master || source_type == whatami::ROUTER {
             for (sid, context) in &mres.session_ctxs {
-                if context.qabl.is_some() {
+                if context
+                    .qabl
+                    .map_or(false, |k| !complete || k & queryable::STORAGE != 0)
+                {
+                    distances.insert(*sid, 0.0);
                     route.entry(*sid).or_insert_with(|| {
                         let reskey = Resource::get_best_key(prefix, suffix, *sid);
                         (context.face.clone(), reskey, None)
@@ -967,19 +1106,31 @@ pub(crate) fn compute_query_routes(tables: &mut Tables, res: &mut Arc<Resource>)
                 .node_indices()
                 .collect::<Vec<NodeIndex>>();
             let max_idx = indexes.iter().max().unwrap();
-            let routers_query_routes = &mut res_mut.context_mut().routers_query_routes;
-            routers_query_routes.clear();
-            routers_query_routes.resize_with(max_idx.index() + 1, || Arc::new(HashMap::new()));
+            let ctx = res_mut.context_mut();
+            ctx.routers_query_routes.clear();
+            ctx.routers_query_routes
+                .resize_with(max_idx.index() + 1, || Arc::new(HashMap::new()));
+            ctx.routers_complete_query_routes.clear();
+            ctx.routers_complete_query_routes
+                .resize_with(max_idx.index() + 1, || Arc::new(HashMap::new()));
 
             for idx in &indexes {
-                routers_query_routes[idx.index()] = compute_query_route(
-                    tables,
-                    res,
-                    "",
-                    queryable::ALL_KINDS,
-                    Some(idx.index()),
-                    whatami::ROUTER,
-                );
+                for complete in &[false, true] {
+                    let route = compute_query_route(
+                        tables,
+                        res,
+                        "",
+                        queryable::ALL_KINDS,
+                        *complete,
+                        Some(idx.index()),
+                        whatami::ROUTER,
+                    );
+                    let ctx = res_mut.context_mut();
+                    match complete {
+                        false => ctx.routers_query_routes[idx.index()] = route,
+                        true => ctx.routers_complete_query_routes[idx.index()] = route,
+                    }
+                }
             }
         }
         if tables.whatami == whatami::ROUTER || tables.whatami == whatami::PEER {
@@ -991,19 +1142,31 @@ pub(crate) fn compute_query_routes(tables: &mut Tables, res: &mut Arc<Resource>)
                 .node_indices()
                 .collect::<Vec<NodeIndex>>();
             let max_idx = indexes.iter().max().unwrap();
-            let peers_query_routes = &mut res_mut.context_mut().peers_query_routes;
-            peers_query_routes.clear();
-            peers_query_routes.resize_with(max_idx.index() + 1, || Arc::new(HashMap::new()));
+            let ctx = res_mut.context_mut();
+            ctx.peers_query_routes.clear();
+            ctx.peers_query_routes
+                .resize_with(max_idx.index() + 1, || Arc::new(HashMap::new()));
+            ctx.peers_complete_query_routes.clear();
+            ctx.peers_complete_query_routes
+                .resize_with(max_idx.index() + 1, || Arc::new(HashMap::new()));
 
             for idx in &indexes {
-                peers_query_routes[idx.index()] = compute_query_route(
-                    tables,
-                    res,
-                    "",
-                    queryable::ALL_KINDS,
-                    Some(idx.index()),
-                    whatami::PEER,
-                );
+                for complete in &[false, true] {
+                    let route = compute_query_route(
+                        tables,
+                        res,
+                        "",
+                        queryable::ALL_KINDS,
+                        *complete,
+                        Some(idx.index()),
+                        whatami::PEER,
+                    );
+                    let ctx = res_mut.context_mut();
+                    match complete {
+                        false => ctx.peers_query_routes[idx.index()] = route,
+                        true => ctx.peers_complete_query_routes[idx.index()] = route,
+                    }
+                }
             }
         }
         if tables.whatami == whatami::CLIENT {
@@ -1012,6 +1175,16 @@ pub(crate) fn compute_query_routes(tables: &mut Tables, res: &mut Arc<Resource>)
                 res,
                 "",
                 queryable::ALL_KINDS,
+                false,
+                None,
+                whatami::CLIENT,
+            ));
+            res_mut.context_mut().client_complete_query_route = Some(compute_query_route(
+                tables,
+                res,
+                "",
+                queryable::ALL_KINDS,
+                true,
                 None,
                 whatami::CLIENT,
             ));
@@ -1050,6 +1223,7 @@ pub fn route_query(
     qid: ZInt,
     target: QueryTarget,
     consolidation: QueryConsolidation,
+    page: Option<QueryPage>,
     routing_context: Option<RoutingContext>,
 ) {
     match tables.get_mapping(&face, &rid) {
@@ -1062,105 +1236,53 @@ pub fn route_query(
                 suffix,
             );
 
-            let route = match tables.whatami {
-                whatami::ROUTER => match face.whatami {
-                    whatami::ROUTER => {
-                        let routers_net = tables.routers_net.as_ref().unwrap();
-                        let local_context = routers_net
-                            .get_local_context(routing_context.map(|rc| rc.tree_id), face.link_id);
-                        (target.kind == queryable::ALL_KINDS)
-                            .then(|| Resource::get_resource(prefix, suffix))
-                            .flatten()
-                            .map(|res| res.routers_query_route(local_context))
-                            .flatten()
-                            .unwrap_or_else(|| {
-                                compute_query_route(
-                                    tables,
-                                    prefix,
-                                    suffix,
-                                    target.kind,
-                                    Some(local_context),
-                                    whatami::ROUTER,
-                                )
-                            })
-                    }
-                    whatami::PEER => {
-                        let peers_net = tables.peers_net.as_ref().unwrap();
-                        let local_context = peers_net
-                            .get_local_context(routing_context.map(|rc| rc.tree_id), face.link_id);
-                        (target.kind == queryable::ALL_KINDS)
-                            .then(|| Resource::get_resource(prefix, suffix))
-                            .flatten()
-                            .map(|res| res.peers_query_route(local_context))
-                            .flatten()
-                            .unwrap_or_else(|| {
-                                compute_query_route(
-                                    tables,
-                                    prefix,
-                                    suffix,
-                                    target.kind,
-                                    Some(local_context),
-                                    whatami::PEER,
-                                )
-                            })
+            let (source, source_type) =
+                match (tables.whatami, face.whatami) {
+                    (whatami::ROUTER, whatami::ROUTER) => {
+                        (
+                            Some(tables.routers_net.as_ref().unwrap().get_local_context(
+                                routing_context.map(|rc| rc.tree_id),
+                                face.link_id,
+                            )),
+                            whatami::ROUTER,
+                        )
                     }
-                    _ => (target.kind == queryable::ALL_KINDS)
-                        .then(|| Resource::get_resource(prefix, suffix))
-                        .flatten()
-                        .map(|res| res.routers_query_route(0))
-                        .flatten()
-                        .unwrap_or_else(|| {
-                            compute_query_route(
-                                tables,
-                                prefix,
-                                suffix,
-                                target.kind,
-                                None,
-                                whatami::CLIENT,
-                            )
-                        }),
-                },
-                whatami::PEER => match face.whatami {
-                    whatami::ROUTER | whatami::PEER => {
-                        let peers_net = tables.peers_net.as_ref().unwrap();
-                        let local_context = peers_net
-                            .get_local_context(routing_context.map(|rc| rc.tree_id), face.link_id);
-                        (target.kind == queryable::ALL_KINDS)
-                            .then(|| Resource::get_resource(prefix, suffix))
-                            .flatten()
-                            .map(|res| res.peers_query_route(local_context))
-                            .flatten()
-                            .unwrap_or_else(|| {
-                                compute_query_route(
-                                    tables,
-                                    prefix,
-                                    suffix,
-                                    target.kind,
-                                    Some(local_context),
-                                    whatami::PEER,
-                                )
-                            })
+                    (whatami::ROUTER, whatami::PEER)
+                    | (whatami::PEER, whatami::ROUTER)
+                    | (whatami::PEER, whatami::PEER) => {
+                        (
+                            Some(tables.peers_net.as_ref().unwrap().get_local_context(
+                                routing_context.map(|rc| rc.tree_id),
+                                face.link_id,
+                            )),
+                            whatami::PEER,
+                        )
                     }
-                    _ => (target.kind == queryable::ALL_KINDS)
-                        .then(|| Resource::get_resource(prefix, suffix))
-                        .flatten()
-                        .map(|res| res.peers_query_route(0))
-                        .flatten()
-                        .unwrap_or_else(|| {
-                            compute_query_route(
-                                tables,
-                                prefix,
-                                suffix,
-                                target.kind,
-                                None,
-                                whatami::CLIENT,
-                            )
-                        }),
-                },
-                _ => (target.kind == queryable::ALL_KINDS)
+                    _ => (None, whatami::CLIENT),
+                };
+
+            let compute_route = |complete: bool| {
+                (target.kind == queryable::ALL_KINDS)
                     .then(|| Resource::get_resource(prefix, suffix))
                     .flatten()
-                    .map(|res| res.client_query_route())
+                    .map(|res| match (tables.whatami, source_type, complete) {
+                        (whatami::ROUTER, whatami::ROUTER, false) => {
+                            res.routers_query_route(source.unwrap())
+                        }
+                        (whatami::ROUTER, whatami::ROUTER, true) => {
+                            res.routers_complete_query_route(source.unwrap())
+                        }
+                        (whatami::ROUTER, whatami::PEER, false) | (whatami::PEER, _, false) => {
+                            res.peers_query_route(source.unwrap_or(0))
+                        }
+                        (whatami::ROUTER, whatami::PEER, true) | (whatami::PEER, _, true) => {
+                            res.peers_complete_query_route(source.unwrap_or(0))
+                        }
+                        (whatami::ROUTER, _, false) => res.routers_query_route(0),
+                        (whatami::ROUTER, _, true) => res.routers_complete_query_route(0),
+                        (_, _, false) => res.client_query_route(),
+                        (_, _, true) => res.client_complete_query_route(),
+                    })
                     .flatten()
                     .unwrap_or_else(|| {
                         compute_query_route(
@@ -1168,10 +1290,103 @@ pub fn route_query(
                             prefix,
                             suffix,
                             target.kind,
-                            None,
-                            whatami::CLIENT,
+                            complete,
+                            source,
+                            source_type,
                         )
-                    }),
+                    })
+            };
+
+            let route = match target.target {
+                Target::Complete { n } => {
+                    // Only query the queryables that are complete for the queried resource.
+                    // Fall back to all the matching queryables if there are none.
+                    let route = compute_route(true);
+                    if route.keys().all(|id| *id == face.id) {
+                        compute_route(false)
+                    } else {
+                        let mut faces = route
+                            .iter()
+                            .filter(|(id, _)| **id != face.id)
+                            .collect::<Vec<_>>();
+                        faces.sort_by_key(|(id, _)| **id);
+                        Arc::new(
+                            faces
+                                .into_iter()
+                                .take((n as usize).max(1))
+                                .map(|(id, dir)| (*id, dir.clone()))
+                                .collect(),
+                        )
+                    }
+                }
+                Target::Nearest => {
+                    // Only query the face leading to the nearest complete queryable.
+                    // Fall back to the nearest matching queryable if there are none.
+                    let mut distances = HashMap::new();
+                    let mut route = compute_query_route_with_distances(
+                        tables,
+                        prefix,
+                        suffix,
+                        target.kind,
+                        true,
+                        source,
+                        source_type,
+                        &mut distances,
+                    );
+                    if route.keys().all(|id| *id == face.id) {
+                        distances.clear();
+                        route = compute_query_route_with_distances(
+                            tables,
+                            prefix,
+                            suffix,
+                            target.kind,
+                            false,
+                            source,
+                            source_type,
+                            &mut distances,
+                        );
+                    }
+                    let nearest = route
+                        .iter()
+                        .filter(|(id, _)| **id != face.id)
+                        .min_by(|(id1, _), (id2, _)| {
+                            let d1 = distances.get(id1).unwrap_or(&f64::MAX);
+                            let d2 = distances.get(id2).unwrap_or(&f64::MAX);
+                            d1.partial_cmp(d2)
+                                .unwrap_or(std::cmp::Ordering::Equal)
+                                .then(id1.cmp(id2))
+                        })
+                        .map(|(id, dir)| (*id, dir.clone()));
+                    Arc::new(nearest.into_iter().collect())
+                }
+                Target::BestMatchingN { n } => {
+                    // Only query the n least loaded faces leading to complete queryables.
+                    // Fall back to all the matching queryables if there are none.
+                    let mut route = compute_route(true);
+                    if route.keys().all(|id| *id == face.id) {
+                        route = compute_route(false);
+                    }
+                    let mut faces = route
+                        .iter()
+                        .filter(|(id, _)| **id != face.id)
+                        .collect::<Vec<_>>();
+                    faces.sort_by_key(|(id, _)| **id);
+                    // Take the equally loaded faces in turn (the sort by load is stable)
+                    if !faces.is_empty() {
+                        let len = faces.len();
+                        faces.rotate_left(tables.query_counter % len);
+                    }
+                    tables.query_counter = tables.query_counter.wrapping_add(1);
+                    faces.sort_by_key(|(_, (outface, _, _))| outface.pending_queries.len());
+                    Arc::new(
+                        faces
+                            .into_iter()
+                            .take((n as usize).max(1))
+                            .map(|(id, dir)| (*id, dir.clone()))
+                            .collect(),
+                    )
+                }
+                _ => compute_route(false),
             };
 
             if route.is_empty()
@@ -1180,9 +1395,22 @@ pub fn route_query(
                 log::debug!("Send final reply {}:{} (no matching queryables)", face, qid);
                 face.primitives.clone().send_reply_final(qid)
             } else {
+                // The consolidation depends on the position of this router on the query path:
+                // the last router only forwards the query to the queryables of its clients and
+                // peers, the others (the router of the querier included) forward it to routers.
+                let is_last_router = face.whatami != whatami::CLIENT
+                    && route.values().all(|(outface, _, _)| {
+                        outface.id == face.id || outface.whatami != whatami::ROUTER
+                    });
                 let query = Arc::new(Query {
                     src_face: face.clone(),
                     src_qid: qid,
+                    consolidation: if is_last_router {
+                        consolidation.last_router
+                    } else {
+                        consolidation.first_routers
+                    },
+                    replies: Mutex::new(HashMap::new()),
                 });
 
                 for (outface, reskey, context) in route.values() {
@@ -1201,6 +1429,7 @@ pub fn route_query(
                             qid,
                             target.clone(),
                             consolidation.clone(),
+                            page.clone(),
                             *context,
                         )
                     }
@@ -1216,7 +1445,7 @@ pub fn route_query(
 
 #[allow(clippy::too_many_arguments)]
 pub(crate) fn route_send_reply_data(
-    _tables: &mut Tables,
+    tables: &mut Tables,
     face: &mut Arc<FaceState>,
     qid: ZInt,
     replier_kind: ZInt,
@@ -1227,14 +1456,25 @@ pub(crate) fn route_send_reply_data(
 ) {
     match face.pending_queries.get(&qid) {
         Some(query) => {
-            query.src_face.primitives.clone().send_reply_data(
-                query.src_qid,
-                replier_kind,
-                replier_id,
-                reskey,
-                info,
-                payload,
-            );
+            let res_name = match &reskey {
+                ResKey::RName(name) => Some(name.clone()),
+                ResKey::RId(rid) => tables.get_mapping(&face, rid).map(|res| res.name()),
+                ResKey::RIdWithSuffix(rid, suffix) => tables
+                    .get_mapping(&face, rid)
+                    .map(|res| [&res.name(), suffix.as_str()].concat()),
+            };
+            if let Some((replier_kind, replier_id, reskey, info, payload)) =
+                query.consolidate(res_name, (replier_kind, replier_id, reskey, info, payload))
+            {
+                query.src_face.primitives.clone().send_reply_data(
+                    query.src_qid,
+                    replier_kind,
+                    replier_id,
+                    reskey,
+                    info,
+                    payload,
+                );
+            }
         }
         None => log::error!("Route reply for unknown query!"),
     }
@@ -1251,11 +1491,7 @@ pub(crate) fn route_send_reply_final(_tables: &mut Tables, face: &mut Arc<FaceSt
             );
             if Arc::strong_count(&query) == 1 {
                 log::debug!("Propagate final reply {}:{}", query.src_face, qid);
-                query
-                    .src_face
-                    .primitives
-                    .clone()
-                    .send_reply_final(query.src_qid);
+                query.send_reply_final();
             }
             get_mut_unchecked(face).pending_queries.remove(&qid);
         }
@@ -1273,12 +1509,114 @@ pub(crate) fn finalize_pending_queries(_tables: &mut Tables, face: &mut Arc<Face
         );
         if Arc::strong_count(&query) == 1 {
             log::debug!("Propagate final reply {}:{}", query.src_face, query.src_qid);
-            query
-                .src_face
-                .primitives
-                .clone()
-                .send_reply_final(query.src_qid);
+            query.send_reply_final();
         }
     }
     get_mut_unchecked(face).pending_queries.clear();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::protocol::core::TimestampId;
+    use super::super::protocol::session::DummyPrimitives;
+    use super::*;
+
+    fn query(consolidation: ConsolidationMode) -> Query {
+        Query {
+            src_face: FaceState::new(
+                0,
+                PeerId::new(1, [0u8; PeerId::MAX_SIZE]),
+                whatami::CLIENT,
+                Arc::new(DummyPrimitives::new()),
+                0,
+                None,
+            ),
+            src_qid: 0,
+            consolidation,
+            replies: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn reply(time: Option<u64>) -> ReplyEntry {
+        let info = time.map(|time| DataInfo {
+            timestamp: Some(Timestamp::new(
+                uhlc::NTP64(time),
+                TimestampId::new(1, [0u8; TimestampId::MAX_SIZE]),
+            )),
+            ..DataInfo::default()
+        });
+        (
+            queryable::STORAGE,
+            PeerId::new(1, [1u8; PeerId::MAX_SIZE]),
+            ResKey::RName("/a".to_string()),
+            info,
+            ZBuf::new(),
+        )
+    }
+
+    fn consolidate(query: &Query, time: Option<u64>) -> Option<Option<u64>> {
+        query
+            .consolidate(Some("/a".to_string()), reply(time))
+            .map(|reply| {
+                reply
+                    .3
+                    .and_then(|info| info.timestamp)
+                    .map(|t| t.get_time().0)
+            })
+    }
+
+    #[test]
+    fn test_consolidate_none() {
+        let query = query(ConsolidationMode::None);
+        assert_eq!(consolidate(&query, Some(2)), Some(Some(2)));
+        assert_eq!(consolidate(&query, Some(1)), Some(Some(1)));
+        assert!(zlock!(query.replies).is_empty());
+    }
+
+    #[test]
+    fn test_consolidate_lazy() {
+        let query = query(ConsolidationMode::Lazy);
+        assert_eq!(consolidate(&query, Some(2)), Some(Some(2)));
+        // the replies which are not newer than the forwarded one are dropped
+        assert_eq!(consolidate(&query, Some(2)), None);
+        assert_eq!(consolidate(&query, Some(1)), None);
+        assert_eq!(consolidate(&query, Some(3)), Some(Some(3)));
+        // the replies without timestamp are forwarded
+        assert_eq!(consolidate(&query, None), Some(None));
+        assert_eq!(consolidate(&query, None), Some(None));
+        assert_eq!(consolidate(&query, Some(1)), None);
+    }
+
+    #[test]
+    fn test_consolidate_full() {
+        let query = query(ConsolidationMode::Full);
+        // the latest reply is held until the query is finalized
+        assert_eq!(consolidate(&query, Some(2)), None);
+        assert_eq!(consolidate(&query, Some(3)), None);
+        assert_eq!(consolidate(&query, Some(1)), None);
+        // the replies without timestamp are forwarded
+        assert_eq!(consolidate(&query, None), Some(None));
+        let replies = zlock!(query.replies);
+        let (timestamp, reply) = replies.get("/a").unwrap();
+        assert_eq!(timestamp.get_time().0, 3);
+        assert!(reply.is_some());
+    }
+
+    #[test]
+    fn test_consolidate_max_resources() {
+        let query = query(ConsolidationMode::Full);
+        for i in 0..*ROUTER_CONSOLIDATION_MAX_RESOURCES {
+            assert!(query
+                .consolidate(Some(format!("/a/{}", i)), reply(Some(1)))
+                .is_none());
+        }
+        // the replies for the resources beyond the bound are forwarded
+        assert!(query
+            .consolidate(Some("/b".to_string()), reply(Some(1)))
+            .is_some());
+        assert_eq!(
+            zlock!(query.replies).len(),
+            *ROUTER_CONSOLIDATION_MAX_RESOURCES
+        );
+    }
+}
@@ -0,0 +1,220 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use super::protocol::proto::smsg;
+use super::protocol::session::Session;
+use async_std::sync::Arc;
+use async_std::task;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+use zenoh_util::properties::config::*;
+
+// The number of declarations exceeding the quota after which a session is considered abusive.
+const MAX_REJECTED_DECLARATIONS: usize = 100;
+
+// A token bucket allowing `rate` operations per second, in bursts of up to `rate` operations.
+struct RateLimiter {
+    tokens: f64,
+    last: Instant,
+}
+
+impl RateLimiter {
+    fn new(rate: u32) -> RateLimiter {
+        RateLimiter {
+            tokens: rate as f64,
+            last: Instant::now(),
+        }
+    }
+
+    fn allow(&mut self, rate: u32) -> bool {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate as f64).min(rate as f64);
+        self.last = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+struct Usage {
+    publications: RateLimiter,
+    queries: RateLimiter,
+    // the entities declared on each face of the subject
+    declarations: HashMap<usize, HashSet<String>>,
+    faces: usize,
+}
+
+/// The quotas enforced by a router on each subject: the publication rate, the query rate and
+/// the number of declared subscribers and queryables, summed over all the sessions of the subject.
+///
+/// The subject of a session is the subject its peer was authenticated as (ex: the subject of its JWT),
+/// or its peer id if it was not authenticated. The sessions with other routers are not limited.
+///
+/// The publications exceeding the quota are dropped, the queries exceeding the quota are answered
+/// with no reply and the declarations exceeding the quota are ignored, leaving the other entities
+/// of the subject untouched. Only a session repeatedly declaring entities beyond the quota is closed,
+/// with the [`QUOTA`](smsg::close_reason::QUOTA) close reason.
+pub struct Quotas {
+    publication_rate: Option<u32>,
+    query_rate: Option<u32>,
+    declarations: Option<usize>,
+    usages: Mutex<HashMap<String, Usage>>,
+}
+
+impl Quotas {
+    /// Returns the quotas configured in `config`, if any.
+    pub fn from_config(config: &ConfigProperties) -> Option<Arc<Quotas>> {
+        fn parse<T: std::str::FromStr>(
+            config: &ConfigProperties,
+            key: u64,
+            name: &str,
+        ) -> Option<T> {
+            let value = config.get(&key)?;
+            match value.parse::<T>() {
+                Ok(value) => Some(value),
+                Err(_) => {
+                    log::warn!("Invalid {} ({}): ignored", name, value);
+                    None
+                }
+            }
+        }
+        let publication_rate = parse(
+            config,
+            ZN_QUOTA_PUBLICATION_RATE_KEY,
+            ZN_QUOTA_PUBLICATION_RATE_STR,
+        );
+        let query_rate = parse(config, ZN_QUOTA_QUERY_RATE_KEY, ZN_QUOTA_QUERY_RATE_STR);
+        let declarations = parse(config, ZN_QUOTA_DECLARATIONS_KEY, ZN_QUOTA_DECLARATIONS_STR);
+        if publication_rate.is_none() && query_rate.is_none() && declarations.is_none() {
+            return None;
+        }
+        log::info!(
+            "Quotas per subject: {:?} publications/s, {:?} queries/s, {:?} declarations",
+            publication_rate,
+            query_rate,
+            declarations
+        );
+        Some(Arc::new(Quotas {
+            publication_rate,
+            query_rate,
+            declarations,
+            usages: Mutex::new(HashMap::new()),
+        }))
+    }
+
+    // Returns the quotas of `subject` for a new face on `session`.
+    pub(crate) fn subject(self: &Arc<Self>, subject: String, session: Session) -> SubjectQuotas {
+        zlock!(self.usages)
+            .entry(subject.clone())
+            .or_insert_with(|| Usage {
+                publications: RateLimiter::new(self.publication_rate.unwrap_or(0)),
+                queries: RateLimiter::new(self.query_rate.unwrap_or(0)),
+                declarations: HashMap::new(),
+                faces: 0,
+            })
+            .faces += 1;
+        SubjectQuotas {
+            quotas: self.clone(),
+            subject,
+            session,
+            rejected: AtomicUsize::new(0),
+        }
+    }
+}
+
+/// The quotas of the subject of a face.
+pub(crate) struct SubjectQuotas {
+    quotas: Arc<Quotas>,
+    subject: String,
+    session: Session,
+    // the declarations of this face rejected so far
+    rejected: AtomicUsize,
+}
+
+impl SubjectQuotas {
+    fn with_usage<T>(&self, f: impl FnOnce(&mut Usage) -> T) -> T {
+        let mut usages = zlock!(self.quotas.usages);
+        f(usages.get_mut(&self.subject).unwrap())
+    }
+
+    pub(crate) fn allow_publication(&self) -> bool {
+        match self.quotas.publication_rate {
+            Some(rate) => self.with_usage(|usage| usage.publications.allow(rate)),
+            None => true,
+        }
+    }
+
+    pub(crate) fn allow_query(&self) -> bool {
+        match self.quotas.query_rate {
+            Some(rate) => self.with_usage(|usage| usage.queries.allow(rate)),
+            None => true,
+        }
+    }
+
+    // Records the declaration of `entity` (its kind and resource name) on the face `face_id`,
+    // returning false if it exceeds the quota.
+    pub(crate) fn declare(&self, face_id: usize, entity: String) -> bool {
+        let max = match self.quotas.declarations {
+            Some(max) => max,
+            None => return true,
+        };
+        self.with_usage(|usage| {
+            let count: usize = usage.declarations.values().map(|d| d.len()).sum();
+            let declarations = usage.declarations.entry(face_id).or_default();
+            declarations.contains(&entity) || (count < max && declarations.insert(entity))
+        })
+    }
+
+    pub(crate) fn undeclare(&self, face_id: usize, entity: &str) {
+        if self.quotas.declarations.is_some() {
+            self.with_usage(|usage| {
+                if let Some(declarations) = usage.declarations.get_mut(&face_id) {
+                    declarations.remove(entity);
+                }
+            })
+        }
+    }
+
+    pub(crate) fn subject(&self) -> &str {
+        &self.subject
+    }
+
+    // Records a declaration of the face exceeding the quota, closing its session once it
+    // rejected MAX_REJECTED_DECLARATIONS declarations. Returns true if the session is closed.
+    pub(crate) fn reject(&self) -> bool {
+        if self.rejected.fetch_add(1, Ordering::Relaxed) + 1 < MAX_REJECTED_DECLARATIONS {
+            return false;
+        }
+        let session = self.session.clone();
+        task::spawn(async move {
+            let _ = session.close_with_reason(smsg::close_reason::QUOTA).await;
+        });
+        true
+    }
+
+    pub(crate) fn close(&self, face_id: usize) {
+        let mut usages = zlock!(self.quotas.usages);
+        let usage = usages.get_mut(&self.subject).unwrap();
+        usage.declarations.remove(&face_id);
+        usage.faces -= 1;
+        if usage.faces == 0 {
+            usages.remove(&self.subject);
+        }
+    }
+}
@@ -0,0 +1,664 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+//! Recording and deterministic replay of the events processed by the routing core.
+//!
+//! When zenoh is built with the `routing_replay` feature and the `routing_trace` configuration
+//! property is set, the routing core writes to that file every face it opens or closes and every
+//! message it receives from a face, whether the face is a client, a peer or a router. It also
+//! writes the changes of its routers and peers networks: the link states it receives, the links
+//! it removes and the computations of the trees, at the point they were processed.
+//! The [`replay`] function feeds such a trace to a fresh routing core, one event at a time on the
+//! calling thread, and returns what it sent to each face, link states included.
+//! Replaying the same trace always produces the same output, so a routing bug observed in the
+//! field, including in the routing between routers, can be reproduced and debugged from its trace.
+//!
+//! The replayed routing core doesn't connect to the peers discovered in the link states, and
+//! advertises no locator in the link states it sends.
+use async_std::sync::Arc;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::{Mutex, RwLock};
+
+use super::face::Face;
+use super::network::LinkStateSender;
+use super::network::{shared_nodes, Network};
+use super::protocol::core::{
+    whatami, CongestionControl, PeerId, QueryConsolidation, QueryPage, QueryTarget, Reliability,
+    ResKey, SubInfo, WhatAmI, ZInt,
+};
+use super::protocol::io::{WBuf, ZBuf};
+use super::protocol::proto::{
+    zmsg, DataInfo, Declaration, ForgetQueryable, ForgetResource, ForgetSubscriber, LinkState,
+    Queryable, ReplierInfo, ReplyContext, Resource, RoutingContext, Subscriber, ZenohBody,
+    ZenohMessage,
+};
+use super::protocol::session::{DeMux, Primitives, SessionEventHandler};
+use super::router::Tables;
+use zenoh_util::core::{ZError, ZErrorKind, ZResult};
+use zenoh_util::{zerror, zerror2, zlock, zwrite};
+
+const TRACE_HEADER: ZInt = 0;
+const TRACE_OPEN_FACE: ZInt = 1;
+const TRACE_CLOSE_FACE: ZInt = 2;
+const TRACE_MESSAGE: ZInt = 3;
+const TRACE_LINK_STATES: ZInt = 4;
+const TRACE_REMOVE_LINK: ZInt = 5;
+const TRACE_COMPUTE_TREES: ZInt = 6;
+// The maximal size in bytes of a record: larger records are neither written nor read.
+const TRACE_MAX_RECORD_SIZE: usize = 64 * 1024 * 1024;
+
+/// The recorder of the events processed by a routing core.
+pub struct RoutingTrace {
+    file: Mutex<BufWriter<File>>,
+}
+
+impl RoutingTrace {
+    /// Creates the trace file `path` for the routing core of the runtime `pid`.
+    pub fn create<P: AsRef<Path>>(
+        path: P,
+        pid: &PeerId,
+        whatami: WhatAmI,
+    ) -> ZResult<RoutingTrace> {
+        let file = File::create(path.as_ref()).map_err(|e| {
+            zerror2!(ZErrorKind::IoError {
+                descr: format!(
+                    "Unable to create routing trace {}: {}",
+                    path.as_ref().display(),
+                    e
+                )
+            })
+        })?;
+        let trace = RoutingTrace {
+            file: Mutex::new(BufWriter::new(file)),
+        };
+        let mut wbuf = WBuf::new(64, false);
+        wbuf.write_zint(TRACE_HEADER);
+        wbuf.write_peerid(pid);
+        wbuf.write_zint(whatami);
+        trace.write(wbuf);
+        Ok(trace)
+    }
+
+    pub(crate) fn open_face(&self, fid: usize, pid: &PeerId, whatami: WhatAmI) {
+        let mut wbuf = WBuf::new(64, false);
+        wbuf.write_zint(TRACE_OPEN_FACE);
+        wbuf.write_zint(fid as ZInt);
+        wbuf.write_peerid(pid);
+        wbuf.write_zint(whatami);
+        self.write(wbuf);
+    }
+
+    pub(crate) fn close_face(&self, fid: usize) {
+        let mut wbuf = WBuf::new(16, false);
+        wbuf.write_zint(TRACE_CLOSE_FACE);
+        wbuf.write_zint(fid as ZInt);
+        self.write(wbuf);
+    }
+
+    pub(crate) fn link_states(&self, net_type: WhatAmI, src: &PeerId, link_states: &[LinkState]) {
+        let mut wbuf = WBuf::new(256, false);
+        wbuf.write_zint(TRACE_LINK_STATES);
+        wbuf.write_zint(net_type);
+        wbuf.write_peerid(src);
+        wbuf.write_zenoh_message(&ZenohMessage::make_link_state_list(
+            link_states.to_vec(),
+            None,
+        ));
+        self.write(wbuf);
+    }
+
+    pub(crate) fn remove_link(&self, net_type: WhatAmI, pid: &PeerId) {
+        let mut wbuf = WBuf::new(64, false);
+        wbuf.write_zint(TRACE_REMOVE_LINK);
+        wbuf.write_zint(net_type);
+        wbuf.write_peerid(pid);
+        self.write(wbuf);
+    }
+
+    pub(crate) fn compute_trees(&self, net_type: WhatAmI) {
+        let mut wbuf = WBuf::new(16, false);
+        wbuf.write_zint(TRACE_COMPUTE_TREES);
+        wbuf.write_zint(net_type);
+        self.write(wbuf);
+    }
+
+    /// The recorder of the messages received by the face `fid`.
+    pub(crate) fn face(&self, fid: usize) -> FaceTrace<'_> {
+        FaceTrace { trace: self, fid }
+    }
+
+    fn message(&self, fid: usize, msg: &ZenohMessage) {
+        let mut wbuf = WBuf::new(256, false);
+        wbuf.write_zint(TRACE_MESSAGE);
+        wbuf.write_zint(fid as ZInt);
+        wbuf.write(msg.is_reliable() as u8);
+        wbuf.write_zenoh_message(msg);
+        self.write(wbuf);
+    }
+
+    // Each record is written as its length (u32 little endian) followed by its bytes,
+    // and flushed so that the trace is complete even if the process crashes.
+    fn write(&self, wbuf: WBuf) {
+        let bytes = ZBuf::from(wbuf).to_vec();
+        if bytes.len() > TRACE_MAX_RECORD_SIZE {
+            log::warn!(
+                "Failed to write routing trace: record of {} bytes exceeds {} bytes",
+                bytes.len(),
+                TRACE_MAX_RECORD_SIZE
+            );
+            return;
+        }
+        let mut file = zlock!(self.file);
+        if let Err(e) = file
+            .write_all(&(bytes.len() as u32).to_le_bytes())
+            .and_then(|_| file.write_all(&bytes))
+            .and_then(|_| file.flush())
+        {
+            log::warn!("Failed to write routing trace: {}", e);
+        }
+    }
+}
+
+/// Records the primitives called on a face as the messages that would carry them,
+/// like the [`Mux`](super::protocol::session::Mux) of a session would send them.
+pub(crate) struct FaceTrace<'a> {
+    trace: &'a RoutingTrace,
+    fid: usize,
+}
+
+impl FaceTrace<'_> {
+    fn declare(&self, declaration: Declaration, routing_context: Option<RoutingContext>) {
+        let msg = ZenohMessage::make_declare(vec![declaration], routing_context, None);
+        self.trace.message(self.fid, &msg);
+    }
+}
+
+impl Primitives for FaceTrace<'_> {
+    fn decl_resource(&self, rid: ZInt, reskey: &ResKey) {
+        let key = reskey.clone();
+        self.declare(Declaration::Resource(Resource { rid, key }), None);
+    }
+
+    fn forget_resource(&self, rid: ZInt) {
+        self.declare(Declaration::ForgetResource(ForgetResource { rid }), None);
+    }
+
+    fn decl_subscriber(
+        &self,
+        reskey: &ResKey,
+        sub_info: &SubInfo,
+        routing_context: Option<RoutingContext>,
+    ) {
+        let d = Declaration::Subscriber(Subscriber {
+            key: reskey.clone(),
+            info: sub_info.clone(),
+        });
+        self.declare(d, routing_context);
+    }
+
+    fn forget_subscriber(&self, reskey: &ResKey, routing_context: Option<RoutingContext>) {
+        let key = reskey.clone();
+        self.declare(
+            Declaration::ForgetSubscriber(ForgetSubscriber { key }),
+            routing_context,
+        );
+    }
+
+    fn decl_publisher(&self, _reskey: &ResKey, _routing_context: Option<RoutingContext>) {}
+
+    fn forget_publisher(&self, _reskey: &ResKey, _routing_context: Option<RoutingContext>) {}
+
+    fn decl_queryable(&self, reskey: &ResKey, kind: ZInt, routing_context: Option<RoutingContext>) {
+        let key = reskey.clone();
+        self.declare(
+            Declaration::Queryable(Queryable { key, kind }),
+            routing_context,
+        );
+    }
+
+    fn forget_queryable(&self, reskey: &ResKey, routing_context: Option<RoutingContext>) {
+        let key = reskey.clone();
+        self.declare(
+            Declaration::ForgetQueryable(ForgetQueryable { key }),
+            routing_context,
+        );
+    }
+
+    fn send_data(
+        &self,
+        reskey: &ResKey,
+        payload: ZBuf,
+        reliability: Reliability,
+        congestion_control: CongestionControl,
+        data_info: Option<DataInfo>,
+        routing_context: Option<RoutingContext>,
+    ) {
+        let msg = ZenohMessage::make_data(
+            reskey.clone(),
+            payload,
+            reliability,
+            congestion_control,
+            data_info,
+            routing_context,
+            None,
+            None,
+        );
+        self.trace.message(self.fid, &msg);
+    }
+
+    fn send_query(
+        &self,
+        reskey: &ResKey,
+        predicate: &str,
+        qid: ZInt,
+        target: QueryTarget,
+        consolidation: QueryConsolidation,
+        page: Option<QueryPage>,
+        routing_context: Option<RoutingContext>,
+    ) {
+        let msg = ZenohMessage::make_query(
+            reskey.clone(),
+            predicate.to_string(),
+            qid,
+            Some(target),
+            consolidation,
+            page,
+            routing_context,
+            None,
+        );
+        self.trace.message(self.fid, &msg);
+    }
+
+    fn send_reply_data(
+        &self,
+        qid: ZInt,
+        replier_kind: ZInt,
+        replier_id: PeerId,
+        reskey: ResKey,
+        data_info: Option<DataInfo>,
+        payload: ZBuf,
+    ) {
+        let msg = ZenohMessage::make_data(
+            reskey,
+            payload,
+            zmsg::default_reliability::REPLY,
+            zmsg::default_congestion_control::REPLY,
+            data_info,
+            None,
+            Some(ReplyContext::make(
+                qid,
+                Some(ReplierInfo {
+                    kind: replier_kind,
+                    id: replier_id,
+                }),
+            )),
+            None,
+        );
+        self.trace.message(self.fid, &msg);
+    }
+
+    fn send_reply_final(&self, qid: ZInt) {
+        let msg = ZenohMessage::make_unit(
+            zmsg::default_reliability::REPLY,
+            zmsg::default_congestion_control::REPLY,
+            Some(ReplyContext::make(qid, None)),
+            None,
+        );
+        self.trace.message(self.fid, &msg);
+    }
+
+    fn send_pull(
+        &self,
+        is_final: bool,
+        reskey: &ResKey,
+        pull_id: ZInt,
+        max_samples: &Option<ZInt>,
+    ) {
+        let msg = ZenohMessage::make_pull(is_final, reskey.clone(), pull_id, *max_samples, None);
+        self.trace.message(self.fid, &msg);
+    }
+
+    fn send_close(&self) {}
+}
+
+fn read_record<R: Read>(trace: &mut R) -> ZResult<Option<ZBuf>> {
+    let mut len = [0u8; 4];
+    match trace.read_exact(&mut len) {
+        Ok(()) => (),
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => {
+            return zerror!(ZErrorKind::IoError {
+                descr: format!("Unable to read routing trace: {}", e)
+            })
+        }
+    }
+    let len = u32::from_le_bytes(len) as usize;
+    if len > TRACE_MAX_RECORD_SIZE {
+        return Err(invalid_record(&format!(
+            "size {} exceeds {} bytes",
+            len, TRACE_MAX_RECORD_SIZE
+        )));
+    }
+    let mut bytes = vec![0u8; len];
+    trace.read_exact(&mut bytes).map_err(|e| {
+        zerror2!(ZErrorKind::IoError {
+            descr: format!("Truncated routing trace: {}", e)
+        })
+    })?;
+    Ok(Some(ZBuf::from(bytes)))
+}
+
+fn invalid_record(descr: &str) -> ZError {
+    zerror2!(ZErrorKind::InvalidMessage {
+        descr: format!("Invalid routing trace record: {}", descr)
+    })
+}
+
+/// Something sent by the replayed routing core to one of its faces.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayOutput {
+    /// The identifier of the face in the trace.
+    pub face: usize,
+    /// The primitive called on the face, with its arguments.
+    pub event: String,
+}
+
+impl fmt::Display for ReplayOutput {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Face{{{}}} <- {}", self.face, self.event)
+    }
+}
+
+struct ReplayPrimitives {
+    face: usize,
+    outputs: Arc<Mutex<Vec<ReplayOutput>>>,
+}
+
+impl ReplayPrimitives {
+    fn push(&self, event: String) {
+        zlock!(self.outputs).push(ReplayOutput {
+            face: self.face,
+            event,
+        });
+    }
+}
+
+impl Primitives for ReplayPrimitives {
+    fn decl_resource(&self, rid: ZInt, reskey: &ResKey) {
+        self.push(format!("decl_resource {} {}", rid, reskey));
+    }
+
+    fn forget_resource(&self, rid: ZInt) {
+        self.push(format!("forget_resource {}", rid));
+    }
+
+    fn decl_publisher(&self, reskey: &ResKey, routing_context: Option<RoutingContext>) {
+        self.push(format!("decl_publisher {} {:?}", reskey, routing_context));
+    }
+
+    fn forget_publisher(&self, reskey: &ResKey, routing_context: Option<RoutingContext>) {
+        self.push(format!("forget_publisher {} {:?}", reskey, routing_context));
+    }
+
+    fn decl_subscriber(
+        &self,
+        reskey: &ResKey,
+        sub_info: &SubInfo,
+        routing_context: Option<RoutingContext>,
+    ) {
+        self.push(format!(
+            "decl_subscriber {} {:?} {:?}",
+            reskey, sub_info, routing_context
+        ));
+    }
+
+    fn forget_subscriber(&self, reskey: &ResKey, routing_context: Option<RoutingContext>) {
+        self.push(format!(
+            "forget_subscriber {} {:?}",
+            reskey, routing_context
+        ));
+    }
+
+    fn decl_queryable(&self, reskey: &ResKey, kind: ZInt, routing_context: Option<RoutingContext>) {
+        self.push(format!(
+            "decl_queryable {} {} {:?}",
+            reskey, kind, routing_context
+        ));
+    }
+
+    fn forget_queryable(&self, reskey: &ResKey, routing_context: Option<RoutingContext>) {
+        self.push(format!("forget_queryable {} {:?}", reskey, routing_context));
+    }
+
+    fn send_data(
+        &self,
+        reskey: &ResKey,
+        payload: ZBuf,
+        reliability: Reliability,
+        congestion_control: CongestionControl,
+        data_info: Option<DataInfo>,
+        routing_context: Option<RoutingContext>,
+    ) {
+        self.push(format!(
+            "send_data {} {:?} {:?} {:?} {:?} {:?}",
+            reskey,
+            payload.to_vec(),
+            reliability,
+            congestion_control,
+            data_info,
+            routing_context
+        ));
+    }
+
+    fn send_query(
+        &self,
+        reskey: &ResKey,
+        predicate: &str,
+        qid: ZInt,
+        target: QueryTarget,
+        consolidation: QueryConsolidation,
+        page: Option<QueryPage>,
+        routing_context: Option<RoutingContext>,
+    ) {
+        self.push(format!(
+            "send_query {} {:?} {} {:?} {:?} {:?} {:?}",
+            reskey, predicate, qid, target, consolidation, page, routing_context
+        ));
+    }
+
+    fn send_reply_data(
+        &self,
+        qid: ZInt,
+        replier_kind: ZInt,
+        replier_id: PeerId,
+        reskey: ResKey,
+        data_info: Option<DataInfo>,
+        payload: ZBuf,
+    ) {
+        self.push(format!(
+            "send_reply_data {} {} {} {} {:?} {:?}",
+            qid,
+            replier_kind,
+            replier_id,
+            reskey,
+            data_info,
+            payload.to_vec()
+        ));
+    }
+
+    fn send_reply_final(&self, qid: ZInt) {
+        self.push(format!("send_reply_final {}", qid));
+    }
+
+    fn send_pull(
+        &self,
+        is_final: bool,
+        reskey: &ResKey,
+        pull_id: ZInt,
+        max_samples: &Option<ZInt>,
+    ) {
+        self.push(format!(
+            "send_pull {} {} {} {:?}",
+            is_final, reskey, pull_id, max_samples
+        ));
+    }
+
+    fn send_close(&self) {
+        self.push("send_close".to_string());
+    }
+}
+
+impl LinkStateSender for ReplayPrimitives {
+    fn send_link_states(&self, msg: ZenohMessage) -> ZResult<()> {
+        if let ZenohBody::LinkStateList(list) = msg.body {
+            self.push(format!("send_link_states {:?}", list.link_states));
+        }
+        Ok(())
+    }
+}
+
+/// Replays a routing trace recorded with the `routing_trace` configuration property,
+/// and returns, in order, what the routing core sent to its faces.
+///
+/// The routing core processes the events of the trace one by one, on the calling thread.
+pub fn replay<R: Read>(mut trace: R) -> ZResult<Vec<ReplayOutput>> {
+    let mut header = read_record(&mut trace)?.ok_or_else(|| invalid_record("empty trace"))?;
+    if header.read_zint() != Some(TRACE_HEADER) {
+        return Err(invalid_record("missing header"));
+    }
+    let pid = header
+        .read_peerid()
+        .ok_or_else(|| invalid_record("header"))?;
+    let whatami = header.read_zint().ok_or_else(|| invalid_record("header"))?;
+    log::debug!(
+        "Replay routing trace of {} {}",
+        whatami::to_string(whatami),
+        pid
+    );
+
+    let mut tables = Tables::new(pid.clone(), whatami, None);
+    if whatami != whatami::CLIENT {
+        tables.peers_net = Some(Network::new(
+            "[Peers network]".to_string(),
+            pid.clone(),
+            whatami,
+            None,
+            false,
+            false,
+        ));
+    }
+    if whatami == whatami::ROUTER {
+        tables.routers_net = Some(Network::new(
+            "[Routers network]".to_string(),
+            pid,
+            whatami,
+            None,
+            false,
+            false,
+        ));
+        tables.shared_nodes = shared_nodes(
+            tables.routers_net.as_ref().unwrap(),
+            tables.peers_net.as_ref().unwrap(),
+        );
+    }
+    let tables = Arc::new(RwLock::new(tables));
+
+    let outputs = Arc::new(Mutex::new(vec![]));
+    let mut faces: HashMap<usize, DeMux<Face>> = HashMap::new();
+    while let Some(mut record) = read_record(&mut trace)? {
+        match record.read_zint().ok_or_else(|| invalid_record("kind"))? {
+            TRACE_OPEN_FACE => {
+                let fid = record.read_zint().ok_or_else(|| invalid_record("face"))? as usize;
+                let pid = record.read_peerid().ok_or_else(|| invalid_record("face"))?;
+                let face_whatami = record.read_zint().ok_or_else(|| invalid_record("face"))?;
+                let primitives = Arc::new(ReplayPrimitives {
+                    face: fid,
+                    outputs: outputs.clone(),
+                });
+                let state = {
+                    let mut tables = zwrite!(tables);
+                    // like a new session, the link with a router or a peer is added before its face
+                    let link_id = match tables.net_type(face_whatami) {
+                        Some(net_type) => {
+                            tables.add_link(net_type, pid.clone(), face_whatami, primitives.clone())
+                        }
+                        None => 0,
+                    };
+                    tables
+                        .open_net_face(pid, face_whatami, primitives, link_id, None)
+                        .upgrade()
+                        .unwrap()
+                };
+                let face = Face {
+                    tables: tables.clone(),
+                    state,
+                };
+                faces.insert(fid, DeMux::new(face));
+            }
+            TRACE_CLOSE_FACE => {
+                let fid = record.read_zint().ok_or_else(|| invalid_record("face"))? as usize;
+                if let Some(demux) = faces.remove(&fid) {
+                    demux.closing();
+                }
+            }
+            TRACE_MESSAGE => {
+                let fid = record.read_zint().ok_or_else(|| invalid_record("face"))? as usize;
+                let reliability = match record.read() {
+                    Some(0) => Reliability::BestEffort,
+                    Some(_) => Reliability::Reliable,
+                    None => return Err(invalid_record("message")),
+                };
+                let msg = record
+                    .read_zenoh_message(reliability)
+                    .ok_or_else(|| invalid_record("message"))?;
+                if let Some(demux) = faces.get(&fid) {
+                    log::trace!("Replay {:?} from Face{{{}}}", msg, fid);
+                    demux.handle_message(msg)?;
+                }
+            }
+            TRACE_LINK_STATES => {
+                let net_type = record.read_zint().ok_or_else(|| invalid_record("link"))?;
+                let src = record.read_peerid().ok_or_else(|| invalid_record("link"))?;
+                let msg = record
+                    .read_zenoh_message(Reliability::Reliable)
+                    .ok_or_else(|| invalid_record("link states"))?;
+                match msg.body {
+                    ZenohBody::LinkStateList(list) => {
+                        log::trace!("Replay link states from {}", src);
+                        zwrite!(tables).link_states(net_type, list.link_states, src);
+                    }
+                    _ => return Err(invalid_record("link states")),
+                }
+            }
+            TRACE_REMOVE_LINK => {
+                let net_type = record.read_zint().ok_or_else(|| invalid_record("link"))?;
+                let pid = record.read_peerid().ok_or_else(|| invalid_record("link"))?;
+                log::trace!("Replay removal of the link with {}", pid);
+                zwrite!(tables).remove_link(net_type, &pid);
+            }
+            TRACE_COMPUTE_TREES => {
+                let net_type = record.read_zint().ok_or_else(|| invalid_record("trees"))?;
+                log::trace!("Replay trees computation");
+                zwrite!(tables).compute_trees(net_type);
+            }
+            kind => return Err(invalid_record(&format!("unknown kind {}", kind))),
+        }
+    }
+
+    let outputs = zlock!(outputs).drain(..).collect();
+    Ok(outputs)
+}
@@ -13,7 +13,7 @@
 //
 use super::face::FaceState;
 use super::protocol::core::rname;
-use super::protocol::core::{PeerId, ResKey, SubInfo, ZInt};
+use super::protocol::core::{PayloadFilter, PeerId, ResKey, SubInfo, ZInt};
 use super::protocol::io::ZBuf;
 use super::protocol::proto::{DataInfo, RoutingContext};
 use super::router::Tables;
@@ -25,6 +25,7 @@ use zenoh_util::sync::get_mut_unchecked;
 
 pub(super) type Route = HashMap<usize, (Arc<FaceState>, ResKey, Option<RoutingContext>)>;
 pub(super) type PullCaches = Vec<Arc<SessionContext>>;
+pub(super) type SubFilters = HashMap<usize, Vec<PayloadFilter>>;
 
 pub(super) struct SessionContext {
     pub(super) face: Arc<FaceState>,
@@ -42,12 +43,16 @@ pub(super) struct ResourceContext {
     pub(super) peer_qabls: HashMap<PeerId, ZInt>,
     pub(super) matches: Vec<Weak<Resource>>,
     pub(super) matching_pulls: Arc<PullCaches>,
+    pub(super) sub_filters: Arc<SubFilters>,
     pub(super) routers_data_routes: Vec<Arc<Route>>,
     pub(super) peers_data_routes: Vec<Arc<Route>>,
     pub(super) client_data_route: Option<Arc<Route>>,
     pub(super) routers_query_routes: Vec<Arc<Route>>,
     pub(super) peers_query_routes: Vec<Arc<Route>>,
     pub(super) client_query_route: Option<Arc<Route>>,
+    pub(super) routers_complete_query_routes: Vec<Arc<Route>>,
+    pub(super) peers_complete_query_routes: Vec<Arc<Route>>,
+    pub(super) client_complete_query_route: Option<Arc<Route>>,
 }
 
 impl ResourceContext {
@@ -59,12 +64,16 @@ impl ResourceContext {
             peer_qabls: HashMap::new(),
             matches: Vec::new(),
             matching_pulls: Arc::new(Vec::new()),
+            sub_filters: Arc::new(HashMap::new()),
             routers_data_routes: Vec::new(),
             peers_data_routes: Vec::new(),
             client_data_route: None,
             routers_query_routes: Vec::new(),
             peers_query_routes: Vec::new(),
             client_query_route: None,
+            routers_complete_query_routes: Vec::new(),
+            peers_complete_query_routes: Vec::new(),
+            client_complete_query_route: None,
         }
     }
 }
@@ -196,6 +205,32 @@ impl Resource {
         }
     }
 
+    #[inline(always)]
+    pub fn routers_complete_query_route(&self, context: usize) -> Option<Arc<Route>> {
+        match &self.context {
+            Some(ctx) => (ctx.routers_complete_query_routes.len() > context)
+                .then(|| ctx.routers_complete_query_routes[context].clone()),
+            None => None,
+        }
+    }
+
+    #[inline(always)]
+    pub fn peers_complete_query_route(&self, context: usize) -> Option<Arc<Route>> {
+        match &self.context {
+            Some(ctx) => (ctx.peers_complete_query_routes.len() > context)
+                .then(|| ctx.peers_complete_query_routes[context].clone()),
+            None => None,
+        }
+    }
+
+    #[inline(always)]
+    pub fn client_complete_query_route(&self) -> Option<Arc<Route>> {
+        match &self.context {
+            Some(ctx) => ctx.client_complete_query_route.clone(),
+            None => None,
+        }
+    }
+
     pub fn root() -> Arc<Resource> {
         Arc::new(Resource {
             parent: None,
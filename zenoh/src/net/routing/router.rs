@@ -20,16 +20,21 @@ use zenoh_util::sync::get_mut_unchecked;
 
 use super::protocol::core::{whatami, PeerId, WhatAmI, ZInt};
 use super::protocol::link::Link;
-use super::protocol::proto::{ZenohBody, ZenohMessage};
+use super::protocol::proto::{LinkState, ZenohBody, ZenohMessage};
 use super::protocol::session::{DeMux, Mux, Primitives, Session, SessionEventHandler};
 
 use zenoh_util::core::ZResult;
 use zenoh_util::zconfigurable;
 
 use super::face::{Face, FaceState};
-use super::network::{shared_nodes, Network};
+use super::latest_value_cache::LatestValueCache;
+use super::network::{shared_nodes, LinkStateSender, Network};
+use super::publication_buffer::PublicationBuffer;
 pub use super::pubsub::*;
 pub use super::queries::*;
+use super::quotas::{Quotas, SubjectQuotas};
+#[cfg(feature = "routing_replay")]
+use super::replay::RoutingTrace;
 pub use super::resource::*;
 use super::runtime::Runtime;
 
@@ -42,6 +47,8 @@ pub struct Tables {
     pub(crate) pid: PeerId,
     pub(crate) whatami: whatami::Type,
     face_counter: usize,
+    // Rotates the selection of the faces among the equally loaded ones for the BestMatchingN queries
+    pub(crate) query_counter: usize,
     #[allow(dead_code)]
     pub(crate) hlc: Option<Arc<HLC>>,
     pub(crate) root_res: Arc<Resource>,
@@ -56,6 +63,11 @@ pub struct Tables {
     pub(crate) shared_nodes: Vec<PeerId>,
     pub(crate) routers_trees_task: Option<JoinHandle<()>>,
     pub(crate) peers_trees_task: Option<JoinHandle<()>>,
+    pub(crate) latest_value_cache: Option<Arc<LatestValueCache>>,
+    pub(crate) publication_buffer: Option<PublicationBuffer>,
+    pub(crate) quotas: Option<Arc<Quotas>>,
+    #[cfg(feature = "routing_replay")]
+    pub(crate) trace: Option<Arc<RoutingTrace>>,
 }
 
 impl Tables {
@@ -64,6 +76,7 @@ impl Tables {
             pid,
             whatami,
             face_counter: 0,
+            query_counter: 0,
             hlc,
             root_res: Resource::root(),
             faces: HashMap::new(),
@@ -77,6 +90,11 @@ impl Tables {
             shared_nodes: vec![],
             routers_trees_task: None,
             peers_trees_task: None,
+            latest_value_cache: None,
+            publication_buffer: None,
+            quotas: None,
+            #[cfg(feature = "routing_replay")]
+            trace: None,
         }
     }
 
@@ -116,25 +134,45 @@ impl Tables {
         self.faces.values().find(|face| face.pid == *pid)
     }
 
-    fn open_net_face(
+    pub(crate) fn open_net_face(
         &mut self,
         pid: PeerId,
         whatami: WhatAmI,
         primitives: Arc<dyn Primitives + Send + Sync>,
         link_id: usize,
+        quotas: Option<SubjectQuotas>,
     ) -> Weak<FaceState> {
         let fid = self.face_counter;
         self.face_counter += 1;
         let mut newface = self
             .faces
             .entry(fid)
-            .or_insert_with(|| FaceState::new(fid, pid, whatami, primitives.clone(), link_id))
+            .or_insert_with(|| {
+                FaceState::new(
+                    fid,
+                    pid.clone(),
+                    whatami,
+                    primitives.clone(),
+                    link_id,
+                    quotas,
+                )
+            })
             .clone();
         log::debug!("New {}", newface);
+        #[cfg(feature = "routing_replay")]
+        if let Some(trace) = &self.trace {
+            trace.open_face(fid, &pid, whatami);
+        }
 
         if whatami == whatami::CLIENT {
             pubsub_new_face(self, &mut newface);
             queries_new_face(self, &mut newface);
+        } else if self.whatami == whatami::CLIENT && self.publication_buffer.is_some() {
+            // a client in reconnect mode re-declares its entities to the router it
+            // (re)connects to, then sends it the publications made while disconnected
+            pubsub_new_face(self, &mut newface);
+            queries_new_face(self, &mut newface);
+            self.publication_buffer.as_ref().unwrap().flush(&newface);
         }
         Arc::downgrade(&newface)
     }
@@ -145,13 +183,32 @@ impl Tables {
         whatami: WhatAmI,
         primitives: Arc<dyn Primitives + Send + Sync>,
     ) -> Weak<FaceState> {
-        self.open_net_face(pid, whatami, primitives, 0)
+        self.open_net_face(pid, whatami, primitives, 0, None)
+    }
+
+    /// Returns the entities declared by the clients, the local sessions included,
+    /// ordered by face.
+    pub(crate) fn client_entities(&self) -> Vec<serde_json::Value> {
+        let mut faces = self
+            .faces
+            .values()
+            .filter(|face| face.whatami == whatami::CLIENT)
+            .collect::<Vec<_>>();
+        faces.sort_by_key(|face| face.id);
+        faces.into_iter().map(|face| face.entities()).collect()
     }
 
     pub fn close_face(&mut self, face: &Weak<FaceState>) {
         match face.upgrade() {
             Some(mut face) => {
                 log::debug!("Close {}", face);
+                if let Some(quotas) = &face.quotas {
+                    quotas.close(face.id);
+                }
+                #[cfg(feature = "routing_replay")]
+                if let Some(trace) = &self.trace {
+                    trace.close_face(face.id);
+                }
                 finalize_pending_queries(self, &mut face);
 
                 let mut face_clone = face.clone();
@@ -171,6 +228,9 @@ impl Tables {
                     undeclare_client_subscription(self, &mut face_clone, &mut res);
                     Resource::clean(&mut res);
                 }
+                for mut res in face.remote_pubs.drain() {
+                    Resource::clean(&mut res);
+                }
                 for mut res in face.remote_qabls.drain() {
                     get_mut_unchecked(&mut res).session_ctxs.remove(&face.id);
                     undeclare_client_queryable(self, &mut face_clone, &mut res);
@@ -201,6 +261,93 @@ impl Tables {
         }
     }
 
+    // Returns the type of the network of the links with the `whatami` nodes, if any.
+    pub(crate) fn net_type(&self, whatami: WhatAmI) -> Option<WhatAmI> {
+        match (self.whatami, whatami) {
+            (whatami::ROUTER, whatami::ROUTER) => Some(whatami::ROUTER),
+            (whatami::ROUTER, whatami::PEER)
+            | (whatami::PEER, whatami::ROUTER)
+            | (whatami::PEER, whatami::PEER) => Some(whatami::PEER),
+            _ => None,
+        }
+    }
+
+    fn update_shared_nodes(&mut self) {
+        if self.whatami == whatami::ROUTER {
+            self.shared_nodes = shared_nodes(
+                self.routers_net.as_ref().unwrap(),
+                self.peers_net.as_ref().unwrap(),
+            );
+        }
+    }
+
+    fn net_mut(&mut self, net_type: WhatAmI) -> &mut Network {
+        match net_type {
+            whatami::ROUTER => self.routers_net.as_mut().unwrap(),
+            _ => self.peers_net.as_mut().unwrap(),
+        }
+    }
+
+    // Adds a link with the `whatami` node `pid` in the `net_type` network, returning its id.
+    pub(crate) fn add_link(
+        &mut self,
+        net_type: WhatAmI,
+        pid: PeerId,
+        whatami: WhatAmI,
+        sender: Arc<dyn LinkStateSender>,
+    ) -> usize {
+        let link_id = self.net_mut(net_type).add_link(pid, whatami, sender);
+        self.update_shared_nodes();
+        link_id
+    }
+
+    // Applies the link states received from the node `src` in the `net_type` network.
+    pub(crate) fn link_states(
+        &mut self,
+        net_type: WhatAmI,
+        link_states: Vec<LinkState>,
+        src: PeerId,
+    ) {
+        #[cfg(feature = "routing_replay")]
+        if let Some(trace) = &self.trace {
+            trace.link_states(net_type, &src, &link_states);
+        }
+        for (_, removed_node) in self.net_mut(net_type).link_states(link_states, src) {
+            pubsub_remove_node(self, &removed_node.pid, net_type);
+            queries_remove_node(self, &removed_node.pid, net_type);
+        }
+        self.update_shared_nodes();
+    }
+
+    // Removes the link with the node `pid` from the `net_type` network.
+    pub(crate) fn remove_link(&mut self, net_type: WhatAmI, pid: &PeerId) {
+        #[cfg(feature = "routing_replay")]
+        if let Some(trace) = &self.trace {
+            trace.remove_link(net_type, pid);
+        }
+        for (_, removed_node) in self.net_mut(net_type).remove_link(pid) {
+            pubsub_remove_node(self, &removed_node.pid, net_type);
+            queries_remove_node(self, &removed_node.pid, net_type);
+        }
+        self.update_shared_nodes();
+    }
+
+    // Computes the trees of the `net_type` network and the routes going through them.
+    pub(crate) fn compute_trees(&mut self, net_type: WhatAmI) {
+        #[cfg(feature = "routing_replay")]
+        if let Some(trace) = &self.trace {
+            trace.compute_trees(net_type);
+        }
+        log::trace!("Compute trees");
+        let new_childs = self.net_mut(net_type).compute_trees();
+
+        log::trace!("Compute routes");
+        pubsub_tree_change(self, &new_childs, net_type);
+        queries_tree_change(self, &new_childs, net_type);
+
+        log::trace!("Computations completed");
+    }
+
     pub(crate) fn schedule_compute_trees(
         &mut self,
         tables_ref: Arc<RwLock<Tables>>,
@@ -214,18 +361,7 @@ impl Tables {
                 async_std::task::sleep(std::time::Duration::from_millis(*TREES_COMPUTATION_DELAY))
                     .await;
                 let mut tables = zwrite!(tables_ref);
-
-                log::trace!("Compute trees");
-                let new_childs = match net_type {
-                    whatami::ROUTER => tables.routers_net.as_mut().unwrap().compute_trees(),
-                    _ => tables.peers_net.as_mut().unwrap().compute_trees(),
-                };
-
-                log::trace!("Compute routes");
-                pubsub_tree_change(&mut tables, &new_childs, net_type);
-                queries_tree_change(&mut tables, &new_childs, net_type);
-
-                log::trace!("Computations completed");
+                tables.compute_trees(net_type);
                 match net_type {
                     whatami::ROUTER => tables.routers_trees_task = None,
                     _ => tables.peers_trees_task = None,
@@ -262,7 +398,8 @@ impl Router {
         tables.peers_net = Some(Network::new(
             "[Peers network]".to_string(),
             tables.pid.clone(),
-            runtime.clone(),
+            runtime.whatami,
+            Some(runtime.clone()),
             peers_autoconnect,
             routers_autoconnect_gossip,
         ));
@@ -270,7 +407,8 @@ impl Router {
             tables.routers_net = Some(Network::new(
                 "[Routers network]".to_string(),
                 tables.pid.clone(),
-                runtime,
+                runtime.whatami,
+                Some(runtime),
                 peers_autoconnect,
                 routers_autoconnect_gossip,
             ));
@@ -281,6 +419,17 @@ impl Router {
         }
     }
 
+    /// Advertises the current locators of this node on the routers and peers networks.
+    pub fn refresh_locators(&self) {
+        let mut tables = zwrite!(self.tables);
+        if let Some(net) = tables.routers_net.as_mut() {
+            net.refresh_locators();
+        }
+        if let Some(net) = tables.peers_net.as_mut() {
+            net.refresh_locators();
+        }
+    }
+
     pub fn new_primitives(&self, primitives: Arc<dyn Primitives + Send + Sync>) -> Arc<Face> {
         Arc::new(Face {
             tables: self.tables.clone(),
@@ -299,26 +448,28 @@ impl Router {
         let mut tables = zwrite!(self.tables);
         let whatami = session.get_whatami()?;
 
-        let link_id = match (self.whatami, whatami) {
-            (whatami::ROUTER, whatami::ROUTER) => tables
-                .routers_net
-                .as_mut()
-                .unwrap()
-                .add_link(session.clone()),
-            (whatami::ROUTER, whatami::PEER)
-            | (whatami::PEER, whatami::ROUTER)
-            | (whatami::PEER, whatami::PEER) => {
-                tables.peers_net.as_mut().unwrap().add_link(session.clone())
-            }
-            _ => 0,
+        let net_type = tables.net_type(whatami);
+        let link_id = match net_type {
+            Some(net_type) => tables.add_link(
+                net_type,
+                session.get_pid()?,
+                whatami,
+                Arc::new(session.clone()),
+            ),
+            None => 0,
         };
 
-        if tables.whatami == whatami::ROUTER {
-            tables.shared_nodes = shared_nodes(
-                tables.routers_net.as_ref().unwrap(),
-                tables.peers_net.as_ref().unwrap(),
-            );
-        }
+        // the sessions with other routers are not limited
+        let quotas = match &tables.quotas {
+            Some(quotas) if whatami != whatami::ROUTER => {
+                let subject = match session.get_parameters()?.auth_subject {
+                    Some(subject) => subject,
+                    None => session.get_pid()?.to_string(),
+                };
+                Some(quotas.subject(subject, session.clone()))
+            }
+            _ => None,
+        };
 
         let handler = Arc::new(LinkStateInterceptor::new(
             session.clone(),
@@ -331,22 +482,15 @@ impl Router {
                         whatami,
                         Arc::new(Mux::new(session)),
                         link_id,
+                        quotas,
                     )
                     .upgrade()
                     .unwrap(),
             },
         ));
 
-        match (self.whatami, whatami) {
-            (whatami::ROUTER, whatami::ROUTER) => {
-                tables.schedule_compute_trees(self.tables.clone(), whatami::ROUTER);
-            }
-            (whatami::ROUTER, whatami::PEER)
-            | (whatami::PEER, whatami::ROUTER)
-            | (whatami::PEER, whatami::PEER) => {
-                tables.schedule_compute_trees(self.tables.clone(), whatami::PEER);
-            }
-            _ => (),
+        if let Some(net_type) = net_type {
+            tables.schedule_compute_trees(self.tables.clone(), net_type);
         }
         Ok(handler)
     }
@@ -376,50 +520,10 @@ impl LinkStateInterceptor {
                 let pid = self.session.get_pid().unwrap();
                 let mut tables = zwrite!(self.tables);
                 let whatami = self.session.get_whatami()?;
-                match (tables.whatami, whatami) {
-                    (whatami::ROUTER, whatami::ROUTER) => {
-                        for (_, removed_node) in tables
-                            .routers_net
-                            .as_mut()
-                            .unwrap()
-                            .link_states(list.link_states, pid)
-                        {
-                            pubsub_remove_node(&mut tables, &removed_node.pid, whatami::ROUTER);
-                            queries_remove_node(&mut tables, &removed_node.pid, whatami::ROUTER);
-                        }
-
-                        tables.shared_nodes = shared_nodes(
-                            tables.routers_net.as_ref().unwrap(),
-                            tables.peers_net.as_ref().unwrap(),
-                        );
-
-                        tables.schedule_compute_trees(self.tables.clone(), whatami::ROUTER);
-                    }
-                    (whatami::ROUTER, whatami::PEER)
-                    | (whatami::PEER, whatami::ROUTER)
-                    | (whatami::PEER, whatami::PEER) => {
-                        for (_, removed_node) in tables
-                            .peers_net
-                            .as_mut()
-                            .unwrap()
-                            .link_states(list.link_states, pid)
-                        {
-                            pubsub_remove_node(&mut tables, &removed_node.pid, whatami::PEER);
-                            queries_remove_node(&mut tables, &removed_node.pid, whatami::PEER);
-                        }
-
-                        if tables.whatami == whatami::ROUTER {
-                            tables.shared_nodes = shared_nodes(
-                                tables.routers_net.as_ref().unwrap(),
-                                tables.peers_net.as_ref().unwrap(),
-                            );
-                        }
-
-                        tables.schedule_compute_trees(self.tables.clone(), whatami::PEER);
-                    }
-                    _ => (),
-                };
-
+                if let Some(net_type) = tables.net_type(whatami) {
+                    tables.link_states(net_type, list.link_states, pid);
+                    tables.schedule_compute_trees(self.tables.clone(), net_type);
+                }
                 Ok(())
             }
             _ => self.demux.handle_message(msg),
@@ -439,43 +543,12 @@ impl LinkStateInterceptor {
             async_std::task::sleep(std::time::Duration::from_millis(*LINK_CLOSURE_DELAY)).await;
             let mut tables = zwrite!(tables_ref);
             match whatami {
-                Ok(whatami) => match (tables.whatami, whatami) {
-                    (whatami::ROUTER, whatami::ROUTER) => {
-                        for (_, removed_node) in
-                            tables.routers_net.as_mut().unwrap().remove_link(&pid)
-                        {
-                            pubsub_remove_node(&mut tables, &removed_node.pid, whatami::ROUTER);
-                            queries_remove_node(&mut tables, &removed_node.pid, whatami::ROUTER);
-                        }
-
-                        tables.shared_nodes = shared_nodes(
-                            tables.routers_net.as_ref().unwrap(),
-                            tables.peers_net.as_ref().unwrap(),
-                        );
-
-                        tables.schedule_compute_trees(tables_ref.clone(), whatami::ROUTER);
+                Ok(whatami) => {
+                    if let Some(net_type) = tables.net_type(whatami) {
+                        tables.remove_link(net_type, &pid);
+                        tables.schedule_compute_trees(tables_ref.clone(), net_type);
                     }
-                    (whatami::ROUTER, whatami::PEER)
-                    | (whatami::PEER, whatami::ROUTER)
-                    | (whatami::PEER, whatami::PEER) => {
-                        for (_, removed_node) in
-                            tables.peers_net.as_mut().unwrap().remove_link(&pid)
-                        {
-                            pubsub_remove_node(&mut tables, &removed_node.pid, whatami::PEER);
-                            queries_remove_node(&mut tables, &removed_node.pid, whatami::PEER);
-                        }
-
-                        if tables.whatami == whatami::ROUTER {
-                            tables.shared_nodes = shared_nodes(
-                                tables.routers_net.as_ref().unwrap(),
-                                tables.peers_net.as_ref().unwrap(),
-                            );
-                        }
-
-                        tables.schedule_compute_trees(tables_ref.clone(), whatami::PEER);
-                    }
-                    _ => (),
-                },
+                }
                 Err(_) => log::error!("Unable to get whatami closing session!"),
             };
         });
@@ -13,15 +13,16 @@
 use super::plugins::PluginsMgr;
 use super::protocol::{
     core::{
-        queryable::EVAL, rname, CongestionControl, PeerId, QueryConsolidation, QueryTarget,
-        Reliability, ResKey, SubInfo, ZInt,
+        queryable::EVAL, rname, whatami, CongestionControl, PeerId, QueryConsolidation, QueryPage,
+        QueryTarget, Reliability, ResKey, SubInfo, ZInt,
     },
     io::ZBuf,
     proto::{encoding, DataInfo, RoutingContext},
-    session::Primitives,
+    session::{Primitives, SessionStats},
 };
 use super::routing::face::Face;
 use super::Runtime;
+use super::{liveness, logger};
 use async_std::sync::Arc;
 use async_std::task;
 use futures::future;
@@ -30,12 +31,93 @@ use log::{error, trace};
 use serde_json::json;
 use std::collections::HashMap;
 use std::sync::Mutex;
+use std::time::{Duration, UNIX_EPOCH};
+use zenoh_util::properties::config::{
+    config_default, config_keys, is_secret, ConfigProperties, ConfigTranscoder,
+    ZN_ADMIN_LOG_STREAM_KEY, ZN_ADMIN_LOG_STREAM_STR, ZN_ADMIN_READ_DEFAULT, ZN_ADMIN_READ_KEY,
+    ZN_ADMIN_READ_STR, ZN_ADMIN_WRITE_DEFAULT, ZN_ADMIN_WRITE_KEY, ZN_ADMIN_WRITE_STR, ZN_FALSE,
+    ZN_MODE_KEY, ZN_TRUE,
+};
+use zenoh_util::properties::KeyTranscoder;
+use zenoh_util::zconfigurable;
+
+// Maximal timeout in seconds of a drain requested via the admin space (one day).
+const ADMIN_DRAIN_MAX_TIMEOUT: f64 = 86_400.0;
+
+zconfigurable! {
+    // Default timeout in seconds of a drain requested via the admin space.
+    static ref ADMIN_DRAIN_DEFAULT_TIMEOUT: f64 = 30.0;
+    // Timeout in seconds of the requests of the traffic counters of the remote peers.
+    static ref ADMIN_REMOTE_STATS_TIMEOUT: f64 = 1.0;
+    // The number of logged records waiting to be published before the next ones are dropped.
+    static ref ADMIN_LOG_STREAM_CAPACITY: usize = 1024;
+}
 
 pub struct AdminContext {
     runtime: Runtime,
-    plugins_mgr: PluginsMgr,
+    plugins_mgr: Arc<PluginsMgr>,
     pid_str: String,
     version: String,
+    read_permissions: AdminPermissions,
+    write_permissions: AdminPermissions,
+}
+
+/// The paths of the admin space of a router on which an operation is permitted, as configured
+/// by `admin_read` for the queries and by `admin_write` for the puts: all of them with `"true"`,
+/// none with `"false"`, or the ones matching a comma-separated list of path expressions
+/// relative to `/@/router/<pid>`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AdminPermissions {
+    // the absolute path expressions of the permitted paths
+    paths: Vec<String>,
+}
+
+impl AdminPermissions {
+    /// Returns the paths of the admin space rooted at `root_path` (`/@/router/<pid>`) answering
+    /// the queries, as configured by `admin_read`. The invalid path expressions are ignored.
+    pub fn reads(config: &ConfigProperties, root_path: &str) -> AdminPermissions {
+        AdminPermissions::parse(
+            ZN_ADMIN_READ_STR,
+            config.get_or(&ZN_ADMIN_READ_KEY, ZN_ADMIN_READ_DEFAULT),
+            root_path,
+        )
+    }
+
+    /// Returns the paths of the admin space rooted at `root_path` (`/@/router/<pid>`) accepting
+    /// the puts, as configured by `admin_write`. The invalid path expressions are ignored.
+    pub fn writes(config: &ConfigProperties, root_path: &str) -> AdminPermissions {
+        AdminPermissions::parse(
+            ZN_ADMIN_WRITE_STR,
+            config.get_or(&ZN_ADMIN_WRITE_KEY, ZN_ADMIN_WRITE_DEFAULT),
+            root_path,
+        )
+    }
+
+    fn parse(name: &str, value: &str, root_path: &str) -> AdminPermissions {
+        let paths = match value.trim().to_lowercase().as_str() {
+            ZN_TRUE => vec![format!("{}/**", root_path)],
+            ZN_FALSE => vec![],
+            _ => value
+                .split(',')
+                .map(str::trim)
+                .filter(|rule| !rule.is_empty())
+                .filter_map(|rule| {
+                    if rule.starts_with('/') {
+                        Some(format!("{}{}", root_path, rule))
+                    } else {
+                        log::warn!("Invalid path expression {} in {}: ignored", rule, name);
+                        None
+                    }
+                })
+                .collect(),
+        };
+        AdminPermissions { paths }
+    }
+
+    /// Returns true if the operation is permitted on `path`.
+    pub fn allows(&self, path: &str) -> bool {
+        self.paths.iter().any(|p| rname::include(p, path))
+    }
 }
 
 type Handler = Box<dyn Fn(&AdminContext) -> BoxFuture<'_, (ZBuf, ZInt)> + Send + Sync>;
@@ -49,7 +131,7 @@ pub struct AdminSpace {
 }
 
 impl AdminSpace {
-    pub async fn start(runtime: &Runtime, plugins_mgr: PluginsMgr, version: String) {
+    pub async fn start(runtime: &Runtime, plugins_mgr: Arc<PluginsMgr>, version: String) {
         let pid_str = runtime.get_pid_str();
         let root_path = format!("/@/router/{}", pid_str);
 
@@ -66,11 +148,47 @@ impl AdminSpace {
             [&root_path, "/linkstate/peers"].concat(),
             Arc::new(Box::new(|context| linkstate_peers_data(context).boxed())),
         );
+        handlers.insert(
+            [&root_path, "/health"].concat(),
+            Arc::new(Box::new(|context| health_data(context).boxed())),
+        );
+        handlers.insert(
+            [&root_path, "/liveness"].concat(),
+            Arc::new(Box::new(|context| liveness_data(context).boxed())),
+        );
+        handlers.insert(
+            [&root_path, "/entities"].concat(),
+            Arc::new(Box::new(|context| entities_data(context).boxed())),
+        );
+        handlers.insert(
+            [&root_path, "/stats"].concat(),
+            Arc::new(Box::new(|context| stats_data(context).boxed())),
+        );
+        handlers.insert(
+            [&root_path, "/logger"].concat(),
+            Arc::new(Box::new(|context| logger_data(context).boxed())),
+        );
+        handlers.insert(
+            [&root_path, "/config"].concat(),
+            Arc::new(Box::new(|context| config_data(context).boxed())),
+        );
+        for (key, name) in config_keys() {
+            handlers.insert(
+                [&root_path, "/config_meta/", &name].concat(),
+                Arc::new(Box::new(move |context| {
+                    config_meta_data(context, key).boxed()
+                })),
+            );
+        }
+        let read_permissions = AdminPermissions::reads(&zread!(runtime.config), &root_path);
+        let write_permissions = AdminPermissions::writes(&zread!(runtime.config), &root_path);
         let context = Arc::new(AdminContext {
             runtime: runtime.clone(),
             plugins_mgr,
             pid_str,
             version,
+            read_permissions,
+            write_permissions,
         });
         let admin = Arc::new(AdminSpace {
             pid: runtime.pid.clone(),
@@ -84,6 +202,46 @@ impl AdminSpace {
         zlock!(admin.primitives).replace(primitives.clone());
 
         primitives.decl_queryable(&[&root_path, "/**"].concat().into(), EVAL, None);
+        for path in &["/drain", "/connect", "/disconnect", "/logger"] {
+            let path = [&root_path, *path].concat();
+            if admin.context.write_permissions.allows(&path) {
+                primitives.decl_subscriber(&path.into(), &SubInfo::default(), None);
+            }
+        }
+
+        let stream_level = zread!(runtime.config)
+            .get(&ZN_ADMIN_LOG_STREAM_KEY)
+            .cloned();
+        if let Some(level) = stream_level {
+            match level.parse::<log::LevelFilter>() {
+                Ok(_) if logger::directives().is_none() => log::warn!(
+                    "{} requires the reloadable logger: ignored",
+                    ZN_ADMIN_LOG_STREAM_STR
+                ),
+                Ok(level) => {
+                    let receiver = logger::stream(level, *ADMIN_LOG_STREAM_CAPACITY);
+                    let reskey = ResKey::from([&root_path, "/logger/lines"].concat());
+                    task::spawn(async move {
+                        while let Ok(line) = receiver.recv_async().await {
+                            let mut data_info = DataInfo::new();
+                            data_info.encoding = Some(encoding::APP_JSON);
+                            // don't stream the records logged while routing the streamed ones
+                            logger::without_streaming(|| {
+                                primitives.send_data(
+                                    &reskey,
+                                    ZBuf::from(line.as_bytes()),
+                                    Reliability::Reliable,
+                                    CongestionControl::Drop,
+                                    Some(data_info),
+                                    None,
+                                )
+                            });
+                        }
+                    });
+                }
+                Err(_) => log::warn!("Invalid {} ({}): ignored", ZN_ADMIN_LOG_STREAM_STR, level),
+            }
+        }
     }
 
     pub fn reskey_to_string(&self, key: &ResKey) -> Option<String> {
@@ -163,6 +321,74 @@ impl Primitives for AdminSpace {
             congestion_control,
             data_info,
         );
+        let path = match self.reskey_to_string(reskey) {
+            Some(path) if self.context.write_permissions.allows(&path) => path,
+            path => {
+                log::warn!(
+                    "Write on {} ignored: not allowed by {}",
+                    path.unwrap_or_default(),
+                    ZN_ADMIN_WRITE_STR
+                );
+                return;
+            }
+        };
+        let root_path = format!("/@/router/{}", self.context.pid_str);
+        if path == format!("{}/connect", root_path) {
+            // The payload is the locator to connect
+            let locator = String::from_utf8_lossy(&payload.contiguous())
+                .trim()
+                .to_string();
+            let runtime = self.context.runtime.clone();
+            task::spawn(async move {
+                match locator.parse() {
+                    Ok(locator) => {
+                        if let Err(e) = runtime.connect(&locator).await {
+                            error!("Unable to connect to {}: {}", locator, e);
+                        }
+                    }
+                    Err(e) => error!("Unable to connect to {}: {}", locator, e),
+                }
+            });
+        } else if path == format!("{}/disconnect", root_path) {
+            // The payload is the id of the router, peer or client to disconnect
+            let pid = String::from_utf8_lossy(&payload.contiguous()).to_string();
+            let runtime = self.context.runtime.clone();
+            task::spawn(async move {
+                match pid.parse() {
+                    Ok(pid) => {
+                        if let Err(e) = runtime.disconnect(&pid).await {
+                            error!("Unable to disconnect from {}: {}", pid, e);
+                        }
+                    }
+                    Err(e) => error!("Unable to disconnect from {}: {}", pid, e),
+                }
+            });
+        } else if path == format!("{}/logger", root_path) {
+            // The payload is the new filter of the logger
+            let directives = String::from_utf8_lossy(&payload.contiguous())
+                .trim()
+                .to_string();
+            match logger::set_directives(&directives) {
+                Ok(()) => log::info!("Logger filter set to '{}'", directives),
+                Err(e) => error!("Unable to set logger filter to '{}': {}", directives, e),
+            }
+        } else if path == format!("{}/drain", root_path) {
+            // The payload is the optional drain timeout in seconds
+            let timeout = match drain_timeout(&String::from_utf8_lossy(&payload.contiguous())) {
+                Ok(timeout) => timeout,
+                Err(e) => {
+                    error!("Drain request ignored: {}", e);
+                    return;
+                }
+            };
+            let runtime = self.context.runtime.clone();
+            task::spawn(async move {
+                if let Err(e) = runtime.drain(timeout).await {
+                    error!("Error while draining: {}", e);
+                }
+                log::info!("Drain completed");
+            });
+        }
     }
 
     fn send_query(
@@ -172,14 +398,16 @@ impl Primitives for AdminSpace {
         qid: ZInt,
         target: QueryTarget,
         _consolidation: QueryConsolidation,
+        page: Option<QueryPage>,
         _routing_context: Option<RoutingContext>,
     ) {
         trace!(
-            "recv Query {:?} {:?} {:?} {:?}",
+            "recv Query {:?} {:?} {:?} {:?} {:?}",
             reskey,
             predicate,
             target,
-            _consolidation
+            _consolidation,
+            page
         );
         let pid = self.pid.clone();
         let context = self.context.clone();
@@ -189,13 +417,28 @@ impl Primitives for AdminSpace {
         match self.reskey_to_string(reskey) {
             Some(name) => {
                 for (path, handler) in &self.handlers {
-                    if rname::intersect(&name, path) {
+                    if !rname::intersect(&name, path) {
+                        continue;
+                    }
+                    if context.read_permissions.allows(path) {
                         matching_handlers.push((path.clone(), handler.clone()));
+                    } else {
+                        log::debug!(
+                            "Query on {} not answered for {}: not allowed by {}",
+                            name,
+                            path,
+                            ZN_ADMIN_READ_STR
+                        );
                     }
                 }
             }
             None => error!("Unknown ResKey!!"),
         };
+        if let Some(page) = page {
+            matching_handlers.retain(|(path, _)| page.follows(path));
+            matching_handlers.sort_by(|(path1, _), (path2, _)| path1.cmp(path2));
+            matching_handlers.truncate(page.limit as usize);
+        }
 
         // router is not re-entrant
         task::spawn(async move {
@@ -293,16 +536,48 @@ pub async fn router_data(context: &AdminContext) -> (ZBuf, ZInt) {
             "links": session.get_links().map_or_else(
                 |_| Vec::new(),
                 |links| links.iter().map(|link| link.get_dst().to_string()).collect()
-            )
+            ),
+            "degraded_links": session.get_degraded_links().map_or_else(
+                |_| Vec::new(),
+                |links| links.iter().map(|link| link.get_dst().to_string()).collect()
+            ),
+            "parameters": session.get_parameters().map_or(serde_json::Value::Null, |p| json!({
+                "version": p.version,
+                "sn_resolution": p.sn_resolution,
+                "batch_sizes": p.batch_sizes.iter().map(|(l, size)| (l.to_string(), json!(size))).collect::<serde_json::Map<_, _>>(),
+                "shm": p.is_shm,
+                "auth": p.auth_methods,
+                "auth_subject": p.auth_subject,
+                "compression": p.compression,
+                "qos": p.qos,
+            }))
         })
     }))
     .await;
 
+    // retries to connect unreachable configured peers
+    let connect_retries = context
+        .runtime
+        .get_connect_retries()
+        .iter()
+        .map(|(locator, state)| {
+            (
+                locator.to_string(),
+                json!({
+                    "attempts": state.attempts,
+                    "delay_ms": state.delay.as_millis() as u64,
+                    "next_attempt": state.next_attempt.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64(),
+                }),
+            )
+        })
+        .collect::<serde_json::Map<_, _>>();
+
     let json = json!({
         "pid": context.pid_str,
         "version": context.version,
         "locators": locators,
         "sessions": sessions,
+        "connect_retries": connect_retries,
         "plugins": plugins,
     });
     log::trace!("AdminSpace router_data: {:?}", json);
@@ -337,3 +612,307 @@ pub async fn linkstate_peers_data(context: &AdminContext) -> (ZBuf, ZInt) {
         encoding::TEXT_PLAIN,
     )
 }
+
+pub async fn entities_data(context: &AdminContext) -> (ZBuf, ZInt) {
+    let entities = zread!(context.runtime.router.tables).client_entities();
+    let json = serde_json::Value::Array(entities);
+    log::trace!("AdminSpace entities_data: {:?}", json);
+    (ZBuf::from(json.to_string().as_bytes()), encoding::APP_JSON)
+}
+
+pub async fn stats_data(context: &AdminContext) -> (ZBuf, ZInt) {
+    fn stats_json(stats: SessionStats) -> serde_json::Value {
+        json!({
+            "tx_batches": stats.tx_batches,
+            "tx_bytes": stats.tx_bytes,
+            "rx_batches": stats.rx_batches,
+            "rx_bytes": stats.rx_bytes,
+            "defrag_aborts": stats.defrag_aborts,
+        })
+    }
+
+    // the counters of each session, as seen locally and by the remote peer,
+    // requested in parallel to all the peers
+    let timeout = Duration::from_secs_f64(*ADMIN_REMOTE_STATS_TIMEOUT);
+    let sessions = future::join_all(context.runtime.manager().get_sessions().into_iter().map(
+        |session| async move {
+            let pid = session.get_pid().ok()?;
+            let local = session.get_stats().ok()?;
+            let remote = session.get_remote_stats(timeout).await.ok();
+            Some((
+                pid.to_string(),
+                json!({
+                    "local": stats_json(local),
+                    "remote": remote.map_or(serde_json::Value::Null, stats_json),
+                }),
+            ))
+        },
+    ))
+    .await;
+
+    let json = sessions
+        .into_iter()
+        .flatten()
+        .collect::<serde_json::Map<_, _>>();
+    log::trace!("AdminSpace stats_data: {:?}", json);
+    (
+        ZBuf::from(serde_json::Value::Object(json).to_string().as_bytes()),
+        encoding::APP_JSON,
+    )
+}
+
+pub async fn health_data(context: &AdminContext) -> (ZBuf, ZInt) {
+    let session_mgr = context.runtime.manager().clone();
+
+    // transports health
+    let locators: Vec<serde_json::Value> = session_mgr
+        .get_locators()
+        .iter()
+        .map(|locator| json!(locator.to_string()))
+        .collect();
+    let transports_ok = !locators.is_empty();
+
+    // plugins health
+    let mut plugins: Vec<serde_json::Value> = context
+        .plugins_mgr
+        .plugins
+        .iter()
+        .map(|plugin| {
+            json!({
+                "name": plugin.name,
+                "path": plugin.path,
+                "status": "ok"
+            })
+        })
+        .collect();
+    plugins.extend(
+        context
+            .plugins_mgr
+            .failed_plugins
+            .iter()
+            .map(|(path, reason)| {
+                json!({
+                    "path": path,
+                    "status": "failed",
+                    "reason": reason
+                })
+            }),
+    );
+    let plugins_ok = context.plugins_mgr.failed_plugins.is_empty();
+
+    // HLC drift: how far ahead of the local system clock the HLC has been pushed
+    let hlc = context.runtime.new_timestamp().map(|ts| {
+        let drift = ts
+            .get_time()
+            .to_duration()
+            .checked_sub(uhlc::system_time_clock().to_duration())
+            .unwrap_or_default();
+        json!({ "drift_ms": drift.as_millis() as u64 })
+    });
+
+    let json = json!({
+        "pid": context.pid_str,
+        "status": if transports_ok && plugins_ok { "ok" } else { "degraded" },
+        "transports": {
+            "status": if transports_ok { "ok" } else { "down" },
+            "locators": locators,
+            "sessions": session_mgr.get_sessions().len(),
+        },
+        "plugins": plugins,
+        "hlc": hlc,
+    });
+    log::trace!("AdminSpace health_data: {:?}", json);
+    (ZBuf::from(json.to_string().as_bytes()), encoding::APP_JSON)
+}
+
+pub async fn liveness_data(context: &AdminContext) -> (ZBuf, ZInt) {
+    let json = match liveness::check(&context.runtime, &context.plugins_mgr).await {
+        Ok(()) => json!({ "alive": true, "reasons": [] }),
+        Err(reasons) => json!({ "alive": false, "reasons": reasons }),
+    };
+    log::trace!("AdminSpace liveness_data: {:?}", json);
+    (ZBuf::from(json.to_string().as_bytes()), encoding::APP_JSON)
+}
+
+pub async fn logger_data(context: &AdminContext) -> (ZBuf, ZInt) {
+    let json = json!({
+        "directives": logger::directives(),
+        "stream": zread!(context.runtime.config).get(&ZN_ADMIN_LOG_STREAM_KEY),
+    });
+    log::trace!("AdminSpace logger_data: {:?}", json);
+    (ZBuf::from(json.to_string().as_bytes()), encoding::APP_JSON)
+}
+
+// Parses the payload of a drain request: the drain timeout in seconds,
+// or the default one if the payload is empty.
+fn drain_timeout(payload: &str) -> Result<Duration, String> {
+    let payload = payload.trim();
+    let secs = if payload.is_empty() {
+        *ADMIN_DRAIN_DEFAULT_TIMEOUT
+    } else {
+        payload
+            .parse::<f64>()
+            .map_err(|e| format!("invalid timeout '{}': {}", payload, e))?
+    };
+    // from_secs_f64 panics on negative, infinite or overflowing values
+    if secs.is_finite() && (0.0..=ADMIN_DRAIN_MAX_TIMEOUT).contains(&secs) {
+        Ok(Duration::from_secs_f64(secs))
+    } else {
+        Err(format!(
+            "invalid timeout '{}': expected a number of seconds between 0 and {}",
+            payload, ADMIN_DRAIN_MAX_TIMEOUT
+        ))
+    }
+}
+
+pub async fn config_data(context: &AdminContext) -> (ZBuf, ZInt) {
+    const MODES: [&str; 3] = ["router", "peer", "client"];
+    let config = zread!(context.runtime.config).clone();
+    let keys: Vec<(u64, String)> = config_keys().collect();
+    let display = |key: u64, value: &str| {
+        if is_secret(key) {
+            json!("*****")
+        } else {
+            json!(value)
+        }
+    };
+
+    // the effective value of each key in the current mode, and where it comes from
+    let mode = whatami::to_string(context.runtime.whatami).to_lowercase();
+    let mut effective = serde_json::Map::new();
+    for (key, name) in &keys {
+        if let Some(value) = config.get(key) {
+            let origin = context
+                .runtime
+                .get_config_origin(*key)
+                .map_or_else(|| "api".to_string(), |origin| origin.source);
+            effective.insert(
+                name.clone(),
+                json!({ "value": display(*key, value), "origin": origin }),
+            );
+        } else if let Some(value) = config_default(*key, &mode) {
+            effective.insert(
+                name.clone(),
+                json!({ "value": display(*key, value), "origin": "default" }),
+            );
+        }
+    }
+
+    // the values the same configuration would resolve to in each mode
+    let mut modes = serde_json::Map::new();
+    for m in MODES.iter() {
+        let resolved: serde_json::Map<String, serde_json::Value> = keys
+            .iter()
+            .filter(|(key, _)| *key != ZN_MODE_KEY)
+            .filter_map(|(key, name)| {
+                config
+                    .get(key)
+                    .map(|v| &v[..])
+                    .or_else(|| config_default(*key, m))
+                    .map(|value| (name.clone(), display(*key, value)))
+            })
+            .collect();
+        modes.insert(m.to_string(), serde_json::Value::Object(resolved));
+    }
+
+    let json = json!({
+        "pid": context.pid_str,
+        "mode": mode,
+        "effective": effective,
+        "modes": modes,
+    });
+    log::trace!("AdminSpace config_data: {:?}", json);
+    (ZBuf::from(json.to_string().as_bytes()), encoding::APP_JSON)
+}
+
+pub async fn config_meta_data(context: &AdminContext, key: u64) -> (ZBuf, ZInt) {
+    let name = ConfigTranscoder::decode(key).unwrap_or_default();
+    let display = |value: &str| {
+        if is_secret(key) {
+            "*****".to_string()
+        } else {
+            value.to_string()
+        }
+    };
+    let json = match (
+        zread!(context.runtime.config).get(&key).cloned(),
+        context.runtime.get_config_origin(key),
+    ) {
+        (Some(value), Some(origin)) => json!({
+            "key": name,
+            "value": display(&value),
+            "source": origin.source,
+            "detail": origin.detail,
+            "time": origin
+                .time
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs_f64(),
+        }),
+        (Some(value), None) => json!({
+            "key": name,
+            "value": display(&value),
+            "source": "api",
+        }),
+        (None, _) => {
+            let mode = whatami::to_string(context.runtime.whatami).to_lowercase();
+            json!({
+                "key": name,
+                "value": config_default(key, &mode).map(display),
+                "source": "default",
+            })
+        }
+    };
+    log::trace!("AdminSpace config_meta_data: {:?}", json);
+    (ZBuf::from(json.to_string().as_bytes()), encoding::APP_JSON)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drain_timeout() {
+        assert_eq!(
+            drain_timeout(""),
+            Ok(Duration::from_secs_f64(*ADMIN_DRAIN_DEFAULT_TIMEOUT))
+        );
+        assert_eq!(drain_timeout(" 1.5\n"), Ok(Duration::from_millis(1500)));
+        assert_eq!(drain_timeout("0"), Ok(Duration::from_secs(0)));
+        for payload in &["inf", "-inf", "NaN", "1e400", "-1", "86401", "abc"] {
+            assert!(drain_timeout(payload).is_err(), "{}", payload);
+        }
+        // the error reports the rejected payload, not its parsed value
+        assert!(drain_timeout("1e400").unwrap_err().contains("'1e400'"));
+    }
+
+    #[test]
+    fn test_admin_permissions() {
+        let root = "/@/router/1234";
+        let all = AdminPermissions::parse(ZN_ADMIN_WRITE_STR, "True", root);
+        assert!(all.allows("/@/router/1234/drain"));
+        assert!(all.allows("/@/router/1234/plugin/storages/backend/fs/storage/s1/import"));
+        assert!(!all.allows("/@/router/5678/drain"));
+
+        let none = AdminPermissions::parse(ZN_ADMIN_WRITE_STR, "false", root);
+        assert!(!none.allows("/@/router/1234/drain"));
+
+        let some = AdminPermissions::parse(
+            ZN_ADMIN_WRITE_STR,
+            " /logger, /plugin/storages/**,drain,",
+            root,
+        );
+        assert!(some.allows("/@/router/1234/logger"));
+        assert!(some.allows("/@/router/1234/plugin/storages/backend/fs/storage/s1/export"));
+        assert!(!some.allows("/@/router/1234/drain"));
+        assert!(!some.allows("/@/router/1234/connect"));
+
+        // e.g. the metrics readable by everyone, but not the configuration
+        let reads = AdminPermissions::parse(ZN_ADMIN_READ_STR, "/stats,/health,/liveness", root);
+        assert!(reads.allows("/@/router/1234/stats"));
+        assert!(reads.allows("/@/router/1234/health"));
+        assert!(!reads.allows("/@/router/1234/config"));
+        assert!(!reads.allows("/@/router/1234/config_meta/mode"));
+        assert!(!reads.allows("/@/router/1234"));
+    }
+}
@@ -0,0 +1,128 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use rand::Rng;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+use zenoh_util::properties::config::*;
+
+/// The backoff strategy used to retry connecting the configured peers (or, for a client,
+/// the routers) when they are unreachable.
+///
+/// The delay before the first retry is `initial`. It's multiplied by `multiplier` after each
+/// failed attempt, up to `max`. Each actual delay is then randomly spread by up to
+/// `jitter` times its value (e.g. with a `jitter` of `0.2`, a delay of 1s becomes 0.8s to 1.2s),
+/// not to have several nodes retrying at the same time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectRetryPolicy {
+    pub initial: Duration,
+    pub max: Duration,
+    pub multiplier: f64,
+    pub jitter: f64,
+}
+
+impl Default for ConnectRetryPolicy {
+    fn default() -> ConnectRetryPolicy {
+        ConnectRetryPolicy {
+            initial: Duration::from_millis(ZN_CONNECT_RETRY_INITIAL_DEFAULT.parse().unwrap()),
+            max: Duration::from_millis(ZN_CONNECT_RETRY_MAX_DEFAULT.parse().unwrap()),
+            multiplier: ZN_CONNECT_RETRY_MULTIPLIER_DEFAULT.parse().unwrap(),
+            jitter: ZN_CONNECT_RETRY_JITTER_DEFAULT.parse().unwrap(),
+        }
+    }
+}
+
+// Returns the value configured for `key` if it's valid, or its default value.
+fn parse_or<T: FromStr>(
+    config: &ConfigProperties,
+    key: u64,
+    name: &str,
+    default: &str,
+    is_valid: impl Fn(&T) -> bool,
+) -> T {
+    match config.get(&key) {
+        Some(value) => match value.parse::<T>() {
+            Ok(v) if is_valid(&v) => v,
+            _ => {
+                log::warn!("Invalid {}: {}, use default value {}", name, value, default);
+                default.parse().ok().unwrap()
+            }
+        },
+        None => default.parse().ok().unwrap(),
+    }
+}
+
+impl ConnectRetryPolicy {
+    pub fn from_config(config: &ConfigProperties) -> ConnectRetryPolicy {
+        let initial: u64 = parse_or(
+            config,
+            ZN_CONNECT_RETRY_INITIAL_KEY,
+            ZN_CONNECT_RETRY_INITIAL_STR,
+            ZN_CONNECT_RETRY_INITIAL_DEFAULT,
+            |v| *v > 0,
+        );
+        let max: u64 = parse_or(
+            config,
+            ZN_CONNECT_RETRY_MAX_KEY,
+            ZN_CONNECT_RETRY_MAX_STR,
+            ZN_CONNECT_RETRY_MAX_DEFAULT,
+            |v| *v > 0,
+        );
+        let multiplier: f64 = parse_or(
+            config,
+            ZN_CONNECT_RETRY_MULTIPLIER_KEY,
+            ZN_CONNECT_RETRY_MULTIPLIER_STR,
+            ZN_CONNECT_RETRY_MULTIPLIER_DEFAULT,
+            |v| *v >= 1.0,
+        );
+        let jitter: f64 = parse_or(
+            config,
+            ZN_CONNECT_RETRY_JITTER_KEY,
+            ZN_CONNECT_RETRY_JITTER_STR,
+            ZN_CONNECT_RETRY_JITTER_DEFAULT,
+            |v| (0.0..=1.0).contains(v),
+        );
+        ConnectRetryPolicy {
+            initial: Duration::from_millis(initial),
+            max: Duration::from_millis(max.max(initial)),
+            multiplier,
+            jitter,
+        }
+    }
+
+    /// Returns the delay to wait after the `delay` used for the previous retry.
+    pub fn next_delay(&self, delay: Duration) -> Duration {
+        delay.mul_f64(self.multiplier).min(self.max)
+    }
+
+    /// Returns `delay` randomly spread according to the jitter of this policy.
+    pub fn jittered(&self, delay: Duration) -> Duration {
+        if self.jitter > 0.0 {
+            let factor = 1.0 + rand::thread_rng().gen_range(-self.jitter..=self.jitter);
+            delay.mul_f64(factor)
+        } else {
+            delay
+        }
+    }
+}
+
+/// The state of the connection retries to a configured peer that is unreachable.
+#[derive(Debug, Clone)]
+pub struct ConnectRetryState {
+    /// The number of failed connection attempts.
+    pub attempts: u32,
+    /// The delay before the next attempt (jitter included).
+    pub delay: Duration,
+    /// When the next attempt is due.
+    pub next_attempt: SystemTime,
+}
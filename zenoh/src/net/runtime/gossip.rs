@@ -0,0 +1,277 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use super::orchestrator::ResponsePolicy;
+use super::protocol::core::whatami;
+use super::protocol::io::{WBuf, ZBuf};
+use super::protocol::proto::{Hello, Scout, SessionBody, SessionMessage};
+use super::Runtime;
+use async_std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use futures::prelude::*;
+use std::time::Duration;
+use zenoh_util::core::{ZError, ZErrorKind, ZResult};
+use zenoh_util::properties::config::*;
+use zenoh_util::{zconfigurable, zerror, zerror2, zlock, zread};
+
+const RCV_BUF_SIZE: usize = 65536;
+const SEND_BUF_INITIAL_SIZE: usize = 64;
+
+zconfigurable! {
+    // Period in milliseconds at which the gossip seeds are scouted until one of them answers.
+    static ref GOSSIP_SCOUT_PERIOD: u64 = 500;
+    // The maximal size in bytes of a gossip reply: the Hellos of the routers beyond it are
+    // not sent, to bound the amplification of a scout with a spoofed source address.
+    static ref GOSSIP_REPLY_MAX_SIZE: usize = 1024;
+    // The minimal period in milliseconds between two gossip replies to a same source address,
+    // unless the configured scouting_response_period is longer.
+    static ref GOSSIP_RESPONSE_PERIOD: u64 = 1000;
+}
+
+impl Runtime {
+    /// Binds the gossip listener on `listener` and spawns the answering of the unicast scouts
+    /// it receives with the locators of this router and of all the routers it knows.
+    pub(super) async fn start_gossip_responder(&self, listener: &str) -> ZResult<()> {
+        let socket = UdpSocket::bind(listener).await.map_err(|err| {
+            zerror2!(ZErrorKind::IoError {
+                descr: format!(
+                    "Unable to bind {} {}: {}",
+                    ZN_GOSSIP_LISTENER_STR, listener, err
+                )
+            })
+        })?;
+        log::info!("Gossip scouting answered on {}", listener);
+        let this = self.clone();
+        async_std::task::spawn(async move { this.gossip_responder(&socket).await });
+        Ok(())
+    }
+
+    // The Hellos of this router, unless it's draining, followed by the ones of the routers
+    // of the link state graph that advertised their locators.
+    fn gossip_hellos(&self) -> Vec<SessionMessage> {
+        let mut hellos = vec![];
+        if !self.is_draining() {
+            hellos.push(SessionMessage::make_hello(
+                Some(self.manager().pid()),
+                Some(self.whatami),
+                Some(self.manager().get_locators()),
+                None,
+            ));
+        }
+        let tables = zread!(self.router.tables);
+        if let Some(net) = &tables.routers_net {
+            for idx in net.graph.node_indices() {
+                let node = &net.graph[idx];
+                match &node.locators {
+                    Some(locators)
+                        if node.pid != self.pid
+                            && node.whatami == whatami::ROUTER
+                            && !locators.is_empty() =>
+                    {
+                        hellos.push(SessionMessage::make_hello(
+                            Some(node.pid.clone()),
+                            Some(node.whatami),
+                            Some(locators.clone()),
+                            None,
+                        ))
+                    }
+                    _ => (),
+                }
+            }
+        }
+        hellos
+    }
+
+    async fn gossip_responder(&self, socket: &UdpSocket) {
+        let mut policy = ResponsePolicy::from_config(&*zread!(self.config))
+            .with_min_period(Duration::from_millis(*GOSSIP_RESPONSE_PERIOD));
+        let mut buf = vec![0; RCV_BUF_SIZE];
+        loop {
+            let (n, peer) = match socket.recv_from(&mut buf).await {
+                Ok(received) => received,
+                Err(err) => {
+                    log::error!("Stop answering gossip scouting: {}", err);
+                    break;
+                }
+            };
+            let mut zbuf = ZBuf::from(&buf[..n]);
+            match zbuf.read_session_message() {
+                Some(SessionMessage {
+                    body: SessionBody::Scout(Scout { what, .. }),
+                    ..
+                }) if what.unwrap_or(whatami::ROUTER) & whatami::ROUTER != 0 => {
+                    let what = what.unwrap_or(whatami::ROUTER);
+                    if !policy.accept(what, &peer.ip()) {
+                        continue;
+                    }
+                    // all the Hellos are sent in a single datagram, up to its maximal size
+                    let mut reply: Vec<u8> = vec![];
+                    for hello in self.gossip_hellos() {
+                        let mut wbuf = WBuf::new(SEND_BUF_INITIAL_SIZE, false);
+                        wbuf.write_session_message(&hello);
+                        let bytes = ZBuf::from(&wbuf).contiguous();
+                        if reply.len() + bytes.len() > *GOSSIP_REPLY_MAX_SIZE {
+                            log::debug!("Gossip to {} truncated to {} bytes", peer, reply.len());
+                            break;
+                        }
+                        reply.extend_from_slice(&bytes);
+                    }
+                    log::trace!("Send gossip to {}", peer);
+                    if let Err(err) = socket.send_to(&reply, peer).await {
+                        log::error!("Unable to send gossip to {} : {}", peer, err);
+                    }
+                }
+                Some(msg) => log::trace!("Ignore {:?} from {}", msg.body, peer),
+                None => log::trace!("Received unexpected UDP datagram from {} : {}", peer, zbuf),
+            }
+        }
+    }
+
+    // Remembers a router learned by gossip, so that it can be connected when the seeds are down.
+    fn record_gossip(&self, hello: &Hello) {
+        if let (Some(pid), Some(locators)) = (&hello.pid, &hello.locators) {
+            let mut routers = zlock!(self.gossip_routers);
+            match routers.iter_mut().find(|(p, _)| p == pid) {
+                Some(entry) => entry.1 = locators.clone(),
+                None => routers.push((pid.clone(), locators.clone())),
+            }
+        }
+    }
+
+    /// Scouts the gossip listeners of the comma separated `seeds` and connects to the first
+    /// router learned from them. If no seed answers within `timeout`, the routers learned
+    /// previously are tried in turn. The learned routers are also recorded in the scouting cache,
+    /// if any, whose routers are connected before scouting the seeds after a restart.
+    pub(super) async fn connect_gossip(&self, seeds: &str, timeout: Duration) -> ZResult<()> {
+        let mut addrs: Vec<SocketAddr> = vec![];
+        for seed in seeds.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+            // a DNS name may be resolved into several seeds
+            match seed.to_socket_addrs().await {
+                Ok(resolved) => addrs.extend(resolved),
+                Err(err) => log::warn!("Unable to resolve gossip seed {}: {}", seed, err),
+            }
+        }
+        let mut sockets = vec![];
+        for unspecified in &["0.0.0.0:0", "[::]:0"] {
+            let ipv4 = unspecified.starts_with('0');
+            if addrs.iter().any(|addr| addr.is_ipv4() == ipv4) {
+                match UdpSocket::bind(unspecified).await {
+                    Ok(socket) => sockets.push(socket),
+                    Err(err) => log::warn!("Unable to bind UDP port {}: {}", unspecified, err),
+                }
+            }
+        }
+        if sockets.is_empty() {
+            return zerror!(ZErrorKind::IoError {
+                descr: format!("No gossip seed to scout in {}", seeds)
+            });
+        }
+
+        log::info!("Scouting gossip seeds {:?} ...", addrs);
+        let send = async {
+            let mut wbuf = WBuf::new(SEND_BUF_INITIAL_SIZE, false);
+            wbuf.write_session_message(&SessionMessage::make_scout(
+                Some(whatami::ROUTER),
+                true,
+                None,
+            ));
+            let scout = ZBuf::from(&wbuf).contiguous();
+            loop {
+                for addr in &addrs {
+                    for socket in sockets.iter().filter(|s| {
+                        s.local_addr()
+                            .map_or(false, |local| local.is_ipv4() == addr.is_ipv4())
+                    }) {
+                        if let Err(err) = socket.send_to(&scout, addr).await {
+                            log::debug!("Unable to scout gossip seed {}: {}", addr, err);
+                        }
+                    }
+                }
+                async_std::task::sleep(Duration::from_millis(*GOSSIP_SCOUT_PERIOD)).await;
+            }
+        };
+        let recvs = async {
+            futures::future::select_all(
+                sockets
+                    .iter()
+                    .map(|socket| self.recv_gossip(socket).boxed()),
+            )
+            .await;
+            true
+        };
+        let timeout = async {
+            async_std::task::sleep(timeout).await;
+            false
+        };
+        if async_std::prelude::FutureExt::race(
+            async_std::prelude::FutureExt::race(send, recvs),
+            timeout,
+        )
+        .await
+        {
+            return Ok(());
+        }
+
+        log::info!("No gossip seed answered, try the routers learned by gossip");
+        let routers = zlock!(self.gossip_routers).clone();
+        for (pid, locators) in routers {
+            if self.connect_any(&locators).await.is_ok() {
+                log::info!("Connected to router {} learned by gossip", pid);
+                return Ok(());
+            }
+            log::debug!("Unable to connect to router {} learned by gossip", pid);
+        }
+        zerror!(ZErrorKind::Timeout {})
+    }
+
+    // Receives the gossip of the seeds until one of the routers learned from them is connected.
+    async fn recv_gossip(&self, socket: &UdpSocket) {
+        let mut buf = vec![0; RCV_BUF_SIZE];
+        loop {
+            let (n, peer) = match socket.recv_from(&mut buf).await {
+                Ok(received) => received,
+                Err(err) => {
+                    // e.g. the ICMP port unreachable of a seed that is down: wait for the
+                    // next scouts rather than spinning, the whole scouting being bounded
+                    log::debug!("Unable to receive gossip: {}", err);
+                    async_std::task::sleep(Duration::from_millis(*GOSSIP_SCOUT_PERIOD)).await;
+                    continue;
+                }
+            };
+            let mut zbuf = ZBuf::from(&buf[..n]);
+            let mut hellos = vec![];
+            while zbuf.can_read() {
+                match zbuf.read_session_message() {
+                    Some(SessionMessage {
+                        body: SessionBody::Hello(hello),
+                        ..
+                    }) => hellos.push(hello),
+                    _ => break,
+                }
+            }
+            log::debug!("Received gossip from {} : {:?}", peer, hellos);
+            for hello in &hellos {
+                self.record_scouted(hello);
+                self.record_gossip(hello);
+            }
+            for hello in &hellos {
+                if let Some(locators) = &hello.locators {
+                    if self.connect_any(locators).await.is_ok() {
+                        log::info!("Connected to router {:?} learned by gossip", hello);
+                        return;
+                    }
+                    log::debug!("Unable to connect to router {:?}", hello);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,64 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+
+//! The liveness check of a router, as used by the watchdog of `zenohd`
+//! and published in the admin space on `/@/router/<pid>/liveness`.
+use super::plugins::PluginsMgr;
+use super::Runtime;
+use async_std::prelude::*;
+use async_std::task;
+use std::time::Duration;
+use zenoh_util::{zconfigurable, zread};
+
+zconfigurable! {
+    // Timeout in milliseconds for the routing tables and the sessions to respond
+    // to a liveness check before the router is deemed stuck.
+    static ref LIVENESS_CHECK_TIMEOUT: u64 = 5000;
+}
+
+/// Checks that the routing tables and the sessions of the router respond,
+/// that the router listens on at least one locator and that all its plugins were loaded.
+/// Returns the reasons why the router is not alive, if any.
+pub async fn check(runtime: &Runtime, plugins_mgr: &PluginsMgr) -> Result<(), Vec<String>> {
+    let mut reasons = vec![];
+
+    // A deadlocked router would never release its routing tables
+    let c_runtime = runtime.clone();
+    let responds = task::spawn_blocking(move || {
+        drop(zread!(c_runtime.router.tables));
+        c_runtime.manager().get_sessions().len()
+    })
+    .timeout(Duration::from_millis(*LIVENESS_CHECK_TIMEOUT))
+    .await;
+    if responds.is_err() {
+        reasons.push(format!(
+            "The routing tables or the sessions didn't respond within {}ms",
+            *LIVENESS_CHECK_TIMEOUT
+        ));
+    }
+
+    if runtime.manager().get_locators().is_empty() {
+        reasons.push("No listener is bound".to_string());
+    }
+
+    for (path, reason) in &plugins_mgr.failed_plugins {
+        reasons.push(format!("Plugin {} failed: {}", path.display(), reason));
+    }
+
+    if reasons.is_empty() {
+        Ok(())
+    } else {
+        Err(reasons)
+    }
+}
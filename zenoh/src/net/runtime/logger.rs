@@ -0,0 +1,183 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+
+//! A logger whose filter can be changed at runtime, e.g. via the admin space of a router,
+//! and whose records can be streamed to the remote operators.
+use flume::{Receiver, Sender, TrySendError};
+use log::{LevelFilter, Log, Metadata, Record};
+use serde_json::json;
+use std::cell::Cell;
+use std::sync::{Mutex, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use zenoh_util::core::{ZError, ZErrorKind, ZResult};
+use zenoh_util::{zerror, zlock, zread, zwrite};
+
+// The directives used when RUST_LOG is not set, as env_logger does.
+const DEFAULT_DIRECTIVES: &str = "error";
+
+struct Filter {
+    directives: String,
+    filter: env_logger::filter::Filter,
+}
+
+struct Stream {
+    level: LevelFilter,
+    sender: Sender<String>,
+}
+
+struct ReloadableLogger {
+    filter: RwLock<Option<Filter>>,
+    output: RwLock<Option<env_logger::Logger>>,
+    streams: Mutex<Vec<Stream>>,
+}
+
+lazy_static! {
+    static ref LOGGER: ReloadableLogger = ReloadableLogger {
+        filter: RwLock::new(None),
+        output: RwLock::new(None),
+        streams: Mutex::new(vec![]),
+    };
+}
+
+thread_local! {
+    // Set while publishing the streamed records, not to stream the records logged meanwhile.
+    static STREAMING: Cell<bool> = const { Cell::new(false) };
+}
+
+fn parse_filter(directives: &str) -> Filter {
+    Filter {
+        directives: directives.to_string(),
+        filter: env_logger::filter::Builder::new().parse(directives).build(),
+    }
+}
+
+impl ReloadableLogger {
+    fn stream(&self, record: &Record) {
+        if STREAMING.with(|streaming| streaming.get()) {
+            return;
+        }
+        let mut streams = zlock!(self.streams);
+        if streams.is_empty() {
+            return;
+        }
+        let line = json!({
+            "time": SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64(),
+            "level": record.level().to_string(),
+            "target": record.target(),
+            "message": record.args().to_string(),
+        })
+        .to_string();
+        streams.retain(|stream| {
+            record.level() > stream.level
+                || !matches!(
+                    stream.sender.try_send(line.clone()),
+                    Err(TrySendError::Disconnected(_))
+                )
+        });
+    }
+}
+
+impl Log for ReloadableLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        match &*zread!(self.filter) {
+            Some(filter) => filter.filter.enabled(metadata),
+            None => false,
+        }
+    }
+
+    fn log(&self, record: &Record) {
+        let matches = match &*zread!(self.filter) {
+            Some(filter) => filter.filter.matches(record),
+            None => false,
+        };
+        if matches {
+            if let Some(output) = &*zread!(self.output) {
+                output.log(record);
+            }
+            self.stream(record);
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(output) = &*zread!(self.output) {
+            output.flush();
+        }
+    }
+}
+
+/// Installs as global logger a logger writing the records as configured in `builder`,
+/// whose filter can then be changed with [`set_directives()`].
+/// The initial filter is given by the `RUST_LOG` environment variable, as for `env_logger`.
+///
+/// The filters configured in `builder` are ignored.
+pub fn init(mut builder: env_logger::Builder) -> ZResult<()> {
+    let directives = std::env::var("RUST_LOG").unwrap_or_else(|_| DEFAULT_DIRECTIVES.to_string());
+    let filter = parse_filter(&directives);
+    let max_level = filter.filter.filter();
+    *zwrite!(LOGGER.filter) = Some(filter);
+    *zwrite!(LOGGER.output) = Some(builder.filter_level(LevelFilter::Trace).build());
+    match log::set_logger(&*LOGGER) {
+        Ok(()) => {
+            log::set_max_level(max_level);
+            Ok(())
+        }
+        Err(e) => {
+            *zwrite!(LOGGER.filter) = None;
+            *zwrite!(LOGGER.output) = None;
+            zerror!(ZErrorKind::Other {
+                descr: format!("Unable to install the logger: {}", e)
+            })
+        }
+    }
+}
+
+/// Returns the directives of the current filter of the logger, if it was installed with [`init()`].
+pub fn directives() -> Option<String> {
+    zread!(LOGGER.filter)
+        .as_ref()
+        .map(|filter| filter.directives.clone())
+}
+
+/// Replaces the filter of the logger installed with [`init()`] with `directives`,
+/// using the `RUST_LOG` syntax (ex: `"info,zenoh::net::protocol=trace"`).
+pub fn set_directives(directives: &str) -> ZResult<()> {
+    let mut current = zwrite!(LOGGER.filter);
+    if current.is_none() {
+        return zerror!(ZErrorKind::Other {
+            descr: "The logger is not reloadable".to_string()
+        });
+    }
+    let filter = parse_filter(directives);
+    log::set_max_level(filter.filter.filter());
+    *current = Some(filter);
+    Ok(())
+}
+
+/// Returns a receiver of the records logged with a level up to `level` (and matching the filter
+/// of the logger), as JSON objects with a time, a level, a target and a message.
+/// The records are dropped when the receiver is full.
+pub(crate) fn stream(level: LevelFilter, capacity: usize) -> Receiver<String> {
+    let (sender, receiver) = flume::bounded(capacity);
+    zlock!(LOGGER.streams).push(Stream { level, sender });
+    receiver
+}
+
+/// Runs `f` without streaming the records it logs on the current thread,
+/// e.g. to publish the streamed records without streaming the logs of the publication.
+pub(crate) fn without_streaming<T>(f: impl FnOnce() -> T) -> T {
+    STREAMING.with(|streaming| streaming.set(true));
+    let res = f();
+    STREAMING.with(|streaming| streaming.set(false));
+    res
+}
@@ -12,36 +12,97 @@
 //   ADLINK zenoh team, <zenoh@adlink-labs.tech>
 //
 mod adminspace;
+mod connect_retry;
+mod gossip;
+pub mod liveness;
+pub mod logger;
 pub mod orchestrator;
+mod scouting_cache;
 
 use super::plugins;
 use super::protocol;
 use super::protocol::core::{whatami, PeerId, WhatAmI};
 use super::protocol::link::{Link, Locator};
-use super::protocol::proto::{Data, ZenohBody, ZenohMessage};
+use super::protocol::proto::{smsg, Data, ZenohBody, ZenohMessage};
 use super::protocol::session::{
-    Session, SessionEventHandler, SessionHandler, SessionManager, SessionManagerConfig,
-    SessionManagerOptionalConfig,
+    CloseReason, Session, SessionEventHandler, SessionHandler, SessionManager,
+    SessionManagerConfig, SessionManagerOptionalConfig,
 };
 use super::routing;
+use super::routing::latest_value_cache::LatestValueCache;
+use super::routing::publication_buffer::PublicationBuffer;
 use super::routing::pubsub::full_reentrant_route_data;
+use super::routing::quotas::Quotas;
+#[cfg(feature = "routing_replay")]
+use super::routing::replay::RoutingTrace;
 use super::routing::router::{LinkStateInterceptor, Router};
-pub use adminspace::AdminSpace;
+pub use adminspace::{AdminPermissions, AdminSpace};
 use async_std::sync::Arc;
+use async_std::task;
+use async_std::task::JoinHandle;
+pub use connect_retry::{ConnectRetryPolicy, ConnectRetryState};
+use scouting_cache::ScoutingCache;
 use std::any::Any;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime};
 use uhlc::HLC;
 use zenoh_util::core::{ZError, ZErrorKind, ZResult};
+use zenoh_util::net::IpSubnet;
 use zenoh_util::properties::config::*;
 use zenoh_util::sync::get_mut_unchecked;
-use zenoh_util::{zerror, zerror2};
+#[cfg(feature = "routing_replay")]
+use zenoh_util::zwrite;
+use zenoh_util::{zconfigurable, zerror, zerror2, zlock, zread};
+
+zconfigurable! {
+    // Period in milliseconds at which the remaining client sessions are checked while draining.
+    static ref DRAIN_CHECK_PERIOD: u64 = 100;
+    // Period in milliseconds without any message from the clients after which their traffic
+    // is considered as quiesced while draining.
+    static ref DRAIN_QUIET_PERIOD: u64 = 1000;
+}
 
 pub struct RuntimeState {
     pub pid: PeerId,
     pub whatami: WhatAmI,
     pub router: Arc<Router>,
-    pub config: ConfigProperties,
+    pub(crate) config: RwLock<ConfigProperties>,
     pub manager: SessionManager,
     pub hlc: Option<Arc<HLC>>,
+    draining: AtomicBool,
+    drain_traffic: AtomicU64,
+    config_origins: Mutex<HashMap<u64, ConfigOrigin>>,
+    scouting_cache: Option<ScoutingCache>,
+    connect_retry_policy: ConnectRetryPolicy,
+    connect_retries: Mutex<HashMap<Locator, ConnectRetryState>>,
+    close_listeners: Mutex<Vec<flume::Sender<PeerCloseEvent>>>,
+    drain_listeners: Mutex<Vec<flume::Sender<()>>>,
+    gossip_routers: Mutex<Vec<(PeerId, Vec<Locator>)>>,
+    scouting_task: Mutex<Option<JoinHandle<()>>>,
+}
+
+/// Where the value of a configuration key comes from.
+#[derive(Clone, Debug)]
+pub struct ConfigOrigin {
+    /// The source of the value (ex: `"file"`, `"env"`, `"cli"`, `"api"`).
+    pub source: String,
+    /// Details on the source (ex: the configuration file path or the environment variable name).
+    pub detail: Option<String>,
+    /// When the value was set.
+    pub time: SystemTime,
+}
+
+/// A notification that the session with a peer was closed.
+#[derive(Clone, Debug)]
+pub struct PeerCloseEvent {
+    /// The id of the peer.
+    pub pid: PeerId,
+    /// What the peer is (router, peer or client).
+    pub whatami: WhatAmI,
+    /// Why the session was closed, to distinguish a graceful close from a network loss.
+    pub reason: CloseReason,
 }
 
 pub(crate) fn parse_mode(m: &str) -> Result<whatami::Type, ()> {
@@ -53,6 +114,35 @@ pub(crate) fn parse_mode(m: &str) -> Result<whatami::Type, ()> {
     }
 }
 
+// Parses a comma separated list of roles configured for `key`, ignoring the invalid ones.
+pub(crate) fn parse_roles(roles: &str, key: &str) -> WhatAmI {
+    roles
+        .split(',')
+        .filter_map(|role| match parse_mode(role.trim()) {
+            Ok(what) => Some(what),
+            Err(()) => {
+                log::warn!("Ignore invalid role in {}: {}", key, role);
+                None
+            }
+        })
+        .fold(0, |mask, what| mask | what)
+}
+
+// Parses a comma separated list of subnets configured for `key`, ignoring the invalid ones.
+pub(crate) fn parse_subnets(subnets: &str, key: &str) -> Vec<IpSubnet> {
+    subnets
+        .split(',')
+        .filter(|s| !s.trim().is_empty())
+        .filter_map(|s| match s.parse() {
+            Ok(subnet) => Some(subnet),
+            Err(err) => {
+                log::warn!("Ignore invalid subnet in {}: {}", key, err);
+                None
+            }
+        })
+        .collect()
+}
+
 #[derive(Clone)]
 pub struct Runtime {
     state: Arc<RuntimeState>,
@@ -106,6 +196,20 @@ impl Runtime {
         };
 
         let router = Arc::new(Router::new(pid.clone(), whatami, hlc.clone()));
+        if let Some(path) = config.get(&ZN_ROUTING_TRACE_KEY) {
+            #[cfg(feature = "routing_replay")]
+            {
+                log::info!("Record routing trace in {}", path);
+                let trace = RoutingTrace::create(path, &pid, whatami)?;
+                zwrite!(router.tables).trace = Some(Arc::new(trace));
+            }
+            #[cfg(not(feature = "routing_replay"))]
+            log::warn!(
+                "Ignore {}={}: zenoh is built without the routing_replay feature",
+                ZN_ROUTING_TRACE_STR,
+                path
+            );
+        }
 
         let handler = Arc::new(RuntimeSessionHandler {
             runtime: std::sync::RwLock::new(None),
@@ -124,9 +228,31 @@ impl Runtime {
                 pid,
                 whatami,
                 router,
-                config: config.clone(),
+                config: RwLock::new(config.clone()),
                 manager: session_manager,
                 hlc,
+                draining: AtomicBool::new(false),
+                drain_traffic: AtomicU64::new(0),
+                config_origins: Mutex::new(
+                    config
+                        .keys()
+                        .map(|key| {
+                            let origin = ConfigOrigin {
+                                source: "api".to_string(),
+                                detail: None,
+                                time: SystemTime::now(),
+                            };
+                            (*key, origin)
+                        })
+                        .collect(),
+                ),
+                scouting_cache: ScoutingCache::from_config(&config),
+                connect_retry_policy: ConnectRetryPolicy::from_config(&config),
+                connect_retries: Mutex::new(HashMap::new()),
+                close_listeners: Mutex::new(vec![]),
+                drain_listeners: Mutex::new(vec![]),
+                gossip_routers: Mutex::new(vec![]),
+                scouting_task: Mutex::new(None),
             }),
         };
         *handler.runtime.write().unwrap() = Some(runtime.clone());
@@ -154,6 +280,21 @@ impl Runtime {
                 routers_autoconnect_gossip,
             );
         }
+        if whatami == whatami::ROUTER {
+            LatestValueCache::start(&runtime.router, &config);
+            zwrite!(runtime.router.tables).quotas = Quotas::from_config(&config);
+        } else if config.get(&ZN_LATEST_VALUE_CACHE_KEY).is_some() {
+            log::warn!(
+                "{} is only supported by routers: ignored",
+                ZN_LATEST_VALUE_CACHE_STR
+            );
+        }
+        if whatami == whatami::CLIENT {
+            zwrite!(runtime.router.tables).publication_buffer =
+                PublicationBuffer::from_config(&config);
+        } else if config.get(&ZN_RECONNECT_KEY).is_some() {
+            log::warn!("{} is only supported by clients: ignored", ZN_RECONNECT_STR);
+        }
         match runtime.start().await {
             Ok(()) => Ok(runtime),
             Err(err) => Err(err),
@@ -165,6 +306,12 @@ impl Runtime {
         &self.manager
     }
 
+    /// Returns the current configuration, including the listeners and peers changes applied
+    /// by [`Runtime::reload_config`].
+    pub fn config(&self) -> ConfigProperties {
+        zread!(self.config).clone()
+    }
+
     pub async fn close(&self) -> ZResult<()> {
         log::trace!("Runtime::close())");
         for session in &mut self.manager().get_sessions() {
@@ -173,6 +320,120 @@ impl Runtime {
         Ok(())
     }
 
+    /// Puts the runtime in drain mode, to let it leave the system with minimal message loss:
+    /// - it stops answering to scouting and accepting new sessions, and closes its listeners;
+    /// - it waits for the connected clients to leave or for their traffic to quiesce, up to `timeout`;
+    /// - it notifies all its faces of its departure, closing the sessions with the clients
+    ///   then with the routers and peers with the [`DRAIN`](smsg::close_reason::DRAIN) close
+    ///   reason, letting the clients reconnect elsewhere and the neighbours re-route.
+    pub async fn drain(&self, timeout: Duration) -> ZResult<()> {
+        log::info!("Draining the runtime (timeout: {:?})", timeout);
+        self.draining.store(true, Ordering::Release);
+        for listener in self.manager().get_listeners() {
+            if let Err(e) = self.manager().del_listener(&listener).await {
+                log::warn!(
+                    "Unable to close listener {} while draining: {}",
+                    listener,
+                    e
+                );
+            }
+        }
+
+        let deadline = Instant::now() + timeout;
+        let quiet_period = Duration::from_millis(*DRAIN_QUIET_PERIOD);
+        let mut traffic = self.drain_traffic.load(Ordering::Acquire);
+        let mut quiet_since = Instant::now();
+        loop {
+            let clients = self
+                .manager()
+                .get_sessions()
+                .iter()
+                .filter(|s| is_client(s))
+                .count();
+            if clients == 0 {
+                log::debug!("No more clients connected: terminate the drain");
+                break;
+            }
+            let now = Instant::now();
+            let current = self.drain_traffic.load(Ordering::Acquire);
+            if current != traffic {
+                traffic = current;
+                quiet_since = now;
+            } else if now.duration_since(quiet_since) >= quiet_period {
+                log::debug!(
+                    "No more traffic from the {} clients still connected: terminate the drain",
+                    clients
+                );
+                break;
+            }
+            if now >= deadline {
+                log::debug!("Drain timeout with {} clients still connected", clients);
+                break;
+            }
+            task::sleep(Duration::from_millis(*DRAIN_CHECK_PERIOD)).await;
+        }
+
+        // Notify the departure to the clients first, so that they reconnect to another
+        // router while the routes through this one still exist
+        let (clients, others): (Vec<Session>, Vec<Session>) = self
+            .manager()
+            .get_sessions()
+            .into_iter()
+            .partition(is_client);
+        let mut res = Ok(());
+        for session in clients.iter().chain(others.iter()) {
+            if let Err(e) = session.close_with_reason(smsg::close_reason::DRAIN).await {
+                log::warn!("Unable to close session while draining: {}", e);
+                if res.is_ok() {
+                    res = Err(e);
+                }
+            }
+        }
+        zlock!(self.drain_listeners).retain(|sender| sender.send(()).is_ok());
+        res
+    }
+
+    /// Returns a channel receiving a notification each time a drain of the runtime completes
+    /// (see [`Runtime::drain`]), so that the process hosting it can exit.
+    pub fn drain_events(&self) -> flume::Receiver<()> {
+        let (sender, receiver) = flume::unbounded();
+        zlock!(self.drain_listeners).push(sender);
+        receiver
+    }
+
+    /// Returns a channel receiving a [`PeerCloseEvent`] each time the session with a peer is closed.
+    pub fn peer_close_events(&self) -> flume::Receiver<PeerCloseEvent> {
+        let (sender, receiver) = flume::unbounded();
+        zlock!(self.close_listeners).push(sender);
+        receiver
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::Acquire)
+    }
+
+    /// Records where the value of the configuration `key` comes from.
+    pub fn set_config_origin(&self, key: u64, source: &str, detail: Option<String>) {
+        let origin = ConfigOrigin {
+            source: source.to_string(),
+            detail,
+            time: SystemTime::now(),
+        };
+        zlock!(self.config_origins).insert(key, origin);
+    }
+
+    /// Returns where the value of the configuration `key` comes from,
+    /// or `None` if the key is not configured.
+    pub fn get_config_origin(&self, key: u64) -> Option<ConfigOrigin> {
+        zlock!(self.config_origins).get(&key).cloned()
+    }
+
+    /// Returns the state of the connection retries to the configured peers that are
+    /// currently unreachable.
+    pub fn get_connect_retries(&self) -> HashMap<Locator, ConnectRetryState> {
+        zlock!(self.connect_retries).clone()
+    }
+
     pub fn get_pid_str(&self) -> String {
         self.pid.to_string()
     }
@@ -182,6 +443,12 @@ impl Runtime {
     }
 }
 
+fn is_client(session: &Session) -> bool {
+    session
+        .get_whatami()
+        .map_or(false, |w| w == whatami::CLIENT)
+}
+
 struct RuntimeSessionHandler {
     runtime: std::sync::RwLock<Option<Runtime>>,
 }
@@ -189,8 +456,13 @@ struct RuntimeSessionHandler {
 impl SessionHandler for RuntimeSessionHandler {
     fn new_session(&self, session: Session) -> ZResult<Arc<dyn SessionEventHandler + Send + Sync>> {
         match &*self.runtime.read().unwrap() {
+            Some(runtime) if runtime.is_draining() => zerror!(ZErrorKind::Other {
+                descr: "Runtime is draining!".to_string()
+            }),
             Some(runtime) => Ok(Arc::new(RuntimeSession {
                 runtime: runtime.clone(),
+                pid: session.get_pid()?,
+                whatami: session.get_whatami()?,
                 locator: std::sync::RwLock::new(None),
                 sub_event_handler: runtime.router.new_session(session).unwrap(),
             })),
@@ -203,12 +475,17 @@ impl SessionHandler for RuntimeSessionHandler {
 
 pub(super) struct RuntimeSession {
     pub(super) runtime: Runtime,
+    pub(super) pid: PeerId,
+    pub(super) whatami: WhatAmI,
     pub(super) locator: std::sync::RwLock<Option<Locator>>,
     pub(super) sub_event_handler: Arc<LinkStateInterceptor>,
 }
 
 impl SessionEventHandler for RuntimeSession {
     fn handle_message(&self, msg: ZenohMessage) -> ZResult<()> {
+        if self.whatami == whatami::CLIENT && self.runtime.is_draining() {
+            self.runtime.drain_traffic.fetch_add(1, Ordering::AcqRel);
+        }
         // critical path shortcut
         if msg.reply_context.is_none() {
             if let ZenohBody::Data(Data {
@@ -253,8 +530,14 @@ impl SessionEventHandler for RuntimeSession {
         Runtime::closing_session(self);
     }
 
-    fn closed(&self) {
-        self.sub_event_handler.closed()
+    fn closed(&self, reason: CloseReason) {
+        self.sub_event_handler.closed();
+        let event = PeerCloseEvent {
+            pid: self.pid.clone(),
+            whatami: self.whatami,
+            reason,
+        };
+        zlock!(self.runtime.close_listeners).retain(|sender| sender.send(event.clone()).is_ok());
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -13,64 +13,136 @@
 //
 use super::protocol::core::{whatami, PeerId, WhatAmI};
 use super::protocol::io::{WBuf, ZBuf};
-use super::protocol::link::Locator;
+use super::protocol::link::{EndPoint, LinkConfig, Locator};
 use super::protocol::proto::{Hello, Scout, SessionBody, SessionMessage};
-use super::protocol::session::Session;
-use super::{Runtime, RuntimeSession};
+use super::protocol::session::{PowerProfile, Session};
+use super::{parse_roles, parse_subnets, ConnectRetryState, Runtime, RuntimeSession};
 use async_std::net::UdpSocket;
+use async_std::task::JoinHandle;
 use futures::prelude::*;
 use socket2::{Domain, Socket, Type};
+use std::collections::HashMap;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 use zenoh_util::core::{ZError, ZErrorKind, ZResult};
+use zenoh_util::net::IpSubnet;
 use zenoh_util::properties::config::*;
-use zenoh_util::zerror;
+use zenoh_util::{zerror, zerror2, zlock, zread, zwrite};
 
 const RCV_BUF_SIZE: usize = 65536;
 const SEND_BUF_INITIAL_SIZE: usize = 8;
 const SCOUT_INITIAL_PERIOD: u64 = 1000; //ms
 const SCOUT_MAX_PERIOD: u64 = 8000; //ms
 const SCOUT_PERIOD_INCREASE_FACTOR: u64 = 2;
-const CONNECTION_RETRY_INITIAL_PERIOD: u64 = 1000; //ms
-const CONNECTION_RETRY_MAX_PERIOD: u64 = 4000; //ms
-const CONNECTION_RETRY_PERIOD_INCREASE_FACTOR: u64 = 2;
-const ROUTER_DEFAULT_LISTENER: &str = "tcp/0.0.0.0:7447";
-const PEER_DEFAULT_LISTENER: &str = "tcp/0.0.0.0:0";
+const TEMPLATE_DEFAULT_PORT: u16 = 7447;
+const IFACE_WATCH_PERIOD: u64 = 2000; //ms
+const RESPONSE_SOURCES_MAX: usize = 1024;
 
 pub enum Loop {
     Continue,
     Break,
 }
 
+// The filtering and rate limiting of the responses to the scouting probes.
+pub(super) struct ResponsePolicy {
+    period: Option<Duration>,
+    what: WhatAmI,
+    subnets: Vec<IpSubnet>,
+    last_responses: HashMap<IpAddr, Instant>,
+}
+
+impl ResponsePolicy {
+    pub(super) fn from_config(config: &ConfigProperties) -> ResponsePolicy {
+        let period = config
+            .get_or(
+                &ZN_SCOUTING_RESPONSE_PERIOD_KEY,
+                ZN_SCOUTING_RESPONSE_PERIOD_DEFAULT,
+            )
+            .parse::<f64>()
+            .ok()
+            .filter(|secs| *secs >= 0.0 && secs.is_finite())
+            .map(Duration::from_secs_f64)
+            .filter(|period| *period > Duration::from_secs(0));
+        let what = match config.get(&ZN_SCOUTING_RESPONSE_WHAT_KEY) {
+            Some(roles) => parse_roles(roles, ZN_SCOUTING_RESPONSE_WHAT_STR),
+            None => whatami::ROUTER | whatami::PEER | whatami::CLIENT,
+        };
+        let subnets = parse_subnets(
+            config.get_or(&ZN_SCOUTING_RESPONSE_SUBNETS_KEY, ""),
+            ZN_SCOUTING_RESPONSE_SUBNETS_STR,
+        );
+        ResponsePolicy {
+            period,
+            what,
+            subnets,
+            last_responses: HashMap::new(),
+        }
+    }
+
+    // Answers a same source at most once per `period`, or per the configured period if longer.
+    pub(super) fn with_min_period(mut self, period: Duration) -> ResponsePolicy {
+        self.period = Some(self.period.map_or(period, |p| p.max(period)));
+        self
+    }
+
+    // Returns true if a scouting probe looking for `what` from `source` must be answered.
+    pub(super) fn accept(&mut self, what: WhatAmI, source: &IpAddr) -> bool {
+        if what & !self.what != 0 {
+            log::trace!("Ignore scouting for {} from {}", what, source);
+            return false;
+        }
+        if !self.subnets.is_empty() && !self.subnets.iter().any(|s| s.contains(source)) {
+            log::trace!("Ignore scouting from {}: not in an allowed subnet", source);
+            return false;
+        }
+        if let Some(period) = self.period {
+            let now = Instant::now();
+            if let Some(last) = self.last_responses.get(source) {
+                if now.duration_since(*last) < period {
+                    log::trace!("Ignore scouting from {}: already answered", source);
+                    return false;
+                }
+            }
+            if self.last_responses.len() >= RESPONSE_SOURCES_MAX {
+                self.last_responses
+                    .retain(|_, last| now.duration_since(*last) < period);
+            }
+            self.last_responses.insert(*source, now);
+        }
+        true
+    }
+}
+
 impl Runtime {
     pub async fn start(&mut self) -> ZResult<()> {
         match self.whatami {
-            whatami::CLIENT => self.start_client().await,
-            whatami::PEER => self.start_peer().await,
-            whatami::ROUTER => self.start_router().await,
+            whatami::CLIENT => self.start_client().await?,
+            whatami::PEER => self.start_peer().await?,
+            whatami::ROUTER => self.start_router().await?,
             _ => {
                 log::error!("Unknown mode");
-                zerror!(ZErrorKind::Other {
+                return zerror!(ZErrorKind::Other {
                     descr: "Unknown mode".to_string()
-                })
+                });
             }
         }
+        self.watch_local_addresses();
+        Ok(())
     }
 
     async fn start_client(&self) -> ZResult<()> {
-        let config = &self.config;
-        let peers = config
-            .get_or(&ZN_PEER_KEY, "")
-            .split(',')
-            .filter_map(|s| match s.trim() {
-                "" => None,
-                s => Some(s.parse().unwrap()),
-            })
-            .collect::<Vec<Locator>>();
-        let scouting = config
+        let config = zread!(self.config).clone();
+        let peers = self.configure_peers(&config)?;
+        let mut scouting = config
             .get_or(&ZN_MULTICAST_SCOUTING_KEY, ZN_MULTICAST_SCOUTING_DEFAULT)
             .to_lowercase()
             == ZN_TRUE;
+        // The multicast scouting is suspended in low power, only the configured peers
+        // and the scouting cache are used
+        if scouting && self.manager().get_power_profile() != PowerProfile::Default {
+            log::debug!("Multicast scouting suspended in low power");
+            scouting = false;
+        }
         let addr = config
             .get_or(&ZN_MULTICAST_ADDRESS_KEY, ZN_MULTICAST_ADDRESS_DEFAULT)
             .parse()
@@ -84,6 +156,26 @@ impl Runtime {
         );
         match peers.len() {
             0 => {
+                if self.connect_cached_router().await {
+                    if scouting {
+                        let this = self.clone();
+                        let ifaces = ifaces.to_string();
+                        async_std::task::spawn(async move {
+                            this.refresh_scouting_cache(&ifaces, whatami::ROUTER, &addr, timeout)
+                                .await
+                        });
+                    }
+                    return Ok(());
+                }
+                if let Some(seeds) = config.get(&ZN_GOSSIP_SEEDS_KEY) {
+                    match self.connect_gossip(seeds, timeout).await {
+                        Ok(()) => return Ok(()),
+                        Err(err) if scouting => {
+                            log::info!("Unable to connect by gossip ({}), scout for router", err)
+                        }
+                        Err(err) => return Err(err),
+                    }
+                }
                 if scouting {
                     log::info!("Scouting for router ...");
                     let ifaces = Runtime::get_interfaces(ifaces);
@@ -128,36 +220,14 @@ impl Runtime {
     }
 
     async fn start_peer(&self) -> ZResult<()> {
-        let config = &self.config;
-        let listeners = config
-            .get_or(&ZN_LISTENER_KEY, PEER_DEFAULT_LISTENER)
-            .split(',')
-            .filter_map(|s| match s.trim() {
-                "" => None,
-                s => Some(s.parse().unwrap()),
-            })
-            .collect::<Vec<Locator>>();
-        let peers = config
-            .get_or(&ZN_PEER_KEY, "")
-            .split(',')
-            .filter_map(|s| match s.trim() {
-                "" => None,
-                s => Some(s.parse().unwrap()),
-            })
-            .collect::<Vec<Locator>>();
-        let scouting = config
-            .get_or(&ZN_MULTICAST_SCOUTING_KEY, ZN_MULTICAST_SCOUTING_DEFAULT)
-            .to_lowercase()
-            == ZN_TRUE;
+        let config = zread!(self.config).clone();
+        let listeners_config = &self.configured_listeners();
+        let listeners = Runtime::expand_listeners(listeners_config)?;
+        let peers = self.configure_peers(&config)?;
         let peers_autoconnect = config
             .get_or(&ZN_PEERS_AUTOCONNECT_KEY, ZN_PEERS_AUTOCONNECT_DEFAULT)
             .to_lowercase()
             == ZN_TRUE;
-        let addr = config
-            .get_or(&ZN_MULTICAST_ADDRESS_KEY, ZN_MULTICAST_ADDRESS_DEFAULT)
-            .parse()
-            .unwrap();
-        let ifaces = config.get_or(&ZN_MULTICAST_INTERFACE_KEY, ZN_MULTICAST_INTERFACE_DEFAULT);
         let delay = std::time::Duration::from_secs_f64(
             config
                 .get_or(&ZN_SCOUTING_DELAY_KEY, ZN_SCOUTING_DELAY_DEFAULT)
@@ -172,60 +242,22 @@ impl Runtime {
             async_std::task::spawn(async move { this.peer_connector(peer).await });
         }
 
-        if scouting {
-            let ifaces = Runtime::get_interfaces(ifaces);
-            let mcast_socket = Runtime::bind_mcast_port(&addr, &ifaces).await?;
-            if !ifaces.is_empty() {
-                let sockets: Vec<UdpSocket> = ifaces
-                    .into_iter()
-                    .filter_map(|iface| Runtime::bind_ucast_port(iface).ok())
-                    .collect();
-                if !sockets.is_empty() {
-                    let this = self.clone();
-                    async_std::task::spawn(async move {
-                        async_std::prelude::FutureExt::race(
-                            this.responder(&mcast_socket, &sockets),
-                            this.connect_all(
-                                &sockets,
-                                if peers_autoconnect {
-                                    whatami::PEER | whatami::ROUTER
-                                } else {
-                                    whatami::ROUTER
-                                },
-                                &addr,
-                            ),
-                        )
-                        .await;
-                    });
-                }
-            }
-        }
+        self.connect_cached_peers(if peers_autoconnect {
+            whatami::PEER | whatami::ROUTER
+        } else {
+            whatami::ROUTER
+        });
+
+        *zlock!(self.scouting_task) = self.start_scouting().await?;
         async_std::task::sleep(delay).await;
         Ok(())
     }
 
     async fn start_router(&self) -> ZResult<()> {
-        let config = &self.config;
-        let listeners = config
-            .get_or(&ZN_LISTENER_KEY, ROUTER_DEFAULT_LISTENER)
-            .split(',')
-            .filter_map(|s| match s.trim() {
-                "" => None,
-                s => Some(s.parse().unwrap()),
-            })
-            .collect::<Vec<Locator>>();
-        let peers = config
-            .get_or(&ZN_PEER_KEY, "")
-            .split(',')
-            .filter_map(|s| match s.trim() {
-                "" => None,
-                s => Some(s.parse().unwrap()),
-            })
-            .collect::<Vec<Locator>>();
-        let scouting = config
-            .get_or(&ZN_MULTICAST_SCOUTING_KEY, ZN_MULTICAST_SCOUTING_DEFAULT)
-            .to_lowercase()
-            == ZN_TRUE;
+        let config = zread!(self.config).clone();
+        let listeners_config = &self.configured_listeners();
+        let listeners = Runtime::expand_listeners(listeners_config)?;
+        let peers = self.configure_peers(&config)?;
         let routers_autoconnect_multicast = config
             .get_or(
                 &ZN_ROUTERS_AUTOCONNECT_MULTICAST_KEY,
@@ -233,50 +265,128 @@ impl Runtime {
             )
             .to_lowercase()
             == ZN_TRUE;
-        let addr = config
-            .get_or(&ZN_MULTICAST_ADDRESS_KEY, ZN_MULTICAST_ADDRESS_DEFAULT)
-            .parse()
-            .unwrap();
-        let ifaces = config.get_or(&ZN_MULTICAST_INTERFACE_KEY, ZN_MULTICAST_INTERFACE_DEFAULT);
 
         self.bind_listeners(&listeners).await?;
+        if let Some(listener) = config.get(&ZN_GOSSIP_LISTENER_KEY) {
+            self.start_gossip_responder(listener).await?;
+        }
 
         for peer in peers {
             let this = self.clone();
             async_std::task::spawn(async move { this.peer_connector(peer).await });
         }
 
-        if scouting {
-            let ifaces = Runtime::get_interfaces(ifaces);
-            let mcast_socket = Runtime::bind_mcast_port(&addr, &ifaces).await?;
-            if !ifaces.is_empty() {
-                let sockets: Vec<UdpSocket> = ifaces
-                    .into_iter()
-                    .filter_map(|iface| Runtime::bind_ucast_port(iface).ok())
-                    .collect();
-                if !sockets.is_empty() {
-                    let this = self.clone();
-                    if routers_autoconnect_multicast {
-                        async_std::prelude::FutureExt::race(
-                            this.responder(&mcast_socket, &sockets),
-                            this.connect_all(&sockets, whatami::ROUTER, &addr),
-                        )
-                        .await;
-                    } else {
-                        async_std::task::spawn(async move {
-                            this.responder(&mcast_socket, &sockets).await;
-                        });
-                    }
+        if routers_autoconnect_multicast {
+            self.connect_cached_peers(whatami::ROUTER);
+        }
+
+        *zlock!(self.scouting_task) = self.start_scouting().await?;
+
+        Ok(())
+    }
+
+    /// Binds the scouting sockets on the configured multicast interfaces and spawns the task
+    /// answering to the scouting and, for the peers and the routers configured to autoconnect,
+    /// connecting to the scouted nodes. Returns `None` if the multicast scouting is disabled
+    /// or if no interface could be bound.
+    async fn start_scouting(&self) -> ZResult<Option<JoinHandle<()>>> {
+        let config = zread!(self.config).clone();
+        let scouting = config
+            .get_or(&ZN_MULTICAST_SCOUTING_KEY, ZN_MULTICAST_SCOUTING_DEFAULT)
+            .to_lowercase()
+            == ZN_TRUE;
+        if !scouting || self.whatami == whatami::CLIENT {
+            return Ok(None);
+        }
+        let autoconnect = if self.whatami == whatami::PEER {
+            if config
+                .get_or(&ZN_PEERS_AUTOCONNECT_KEY, ZN_PEERS_AUTOCONNECT_DEFAULT)
+                .to_lowercase()
+                == ZN_TRUE
+            {
+                Some(whatami::PEER | whatami::ROUTER)
+            } else {
+                Some(whatami::ROUTER)
+            }
+        } else if config
+            .get_or(
+                &ZN_ROUTERS_AUTOCONNECT_MULTICAST_KEY,
+                ZN_ROUTERS_AUTOCONNECT_MULTICAST_DEFAULT,
+            )
+            .to_lowercase()
+            == ZN_TRUE
+        {
+            Some(whatami::ROUTER)
+        } else {
+            None
+        };
+        let addr: SocketAddr = config
+            .get_or(&ZN_MULTICAST_ADDRESS_KEY, ZN_MULTICAST_ADDRESS_DEFAULT)
+            .parse()
+            .unwrap();
+        let ifaces = Runtime::get_interfaces(
+            config.get_or(&ZN_MULTICAST_INTERFACE_KEY, ZN_MULTICAST_INTERFACE_DEFAULT),
+        );
+        let mcast_socket = Runtime::bind_mcast_port(&addr, &ifaces).await?;
+        let sockets: Vec<UdpSocket> = ifaces
+            .into_iter()
+            .filter_map(|iface| Runtime::bind_ucast_port(iface).ok())
+            .collect();
+        if sockets.is_empty() {
+            return Ok(None);
+        }
+        let this = self.clone();
+        Ok(Some(async_std::task::spawn(async move {
+            match autoconnect {
+                Some(what) => {
+                    async_std::prelude::FutureExt::race(
+                        this.responder(&mcast_socket, &sockets),
+                        this.connect_all(&sockets, what, &addr),
+                    )
+                    .await
                 }
+                None => this.responder(&mcast_socket, &sockets).await,
             }
+        })))
+    }
+
+    /// Stops the multicast scouting and starts it again on the current interfaces.
+    async fn restart_scouting(&self) {
+        let task = zlock!(self.scouting_task).take();
+        if let Some(task) = task {
+            task.cancel().await;
+        }
+        match self.start_scouting().await {
+            Ok(task) => *zlock!(self.scouting_task) = task,
+            Err(err) => log::warn!("Unable to restart the multicast scouting : {}", err),
         }
+    }
 
-        Ok(())
+    /// Closes the sessions whose links were all established from one of the `vanished`
+    /// addresses, for them to be re-established from the remaining ones.
+    async fn close_vanished_sessions(&self, vanished: &[IpAddr]) {
+        if vanished.is_empty() {
+            return;
+        }
+        for session in self.manager().get_sessions() {
+            let stale = session.get_links().map_or(false, |links| {
+                !links.is_empty()
+                    && links.iter().all(|link| {
+                        link.get_src()
+                            .get_ip()
+                            .map_or(false, |ip| vanished.contains(&ip))
+                    })
+            });
+            if stale {
+                log::info!("Closing session {:?} from a vanished address", session);
+                let _ = session.close().await;
+            }
+        }
     }
 
-    async fn bind_listeners(&self, listeners: &[Locator]) -> ZResult<()> {
+    async fn bind_listeners(&self, listeners: &[EndPoint]) -> ZResult<()> {
         for listener in listeners {
-            match self.manager().add_listener(&listener).await {
+            match self.add_listener(&listener).await {
                 Ok(listener) => log::debug!("Listener {} added", listener),
                 Err(err) => {
                     log::error!("Unable to open listener {} : {}", listener, err);
@@ -295,6 +405,370 @@ impl Runtime {
         Ok(())
     }
 
+    /// Opens a listener on `endpoint`, and registers the session parameters it overrides
+    /// for the links it accepts.
+    async fn add_listener(&self, endpoint: &EndPoint) -> ZResult<Locator> {
+        self.manager()
+            .set_link_config(&endpoint.locator, endpoint.config.clone());
+        let locator = self.manager().add_listener(&endpoint.locator).await?;
+        if locator != endpoint.locator {
+            // e.g. a listener on port 0 is bound to an ephemeral port
+            self.manager()
+                .set_link_config(&locator, endpoint.config.clone());
+        }
+        Ok(locator)
+    }
+
+    async fn del_listener(&self, endpoint: &EndPoint) -> ZResult<()> {
+        self.manager()
+            .set_link_config(&endpoint.locator, LinkConfig::default());
+        self.manager().del_listener(&endpoint.locator).await
+    }
+
+    /// Spawns a task that periodically checks the local addresses and, when they change:
+    ///  - re-expands the configured listener templates, closing the listeners on vanished
+    ///    addresses and opening the ones on new addresses;
+    ///  - closes the sessions established from vanished addresses, for them to be
+    ///    re-established (a client scouts again for a router);
+    ///  - advertises the new locators in the link states sent to the routers and peers;
+    ///  - restarts the multicast scouting on the current interfaces.
+    ///
+    /// The task stops when the runtime is drained.
+    fn watch_local_addresses(&self) {
+        let this = self.clone();
+        async_std::task::spawn(async move {
+            // clients don't listen
+            let expand = |listeners: &str| {
+                if this.whatami == whatami::CLIENT {
+                    return vec![];
+                }
+                listeners
+                    .split(',')
+                    .map(|s| s.trim())
+                    .filter(|s| Runtime::is_listener_template(s))
+                    .flat_map(|s| {
+                        Runtime::expand_listener(s).unwrap_or_else(|err| {
+                            log::warn!("Unable to expand listener {} : {}", s, err);
+                            vec![]
+                        })
+                    })
+                    .collect::<Vec<EndPoint>>()
+            };
+            let addresses = || {
+                let mut addresses = zenoh_util::net::get_local_addresses().unwrap_or_default();
+                addresses.sort();
+                addresses
+            };
+            let mut listeners = this.configured_listeners();
+            let mut opened = expand(&listeners);
+            let mut known = addresses();
+            loop {
+                async_std::task::sleep(Duration::from_millis(IFACE_WATCH_PERIOD)).await;
+                if this.is_draining() {
+                    break;
+                }
+                // the listeners reconfigured by reload_config are already opened
+                let configured = this.configured_listeners();
+                if configured != listeners {
+                    opened = expand(&configured);
+                    listeners = configured;
+                }
+                let current = addresses();
+                if current == known {
+                    continue;
+                }
+                log::info!("Local addresses changed: {:?} -> {:?}", known, current);
+                let vanished = known
+                    .iter()
+                    .filter(|addr| !current.contains(addr))
+                    .cloned()
+                    .collect::<Vec<IpAddr>>();
+                known = current;
+
+                let expanded = expand(&listeners);
+                for endpoint in opened.iter().filter(|e| !expanded.contains(e)) {
+                    match this.del_listener(endpoint).await {
+                        Ok(()) => log::info!("Listener {} closed", endpoint.locator),
+                        Err(err) => {
+                            log::debug!("Unable to close listener {} : {}", endpoint.locator, err)
+                        }
+                    }
+                }
+                let mut reopened = vec![];
+                for endpoint in expanded {
+                    if opened.contains(&endpoint) {
+                        reopened.push(endpoint);
+                        continue;
+                    }
+                    match this.add_listener(&endpoint).await {
+                        Ok(locator) => {
+                            log::info!("Listener {} added", locator);
+                            reopened.push(EndPoint {
+                                locator,
+                                config: endpoint.config,
+                            });
+                        }
+                        Err(err) => log::error!("Unable to open listener {} : {}", endpoint, err),
+                    }
+                }
+                opened = reopened;
+
+                this.close_vanished_sessions(&vanished).await;
+                this.router.refresh_locators();
+                this.restart_scouting().await;
+            }
+        });
+    }
+
+    /// Returns the configured listeners, or the default ones for the runtime's mode.
+    fn configured_listeners(&self) -> String {
+        let mode = whatami::to_string(self.whatami).to_lowercase();
+        let config = zread!(self.config);
+        config
+            .get(&ZN_LISTENER_KEY)
+            .map(|s| &s[..])
+            .or_else(|| config_default(ZN_LISTENER_KEY, &mode))
+            .unwrap_or("")
+            .to_string()
+    }
+
+    fn parse_peer_endpoints(config: &ConfigProperties) -> ZResult<Vec<EndPoint>> {
+        config
+            .get_or(&ZN_PEER_KEY, "")
+            .split(',')
+            .filter_map(|s| match s.trim() {
+                "" => None,
+                s => Some(s.parse()),
+            })
+            .collect()
+    }
+
+    fn parse_peers(config: &ConfigProperties) -> ZResult<Vec<Locator>> {
+        Ok(Runtime::parse_peer_endpoints(config)?
+            .into_iter()
+            .map(|endpoint| endpoint.locator)
+            .collect())
+    }
+
+    /// Returns the peers configured in `config`, after having registered the session
+    /// parameters they override for the links opened towards them.
+    fn configure_peers(&self, config: &ConfigProperties) -> ZResult<Vec<Locator>> {
+        let endpoints = Runtime::parse_peer_endpoints(config)?;
+        for endpoint in &endpoints {
+            self.manager()
+                .set_link_config(&endpoint.locator, endpoint.config.clone());
+        }
+        Ok(endpoints
+            .into_iter()
+            .map(|endpoint| endpoint.locator)
+            .collect())
+    }
+
+    fn is_configured_peer(&self, peer: &Locator) -> bool {
+        Runtime::parse_peers(&*zread!(self.config))
+            .map(|peers| peers.contains(peer))
+            .unwrap_or(false)
+    }
+
+    /// Applies a new configuration to the running runtime, without closing the sessions that
+    /// are not impacted:
+    ///  - the listeners that are no longer configured are closed and the new ones are opened;
+    ///  - the sessions with the peers that are no longer configured are closed and the new
+    ///    peers are connected.
+    ///
+    /// The other configuration keys can't be changed without a restart and are left unchanged.
+    /// Returns the keys whose value differs in `config` but that were not applied.
+    pub async fn reload_config(&self, config: ConfigProperties) -> ZResult<Vec<u64>> {
+        // Check the new configuration before changing anything
+        let new_peers = Runtime::parse_peers(&config)?;
+        let mode = whatami::to_string(self.whatami).to_lowercase();
+        let new_listeners = config
+            .get(&ZN_LISTENER_KEY)
+            .map(|s| &s[..])
+            .or_else(|| config_default(ZN_LISTENER_KEY, &mode))
+            .unwrap_or("")
+            .to_string();
+        let new = Runtime::expand_listeners(&new_listeners)?;
+
+        let old_config = zread!(self.config).clone();
+        let old_listeners = self.configured_listeners();
+        let old_peers = Runtime::parse_peers(&old_config).unwrap_or_default();
+
+        let mut ignored: Vec<u64> = old_config
+            .keys()
+            .chain(config.keys())
+            .filter(|key| **key != ZN_LISTENER_KEY && **key != ZN_PEER_KEY)
+            .filter(|key| old_config.get(key) != config.get(key))
+            .cloned()
+            .collect();
+        ignored.sort_unstable();
+        ignored.dedup();
+
+        {
+            let mut guard = zwrite!(self.config);
+            for key in [ZN_LISTENER_KEY, ZN_PEER_KEY].iter() {
+                match config.get(key) {
+                    Some(value) => guard.insert(*key, value.clone()),
+                    None => guard.remove(key),
+                };
+            }
+        }
+
+        // Listeners (clients don't listen)
+        if self.whatami != whatami::CLIENT && new_listeners != old_listeners {
+            // the old listeners were expanded without error when opened, but an interface
+            // may have vanished since
+            let old = old_listeners
+                .split(',')
+                .flat_map(|s| Runtime::expand_listener(s.trim()).unwrap_or_default())
+                .collect::<Vec<EndPoint>>();
+            for endpoint in old.iter().filter(|e| !new.contains(e)) {
+                match self.del_listener(endpoint).await {
+                    Ok(()) => log::info!("Listener {} closed", endpoint.locator),
+                    Err(err) => {
+                        log::warn!("Unable to close listener {} : {}", endpoint.locator, err)
+                    }
+                }
+            }
+            for endpoint in new.iter().filter(|e| !old.contains(e)) {
+                match self.add_listener(endpoint).await {
+                    Ok(locator) => log::info!("Listener {} added", locator),
+                    Err(err) => log::error!("Unable to open listener {} : {}", endpoint, err),
+                }
+            }
+        }
+
+        // Peers
+        for peer in old_peers.iter().filter(|p| !new_peers.contains(p)) {
+            self.manager().set_link_config(peer, LinkConfig::default());
+        }
+        self.configure_peers(&config)?;
+        for session in self.manager().get_sessions() {
+            if let Ok(Some(callback)) = session.get_callback() {
+                if let Some(orch_session) = callback.as_any().downcast_ref::<RuntimeSession>() {
+                    let removed = match &*zread!(orch_session.locator) {
+                        Some(locator) => !new_peers.contains(locator),
+                        None => false,
+                    };
+                    if removed {
+                        *zwrite!(orch_session.locator) = None;
+                        log::info!("Closing session with unconfigured peer {:?}", session);
+                        let _ = session.close().await;
+                    }
+                }
+            }
+        }
+        if self.whatami != whatami::CLIENT {
+            for peer in new_peers.into_iter().filter(|p| !old_peers.contains(p)) {
+                let this = self.clone();
+                async_std::task::spawn(async move { this.peer_connector(peer).await });
+            }
+        }
+
+        Ok(ignored)
+    }
+
+    fn is_listener_template(listener: &str) -> bool {
+        let (listener, _) = EndPoint::split(listener);
+        match listener.find('/') {
+            Some(i) => {
+                let addr = &listener[i + 1..];
+                addr.starts_with("iface:") || addr == "auto" || addr.starts_with("auto:")
+            }
+            None => false,
+        }
+    }
+
+    /// Expands a listener into concrete endpoints. Besides plain locators, 2 templates are accepted:
+    ///  - `<proto>/iface:<name>:<port>` : one locator per address of the interface `<name>`.
+    ///  - `<proto>/auto[:<port>]` : one locator per active multicast interface.
+    ///
+    /// If the port is omitted, 7447 is used. The IPv6 link-local addresses are skipped,
+    /// their scope id can't be expressed in a locator. The endpoint metadata (e.g. `#lease=1000`)
+    /// of a template applies to all its expanded locators.
+    ///
+    /// Fails if the listener is invalid, or if the interface of a template doesn't exist.
+    pub fn expand_listener(listener: &str) -> ZResult<Vec<EndPoint>> {
+        let (listener, metadata) = EndPoint::split(listener);
+        if listener.is_empty() {
+            return Ok(vec![]);
+        }
+        let config = match metadata {
+            Some(metadata) => metadata.parse::<LinkConfig>()?,
+            None => LinkConfig::default(),
+        };
+        let endpoint = |locator: Locator| EndPoint {
+            locator,
+            config: config.clone(),
+        };
+        let (proto, addr) = match listener.find('/') {
+            Some(i) => (&listener[..i], &listener[i + 1..]),
+            None => return Ok(vec![endpoint(listener.parse()?)]),
+        };
+        let (ips, port) = if let Some(iface) = addr.strip_prefix("iface:") {
+            let (name, port) = match iface.rfind(':') {
+                Some(i) => (&iface[..i], &iface[i + 1..]),
+                None => (iface, ""),
+            };
+            let ips = zenoh_util::net::get_interface_addresses(name).map_err(|err| {
+                zerror2!(
+                    ZErrorKind::InvalidLocator {
+                        descr: format!("Unable to find interface {} for {}", name, listener)
+                    },
+                    err
+                )
+            })?;
+            if ips.is_empty() {
+                log::warn!("No address found on interface {} for {}", name, listener);
+            }
+            (ips, port)
+        } else if addr == "auto" || addr.starts_with("auto:") {
+            (
+                zenoh_util::net::get_multicast_interfaces(),
+                addr.strip_prefix("auto:").unwrap_or(""),
+            )
+        } else {
+            return Ok(vec![endpoint(listener.parse()?)]);
+        };
+        let port = if port.is_empty() {
+            TEMPLATE_DEFAULT_PORT
+        } else {
+            port.parse::<u16>().map_err(|_| {
+                zerror2!(ZErrorKind::InvalidLocator {
+                    descr: format!("Invalid port in {}", listener)
+                })
+            })?
+        };
+        let endpoints = ips
+            .into_iter()
+            .filter(|ip| {
+                // a locator has no room for the scope id a link-local address requires
+                let link_local = zenoh_util::net::is_ipv6_link_local(ip);
+                if link_local {
+                    log::debug!("Link-local address {} skipped for {}", ip, listener);
+                }
+                !link_local
+            })
+            .map(|ip| {
+                format!("{}/{}", proto, SocketAddr::new(ip, port))
+                    .parse()
+                    .map(endpoint)
+            })
+            .collect::<ZResult<Vec<EndPoint>>>()?;
+        log::debug!("Listener {} expanded to {:?}", listener, endpoints);
+        Ok(endpoints)
+    }
+
+    /// Expands the comma separated `listeners` into concrete endpoints
+    /// (see [`Runtime::expand_listener()`]).
+    pub fn expand_listeners(listeners: &str) -> ZResult<Vec<EndPoint>> {
+        let mut endpoints = vec![];
+        for listener in listeners.split(',') {
+            endpoints.extend(Runtime::expand_listener(listener.trim())?);
+        }
+        Ok(endpoints)
+    }
+
     pub fn get_interfaces(names: &str) -> Vec<IpAddr> {
         if names == "auto" {
             let ifaces = zenoh_util::net::get_multicast_interfaces();
@@ -463,11 +937,20 @@ impl Runtime {
     }
 
     async fn peer_connector(&self, peer: Locator) {
-        let mut delay = CONNECTION_RETRY_INITIAL_PERIOD;
+        let policy = &self.connect_retry_policy;
+        let mut delay = policy.initial;
+        let mut attempts = 0;
         loop {
+            // the peer was removed from the configuration by reload_config
+            if !self.is_configured_peer(&peer) {
+                log::info!("Stop connecting to peer {}: no longer configured", peer);
+                zlock!(self.connect_retries).remove(&peer);
+                break;
+            }
             log::trace!("Trying to connect to configured peer {}", peer);
             if let Ok(session) = self.manager().open_session(&peer).await {
                 log::debug!("Successfully connected to configured peer {}", peer);
+                zlock!(self.connect_retries).remove(&peer);
                 if let Some(orch_session) = session
                     .get_callback()
                     .unwrap()
@@ -479,16 +962,23 @@ impl Runtime {
                 }
                 break;
             }
+            attempts += 1;
+            let jittered = policy.jittered(delay);
             log::debug!(
-                "Unable to connect to configured peer {}. Retry in {} ms.",
+                "Unable to connect to configured peer {}. Retry in {:?}.",
                 peer,
-                delay
+                jittered
             );
-            async_std::task::sleep(Duration::from_millis(delay)).await;
-            delay *= CONNECTION_RETRY_PERIOD_INCREASE_FACTOR;
-            if delay > CONNECTION_RETRY_MAX_PERIOD {
-                delay = CONNECTION_RETRY_MAX_PERIOD;
-            }
+            zlock!(self.connect_retries).insert(
+                peer.clone(),
+                ConnectRetryState {
+                    attempts,
+                    delay: jittered,
+                    next_attempt: SystemTime::now() + jittered,
+                },
+            );
+            async_std::task::sleep(jittered).await;
+            delay = policy.next_delay(delay);
         }
     }
 
@@ -566,7 +1056,7 @@ impl Runtime {
         async_std::prelude::FutureExt::race(send, recvs).await;
     }
 
-    async fn connect(&self, locators: &[Locator]) -> ZResult<Session> {
+    pub(super) async fn connect_any(&self, locators: &[Locator]) -> ZResult<Session> {
         for locator in locators {
             let session = self.manager().open_session(locator).await;
             if session.is_ok() {
@@ -578,10 +1068,32 @@ impl Runtime {
         })
     }
 
+    /// Opens a session with the router or peer reachable at `locator`, returning its id.
+    /// The session is not re-opened if it's closed later on.
+    pub async fn connect(&self, locator: &Locator) -> ZResult<PeerId> {
+        let session = self.manager().open_session(locator).await?;
+        let pid = session.get_pid()?;
+        log::info!("Connected to {} on {}", pid, locator);
+        Ok(pid)
+    }
+
+    /// Closes the session with the router, peer or client of id `pid`.
+    pub async fn disconnect(&self, pid: &PeerId) -> ZResult<()> {
+        match self.manager().get_session(pid) {
+            Some(session) => {
+                log::info!("Disconnect from {}", pid);
+                session.close().await
+            }
+            None => zerror!(ZErrorKind::Other {
+                descr: format!("Not connected to {}", pid)
+            }),
+        }
+    }
+
     pub async fn connect_peer(&self, pid: &PeerId, locators: &[Locator]) {
         if pid != &self.manager().pid() {
             if self.manager().get_session(pid).is_none() {
-                let session = self.connect(locators).await;
+                let session = self.connect_any(locators).await;
                 if session.is_ok() {
                     log::debug!("Successfully connected to newly scouted {}", pid);
                 } else {
@@ -603,8 +1115,9 @@ impl Runtime {
         let scout = async {
             Runtime::scout(sockets, what, addr, move |hello| async move {
                 log::info!("Found {:?}", hello);
+                self.record_scouted(&hello);
                 if let Some(locators) = &hello.locators {
-                    if self.connect(locators).await.is_ok() {
+                    if self.connect_any(locators).await.is_ok() {
                         log::debug!("Successfully connected to newly scouted {:?}", hello);
                         return Loop::Break;
                     }
@@ -626,6 +1139,7 @@ impl Runtime {
 
     async fn connect_all(&self, ucast_sockets: &[UdpSocket], what: WhatAmI, addr: &SocketAddr) {
         Runtime::scout(ucast_sockets, what, addr, move |hello| async move {
+            self.record_scouted(&hello);
             match &hello.pid {
                 Some(pid) => {
                     if let Some(locators) = &hello.locators {
@@ -643,6 +1157,60 @@ impl Runtime {
         .await
     }
 
+    pub(super) fn record_scouted(&self, hello: &Hello) {
+        if let Some(cache) = &self.scouting_cache {
+            cache.record(hello);
+        }
+    }
+
+    /// Tries to connect to the routers of the scouting cache, the most recently scouted first.
+    async fn connect_cached_router(&self) -> bool {
+        if let Some(cache) = &self.scouting_cache {
+            for (pid, locators) in cache.get(whatami::ROUTER) {
+                if self.connect_any(&locators).await.is_ok() {
+                    log::info!("Connected to cached router {}", pid);
+                    return true;
+                }
+                log::debug!("Unable to connect to cached router {}", pid);
+            }
+        }
+        false
+    }
+
+    /// Spawns the connection to the routers and/or peers of the scouting cache matching `what`.
+    fn connect_cached_peers(&self, what: WhatAmI) {
+        if let Some(cache) = &self.scouting_cache {
+            for (pid, locators) in cache.get(what) {
+                log::debug!("Connect to cached {}", pid);
+                let this = self.clone();
+                async_std::task::spawn(async move { this.connect_peer(&pid, &locators).await });
+            }
+        }
+    }
+
+    /// Scouts during `timeout` without connecting, only to refresh the scouting cache.
+    async fn refresh_scouting_cache(
+        &self,
+        ifaces: &str,
+        what: WhatAmI,
+        addr: &SocketAddr,
+        timeout: std::time::Duration,
+    ) {
+        let sockets: Vec<UdpSocket> = Runtime::get_interfaces(ifaces)
+            .into_iter()
+            .filter_map(|iface| Runtime::bind_ucast_port(iface).ok())
+            .collect();
+        if sockets.is_empty() {
+            return;
+        }
+        let scout = Runtime::scout(&sockets, what, addr, move |hello| async move {
+            self.record_scouted(&hello);
+            Loop::Continue
+        });
+        let timeout = async_std::task::sleep(timeout);
+        async_std::prelude::FutureExt::race(scout, timeout).await;
+    }
+
     async fn responder(&self, mcast_socket: &UdpSocket, ucast_sockets: &[UdpSocket]) {
         fn get_best_match<'a>(addr: &IpAddr, sockets: &'a [UdpSocket]) -> Option<&'a UdpSocket> {
             fn octets(addr: &IpAddr) -> Vec<u8> {
@@ -670,6 +1238,7 @@ impl Runtime {
                 })
         }
 
+        let mut policy = ResponsePolicy::from_config(&*zread!(self.config));
         let mut buf = vec![0; RCV_BUF_SIZE];
         let local_addrs: Vec<SocketAddr> = ucast_sockets
             .iter()
@@ -691,7 +1260,10 @@ impl Runtime {
                 }) = &msg.body
                 {
                     let what = what.or(Some(whatami::ROUTER)).unwrap();
-                    if what & self.whatami != 0 {
+                    if what & self.whatami != 0
+                        && !self.is_draining()
+                        && policy.accept(what, &peer.ip())
+                    {
                         let mut wbuf = WBuf::new(SEND_BUF_INITIAL_SIZE, false);
                         let pid = if *pid_request {
                             Some(self.manager().pid())
@@ -732,13 +1304,11 @@ impl Runtime {
             whatami::CLIENT => {
                 let runtime = session.runtime.clone();
                 async_std::task::spawn(async move {
-                    let mut delay = CONNECTION_RETRY_INITIAL_PERIOD;
+                    let policy = &runtime.connect_retry_policy;
+                    let mut delay = policy.initial;
                     while runtime.start_client().await.is_err() {
-                        async_std::task::sleep(std::time::Duration::from_millis(delay)).await;
-                        delay *= CONNECTION_RETRY_PERIOD_INCREASE_FACTOR;
-                        if delay > CONNECTION_RETRY_MAX_PERIOD {
-                            delay = CONNECTION_RETRY_MAX_PERIOD;
-                        }
+                        async_std::task::sleep(policy.jittered(delay)).await;
+                        delay = policy.next_delay(delay);
                     }
                 });
             }
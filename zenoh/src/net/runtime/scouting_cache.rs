@@ -0,0 +1,204 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use super::parse_mode;
+use super::protocol::core::{whatami, PeerId, WhatAmI};
+use super::protocol::link::Locator;
+use super::protocol::proto::Hello;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use zenoh_util::properties::config::*;
+use zenoh_util::{zconfigurable, zlock};
+
+zconfigurable! {
+    // Period in seconds after which the time of an unchanged cache entry is refreshed on disk.
+    static ref SCOUTING_CACHE_REFRESH_PERIOD: u64 = 60;
+}
+
+struct CacheEntry {
+    whatami: WhatAmI,
+    locators: Vec<Locator>,
+    time: SystemTime,
+}
+
+/// The routers and peers discovered by multicast scouting, persisted in a file so that
+/// they can be connected right away at the next startup.
+///
+/// The file contains a JSON array of objects with the `pid`, `whatami`, `locators`
+/// and `time` (in seconds since the UNIX epoch) of the scouted routers and peers.
+pub(crate) struct ScoutingCache {
+    path: PathBuf,
+    max_age: Duration,
+    entries: Mutex<HashMap<PeerId, CacheEntry>>,
+}
+
+impl ScoutingCache {
+    /// Returns the cache configured in `config`, if any, loaded with its non-stale entries.
+    pub(crate) fn from_config(config: &ConfigProperties) -> Option<ScoutingCache> {
+        let path = PathBuf::from(config.get(&ZN_SCOUTING_CACHE_KEY)?);
+        let max_age = config
+            .get_or(
+                &ZN_SCOUTING_CACHE_MAX_AGE_KEY,
+                ZN_SCOUTING_CACHE_MAX_AGE_DEFAULT,
+            )
+            .parse::<f64>()
+            .ok()
+            .filter(|secs| *secs >= 0.0)
+            .map(Duration::from_secs_f64)
+            .unwrap_or_else(|| {
+                log::warn!(
+                    "Invalid {}, use default value {}",
+                    ZN_SCOUTING_CACHE_MAX_AGE_STR,
+                    ZN_SCOUTING_CACHE_MAX_AGE_DEFAULT
+                );
+                Duration::from_secs_f64(ZN_SCOUTING_CACHE_MAX_AGE_DEFAULT.parse().unwrap())
+            });
+        let cache = ScoutingCache {
+            path,
+            max_age,
+            entries: Mutex::new(HashMap::new()),
+        };
+        cache.load();
+        Some(cache)
+    }
+
+    fn load(&self) {
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(err) => {
+                log::debug!("No scouting cache loaded from {:?}: {}", self.path, err);
+                return;
+            }
+        };
+        let array = match serde_json::from_str::<Value>(&content) {
+            Ok(Value::Array(array)) => array,
+            _ => {
+                log::warn!("Ignore invalid scouting cache {:?}", self.path);
+                return;
+            }
+        };
+        let mut entries = zlock!(self.entries);
+        for value in array {
+            match ScoutingCache::parse_entry(&value) {
+                Some((pid, entry)) if self.is_fresh(&entry) => {
+                    entries.insert(pid, entry);
+                }
+                Some((pid, _)) => log::debug!("Discard stale scouting cache entry for {}", pid),
+                None => log::warn!("Ignore invalid scouting cache entry: {}", value),
+            }
+        }
+        log::debug!(
+            "Loaded {} entries from scouting cache {:?}",
+            entries.len(),
+            self.path
+        );
+    }
+
+    fn parse_entry(value: &Value) -> Option<(PeerId, CacheEntry)> {
+        let pid = value.get("pid")?.as_str()?.parse::<PeerId>().ok()?;
+        let whatami = parse_mode(value.get("whatami")?.as_str()?).ok()?;
+        let locators = value
+            .get("locators")?
+            .as_array()?
+            .iter()
+            .map(|l| l.as_str()?.parse().ok())
+            .collect::<Option<Vec<Locator>>>()?;
+        let time = UNIX_EPOCH + Duration::from_secs_f64(value.get("time")?.as_f64()?);
+        Some((
+            pid,
+            CacheEntry {
+                whatami,
+                locators,
+                time,
+            },
+        ))
+    }
+
+    fn is_fresh(&self, entry: &CacheEntry) -> bool {
+        match SystemTime::now().duration_since(entry.time) {
+            Ok(age) => age <= self.max_age,
+            // an entry from the future is likely due to a clock adjustment: keep it
+            Err(_) => true,
+        }
+    }
+
+    /// Returns the non-stale cached routers and/or peers matching `what`,
+    /// the most recently scouted first.
+    pub(crate) fn get(&self, what: WhatAmI) -> Vec<(PeerId, Vec<Locator>)> {
+        let entries = zlock!(self.entries);
+        let mut result = entries
+            .iter()
+            .filter(|(_, entry)| entry.whatami & what != 0 && self.is_fresh(entry))
+            .collect::<Vec<_>>();
+        result.sort_by(|(_, e1), (_, e2)| e2.time.cmp(&e1.time));
+        result
+            .into_iter()
+            .map(|(pid, entry)| (pid.clone(), entry.locators.clone()))
+            .collect()
+    }
+
+    /// Records a scouted router or peer, and saves the cache if it changed.
+    pub(crate) fn record(&self, hello: &Hello) {
+        let (pid, locators) = match (&hello.pid, &hello.locators) {
+            (Some(pid), Some(locators)) if !locators.is_empty() => (pid, locators),
+            _ => return,
+        };
+        let whatami = hello.whatami.unwrap_or(whatami::ROUTER);
+        let now = SystemTime::now();
+        let mut entries = zlock!(self.entries);
+        if let Some(entry) = entries.get(pid) {
+            if entry.whatami == whatami
+                && &entry.locators == locators
+                && now
+                    .duration_since(entry.time)
+                    .map_or(true, |d| d.as_secs() < *SCOUTING_CACHE_REFRESH_PERIOD)
+            {
+                return;
+            }
+        }
+        entries.insert(
+            pid.clone(),
+            CacheEntry {
+                whatami,
+                locators: locators.clone(),
+                time: now,
+            },
+        );
+        self.save(&entries);
+    }
+
+    fn save(&self, entries: &HashMap<PeerId, CacheEntry>) {
+        let array = entries
+            .iter()
+            .filter(|(_, entry)| self.is_fresh(entry))
+            .map(|(pid, entry)| {
+                json!({
+                    "pid": pid.to_string(),
+                    "whatami": whatami::to_string(entry.whatami).to_lowercase(),
+                    "locators": entry.locators.iter().map(|l| l.to_string()).collect::<Vec<String>>(),
+                    "time": entry.time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64(),
+                })
+            })
+            .collect::<Vec<Value>>();
+        // write in a temporary file and rename it, not to leave a truncated cache on failure
+        let tmp = self.path.with_extension("tmp");
+        let result = std::fs::write(&tmp, Value::Array(array).to_string())
+            .and_then(|()| std::fs::rename(&tmp, &self.path));
+        if let Err(err) = result {
+            log::warn!("Unable to save scouting cache {:?}: {}", self.path, err);
+        }
+    }
+}
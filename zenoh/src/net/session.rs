@@ -20,8 +20,8 @@ use flume::{bounded, Sender};
 use log::{error, trace, warn};
 use protocol::{
     core::{
-        queryable, rname, AtomicZInt, CongestionControl, QueryConsolidation, QueryTarget, ResKey,
-        ResourceId, ZInt,
+        queryable, rname, AtomicZInt, CongestionControl, QueryConsolidation, QueryPage,
+        QueryTarget, ResKey, ResourceId, Target, ZInt,
     },
     io::ZBuf,
     proto::RoutingContext,
@@ -31,7 +31,7 @@ use runtime::Runtime;
 use std::collections::HashMap;
 use std::fmt;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::RwLock;
+use std::sync::{Mutex, RwLock};
 use std::time::Duration;
 use zenoh_util::core::{ZError, ZErrorKind, ZResult};
 use zenoh_util::{zconfigurable, zerror, zpending, zresolved};
@@ -57,6 +57,9 @@ pub(crate) struct SessionState {
     queryables: HashMap<Id, Arc<QueryableState>>,
     queries: HashMap<ZInt, QueryState>,
     local_routing: bool,
+    payload_digest: bool,
+    provenance: bool,
+    digest_mismatches: AtomicUsize,
     join_subscriptions: Vec<String>,
     join_publications: Vec<String>,
 }
@@ -64,6 +67,8 @@ pub(crate) struct SessionState {
 impl SessionState {
     pub(crate) fn new(
         local_routing: bool,
+        payload_digest: bool,
+        provenance: bool,
         join_subscriptions: Vec<String>,
         join_publications: Vec<String>,
     ) -> SessionState {
@@ -80,6 +85,9 @@ impl SessionState {
             queryables: HashMap::new(),
             queries: HashMap::new(),
             local_routing,
+            payload_digest,
+            provenance,
+            digest_mismatches: AtomicUsize::new(0),
             join_subscriptions,
             join_publications,
         }
@@ -234,6 +242,26 @@ impl Session {
         zresolved!(self.runtime.get_pid_str())
     }
 
+    /// Returns the [`Runtime`] of this session, allowing to drive its connections.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # async_std::task::block_on(async {
+    /// use zenoh::net::*;
+    ///
+    /// let session = open(config::peer()).await.unwrap();
+    /// let pid = session
+    ///     .runtime()
+    ///     .connect(&"tcp/192.168.1.1:7447".parse().unwrap())
+    ///     .await
+    ///     .unwrap();
+    /// session.runtime().disconnect(&pid).await.unwrap();
+    /// # })
+    /// ```
+    pub fn runtime(&self) -> &Runtime {
+        &self.runtime
+    }
+
     /// Initialize a Session with an existing Runtime.
     /// This operation is used by the plugins to share the same Runtime than the router.
     #[doc(hidden)]
@@ -244,8 +272,19 @@ impl Session {
         join_publications: Vec<String>,
     ) -> ZResolvedFuture<Session> {
         let router = runtime.router.clone();
+        let config = runtime.config();
+        let payload_digest = config
+            .get_or(&ZN_PAYLOAD_DIGEST_KEY, ZN_PAYLOAD_DIGEST_DEFAULT)
+            .to_lowercase()
+            == ZN_TRUE;
+        let provenance = config
+            .get_or(&ZN_PROVENANCE_KEY, ZN_PROVENANCE_DEFAULT)
+            .to_lowercase()
+            == ZN_TRUE;
         let state = Arc::new(RwLock::new(SessionState::new(
             local_routing,
+            payload_digest,
+            provenance,
             join_subscriptions,
             join_publications,
         )));
@@ -348,9 +387,99 @@ impl Session {
             ZN_INFO_PID_KEY,
             hex::encode_upper(self.runtime.pid.as_slice()),
         );
+        info.insert(
+            ZN_INFO_DIGEST_MISMATCHES_KEY,
+            zread!(self.state)
+                .digest_mismatches
+                .load(Ordering::Relaxed)
+                .to_string(),
+        );
         zresolved!(info)
     }
 
+    /// Get the parameters negotiated with each router, peer or client the zenoh-net
+    /// [Session](Session) is connected to, to check which transport optimizations are active.
+    ///
+    /// # Examples
+    /// ```
+    /// # async_std::task::block_on(async {
+    /// use zenoh::net::*;
+    ///
+    /// let session = open(config::peer()).await.unwrap();
+    /// for (pid, parameters) in session.transports().await {
+    ///     println!("{}: {:?}", pid, parameters);
+    /// }
+    /// # })
+    /// ```
+    pub fn transports(&self) -> ZResolvedFuture<Vec<(PeerId, SessionParameters)>> {
+        trace!("transports()");
+        let transports = self
+            .runtime
+            .manager()
+            .get_sessions()
+            .iter()
+            .filter_map(|s| Some((s.get_pid().ok()?, s.get_parameters().ok()?)))
+            .collect();
+        zresolved!(transports)
+    }
+
+    /// Switches the power profile of this session, e.g. to [`PowerProfile::LowPower`] to extend
+    /// the battery life of a portable client while it only reports from time to time, and back to
+    /// [`PowerProfile::Default`] when it must react promptly.
+    ///
+    /// # Examples
+    /// ```
+    /// # async_std::task::block_on(async {
+    /// use zenoh::net::*;
+    ///
+    /// let session = open(config::peer()).await.unwrap();
+    /// session
+    ///     .set_power_profile(PowerProfile::LowPower { wake_window: 5000 })
+    ///     .await;
+    /// # })
+    /// ```
+    pub fn set_power_profile(&self, profile: PowerProfile) -> ZResolvedFuture<()> {
+        trace!("set_power_profile({})", profile);
+        self.runtime.manager().set_power_profile(profile);
+        zresolved!(())
+    }
+
+    /// Returns the current power profile of this session.
+    pub fn power_profile(&self) -> ZResolvedFuture<PowerProfile> {
+        zresolved!(self.runtime.manager().get_power_profile())
+    }
+
+    /// Returns the counters of the pool of buffers the batches are received onto,
+    /// shared by the sessions with all the peers.
+    #[cfg(feature = "stats")]
+    pub fn buffer_pool_stats(&self) -> ZResolvedFuture<BufferPoolStats> {
+        zresolved!(self.runtime.manager().get_buffer_pool_stats())
+    }
+
+    /// Returns a stream of [`PeerCloseEvent`]s notified each time the session with a peer is
+    /// closed, with the reason why it was closed. This allows to distinguish a peer which
+    /// gracefully closed its session from a peer lost because of a lease expiration or a link error.
+    ///
+    /// # Examples
+    /// ```
+    /// # async_std::task::block_on(async {
+    /// use zenoh::net::*;
+    /// use futures::prelude::*;
+    ///
+    /// let session = open(config::peer()).await.unwrap();
+    /// let mut events = session.peer_close_events();
+    /// async_std::task::spawn(async move {
+    ///     while let Some(event) = events.next().await {
+    ///         println!("Session with {} closed: {}", event.pid, event.reason);
+    ///     }
+    /// });
+    /// # })
+    /// ```
+    pub fn peer_close_events(&self) -> PeerCloseEventReceiver {
+        trace!("peer_close_events()");
+        PeerCloseEventReceiver::new(self.runtime.peer_close_events())
+    }
+
     /// Associate a numerical Id with the given resource key.
     ///
     /// This numerical Id will be used on the network to save bandwidth and
@@ -550,25 +679,41 @@ impl Session {
             id,
             reskey: reskey.clone(),
             resname,
+            filter: info.filter.clone(),
             invoker,
         });
+        // a subscriber asking for the latest values declares its subscription again,
+        // for the router to send them
+        let redeclare = info.history == History::Latest;
+        // the payload filter is only declared for a subscription of a single subscriber:
+        // the twin of a filtered subscriber declares the subscription again without filter
+        let mut declared_info = info.clone();
         let declared_sub = match state
             .join_subscriptions
             .iter()
             .find(|s| rname::include(s, &sub_state.resname))
         {
             Some(join_sub) => {
+                declared_info.filter = None;
                 let joined_sub = state.subscribers.values().any(|s| {
                     rname::include(join_sub, &state.localkey_to_resname(&s.reskey).unwrap())
                 });
-                (!joined_sub).then(|| join_sub.clone().into())
+                (!joined_sub || redeclare).then(|| join_sub.clone().into())
             }
             None => {
-                let twin_sub = state.subscribers.values().any(|s| {
-                    state.localkey_to_resname(&s.reskey).unwrap()
-                        == state.localkey_to_resname(&sub_state.reskey).unwrap()
-                });
-                (!twin_sub).then(|| sub_state.reskey.clone())
+                let twin_subs = state
+                    .subscribers
+                    .values()
+                    .filter(|s| {
+                        state.localkey_to_resname(&s.reskey).unwrap()
+                            == state.localkey_to_resname(&sub_state.reskey).unwrap()
+                    })
+                    .collect::<Vec<&Arc<SubscriberState>>>();
+                let refilter = twin_subs.iter().any(|s| s.filter.is_some());
+                if !twin_subs.is_empty() {
+                    declared_info.filter = None;
+                }
+                (twin_subs.is_empty() || redeclare || refilter).then(|| sub_state.reskey.clone())
             }
         };
 
@@ -603,7 +748,7 @@ impl Session {
                 reskey => reskey,
             };
 
-            primitives.decl_subscriber(&reskey, info, None);
+            primitives.decl_subscriber(&reskey, &declared_info, None);
         }
 
         Ok(sub_state)
@@ -626,7 +771,9 @@ impl Session {
     /// let sub_info = SubInfo {
     ///     reliability: Reliability::Reliable,
     ///     mode: SubMode::Push,
-    ///     period: None
+    ///     period: None,
+    ///     history: History::None,
+    ///     filter: None,
     /// };
     /// let mut subscriber = session.declare_subscriber(&"/resource/name".into(), &sub_info).await.unwrap();
     /// while let Some(sample) = subscriber.receiver().next().await {
@@ -669,7 +816,9 @@ impl Session {
     /// let sub_info = SubInfo {
     ///     reliability: Reliability::Reliable,
     ///     mode: SubMode::Push,
-    ///     period: None
+    ///     period: None,
+    ///     history: History::None,
+    ///     filter: None,
     /// };
     /// let subscriber = session.declare_callback_subscriber(&"/resource/name".into(), &sub_info,
     ///     |sample| { println!("Received : {} {}", sample.res_name, sample.payload); }
@@ -712,6 +861,7 @@ impl Session {
                     id,
                     reskey: reskey.clone(),
                     resname,
+                    filter: None,
                     invoker: SubscriberInvoker::Sender(sender),
                 });
                 state
@@ -919,17 +1069,29 @@ impl Session {
     /// ```
     pub fn write(&self, resource: &ResKey, payload: ZBuf) -> ZResolvedFuture<ZResult<()>> {
         trace!("write({:?}, [...])", resource);
+        if let Err(e) = self.check_message_size(&payload) {
+            return zresolved!(Err(e));
+        }
         let state = zread!(self.state);
         let primitives = state.primitives.as_ref().unwrap().clone();
         let local_routing = state.local_routing;
+        let payload_digest = state.payload_digest;
+        let provenance = state.provenance;
         drop(state);
 
         // if we can create a local timestamp, send it into a DataInfo
-        let data_info = self.runtime.new_timestamp().map(|ts| {
+        let mut data_info = self.runtime.new_timestamp().map(|ts| {
             let mut data_info = DataInfo::new();
             data_info.timestamp = Some(ts);
             data_info
         });
+        if payload_digest {
+            data_info.get_or_insert_with(DataInfo::new).digest = Some(payload_digest_of(&payload));
+        }
+        if provenance {
+            data_info.get_or_insert_with(DataInfo::new).provenance =
+                Some(vec![ProvenanceHop::new(self.runtime.pid.clone())]);
+        }
 
         primitives.send_data(
             resource,
@@ -945,6 +1107,36 @@ impl Session {
         zresolved!(Ok(()))
     }
 
+    /// Create a [Batch](Batch) of writes, sent together to the network with the given congestion
+    /// control when it's flushed.
+    ///
+    /// # Arguments
+    ///
+    /// * `congestion_control` - The congestion control of all the writes of the batch
+    ///
+    /// # Examples
+    /// ```
+    /// # async_std::task::block_on(async {
+    /// use zenoh::net::*;
+    /// use std::time::Duration;
+    ///
+    /// let session = open(config::peer()).await.unwrap();
+    /// let batch = session
+    ///     .batch(CongestionControl::Drop)
+    ///     .max_size(8192)
+    ///     .flush_period(Duration::from_millis(10));
+    /// batch.write(&"/resource/name".into(), "value".as_bytes().into()).await.unwrap();
+    /// # })
+    /// ```
+    pub fn batch(&self, congestion_control: CongestionControl) -> Batch<'_> {
+        Batch {
+            session: self,
+            congestion_control,
+            max_size: None,
+            state: Arc::new(Mutex::new(BatchState::default())),
+        }
+    }
+
     /// Write data with options.
     ///
     /// # Arguments
@@ -973,15 +1165,80 @@ impl Session {
         congestion_control: CongestionControl,
     ) -> ZResolvedFuture<ZResult<()>> {
         trace!("write_ext({:?}, [...])", resource);
+        if let Err(e) = self.check_message_size(&payload) {
+            return zresolved!(Err(e));
+        }
         let state = zread!(self.state);
         let primitives = state.primitives.as_ref().unwrap().clone();
         let local_routing = state.local_routing;
+        let payload_digest = state.payload_digest;
+        let provenance = state.provenance;
         drop(state);
 
         let mut info = protocol::proto::DataInfo::new();
         info.kind = Some(kind);
         info.encoding = Some(encoding);
         info.timestamp = self.runtime.new_timestamp();
+        if payload_digest {
+            info.digest = Some(payload_digest_of(&payload));
+        }
+        if provenance {
+            info.provenance = Some(vec![ProvenanceHop::new(self.runtime.pid.clone())]);
+        }
+        let data_info = Some(info);
+
+        primitives.send_data(
+            resource,
+            payload.clone(),
+            Reliability::Reliable, // TODO: need to check subscriptions to determine the right reliability value
+            congestion_control,
+            data_info.clone(),
+            None,
+        );
+        if local_routing {
+            self.handle_data(true, resource, data_info, payload);
+        }
+        zresolved!(Ok(()))
+    }
+
+    /// Republish data received by another session, as done by the bridges.
+    ///
+    /// The encoding, the kind and the provenance chain of the data are kept, the hop of this
+    /// session being appended to the chain, while its timestamp is renewed.
+    ///
+    /// # Arguments
+    ///
+    /// * `resource` - The resource key to write
+    /// * `payload` - The value to write
+    /// * `data_info` - The data info of the received data
+    /// * `congestion_control` - The value for the congestion control
+    pub fn forward(
+        &self,
+        resource: &ResKey,
+        payload: ZBuf,
+        data_info: Option<DataInfo>,
+        congestion_control: CongestionControl,
+    ) -> ZResolvedFuture<ZResult<()>> {
+        trace!("forward({:?}, [...])", resource);
+        if let Err(e) = self.check_message_size(&payload) {
+            return zresolved!(Err(e));
+        }
+        let state = zread!(self.state);
+        let primitives = state.primitives.as_ref().unwrap().clone();
+        let local_routing = state.local_routing;
+        let payload_digest = state.payload_digest;
+        drop(state);
+
+        let received = data_info.unwrap_or_default();
+        let mut info = DataInfo::new();
+        info.kind = received.kind;
+        info.encoding = received.encoding;
+        info.timestamp = self.runtime.new_timestamp();
+        if payload_digest {
+            info.digest = Some(payload_digest_of(&payload));
+        }
+        info.provenance = received.provenance;
+        info.append_hop(self.runtime.pid.clone());
         let data_info = Some(info);
 
         primitives.send_data(
@@ -1033,9 +1290,16 @@ impl Session {
                 Some(res) => {
                     if !local && res.subscribers.len() == 1 {
                         let sub = res.subscribers.get(0).unwrap();
-                        Session::invoke_subscriber(&sub.invoker, res.name.clone(), payload, info);
+                        if sub.accepts(&payload) {
+                            Session::invoke_subscriber(
+                                &sub.invoker,
+                                res.name.clone(),
+                                payload,
+                                info,
+                            );
+                        }
                     } else {
-                        for sub in &res.subscribers {
+                        for sub in res.subscribers.iter().filter(|sub| sub.accepts(&payload)) {
                             Session::invoke_subscriber(
                                 &sub.invoker,
                                 res.name.clone(),
@@ -1063,7 +1327,7 @@ impl Session {
             match state.reskey_to_resname(reskey, local) {
                 Ok(resname) => {
                     for sub in state.subscribers.values() {
-                        if rname::matches(&sub.resname, &resname) {
+                        if rname::matches(&sub.resname, &resname) && sub.accepts(&payload) {
                             Session::invoke_subscriber(
                                 &sub.invoker,
                                 resname.clone(),
@@ -1107,7 +1371,12 @@ impl Session {
     ///
     /// * `resource` - The resource key to query
     /// * `predicate` - An indication to matching queryables about the queried data
-    /// * `target` - The kind of queryables that should be target of this query
+    /// * `target` - The kind of queryables that should be target of this query.
+    ///   With a [`Target::Complete`] target, only the storages declared on a resource
+    ///   that includes the whole queried resource are queried (if any).
+    ///   With a [`Target::Nearest`] target, only the nearest of those is queried.
+    ///   With a [`Target::BestMatchingN`] target, only `n` of those are queried, balancing
+    ///   the queries among them.
     /// * `consolidation` - The kind of consolidation that should be applied on replies
     ///
     /// # Examples
@@ -1142,13 +1411,221 @@ impl Session {
             target,
             consolidation
         );
+        self.send_paged_query(resource, predicate, target, consolidation, None)
+    }
+
+    /// Query a page of data from the matching queryables in the system.
+    ///
+    /// Each matching queryable replies at most `page.limit` samples, on the resources
+    /// sorting after `page.after`, in resource name order.
+    ///
+    /// # Arguments
+    ///
+    /// * `resource` - The resource key to query
+    /// * `predicate` - An indication to matching queryables about the queried data
+    /// * `target` - The kind of queryables that should be target of this query
+    /// * `consolidation` - The kind of consolidation that should be applied on replies
+    /// * `page` - The page of replies to query
+    ///
+    /// # Examples
+    /// ```
+    /// # async_std::task::block_on(async {
+    /// use zenoh::net::*;
+    /// use futures::prelude::*;
+    ///
+    /// let session = open(config::peer()).await.unwrap();
+    /// let mut replies = session.query_page(
+    ///     &"/resource/**".into(),
+    ///     "predicate",
+    ///     QueryTarget::default(),
+    ///     QueryConsolidation::default(),
+    ///     QueryPage { limit: 100, after: Some("/resource/name".to_string()) }
+    /// ).await.unwrap();
+    /// while let Some(reply) = replies.next().await {
+    ///     println!(">> Received {:?}", reply.data);
+    /// }
+    /// # })
+    /// ```
+    pub fn query_page(
+        &self,
+        resource: &ResKey,
+        predicate: &str,
+        target: QueryTarget,
+        consolidation: QueryConsolidation,
+        page: QueryPage,
+    ) -> ZResolvedFuture<ZResult<ReplyReceiver>> {
+        trace!(
+            "query_page({:?}, {:?}, {:?}, {:?}, {:?})",
+            resource,
+            predicate,
+            target,
+            consolidation,
+            page
+        );
+        self.send_paged_query(resource, predicate, target, consolidation, Some(page))
+    }
+
+    /// Query data from the matching queryables in the system, page by page.
+    ///
+    /// The replies are queried by pages of `limit` resource names when requested with
+    /// [next_page](QueryPages::next_page), each page resuming after the last resource name
+    /// of the previous one.
+    ///
+    /// # Arguments
+    ///
+    /// * `resource` - The resource key to query
+    /// * `predicate` - An indication to matching queryables about the queried data
+    /// * `target` - The kind of queryables that should be target of this query
+    /// * `consolidation` - The kind of consolidation that should be applied on replies
+    /// * `limit` - The number of resource names of each page
+    ///
+    /// # Examples
+    /// ```
+    /// # async_std::task::block_on(async {
+    /// use zenoh::net::*;
+    ///
+    /// let session = open(config::peer()).await.unwrap();
+    /// let mut pages = session.query_pages(
+    ///     &"/resource/**".into(),
+    ///     "predicate",
+    ///     QueryTarget::default(),
+    ///     QueryConsolidation::default(),
+    ///     100
+    /// );
+    /// while let Some(page) = pages.next_page().await.unwrap() {
+    ///     println!(">> Received a page of {} replies", page.len());
+    /// }
+    /// # })
+    /// ```
+    pub fn query_pages(
+        &self,
+        resource: &ResKey,
+        predicate: &str,
+        target: QueryTarget,
+        consolidation: QueryConsolidation,
+        limit: ZInt,
+    ) -> QueryPages<'_> {
+        QueryPages {
+            session: self,
+            resource: resource.clone(),
+            predicate: predicate.to_string(),
+            target,
+            consolidation,
+            limit: limit.max(1),
+            after: None,
+            done: false,
+        }
+    }
+
+    /// Query data from the matching queryables in the system for several selectors at once.
+    ///
+    /// The queries are sent back to back and their replies are received on a single
+    /// [`MultiReplyReceiver`], tagged with the index of their query in `queries`.
+    /// A [`MultiReply::Final`] marker is received once all the replies to a query were
+    /// received, and the receiver ends once all the queries are complete.
+    ///
+    /// The consolidation applies across the queries: a reply on a resource already received
+    /// for another query is not received again, unless it's more recent.
+    ///
+    /// # Arguments
+    ///
+    /// * `queries` - The resource keys and predicates to query
+    /// * `target` - The kind of queryables that should be target of the queries
+    /// * `consolidation` - The kind of consolidation that should be applied on replies
+    ///
+    /// # Examples
+    /// ```
+    /// # async_std::task::block_on(async {
+    /// use zenoh::net::*;
+    /// use futures::prelude::*;
+    ///
+    /// let session = open(config::peer()).await.unwrap();
+    /// let mut replies = session.query_many(
+    ///     &[("/resource/a".into(), ""), ("/resource/b".into(), "predicate")],
+    ///     QueryTarget::default(),
+    ///     QueryConsolidation::default()
+    /// ).await.unwrap();
+    /// while let Some(reply) = replies.next().await {
+    ///     match reply {
+    ///         MultiReply::Reply(index, reply) => println!(">> Received for query {}: {:?}", index, reply.data),
+    ///         MultiReply::Final(index) => println!(">> Query {} complete", index),
+    ///     }
+    /// }
+    /// # })
+    /// ```
+    pub fn query_many(
+        &self,
+        queries: &[(ResKey, &str)],
+        target: QueryTarget,
+        consolidation: QueryConsolidation,
+    ) -> ZResolvedFuture<ZResult<MultiReplyReceiver>> {
+        trace!(
+            "query_many({:?}, {:?}, {:?})",
+            queries,
+            target,
+            consolidation
+        );
+        let (sender, receiver) = bounded(*API_REPLY_RECEPTION_CHANNEL_SIZE);
+        let many_state = Arc::new(ManyQueriesState {
+            sender,
+            sent: if consolidation.reception != ConsolidationMode::None {
+                Some(Mutex::new(HashMap::new()))
+            } else {
+                None
+            },
+        });
+        for (index, (resource, predicate)) in queries.iter().enumerate() {
+            self.send_query(
+                resource,
+                predicate,
+                target.clone(),
+                consolidation.clone(),
+                None,
+                ReplySender::Many(index, many_state.clone()),
+            );
+        }
+        // the receiver ends once the states of all the queries are dropped
+        drop(many_state);
+        zresolved!(Ok(MultiReplyReceiver::new(receiver)))
+    }
+
+    fn send_paged_query(
+        &self,
+        resource: &ResKey,
+        predicate: &str,
+        target: QueryTarget,
+        consolidation: QueryConsolidation,
+        page: Option<QueryPage>,
+    ) -> ZResolvedFuture<ZResult<ReplyReceiver>> {
+        let (rep_sender, rep_receiver) = bounded(*API_REPLY_RECEPTION_CHANNEL_SIZE);
+        self.send_query(
+            resource,
+            predicate,
+            target,
+            consolidation,
+            page,
+            ReplySender::Query(rep_sender),
+        );
+        zresolved!(Ok(ReplyReceiver::new(rep_receiver)))
+    }
+
+    fn send_query(
+        &self,
+        resource: &ResKey,
+        predicate: &str,
+        target: QueryTarget,
+        consolidation: QueryConsolidation,
+        page: Option<QueryPage>,
+        rep_sender: ReplySender,
+    ) {
         let mut state = zwrite!(self.state);
         let qid = state.qid_counter.fetch_add(1, Ordering::SeqCst);
-        let (rep_sender, rep_receiver) = bounded(*API_REPLY_RECEPTION_CHANNEL_SIZE);
+        let local_routing = state.local_routing;
         state.queries.insert(
             qid,
             QueryState {
-                nb_final: 2,
+                // the final replies of the router and of the local queryables, if routed locally
+                nb_final: if local_routing { 2 } else { 1 },
                 reception_mode: consolidation.reception,
                 replies: if consolidation.reception != ConsolidationMode::None {
                     Some(HashMap::new())
@@ -1160,7 +1637,6 @@ impl Session {
         );
 
         let primitives = state.primitives.as_ref().unwrap().clone();
-        let local_routing = state.local_routing;
         drop(state);
         primitives.send_query(
             resource,
@@ -1168,15 +1644,98 @@ impl Session {
             qid,
             target.clone(),
             consolidation.clone(),
+            page.clone(),
             None,
         );
         if local_routing {
-            self.handle_query(true, resource, predicate, qid, target, consolidation);
+            self.handle_query(true, resource, predicate, qid, target, consolidation, page);
         }
+    }
 
-        zresolved!(Ok(ReplyReceiver::new(rep_receiver)))
+    // Returns the sample of a write added to a batch, with its data info set at the time
+    // of the addition.
+    pub(crate) fn new_batched_sample(
+        &self,
+        resource: &ResKey,
+        payload: ZBuf,
+        encoding_and_kind: Option<(ZInt, ZInt)>,
+    ) -> ZResult<BatchedSample> {
+        trace!("batch write({:?}, [...])", resource);
+        self.check_message_size(&payload)?;
+        let state = zread!(self.state);
+        let payload_digest = state.payload_digest;
+        let provenance = state.provenance;
+        drop(state);
+
+        let mut data_info = self.runtime.new_timestamp().map(|ts| {
+            let mut data_info = DataInfo::new();
+            data_info.timestamp = Some(ts);
+            data_info
+        });
+        if let Some((encoding, kind)) = encoding_and_kind {
+            let info = data_info.get_or_insert_with(DataInfo::new);
+            info.encoding = Some(encoding);
+            info.kind = Some(kind);
+        }
+        if payload_digest {
+            data_info.get_or_insert_with(DataInfo::new).digest = Some(payload_digest_of(&payload));
+        }
+        if provenance {
+            data_info.get_or_insert_with(DataInfo::new).provenance =
+                Some(vec![ProvenanceHop::new(self.runtime.pid.clone())]);
+        }
+        Ok(BatchedSample {
+            reskey: resource.clone(),
+            payload,
+            data_info,
+        })
     }
 
+    // Sends the samples of a batch back to back, with the same congestion control.
+    pub(crate) fn write_batch(
+        &self,
+        samples: Vec<BatchedSample>,
+        congestion_control: CongestionControl,
+    ) {
+        if samples.is_empty() {
+            return;
+        }
+        trace!("write_batch({} samples)", samples.len());
+        let state = zread!(self.state);
+        let primitives = state.primitives.as_ref().unwrap().clone();
+        let local_routing = state.local_routing;
+        drop(state);
+
+        for sample in samples {
+            primitives.send_data(
+                &sample.reskey,
+                sample.payload.clone(),
+                Reliability::Reliable, // TODO: need to check subscriptions to determine the right reliability value
+                congestion_control,
+                sample.data_info.clone(),
+                None,
+            );
+            if local_routing {
+                self.handle_data(true, &sample.reskey, sample.data_info, sample.payload);
+            }
+        }
+    }
+
+    fn check_message_size(&self, payload: &ZBuf) -> ZResult<()> {
+        let max_message_size = self.runtime.manager().max_message_size();
+        if exceeds_message_size(payload, max_message_size) {
+            return zerror!(ZErrorKind::Other {
+                descr: format!(
+                    "Payload of {} bytes exceeds the maximum message size of {} bytes",
+                    payload.len(),
+                    max_message_size
+                )
+            });
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
     fn handle_query(
         &self,
         local: bool,
@@ -1185,33 +1744,71 @@ impl Session {
         qid: ZInt,
         target: QueryTarget,
         _consolidation: QueryConsolidation,
+        page: Option<QueryPage>,
     ) {
         let (primitives, resname, kinds_and_senders) = {
             let state = zread!(self.state);
             match state.reskey_to_resname(reskey, local) {
                 Ok(resname) => {
-                    let kinds_and_senders = state
+                    let matching = state
                         .queryables
                         .values()
-                        .filter(
-                            |queryable| match state.localkey_to_resname(&queryable.reskey) {
-                                Ok(qablname) => {
-                                    rname::matches(&qablname, &resname)
-                                        && ((queryable.kind == queryable::ALL_KINDS
-                                            || target.kind == queryable::ALL_KINDS)
-                                            || (queryable.kind & target.kind != 0))
-                                }
+                        .filter_map(|queryable| {
+                            match state.localkey_to_resname(&queryable.reskey) {
+                                Ok(qablname) => (rname::matches(&qablname, &resname)
+                                    && ((queryable.kind == queryable::ALL_KINDS
+                                        || target.kind == queryable::ALL_KINDS)
+                                        || (queryable.kind & target.kind != 0)))
+                                    .then(|| (qablname, queryable)),
                                 Err(err) => {
                                     error!(
                                         "{}. Internal error (queryable reskey to resname failed).",
                                         err
                                     );
-                                    false
+                                    None
                                 }
-                            },
-                        )
-                        .map(|qable| (qable.kind, qable.sender.clone()))
-                        .collect::<Vec<(ZInt, Sender<Query>)>>();
+                            }
+                        })
+                        .collect::<Vec<(String, &Arc<QueryableState>)>>();
+                    // With a Complete target, only the storages including the whole
+                    // queried resource are queried (if any).
+                    let complete = match target.target {
+                        Target::Complete { n } | Target::BestMatchingN { n } => matching
+                            .iter()
+                            .filter(|(qablname, qable)| {
+                                qable.kind & queryable::STORAGE != 0
+                                    && rname::include(qablname, &resname)
+                            })
+                            .take((n as usize).max(1))
+                            .map(|(_, qable)| (qable.kind, qable.sender.clone()))
+                            .collect::<Vec<(ZInt, Sender<Query>)>>(),
+                        Target::Nearest => matching
+                            .iter()
+                            .filter(|(qablname, qable)| {
+                                qable.kind & queryable::STORAGE != 0
+                                    && rname::include(qablname, &resname)
+                            })
+                            .take(1)
+                            .map(|(_, qable)| (qable.kind, qable.sender.clone()))
+                            .collect::<Vec<(ZInt, Sender<Query>)>>(),
+                        _ => vec![],
+                    };
+                    // All the local queryables are equally near: a Nearest target only
+                    // queries one of them, and a BestMatchingN target only `n` of them.
+                    let max = match target.target {
+                        Target::Nearest => 1,
+                        Target::BestMatchingN { n } => (n as usize).max(1),
+                        _ => usize::MAX,
+                    };
+                    let kinds_and_senders = if complete.is_empty() {
+                        matching
+                            .iter()
+                            .take(max)
+                            .map(|(_, qable)| (qable.kind, qable.sender.clone()))
+                            .collect::<Vec<(ZInt, Sender<Query>)>>()
+                    } else {
+                        complete
+                    };
                     (
                         state.primitives.as_ref().unwrap().clone(),
                         resname,
@@ -1230,12 +1827,14 @@ impl Session {
 
         let pid = self.runtime.pid.clone(); // @TODO build/use prebuilt specific pid
 
-        for (kind, req_sender) in kinds_and_senders {
+        for (index, (kind, req_sender)) in kinds_and_senders.into_iter().enumerate() {
             let _ = req_sender.send(Query {
                 res_name: resname.clone(),
                 predicate: predicate.clone(),
+                page: page.clone(),
                 replies_sender: RepliesSender {
                     kind,
+                    index,
                     sender: rep_sender.clone(),
                 },
             });
@@ -1244,10 +1843,14 @@ impl Session {
 
         // router is not re-entrant
 
+        let mut page_filter = PageFilter::new(page);
         if local {
             let this = self.clone();
             task::spawn(async move {
-                while let Some((kind, sample)) = rep_receiver.stream().next().await {
+                while let Some((index, kind, sample)) = rep_receiver.stream().next().await {
+                    if !page_filter.accept(index, &sample.res_name) {
+                        continue;
+                    }
                     this.send_reply_data(
                         qid,
                         kind,
@@ -1260,8 +1863,21 @@ impl Session {
                 this.send_reply_final(qid);
             });
         } else {
+            let max_message_size = self.runtime.manager().max_message_size();
             task::spawn(async move {
-                while let Some((kind, sample)) = rep_receiver.stream().next().await {
+                while let Some((index, kind, sample)) = rep_receiver.stream().next().await {
+                    if !page_filter.accept(index, &sample.res_name) {
+                        continue;
+                    }
+                    if exceeds_message_size(&sample.payload, max_message_size) {
+                        error!(
+                            "Reply on {} dropped: payload of {} bytes exceeds the maximum message size of {} bytes",
+                            sample.res_name,
+                            sample.payload.len(),
+                            max_message_size
+                        );
+                        continue;
+                    }
                     primitives.send_reply_data(
                         qid,
                         kind,
@@ -1351,6 +1967,17 @@ impl Primitives for Session {
             congestion_control,
             info,
         );
+        if let Some(digest) = info.as_ref().and_then(|info| info.digest.as_ref()) {
+            if payload_digest_of(&payload) != *digest {
+                let state = zread!(self.state);
+                state.digest_mismatches.fetch_add(1, Ordering::Relaxed);
+                warn!(
+                    "Data for {:?} dropped: the payload does not match its digest",
+                    reskey
+                );
+                return;
+            }
+        }
         self.handle_data(false, reskey, info, payload)
     }
 
@@ -1361,16 +1988,18 @@ impl Primitives for Session {
         qid: ZInt,
         target: QueryTarget,
         consolidation: QueryConsolidation,
+        page: Option<QueryPage>,
         _routing_context: Option<RoutingContext>,
     ) {
         trace!(
-            "recv Query {:?} {:?} {:?} {:?}",
+            "recv Query {:?} {:?} {:?} {:?} {:?}",
             reskey,
             predicate,
             target,
-            consolidation
+            consolidation,
+            page
         );
-        self.handle_query(false, reskey, predicate, qid, target, consolidation)
+        self.handle_query(false, reskey, predicate, qid, target, consolidation, page)
     }
 
     fn send_reply_data(
@@ -1412,7 +2041,7 @@ impl Primitives for Session {
                 };
                 match query.reception_mode {
                     ConsolidationMode::None => {
-                        let _ = query.rep_sender.send(new_reply);
+                        query.rep_sender.send(new_reply);
                     }
                     ConsolidationMode::Lazy => {
                         match query
@@ -1428,7 +2057,7 @@ impl Primitives for Session {
                                         .as_mut()
                                         .unwrap()
                                         .insert(new_reply.data.res_name.clone(), new_reply.clone());
-                                    let _ = query.rep_sender.send(new_reply);
+                                    query.rep_sender.send(new_reply);
                                 }
                             }
                             None => {
@@ -1437,7 +2066,7 @@ impl Primitives for Session {
                                     .as_mut()
                                     .unwrap()
                                     .insert(new_reply.data.res_name.clone(), new_reply.clone());
-                                let _ = query.rep_sender.send(new_reply);
+                                query.rep_sender.send(new_reply);
                             }
                         }
                     }
@@ -1484,9 +2113,10 @@ impl Primitives for Session {
                     let query = state.queries.remove(&qid).unwrap();
                     if query.reception_mode == ConsolidationMode::Full {
                         for (_, reply) in query.replies.unwrap().into_iter() {
-                            let _ = query.rep_sender.send(reply);
+                            query.rep_sender.send(reply);
                         }
                     }
+                    query.rep_sender.send_final();
                 }
             }
             None => {
@@ -1529,3 +2159,17 @@ impl fmt::Debug for Session {
         write!(f, "Session{{...}}")
     }
 }
+
+fn payload_digest_of(payload: &ZBuf) -> Vec<u8> {
+    blake3::hash(&payload.contiguous()).as_bytes().to_vec()
+}
+
+// The payloads in shared memory are only sent as their SHM info to the peers able to map them,
+// and serialized for the others, which check their size at reception: they are not limited here.
+fn exceeds_message_size(payload: &ZBuf, max_message_size: usize) -> bool {
+    #[cfg(feature = "zero-copy")]
+    if payload.has_shmbuf() {
+        return false;
+    }
+    payload.len() > max_message_size
+}
@@ -14,13 +14,17 @@
 use crate::net::Session;
 use crate::utils::new_reception_timestamp;
 use async_std::sync::Arc;
+use async_std::task;
 use flume::*;
+use futures::StreamExt;
 use std::collections::HashMap;
 use std::fmt;
 use std::pin::Pin;
-use std::sync::RwLock;
+use std::sync::{Mutex, RwLock};
 use std::task::{Context, Poll};
+use std::time::Duration;
 use uhlc::Timestamp;
+use zenoh_util::zlock;
 
 /// A read-only bytes buffer.
 pub use super::protocol::io::{ZBuf, ZSlice};
@@ -45,6 +49,25 @@ pub use super::protocol::core::SubInfo;
 /// The global unique id of a zenoh peer.
 pub use super::protocol::core::PeerId;
 
+/// The parameters negotiated with a connected peer.
+pub use super::protocol::session::SessionParameters;
+
+/// The traffic counters of the session with a connected peer.
+pub use super::protocol::session::SessionStats;
+
+/// The counters of the pool of buffers the batches are received onto.
+#[cfg(feature = "stats")]
+pub use super::protocol::session::BufferPoolStats;
+
+/// The reason why the session with a peer was closed.
+pub use super::protocol::session::CloseReason;
+
+/// The power profile of a session.
+pub use super::protocol::session::PowerProfile;
+
+/// A notification that the session with a peer was closed.
+pub use super::runtime::PeerCloseEvent;
+
 /// A time period.
 pub use super::protocol::core::Period;
 
@@ -61,6 +84,9 @@ pub use super::protocol::core::ConsolidationMode;
 /// at different stages of the reply process.
 pub use super::protocol::core::QueryConsolidation;
 
+/// The page of replies requested by a [paged query](Session::query_page).
+pub use super::protocol::core::QueryPage;
+
 /// The kind of congestion control.
 pub use super::protocol::core::CongestionControl;
 
@@ -73,6 +99,12 @@ pub use super::protocol::core::ResKey;
 /// The subscription mode.
 pub use super::protocol::core::SubMode;
 
+/// The publications a subscriber receives at its declaration.
+pub use super::protocol::core::History;
+
+/// A filter on the payloads received by a subscriber.
+pub use super::protocol::core::PayloadFilter;
+
 /// A zenoh integer.
 pub use super::protocol::core::ZInt;
 
@@ -106,6 +138,7 @@ pub use zenoh_util::sync::ZResolvedFuture;
 /// }
 /// ```
 pub use super::protocol::proto::DataInfo;
+pub use super::protocol::proto::ProvenanceHop;
 
 /// A zenoh error.
 pub use zenoh_util::core::ZError;
@@ -123,6 +156,12 @@ zreceiver! {
     }
 }
 
+zreceiver! {
+    #[derive(Clone)]
+    pub struct PeerCloseEventReceiver : Receiver<PeerCloseEvent> {
+    }
+}
+
 /// A zenoh value.
 #[derive(Debug, Clone)]
 pub struct Sample {
@@ -139,6 +178,14 @@ impl Sample {
             .and_then(|info| info.timestamp.as_ref())
     }
 
+    /// Returns the hops this Sample went through, from its publisher to the last router or
+    /// bridge, if its publisher attached a provenance chain.
+    pub fn get_provenance(&self) -> Option<&[ProvenanceHop]> {
+        self.data_info
+            .as_ref()
+            .and_then(|info| info.provenance.as_deref())
+    }
+
     /// Ensure that an associated Timestamp is present in this Sample.
     /// If not, a new one is created with the current system time and 0x00 as id.
     pub fn ensure_timestamp(&mut self) {
@@ -160,9 +207,14 @@ impl Sample {
 pub type DataHandler = dyn FnMut(Sample) + Send + Sync + 'static;
 
 /// Structs received b y a [Queryable](Queryable).
+///
+/// When the query requests a [page](QueryPage) of replies, the queryable should only reply
+/// the first `limit` samples sorting after the `after` resource name, in resource name order.
+/// The replies exceeding the page are dropped by the session.
 pub struct Query {
     pub res_name: String,
     pub predicate: String,
+    pub page: Option<QueryPage>,
     pub replies_sender: RepliesSender,
 }
 
@@ -181,18 +233,68 @@ impl Query {
     pub async fn reply_async(&'_ self, msg: Sample) {
         self.replies_sender.send_async(msg).await
     }
+
+    /// Replies with a buffer in shared memory. The reply is sent as a reference to the buffer
+    /// to the queriers and routers mapping the same shared memory, and serialized for the others.
+    #[cfg(feature = "zero-copy")]
+    #[inline(always)]
+    pub fn reply_shm(
+        &'_ self,
+        res_name: String,
+        sbuf: SharedMemoryBuf,
+        data_info: Option<DataInfo>,
+    ) {
+        self.reply(Sample {
+            res_name,
+            payload: sbuf.into(),
+            data_info,
+        })
+    }
 }
 
 impl fmt::Debug for Query {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "Query{{ res_name: '{}', predicate: '{}' }}",
-            self.res_name, self.predicate
+            "Query{{ res_name: '{}', predicate: '{}', page: {:?} }}",
+            self.res_name, self.predicate, self.page
         )
     }
 }
 
+// Restricts the replies of the local queryables to the page requested by a query:
+// at most `limit` replies per queryable, on resources sorting after `after`.
+pub(crate) struct PageFilter {
+    page: Option<QueryPage>,
+    counts: HashMap<usize, ZInt>,
+}
+
+impl PageFilter {
+    pub(crate) fn new(page: Option<QueryPage>) -> PageFilter {
+        PageFilter {
+            page,
+            counts: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn accept(&mut self, index: usize, res_name: &str) -> bool {
+        match &self.page {
+            Some(page) => {
+                if !page.follows(res_name) {
+                    return false;
+                }
+                let count = self.counts.entry(index).or_insert(0);
+                if *count >= page.limit {
+                    return false;
+                }
+                *count += 1;
+                true
+            }
+            None => true,
+        }
+    }
+}
+
 /// Structs returned by a [query](Session::query).
 #[derive(Clone, Debug)]
 pub struct Reply {
@@ -201,12 +303,152 @@ pub struct Reply {
     pub replier_id: PeerId,
 }
 
+/// An iterator over the pages of replies to a query, returned by [query_pages](Session::query_pages).
+///
+/// Each page is queried when requested, so that the queryables only send the replies the
+/// querier is ready to process.
+pub struct QueryPages<'a> {
+    pub(crate) session: &'a Session,
+    pub(crate) resource: ResKey,
+    pub(crate) predicate: String,
+    pub(crate) target: QueryTarget,
+    pub(crate) consolidation: QueryConsolidation,
+    pub(crate) limit: ZInt,
+    pub(crate) after: Option<String>,
+    pub(crate) done: bool,
+}
+
+impl QueryPages<'_> {
+    /// Query the next page of replies, sorted by resource name.
+    ///
+    /// A page contains the replies on the next `limit` resource names; it's larger than `limit`
+    /// if several queryables replied on the same resource names. Returns `None` once all the
+    /// replies have been received.
+    ///
+    /// # Examples
+    /// ```
+    /// # async_std::task::block_on(async {
+    /// use zenoh::net::*;
+    ///
+    /// let session = open(config::peer()).await.unwrap();
+    /// let mut pages = session.query_pages(
+    ///     &"/resource/**".into(),
+    ///     "",
+    ///     QueryTarget::default(),
+    ///     QueryConsolidation::default(),
+    ///     100
+    /// );
+    /// while let Some(page) = pages.next_page().await.unwrap() {
+    ///     for reply in page {
+    ///         println!(">> Received {:?}", reply.data);
+    ///     }
+    /// }
+    /// # })
+    /// ```
+    pub async fn next_page(&mut self) -> ZResult<Option<Vec<Reply>>> {
+        if self.done {
+            return Ok(None);
+        }
+        let page = QueryPage {
+            limit: self.limit,
+            after: self.after.clone(),
+        };
+        let mut replies: Vec<Reply> = self
+            .session
+            .query_page(
+                &self.resource,
+                &self.predicate,
+                self.target.clone(),
+                self.consolidation.clone(),
+                page,
+            )
+            .await?
+            .collect()
+            .await;
+        replies.sort_by(|r1, r2| r1.data.res_name.cmp(&r2.data.res_name));
+
+        // Each queryable replied the first `limit` resource names after the previous page,
+        // so all the replies on the first `limit` received resource names have been received.
+        let mut res_names = replies
+            .iter()
+            .map(|reply| reply.data.res_name.as_str())
+            .collect::<Vec<&str>>();
+        res_names.dedup();
+        match res_names.get(self.limit as usize - 1) {
+            Some(last) => {
+                let last = last.to_string();
+                replies.retain(|reply| reply.data.res_name <= last);
+                self.after = Some(last);
+            }
+            None => self.done = true,
+        }
+        if replies.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(replies))
+        }
+    }
+}
+
+impl fmt::Debug for QueryPages<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "QueryPages{{ resource: {}, limit: {}, after: {:?} }}",
+            self.resource, self.limit, self.after
+        )
+    }
+}
+
 #[derive(Clone, Debug)]
 pub(crate) struct QueryState {
     pub(crate) nb_final: usize,
     pub(crate) reception_mode: ConsolidationMode,
     pub(crate) replies: Option<HashMap<String, Reply>>,
-    pub(crate) rep_sender: Sender<Reply>,
+    pub(crate) rep_sender: ReplySender,
+}
+
+// Where the replies to a query are sent.
+#[derive(Clone, Debug)]
+pub(crate) enum ReplySender {
+    Query(Sender<Reply>),
+    // A query of a query_many: its index and the state shared with the other queries.
+    Many(usize, Arc<ManyQueriesState>),
+}
+
+#[derive(Debug)]
+pub(crate) struct ManyQueriesState {
+    pub(crate) sender: Sender<MultiReply>,
+    // The data info of the last reply sent for each resource, if the replies are consolidated.
+    pub(crate) sent: Option<Mutex<HashMap<String, Option<DataInfo>>>>,
+}
+
+impl ReplySender {
+    pub(crate) fn send(&self, reply: Reply) {
+        match self {
+            ReplySender::Query(sender) => {
+                let _ = sender.send(reply);
+            }
+            ReplySender::Many(index, state) => {
+                if let Some(sent) = &state.sent {
+                    let mut sent = zlock!(sent);
+                    match sent.get(&reply.data.res_name) {
+                        Some(data_info) if reply.data.data_info > *data_info => (),
+                        Some(_) => return,
+                        None => (),
+                    }
+                    sent.insert(reply.data.res_name.clone(), reply.data.data_info.clone());
+                }
+                let _ = state.sender.send(MultiReply::Reply(*index, reply));
+            }
+        }
+    }
+
+    pub(crate) fn send_final(&self) {
+        if let ReplySender::Many(index, state) = self {
+            let _ = state.sender.send(MultiReply::Final(*index));
+        }
+    }
 }
 
 pub(crate) type Id = usize;
@@ -263,6 +505,189 @@ impl fmt::Debug for Publisher<'_> {
     }
 }
 
+pub(crate) struct BatchedSample {
+    pub(crate) reskey: ResKey,
+    pub(crate) payload: ZBuf,
+    pub(crate) data_info: Option<DataInfo>,
+}
+
+#[derive(Default)]
+pub(crate) struct BatchState {
+    pub(crate) samples: Vec<BatchedSample>,
+    pub(crate) size: usize,
+}
+
+impl BatchState {
+    #[inline]
+    fn take(&mut self) -> Vec<BatchedSample> {
+        self.size = 0;
+        std::mem::take(&mut self.samples)
+    }
+}
+
+/// A batch of writes, sent to the network together when flushed.
+///
+/// All the writes of a batch share the same congestion control, and are pushed back to back
+/// to the transmission pipelines, so that they are serialized in as few network batches as
+/// possible. A batch is flushed by [flush](Batch::flush), when the size of its payloads reaches
+/// its [max_size](Batch::max_size), every [flush_period](Batch::flush_period), and when dropped.
+pub struct Batch<'a> {
+    pub(crate) session: &'a Session,
+    pub(crate) congestion_control: CongestionControl,
+    pub(crate) max_size: Option<usize>,
+    pub(crate) state: Arc<Mutex<BatchState>>,
+}
+
+impl Batch<'_> {
+    /// Flush this [Batch](Batch) as soon as the size of its payloads reaches `max_size` bytes.
+    #[inline]
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Flush this [Batch](Batch) every `period`, until it's dropped.
+    pub fn flush_period(self, period: Duration) -> Self {
+        let session = self.session.clone();
+        let state = Arc::downgrade(&self.state);
+        let congestion_control = self.congestion_control;
+        task::spawn(async move {
+            loop {
+                task::sleep(period).await;
+                match state.upgrade() {
+                    Some(state) => {
+                        let samples = zlock!(state).take();
+                        session.write_batch(samples, congestion_control);
+                    }
+                    None => break,
+                }
+            }
+        });
+        self
+    }
+
+    /// Add a write of data to this [Batch](Batch).
+    ///
+    /// # Arguments
+    ///
+    /// * `resource` - The resource key to write
+    /// * `payload` - The value to write
+    ///
+    /// # Examples
+    /// ```
+    /// # async_std::task::block_on(async {
+    /// use zenoh::net::*;
+    ///
+    /// let session = open(config::peer()).await.unwrap();
+    /// let batch = session.batch(CongestionControl::Drop);
+    /// batch.write(&"/resource/name".into(), "value".as_bytes().into()).await.unwrap();
+    /// batch.flush().await.unwrap();
+    /// # })
+    /// ```
+    #[inline]
+    pub fn write(&self, resource: &ResKey, payload: ZBuf) -> ZResolvedFuture<ZResult<()>> {
+        self.add(resource, payload, None)
+    }
+
+    /// Add a write of data with options to this [Batch](Batch).
+    ///
+    /// # Arguments
+    ///
+    /// * `resource` - The resource key to write
+    /// * `payload` - The value to write
+    /// * `encoding` - The encoding of the value
+    /// * `kind` - The kind of value
+    ///
+    /// # Examples
+    /// ```
+    /// # async_std::task::block_on(async {
+    /// use zenoh::net::*;
+    ///
+    /// let session = open(config::peer()).await.unwrap();
+    /// let batch = session.batch(CongestionControl::Drop);
+    /// batch.write_ext(&"/resource/name".into(), ZBuf::new(), encoding::NONE, data_kind::DELETE).await.unwrap();
+    /// batch.flush().await.unwrap();
+    /// # })
+    /// ```
+    #[inline]
+    pub fn write_ext(
+        &self,
+        resource: &ResKey,
+        payload: ZBuf,
+        encoding: ZInt,
+        kind: ZInt,
+    ) -> ZResolvedFuture<ZResult<()>> {
+        self.add(resource, payload, Some((encoding, kind)))
+    }
+
+    fn add(
+        &self,
+        resource: &ResKey,
+        payload: ZBuf,
+        encoding_and_kind: Option<(ZInt, ZInt)>,
+    ) -> ZResolvedFuture<ZResult<()>> {
+        let sample = match self
+            .session
+            .new_batched_sample(resource, payload, encoding_and_kind)
+        {
+            Ok(sample) => sample,
+            Err(e) => return zresolved!(Err(e)),
+        };
+        let mut state = zlock!(self.state);
+        state.size += sample.payload.len();
+        state.samples.push(sample);
+        if self
+            .max_size
+            .map_or(false, |max_size| state.size >= max_size)
+        {
+            let samples = state.take();
+            drop(state);
+            self.session.write_batch(samples, self.congestion_control);
+        }
+        zresolved!(Ok(()))
+    }
+
+    /// Send all the writes added to this [Batch](Batch) since its last flush.
+    ///
+    /// # Examples
+    /// ```
+    /// # async_std::task::block_on(async {
+    /// use zenoh::net::*;
+    ///
+    /// let session = open(config::peer()).await.unwrap();
+    /// let batch = session.batch(CongestionControl::Block);
+    /// for i in 0..10u8 {
+    ///     batch.write(&"/resource/name".into(), vec![i].into()).await.unwrap();
+    /// }
+    /// batch.flush().await.unwrap();
+    /// # })
+    /// ```
+    pub fn flush(&self) -> ZResolvedFuture<ZResult<()>> {
+        let samples = zlock!(self.state).take();
+        self.session.write_batch(samples, self.congestion_control);
+        zresolved!(Ok(()))
+    }
+}
+
+impl Drop for Batch<'_> {
+    fn drop(&mut self) {
+        let samples = zlock!(self.state).take();
+        self.session.write_batch(samples, self.congestion_control);
+    }
+}
+
+impl fmt::Debug for Batch<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let state = zlock!(self.state);
+        write!(
+            f,
+            "Batch{{ samples:{}, size:{} }}",
+            state.samples.len(),
+            state.size
+        )
+    }
+}
+
 pub(crate) enum SubscriberInvoker {
     Sender(Sender<Sample>),
     Handler(Arc<RwLock<DataHandler>>),
@@ -272,9 +697,20 @@ pub(crate) struct SubscriberState {
     pub(crate) id: Id,
     pub(crate) reskey: ResKey,
     pub(crate) resname: String,
+    pub(crate) filter: Option<PayloadFilter>,
     pub(crate) invoker: SubscriberInvoker,
 }
 
+impl SubscriberState {
+    #[inline]
+    pub(crate) fn accepts(&self, payload: &ZBuf) -> bool {
+        match &self.filter {
+            Some(filter) => filter.matches(&payload.contiguous()),
+            None => true,
+        }
+    }
+}
+
 impl fmt::Debug for SubscriberState {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -317,7 +753,9 @@ impl Subscriber<'_> {
     /// # let sub_info = SubInfo {
     /// #     reliability: Reliability::Reliable,
     /// #     mode: SubMode::Pull,
-    /// #     period: None
+    /// #     period: None,
+    /// #     history: History::None,
+    /// #     filter: None,
     /// # };
     /// let mut subscriber = session.declare_subscriber(&"/resource/name".into(), &sub_info).await.unwrap();
     /// async_std::task::spawn(subscriber.receiver().clone().for_each(
@@ -344,7 +782,9 @@ impl Subscriber<'_> {
     /// # let sub_info = SubInfo {
     /// #     reliability: Reliability::Reliable,
     /// #     mode: SubMode::Push,
-    /// #     period: None
+    /// #     period: None,
+    /// #     history: History::None,
+    /// #     filter: None,
     /// # };
     /// let subscriber = session.declare_subscriber(&"/resource/name".into(), &sub_info).await.unwrap();
     /// subscriber.undeclare().await.unwrap();
@@ -392,7 +832,9 @@ impl CallbackSubscriber<'_> {
     /// # let sub_info = SubInfo {
     /// #     reliability: Reliability::Reliable,
     /// #     mode: SubMode::Pull,
-    /// #     period: None
+    /// #     period: None,
+    /// #     history: History::None,
+    /// #     filter: None,
     /// # };
     /// let subscriber = session.declare_callback_subscriber(&"/resource/name".into(), &sub_info,
     ///     |sample| { println!("Received : {} {}", sample.res_name, sample.payload); }
@@ -418,7 +860,9 @@ impl CallbackSubscriber<'_> {
     /// # let sub_info = SubInfo {
     /// #     reliability: Reliability::Reliable,
     /// #     mode: SubMode::Push,
-    /// #     period: None
+    /// #     period: None,
+    /// #     history: History::None,
+    /// #     filter: None,
     /// # };
     /// # fn data_handler(_sample: Sample) { };
     /// let subscriber = session.declare_callback_subscriber(&"/resource/name".into(), &sub_info, data_handler).await.unwrap();
@@ -451,6 +895,20 @@ zreceiver! {
     pub struct ReplyReceiver : Receiver<Reply> {}
 }
 
+/// An item received for the queries of a [query_many](Session::query_many).
+#[derive(Clone, Debug)]
+pub enum MultiReply {
+    /// A reply to the query of the given index.
+    Reply(usize, Reply),
+    /// No more replies will be received for the query of the given index.
+    Final(usize),
+}
+
+zreceiver! {
+    #[derive(Clone)]
+    pub struct MultiReplyReceiver : Receiver<MultiReply> {}
+}
+
 pub(crate) struct QueryableState {
     pub(crate) id: Id,
     pub(crate) reskey: ResKey,
@@ -525,23 +983,25 @@ impl fmt::Debug for Queryable<'_> {
 #[derive(Clone)]
 pub struct RepliesSender {
     pub(crate) kind: ZInt,
-    pub(crate) sender: Sender<(ZInt, Sample)>,
+    // The index of the replying queryable among the queryables receiving the query.
+    pub(crate) index: usize,
+    pub(crate) sender: Sender<(usize, ZInt, Sample)>,
 }
 
 impl RepliesSender {
     #[inline(always)]
     pub fn send(&'_ self, msg: Sample) {
-        if let Err(e) = self.sender.send((self.kind, msg)) {
+        if let Err(e) = self.sender.send((self.index, self.kind, msg)) {
             log::error!("Error sending reply: {}", e);
         }
     }
 
     #[inline(always)]
     pub fn try_send(&self, msg: Sample) -> Result<(), TrySendError<Sample>> {
-        match self.sender.try_send((self.kind, msg)) {
+        match self.sender.try_send((self.index, self.kind, msg)) {
             Ok(()) => Ok(()),
-            Err(TrySendError::Full(sample)) => Err(TrySendError::Full(sample.1)),
-            Err(TrySendError::Disconnected(sample)) => Err(TrySendError::Disconnected(sample.1)),
+            Err(TrySendError::Full(sample)) => Err(TrySendError::Full(sample.2)),
+            Err(TrySendError::Disconnected(sample)) => Err(TrySendError::Disconnected(sample.2)),
         }
     }
 
@@ -567,7 +1027,7 @@ impl RepliesSender {
 
     #[inline(always)]
     pub async fn send_async(&self, msg: Sample) {
-        if let Err(e) = self.sender.send_async((self.kind, msg)).await {
+        if let Err(e) = self.sender.send_async((self.index, self.kind, msg)).await {
             log::error!("Error sending reply: {}", e);
         }
     }
@@ -13,7 +13,7 @@
 //
 use crate::net::queryable::EVAL;
 use crate::net::{
-    data_kind, encoding, CallbackSubscriber, CongestionControl, DataInfo, Query,
+    data_kind, encoding, CallbackSubscriber, CongestionControl, DataInfo, History, Query,
     QueryConsolidation, QueryTarget, Queryable, Receiver, RecvError, RecvTimeoutError, Reliability,
     RepliesSender, Reply, ReplyReceiver, ResKey, Sample, SampleReceiver, Session, SubInfo, SubMode,
     Subscriber, TryRecvError, ZBuf, ZFuture, ZInt, ZResolvedFuture,
@@ -273,6 +273,8 @@ impl Workspace<'_> {
                 reliability: Reliability::Reliable,
                 mode: SubMode::Push,
                 period: None,
+                history: History::None,
+                filter: None,
             };
 
             self.session()
@@ -335,6 +337,8 @@ impl Workspace<'_> {
                 reliability: Reliability::Reliable,
                 mode: SubMode::Push,
                 period: None,
+                history: History::None,
+                filter: None,
             };
 
             let subscriber = self
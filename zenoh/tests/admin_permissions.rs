@@ -0,0 +1,75 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use async_std::prelude::*;
+use async_std::sync::Arc;
+use async_std::task;
+use std::time::Duration;
+use zenoh::net::plugins::PluginsMgr;
+use zenoh::net::runtime::AdminSpace;
+use zenoh::net::*;
+use zenoh_util::properties::config::*;
+use zenoh_util::LibLoader;
+
+const ROUTER_LOCATOR: &str = "tcp/127.0.0.1:14497";
+const TIMEOUT: Duration = Duration::from_secs(10);
+const SLEEP: Duration = Duration::from_millis(500);
+
+#[test]
+fn admin_read() {
+    task::block_on(async {
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "router".to_string());
+        config.insert(ZN_LISTENER_KEY, ROUTER_LOCATOR.to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        config.insert(ZN_ADMIN_READ_KEY, "/stats,/health".to_string());
+        let router = open(config).await.unwrap();
+        let plugins_mgr = Arc::new(PluginsMgr::new(LibLoader::default()));
+        AdminSpace::start(router.runtime(), plugins_mgr, "test".to_string()).await;
+        let root_path = format!("/@/router/{}", router.runtime().get_pid_str());
+
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "client".to_string());
+        config.insert(ZN_PEER_KEY, ROUTER_LOCATOR.to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        let client = open(config).await.unwrap();
+        task::sleep(SLEEP).await;
+
+        // only the paths allowed by admin_read answer the queries
+        let replies: Vec<Reply> = client
+            .query(
+                &format!("{}/**", root_path).into(),
+                "",
+                QueryTarget::default(),
+                QueryConsolidation::none(),
+            )
+            .await
+            .unwrap()
+            .collect()
+            .timeout(TIMEOUT)
+            .await
+            .unwrap();
+        let mut paths: Vec<String> = replies.into_iter().map(|r| r.data.res_name).collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                format!("{}/health", root_path),
+                format!("{}/stats", root_path)
+            ]
+        );
+
+        client.close().await.unwrap();
+        router.close().await.unwrap();
+    });
+}
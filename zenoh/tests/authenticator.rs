@@ -11,25 +11,40 @@
 // Contributors:
 //   ADLINK zenoh team, <zenoh@adlink-labs.tech>
 //
+use async_std::net::TcpListener;
+use async_std::prelude::*;
 use async_std::sync::Arc;
 use async_std::task;
+use ring::rand::SystemRandom;
+use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING};
 use std::any::Any;
 use std::collections::HashMap;
-use std::time::Duration;
-use zenoh::net::protocol::core::{whatami, PeerId};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use zenoh::net::protocol::core::{whatami, PeerId, Property};
+use zenoh::net::protocol::io::{WBuf, ZBuf};
 use zenoh::net::protocol::link::{Link, Locator, LocatorProperty};
 use zenoh::net::protocol::proto::ZenohMessage;
 #[cfg(feature = "zero-copy")]
 use zenoh::net::protocol::session::authenticator::SharedMemoryAuthenticator;
-use zenoh::net::protocol::session::authenticator::UserPasswordAuthenticator;
+use zenoh::net::protocol::session::authenticator::{
+    AuthenticatedPeerLink, JwtAuthenticator, JwtValidator, PeerAuthenticatorTrait,
+    UserPasswordAuthenticator,
+};
 use zenoh::net::protocol::session::{
-    DummySessionEventHandler, Session, SessionEventHandler, SessionHandler, SessionManager,
-    SessionManagerConfig, SessionManagerOptionalConfig,
+    CloseReason, DummySessionEventHandler, Session, SessionEventHandler, SessionHandler,
+    SessionManager, SessionManagerConfig, SessionManagerOptionalConfig,
 };
 use zenoh_util::core::ZResult;
+use zenoh_util::crypto::hmac;
 use zenoh_util::zasync_executor_init;
 
 const SLEEP: Duration = Duration::from_millis(100);
+#[cfg(feature = "transport_tcp")]
+const JWKS_ADDR_TCP: &str = "127.0.0.1:14454";
+#[cfg(feature = "transport_tls")]
+const JWKS_ADDR_TLS: &str = "127.0.0.1:14495";
+#[cfg(feature = "transport_quic")]
+const JWKS_ADDR_QUIC: &str = "127.0.0.1:14496";
 
 #[cfg(test)]
 struct SHRouterAuthenticator;
@@ -64,7 +79,7 @@ impl SessionEventHandler for MHRouterAuthenticator {
     fn new_link(&self, _link: Link) {}
     fn del_link(&self, _link: Link) {}
     fn closing(&self) {}
-    fn closed(&self) {}
+    fn closed(&self, _reason: CloseReason) {}
 
     fn as_any(&self) -> &dyn Any {
         self
@@ -123,17 +138,9 @@ async fn authenticator_user_password(
 
     let peer_authenticator_router = Arc::new(UserPasswordAuthenticator::new(lookup, None));
     let opt_config = SessionManagerOptionalConfig {
-        lease: None,
-        keep_alive: None,
-        sn_resolution: None,
-        open_timeout: None,
-        open_incoming_pending: None,
-        batch_size: None,
-        max_sessions: None,
-        max_links: None,
         peer_authenticator: Some(vec![peer_authenticator_router.clone().into()]),
-        link_authenticator: None,
         locator_property: locator_property.clone(),
+        ..Default::default()
     };
     let router_manager = SessionManager::new(config, Some(opt_config));
 
@@ -150,17 +157,9 @@ async fn authenticator_user_password(
         Some((user01.clone().into(), password01.clone().into())),
     );
     let opt_config = SessionManagerOptionalConfig {
-        lease: None,
-        keep_alive: None,
-        sn_resolution: None,
-        open_timeout: None,
-        open_incoming_pending: None,
-        batch_size: None,
-        max_sessions: None,
-        max_links: None,
         peer_authenticator: Some(vec![peer_authenticator_client01.into()]),
-        link_authenticator: None,
         locator_property: locator_property.clone(),
+        ..Default::default()
     };
     let client01_manager = SessionManager::new(config, Some(opt_config));
 
@@ -177,17 +176,9 @@ async fn authenticator_user_password(
         Some((user02.clone().into(), password02.clone().into())),
     );
     let opt_config = SessionManagerOptionalConfig {
-        lease: None,
-        keep_alive: None,
-        sn_resolution: None,
-        open_timeout: None,
-        open_incoming_pending: None,
-        batch_size: None,
-        max_sessions: None,
-        max_links: None,
         peer_authenticator: Some(vec![peer_authenticator_client02.into()]),
-        link_authenticator: None,
         locator_property: locator_property.clone(),
+        ..Default::default()
     };
     let client02_manager = SessionManager::new(config, Some(opt_config));
 
@@ -202,17 +193,9 @@ async fn authenticator_user_password(
     let peer_authenticator_client03 =
         UserPasswordAuthenticator::new(lookup, Some((user03.into(), password03.into())));
     let opt_config = SessionManagerOptionalConfig {
-        lease: None,
-        keep_alive: None,
-        sn_resolution: None,
-        open_timeout: None,
-        open_incoming_pending: None,
-        batch_size: None,
-        max_sessions: None,
-        max_links: None,
         peer_authenticator: Some(vec![peer_authenticator_client03.into()]),
-        link_authenticator: None,
         locator_property,
+        ..Default::default()
     };
     let client03_manager = SessionManager::new(config, Some(opt_config));
 
@@ -235,6 +218,13 @@ async fn authenticator_user_password(
     println!("Session Authenticator UserPassword [2a1]: {:?}", res);
     assert!(res.is_ok());
     let c_ses1 = res.unwrap();
+    let parameters = c_ses1.get_parameters().unwrap();
+    assert_eq!(parameters.auth_methods, vec!["usrpwd"]);
+    let r_ses1 = router_manager.get_session(&client01_id).unwrap();
+    assert_eq!(
+        r_ses1.get_parameters().unwrap().auth_methods,
+        vec!["usrpwd"]
+    );
 
     /* [3] */
     println!("Session Authenticator UserPassword [3a1]");
@@ -303,6 +293,222 @@ async fn authenticator_user_password(
     task::sleep(SLEEP).await;
 }
 
+// Returns a compact JWT with `claims`, signed by `key_pair` with ES256.
+fn jwt_sign(key_pair: &EcdsaKeyPair, kid: &str, claims: serde_json::Value) -> String {
+    let encode = |bytes: &[u8]| base64::encode_config(bytes, base64::URL_SAFE_NO_PAD);
+    let header = serde_json::json!({"alg": "ES256", "typ": "JWT", "kid": kid});
+    let message = format!(
+        "{}.{}",
+        encode(header.to_string().as_bytes()),
+        encode(claims.to_string().as_bytes())
+    );
+    let signature = key_pair
+        .sign(&SystemRandom::new(), message.as_bytes())
+        .unwrap();
+    format!("{}.{}", message, encode(signature.as_ref()))
+}
+
+fn jwt_key_pair() -> EcdsaKeyPair {
+    let pkcs8 =
+        EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &SystemRandom::new())
+            .unwrap();
+    EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref()).unwrap()
+}
+
+// Serves `jwks` to the HTTP requests received on `addr`.
+async fn jwks_server(addr: &str, jwks: String) {
+    let listener = TcpListener::bind(addr).await.unwrap();
+    task::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request).await;
+            let response = format!(
+                "HTTP/1.0 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                jwks.len(),
+                jwks
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+        }
+    });
+}
+
+async fn authenticator_jwt(
+    locator: Locator,
+    locator_property: Option<Vec<LocatorProperty>>,
+    jwks_addr: &str,
+    encrypted: bool,
+) {
+    let key_pair = jwt_key_pair();
+    let point = key_pair.public_key().as_ref();
+    let jwks = serde_json::json!({"keys": [{
+        "kty": "EC",
+        "crv": "P-256",
+        "kid": "key01",
+        "x": base64::encode_config(&point[1..33], base64::URL_SAFE_NO_PAD),
+        "y": base64::encode_config(&point[33..], base64::URL_SAFE_NO_PAD),
+    }]});
+    jwks_server(jwks_addr, jwks.to_string()).await;
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let claims =
+        |sub: &str, aud: &str, exp: u64| serde_json::json!({"sub": sub, "aud": aud, "exp": exp});
+
+    /* [ROUTER] */
+    let router_id = PeerId::new(1, [0u8; PeerId::MAX_SIZE]);
+    let config = SessionManagerConfig {
+        version: 0,
+        whatami: whatami::ROUTER,
+        id: router_id.clone(),
+        handler: Arc::new(SHRouterAuthenticator::new()),
+    };
+    // An http:// JWKS is only accepted when explicitly allowed
+    let res = JwtValidator::new(
+        &format!("http://{}/jwks", jwks_addr),
+        false,
+        Some("zenoh".to_string()),
+        "sub".to_string(),
+    )
+    .await;
+    assert!(res.is_err());
+    let validator = JwtValidator::new(
+        &format!("http://{}/jwks", jwks_addr),
+        true,
+        Some("zenoh".to_string()),
+        "sub".to_string(),
+    )
+    .await
+    .unwrap();
+    let opt_config = SessionManagerOptionalConfig {
+        peer_authenticator: Some(vec![JwtAuthenticator::new(None, Some(validator)).into()]),
+        locator_property: locator_property.clone(),
+        ..Default::default()
+    };
+    let router_manager = SessionManager::new(config, Some(opt_config));
+
+    /* [CLIENT] */
+    let client_manager = |id: u8, token: Option<String>| {
+        let config = SessionManagerConfig {
+            version: 0,
+            whatami: whatami::CLIENT,
+            id: PeerId::new(1, [id; PeerId::MAX_SIZE]),
+            handler: Arc::new(SHClientAuthenticator::new()),
+        };
+        let opt_config = SessionManagerOptionalConfig {
+            peer_authenticator: Some(vec![JwtAuthenticator::new(token, None).into()]),
+            locator_property: locator_property.clone(),
+            ..Default::default()
+        };
+        SessionManager::new(config, Some(opt_config))
+    };
+    // A valid token
+    let client01_manager = client_manager(
+        1,
+        Some(jwt_sign(
+            &key_pair,
+            "key01",
+            claims("user01", "zenoh", now + 3600),
+        )),
+    );
+    // An expired token
+    let client02_manager = client_manager(
+        2,
+        Some(jwt_sign(
+            &key_pair,
+            "key01",
+            claims("user02", "zenoh", now - 3600),
+        )),
+    );
+    // A token intended for another audience
+    let client03_manager = client_manager(
+        3,
+        Some(jwt_sign(
+            &key_pair,
+            "key01",
+            claims("user03", "other", now + 3600),
+        )),
+    );
+    // A token signed with another key
+    let client04_manager = client_manager(
+        4,
+        Some(jwt_sign(
+            &jwt_key_pair(),
+            "key01",
+            claims("user04", "zenoh", now + 3600),
+        )),
+    );
+    // No token
+    let client05_manager = client_manager(5, None);
+
+    /* [1] */
+    println!("\nSession Authenticator JWT [1a1]");
+    let res = router_manager.add_listener(&locator).await;
+    println!("Session Authenticator JWT [1a1]: {:?}", res);
+    assert!(res.is_ok());
+
+    /* [2] */
+    // Open a session with a valid token
+    // -> This should be accepted, the client being authenticated as the subject of the token,
+    //    unless the link is unencrypted: the client doesn't send its token there
+    println!("Session Authenticator JWT [2a1]");
+    let res = client01_manager.open_session(&locator).await;
+    println!("Session Authenticator JWT [2a1]: {:?}", res);
+    if !encrypted {
+        assert!(res.is_err());
+
+        let res = router_manager.del_listener(&locator).await;
+        assert!(res.is_ok());
+        task::sleep(SLEEP).await;
+        return;
+    }
+    assert!(res.is_ok());
+    let c_ses1 = res.unwrap();
+    assert_eq!(c_ses1.get_parameters().unwrap().auth_methods, vec!["jwt"]);
+    let r_ses1 = router_manager
+        .get_session(&PeerId::new(1, [1u8; PeerId::MAX_SIZE]))
+        .unwrap();
+    let parameters = r_ses1.get_parameters().unwrap();
+    assert_eq!(parameters.auth_methods, vec!["jwt"]);
+    assert_eq!(parameters.auth_subject, Some("user01".to_string()));
+
+    /* [3] */
+    // Open sessions with invalid tokens or without token
+    // -> These should be rejected
+    for (i, manager) in [
+        &client02_manager,
+        &client03_manager,
+        &client04_manager,
+        &client05_manager,
+    ]
+    .iter()
+    .enumerate()
+    {
+        println!("Session Authenticator JWT [3a{}]", i + 1);
+        let res = manager.open_session(&locator).await;
+        println!("Session Authenticator JWT [3a{}]: {:?}", i + 1, res);
+        assert!(res.is_err());
+    }
+
+    /* [4] */
+    println!("Session Authenticator JWT [4a1]");
+    let res = c_ses1.close().await;
+    println!("Session Authenticator JWT [4a1]: {:?}", res);
+    assert!(res.is_ok());
+
+    task::sleep(SLEEP).await;
+
+    /* [5] */
+    // Perform clean up of the open locators
+    println!("Session Authenticator JWT [5a1]");
+    let res = router_manager.del_listener(&locator).await;
+    println!("Session Authenticator JWT [5a1]: {:?}", res);
+    assert!(res.is_ok());
+
+    task::sleep(SLEEP).await;
+}
+
 #[cfg(feature = "zero-copy")]
 async fn authenticator_shared_memory(
     locator: Locator,
@@ -324,17 +530,9 @@ async fn authenticator_shared_memory(
 
     let peer_authenticator_router = SharedMemoryAuthenticator::new();
     let opt_config = SessionManagerOptionalConfig {
-        lease: None,
-        keep_alive: None,
-        sn_resolution: None,
-        open_timeout: None,
-        open_incoming_pending: None,
-        batch_size: None,
-        max_sessions: None,
-        max_links: None,
         peer_authenticator: Some(vec![peer_authenticator_router.into()]),
-        link_authenticator: None,
         locator_property: locator_property.clone(),
+        ..Default::default()
     };
     let router_manager = SessionManager::new(config, Some(opt_config));
 
@@ -347,17 +545,9 @@ async fn authenticator_shared_memory(
     };
     let peer_authenticator_client = SharedMemoryAuthenticator::new();
     let opt_config = SessionManagerOptionalConfig {
-        lease: None,
-        keep_alive: None,
-        sn_resolution: None,
-        open_timeout: None,
-        open_incoming_pending: None,
-        batch_size: None,
-        max_sessions: None,
-        max_links: None,
         peer_authenticator: Some(vec![peer_authenticator_client.into()]),
-        link_authenticator: None,
         locator_property,
+        ..Default::default()
     };
     let client_manager = SessionManager::new(config, Some(opt_config));
 
@@ -381,6 +571,12 @@ async fn authenticator_shared_memory(
     assert!(res.is_ok());
     let c_ses1 = res.unwrap();
     assert!(c_ses1.is_shm().unwrap());
+    assert!(c_ses1.get_parameters().unwrap().is_shm);
+    assert!(c_ses1
+        .get_parameters()
+        .unwrap()
+        .auth_methods
+        .contains(&"shm"));
 
     /* [3] */
     println!("Session Authenticator SharedMemory [3a1]");
@@ -400,6 +596,117 @@ async fn authenticator_shared_memory(
     task::sleep(SLEEP).await;
 }
 
+// Runs the InitSyn of `opener` on `link`, then the InitAck and OpenSyn with `acceptor`,
+// and returns the properties of the InitSyn and of the OpenSyn
+async fn user_password_handshake(
+    opener: &UserPasswordAuthenticator,
+    acceptor: &UserPasswordAuthenticator,
+    link: &AuthenticatedPeerLink,
+) -> (Vec<Property>, Vec<Property>) {
+    let opener_id = PeerId::new(1, [1u8; PeerId::MAX_SIZE]);
+    let acceptor_id = PeerId::new(1, [0u8; PeerId::MAX_SIZE]);
+    let init_syn = opener
+        .get_init_syn_properties(link, &opener_id)
+        .await
+        .unwrap()
+        .properties;
+    let init_ack = acceptor
+        .handle_init_syn(link, &opener_id, 1 << 28, &init_syn)
+        .await
+        .unwrap()
+        .properties;
+    let open_syn = opener
+        .handle_init_ack(link, &acceptor_id, 1 << 28, &init_ack)
+        .await
+        .unwrap()
+        .properties;
+    (init_syn, open_syn)
+}
+
+#[test]
+fn authenticator_user_password_replay() {
+    task::block_on(async {
+        let user = "user01".to_string();
+        let password = "password01".to_string();
+        let mut lookup: HashMap<Vec<u8>, Vec<u8>> = HashMap::new();
+        lookup.insert(user.clone().into(), password.clone().into());
+        let acceptor = UserPasswordAuthenticator::new(lookup, None);
+        let opener =
+            UserPasswordAuthenticator::new(HashMap::new(), Some((user.into(), password.into())));
+        let new_link = |port: u16| AuthenticatedPeerLink {
+            src: format!("tcp/127.0.0.1:{}", port).parse().unwrap(),
+            dst: "tcp/127.0.0.1:7447".parse().unwrap(),
+            peer_id: None,
+            properties: None,
+            peer_certificate: None,
+        };
+
+        let link = new_link(10001);
+        let (init_syn, open_syn) = user_password_handshake(&opener, &acceptor, &link).await;
+        assert!(acceptor.handle_open_syn(&link, &open_syn).await.is_ok());
+        assert_eq!(acceptor.rejected_replays(), 0);
+
+        // The InitSyn is replayed on another link
+        let link = new_link(10002);
+        let opener_id = PeerId::new(1, [1u8; PeerId::MAX_SIZE]);
+        let res = acceptor
+            .handle_init_syn(&link, &opener_id, 1 << 28, &init_syn)
+            .await;
+        assert!(res.is_err());
+        assert_eq!(acceptor.rejected_replays(), 1);
+
+        // The OpenSyn is replayed in answer to a new challenge
+        let link = new_link(10003);
+        let _ = user_password_handshake(&opener, &acceptor, &link).await;
+        assert!(acceptor.handle_open_syn(&link, &open_syn).await.is_err());
+        assert_eq!(acceptor.rejected_replays(), 2);
+    });
+}
+
+#[test]
+fn authenticator_user_password_version_0() {
+    task::block_on(async {
+        let user = "user01".to_string();
+        let password = "password01".to_string();
+        let mut lookup: HashMap<Vec<u8>, Vec<u8>> = HashMap::new();
+        lookup.insert(user.clone().into(), password.clone().into());
+        let acceptor = UserPasswordAuthenticator::new(lookup, None);
+        let link = AuthenticatedPeerLink {
+            src: "tcp/127.0.0.1:10004".parse().unwrap(),
+            dst: "tcp/127.0.0.1:7447".parse().unwrap(),
+            peer_id: None,
+            properties: None,
+            peer_certificate: None,
+        };
+        let opener_id = PeerId::new(1, [1u8; PeerId::MAX_SIZE]);
+
+        // A version 0 opener only sends its version in the InitSyn
+        let usrpwd = |value: WBuf| {
+            vec![Property {
+                key: 1,
+                value: ZBuf::from(value).to_vec(),
+            }]
+        };
+        let mut wbuf = WBuf::new(64, false);
+        wbuf.write_zint(0);
+        let init_ack = acceptor
+            .handle_init_syn(&link, &opener_id, 1 << 28, &usrpwd(wbuf))
+            .await
+            .unwrap()
+            .properties;
+        let nonce = ZBuf::from(init_ack[0].value.clone()).read_zint().unwrap();
+
+        // And answers with the HMAC of the password keyed by the nonce of the acceptor
+        let hmac = hmac::sign(&nonce.to_le_bytes(), password.as_bytes()).unwrap();
+        let mut wbuf = WBuf::new(64, false);
+        wbuf.write_bytes_array(user.as_bytes());
+        wbuf.write_bytes_array(&hmac);
+        let res = acceptor.handle_open_syn(&link, &usrpwd(wbuf)).await;
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap().session.methods, vec!["usrpwd"]);
+    });
+}
+
 #[cfg(feature = "transport_tcp")]
 #[test]
 fn authenticator_tcp() {
@@ -410,6 +717,7 @@ fn authenticator_tcp() {
     let locator: Locator = "tcp/127.0.0.1:11447".parse().unwrap();
     task::block_on(async {
         authenticator_user_password(locator.clone(), None).await;
+        authenticator_jwt(locator.clone(), None, JWKS_ADDR_TCP, false).await;
         #[cfg(feature = "zero-copy")]
         authenticator_shared_memory(locator, None).await;
     });
@@ -555,6 +863,13 @@ tOzot3pwe+3SJtpk90xAQrABEO0Zh2unrC8i83ySfg==
     let locator_property = vec![(client_config, server_config).into()];
     task::block_on(async {
         authenticator_user_password(locator.clone(), Some(locator_property.clone())).await;
+        authenticator_jwt(
+            locator.clone(),
+            Some(locator_property.clone()),
+            JWKS_ADDR_TLS,
+            true,
+        )
+        .await;
         #[cfg(feature = "zero-copy")]
         authenticator_shared_memory(locator, Some(locator_property)).await;
     });
@@ -670,6 +985,13 @@ tOzot3pwe+3SJtpk90xAQrABEO0Zh2unrC8i83ySfg==
     let locator_property = vec![(client_config, server_config).into()];
     task::block_on(async {
         authenticator_user_password(locator.clone(), Some(locator_property.clone())).await;
+        authenticator_jwt(
+            locator.clone(),
+            Some(locator_property.clone()),
+            JWKS_ADDR_QUIC,
+            true,
+        )
+        .await;
         #[cfg(feature = "zero-copy")]
         authenticator_shared_memory(locator, Some(locator_property)).await;
     });
@@ -0,0 +1,131 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use async_std::prelude::*;
+use async_std::task;
+use std::time::Duration;
+use zenoh::net::*;
+use zenoh_util::properties::config::*;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+const SLEEP: Duration = Duration::from_millis(500);
+
+async fn open_peer() -> Session {
+    let mut config = ConfigProperties::default();
+    config.insert(ZN_MODE_KEY, "peer".to_string());
+    config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+    open(config).await.unwrap()
+}
+
+async fn next_value(subscriber: &mut Subscriber<'_>) -> Vec<u8> {
+    subscriber
+        .receiver()
+        .next()
+        .timeout(TIMEOUT)
+        .await
+        .unwrap()
+        .unwrap()
+        .payload
+        .to_vec()
+}
+
+async fn nothing_received(subscriber: &mut Subscriber<'_>) -> bool {
+    subscriber.receiver().next().timeout(SLEEP).await.is_err()
+}
+
+#[test]
+fn batch() {
+    task::block_on(async {
+        let session = open_peer().await;
+        let sub_info = SubInfo {
+            reliability: Reliability::Reliable,
+            mode: SubMode::Push,
+            period: None,
+            history: History::None,
+            filter: None,
+        };
+        let mut subscriber = session
+            .declare_subscriber(&"/batch/**".into(), &sub_info)
+            .await
+            .unwrap();
+
+        // explicit flush
+        let batch = session.batch(CongestionControl::Block);
+        batch
+            .write(&"/batch/a".into(), vec![1].into())
+            .await
+            .unwrap();
+        batch
+            .write_ext(
+                &"/batch/a".into(),
+                ZBuf::new(),
+                encoding::NONE,
+                data_kind::DELETE,
+            )
+            .await
+            .unwrap();
+        assert!(nothing_received(&mut subscriber).await);
+        batch.flush().await.unwrap();
+        assert_eq!(next_value(&mut subscriber).await, vec![1]);
+        let sample = subscriber
+            .receiver()
+            .next()
+            .timeout(TIMEOUT)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(sample.data_info.unwrap().kind, Some(data_kind::DELETE));
+
+        // flush when dropped
+        batch
+            .write(&"/batch/a".into(), vec![2].into())
+            .await
+            .unwrap();
+        drop(batch);
+        assert_eq!(next_value(&mut subscriber).await, vec![2]);
+
+        // flush when the max size is reached
+        let batch = session.batch(CongestionControl::Block).max_size(3);
+        for i in 3..6u8 {
+            batch
+                .write(&"/batch/a".into(), vec![i].into())
+                .await
+                .unwrap();
+        }
+        for i in 3..6u8 {
+            assert_eq!(next_value(&mut subscriber).await, vec![i]);
+        }
+        batch
+            .write(&"/batch/a".into(), vec![6].into())
+            .await
+            .unwrap();
+        assert!(nothing_received(&mut subscriber).await);
+        batch.flush().await.unwrap();
+        assert_eq!(next_value(&mut subscriber).await, vec![6]);
+        drop(batch);
+
+        // periodic flush
+        let batch = session
+            .batch(CongestionControl::Block)
+            .flush_period(Duration::from_millis(100));
+        batch
+            .write(&"/batch/a".into(), vec![7].into())
+            .await
+            .unwrap();
+        assert_eq!(next_value(&mut subscriber).await, vec![7]);
+        drop(batch);
+
+        subscriber.undeclare().await.unwrap();
+        session.close().await.unwrap();
+    });
+}
@@ -0,0 +1,90 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use async_std::task;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use zenoh::net::*;
+use zenoh_util::properties::config::*;
+
+const ROUTER_LOCATOR: &str = "tcp/127.0.0.1:14463";
+const RES_NAME: &str = "/pool/value";
+const SLEEP: Duration = Duration::from_millis(500);
+// The router keeps 2 classes of buffers (512 and 1024 bytes) of 32KB each
+const BUFFER_POOL_SIZE: usize = 65_536;
+const BATCH_SIZE: usize = 1_024;
+// Payloads fitting each class, and exceeding the largest class
+const PAYLOAD_SIZES: [usize; 3] = [100, 800, 20_000];
+const NB_WRITES: usize = 10;
+
+fn payload(size: usize, i: usize) -> Vec<u8> {
+    (0..size).map(|j| (i + j) as u8).collect()
+}
+
+#[test]
+fn buffer_pool() {
+    task::block_on(async {
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "router".to_string());
+        config.insert(ZN_LISTENER_KEY, ROUTER_LOCATOR.to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        config.insert(ZN_BATCH_SIZE_KEY, BATCH_SIZE.to_string());
+        config.insert(ZN_BUFFER_POOL_SIZE_KEY, BUFFER_POOL_SIZE.to_string());
+        let router = open(config).await.unwrap();
+
+        let received = Arc::new(Mutex::new(vec![]));
+        let c_received = received.clone();
+        let subscriber = router
+            .declare_callback_subscriber(&RES_NAME.into(), &SubInfo::default(), move |sample| {
+                c_received.lock().unwrap().push(sample.payload.to_vec())
+            })
+            .await
+            .unwrap();
+
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "client".to_string());
+        config.insert(ZN_PEER_KEY, ROUTER_LOCATOR.to_string());
+        let client = open(config).await.unwrap();
+        task::sleep(SLEEP).await;
+
+        // the batches are received onto buffers of the pool, or allocated outside of it
+        // when exceeding its largest class, without altering the messages
+        let mut sent = vec![];
+        for i in 0..NB_WRITES {
+            for size in PAYLOAD_SIZES.iter() {
+                let payload = payload(*size, i);
+                client
+                    .write(&RES_NAME.into(), payload.clone().into())
+                    .await
+                    .unwrap();
+                sent.push(payload);
+            }
+            task::sleep(Duration::from_millis(10)).await;
+        }
+        task::sleep(SLEEP).await;
+        assert_eq!(*received.lock().unwrap(), sent);
+
+        #[cfg(feature = "stats")]
+        {
+            let stats = router.buffer_pool_stats().await;
+            assert!(stats.hits > 0);
+            assert!(stats.misses >= NB_WRITES as u64);
+            assert!(stats.allocated > 0);
+            assert!(stats.allocated_bytes <= BUFFER_POOL_SIZE as u64);
+        }
+
+        subscriber.undeclare().await.unwrap();
+        client.close().await.unwrap();
+        router.close().await.unwrap();
+    });
+}
@@ -23,8 +23,8 @@ use zenoh::net::protocol::io::ZBuf;
 use zenoh::net::protocol::link::{Link, Locator};
 use zenoh::net::protocol::proto::ZenohMessage;
 use zenoh::net::protocol::session::{
-    Session, SessionEventHandler, SessionHandler, SessionManager, SessionManagerConfig,
-    SessionManagerOptionalConfig,
+    CloseReason, Session, SessionEventHandler, SessionHandler, SessionManager,
+    SessionManagerConfig, SessionManagerOptionalConfig,
 };
 use zenoh_util::core::ZResult;
 use zenoh_util::zasync_executor_init;
@@ -80,7 +80,7 @@ impl SessionEventHandler for MHPeer {
     fn new_link(&self, _link: Link) {}
     fn del_link(&self, _link: Link) {}
     fn closing(&self) {}
-    fn closed(&self) {}
+    fn closed(&self, _reason: CloseReason) {}
 
     fn as_any(&self) -> &dyn Any {
         self
@@ -107,16 +107,7 @@ async fn session_concurrent(locator01: Vec<Locator>, locator02: Vec<Locator>) {
     };
     let opt_config = SessionManagerOptionalConfig {
         lease: Some(lease),
-        keep_alive: None,
-        sn_resolution: None,
-        open_timeout: None,
-        open_incoming_pending: None,
-        batch_size: None,
-        max_sessions: None,
-        max_links: None,
-        peer_authenticator: None,
-        link_authenticator: None,
-        locator_property: None,
+        ..Default::default()
     };
     let peer01_manager = SessionManager::new(config, Some(opt_config));
 
@@ -130,16 +121,7 @@ async fn session_concurrent(locator01: Vec<Locator>, locator02: Vec<Locator>) {
     };
     let opt_config = SessionManagerOptionalConfig {
         lease: Some(lease),
-        keep_alive: None,
-        sn_resolution: None,
-        open_timeout: None,
-        open_incoming_pending: None,
-        batch_size: None,
-        max_sessions: None,
-        max_links: None,
-        peer_authenticator: None,
-        link_authenticator: None,
-        locator_property: None,
+        ..Default::default()
     };
     let peer02_manager = SessionManager::new(config, Some(opt_config));
 
@@ -0,0 +1,84 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use async_std::task;
+use std::time::Duration;
+use zenoh::net::runtime::ConnectRetryPolicy;
+use zenoh::net::*;
+use zenoh_util::properties::config::*;
+
+const ROUTER_LOCATOR: &str = "tcp/127.0.0.1:14451";
+
+#[test]
+fn connect_retry_policy() {
+    let mut config = ConfigProperties::default();
+    config.insert(ZN_CONNECT_RETRY_INITIAL_KEY, "100".to_string());
+    config.insert(ZN_CONNECT_RETRY_MAX_KEY, "1000".to_string());
+    config.insert(ZN_CONNECT_RETRY_MULTIPLIER_KEY, "3".to_string());
+    let policy = ConnectRetryPolicy::from_config(&config);
+    assert_eq!(policy.initial, Duration::from_millis(100));
+    let delay = policy.next_delay(policy.initial);
+    assert_eq!(delay, Duration::from_millis(300));
+    let delay = policy.next_delay(delay);
+    assert_eq!(delay, Duration::from_millis(900));
+    assert_eq!(policy.next_delay(delay), Duration::from_millis(1000));
+    assert_eq!(policy.jittered(delay), delay);
+
+    config.insert(ZN_CONNECT_RETRY_JITTER_KEY, "0.5".to_string());
+    let policy = ConnectRetryPolicy::from_config(&config);
+    for _ in 0..100 {
+        let delay = policy.jittered(Duration::from_millis(1000));
+        assert!(delay >= Duration::from_millis(500) && delay <= Duration::from_millis(1500));
+    }
+
+    // invalid values are replaced by the default ones
+    config.insert(ZN_CONNECT_RETRY_MULTIPLIER_KEY, "0.5".to_string());
+    config.insert(ZN_CONNECT_RETRY_JITTER_KEY, "2".to_string());
+    let policy = ConnectRetryPolicy::from_config(&config);
+    assert_eq!(policy.multiplier, ConnectRetryPolicy::default().multiplier);
+    assert_eq!(policy.jitter, ConnectRetryPolicy::default().jitter);
+}
+
+#[test]
+fn connect_retry_state() {
+    task::block_on(async {
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "peer".to_string());
+        config.insert(ZN_PEER_KEY, ROUTER_LOCATOR.to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        config.insert(ZN_CONNECT_RETRY_INITIAL_KEY, "50".to_string());
+        config.insert(ZN_CONNECT_RETRY_MAX_KEY, "200".to_string());
+        let peer = open(config).await.unwrap();
+
+        // the unreachable peer is retried with an increasing delay
+        task::sleep(Duration::from_millis(500)).await;
+        let retries = peer.runtime().get_connect_retries();
+        let state = &retries[&ROUTER_LOCATOR.parse().unwrap()];
+        assert!(state.attempts >= 2);
+        assert!(state.delay > Duration::from_millis(50));
+        assert!(state.delay <= Duration::from_millis(200));
+
+        // once reachable, it's connected and its retry state is cleared
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "router".to_string());
+        config.insert(ZN_LISTENER_KEY, ROUTER_LOCATOR.to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        let router = open(config).await.unwrap();
+        task::sleep(Duration::from_millis(500)).await;
+        assert!(peer.runtime().get_connect_retries().is_empty());
+        assert_eq!(peer.transports().await.len(), 1);
+
+        peer.close().await.unwrap();
+        router.close().await.unwrap();
+    });
+}
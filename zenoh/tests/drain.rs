@@ -0,0 +1,123 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use async_std::prelude::*;
+use async_std::sync::Arc;
+use async_std::task;
+use std::time::Duration;
+use zenoh::net::plugins::PluginsMgr;
+use zenoh::net::protocol::proto::smsg;
+use zenoh::net::runtime::AdminSpace;
+use zenoh::net::*;
+use zenoh_util::properties::config::*;
+use zenoh_util::LibLoader;
+
+const ROUTER_LOCATOR: &str = "tcp/127.0.0.1:14472";
+const DISABLED_ROUTER_LOCATOR: &str = "tcp/127.0.0.1:14473";
+const TIMEOUT: Duration = Duration::from_secs(10);
+const SLEEP: Duration = Duration::from_millis(500);
+
+async fn open_router(locator: &str, admin_write: Option<&str>) -> Session {
+    let mut config = ConfigProperties::default();
+    config.insert(ZN_MODE_KEY, "router".to_string());
+    config.insert(ZN_LISTENER_KEY, locator.to_string());
+    config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+    if let Some(admin_write) = admin_write {
+        config.insert(ZN_ADMIN_WRITE_KEY, admin_write.to_string());
+    }
+    let router = open(config).await.unwrap();
+    let plugins_mgr = Arc::new(PluginsMgr::new(LibLoader::default()));
+    AdminSpace::start(router.runtime(), plugins_mgr, "test".to_string()).await;
+    router
+}
+
+async fn open_client(locator: &str) -> Session {
+    let mut config = ConfigProperties::default();
+    config.insert(ZN_MODE_KEY, "client".to_string());
+    config.insert(ZN_PEER_KEY, locator.to_string());
+    config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+    open(config).await.unwrap()
+}
+
+#[test]
+fn drain() {
+    task::block_on(async {
+        let router = open_router(ROUTER_LOCATOR, Some(ZN_TRUE)).await;
+        let drain_path = format!("/@/router/{}/drain", router.runtime().get_pid_str());
+        let drain_events = router.runtime().drain_events();
+        let client = open_client(ROUTER_LOCATOR).await;
+        task::sleep(SLEEP).await;
+
+        // the invalid timeouts are rejected without draining
+        for timeout in &["inf", "NaN", "1e400", "-1", "abc"] {
+            client
+                .write(&drain_path.clone().into(), timeout.as_bytes().into())
+                .await
+                .unwrap();
+        }
+        assert!(drain_events.recv_async().timeout(SLEEP).await.is_err());
+        assert_eq!(router.runtime().manager().get_sessions().len(), 1);
+
+        // the drain completes once the client is gone, and is notified instead of exiting
+        let close_events = client.runtime().peer_close_events();
+        client
+            .write(&drain_path.clone().into(), "0.2".as_bytes().into())
+            .await
+            .unwrap();
+        drain_events
+            .recv_async()
+            .timeout(TIMEOUT)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(router.runtime().manager().get_sessions().is_empty());
+        assert!(router.runtime().manager().get_listeners().is_empty());
+
+        // the departure of the router is notified to the client
+        let event = close_events
+            .recv_async()
+            .timeout(TIMEOUT)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            event.reason,
+            CloseReason::ClosedByPeer(smsg::close_reason::DRAIN)
+        );
+
+        client.close().await.unwrap();
+        router.close().await.unwrap();
+    });
+}
+
+#[test]
+fn drain_disabled() {
+    task::block_on(async {
+        // the write operations on the admin space are disabled by default
+        let router = open_router(DISABLED_ROUTER_LOCATOR, None).await;
+        let drain_path = format!("/@/router/{}/drain", router.runtime().get_pid_str());
+        let drain_events = router.runtime().drain_events();
+        let client = open_client(DISABLED_ROUTER_LOCATOR).await;
+        task::sleep(SLEEP).await;
+
+        client
+            .write(&drain_path.clone().into(), "0".as_bytes().into())
+            .await
+            .unwrap();
+        assert!(drain_events.recv_async().timeout(SLEEP).await.is_err());
+        assert_eq!(router.runtime().manager().get_sessions().len(), 1);
+
+        client.close().await.unwrap();
+        router.close().await.unwrap();
+    });
+}
@@ -0,0 +1,195 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use async_std::task;
+use std::time::Duration;
+use zenoh::net::protocol::link::{EndPoint, LinkConfig, Locator};
+use zenoh::net::runtime::Runtime;
+use zenoh::net::*;
+use zenoh_util::properties::config::*;
+
+const ROUTER_LISTENER: &str = "tcp/0.0.0.0:14449#batch=8192";
+const ROUTER_LOCATOR: &str = "tcp/127.0.0.1:14449";
+const ECHO_LOCATOR: &str = "tcp/127.0.0.1:14452";
+const PARTIAL_LOCATOR: &str = "tcp/127.0.0.1:14474";
+// An address of TEST-NET-3, that isn't assigned to any local interface
+const UNBINDABLE_LOCATOR: &str = "tcp/203.0.113.1:14474";
+
+#[test]
+fn endpoint_from_str() {
+    let endpoint: EndPoint = "tcp/127.0.0.1:7447#lease=1000;batch=16384".parse().unwrap();
+    assert_eq!(
+        endpoint.locator,
+        "tcp/127.0.0.1:7447".parse::<Locator>().unwrap()
+    );
+    assert_eq!(
+        endpoint.config,
+        LinkConfig {
+            lease: Some(1000),
+            keep_alive: None,
+            keep_alive_echo: None,
+            keep_alive_forced: None,
+            batch_size: Some(16384),
+            proxy: None,
+        }
+    );
+    assert_eq!(
+        endpoint.to_string(),
+        "tcp/127.0.0.1:7447#lease=1000;batch=16384"
+    );
+
+    let endpoint: EndPoint = "tcp/127.0.0.1:7447".parse().unwrap();
+    assert!(endpoint.config.is_empty());
+    assert_eq!(endpoint.to_string(), "tcp/127.0.0.1:7447");
+
+    assert!("tcp/127.0.0.1:7447#unknown=1".parse::<EndPoint>().is_err());
+    assert!("tcp/127.0.0.1:7447#lease=abc".parse::<EndPoint>().is_err());
+    assert!("tcp/127.0.0.1:7447#batch=0".parse::<EndPoint>().is_err());
+
+    let endpoint: EndPoint = "tcp/127.0.0.1:7447#keep_alive_echo=true".parse().unwrap();
+    assert_eq!(endpoint.config.keep_alive_echo, Some(true));
+    assert_eq!(
+        endpoint.to_string(),
+        "tcp/127.0.0.1:7447#keep_alive_echo=true"
+    );
+    assert!("tcp/127.0.0.1:7447#keep_alive_echo=1"
+        .parse::<EndPoint>()
+        .is_err());
+
+    let endpoint: EndPoint = "tcp/127.0.0.1:7447#keep_alive=1000;keep_alive_forced=true"
+        .parse()
+        .unwrap();
+    assert_eq!(endpoint.config.keep_alive, Some(1000));
+    assert_eq!(endpoint.config.keep_alive_forced, Some(true));
+    assert_eq!(
+        endpoint.to_string(),
+        "tcp/127.0.0.1:7447#keep_alive=1000;keep_alive_forced=true"
+    );
+}
+
+#[test]
+fn endpoint_batch_size_override() {
+    task::block_on(async {
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "router".to_string());
+        config.insert(ZN_LISTENER_KEY, ROUTER_LISTENER.to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        let router = open(config).await.unwrap();
+
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "client".to_string());
+        config.insert(
+            ZN_PEER_KEY,
+            format!("{}#lease=2000;keep_alive=200;batch=4096", ROUTER_LOCATOR),
+        );
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        let client = open(config).await.unwrap();
+
+        let transports = client.transports().await;
+        assert_eq!(transports.len(), 1);
+        let batch_sizes = &transports[0].1.batch_sizes;
+        assert_eq!(batch_sizes.len(), 1);
+        assert_eq!(batch_sizes[0].0.to_string(), ROUTER_LOCATOR);
+        assert_eq!(batch_sizes[0].1, 4096);
+
+        // the listener's override applies to the links it accepted on any address
+        let transports = router.transports().await;
+        assert_eq!(transports.len(), 1);
+        let batch_sizes = &transports[0].1.batch_sizes;
+        assert_eq!(batch_sizes.len(), 1);
+        assert_eq!(batch_sizes[0].1, 8192);
+
+        client.close().await.unwrap();
+        router.close().await.unwrap();
+    });
+}
+
+#[test]
+fn endpoint_keep_alive_echo() {
+    task::block_on(async {
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "router".to_string());
+        config.insert(ZN_LISTENER_KEY, ECHO_LOCATOR.to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        let router = open(config).await.unwrap();
+
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "client".to_string());
+        config.insert(
+            ZN_PEER_KEY,
+            format!("{}#keep_alive=100;keep_alive_echo=true", ECHO_LOCATOR),
+        );
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        let client = open(config).await.unwrap();
+
+        // the router echoes the tokens: the link is not degraded
+        task::sleep(Duration::from_secs(1)).await;
+        let sessions = client.runtime().manager().get_sessions();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].get_links().unwrap().len(), 1);
+        assert!(sessions[0].get_degraded_links().unwrap().is_empty());
+
+        client.close().await.unwrap();
+        router.close().await.unwrap();
+    });
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn endpoint_listener_template() {
+    let endpoints = Runtime::expand_listener("tcp/iface:lo:7447#batch=8192").unwrap();
+    assert!(endpoints
+        .iter()
+        .any(|e| e.locator == "tcp/127.0.0.1:7447".parse::<Locator>().unwrap()));
+    assert!(endpoints.iter().all(|e| e.config.batch_size == Some(8192)));
+
+    assert!(Runtime::expand_listener("tcp/iface:lo:abc").is_err());
+    assert!(Runtime::expand_listener("tcp/iface:no-such-iface:7447").is_err());
+    assert!(Runtime::expand_listener("tcp/iface:lo:7447#batch=abc").is_err());
+    assert!(Runtime::expand_listeners("tcp/127.0.0.1:7447,foo/iface:lo:7447").is_err());
+}
+
+#[test]
+fn endpoint_listener_template_link_local() {
+    // the link-local addresses can't be bound without their scope id
+    for endpoint in Runtime::expand_listener("tcp/auto:7447")
+        .unwrap()
+        .into_iter()
+        .chain(Runtime::expand_listener("tcp/iface:eth0:7447").unwrap_or_default())
+    {
+        let locator = endpoint.locator.to_string();
+        assert!(!locator.contains("fe80:"), "{}", locator);
+    }
+}
+
+#[test]
+fn endpoint_listener_failure() {
+    task::block_on(async {
+        // the router fails to open if any of its listeners fails to open
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "router".to_string());
+        config.insert(
+            ZN_LISTENER_KEY,
+            format!("{},{}", PARTIAL_LOCATOR, UNBINDABLE_LOCATOR),
+        );
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        assert!(open(config).await.is_err());
+
+        // or if a listener is invalid
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "router".to_string());
+        config.insert(ZN_LISTENER_KEY, "tcp/iface:no-such-iface:14474".to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        assert!(open(config).await.is_err());
+    });
+}
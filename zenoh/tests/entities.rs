@@ -0,0 +1,146 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use async_std::prelude::*;
+use async_std::sync::Arc;
+use async_std::task;
+use std::time::Duration;
+use zenoh::net::plugins::PluginsMgr;
+use zenoh::net::runtime::AdminSpace;
+use zenoh::net::*;
+use zenoh_util::properties::config::*;
+use zenoh_util::LibLoader;
+
+const ROUTER_LOCATOR: &str = "tcp/127.0.0.1:14471";
+const TIMEOUT: Duration = Duration::from_secs(10);
+const SLEEP: Duration = Duration::from_millis(500);
+
+// Returns the entities declared by the session `pid`, as listed in the admin space of `router`.
+async fn entities(router: &Session, pid: &str) -> serde_json::Value {
+    let selector = format!("/@/router/{}/entities", router.runtime().get_pid_str());
+    let replies: Vec<Reply> = router
+        .query(
+            &selector.into(),
+            "",
+            QueryTarget::default(),
+            QueryConsolidation::default(),
+        )
+        .await
+        .unwrap()
+        .collect()
+        .timeout(TIMEOUT)
+        .await
+        .unwrap();
+    assert_eq!(replies.len(), 1);
+    let json: serde_json::Value =
+        serde_json::from_slice(&replies[0].data.payload.to_vec()).unwrap();
+    json.as_array()
+        .unwrap()
+        .iter()
+        .find(|face| face["pid"] == pid)
+        .cloned()
+        .unwrap()
+}
+
+// The queryables declared by the test, the client may also redeclare the admin space of the router.
+fn entities_queryables(face: &serde_json::Value) -> Vec<serde_json::Value> {
+    face["queryables"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|qabl| qabl["key"].as_str().unwrap().starts_with("/entities/"))
+        .cloned()
+        .collect()
+}
+
+#[test]
+fn entities_admin() {
+    task::block_on(async {
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "router".to_string());
+        config.insert(ZN_LISTENER_KEY, ROUTER_LOCATOR.to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        let router = open(config).await.unwrap();
+        let plugins_mgr = Arc::new(PluginsMgr::new(LibLoader::default()));
+        AdminSpace::start(router.runtime(), plugins_mgr, "test".to_string()).await;
+
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "client".to_string());
+        config.insert(ZN_PEER_KEY, ROUTER_LOCATOR.to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        let client = open(config).await.unwrap();
+        let pid = client.runtime().get_pid_str();
+
+        let publisher = client
+            .declare_publisher(&"/entities/pub".into())
+            .await
+            .unwrap();
+        let sub_info = SubInfo {
+            reliability: Reliability::BestEffort,
+            mode: SubMode::Pull,
+            period: None,
+            history: History::None,
+            filter: None,
+        };
+        let subscriber = client
+            .declare_subscriber(&"/entities/sub/**".into(), &sub_info)
+            .await
+            .unwrap();
+        let queryable = client
+            .declare_queryable(&"/entities/eval".into(), queryable::EVAL)
+            .await
+            .unwrap();
+        task::sleep(SLEEP).await;
+
+        let face = entities(&router, &pid).await;
+        assert_eq!(face["whatami"], "client");
+        let publishers = face["publishers"].as_array().unwrap();
+        assert_eq!(publishers.len(), 1);
+        assert_eq!(publishers[0]["key"], "/entities/pub");
+        assert!(publishers[0]["declared"].as_f64().unwrap() > 0.0);
+        let subscribers = face["subscribers"].as_array().unwrap();
+        assert_eq!(subscribers.len(), 1);
+        assert_eq!(subscribers[0]["key"], "/entities/sub/**");
+        assert_eq!(subscribers[0]["reliability"], "best_effort");
+        assert_eq!(subscribers[0]["mode"], "pull");
+        let queryables = entities_queryables(&face);
+        assert_eq!(queryables.len(), 1);
+        assert_eq!(queryables[0]["key"], "/entities/eval");
+        assert_eq!(queryables[0]["kind"], "eval");
+
+        // the undeclared entities are no longer listed
+        publisher.undeclare().await.unwrap();
+        subscriber.undeclare().await.unwrap();
+        queryable.undeclare().await.unwrap();
+        task::sleep(SLEEP).await;
+        let face = entities(&router, &pid).await;
+        assert!(face["publishers"].as_array().unwrap().is_empty());
+        assert!(face["subscribers"].as_array().unwrap().is_empty());
+        assert!(entities_queryables(&face).is_empty());
+
+        // the undeclarations are recorded with the declaration times of the entities
+        let churn = &face["churn"];
+        assert!(churn["declarations"].as_u64().unwrap() >= 3);
+        assert!(churn["undeclarations"].as_u64().unwrap() >= 3);
+        assert!(churn["rate"].as_f64().unwrap() > 0.0);
+        let undeclared = churn["undeclared"].as_array().unwrap();
+        let sub = undeclared
+            .iter()
+            .find(|e| e["entity"] == "sub:/entities/sub/**")
+            .unwrap();
+        assert!(sub["declared"].as_f64().unwrap() <= sub["undeclared"].as_f64().unwrap());
+
+        client.close().await.unwrap();
+        router.close().await.unwrap();
+    });
+}
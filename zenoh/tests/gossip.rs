@@ -0,0 +1,207 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use async_std::net::UdpSocket;
+use async_std::prelude::*;
+use async_std::task;
+use std::process::Command;
+use std::time::Duration;
+use zenoh::net::protocol::io::{WBuf, ZBuf};
+use zenoh::net::protocol::proto::{SessionBody, SessionMessage};
+use zenoh::net::*;
+use zenoh_util::properties::config::*;
+
+const SEED_LOCATOR: &str = "tcp/127.0.0.1:14467";
+const SEED_GOSSIP: &str = "127.0.0.1:14468";
+const OTHER_LOCATOR: &str = "tcp/127.0.0.1:14469";
+const COLD_SEED_LOCATOR: &str = "tcp/127.0.0.1:14475";
+const COLD_SEED_GOSSIP: &str = "127.0.0.1:14476";
+const COLD_OTHER_LOCATOR: &str = "tcp/127.0.0.1:14477";
+// A gossip seed that never answers
+const DEAD_SEED_GOSSIP: &str = "127.0.0.1:14478";
+const RES_NAME: &str = "/gossip/failover";
+const TIMEOUT: Duration = Duration::from_secs(10);
+const SLEEP: Duration = Duration::from_millis(500);
+// Set in the environment of the process running the seed router
+const SEED_ENV: &str = "ZENOH_TEST_GOSSIP_SEED";
+
+fn router_config(listener: &str) -> ConfigProperties {
+    let mut config = ConfigProperties::default();
+    config.insert(ZN_MODE_KEY, "router".to_string());
+    config.insert(ZN_LISTENER_KEY, listener.to_string());
+    config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+    config
+}
+
+fn client_config() -> ConfigProperties {
+    seed_client_config(SEED_GOSSIP)
+}
+
+fn seed_client_config(seeds: &str) -> ConfigProperties {
+    let mut config = ConfigProperties::default();
+    config.insert(ZN_MODE_KEY, "client".to_string());
+    config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+    config.insert(ZN_GOSSIP_SEEDS_KEY, seeds.to_string());
+    config.insert(ZN_SCOUTING_TIMEOUT_KEY, "1".to_string());
+    config
+}
+
+// Run in a process of its own by gossip_failover, so that it can be killed.
+#[test]
+fn gossip_seed() {
+    if std::env::var(SEED_ENV).is_err() {
+        return;
+    }
+    task::block_on(async {
+        let mut config = router_config(SEED_LOCATOR);
+        config.insert(ZN_GOSSIP_LISTENER_KEY, SEED_GOSSIP.to_string());
+        let _seed = open(config).await.unwrap();
+        task::sleep(6 * TIMEOUT).await;
+    });
+}
+
+#[test]
+fn gossip_failover() {
+    task::block_on(async {
+        let mut seed = Command::new(std::env::current_exe().unwrap())
+            .args(&["--exact", "gossip_seed"])
+            .env(SEED_ENV, "1")
+            .spawn()
+            .unwrap();
+        task::sleep(2 * SLEEP).await;
+
+        let mut config = router_config(OTHER_LOCATOR);
+        config.insert(ZN_PEER_KEY, SEED_LOCATOR.to_string());
+        let other = open(config).await.unwrap();
+        let mut subscriber = other
+            .declare_subscriber(&RES_NAME.into(), &SubInfo::default())
+            .await
+            .unwrap();
+        // let the seed learn the other router by link state
+        task::sleep(2 * SLEEP).await;
+
+        let client = open(client_config()).await.unwrap();
+
+        // the client connects to the seed, and to the router it learned by gossip once it's down
+        seed.kill().unwrap();
+        seed.wait().unwrap();
+        let publish = async {
+            loop {
+                let _ = client.write(&RES_NAME.into(), vec![1, 2, 3].into()).await;
+                task::sleep(SLEEP).await;
+            }
+        };
+        let sample = subscriber
+            .receiver()
+            .next()
+            .race(publish)
+            .timeout(TIMEOUT)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(sample.payload.to_vec(), vec![1, 2, 3]);
+
+        // with no seed and no learned router up, the client can't connect
+        subscriber.undeclare().await.unwrap();
+        other.close().await.unwrap();
+        assert!(open(client_config()).await.is_err());
+
+        client.close().await.unwrap();
+    });
+}
+
+#[test]
+fn gossip_cold_start() {
+    task::block_on(async {
+        let path = std::env::temp_dir().join(format!(
+            "zenoh-gossip-cache-{}.json",
+            uuid::Uuid::new_v4().to_simple()
+        ));
+        let cache = path.to_str().unwrap();
+
+        let mut config = router_config(COLD_SEED_LOCATOR);
+        config.insert(ZN_GOSSIP_LISTENER_KEY, COLD_SEED_GOSSIP.to_string());
+        let seed = open(config).await.unwrap();
+        let mut config = router_config(COLD_OTHER_LOCATOR);
+        config.insert(ZN_PEER_KEY, COLD_SEED_LOCATOR.to_string());
+        let other = open(config).await.unwrap();
+        let other_pid = other.id().await;
+        task::sleep(2 * SLEEP).await;
+
+        // the routers learned by gossip are recorded in the scouting cache
+        let mut config = seed_client_config(COLD_SEED_GOSSIP);
+        config.insert(ZN_SCOUTING_CACHE_KEY, cache.to_string());
+        let client = open(config).await.unwrap();
+        client.close().await.unwrap();
+        seed.close().await.unwrap();
+        assert!(std::fs::read_to_string(&path).unwrap().contains(&other_pid));
+
+        // a client restarted while the seeds are down connects to them
+        let mut config = seed_client_config(DEAD_SEED_GOSSIP);
+        config.insert(ZN_SCOUTING_CACHE_KEY, cache.to_string());
+        let client = open(config).await.unwrap();
+        let transports = client.transports().await;
+        assert_eq!(transports.len(), 1);
+        assert_eq!(transports[0].0.to_string(), other_pid);
+
+        client.close().await.unwrap();
+        other.close().await.unwrap();
+        let _ = std::fs::remove_file(&path);
+    });
+}
+
+#[test]
+fn gossip_rate_limit() {
+    task::block_on(async {
+        let gossip = "127.0.0.1:14479";
+        let mut config = router_config("tcp/127.0.0.1:14480");
+        config.insert(ZN_GOSSIP_LISTENER_KEY, gossip.to_string());
+        let router = open(config).await.unwrap();
+        let router_pid = router.id().await;
+
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let mut wbuf = WBuf::new(64, false);
+        wbuf.write_session_message(&SessionMessage::make_scout(None, true, None));
+        let scout = ZBuf::from(&wbuf).contiguous();
+        let mut buf = vec![0; 65536];
+
+        // a same source is answered once per period
+        socket.send_to(&scout, gossip).await.unwrap();
+        socket.send_to(&scout, gossip).await.unwrap();
+        let (n, _) = socket
+            .recv_from(&mut buf)
+            .timeout(TIMEOUT)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(socket.recv_from(&mut buf).timeout(SLEEP).await.is_err());
+        match ZBuf::from(&buf[..n]).read_session_message() {
+            Some(SessionMessage {
+                body: SessionBody::Hello(hello),
+                ..
+            }) => assert_eq!(hello.pid.unwrap().to_string(), router_pid),
+            msg => panic!("unexpected gossip {:?}", msg),
+        }
+
+        task::sleep(3 * SLEEP).await;
+        socket.send_to(&scout, gossip).await.unwrap();
+        assert!(socket
+            .recv_from(&mut buf)
+            .timeout(TIMEOUT)
+            .await
+            .unwrap()
+            .is_ok());
+
+        router.close().await.unwrap();
+    });
+}
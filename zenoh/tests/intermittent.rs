@@ -24,8 +24,8 @@ use zenoh::net::protocol::io::ZBuf;
 use zenoh::net::protocol::link::{Link, Locator, LocatorProperty};
 use zenoh::net::protocol::proto::ZenohMessage;
 use zenoh::net::protocol::session::{
-    DummySessionEventHandler, Session, SessionEventHandler, SessionHandler, SessionManager,
-    SessionManagerConfig, SessionManagerOptionalConfig,
+    CloseReason, DummySessionEventHandler, Session, SessionEventHandler, SessionHandler,
+    SessionManager, SessionManagerConfig, SessionManagerOptionalConfig,
 };
 use zenoh_util::core::ZResult;
 use zenoh_util::zasync_executor_init;
@@ -112,7 +112,7 @@ impl SessionEventHandler for SCClient {
     fn new_link(&self, _link: Link) {}
     fn del_link(&self, _link: Link) {}
     fn closing(&self) {}
-    fn closed(&self) {}
+    fn closed(&self, _reason: CloseReason) {}
 
     fn as_any(&self) -> &dyn Any {
         self
@@ -132,17 +132,10 @@ async fn session_intermittent(locator: Locator, locator_property: Option<Vec<Loc
         handler: router_handler.clone(),
     };
     let opt_config = SessionManagerOptionalConfig {
-        lease: None,
-        keep_alive: None,
-        sn_resolution: None,
-        open_timeout: None,
-        open_incoming_pending: None,
-        batch_size: None,
         max_sessions: Some(3),
         max_links: Some(1),
-        peer_authenticator: None,
-        link_authenticator: None,
         locator_property: locator_property.clone(),
+        ..Default::default()
     };
     let router_manager = SessionManager::new(config, Some(opt_config));
 
@@ -160,17 +153,10 @@ async fn session_intermittent(locator: Locator, locator_property: Option<Vec<Loc
         handler: Arc::new(SHClientStable::new(counter.clone())),
     };
     let opt_config = SessionManagerOptionalConfig {
-        lease: None,
-        keep_alive: None,
-        sn_resolution: None,
-        open_timeout: None,
-        open_incoming_pending: None,
-        batch_size: None,
         max_sessions: Some(1),
         max_links: Some(1),
-        peer_authenticator: None,
-        link_authenticator: None,
         locator_property: locator_property.clone(),
+        ..Default::default()
     };
     let client01_manager = SessionManager::new(config, Some(opt_config));
 
@@ -182,17 +168,10 @@ async fn session_intermittent(locator: Locator, locator_property: Option<Vec<Loc
         handler: Arc::new(SHClientIntermittent::new()),
     };
     let opt_config = SessionManagerOptionalConfig {
-        lease: None,
-        keep_alive: None,
-        sn_resolution: None,
-        open_timeout: None,
-        open_incoming_pending: None,
-        batch_size: None,
         max_sessions: Some(1),
         max_links: Some(1),
-        peer_authenticator: None,
-        link_authenticator: None,
         locator_property: locator_property.clone(),
+        ..Default::default()
     };
     let client02_manager = SessionManager::new(config, Some(opt_config));
 
@@ -204,17 +183,10 @@ async fn session_intermittent(locator: Locator, locator_property: Option<Vec<Loc
         handler: Arc::new(SHClientIntermittent::new()),
     };
     let opt_config = SessionManagerOptionalConfig {
-        lease: None,
-        keep_alive: None,
-        sn_resolution: None,
-        open_timeout: None,
-        open_incoming_pending: None,
-        batch_size: None,
         max_sessions: Some(1),
         max_links: Some(1),
-        peer_authenticator: None,
-        link_authenticator: None,
         locator_property,
+        ..Default::default()
     };
     let client03_manager = SessionManager::new(config, Some(opt_config));
 
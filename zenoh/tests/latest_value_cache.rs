@@ -0,0 +1,125 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use async_std::prelude::*;
+use async_std::task;
+use std::time::Duration;
+use zenoh::net::*;
+use zenoh_util::properties::config::*;
+
+const ROUTER_LOCATOR: &str = "tcp/127.0.0.1:15447";
+const TIMEOUT: Duration = Duration::from_secs(10);
+const SLEEP: Duration = Duration::from_millis(500);
+
+async fn open_client() -> Session {
+    let mut config = ConfigProperties::default();
+    config.insert(ZN_MODE_KEY, "client".to_string());
+    config.insert(ZN_PEER_KEY, ROUTER_LOCATOR.to_string());
+    config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+    open(config).await.unwrap()
+}
+
+#[test]
+fn latest_value_cache() {
+    task::block_on(async {
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "router".to_string());
+        config.insert(ZN_LISTENER_KEY, ROUTER_LOCATOR.to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        config.insert(ZN_LATEST_VALUE_CACHE_KEY, "/lvc/**".to_string());
+        config.insert(ZN_LATEST_VALUE_CACHE_SIZE_KEY, "2".to_string());
+        let router = open(config).await.unwrap();
+
+        let publisher = open_client().await;
+        for (path, value) in &[("/lvc/a", "1"), ("/lvc/b", "2"), ("/lvc/c", "3")] {
+            publisher
+                .write(&(*path).into(), value.as_bytes().into())
+                .await
+                .unwrap();
+        }
+        publisher
+            .write(&"/other/a".into(), "4".as_bytes().into())
+            .await
+            .unwrap();
+        publisher
+            .write_ext(
+                &"/lvc/c".into(),
+                ZBuf::new(),
+                encoding::NONE,
+                data_kind::DELETE,
+                CongestionControl::Block,
+            )
+            .await
+            .unwrap();
+        task::sleep(SLEEP).await;
+
+        // "/lvc/a" was evicted by "/lvc/c", then "/lvc/c" was deleted
+        let client = open_client().await;
+        let mut sub_info = SubInfo {
+            reliability: Reliability::Reliable,
+            mode: SubMode::Push,
+            period: None,
+            history: History::None,
+            filter: None,
+        };
+        let mut live_subscriber = client
+            .declare_subscriber(&"/**".into(), &sub_info)
+            .await
+            .unwrap();
+        assert!(live_subscriber
+            .receiver()
+            .next()
+            .timeout(SLEEP)
+            .await
+            .is_err());
+
+        // only the subscribers asking for the latest values receive them
+        sub_info.history = History::Latest;
+        let mut subscriber = client
+            .declare_subscriber(&"/**".into(), &sub_info)
+            .await
+            .unwrap();
+        let sample = subscriber
+            .receiver()
+            .next()
+            .timeout(TIMEOUT)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(sample.res_name, "/lvc/b");
+        assert_eq!(sample.payload.to_vec(), b"2".to_vec());
+        assert!(subscriber.receiver().next().timeout(SLEEP).await.is_err());
+
+        let replies: Vec<Reply> = client
+            .query(
+                &"/lvc/*".into(),
+                "",
+                QueryTarget::default(),
+                QueryConsolidation::default(),
+            )
+            .await
+            .unwrap()
+            .collect()
+            .timeout(TIMEOUT)
+            .await
+            .unwrap();
+        assert_eq!(replies.len(), 1);
+        assert_eq!(replies[0].data.res_name, "/lvc/b");
+
+        subscriber.undeclare().await.unwrap();
+        live_subscriber.undeclare().await.unwrap();
+        client.close().await.unwrap();
+        publisher.close().await.unwrap();
+        router.close().await.unwrap();
+    });
+}
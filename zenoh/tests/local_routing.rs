@@ -0,0 +1,85 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use async_std::prelude::*;
+use async_std::task;
+use std::time::Duration;
+use zenoh::net::queryable::EVAL;
+use zenoh::net::*;
+use zenoh_util::properties::config::*;
+
+const ROUTER_LOCATOR: &str = "tcp/127.0.0.1:14498";
+const TIMEOUT: Duration = Duration::from_secs(10);
+const SLEEP: Duration = Duration::from_millis(500);
+
+async fn open_client(local_routing: bool) -> Session {
+    let mut config = ConfigProperties::default();
+    config.insert(ZN_MODE_KEY, "client".to_string());
+    config.insert(ZN_PEER_KEY, ROUTER_LOCATOR.to_string());
+    config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+    config.insert(ZN_LOCAL_ROUTING_KEY, local_routing.to_string());
+    open(config).await.unwrap()
+}
+
+#[test]
+fn query_without_local_routing() {
+    task::block_on(async {
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "router".to_string());
+        config.insert(ZN_LISTENER_KEY, ROUTER_LOCATOR.to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        let router = open(config).await.unwrap();
+
+        let replier = open_client(true).await;
+        let querier = open_client(false).await;
+        let mut queryable = replier
+            .declare_queryable(&"/local_routing/**".into(), EVAL)
+            .await
+            .unwrap();
+        task::sleep(SLEEP).await;
+
+        let serve = async {
+            while let Some(query) = queryable.receiver().next().await {
+                query.reply(Sample {
+                    res_name: "/local_routing/value".to_string(),
+                    payload: b"routed".to_vec().into(),
+                    data_info: None,
+                });
+            }
+        };
+        // the replies end with the final reply of the router alone
+        let query = async {
+            let replies: Vec<Reply> = querier
+                .query(
+                    &"/local_routing/**".into(),
+                    "",
+                    QueryTarget::default(),
+                    QueryConsolidation::default(),
+                )
+                .await
+                .unwrap()
+                .collect()
+                .timeout(TIMEOUT)
+                .await
+                .unwrap();
+            assert_eq!(replies.len(), 1);
+            assert_eq!(replies[0].data.payload.to_vec(), b"routed".to_vec());
+        };
+        query.race(serve).await;
+
+        queryable.undeclare().await.unwrap();
+        querier.close().await.unwrap();
+        replier.close().await.unwrap();
+        router.close().await.unwrap();
+    });
+}
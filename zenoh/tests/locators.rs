@@ -19,8 +19,8 @@ use zenoh::net::protocol::core::{whatami, PeerId};
 use zenoh::net::protocol::link::{Link, Locator, LocatorProperty};
 use zenoh::net::protocol::proto::ZenohMessage;
 use zenoh::net::protocol::session::{
-    Session, SessionEventHandler, SessionHandler, SessionManager, SessionManagerConfig,
-    SessionManagerOptionalConfig,
+    CloseReason, Session, SessionEventHandler, SessionHandler, SessionManager,
+    SessionManagerConfig, SessionManagerOptionalConfig,
 };
 use zenoh_util::core::ZResult;
 use zenoh_util::zasync_executor_init;
@@ -63,7 +63,7 @@ impl SessionEventHandler for SC {
     fn new_link(&self, _link: Link) {}
     fn del_link(&self, _link: Link) {}
     fn closing(&self) {}
-    fn closed(&self) {}
+    fn closed(&self, _reason: CloseReason) {}
 
     fn as_any(&self) -> &dyn Any {
         self
@@ -79,17 +79,8 @@ async fn run(locators: &[Locator], locator_property: Option<Vec<LocatorProperty>
         handler: Arc::new(SH::new()),
     };
     let opt_config = SessionManagerOptionalConfig {
-        lease: None,
-        keep_alive: None,
-        sn_resolution: None,
-        open_timeout: None,
-        open_incoming_pending: None,
-        batch_size: None,
-        max_sessions: None,
-        max_links: None,
-        peer_authenticator: None,
-        link_authenticator: None,
         locator_property,
+        ..Default::default()
     };
     let sm = SessionManager::new(config, Some(opt_config));
 
@@ -148,6 +139,22 @@ fn locator_udp() {
     task::block_on(run(&locators, locator_property));
 }
 
+#[cfg(feature = "transport_mem")]
+#[test]
+fn locator_mem() {
+    task::block_on(async {
+        zasync_executor_init!();
+    });
+
+    // Define the locators
+    let locators: Vec<Locator> = vec![
+        "mem/locator_mem_1".parse().unwrap(),
+        "mem/locator_mem_2".parse().unwrap(),
+    ];
+    let locator_property = None;
+    task::block_on(run(&locators, locator_property));
+}
+
 #[cfg(all(feature = "transport_unixsock-stream", target_family = "unix"))]
 #[test]
 fn locator_unix() {
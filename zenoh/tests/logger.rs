@@ -0,0 +1,123 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use async_std::prelude::*;
+use async_std::sync::Arc;
+use async_std::task;
+use std::time::Duration;
+use zenoh::net::plugins::PluginsMgr;
+use zenoh::net::runtime::{logger, AdminSpace};
+use zenoh::net::*;
+use zenoh_util::properties::config::*;
+use zenoh_util::LibLoader;
+
+const ROUTER_LOCATOR: &str = "tcp/127.0.0.1:14457";
+const TIMEOUT: Duration = Duration::from_secs(10);
+const SLEEP: Duration = Duration::from_millis(500);
+
+async fn get_directives(session: &Session, path: &str) -> Option<String> {
+    let replies: Vec<Reply> = session
+        .query(
+            &path.into(),
+            "",
+            QueryTarget::default(),
+            QueryConsolidation::default(),
+        )
+        .await
+        .unwrap()
+        .collect()
+        .timeout(TIMEOUT)
+        .await
+        .unwrap();
+    assert_eq!(replies.len(), 1);
+    let json: serde_json::Value =
+        serde_json::from_slice(&replies[0].data.payload.to_vec()).unwrap();
+    json["directives"].as_str().map(|d| d.to_string())
+}
+
+#[test]
+fn logger() {
+    std::env::remove_var("RUST_LOG");
+    let mut builder = env_logger::Builder::new();
+    builder.is_test(true);
+    logger::init(builder).unwrap();
+
+    task::block_on(async {
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "router".to_string());
+        config.insert(ZN_LISTENER_KEY, ROUTER_LOCATOR.to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        config.insert(ZN_ADMIN_LOG_STREAM_KEY, "info".to_string());
+        config.insert(ZN_ADMIN_WRITE_KEY, ZN_TRUE.to_string());
+        let router = open(config).await.unwrap();
+        let plugins_mgr = Arc::new(PluginsMgr::new(LibLoader::default()));
+        AdminSpace::start(router.runtime(), plugins_mgr, "test".to_string()).await;
+        let logger_path = format!("/@/router/{}/logger", router.runtime().get_pid_str());
+
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "client".to_string());
+        config.insert(ZN_PEER_KEY, ROUTER_LOCATOR.to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        let client = open(config).await.unwrap();
+
+        let mut lines = client
+            .declare_subscriber(
+                &format!("{}/lines", logger_path).into(),
+                &SubInfo::default(),
+            )
+            .await
+            .unwrap();
+        task::sleep(SLEEP).await;
+
+        // the records filtered out are not streamed
+        assert_eq!(
+            get_directives(&client, &logger_path).await.as_deref(),
+            Some("error")
+        );
+        log::info!(target: "zenoh::test", "filtered out");
+        assert!(lines.receiver().next().timeout(SLEEP).await.is_err());
+
+        client
+            .write(
+                &logger_path.clone().into(),
+                "zenoh::test=debug".as_bytes().into(),
+            )
+            .await
+            .unwrap();
+        task::sleep(SLEEP).await;
+        assert_eq!(
+            get_directives(&client, &logger_path).await.as_deref(),
+            Some("zenoh::test=debug")
+        );
+
+        // the records above the streamed level are not streamed
+        log::debug!(target: "zenoh::test", "not streamed");
+        log::info!(target: "zenoh::test", "streamed");
+        let sample = lines
+            .receiver()
+            .next()
+            .timeout(TIMEOUT)
+            .await
+            .unwrap()
+            .unwrap();
+        let line: serde_json::Value = serde_json::from_slice(&sample.payload.to_vec()).unwrap();
+        assert_eq!(line["level"], "INFO");
+        assert_eq!(line["target"], "zenoh::test");
+        assert_eq!(line["message"], "streamed");
+        assert!(lines.receiver().next().timeout(SLEEP).await.is_err());
+
+        lines.undeclare().await.unwrap();
+        client.close().await.unwrap();
+        router.close().await.unwrap();
+    });
+}
@@ -111,6 +111,8 @@ fn gen_declarations() -> Vec<Declaration> {
             reliability: Reliability::Reliable,
             mode: SubMode::Push,
             period: None,
+            history: History::None,
+            filter: None,
         },
     }));
     decls.push(Declaration::Subscriber(Subscriber {
@@ -119,6 +121,8 @@ fn gen_declarations() -> Vec<Declaration> {
             reliability: Reliability::BestEffort,
             mode: SubMode::Pull,
             period: None,
+            history: History::Latest,
+            filter: None,
         },
     }));
     decls.push(Declaration::Subscriber(Subscriber {
@@ -131,6 +135,18 @@ fn gen_declarations() -> Vec<Declaration> {
                 period: gen!(ZInt),
                 duration: gen!(ZInt),
             }),
+            history: History::Latest,
+            filter: None,
+        },
+    }));
+    decls.push(Declaration::Subscriber(Subscriber {
+        key: gen_key(),
+        info: SubInfo {
+            reliability: Reliability::Reliable,
+            mode: SubMode::Push,
+            period: None,
+            history: History::None,
+            filter: Some("temperature>20&unit=\"C\"".parse().unwrap()),
         },
     }));
     decls.push(Declaration::Subscriber(Subscriber {
@@ -143,6 +159,8 @@ fn gen_declarations() -> Vec<Declaration> {
                 period: gen!(ZInt),
                 duration: gen!(ZInt),
             }),
+            history: History::None,
+            filter: None,
         },
     }));
     decls.push(Declaration::ForgetSubscriber(ForgetSubscriber {
@@ -182,11 +200,13 @@ fn gen_query_target() -> QueryTarget {
 }
 
 fn gen_target() -> Target {
-    let num: u8 = thread_rng().gen_range(0..4);
+    let num: u8 = thread_rng().gen_range(0..6);
     match num {
         0 => Target::BestMatching,
         1 => Target::Complete { n: 3 },
         2 => Target::All,
+        3 => Target::Nearest,
+        4 => Target::BestMatchingN { n: 2 },
         _ => Target::None,
     }
 }
@@ -208,16 +228,35 @@ fn gen_consolidation() -> QueryConsolidation {
     }
 }
 
+fn gen_query_page(after: Option<String>) -> QueryPage {
+    QueryPage {
+        limit: gen!(ZInt),
+        after,
+    }
+}
+
 fn gen_timestamp() -> Timestamp {
     Timestamp::new(uhlc::NTP64(gen!(u64)), uhlc::ID::from(uuid::Uuid::new_v4()))
 }
 
+fn gen_provenance() -> Vec<ProvenanceHop> {
+    let len = thread_rng().gen_range(0..4);
+    (0..len)
+        .map(|_| ProvenanceHop {
+            pid: gen_pid(),
+            time: gen!(ZInt),
+        })
+        .collect()
+}
+
 fn gen_data_info() -> DataInfo {
     DataInfo {
         source_id: option_gen!(gen_pid()),
         source_sn: option_gen!(gen!(ZInt)),
         first_router_id: option_gen!(gen_pid()),
         first_router_sn: option_gen!(gen!(ZInt)),
+        digest: option_gen!(gen_buffer(32)),
+        provenance: option_gen!(gen_provenance()),
         timestamp: option_gen!(gen_timestamp()),
         kind: option_gen!(gen!(ZInt)),
         encoding: option_gen!(gen!(ZInt)),
@@ -724,6 +763,11 @@ fn codec_query() {
     for _ in 0..NUM_ITER {
         let predicate = [String::default(), "my_predicate".to_string()];
         let target = [None, Some(gen_query_target())];
+        let page = [
+            None,
+            Some(gen_query_page(None)),
+            Some(gen_query_page(Some("/my/after".to_string()))),
+        ];
         let routing_context = [None, Some(gen_routing_context())];
         let attachment = [None, Some(gen_attachment())];
         let reliability = [Reliability::BestEffort, Reliability::Reliable];
@@ -731,18 +775,21 @@ fn codec_query() {
         for rl in reliability.iter() {
             for p in predicate.iter() {
                 for t in target.iter() {
-                    for roc in routing_context.iter() {
-                        for a in attachment.iter() {
-                            let msg = ZenohMessage::make_query(
-                                gen_key(),
-                                p.clone(),
-                                gen!(ZInt),
-                                t.clone(),
-                                gen_consolidation(),
-                                roc.clone(),
-                                a.clone(),
-                            );
-                            test_write_read_zenoh_message(msg, *rl);
+                    for pg in page.iter() {
+                        for roc in routing_context.iter() {
+                            for a in attachment.iter() {
+                                let msg = ZenohMessage::make_query(
+                                    gen_key(),
+                                    p.clone(),
+                                    gen!(ZInt),
+                                    t.clone(),
+                                    gen_consolidation(),
+                                    pg.clone(),
+                                    roc.clone(),
+                                    a.clone(),
+                                );
+                                test_write_read_zenoh_message(msg, *rl);
+                            }
                         }
                     }
                 }
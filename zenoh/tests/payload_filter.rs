@@ -0,0 +1,82 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use zenoh::net::protocol::core::{PayloadFilter, PayloadValue};
+
+fn matches(filter: &str, payload: &str) -> bool {
+    filter
+        .parse::<PayloadFilter>()
+        .unwrap()
+        .matches(payload.as_bytes())
+}
+
+#[test]
+fn payload_filter_parse() {
+    assert!("x>1".parse::<PayloadFilter>().is_ok());
+    assert!("x>1&y<=2&name=\"a\"&ok!=true"
+        .parse::<PayloadFilter>()
+        .is_ok());
+    assert!("x".parse::<PayloadFilter>().is_err());
+    assert!(">1".parse::<PayloadFilter>().is_err());
+    assert!("x>1&".parse::<PayloadFilter>().is_err());
+    assert_eq!(
+        "x>1&y<2".parse::<PayloadFilter>().unwrap().expr(),
+        "x>1&y<2"
+    );
+}
+
+#[test]
+fn payload_filter_matches() {
+    let payload = r#"{"x": 2, "y": 1.5, "name": "zenoh", "ok": true, "pos": {"lat": 48.8}, "tags": ["a", "b"]}"#;
+    assert!(matches("x>1", payload));
+    assert!(matches("x>=2", payload));
+    assert!(!matches("x<2", payload));
+    assert!(matches("x<=2&y<2", payload));
+    assert!(!matches("x=2&y>2", payload));
+    assert!(matches("x!=3", payload));
+    assert!(matches("name=\"zenoh\"", payload));
+    assert!(matches("name=zenoh", payload));
+    assert!(matches("name>abc", payload));
+    assert!(matches("ok=true", payload));
+    assert!(!matches("ok=false", payload));
+    assert!(matches("pos.lat>48", payload));
+    assert!(matches("tags.1=b", payload));
+
+    // a predicate on a missing field or on a field of another type is false
+    assert!(!matches("z=1", payload));
+    assert!(!matches("name>1", payload));
+    assert!(!matches("pos.lat.x=1", payload));
+
+    // a payload which is not JSON doesn't match
+    assert!(!matches("x>1", "x=2"));
+}
+
+#[test]
+fn payload_filter_matches_cbor() {
+    let json = serde_json::json!({"x": 2, "name": "zenoh", "pos": {"lat": 48.8}});
+    let payload = serde_cbor::to_vec(&json).unwrap();
+    let filter = "x>1&name=zenoh&pos.lat>48"
+        .parse::<PayloadFilter>()
+        .unwrap();
+    assert!(filter.matches(&payload));
+    assert!(!"x<2".parse::<PayloadFilter>().unwrap().matches(&payload));
+
+    // a payload decoded once is matched against several filters
+    let value = PayloadValue::decode(&payload);
+    assert!(filter.matches_value(&value));
+    assert!("name!=abc"
+        .parse::<PayloadFilter>()
+        .unwrap()
+        .matches_value(&value));
+    assert!(!filter.matches_value(&PayloadValue::decode(&[0xff, 0x00])));
+}
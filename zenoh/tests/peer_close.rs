@@ -0,0 +1,91 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use async_std::prelude::*;
+use async_std::task;
+use std::time::Duration;
+use zenoh::net::*;
+use zenoh_util::properties::config::*;
+
+const LOCATOR: &str = "tcp/127.0.0.1:18447";
+const TIMEOUT: Duration = Duration::from_secs(10);
+const SLEEP: Duration = Duration::from_millis(500);
+
+async fn open_peer(listener: Option<&str>, peer: Option<&str>) -> Session {
+    let mut config = ConfigProperties::default();
+    config.insert(ZN_MODE_KEY, "peer".to_string());
+    config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+    config.insert(ZN_PEERS_AUTOCONNECT_KEY, ZN_FALSE.to_string());
+    if let Some(listener) = listener {
+        config.insert(ZN_LISTENER_KEY, listener.to_string());
+    }
+    if let Some(peer) = peer {
+        config.insert(ZN_PEER_KEY, peer.to_string());
+    }
+    open(config).await.unwrap()
+}
+
+// Waits for `session` to be connected to the peer `pid`.
+async fn connected(session: &Session, pid: &str) -> PeerId {
+    let connected = async {
+        loop {
+            let pids = session.transports().await.into_iter().map(|(pid, _)| pid);
+            if let Some(pid) = pids.into_iter().find(|p| p.to_string() == pid) {
+                return pid;
+            }
+            task::sleep(SLEEP).await;
+        }
+    };
+    connected.timeout(TIMEOUT).await.unwrap()
+}
+
+// Returns the next event about the session with `pid`.
+async fn next_event(events: &mut PeerCloseEventReceiver, pid: &str) -> PeerCloseEvent {
+    loop {
+        let event = events.next().timeout(TIMEOUT).await.unwrap().unwrap();
+        if event.pid.to_string() == pid {
+            return event;
+        }
+    }
+}
+
+#[test]
+fn peer_close() {
+    task::block_on(async {
+        let listener = open_peer(Some(LOCATOR), None).await;
+        let mut events = listener.peer_close_events();
+
+        // the peer closes its session
+        let peer = open_peer(None, Some(LOCATOR)).await;
+        let peer_id = peer.id().await;
+        connected(&listener, &peer_id).await;
+        peer.close().await.unwrap();
+        let event = next_event(&mut events, &peer_id).await;
+        assert_eq!(event.whatami, whatami::PEER);
+        assert!(matches!(event.reason, CloseReason::ClosedByPeer(_)));
+
+        // the session with the peer is closed locally
+        let peer = open_peer(None, Some(LOCATOR)).await;
+        let peer_id = peer.id().await;
+        let mut peer_events = peer.peer_close_events();
+        let pid = connected(&listener, &peer_id).await;
+        listener.runtime().disconnect(&pid).await.unwrap();
+        let event = next_event(&mut events, &peer_id).await;
+        assert_eq!(event.reason, CloseReason::Closed);
+        let event = next_event(&mut peer_events, &listener.id().await).await;
+        assert!(matches!(event.reason, CloseReason::ClosedByPeer(_)));
+
+        peer.close().await.unwrap();
+        listener.close().await.unwrap();
+    });
+}
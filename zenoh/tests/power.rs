@@ -0,0 +1,102 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use async_std::task;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use zenoh::net::*;
+use zenoh_util::properties::config::*;
+
+const ROUTER_LOCATOR: &str = "tcp/127.0.0.1:14461";
+const RES_NAME: &str = "/power/value";
+const WAKE_WINDOW: u64 = 1000;
+const SLEEP: Duration = Duration::from_millis(500);
+const WRITE_PERIOD: Duration = Duration::from_millis(200);
+const NB_WRITES: usize = 3;
+
+// Returns the delays between the writes of `NB_WRITES` samples and their reception
+async fn write_and_receive(client: &Session, received: &Mutex<Vec<Instant>>) -> Vec<Duration> {
+    lock(received).clear();
+    let mut sent = vec![];
+    for i in 0..NB_WRITES {
+        sent.push(Instant::now());
+        client
+            .write(&RES_NAME.into(), vec![i as u8].into())
+            .await
+            .unwrap();
+        task::sleep(WRITE_PERIOD).await;
+    }
+    task::sleep(Duration::from_millis(2 * WAKE_WINDOW)).await;
+    let received = lock(received).clone();
+    assert_eq!(received.len(), NB_WRITES);
+    sent.iter()
+        .zip(received.iter())
+        .map(|(sent, received)| received.duration_since(*sent))
+        .collect()
+}
+
+fn lock(received: &Mutex<Vec<Instant>>) -> std::sync::MutexGuard<'_, Vec<Instant>> {
+    received.lock().unwrap()
+}
+
+#[test]
+fn power_profile() {
+    task::block_on(async {
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "router".to_string());
+        config.insert(ZN_LISTENER_KEY, ROUTER_LOCATOR.to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        let router = open(config).await.unwrap();
+
+        let received = Arc::new(Mutex::new(vec![]));
+        let c_received = received.clone();
+        let subscriber = router
+            .declare_callback_subscriber(&RES_NAME.into(), &SubInfo::default(), move |_| {
+                lock(&c_received).push(Instant::now())
+            })
+            .await
+            .unwrap();
+
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "client".to_string());
+        config.insert(ZN_PEER_KEY, ROUTER_LOCATOR.to_string());
+        config.insert(ZN_POWER_PROFILE_KEY, "low_power".to_string());
+        config.insert(ZN_POWER_WAKE_WINDOW_KEY, WAKE_WINDOW.to_string());
+        let client = open(config).await.unwrap();
+        assert_eq!(
+            client.power_profile().await,
+            PowerProfile::LowPower {
+                wake_window: WAKE_WINDOW
+            }
+        );
+        task::sleep(SLEEP).await;
+
+        // the samples written during a wake window are only sent at its end: the samples
+        // written 200ms apart can't all be sent within 300ms
+        let delays = write_and_receive(&client, &received).await;
+        assert!(delays.iter().any(|d| *d >= Duration::from_millis(300)));
+        assert!(delays
+            .iter()
+            .all(|d| *d <= Duration::from_millis(WAKE_WINDOW + 200)));
+
+        // the samples are sent as soon as possible again
+        client.set_power_profile(PowerProfile::Default).await;
+        assert_eq!(client.power_profile().await, PowerProfile::Default);
+        let delays = write_and_receive(&client, &received).await;
+        assert!(delays.iter().all(|d| *d < Duration::from_millis(200)));
+
+        subscriber.undeclare().await.unwrap();
+        client.close().await.unwrap();
+        router.close().await.unwrap();
+    });
+}
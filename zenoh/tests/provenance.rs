@@ -0,0 +1,150 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use async_std::task;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use zenoh::net::protocol::core::PeerId;
+use zenoh::net::protocol::io::{WBuf, ZBuf};
+use zenoh::net::protocol::proto::{ProvenanceHop, ZenohMessage, PROVENANCE_MAX_HOPS};
+use zenoh::net::*;
+use zenoh_util::properties::config::*;
+
+const ROUTER_LOCATOR: &str = "tcp/127.0.0.1:14465";
+const RES_NAME: &str = "/provenance/value";
+const SLEEP: Duration = Duration::from_millis(500);
+
+async fn open_client(provenance: bool) -> Session {
+    let mut config = ConfigProperties::default();
+    config.insert(ZN_MODE_KEY, "client".to_string());
+    config.insert(ZN_PEER_KEY, ROUTER_LOCATOR.to_string());
+    config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+    config.insert(ZN_PROVENANCE_KEY, provenance.to_string());
+    open(config).await.unwrap()
+}
+
+#[test]
+fn provenance() {
+    task::block_on(async {
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "router".to_string());
+        config.insert(ZN_LISTENER_KEY, ROUTER_LOCATOR.to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        let router = open(config).await.unwrap();
+        let router_pid: PeerId = router.id().await.parse().unwrap();
+
+        let subscriber_session = open_client(false).await;
+        // the provenance chain of the received samples, by payload
+        let received = Arc::new(Mutex::new(HashMap::new()));
+        let c_received = received.clone();
+        let subscriber = subscriber_session
+            .declare_callback_subscriber(&RES_NAME.into(), &SubInfo::default(), move |sample| {
+                c_received.lock().unwrap().insert(
+                    sample.payload.to_vec()[0],
+                    sample.get_provenance().map(|hops| hops.to_vec()),
+                );
+            })
+            .await
+            .unwrap();
+
+        let publisher = open_client(true).await;
+        let publisher_pid: PeerId = publisher.id().await.parse().unwrap();
+        let plain_publisher = open_client(false).await;
+        task::sleep(SLEEP).await;
+
+        // the chain started by the publisher is appended the hop of the router
+        publisher
+            .write(&RES_NAME.into(), vec![1u8].into())
+            .await
+            .unwrap();
+        // no chain is attached by the publishers not configured to
+        plain_publisher
+            .write(&RES_NAME.into(), vec![2u8].into())
+            .await
+            .unwrap();
+        task::sleep(SLEEP).await;
+
+        // the two publishers being independent sessions, their samples may arrive in any order
+        let received = received.lock().unwrap().clone();
+        assert_eq!(received.len(), 2);
+        let hops = received.get(&1u8).unwrap().as_ref().unwrap();
+        assert_eq!(hops.len(), 2);
+        assert_eq!(hops[0].pid, publisher_pid);
+        assert_eq!(hops[1].pid, router_pid);
+        assert!(hops[0].time <= hops[1].time);
+        assert!(received.get(&2u8).unwrap().is_none());
+
+        subscriber.undeclare().await.unwrap();
+        subscriber_session.close().await.unwrap();
+        publisher.close().await.unwrap();
+        plain_publisher.close().await.unwrap();
+        router.close().await.unwrap();
+    });
+}
+
+#[test]
+fn provenance_max_hops() {
+    let pids: Vec<PeerId> = (0..*PROVENANCE_MAX_HOPS + 2)
+        .map(|_| PeerId::from(uuid::Uuid::new_v4()))
+        .collect();
+    let mut info = DataInfo::new();
+
+    // no hop is appended to the data without a chain
+    info.append_hop(pids[0].clone());
+    assert!(info.provenance.is_none());
+
+    // the publisher's hop is kept, and the latest hops once the chain is full
+    info.provenance = Some(vec![]);
+    for pid in pids.iter() {
+        info.append_hop(pid.clone());
+    }
+    let hops = info.provenance.unwrap();
+    assert_eq!(hops.len(), *PROVENANCE_MAX_HOPS);
+    assert_eq!(hops[0].pid, pids[0]);
+    assert_eq!(hops[1].pid, pids[3]);
+    assert_eq!(hops.last().unwrap().pid, *pids.last().unwrap());
+}
+
+#[test]
+fn provenance_max_hops_decoding() {
+    let message = |hops: usize| {
+        let mut info = DataInfo::new();
+        info.provenance = Some(
+            (0..hops)
+                .map(|_| ProvenanceHop::new(PeerId::from(uuid::Uuid::new_v4())))
+                .collect(),
+        );
+        let msg = ZenohMessage::make_data(
+            RES_NAME.into(),
+            ZBuf::from(vec![0u8]),
+            Reliability::Reliable,
+            CongestionControl::Block,
+            Some(info),
+            None,
+            None,
+            None,
+        );
+        let mut wbuf = WBuf::new(64, false);
+        assert!(wbuf.write_zenoh_message(&msg));
+        ZBuf::from(&wbuf)
+    };
+
+    // a full chain is decoded, a longer one is rejected
+    assert!(message(*PROVENANCE_MAX_HOPS)
+        .read_zenoh_message(Reliability::Reliable)
+        .is_some());
+    assert!(message(*PROVENANCE_MAX_HOPS + 1)
+        .read_zenoh_message(Reliability::Reliable)
+        .is_none());
+}
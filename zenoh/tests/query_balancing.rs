@@ -0,0 +1,125 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use async_std::prelude::*;
+use async_std::task;
+use std::time::Duration;
+use zenoh::net::protocol::core::Target;
+use zenoh::net::queryable::{ALL_KINDS, STORAGE};
+use zenoh::net::*;
+use zenoh_util::properties::config::*;
+
+const ROUTER_LOCATOR: &str = "tcp/127.0.0.1:14460";
+const TIMEOUT: Duration = Duration::from_secs(10);
+const SLEEP: Duration = Duration::from_millis(500);
+const RES_NAME: &str = "/balanced/value";
+
+async fn open_session(mode: &str) -> Session {
+    let mut config = ConfigProperties::default();
+    config.insert(ZN_MODE_KEY, mode.to_string());
+    if mode == "router" {
+        config.insert(ZN_LISTENER_KEY, ROUTER_LOCATOR.to_string());
+    } else {
+        config.insert(ZN_PEER_KEY, ROUTER_LOCATOR.to_string());
+    }
+    config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+    open(config).await.unwrap()
+}
+
+// Replies to the queries with the name of the replica
+async fn serve(queryable: &mut Queryable<'_>, replica: &str) {
+    while let Some(query) = queryable.receiver().next().await {
+        query.reply(Sample {
+            res_name: RES_NAME.to_string(),
+            payload: replica.as_bytes().into(),
+            data_info: None,
+        });
+    }
+}
+
+// Returns the names of the replicas that replied to a query with `target`
+async fn query(session: &Session, target: Target) -> Vec<String> {
+    let replies: Vec<Reply> = session
+        .query(
+            &RES_NAME.into(),
+            "",
+            QueryTarget {
+                kind: ALL_KINDS,
+                target,
+            },
+            QueryConsolidation::none(),
+        )
+        .await
+        .unwrap()
+        .collect()
+        .timeout(TIMEOUT)
+        .await
+        .unwrap();
+    let mut replicas: Vec<String> = replies
+        .into_iter()
+        .map(|reply| String::from_utf8(reply.data.payload.to_vec()).unwrap())
+        .collect();
+    replicas.sort();
+    replicas
+}
+
+#[test]
+fn query_balancing() {
+    task::block_on(async {
+        let router = open_session("router").await;
+        let replica_a = open_session("client").await;
+        let replica_b = open_session("client").await;
+        let querier = open_session("client").await;
+
+        let mut storage_a = replica_a
+            .declare_queryable(&"/balanced/**".into(), STORAGE)
+            .await
+            .unwrap();
+        let mut storage_b = replica_b
+            .declare_queryable(&"/balanced/**".into(), STORAGE)
+            .await
+            .unwrap();
+        task::sleep(SLEEP).await;
+
+        let queries = async {
+            // all the replicas are queried with an All target
+            assert_eq!(query(&querier, Target::All).await, vec!["a", "b"]);
+
+            // a single replica is queried, each in turn
+            let mut replicas = vec![];
+            for _ in 0..4 {
+                let replied = query(&querier, Target::BestMatchingN { n: 1 }).await;
+                assert_eq!(replied.len(), 1);
+                replicas.extend(replied);
+            }
+            replicas.sort();
+            assert_eq!(replicas, vec!["a", "a", "b", "b"]);
+
+            assert_eq!(
+                query(&querier, Target::BestMatchingN { n: 2 }).await,
+                vec!["a", "b"]
+            );
+        };
+        queries
+            .race(serve(&mut storage_a, "a"))
+            .race(serve(&mut storage_b, "b"))
+            .await;
+
+        storage_a.undeclare().await.unwrap();
+        storage_b.undeclare().await.unwrap();
+        querier.close().await.unwrap();
+        replica_a.close().await.unwrap();
+        replica_b.close().await.unwrap();
+        router.close().await.unwrap();
+    });
+}
@@ -0,0 +1,151 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use async_std::prelude::*;
+use async_std::task;
+use std::time::Duration;
+use zenoh::net::protocol::core::Target;
+use zenoh::net::queryable::{ALL_KINDS, EVAL, STORAGE};
+use zenoh::net::*;
+use zenoh_util::properties::config::*;
+
+const ROUTER_LOCATOR: &str = "tcp/127.0.0.1:14482";
+const TIMEOUT: Duration = Duration::from_secs(10);
+const SLEEP: Duration = Duration::from_millis(500);
+
+async fn open_session(mode: &str) -> Session {
+    let mut config = ConfigProperties::default();
+    config.insert(ZN_MODE_KEY, mode.to_string());
+    if mode == "router" {
+        config.insert(ZN_LISTENER_KEY, ROUTER_LOCATOR.to_string());
+    } else {
+        config.insert(ZN_PEER_KEY, ROUTER_LOCATOR.to_string());
+    }
+    config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+    open(config).await.unwrap()
+}
+
+// Replies to the queries with the name of the queryable
+async fn serve(queryable: &mut Queryable<'_>, name: &str) {
+    while let Some(query) = queryable.receiver().next().await {
+        query.reply(Sample {
+            res_name: "/complete/a/x".to_string(),
+            payload: name.as_bytes().into(),
+            data_info: None,
+        });
+    }
+}
+
+// Returns the names of the queryables that replied to a query on `res_name`
+async fn query(session: &Session, res_name: &str, kind: ZInt, target: Target) -> Vec<String> {
+    let replies: Vec<Reply> = session
+        .query(
+            &res_name.into(),
+            "",
+            QueryTarget { kind, target },
+            QueryConsolidation::none(),
+        )
+        .await
+        .unwrap()
+        .collect()
+        .timeout(TIMEOUT)
+        .await
+        .unwrap();
+    let mut names: Vec<String> = replies
+        .into_iter()
+        .map(|reply| String::from_utf8(reply.data.payload.to_vec()).unwrap())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn query_complete() {
+    task::block_on(async {
+        let router = open_session("router").await;
+        let storages = open_session("client").await;
+        let evals = open_session("client").await;
+        let querier = open_session("client").await;
+
+        let mut storage = storages
+            .declare_queryable(&"/complete/**".into(), STORAGE)
+            .await
+            .unwrap();
+        let mut partial = storages
+            .declare_queryable(&"/complete/a/x".into(), STORAGE)
+            .await
+            .unwrap();
+        let mut eval = evals
+            .declare_queryable(&"/complete/**".into(), EVAL)
+            .await
+            .unwrap();
+        task::sleep(SLEEP).await;
+
+        let queries = async {
+            let complete = Target::Complete { n: 2 };
+            assert_eq!(
+                query(&querier, "/complete/a/*", ALL_KINDS, Target::All).await,
+                vec!["eval", "partial", "storage"]
+            );
+
+            // only the storages including the whole queried resource are complete
+            assert_eq!(
+                query(&querier, "/complete/a/*", ALL_KINDS, complete.clone()).await,
+                vec!["storage"]
+            );
+            // on a resource known by the router, with its cached routes
+            assert_eq!(
+                query(&querier, "/complete/a/x", ALL_KINDS, complete.clone()).await,
+                vec!["partial", "storage"]
+            );
+
+            // the queries for other kinds fall back to all the matching queryables
+            assert_eq!(
+                query(&querier, "/complete/a/*", EVAL, complete.clone()).await,
+                vec!["eval"]
+            );
+        };
+        queries
+            .race(serve(&mut storage, "storage"))
+            .race(serve(&mut partial, "partial"))
+            .race(serve(&mut eval, "eval"))
+            .await;
+
+        // without complete storage, all the matching queryables are queried
+        storage.undeclare().await.unwrap();
+        task::sleep(SLEEP).await;
+        let queries = async {
+            assert_eq!(
+                query(
+                    &querier,
+                    "/complete/a/*",
+                    ALL_KINDS,
+                    Target::Complete { n: 2 }
+                )
+                .await,
+                vec!["eval", "partial"]
+            );
+        };
+        queries
+            .race(serve(&mut partial, "partial"))
+            .race(serve(&mut eval, "eval"))
+            .await;
+
+        partial.undeclare().await.unwrap();
+        eval.undeclare().await.unwrap();
+        querier.close().await.unwrap();
+        evals.close().await.unwrap();
+        storages.close().await.unwrap();
+        router.close().await.unwrap();
+    });
+}
@@ -0,0 +1,168 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use async_std::prelude::*;
+use async_std::task;
+use std::time::Duration;
+use uhlc::{Timestamp, HLC};
+use zenoh::net::queryable::STORAGE;
+use zenoh::net::*;
+use zenoh_util::properties::config::*;
+
+// the routers are chained: ROUTER1 <- ROUTER2 <- ROUTER3
+const ROUTER1_LOCATOR: &str = "tcp/127.0.0.1:14492";
+const ROUTER2_LOCATOR: &str = "tcp/127.0.0.1:14493";
+const TIMEOUT: Duration = Duration::from_secs(10);
+const SLEEP: Duration = Duration::from_secs(1);
+const RES_NAME: &str = "/consolidation/a";
+
+async fn open_session(mode: &str, listener: Option<&str>, peer: Option<&str>) -> Session {
+    let mut config = ConfigProperties::default();
+    config.insert(ZN_MODE_KEY, mode.to_string());
+    if let Some(listener) = listener {
+        config.insert(ZN_LISTENER_KEY, listener.to_string());
+    }
+    if let Some(peer) = peer {
+        config.insert(ZN_PEER_KEY, peer.to_string());
+    }
+    config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+    open(config).await.unwrap()
+}
+
+// Replies to the queries with the name of the storage, timestamped with `timestamp`
+async fn serve(queryable: &mut Queryable<'_>, name: &str, timestamp: Timestamp) {
+    while let Some(query) = queryable.receiver().next().await {
+        query.reply(Sample {
+            res_name: RES_NAME.to_string(),
+            payload: name.as_bytes().into(),
+            data_info: Some(DataInfo {
+                timestamp: Some(timestamp.clone()),
+                ..DataInfo::default()
+            }),
+        });
+    }
+}
+
+// Returns the names of the storages whose replies reached the querier
+async fn query(
+    session: &Session,
+    first_routers: ConsolidationMode,
+    last_router: ConsolidationMode,
+) -> Vec<String> {
+    let replies: Vec<Reply> = session
+        .query(
+            &RES_NAME.into(),
+            "",
+            QueryTarget {
+                kind: STORAGE,
+                target: Target::All,
+            },
+            QueryConsolidation {
+                first_routers,
+                last_router,
+                reception: ConsolidationMode::None,
+            },
+        )
+        .await
+        .unwrap()
+        .collect()
+        .timeout(TIMEOUT)
+        .await
+        .unwrap();
+    let mut names: Vec<String> = replies
+        .into_iter()
+        .map(|reply| String::from_utf8(reply.data.payload.to_vec()).unwrap())
+        .collect();
+    names.sort();
+    names
+}
+
+// Opens a router serving the given storages until the end of the test
+async fn run_router(
+    listener: Option<&'static str>,
+    peer: Option<&'static str>,
+    storages: Vec<(&'static str, Timestamp)>,
+) {
+    let router = open_session("router", listener, peer).await;
+    let mut queryables = vec![];
+    for _ in &storages {
+        queryables.push(
+            router
+                .declare_queryable(&RES_NAME.into(), STORAGE)
+                .await
+                .unwrap(),
+        );
+    }
+    let mut servers = vec![];
+    for (queryable, (name, timestamp)) in queryables.iter_mut().zip(storages) {
+        servers.push(serve(queryable, name, timestamp));
+    }
+    futures::future::join_all(servers).await;
+    // the router without storage must not be closed
+    futures::future::pending::<()>().await;
+}
+
+#[test]
+fn query_consolidation() {
+    task::block_on(async {
+        // the storage of the router of the querier has the oldest sample,
+        // the latest one is in the second storage of the last router
+        let hlc = HLC::default();
+        let (ts1, ts3a, ts3b) = (
+            hlc.new_timestamp(),
+            hlc.new_timestamp(),
+            hlc.new_timestamp(),
+        );
+        task::spawn(run_router(
+            Some(ROUTER1_LOCATOR),
+            None,
+            vec![("storage1", ts1)],
+        ));
+        task::sleep(SLEEP).await;
+        task::spawn(run_router(
+            Some(ROUTER2_LOCATOR),
+            Some(ROUTER1_LOCATOR),
+            vec![],
+        ));
+        task::sleep(SLEEP).await;
+        task::spawn(run_router(
+            None,
+            Some(ROUTER2_LOCATOR),
+            vec![("storage3a", ts3a), ("storage3b", ts3b)],
+        ));
+        let querier = open_session("client", None, Some(ROUTER1_LOCATOR)).await;
+        task::sleep(SLEEP).await;
+
+        // only the last router consolidates: the replies of its storages are consolidated
+        // before crossing the routers links, and the one of the first router is forwarded
+        assert_eq!(
+            query(&querier, ConsolidationMode::None, ConsolidationMode::Full).await,
+            vec!["storage1", "storage3b"]
+        );
+
+        // only the first routers consolidate: the replies of the storages of the last router
+        // cross the link with the second router, that forwards the latest one to the first router
+        assert_eq!(
+            query(&querier, ConsolidationMode::Full, ConsolidationMode::None).await,
+            vec!["storage3b"]
+        );
+
+        // no router consolidates
+        assert_eq!(
+            query(&querier, ConsolidationMode::None, ConsolidationMode::None).await,
+            vec!["storage1", "storage3a", "storage3b"]
+        );
+
+        querier.close().await.unwrap();
+    });
+}
@@ -0,0 +1,128 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use async_std::prelude::*;
+use async_std::task;
+use std::time::Duration;
+use zenoh::net::queryable::STORAGE;
+use zenoh::net::utils::resource_name;
+use zenoh::net::*;
+use zenoh_util::properties::config::*;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+const SLEEP: Duration = Duration::from_millis(500);
+const RES_NAMES: [&str; 2] = ["/many/a", "/many/b"];
+
+async fn serve(queryable: &mut Queryable<'_>) {
+    while let Some(query) = queryable.receiver().next().await {
+        for res_name in RES_NAMES.iter() {
+            if resource_name::intersect(&query.res_name, res_name) {
+                query.reply(Sample {
+                    res_name: res_name.to_string(),
+                    payload: vec![0u8].into(),
+                    data_info: None,
+                });
+            }
+        }
+    }
+}
+
+// Returns the resource names received for each query, checking that every query is complete.
+async fn receive(mut replies: MultiReplyReceiver, nb_queries: usize) -> Vec<Vec<String>> {
+    let mut received = vec![vec![]; nb_queries];
+    let mut complete = vec![false; nb_queries];
+    while let Some(reply) = replies.next().timeout(TIMEOUT).await.unwrap() {
+        match reply {
+            MultiReply::Reply(index, reply) => {
+                assert!(!complete[index]);
+                received[index].push(reply.data.res_name);
+            }
+            MultiReply::Final(index) => {
+                assert!(!complete[index]);
+                complete[index] = true;
+            }
+        }
+    }
+    assert!(complete.iter().all(|c| *c));
+    for res_names in received.iter_mut() {
+        res_names.sort();
+    }
+    received
+}
+
+#[test]
+fn query_many() {
+    task::block_on(async {
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "peer".to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        let session = open(config).await.unwrap();
+        let mut storage = session
+            .declare_queryable(&"/many/**".into(), STORAGE)
+            .await
+            .unwrap();
+        task::sleep(SLEEP).await;
+
+        let queries = async {
+            // no consolidation: all the replies of each query are received
+            let replies = session
+                .query_many(
+                    &[("/many/a".into(), ""), ("/many/*".into(), "")],
+                    QueryTarget::default(),
+                    QueryConsolidation::none(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(
+                receive(replies, 2).await,
+                vec![vec!["/many/a"], vec!["/many/a", "/many/b"]]
+            );
+
+            // consolidation across the queries: each resource is received once
+            let replies = session
+                .query_many(
+                    &[("/many/*".into(), ""), ("/many/a".into(), "")],
+                    QueryTarget::default(),
+                    QueryConsolidation::default(),
+                )
+                .await
+                .unwrap();
+            let received = receive(replies, 2).await;
+            let mut all = received.concat();
+            all.sort();
+            assert_eq!(all, RES_NAMES.to_vec());
+
+            // a query without replies is complete as well
+            let replies = session
+                .query_many(
+                    &[
+                        ("/many/a".into(), ""),
+                        ("/many/b".into(), ""),
+                        ("/many/c".into(), ""),
+                    ],
+                    QueryTarget::default(),
+                    QueryConsolidation::default(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(
+                receive(replies, 3).await,
+                vec![vec!["/many/a"], vec!["/many/b"], vec![]]
+            );
+        };
+        queries.race(serve(&mut storage)).await;
+
+        storage.undeclare().await.unwrap();
+        session.close().await.unwrap();
+    });
+}
@@ -0,0 +1,123 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use async_std::prelude::*;
+use async_std::task;
+use std::time::Duration;
+use zenoh::net::queryable::{EVAL, STORAGE};
+use zenoh::net::*;
+use zenoh_util::properties::config::*;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+const SLEEP: Duration = Duration::from_millis(500);
+
+async fn open_peer() -> Session {
+    let mut config = ConfigProperties::default();
+    config.insert(ZN_MODE_KEY, "peer".to_string());
+    config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+    open(config).await.unwrap()
+}
+
+// Replies to the queries on the given indexes, in resource name order.
+// If `paged` is false, the requested page is ignored and everything is replied.
+async fn serve(queryable: &mut Queryable<'_>, indexes: Vec<usize>, paged: bool) {
+    while let Some(query) = queryable.receiver().next().await {
+        for i in &indexes {
+            let res_name = format!("/pages/{:02}", i);
+            if paged {
+                if let Some(page) = &query.page {
+                    if !page.follows(&res_name) {
+                        continue;
+                    }
+                }
+            }
+            query.reply(Sample {
+                res_name,
+                payload: vec![*i as u8].into(),
+                data_info: None,
+            });
+        }
+    }
+}
+
+async fn res_names(mut replies: ReplyReceiver) -> Vec<String> {
+    let mut res_names = vec![];
+    while let Some(reply) = replies.next().timeout(TIMEOUT).await.unwrap() {
+        res_names.push(reply.data.res_name);
+    }
+    res_names.sort();
+    res_names
+}
+
+#[test]
+fn query_pages() {
+    task::block_on(async {
+        let session = open_peer().await;
+        let mut storage = session
+            .declare_queryable(&"/pages/**".into(), STORAGE)
+            .await
+            .unwrap();
+        let mut eval = session
+            .declare_queryable(&"/pages/**".into(), EVAL)
+            .await
+            .unwrap();
+        task::sleep(SLEEP).await;
+
+        let queries = async {
+            // a single page
+            let replies = session
+                .query_page(
+                    &"/pages/**".into(),
+                    "",
+                    QueryTarget::default(),
+                    QueryConsolidation::default(),
+                    QueryPage {
+                        limit: 2,
+                        after: Some("/pages/05".to_string()),
+                    },
+                )
+                .await
+                .unwrap();
+            assert_eq!(
+                res_names(replies).await,
+                vec!["/pages/06", "/pages/07", "/pages/08", "/pages/09"]
+            );
+
+            // all the pages
+            let mut pages = session.query_pages(
+                &"/pages/**".into(),
+                "",
+                QueryTarget::default(),
+                QueryConsolidation::default(),
+                3,
+            );
+            let mut received = vec![];
+            while let Some(page) = pages.next_page().timeout(TIMEOUT).await.unwrap().unwrap() {
+                assert!(!page.is_empty() && page.len() <= 3);
+                received.extend(page.into_iter().map(|reply| reply.data.res_name));
+            }
+            let expected = (0..20)
+                .map(|i| format!("/pages/{:02}", i))
+                .collect::<Vec<String>>();
+            assert_eq!(received, expected);
+        };
+        queries
+            .race(serve(&mut storage, (0..20).step_by(2).collect(), true))
+            .race(serve(&mut eval, (1..20).step_by(2).collect(), false))
+            .await;
+
+        storage.undeclare().await.unwrap();
+        eval.undeclare().await.unwrap();
+        session.close().await.unwrap();
+    });
+}
@@ -0,0 +1,275 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use async_std::prelude::*;
+use async_std::sync::Arc;
+use async_std::task;
+use std::any::Any;
+use std::time::Duration;
+use zenoh::net::protocol::link::Link;
+use zenoh::net::protocol::proto::{
+    smsg, Declaration, Queryable, Resource, Subscriber, ZenohMessage,
+};
+use zenoh::net::protocol::session::{
+    self as transport, SessionEventHandler, SessionHandler, SessionManager, SessionManagerConfig,
+};
+use zenoh::net::queryable::STORAGE;
+use zenoh::net::*;
+use zenoh_util::core::ZResult;
+use zenoh_util::properties::config::*;
+
+const ROUTER_LOCATOR: &str = "tcp/127.0.0.1:14455";
+const DECLARATIONS_ROUTER_LOCATOR: &str = "tcp/127.0.0.1:14481";
+const TIMEOUT: Duration = Duration::from_secs(10);
+const SLEEP: Duration = Duration::from_millis(500);
+
+const PUBLICATION_RATE: usize = 10;
+const QUERY_RATE: usize = 2;
+// the rejected declarations after which the router closes a session
+const MAX_REJECTED_DECLARATIONS: usize = 100;
+
+async fn open_client(locator: &str) -> Session {
+    let mut config = ConfigProperties::default();
+    config.insert(ZN_MODE_KEY, "client".to_string());
+    config.insert(ZN_PEER_KEY, locator.to_string());
+    config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+    open(config).await.unwrap()
+}
+
+#[test]
+fn quotas() {
+    task::block_on(async {
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "router".to_string());
+        config.insert(ZN_LISTENER_KEY, ROUTER_LOCATOR.to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        config.insert(ZN_QUOTA_PUBLICATION_RATE_KEY, PUBLICATION_RATE.to_string());
+        config.insert(ZN_QUOTA_QUERY_RATE_KEY, QUERY_RATE.to_string());
+        config.insert(ZN_QUOTA_DECLARATIONS_KEY, "2".to_string());
+        let router = open(config).await.unwrap();
+
+        let client = open_client(ROUTER_LOCATOR).await;
+        let sub_info = SubInfo {
+            reliability: Reliability::Reliable,
+            mode: SubMode::Push,
+            period: None,
+            history: History::None,
+            filter: None,
+        };
+        let mut subscriber = client
+            .declare_subscriber(&"/quota/sub/**".into(), &sub_info)
+            .await
+            .unwrap();
+        let mut queryable = client
+            .declare_queryable(&"/quota/qabl".into(), STORAGE)
+            .await
+            .unwrap();
+        // the subscriber exceeding the declarations quota is ignored by the router
+        let mut rejected = client
+            .declare_subscriber(&"/quota/rejected".into(), &sub_info)
+            .await
+            .unwrap();
+        task::sleep(SLEEP).await;
+
+        let publisher = open_client(ROUTER_LOCATOR).await;
+        for i in 0..5 * PUBLICATION_RATE {
+            publisher
+                .write(&"/quota/sub/a".into(), i.to_string().into_bytes().into())
+                .await
+                .unwrap();
+        }
+        let mut received = 0;
+        while subscriber.receiver().next().timeout(SLEEP).await.is_ok() {
+            received += 1;
+        }
+        assert!(received > 0);
+        assert!(received < 2 * PUBLICATION_RATE);
+
+        let mut receivers = vec![];
+        for _ in 0..5 * QUERY_RATE {
+            receivers.push(
+                publisher
+                    .query(
+                        &"/quota/qabl".into(),
+                        "",
+                        QueryTarget::default(),
+                        QueryConsolidation::default(),
+                    )
+                    .await
+                    .unwrap(),
+            );
+        }
+        // the queries exceeding the quota don't reach the queryable
+        while let Ok(Some(query)) = queryable.receiver().next().timeout(SLEEP).await {
+            query
+                .reply_async(Sample {
+                    res_name: "/quota/qabl".to_string(),
+                    payload: "reply".as_bytes().into(),
+                    data_info: None,
+                })
+                .await;
+        }
+        let mut replied = 0;
+        for replies in receivers {
+            let replies: Vec<Reply> = replies.collect().timeout(TIMEOUT).await.unwrap();
+            replied += replies.len();
+        }
+        assert!(replied > 0);
+        assert!(replied < 2 * QUERY_RATE);
+
+        // the session declaring it is kept, with its other entities
+        task::sleep(2 * SLEEP).await;
+        publisher
+            .write(&"/quota/rejected".into(), "rejected".as_bytes().into())
+            .await
+            .unwrap();
+        publisher
+            .write(&"/quota/sub/b".into(), "accepted".as_bytes().into())
+            .await
+            .unwrap();
+        let sample = subscriber.receiver().next().timeout(TIMEOUT).await.unwrap();
+        assert_eq!(sample.unwrap().res_name, "/quota/sub/b");
+        assert!(rejected.receiver().next().timeout(SLEEP).await.is_err());
+
+        rejected.undeclare().await.unwrap();
+        queryable.undeclare().await.unwrap();
+        subscriber.undeclare().await.unwrap();
+        publisher.close().await.unwrap();
+        client.close().await.unwrap();
+        router.close().await.unwrap();
+    });
+}
+
+// A session handler notifying the reason of the close of its sessions.
+struct SHCloseReason(flume::Sender<CloseReason>);
+
+impl SessionHandler for SHCloseReason {
+    fn new_session(
+        &self,
+        _session: transport::Session,
+    ) -> ZResult<Arc<dyn SessionEventHandler + Send + Sync>> {
+        Ok(Arc::new(SEHCloseReason(self.0.clone())))
+    }
+}
+
+struct SEHCloseReason(flume::Sender<CloseReason>);
+
+impl SessionEventHandler for SEHCloseReason {
+    fn handle_message(&self, _message: ZenohMessage) -> ZResult<()> {
+        Ok(())
+    }
+
+    fn new_link(&self, _link: Link) {}
+    fn del_link(&self, _link: Link) {}
+    fn closing(&self) {}
+
+    fn closed(&self, reason: CloseReason) {
+        let _ = self.0.send(reason);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[test]
+fn quotas_declarations() {
+    task::block_on(async {
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "router".to_string());
+        config.insert(ZN_LISTENER_KEY, DECLARATIONS_ROUTER_LOCATOR.to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        config.insert(ZN_QUOTA_DECLARATIONS_KEY, "2".to_string());
+        let router = open(config).await.unwrap();
+
+        // a client declaring its entities at the protocol level, to declare them by rid and by name
+        let (sender, close_reasons) = flume::unbounded();
+        let config = SessionManagerConfig {
+            version: 0,
+            whatami: whatami::CLIENT,
+            id: PeerId::new(1, [7u8; PeerId::MAX_SIZE]),
+            handler: Arc::new(SHCloseReason(sender)),
+        };
+        let manager = SessionManager::new(config, None);
+        let session = manager
+            .open_session(&DECLARATIONS_ROUTER_LOCATOR.parse().unwrap())
+            .await
+            .unwrap();
+        let sub_info = SubInfo::default();
+
+        // an entity declared both by rid and by name is counted once
+        session
+            .schedule(ZenohMessage::make_declare(
+                vec![
+                    Declaration::Resource(Resource {
+                        rid: 1,
+                        key: "/quota/decl".into(),
+                    }),
+                    Declaration::Subscriber(Subscriber {
+                        key: ResKey::RId(1),
+                        info: sub_info.clone(),
+                    }),
+                    Declaration::Subscriber(Subscriber {
+                        key: "/quota/decl".into(),
+                        info: sub_info.clone(),
+                    }),
+                    Declaration::Queryable(Queryable {
+                        key: "/quota/decl".into(),
+                        kind: STORAGE,
+                    }),
+                ],
+                None,
+                None,
+            ))
+            .unwrap();
+        assert!(close_reasons.recv_async().timeout(SLEEP).await.is_err());
+
+        // the session declaring an entity beyond the quota is kept
+        session
+            .schedule(ZenohMessage::make_declare(
+                vec![Declaration::Subscriber(Subscriber {
+                    key: "/quota/rejected".into(),
+                    info: sub_info.clone(),
+                })],
+                None,
+                None,
+            ))
+            .unwrap();
+        assert!(close_reasons.recv_async().timeout(SLEEP).await.is_err());
+
+        // the session repeatedly declaring entities beyond the quota is closed with an explicit reason
+        session
+            .schedule(ZenohMessage::make_declare(
+                (0..MAX_REJECTED_DECLARATIONS)
+                    .map(|i| {
+                        Declaration::Subscriber(Subscriber {
+                            key: format!("/quota/rejected/{}", i).into(),
+                            info: sub_info.clone(),
+                        })
+                    })
+                    .collect(),
+                None,
+                None,
+            ))
+            .unwrap();
+        let reason = close_reasons
+            .recv_async()
+            .timeout(TIMEOUT)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(reason, CloseReason::ClosedByPeer(smsg::close_reason::QUOTA));
+
+        router.close().await.unwrap();
+    });
+}
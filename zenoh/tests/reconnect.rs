@@ -0,0 +1,115 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use async_std::prelude::*;
+use async_std::task;
+use std::time::Duration;
+use zenoh::net::*;
+use zenoh_util::properties::config::*;
+
+const ROUTER_LOCATOR: &str = "tcp/127.0.0.1:17447";
+const TIMEOUT: Duration = Duration::from_secs(10);
+const SLEEP: Duration = Duration::from_millis(500);
+
+async fn open_router() -> Session {
+    let mut config = ConfigProperties::default();
+    config.insert(ZN_MODE_KEY, "router".to_string());
+    config.insert(ZN_LISTENER_KEY, ROUTER_LOCATOR.to_string());
+    config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+    open(config).await.unwrap()
+}
+
+async fn open_client(retry_period: &str) -> Session {
+    let mut config = ConfigProperties::default();
+    config.insert(ZN_MODE_KEY, "client".to_string());
+    config.insert(ZN_PEER_KEY, ROUTER_LOCATOR.to_string());
+    config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+    config.insert(ZN_RECONNECT_KEY, ZN_TRUE.to_string());
+    config.insert(ZN_CONNECT_RETRY_INITIAL_KEY, retry_period.to_string());
+    config.insert(ZN_CONNECT_RETRY_MAX_KEY, retry_period.to_string());
+    open(config).await.unwrap()
+}
+
+async fn next_value(subscriber: &mut Subscriber<'_>) -> Vec<u8> {
+    subscriber
+        .receiver()
+        .next()
+        .timeout(TIMEOUT)
+        .await
+        .unwrap()
+        .unwrap()
+        .payload
+        .to_vec()
+}
+
+#[test]
+fn reconnect() {
+    task::block_on(async {
+        let router = open_router().await;
+
+        // the subscriber reconnects faster than the publisher, to be re-declared
+        // before the buffered publications are sent
+        let client = open_client("100").await;
+        let publisher = open_client("2000").await;
+        let sub_info = SubInfo {
+            reliability: Reliability::Reliable,
+            mode: SubMode::Push,
+            period: None,
+            history: History::None,
+            filter: None,
+        };
+        let mut subscriber = client
+            .declare_subscriber(&"/reconnect/**".into(), &sub_info)
+            .await
+            .unwrap();
+        task::sleep(SLEEP).await;
+
+        publisher
+            .write(&"/reconnect/a".into(), "1".as_bytes().into())
+            .await
+            .unwrap();
+        assert_eq!(next_value(&mut subscriber).await, b"1".to_vec());
+
+        // the publications made while disconnected are buffered
+        router
+            .runtime()
+            .drain(Duration::from_secs(0))
+            .await
+            .unwrap();
+        router.close().await.unwrap();
+        task::sleep(SLEEP).await;
+        for value in &["2", "3"] {
+            publisher
+                .write(&"/reconnect/a".into(), value.as_bytes().into())
+                .await
+                .unwrap();
+        }
+
+        // the subscriber is re-declared to the new router and receives them
+        let router = open_router().await;
+        assert_eq!(next_value(&mut subscriber).await, b"2".to_vec());
+        assert_eq!(next_value(&mut subscriber).await, b"3".to_vec());
+        task::sleep(SLEEP).await;
+
+        publisher
+            .write(&"/reconnect/a".into(), "4".as_bytes().into())
+            .await
+            .unwrap();
+        assert_eq!(next_value(&mut subscriber).await, b"4".to_vec());
+
+        subscriber.undeclare().await.unwrap();
+        client.close().await.unwrap();
+        publisher.close().await.unwrap();
+        router.close().await.unwrap();
+    });
+}
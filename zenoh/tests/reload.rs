@@ -0,0 +1,178 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use async_std::task;
+use std::time::Duration;
+use zenoh::net::protocol::link::Locator;
+use zenoh::net::*;
+use zenoh_util::properties::config::*;
+
+const OLD_LISTENER: &str = "tcp/127.0.0.1:14483";
+const NEW_LISTENER: &str = "tcp/127.0.0.1:14484";
+const PEER_LISTENER: &str = "tcp/127.0.0.1:14485";
+const UNREACHABLE_PEER: &str = "tcp/127.0.0.1:14486";
+const ZENOHD_OLD_LISTENER: &str = "tcp/127.0.0.1:14487";
+const ZENOHD_NEW_LISTENER: &str = "tcp/127.0.0.1:14488";
+const ZENOHD_SIGHUP_LISTENER: &str = "tcp/127.0.0.1:14489";
+const TIMEOUT: Duration = Duration::from_secs(10);
+const SLEEP: Duration = Duration::from_millis(500);
+
+fn router_config(listener: &str, peer: Option<&str>) -> ConfigProperties {
+    let mut config = ConfigProperties::default();
+    config.insert(ZN_MODE_KEY, "router".to_string());
+    config.insert(ZN_LISTENER_KEY, listener.to_string());
+    config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+    if let Some(peer) = peer {
+        config.insert(ZN_PEER_KEY, peer.to_string());
+    }
+    config
+}
+
+async fn open_client(locator: &str) -> ZResult<Session> {
+    let mut config = ConfigProperties::default();
+    config.insert(ZN_MODE_KEY, "client".to_string());
+    config.insert(ZN_PEER_KEY, locator.to_string());
+    config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+    open(config).await
+}
+
+#[test]
+fn reload_listeners_and_peers() {
+    task::block_on(async {
+        let router = open(router_config(OLD_LISTENER, None)).await.unwrap();
+        let peer = open(router_config(PEER_LISTENER, None)).await.unwrap();
+        let runtime = router.runtime();
+
+        // the listener is replaced and the new peer is connected,
+        // the keys that can't be changed at runtime are reported
+        let mut config = router_config(NEW_LISTENER, Some(PEER_LISTENER));
+        config.insert(ZN_ADD_TIMESTAMP_KEY, ZN_FALSE.to_string());
+        let ignored = runtime.reload_config(config).await.unwrap();
+        assert_eq!(ignored, vec![ZN_ADD_TIMESTAMP_KEY]);
+        let listeners = runtime.manager().get_listeners();
+        assert!(listeners.contains(&NEW_LISTENER.parse::<Locator>().unwrap()));
+        assert!(!listeners.contains(&OLD_LISTENER.parse::<Locator>().unwrap()));
+        assert!(open_client(OLD_LISTENER).await.is_err());
+        let client = open_client(NEW_LISTENER).await.unwrap();
+        task::sleep(SLEEP).await;
+        assert_eq!(peer.runtime().manager().get_sessions().len(), 1);
+        assert_eq!(
+            runtime.config().get(&ZN_PEER_KEY).map(|s| &s[..]),
+            Some(PEER_LISTENER)
+        );
+        assert!(runtime.config().get(&ZN_ADD_TIMESTAMP_KEY).is_none());
+
+        // the session with the removed peer is closed, not the session with the client
+        let ignored = runtime
+            .reload_config(router_config(NEW_LISTENER, Some(UNREACHABLE_PEER)))
+            .await
+            .unwrap();
+        assert!(ignored.is_empty());
+        task::sleep(SLEEP).await;
+        assert!(peer.runtime().manager().get_sessions().is_empty());
+        assert_eq!(runtime.manager().get_sessions().len(), 1);
+
+        // the connection retries stop once the unreachable peer is removed
+        let unreachable = UNREACHABLE_PEER.parse::<Locator>().unwrap();
+        assert!(runtime.get_connect_retries().contains_key(&unreachable));
+        runtime
+            .reload_config(router_config(NEW_LISTENER, None))
+            .await
+            .unwrap();
+        let start = std::time::Instant::now();
+        while runtime.get_connect_retries().contains_key(&unreachable) {
+            assert!(start.elapsed() < TIMEOUT);
+            task::sleep(SLEEP).await;
+        }
+        assert!(runtime.config().get(&ZN_PEER_KEY).is_none());
+
+        // an invalid configuration is rejected without changing anything
+        assert!(runtime
+            .reload_config(router_config("unknown/127.0.0.1:14490", None))
+            .await
+            .is_err());
+        assert!(runtime
+            .manager()
+            .get_listeners()
+            .contains(&NEW_LISTENER.parse::<Locator>().unwrap()));
+
+        client.close().await.unwrap();
+        peer.close().await.unwrap();
+        router.close().await.unwrap();
+    });
+}
+
+#[cfg(unix)]
+#[test]
+fn reload_zenohd() {
+    use std::process::Command;
+
+    // opens a client on `locator` as soon as zenohd listens on it
+    async fn wait_client(locator: &str) -> Session {
+        let start = std::time::Instant::now();
+        loop {
+            match open_client(locator).await {
+                Ok(session) => return session,
+                Err(err) => assert!(start.elapsed() < TIMEOUT, "{}", err),
+            }
+            task::sleep(SLEEP).await;
+        }
+    }
+
+    task::block_on(async {
+        let conf_file = std::env::temp_dir().join("zenoh-test-reload.conf");
+        std::fs::write(&conf_file, format!("listener={}\n", ZENOHD_OLD_LISTENER)).unwrap();
+        let mut zenohd = Command::new(env!("CARGO_BIN_EXE_zenohd"))
+            .arg("--config")
+            .arg(&conf_file)
+            .args(&["--plugin-nolookup", "--no-multicast-scouting"])
+            .spawn()
+            .unwrap();
+        wait_client(ZENOHD_OLD_LISTENER)
+            .await
+            .close()
+            .await
+            .unwrap();
+
+        // the configuration file is reloaded when modified
+        std::fs::write(&conf_file, format!("listener={}\n", ZENOHD_NEW_LISTENER)).unwrap();
+        wait_client(ZENOHD_NEW_LISTENER)
+            .await
+            .close()
+            .await
+            .unwrap();
+        assert!(open_client(ZENOHD_OLD_LISTENER).await.is_err());
+
+        // and on SIGHUP, even if its modification time didn't change
+        let modified = std::fs::metadata(&conf_file).unwrap().modified().unwrap();
+        std::fs::write(&conf_file, format!("listener={}\n", ZENOHD_SIGHUP_LISTENER)).unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&conf_file)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        task::sleep(SLEEP * 4).await;
+        assert!(open_client(ZENOHD_SIGHUP_LISTENER).await.is_err());
+        unsafe { libc::kill(zenohd.id() as libc::pid_t, libc::SIGHUP) };
+        wait_client(ZENOHD_SIGHUP_LISTENER)
+            .await
+            .close()
+            .await
+            .unwrap();
+
+        unsafe { libc::kill(zenohd.id() as libc::pid_t, libc::SIGTERM) };
+        assert!(zenohd.wait().unwrap().success());
+        let _ = std::fs::remove_file(&conf_file);
+    });
+}
@@ -0,0 +1,160 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+#![cfg(feature = "routing_replay")]
+use async_std::prelude::*;
+use async_std::task;
+use std::fs::File;
+use std::time::Duration;
+use zenoh::net::routing::replay::replay;
+use zenoh::net::runtime::Runtime;
+use zenoh::net::*;
+use zenoh_util::properties::config::*;
+
+const TIMEOUT: Duration = Duration::from_secs(60);
+const SLEEP: Duration = Duration::from_secs(1);
+const ROUTER_LOCATOR: &str = "tcp/127.0.0.1:14491";
+
+fn trace_path() -> std::path::PathBuf {
+    std::env::temp_dir().join(format!(
+        "zenoh-routing-trace-{}.bin",
+        uuid::Uuid::new_v4().to_simple()
+    ))
+}
+
+fn sub_info() -> SubInfo {
+    SubInfo {
+        reliability: Reliability::Reliable,
+        mode: SubMode::Push,
+        period: None,
+        history: History::None,
+        filter: None,
+    }
+}
+
+#[test]
+fn routing_replay_local_session() {
+    task::block_on(async {
+        let path = trace_path();
+
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "peer".to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        config.insert(ZN_ROUTING_TRACE_KEY, path.to_str().unwrap().to_string());
+        let runtime = Runtime::new(0, config, None).await.unwrap();
+        let sub_session = Session::init(runtime.clone(), true, vec![], vec![]).await;
+        let pub_session = Session::init(runtime, true, vec![], vec![]).await;
+
+        let mut sub = sub_session
+            .declare_subscriber(&"/test/replay".into(), &sub_info())
+            .await
+            .unwrap();
+        pub_session
+            .write(&"/test/replay".into(), b"traced".to_vec().into())
+            .await
+            .unwrap();
+        let sample = sub
+            .receiver()
+            .next()
+            .timeout(TIMEOUT)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(sample.payload.to_vec(), b"traced".to_vec());
+        sub.undeclare().await.unwrap();
+        pub_session.close().await.unwrap();
+        sub_session.close().await.unwrap();
+
+        let outputs = replay(File::open(&path).unwrap()).unwrap();
+        let data: Vec<_> = outputs
+            .iter()
+            .filter(|o| o.event.starts_with("send_data "))
+            .collect();
+        assert_eq!(data.len(), 1);
+        assert!(data[0].event.contains(&format!("{:?}", b"traced".to_vec())));
+
+        // Replaying the same trace produces the same output
+        assert_eq!(replay(File::open(&path).unwrap()).unwrap(), outputs);
+
+        let _ = std::fs::remove_file(&path);
+    });
+}
+
+#[test]
+fn routing_replay_routers() {
+    task::block_on(async {
+        let path = trace_path();
+
+        // the traced router routes the data of its local session to the subscriber of another router
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "router".to_string());
+        config.insert(ZN_LISTENER_KEY, ROUTER_LOCATOR.to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        config.insert(ZN_ROUTING_TRACE_KEY, path.to_str().unwrap().to_string());
+        let traced = open(config).await.unwrap();
+
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "router".to_string());
+        config.insert(ZN_PEER_KEY, ROUTER_LOCATOR.to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        let remote = open(config).await.unwrap();
+        let mut sub = remote
+            .declare_subscriber(&"/test/replay/routers".into(), &sub_info())
+            .await
+            .unwrap();
+        task::sleep(SLEEP).await;
+
+        traced
+            .write(&"/test/replay/routers".into(), b"routed".to_vec().into())
+            .await
+            .unwrap();
+        let sample = sub
+            .receiver()
+            .next()
+            .timeout(TIMEOUT)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(sample.payload.to_vec(), b"routed".to_vec());
+        sub.undeclare().await.unwrap();
+        remote.close().await.unwrap();
+        traced.close().await.unwrap();
+
+        // the replayed router exchanges link states with the other router, and routes the data to it
+        let outputs = replay(File::open(&path).unwrap()).unwrap();
+        assert!(outputs
+            .iter()
+            .any(|o| o.event.starts_with("send_link_states ")));
+        let data: Vec<_> = outputs
+            .iter()
+            .filter(|o| o.event.starts_with("send_data "))
+            .collect();
+        assert_eq!(data.len(), 1);
+        assert!(data[0].event.contains(&format!("{:?}", b"routed".to_vec())));
+        assert!(outputs
+            .iter()
+            .any(|o| o.face == data[0].face && o.event.starts_with("send_link_states ")));
+
+        // Replaying the same trace produces the same output
+        assert_eq!(replay(File::open(&path).unwrap()).unwrap(), outputs);
+
+        let _ = std::fs::remove_file(&path);
+    });
+}
+
+#[test]
+fn routing_replay_oversized_record() {
+    // A corrupted length is rejected before allocating the record
+    let trace = u32::MAX.to_le_bytes().to_vec();
+    assert!(replay(&trace[..]).is_err());
+}
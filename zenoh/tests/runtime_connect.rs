@@ -0,0 +1,67 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use async_std::task;
+use zenoh::net::protocol::core::PeerId;
+use zenoh::net::*;
+use zenoh_util::properties::config::*;
+
+const ROUTER_LOCATOR: &str = "tcp/127.0.0.1:14447";
+
+#[test]
+fn peer_id_from_str() {
+    let pid: PeerId = "a1b2c3".parse().unwrap();
+    assert_eq!(pid.to_string(), "A1B2C3");
+    assert!("".parse::<PeerId>().is_err());
+    assert!("not-hex".parse::<PeerId>().is_err());
+    assert!("00".repeat(PeerId::MAX_SIZE + 1).parse::<PeerId>().is_err());
+}
+
+#[test]
+fn runtime_connect_disconnect() {
+    task::block_on(async {
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "router".to_string());
+        config.insert(ZN_LISTENER_KEY, ROUTER_LOCATOR.to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        let router = open(config).await.unwrap();
+        let router_pid = router.id().await;
+
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "peer".to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        let peer = open(config).await.unwrap();
+
+        let pid = peer
+            .runtime()
+            .connect(&ROUTER_LOCATOR.parse().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(pid.to_string(), router_pid);
+
+        let transports = peer.transports().await;
+        assert_eq!(transports.len(), 1);
+        let (transport_pid, parameters) = &transports[0];
+        assert_eq!(transport_pid, &pid);
+        assert_eq!(parameters.batch_sizes.len(), 1);
+        assert_eq!(parameters.batch_sizes[0].0.to_string(), ROUTER_LOCATOR);
+        // shared memory is negotiated only with the zero-copy feature
+        assert_eq!(parameters.is_shm, parameters.auth_methods.contains(&"shm"));
+
+        peer.runtime().disconnect(&pid).await.unwrap();
+        assert!(peer.runtime().disconnect(&pid).await.is_err());
+
+        peer.close().await.unwrap();
+        router.close().await.unwrap();
+    });
+}
@@ -0,0 +1,72 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use async_std::task;
+use std::time::{SystemTime, UNIX_EPOCH};
+use zenoh::net::*;
+use zenoh_util::properties::config::*;
+
+const ROUTER_LOCATOR: &str = "tcp/127.0.0.1:13447";
+
+fn client_config(cache: &str) -> ConfigProperties {
+    let mut config = ConfigProperties::default();
+    config.insert(ZN_MODE_KEY, "client".to_string());
+    config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+    config.insert(ZN_SCOUTING_CACHE_KEY, cache.to_string());
+    config
+}
+
+#[test]
+fn scouting_cache_connect() {
+    task::block_on(async {
+        let path = std::env::temp_dir().join(format!(
+            "zenoh-scouting-cache-{}.json",
+            uuid::Uuid::new_v4().to_simple()
+        ));
+        let cache = path.to_str().unwrap();
+
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "router".to_string());
+        config.insert(ZN_LISTENER_KEY, ROUTER_LOCATOR.to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        let router = open(config).await.unwrap();
+        let router_pid = router.id().await;
+
+        // No peer configured, no scouting and no cache: the client can't connect
+        assert!(open(client_config(cache)).await.is_err());
+
+        // A fresh cache entry is connected without scouting
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs_f64();
+        let entry = |time: f64| {
+            format!(
+                r#"[{{"pid":"{}","whatami":"router","locators":["{}"],"time":{}}}]"#,
+                router_pid, ROUTER_LOCATOR, time
+            )
+        };
+        std::fs::write(&path, entry(now)).unwrap();
+        let client = open(client_config(cache)).await.unwrap();
+        client.close().await.unwrap();
+
+        // A stale cache entry is ignored
+        std::fs::write(&path, entry(now - 3600.0)).unwrap();
+        let mut config = client_config(cache);
+        config.insert(ZN_SCOUTING_CACHE_MAX_AGE_KEY, "60".to_string());
+        assert!(open(config).await.is_err());
+
+        router.close().await.unwrap();
+        let _ = std::fs::remove_file(&path);
+    });
+}
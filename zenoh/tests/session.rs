@@ -76,17 +76,10 @@ async fn session_open_close(locator: Locator, locator_property: Option<Vec<Locat
         handler: router_handler.clone(),
     };
     let opt_config = SessionManagerOptionalConfig {
-        lease: None,
-        keep_alive: None,
-        sn_resolution: None,
-        open_timeout: None,
-        open_incoming_pending: None,
-        batch_size: None,
         max_sessions: Some(1),
         max_links: Some(2),
-        peer_authenticator: None,
-        link_authenticator: None,
         locator_property: locator_property.clone(),
+        ..Default::default()
     };
     let router_manager = SessionManager::new(config, Some(opt_config));
 
@@ -102,17 +95,10 @@ async fn session_open_close(locator: Locator, locator_property: Option<Vec<Locat
         handler: Arc::new(SHClientOpenClose::new()),
     };
     let opt_config = SessionManagerOptionalConfig {
-        lease: None,
-        keep_alive: None,
-        sn_resolution: None,
-        open_timeout: None,
-        open_incoming_pending: None,
-        batch_size: None,
         max_sessions: Some(1),
         max_links: Some(2),
-        peer_authenticator: None,
-        link_authenticator: None,
         locator_property: locator_property.clone(),
+        ..Default::default()
     };
     let client01_manager = SessionManager::new(config, Some(opt_config));
 
@@ -124,17 +110,10 @@ async fn session_open_close(locator: Locator, locator_property: Option<Vec<Locat
         handler: Arc::new(SHClientOpenClose::new()),
     };
     let opt_config = SessionManagerOptionalConfig {
-        lease: None,
-        keep_alive: None,
-        sn_resolution: None,
-        open_timeout: None,
-        open_incoming_pending: None,
-        batch_size: None,
         max_sessions: Some(1),
         max_links: Some(2),
-        peer_authenticator: None,
-        link_authenticator: None,
         locator_property,
+        ..Default::default()
     };
     let client02_manager = SessionManager::new(config, Some(opt_config));
 
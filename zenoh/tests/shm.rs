@@ -25,8 +25,8 @@ mod tests {
     use zenoh::net::protocol::proto::{Data, ZenohBody, ZenohMessage};
     use zenoh::net::protocol::session::authenticator::SharedMemoryAuthenticator;
     use zenoh::net::protocol::session::{
-        Session, SessionEventHandler, SessionHandler, SessionManager, SessionManagerConfig,
-        SessionManagerOptionalConfig,
+        CloseReason, Session, SessionEventHandler, SessionHandler, SessionManager,
+        SessionManagerConfig, SessionManagerOptionalConfig,
     };
     use zenoh_util::core::ZResult;
     use zenoh_util::zasync_executor_init;
@@ -105,7 +105,7 @@ mod tests {
         fn new_link(&self, _link: Link) {}
         fn del_link(&self, _link: Link) {}
         fn closing(&self) {}
-        fn closed(&self) {}
+        fn closed(&self, _reason: CloseReason) {}
 
         fn as_any(&self) -> &dyn Any {
             self
@@ -130,17 +130,8 @@ mod tests {
             handler: peer_shm01_handler.clone(),
         };
         let opt_config = SessionManagerOptionalConfig {
-            lease: None,
-            keep_alive: None,
-            sn_resolution: None,
-            open_timeout: None,
-            open_incoming_pending: None,
-            batch_size: None,
-            max_sessions: None,
-            max_links: None,
             peer_authenticator: Some(vec![SharedMemoryAuthenticator::new().into()]),
-            link_authenticator: None,
-            locator_property: None,
+            ..Default::default()
         };
         let peer_shm01_manager = SessionManager::new(config, Some(opt_config));
 
@@ -153,17 +144,8 @@ mod tests {
             handler: peer_shm02_handler.clone(),
         };
         let opt_config = SessionManagerOptionalConfig {
-            lease: None,
-            keep_alive: None,
-            sn_resolution: None,
-            open_timeout: None,
-            open_incoming_pending: None,
-            batch_size: None,
-            max_sessions: None,
-            max_links: None,
             peer_authenticator: Some(vec![SharedMemoryAuthenticator::new().into()]),
-            link_authenticator: None,
-            locator_property: None,
+            ..Default::default()
         };
         let peer_shm02_manager = SessionManager::new(config, Some(opt_config));
 
@@ -0,0 +1,137 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+#![cfg(all(
+    feature = "zero-copy",
+    feature = "transport_unixsock-stream",
+    target_os = "linux"
+))]
+use async_std::prelude::*;
+use async_std::task;
+use std::process::Command;
+use std::time::Duration;
+use zenoh::net::*;
+use zenoh_util::properties::config::*;
+
+const ROUTER_SOCKET: &str = "zenoh-test-shm-memfd.sock";
+const RES_NAME: &str = "/shm/memfd";
+const TIMEOUT: Duration = Duration::from_secs(10);
+const SLEEP: Duration = Duration::from_millis(500);
+const PAYLOAD_SIZE: usize = 64 * 1024;
+// Set in the environment of the process running the publisher
+const PUBLISHER_ENV: &str = "ZENOH_TEST_SHM_MEMFD_PUBLISHER";
+
+// The socket is created in the temporary directory, inherited by the publisher process
+fn router_locator() -> String {
+    format!(
+        "unixsock-stream/{}",
+        std::env::temp_dir().join(ROUTER_SOCKET).display()
+    )
+}
+
+fn memfd_config(mode: &str) -> ConfigProperties {
+    let mut config = ConfigProperties::default();
+    config.insert(ZN_MODE_KEY, mode.to_string());
+    config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+    config.insert(ZN_SHM_PROVIDER_KEY, "memfd".to_string());
+    config
+}
+
+// Run in a process of its own by shm_memfd, so that its segment can only be mapped
+// by the router through the file descriptors passed along the link.
+#[test]
+fn shm_memfd_publisher() {
+    if std::env::var(PUBLISHER_ENV).is_err() {
+        return;
+    }
+    task::block_on(async {
+        let mut config = memfd_config("client");
+        config.insert(ZN_PEER_KEY, router_locator());
+        let publisher = open(config).await.unwrap();
+        let mut shm =
+            SharedMemoryManager::new_memfd(format!("{}.memfd", publisher.id().await), PAYLOAD_SIZE)
+                .unwrap();
+        task::sleep(SLEEP).await;
+
+        let mut sbuf = shm.alloc(PAYLOAD_SIZE).unwrap();
+        let bs = unsafe { sbuf.as_mut_slice() };
+        for (i, b) in bs.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+        publisher
+            .write(&RES_NAME.into(), sbuf.into())
+            .await
+            .unwrap();
+        task::sleep(SLEEP).await;
+
+        publisher.close().await.unwrap();
+    });
+}
+
+#[test]
+fn shm_memfd() {
+    task::block_on(async {
+        let mut config = memfd_config("router");
+        config.insert(ZN_LISTENER_KEY, router_locator());
+        let router = open(config).await.unwrap();
+        let mut router_subscriber = router
+            .declare_subscriber(&RES_NAME.into(), &SubInfo::default())
+            .await
+            .unwrap();
+
+        let mut config = memfd_config("client");
+        config.insert(ZN_PEER_KEY, router_locator());
+        let session = open(config).await.unwrap();
+        let mut subscriber = session
+            .declare_subscriber(&RES_NAME.into(), &SubInfo::default())
+            .await
+            .unwrap();
+        task::sleep(SLEEP).await;
+
+        let mut publisher = Command::new(std::env::current_exe().unwrap())
+            .args(&["--exact", "shm_memfd_publisher"])
+            .env(PUBLISHER_ENV, "1")
+            .spawn()
+            .unwrap();
+
+        // the segment of the publisher is passed to the router, that maps it
+        let sample = router_subscriber
+            .receiver()
+            .next()
+            .timeout(TIMEOUT)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(sample.payload.has_shmbuf());
+        let payload = sample.payload.to_vec();
+        assert_eq!(payload.len(), PAYLOAD_SIZE);
+        assert!(payload.iter().enumerate().all(|(i, b)| *b == i as u8));
+
+        // but not by the router to the client, that receives a copy of the buffer
+        let sample = subscriber
+            .receiver()
+            .next()
+            .timeout(TIMEOUT)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!sample.payload.has_shmbuf());
+        assert_eq!(sample.payload.to_vec(), payload);
+        assert!(publisher.wait().unwrap().success());
+
+        subscriber.undeclare().await.unwrap();
+        router_subscriber.undeclare().await.unwrap();
+        session.close().await.unwrap();
+        router.close().await.unwrap();
+    });
+}
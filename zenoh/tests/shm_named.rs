@@ -0,0 +1,85 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+#![cfg(all(feature = "zero-copy", windows))]
+use async_std::prelude::*;
+use async_std::task;
+use std::time::Duration;
+use zenoh::net::*;
+use zenoh_util::properties::config::*;
+
+const ROUTER_LOCATOR: &str = "tcp/127.0.0.1:14470";
+const RES_NAME: &str = "/shm/named";
+const TIMEOUT: Duration = Duration::from_secs(10);
+const SLEEP: Duration = Duration::from_millis(500);
+const PAYLOAD_SIZE: usize = 64 * 1024;
+
+fn named_config(mode: &str) -> ConfigProperties {
+    let mut config = ConfigProperties::default();
+    config.insert(ZN_MODE_KEY, mode.to_string());
+    config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+    config.insert(ZN_SHM_PROVIDER_KEY, "named".to_string());
+    config
+}
+
+#[test]
+fn shm_named() {
+    task::block_on(async {
+        let mut config = named_config("router");
+        config.insert(ZN_LISTENER_KEY, ROUTER_LOCATOR.to_string());
+        let router = open(config).await.unwrap();
+
+        let mut config = named_config("client");
+        config.insert(ZN_PEER_KEY, ROUTER_LOCATOR.to_string());
+        let session = open(config).await.unwrap();
+        let mut subscriber = session
+            .declare_subscriber(&RES_NAME.into(), &SubInfo::default())
+            .await
+            .unwrap();
+
+        let mut config = named_config("client");
+        config.insert(ZN_PEER_KEY, ROUTER_LOCATOR.to_string());
+        let publisher = open(config).await.unwrap();
+        let mut shm =
+            SharedMemoryManager::new_named(format!("{}.named", publisher.id().await), PAYLOAD_SIZE)
+                .unwrap();
+        task::sleep(SLEEP).await;
+
+        let mut sbuf = shm.alloc(PAYLOAD_SIZE).unwrap();
+        let bs = unsafe { sbuf.as_mut_slice() };
+        for (i, b) in bs.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+        publisher
+            .write(&RES_NAME.into(), sbuf.into())
+            .await
+            .unwrap();
+
+        let sample = subscriber
+            .receiver()
+            .next()
+            .timeout(TIMEOUT)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(sample.payload.has_shmbuf());
+        let payload = sample.payload.to_vec();
+        assert_eq!(payload.len(), PAYLOAD_SIZE);
+        assert!(payload.iter().enumerate().all(|(i, b)| *b == i as u8));
+
+        subscriber.undeclare().await.unwrap();
+        publisher.close().await.unwrap();
+        session.close().await.unwrap();
+        router.close().await.unwrap();
+    });
+}
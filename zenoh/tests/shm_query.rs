@@ -0,0 +1,176 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+#![cfg(feature = "zero-copy")]
+use async_std::prelude::*;
+use async_std::task;
+use std::time::Duration;
+use zenoh::net::queryable::EVAL;
+use zenoh::net::*;
+use zenoh_util::properties::config::*;
+
+const ROUTER_LOCATOR: &str = "tcp/127.0.0.1:14462";
+const STORE_ROUTER_LOCATOR: &str = "tcp/127.0.0.1:14466";
+const RES_NAME: &str = "/shm/reply";
+const TIMEOUT: Duration = Duration::from_secs(10);
+const SLEEP: Duration = Duration::from_millis(500);
+// Larger than the maximum message size of the replier
+const MAX_MESSAGE_SIZE: usize = 1024;
+const PAYLOAD_SIZE: usize = 64 * 1024;
+
+async fn open_client(router: &str, zero_copy: bool) -> Session {
+    let mut config = ConfigProperties::default();
+    config.insert(ZN_MODE_KEY, "client".to_string());
+    config.insert(ZN_PEER_KEY, router.to_string());
+    config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+    config.insert(ZN_ZERO_COPY_KEY, zero_copy.to_string());
+    open(config).await.unwrap()
+}
+
+async fn serve(queryable: &mut Queryable<'_>, shm: &mut SharedMemoryManager) {
+    while let Some(query) = queryable.receiver().next().await {
+        let mut sbuf = shm.alloc(PAYLOAD_SIZE).unwrap();
+        let bs = unsafe { sbuf.as_mut_slice() };
+        for (i, b) in bs.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+        query.reply_shm(RES_NAME.to_string(), sbuf, None);
+        shm.garbage_collect();
+    }
+}
+
+async fn query(session: &Session) {
+    let replies: Vec<Reply> = session
+        .query(
+            &RES_NAME.into(),
+            "",
+            QueryTarget::default(),
+            QueryConsolidation::none(),
+        )
+        .await
+        .unwrap()
+        .collect()
+        .timeout(TIMEOUT)
+        .await
+        .unwrap();
+    assert_eq!(replies.len(), 1);
+    let payload = replies[0].data.payload.to_vec();
+    assert_eq!(payload.len(), PAYLOAD_SIZE);
+    assert!(payload.iter().enumerate().all(|(i, b)| *b == i as u8));
+}
+
+#[test]
+fn shm_query() {
+    task::block_on(async {
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "router".to_string());
+        config.insert(ZN_LISTENER_KEY, ROUTER_LOCATOR.to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        config.insert(ZN_MAX_MESSAGE_SIZE_KEY, MAX_MESSAGE_SIZE.to_string());
+        let router = open(config).await.unwrap();
+        let mut shm =
+            SharedMemoryManager::new(format!("{}.shm_query", router.id().await), 4 * PAYLOAD_SIZE)
+                .unwrap();
+        let mut queryable = router
+            .declare_queryable(&RES_NAME.into(), EVAL)
+            .await
+            .unwrap();
+
+        // the replies in shared memory are sent as their SHM info to the clients able to map them
+        let shm_client = open_client(ROUTER_LOCATOR, true).await;
+        // and serialized for the others
+        let net_client = open_client(ROUTER_LOCATOR, false).await;
+        task::sleep(SLEEP).await;
+
+        let queries = async {
+            query(&shm_client).await;
+            query(&net_client).await;
+        };
+        queries.race(serve(&mut queryable, &mut shm)).await;
+
+        queryable.undeclare().await.unwrap();
+        shm_client.close().await.unwrap();
+        net_client.close().await.unwrap();
+        router.close().await.unwrap();
+    });
+}
+
+#[test]
+fn shm_query_stored_sample() {
+    task::block_on(async {
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "router".to_string());
+        config.insert(ZN_LISTENER_KEY, STORE_ROUTER_LOCATOR.to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        config.insert(ZN_MAX_MESSAGE_SIZE_KEY, MAX_MESSAGE_SIZE.to_string());
+        let router = open(config).await.unwrap();
+
+        // the storage receives the publication in shared memory
+        let storage = open_client(STORE_ROUTER_LOCATOR, true).await;
+        let mut subscriber = storage
+            .declare_subscriber(&RES_NAME.into(), &SubInfo::default())
+            .await
+            .unwrap();
+        let mut queryable = storage
+            .declare_queryable(&RES_NAME.into(), EVAL)
+            .await
+            .unwrap();
+        let publisher = open_client(STORE_ROUTER_LOCATOR, true).await;
+        let mut shm = SharedMemoryManager::new(
+            format!("{}.shm_store", publisher.id().await),
+            2 * PAYLOAD_SIZE,
+        )
+        .unwrap();
+        let shm_client = open_client(STORE_ROUTER_LOCATOR, true).await;
+        let net_client = open_client(STORE_ROUTER_LOCATOR, false).await;
+        task::sleep(SLEEP).await;
+
+        let mut sbuf = shm.alloc(PAYLOAD_SIZE).unwrap();
+        let bs = unsafe { sbuf.as_mut_slice() };
+        for (i, b) in bs.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+        publisher
+            .write(&RES_NAME.into(), sbuf.into())
+            .await
+            .unwrap();
+        let stored = subscriber
+            .receiver()
+            .next()
+            .timeout(TIMEOUT)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(stored.payload.has_shmbuf());
+
+        // and replies it from shared memory without copying it
+        let serve = async {
+            while let Some(query) = queryable.receiver().next().await {
+                query.reply(stored.clone());
+            }
+        };
+        let queries = async {
+            query(&shm_client).await;
+            query(&net_client).await;
+        };
+        queries.race(serve).await;
+
+        subscriber.undeclare().await.unwrap();
+        queryable.undeclare().await.unwrap();
+        storage.close().await.unwrap();
+        publisher.close().await.unwrap();
+        shm_client.close().await.unwrap();
+        net_client.close().await.unwrap();
+        router.close().await.unwrap();
+    });
+}
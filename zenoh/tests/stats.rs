@@ -0,0 +1,179 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use async_std::prelude::*;
+use async_std::sync::Arc;
+use async_std::task;
+use std::time::Duration;
+use zenoh::net::plugins::PluginsMgr;
+use zenoh::net::runtime::AdminSpace;
+use zenoh::net::*;
+use zenoh_util::properties::config::*;
+use zenoh_util::LibLoader;
+
+const ROUTER_LOCATOR: &str = "tcp/127.0.0.1:14453";
+const DEFRAG_LOCATOR: &str = "tcp/127.0.0.1:14494";
+const TIMEOUT: Duration = Duration::from_secs(10);
+const SLEEP: Duration = Duration::from_millis(500);
+
+#[test]
+fn stats() {
+    task::block_on(async {
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "router".to_string());
+        config.insert(ZN_LISTENER_KEY, ROUTER_LOCATOR.to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        let router = open(config).await.unwrap();
+        let plugins_mgr = Arc::new(PluginsMgr::new(LibLoader::default()));
+        AdminSpace::start(router.runtime(), plugins_mgr, "test".to_string()).await;
+
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "client".to_string());
+        config.insert(ZN_PEER_KEY, ROUTER_LOCATOR.to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        let client = open(config).await.unwrap();
+
+        let sub_info = SubInfo {
+            reliability: Reliability::Reliable,
+            mode: SubMode::Push,
+            period: None,
+            history: History::None,
+            filter: None,
+        };
+        let mut subscriber = router
+            .declare_subscriber(&"/stats/**".into(), &sub_info)
+            .await
+            .unwrap();
+        task::sleep(SLEEP).await;
+        for _ in 0..10 {
+            client
+                .write(&"/stats/a".into(), vec![0u8; 1024].into())
+                .await
+                .unwrap();
+        }
+        for _ in 0..10 {
+            subscriber
+                .receiver()
+                .next()
+                .timeout(TIMEOUT)
+                .await
+                .unwrap()
+                .unwrap();
+        }
+        task::sleep(SLEEP).await;
+
+        // the counters of the router are requested in-band
+        let sessions = client.runtime().manager().get_sessions();
+        assert_eq!(sessions.len(), 1);
+        let local = sessions[0].get_stats().unwrap();
+        assert!(local.tx_bytes > 10 * 1024);
+        assert!(local.rx_batches > 0);
+        let remote = sessions[0].get_remote_stats(TIMEOUT).await.unwrap();
+        assert!(remote.rx_batches > local.tx_batches);
+        assert!(remote.rx_bytes > local.tx_bytes);
+        assert!(remote.tx_batches >= local.rx_batches);
+
+        // the router reports the counters of the client via its admin space
+        let selector = format!("/@/router/{}/stats", router.runtime().get_pid_str());
+        let replies: Vec<Reply> = client
+            .query(
+                &selector.into(),
+                "",
+                QueryTarget::default(),
+                QueryConsolidation::default(),
+            )
+            .await
+            .unwrap()
+            .collect()
+            .timeout(TIMEOUT)
+            .await
+            .unwrap();
+        assert_eq!(replies.len(), 1);
+        let json: serde_json::Value =
+            serde_json::from_slice(&replies[0].data.payload.to_vec()).unwrap();
+        let stats = &json[client.runtime().get_pid_str()];
+        assert!(stats["local"]["rx_bytes"].as_u64().unwrap() > 10 * 1024);
+        assert!(stats["remote"]["tx_bytes"].as_u64().unwrap() > 10 * 1024);
+
+        subscriber.undeclare().await.unwrap();
+        client.close().await.unwrap();
+        router.close().await.unwrap();
+    });
+}
+
+#[test]
+fn defrag_aborts() {
+    task::block_on(async {
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "router".to_string());
+        config.insert(ZN_LISTENER_KEY, DEFRAG_LOCATOR.to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        config.insert(ZN_MAX_MESSAGE_SIZE_KEY, "16384".to_string());
+        let router = open(config).await.unwrap();
+
+        let mut config = ConfigProperties::default();
+        config.insert(ZN_MODE_KEY, "client".to_string());
+        config.insert(ZN_PEER_KEY, DEFRAG_LOCATOR.to_string());
+        config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+        let client = open(config).await.unwrap();
+
+        let sub_info = SubInfo {
+            reliability: Reliability::Reliable,
+            mode: SubMode::Push,
+            period: None,
+            history: History::None,
+            filter: None,
+        };
+        let mut subscriber = router
+            .declare_subscriber(&"/defrag/**".into(), &sub_info)
+            .await
+            .unwrap();
+        task::sleep(SLEEP).await;
+
+        // fragmented messages exceeding the maximum message size of the router are dropped,
+        // without closing the session
+        for _ in 0..2 {
+            client
+                .write(&"/defrag/large".into(), vec![0u8; 128 * 1024].into())
+                .await
+                .unwrap();
+        }
+        client
+            .write(&"/defrag/small".into(), vec![0u8; 1024].into())
+            .await
+            .unwrap();
+        let sample = subscriber
+            .receiver()
+            .next()
+            .timeout(TIMEOUT)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(sample.res_name, "/defrag/small");
+
+        let sessions = router.runtime().manager().get_sessions();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].get_stats().unwrap().defrag_aborts, 2);
+
+        // and reported to the client in-band
+        let sessions = client.runtime().manager().get_sessions();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].get_stats().unwrap().defrag_aborts, 0);
+        let remote = sessions[0].get_remote_stats(TIMEOUT).await.unwrap();
+        assert_eq!(remote.defrag_aborts, 2);
+
+        subscriber.undeclare().await.unwrap();
+        client.close().await.unwrap();
+        router.close().await.unwrap();
+    });
+}
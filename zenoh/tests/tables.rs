@@ -16,8 +16,8 @@ use std::convert::TryInto;
 use uhlc::HLC;
 use zenoh::net::protocol::core::rname::intersect;
 use zenoh::net::protocol::core::{
-    whatami, CongestionControl, PeerId, QueryConsolidation, QueryTarget, Reliability, ResKey,
-    SubInfo, SubMode, ZInt,
+    whatami, CongestionControl, History, PeerId, QueryConsolidation, QueryPage, QueryTarget,
+    Reliability, ResKey, SubInfo, SubMode, ZInt,
 };
 use zenoh::net::protocol::io::ZBuf;
 use zenoh::net::protocol::proto::{DataInfo, RoutingContext};
@@ -53,6 +53,8 @@ fn base_test() {
         reliability: Reliability::Reliable,
         mode: SubMode::Push,
         period: None,
+        history: History::None,
+        filter: None,
     };
     declare_client_subscription(
         &mut tables,
@@ -222,6 +224,8 @@ fn clean_test() {
         reliability: Reliability::Reliable,
         mode: SubMode::Push,
         period: None,
+        history: History::None,
+        filter: None,
     };
 
     declare_client_subscription(
@@ -433,6 +437,7 @@ impl Primitives for ClientPrimitives {
         _qid: ZInt,
         _target: QueryTarget,
         _consolidation: QueryConsolidation,
+        _page: Option<QueryPage>,
         _routing_context: Option<RoutingContext>,
     ) {
     }
@@ -472,6 +477,8 @@ fn client_test() {
         reliability: Reliability::Reliable,
         mode: SubMode::Push,
         period: None,
+        history: History::None,
+        filter: None,
     };
 
     let primitives0 = Arc::new(ClientPrimitives::new());
@@ -686,3 +693,73 @@ fn client_test() {
     // mapping strategy check
     // assert_eq!(primitives2.get_last_key().unwrap(), ResKey::RIdWithSuffix(31, "/z2_pub1".to_string()));
 }
+
+#[test]
+fn filter_test() {
+    let mut tables = Tables::new(
+        PeerId::new(0, [0; 16]),
+        whatami::CLIENT,
+        Some(Arc::new(HLC::default())),
+    );
+    let sub_info = SubInfo {
+        reliability: Reliability::Reliable,
+        mode: SubMode::Push,
+        period: None,
+        history: History::None,
+        filter: Some("temperature>20".parse().unwrap()),
+    };
+
+    let primitives0 = Arc::new(ClientPrimitives::new());
+    let face0 = tables.open_face(
+        PeerId::new(0, [0; 16]),
+        whatami::CLIENT,
+        primitives0.clone(),
+    );
+
+    let primitives1 = Arc::new(ClientPrimitives::new());
+    let face1 = tables.open_face(
+        PeerId::new(0, [0; 16]),
+        whatami::CLIENT,
+        primitives1.clone(),
+    );
+    declare_client_subscription(
+        &mut tables,
+        &mut face1.upgrade().unwrap(),
+        0,
+        "/test/filter/**",
+        &sub_info,
+    );
+
+    let route = |tables: &Tables, payload: &str| {
+        route_data(
+            tables,
+            &face0.upgrade().unwrap(),
+            0,
+            "/test/filter/room1",
+            CongestionControl::Block,
+            None,
+            payload.as_bytes().to_vec().into(),
+            None,
+        );
+    };
+
+    route(&tables, r#"{"temperature": 25}"#);
+    assert_eq!(primitives1.get_last_name().unwrap(), "/test/filter/room1");
+    primitives1.clear_data();
+
+    route(&tables, r#"{"temperature": 15}"#);
+    assert!(primitives1.get_last_name().is_none());
+    route(&tables, "not json");
+    assert!(primitives1.get_last_name().is_none());
+
+    // an unfiltered subscription of the same face receives all the data
+    declare_client_subscription(
+        &mut tables,
+        &mut face1.upgrade().unwrap(),
+        0,
+        "/test/filter/room1",
+        &SubInfo::default(),
+    );
+    route(&tables, r#"{"temperature": 15}"#);
+    assert_eq!(primitives1.get_last_name().unwrap(), "/test/filter/room1");
+}
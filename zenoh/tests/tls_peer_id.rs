@@ -0,0 +1,190 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+#![cfg(any(feature = "transport_tls", feature = "transport_quic"))]
+use async_std::sync::Arc;
+use async_std::task;
+use rcgen::{BasicConstraints, Certificate, CertificateParams, DnType, IsCa};
+use std::path::{Path, PathBuf};
+use zenoh::net::protocol::core::{whatami, PeerId};
+use zenoh::net::protocol::link::Locator;
+use zenoh::net::protocol::session::{
+    DummySessionEventHandler, Session, SessionEventHandler, SessionHandler, SessionManager,
+    SessionManagerConfig, SessionManagerOptionalConfig,
+};
+use zenoh_util::core::ZResult;
+use zenoh_util::properties::config::*;
+use zenoh_util::zasync_executor_init;
+
+struct SHPeerId;
+
+impl SessionHandler for SHPeerId {
+    fn new_session(
+        &self,
+        _session: Session,
+    ) -> ZResult<Arc<dyn SessionEventHandler + Send + Sync>> {
+        Ok(Arc::new(DummySessionEventHandler::new()))
+    }
+}
+
+fn write_file(dir: &Path, name: &str, content: &str) -> String {
+    let path = dir.join(name);
+    std::fs::write(&path, content).unwrap();
+    path.to_string_lossy().to_string()
+}
+
+// Generates a certificate signed by `ca` with the DNS subject alternative names `names`,
+// and returns the paths of the certificate and of its private key
+fn generate_certificate(
+    dir: &Path,
+    name: &str,
+    names: Vec<String>,
+    ca: &Certificate,
+) -> (String, String) {
+    let mut params = CertificateParams::new(names);
+    params.distinguished_name.push(DnType::CommonName, name);
+    let cert = Certificate::from_params(params).unwrap();
+    (
+        write_file(
+            dir,
+            &format!("{}.pem", name),
+            &cert.serialize_pem_with_signer(ca).unwrap(),
+        ),
+        write_file(
+            dir,
+            &format!("{}.key", name),
+            &cert.serialize_private_key_pem(),
+        ),
+    )
+}
+
+async fn new_manager(
+    id: &PeerId,
+    whatami: whatami::Type,
+    ca: &str,
+    cert: Option<&(String, String)>,
+) -> SessionManager {
+    let mut config = ConfigProperties::default();
+    config.insert(ZN_TLS_ROOT_CA_CERTIFICATE_KEY, ca.to_string());
+    config.insert(ZN_TLS_PEER_ID_BINDING_KEY, ZN_TRUE.to_string());
+    if let Some((cert, key)) = cert {
+        if whatami == whatami::ROUTER {
+            config.insert(ZN_TLS_SERVER_CERTIFICATE_KEY, cert.clone());
+            config.insert(ZN_TLS_SERVER_PRIVATE_KEY_KEY, key.clone());
+        } else {
+            config.insert(ZN_TLS_CLIENT_CERTIFICATE_KEY, cert.clone());
+            config.insert(ZN_TLS_CLIENT_PRIVATE_KEY_KEY, key.clone());
+        }
+    }
+    let opt_config = SessionManagerOptionalConfig::from_properties(&config)
+        .await
+        .unwrap();
+    let config = SessionManagerConfig {
+        version: 0,
+        whatami,
+        id: id.clone(),
+        handler: Arc::new(SHPeerId),
+    };
+    SessionManager::new(config, opt_config)
+}
+
+fn run(locator: &str, dir: &str) {
+    task::block_on(async {
+        zasync_executor_init!();
+    });
+
+    let dir: PathBuf = std::env::temp_dir().join(dir);
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let mut params = CertificateParams::new(vec![]);
+    params
+        .distinguished_name
+        .push(DnType::CommonName, "zenoh test ca");
+    params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    let ca = Certificate::from_params(params).unwrap();
+    let ca_path = write_file(&dir, "ca.pem", &ca.serialize_pem().unwrap());
+
+    let router_id = PeerId::new(16, [0xA0; PeerId::MAX_SIZE]);
+    let client01_id = PeerId::new(16, [0xA1; PeerId::MAX_SIZE]);
+    let client02_id = PeerId::new(16, [0xA2; PeerId::MAX_SIZE]);
+    let client03_id = PeerId::new(16, [0xA3; PeerId::MAX_SIZE]);
+
+    let router_cert = generate_certificate(
+        &dir,
+        "router",
+        vec!["localhost".to_string(), format!("{}.zenoh", router_id)],
+        &ca,
+    );
+    // the peer id in lower case, as generated by some tools
+    let client01_cert = generate_certificate(
+        &dir,
+        "client01",
+        vec![format!("{}.zenoh", client01_id).to_lowercase()],
+        &ca,
+    );
+
+    task::block_on(async {
+        let locator: Locator = locator.parse().unwrap();
+        let router_manager =
+            new_manager(&router_id, whatami::ROUTER, &ca_path, Some(&router_cert)).await;
+        router_manager.add_listener(&locator).await.unwrap();
+
+        // The certificate is bound to the peer id of the client
+        let client01_manager = new_manager(
+            &client01_id,
+            whatami::CLIENT,
+            &ca_path,
+            Some(&client01_cert),
+        )
+        .await;
+        let res = client01_manager.open_session(&locator).await;
+        assert!(res.is_ok());
+        let session = res.unwrap();
+        assert!(router_manager.get_session(&client01_id).is_some());
+
+        // The certificate is bound to the peer id of another client
+        let client02_manager = new_manager(
+            &client02_id,
+            whatami::CLIENT,
+            &ca_path,
+            Some(&client01_cert),
+        )
+        .await;
+        let res = client02_manager.open_session(&locator).await;
+        assert!(res.is_err());
+        assert!(router_manager.get_session(&client02_id).is_none());
+
+        // No certificate is presented
+        let client03_manager = new_manager(&client03_id, whatami::CLIENT, &ca_path, None).await;
+        let res = client03_manager.open_session(&locator).await;
+        assert!(res.is_err());
+        assert!(router_manager.get_session(&client03_id).is_none());
+
+        session.close().await.unwrap();
+        router_manager.del_listener(&locator).await.unwrap();
+    });
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[cfg(feature = "transport_tls")]
+#[test]
+fn tls_peer_id() {
+    run("tls/localhost:14458", "zenoh-test-tls-peer-id");
+}
+
+#[cfg(feature = "transport_quic")]
+#[test]
+fn quic_peer_id() {
+    run("quic/localhost:14459", "zenoh-test-quic-peer-id");
+}
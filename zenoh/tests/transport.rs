@@ -22,8 +22,8 @@ use zenoh::net::protocol::io::ZBuf;
 use zenoh::net::protocol::link::{Link, Locator, LocatorProperty};
 use zenoh::net::protocol::proto::ZenohMessage;
 use zenoh::net::protocol::session::{
-    Session, SessionEventHandler, SessionHandler, SessionManager, SessionManagerConfig,
-    SessionManagerOptionalConfig,
+    CloseReason, Session, SessionEventHandler, SessionHandler, SessionManager,
+    SessionManagerConfig, SessionManagerOptionalConfig,
 };
 use zenoh_util::core::ZResult;
 use zenoh_util::zasync_executor_init;
@@ -82,7 +82,7 @@ impl SessionEventHandler for SCRouter {
     fn new_link(&self, _link: Link) {}
     fn del_link(&self, _link: Link) {}
     fn closing(&self) {}
-    fn closed(&self) {}
+    fn closed(&self, _reason: CloseReason) {}
 
     fn as_any(&self) -> &dyn Any {
         self
@@ -124,7 +124,7 @@ impl SessionEventHandler for SCClient {
     fn new_link(&self, _link: Link) {}
     fn del_link(&self, _link: Link) {}
     fn closing(&self) {}
-    fn closed(&self) {}
+    fn closed(&self, _reason: CloseReason) {}
 
     fn as_any(&self) -> &dyn Any {
         self
@@ -148,17 +148,8 @@ async fn open_session(
         handler: router_handler.clone(),
     };
     let opt_config = SessionManagerOptionalConfig {
-        lease: None,
-        keep_alive: None,
-        sn_resolution: None,
-        open_timeout: None,
-        open_incoming_pending: None,
-        batch_size: None,
-        max_sessions: None,
-        max_links: None,
-        peer_authenticator: None,
-        link_authenticator: None,
         locator_property: locator_property.clone(),
+        ..Default::default()
     };
     let router_manager = SessionManager::new(config, Some(opt_config));
 
@@ -170,17 +161,8 @@ async fn open_session(
         handler: Arc::new(SHClient::new()),
     };
     let opt_config = SessionManagerOptionalConfig {
-        lease: None,
-        keep_alive: None,
-        sn_resolution: None,
-        open_timeout: None,
-        open_incoming_pending: None,
-        batch_size: None,
-        max_sessions: None,
-        max_links: None,
-        peer_authenticator: None,
-        link_authenticator: None,
         locator_property,
+        ..Default::default()
     };
     let client_manager = SessionManager::new(config, Some(opt_config));
 
@@ -373,6 +355,29 @@ fn transport_tcp_only() {
     ));
 }
 
+#[cfg(feature = "transport_mem")]
+#[test]
+fn transport_mem_only() {
+    task::block_on(async {
+        zasync_executor_init!();
+    });
+
+    // Define the locator
+    let locators: Vec<Locator> = vec!["mem/transport_mem_only".parse().unwrap()];
+    let properties = None;
+    // Define the reliability and congestion control
+    let reliability = [Reliability::Reliable, Reliability::BestEffort];
+    let congestion_control = [CongestionControl::Block, CongestionControl::Drop];
+    // Run
+    task::block_on(run(
+        &locators,
+        properties,
+        &reliability,
+        &congestion_control,
+        &MSG_SIZE_NOFRAG,
+    ));
+}
+
 #[cfg(feature = "transport_udp")]
 #[test]
 fn transport_udp_only() {
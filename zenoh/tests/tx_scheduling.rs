@@ -0,0 +1,79 @@
+//
+// Copyright (c) 2017, 2020 ADLINK Technology Inc.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ADLINK zenoh team, <zenoh@adlink-labs.tech>
+//
+use async_std::task;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use zenoh::net::*;
+use zenoh_util::properties::config::*;
+
+const ROUTER_LOCATOR: &str = "tcp/127.0.0.1:14464";
+const RES_NAME: &str = "/scheduling/value";
+const SLEEP: Duration = Duration::from_millis(500);
+const NB_WRITES: usize = 100;
+
+fn wrr_config(weights: &str) -> ConfigProperties {
+    let mut config = ConfigProperties::default();
+    config.insert(ZN_MULTICAST_SCOUTING_KEY, ZN_FALSE.to_string());
+    config.insert(ZN_TX_SCHEDULING_KEY, "wrr".to_string());
+    config.insert(ZN_TX_WRR_WEIGHTS_KEY, weights.to_string());
+    config
+}
+
+#[test]
+fn tx_scheduling() {
+    task::block_on(async {
+        let mut config = wrr_config("1,1,1");
+        config.insert(ZN_MODE_KEY, "router".to_string());
+        config.insert(ZN_LISTENER_KEY, ROUTER_LOCATOR.to_string());
+        let router = open(config).await.unwrap();
+
+        // the weights must be non-zero, one per priority queue
+        for weights in ["1,0,1", "1,1", "a,b,c"].iter() {
+            let mut config = wrr_config(weights);
+            config.insert(ZN_MODE_KEY, "client".to_string());
+            config.insert(ZN_PEER_KEY, ROUTER_LOCATOR.to_string());
+            assert!(open(config).await.is_err());
+        }
+
+        let mut config = wrr_config("4,2,1");
+        config.insert(ZN_MODE_KEY, "client".to_string());
+        config.insert(ZN_PEER_KEY, ROUTER_LOCATOR.to_string());
+        let client = open(config).await.unwrap();
+
+        let received = Arc::new(AtomicUsize::new(0));
+        let c_received = received.clone();
+        let subscriber = client
+            .declare_callback_subscriber(&RES_NAME.into(), &SubInfo::default(), move |_| {
+                c_received.fetch_add(1, Ordering::Relaxed);
+            })
+            .await
+            .unwrap();
+        task::sleep(SLEEP).await;
+
+        // the data is sent with the weighted round robin scheduling on both sides
+        for i in 0..NB_WRITES {
+            router
+                .write(&RES_NAME.into(), vec![i as u8].into())
+                .await
+                .unwrap();
+        }
+        task::sleep(SLEEP).await;
+        assert_eq!(received.load(Ordering::Relaxed), NB_WRITES);
+
+        subscriber.undeclare().await.unwrap();
+        client.close().await.unwrap();
+        router.close().await.unwrap();
+    });
+}